@@ -6,6 +6,9 @@ SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
 PROJECT_ROOT="$(dirname "$SCRIPT_DIR")"
 BUILD_DIR="$PROJECT_ROOT/.sidecar-build"
 BINARIES_DIR="$PROJECT_ROOT/src-tauri/binaries"
+# Keep in sync with CLAUDE_CODE_ACP_PACKAGE in src-tauri/src/backend/acp/process.rs,
+# which runs the same version through npx when the sidecar is missing
+CLAUDE_CODE_ACP_VERSION="0.12.6"
 
 # Target can be passed as argument: darwin-arm64, darwin-x64, linux-x64, windows-x64
 TARGET="${1:-auto}"
@@ -72,9 +75,9 @@ mkdir -p "$BINARIES_DIR"
 cd "$BUILD_DIR"
 
 # Initialize and install the package
-echo "Installing @zed-industries/claude-code-acp..."
+echo "Installing @zed-industries/claude-code-acp@$CLAUDE_CODE_ACP_VERSION..."
 bun init -y > /dev/null
-bun add @zed-industries/claude-code-acp
+bun add --exact "@zed-industries/claude-code-acp@$CLAUDE_CODE_ACP_VERSION"
 
 # Auto-detect platform if not specified
 if [ "$TARGET" = "auto" ]; then
@@ -0,0 +1,197 @@
+//! `acp batch`: run a prompt template over nodes of a saved project without
+//! the UI, the same way the app's `batch_generate` does. The project is
+//! locked while the batch runs, answers are added as child nodes, and the
+//! results are printed to stdout as JSON; progress goes to stderr.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::repl::{load_config, parse_provider};
+use crate::backend::batch::{
+    batch_inputs, read_project, run_batch, validate_batch_template, write_results, BatchAgent,
+    BatchProgress, MAX_BATCH_NODES,
+};
+use crate::backend::config_file::ConfigFile;
+use crate::backend::project::{project_notes_directory, read_project_settings};
+use crate::backend::project_file::is_project_file;
+use crate::backend::project_lock::{LockError, ProjectLocks};
+use crate::backend::queue::GenerationQueue;
+use crate::backend::types::AgentProvider;
+
+/// Command-line options of `acp batch`; what isn't given comes from the
+/// app's settings
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(super) struct BatchOptions {
+    project: PathBuf,
+    node_ids: Vec<String>,
+    template: String,
+    provider: Option<AgentProvider>,
+    /// The bulk model override, or else the cheapest model, when not given
+    model_id: Option<String>,
+    config_path: Option<PathBuf>,
+}
+
+/// Parse the arguments after `batch`
+pub(super) fn parse_batch_args(args: &[String]) -> Result<BatchOptions, String> {
+    let mut options = BatchOptions::default();
+    let mut project = None;
+    let mut template = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .map(String::as_str)
+                .ok_or_else(|| format!("{arg} needs a value"))
+        };
+        match arg.as_str() {
+            // Repeatable, and each value may list several ids
+            "--nodes" => options.node_ids.extend(
+                value()?
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|id| !id.is_empty())
+                    .map(String::from),
+            ),
+            "--template" => template = Some(value()?.to_string()),
+            "--provider" => options.provider = Some(parse_provider(value()?)?),
+            "--model" => options.model_id = Some(value()?.to_string()),
+            "--config" => options.config_path = Some(PathBuf::from(value()?)),
+            flag if flag.starts_with("--") => return Err(format!("Unknown argument {arg:?}")),
+            _ if project.is_none() => project = Some(PathBuf::from(arg)),
+            _ => return Err(format!("Unexpected argument {arg:?}")),
+        }
+    }
+    options.project = project.ok_or("batch needs a project file")?;
+    options.template = template.ok_or("batch needs --template")?;
+    if options.node_ids.is_empty() {
+        return Err("batch needs --nodes".to_string());
+    }
+    Ok(options)
+}
+
+pub(super) async fn batch(options: BatchOptions) -> Result<(), String> {
+    validate_batch_template(&options.template)?;
+    if options.node_ids.len() > MAX_BATCH_NODES {
+        return Err(format!(
+            "Too many nodes for one batch ({}, limit {MAX_BATCH_NODES})",
+            options.node_ids.len()
+        ));
+    }
+    let project = std::fs::canonicalize(&options.project)
+        .ok()
+        .filter(|path| path.is_file() && is_project_file(path))
+        .ok_or_else(|| format!("Not a project file: {}", options.project.display()))?;
+    let config = load_config(options.config_path.as_deref())?;
+
+    // Keeps the app from opening the project while answers are added
+    let locks = ProjectLocks::default();
+    locks.acquire(&project).map_err(|e| match e {
+        LockError::Held(e) | LockError::Io(e) => e,
+    })?;
+    let result = run(&options, &config, &project).await;
+    locks.release(&project);
+    result
+}
+
+async fn run(options: &BatchOptions, config: &ConfigFile, project: &Path) -> Result<(), String> {
+    let inputs = batch_inputs(&read_project(project)?, &options.node_ids)?;
+
+    // The same working directory as prompts in this project
+    let default_notes_directory = match config.notes_directory() {
+        Some(dir) => dir,
+        None => project
+            .parent()
+            .map(PathBuf::from)
+            .ok_or("Project has no parent directory")?,
+    };
+    let notes_directory = project_notes_directory(
+        &read_project_settings(project)?,
+        default_notes_directory,
+        &config.workspace_directories(),
+    );
+    let provider = options
+        .provider
+        .clone()
+        .unwrap_or_else(|| config.default_provider());
+    let model_override = options
+        .model_id
+        .clone()
+        .or_else(|| config.bulk_model_override(&provider));
+    let provider_id = serde_json::to_value(&provider)
+        .ok()
+        .and_then(|v| v.as_str().map(String::from))
+        .unwrap_or_default();
+    let agent = BatchAgent {
+        provider,
+        model_override,
+        notes_directory,
+        spawn_config: config.spawn_config(),
+        timeout: Duration::from_secs(config.prompt_timeout_secs()),
+    };
+    let queue = Arc::new(GenerationQueue::new(config.max_concurrent_generations()));
+
+    eprintln!(
+        "Running {} nodes on {} in {}",
+        inputs.len(),
+        agent.provider.display_name(),
+        agent.notes_directory.display()
+    );
+    let report = |progress: &BatchProgress| {
+        let step = format!("[{}/{}]", progress.completed, progress.total);
+        match &progress.error {
+            Some(e) => eprintln!("{step} {}: {e}", progress.node_id),
+            None => eprintln!("{step} {}", progress.node_id),
+        }
+    };
+    let mut answers = run_batch(&agent, &options.template, inputs, &queue, report).await;
+
+    if answers.iter().any(|(_, answer)| answer.is_some()) {
+        write_results(project, &mut answers, &provider_id)?;
+    }
+    let results: Vec<_> = answers.into_iter().map(|(result, _)| result).collect();
+    let json = serde_json::to_string_pretty(&results)
+        .map_err(|e| format!("Failed to serialize results: {e}"))?;
+    println!("{json}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_batch_args() {
+        assert_eq!(
+            parse_batch_args(&args(&[
+                "notes/a.thoughttree",
+                "--nodes",
+                "n1, n2",
+                "--nodes",
+                "n3",
+                "--template",
+                "Summarize: {{content}}",
+                "--provider",
+                "gemini-cli",
+            ]))
+            .unwrap(),
+            BatchOptions {
+                project: PathBuf::from("notes/a.thoughttree"),
+                node_ids: ["n1", "n2", "n3"].map(String::from).to_vec(),
+                template: "Summarize: {{content}}".to_string(),
+                provider: Some(AgentProvider::GeminiCli),
+                model_id: None,
+                config_path: None,
+            }
+        );
+        assert!(parse_batch_args(&args(&["--nodes", "n1", "--template", "t"])).is_err());
+        assert!(parse_batch_args(&args(&["a.thoughttree", "--template", "t"])).is_err());
+        assert!(parse_batch_args(&args(&["a.thoughttree", "--nodes", "n1"])).is_err());
+        assert!(parse_batch_args(&args(&["a", "b", "--nodes", "n1", "--template", "t"])).is_err());
+        assert!(parse_batch_args(&args(&["a", "--nodes", "n1", "--repl"])).is_err());
+    }
+}
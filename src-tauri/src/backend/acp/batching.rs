@@ -0,0 +1,76 @@
+use std::time::Duration;
+
+/// How long streamed text may sit in the buffer before it is flushed
+pub(crate) const CHUNK_FLUSH_INTERVAL: Duration = Duration::from_millis(40);
+
+/// Buffered text size that triggers an immediate flush
+pub(crate) const CHUNK_FLUSH_BYTES: usize = 4096;
+
+/// What the caller should do after buffering a chunk
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum BatchAction {
+    /// Buffer is full: emit this text now
+    FlushNow(String),
+    /// First text in an empty buffer: schedule a flush after the interval
+    ScheduleFlush,
+    /// A flush is already scheduled
+    Buffered,
+}
+
+/// Coalesces streamed answer chunks so the frontend gets one event per
+/// interval instead of one per ACP notification
+#[derive(Debug, Default)]
+pub(crate) struct ChunkBatcher {
+    buffer: String,
+    flush_scheduled: bool,
+}
+
+impl ChunkBatcher {
+    pub(crate) fn push(&mut self, text: &str) -> BatchAction {
+        self.buffer.push_str(text);
+        if self.buffer.len() >= CHUNK_FLUSH_BYTES {
+            // A scheduled flush may still fire; it will find the buffer empty
+            return BatchAction::FlushNow(std::mem::take(&mut self.buffer));
+        }
+        if self.flush_scheduled {
+            BatchAction::Buffered
+        } else {
+            self.flush_scheduled = true;
+            BatchAction::ScheduleFlush
+        }
+    }
+
+    /// Take the buffered text, if any, and allow the next chunk to schedule a flush
+    pub(crate) fn take(&mut self) -> Option<String> {
+        self.flush_scheduled = false;
+        if self.buffer.is_empty() {
+            None
+        } else {
+            Some(std::mem::take(&mut self.buffer))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batcher_coalesces_until_flush() {
+        let mut batcher = ChunkBatcher::default();
+        assert_eq!(batcher.push("Hel"), BatchAction::ScheduleFlush);
+        assert_eq!(batcher.push("lo"), BatchAction::Buffered);
+        assert_eq!(batcher.take(), Some("Hello".to_string()));
+        assert_eq!(batcher.take(), None);
+        assert_eq!(batcher.push("!"), BatchAction::ScheduleFlush);
+    }
+
+    #[test]
+    fn test_batcher_flushes_large_buffers_immediately() {
+        let mut batcher = ChunkBatcher::default();
+        assert_eq!(batcher.push("a"), BatchAction::ScheduleFlush);
+        let big = "b".repeat(CHUNK_FLUSH_BYTES);
+        assert_eq!(batcher.push(&big), BatchAction::FlushNow(format!("a{big}")));
+        assert_eq!(batcher.take(), None);
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use tokio::process::Child;
+use tracing::{info, warn};
+
+/// Registry of live agent subprocesses so every one of them (and anything it
+/// spawned) can be terminated when the app exits. `kill_on_drop` alone doesn't
+/// run when the process is torn down mid-generation.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ChildRegistry {
+    pids: Arc<Mutex<HashSet<u32>>>,
+}
+
+impl ChildRegistry {
+    /// Register a freshly spawned child; it is unregistered when the returned
+    /// [`TrackedChild`] is dropped
+    pub(crate) fn track(&self, child: Child) -> TrackedChild {
+        let pid = child.id();
+        if let (Some(pid), Ok(mut pids)) = (pid, self.pids.lock()) {
+            pids.insert(pid);
+        }
+        TrackedChild {
+            child,
+            guard: ChildGuard {
+                pid,
+                pids: self.pids.clone(),
+            },
+        }
+    }
+
+    /// Kill every tracked subprocess together with its process group
+    pub(crate) fn terminate_all(&self) {
+        let pids: Vec<u32> = match self.pids.lock() {
+            Ok(mut pids) => pids.drain().collect(),
+            Err(_) => return,
+        };
+        if pids.is_empty() {
+            return;
+        }
+
+        info!("Terminating {} agent subprocess(es)", pids.len());
+        for pid in pids {
+            terminate_process_tree(pid);
+        }
+    }
+}
+
+/// A spawned agent subprocess that stays registered while alive
+pub(crate) struct TrackedChild {
+    pub child: Child,
+    pub guard: ChildGuard,
+}
+
+/// Removes a child from the registry once it has been reaped or dropped
+pub(crate) struct ChildGuard {
+    pid: Option<u32>,
+    pids: Arc<Mutex<HashSet<u32>>>,
+}
+
+impl Drop for ChildGuard {
+    fn drop(&mut self) {
+        if let (Some(pid), Ok(mut pids)) = (self.pid, self.pids.lock()) {
+            pids.remove(&pid);
+        }
+    }
+}
+
+/// Agents are spawned as process-group leaders, so killing the group also
+/// takes down the CLI and any tools it started
+#[cfg(unix)]
+fn terminate_process_tree(pid: u32) {
+    let Ok(pgid) = libc::pid_t::try_from(pid) else {
+        return;
+    };
+    // SAFETY: killpg only sends a signal; pgid is a group we created at spawn
+    if unsafe { libc::killpg(pgid, libc::SIGKILL) } != 0 {
+        warn!(
+            "Failed to kill process group {}: {}",
+            pgid,
+            std::io::Error::last_os_error()
+        );
+    }
+}
+
+#[cfg(windows)]
+fn terminate_process_tree(pid: u32) {
+    let result = std::process::Command::new("taskkill")
+        .args(["/PID", &pid.to_string(), "/T", "/F"])
+        .output();
+    if let Err(e) = result {
+        warn!("Failed to kill process tree {}: {}", pid, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guard_unregisters_on_drop() {
+        let registry = ChildRegistry::default();
+        let guard = ChildGuard {
+            pid: Some(42),
+            pids: registry.pids.clone(),
+        };
+        registry.pids.lock().unwrap().insert(42);
+
+        drop(guard);
+        assert!(registry.pids.lock().unwrap().is_empty());
+    }
+}
@@ -1,10 +1,13 @@
-use std::collections::HashMap;
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
 
 use agent_client_protocol::{
-    Client, ContentBlock, RequestPermissionOutcome, RequestPermissionRequest,
+    AvailableCommandInput, Client, ContentBlock, EmbeddedResourceResource, ReadTextFileRequest,
+    ReadTextFileResponse, RequestPermissionOutcome, RequestPermissionRequest,
     RequestPermissionResponse, SelectedPermissionOutcome, SessionNotification, SessionUpdate,
+    ToolCallContent, ToolCallLocation,
 };
 use async_trait::async_trait;
 use futures::lock::Mutex;
@@ -12,28 +15,332 @@ use tauri::{AppHandle, Emitter};
 use tokio::sync::oneshot;
 use tracing::{debug, error, info, warn};
 
-use crate::backend::types::{ChunkPayload, PermissionOption, PermissionPayload};
+use crate::backend::acp::batching::{BatchAction, ChunkBatcher, CHUNK_FLUSH_INTERVAL};
+use crate::backend::audit::{self, AuditEntry, AuditKind};
+use crate::backend::config::{self, DEFAULT_PERMISSION_TIMEOUT_SECS};
+use crate::backend::note_files::{
+    read_text_prefix, validate_path_in_notes_dir, MAX_READ_NOTE_BYTES,
+};
+use crate::backend::permissions::PendingPermissions;
+use crate::backend::project::{project_tool_decision, ProjectToolDecision};
+use crate::backend::read_roots::within_read_roots;
+use crate::backend::recovery::{recovery_dir, RecoveryJournal};
+use crate::backend::replay::StreamReplay;
+use crate::backend::types::{
+    AgentCommandInfo, PermissionExpiredPayload, PermissionOption, PermissionPayload,
+    ProjectPermissions, ToolActivityContent, ToolActivityPayload,
+};
+
+/// Cap on forwarded tool result text so large file reads don't flood the IPC bridge
+const MAX_TOOL_CONTENT_CHARS: usize = 2000;
+
+/// Truncate to at most `max_chars` characters without splitting a UTF-8 sequence
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((idx, _)) => format!("{}…", &text[..idx]),
+        None => text.to_string(),
+    }
+}
+
+/// Convert ACP tool call content into the frontend's `tool-activity` shape.
+/// Terminals and binary content carry nothing displayable and are skipped.
+pub(crate) fn tool_activity_content(content: &[ToolCallContent]) -> Vec<ToolActivityContent> {
+    content
+        .iter()
+        .filter_map(|item| match item {
+            ToolCallContent::Content(block) => match &block.content {
+                ContentBlock::Text(text) => Some(ToolActivityContent::Text {
+                    text: truncate_chars(&text.text, MAX_TOOL_CONTENT_CHARS),
+                }),
+                ContentBlock::ResourceLink(link) => Some(ToolActivityContent::Resource {
+                    uri: link.uri.clone(),
+                    text: None,
+                }),
+                ContentBlock::Resource(embedded) => match &embedded.resource {
+                    EmbeddedResourceResource::TextResourceContents(res) => {
+                        Some(ToolActivityContent::Resource {
+                            uri: res.uri.clone(),
+                            text: Some(truncate_chars(&res.text, MAX_TOOL_CONTENT_CHARS)),
+                        })
+                    }
+                    _ => None,
+                },
+                _ => None,
+            },
+            ToolCallContent::Diff(diff) => Some(ToolActivityContent::Diff {
+                path: diff.path.display().to_string(),
+                old_text: diff
+                    .old_text
+                    .as_deref()
+                    .map(|text| truncate_chars(text, MAX_TOOL_CONTENT_CHARS)),
+                new_text: truncate_chars(&diff.new_text, MAX_TOOL_CONTENT_CHARS),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+fn tool_activity_locations(locations: &[ToolCallLocation]) -> Vec<String> {
+    locations
+        .iter()
+        .map(|loc| loc.path.display().to_string())
+        .collect()
+}
+
+/// Cap raw tool input forwarded with permission requests; oversized input is
+/// replaced by its truncated JSON text
+fn permission_raw_input(raw_input: Option<&serde_json::Value>) -> Option<serde_json::Value> {
+    let value = raw_input?;
+    let serialized = value.to_string();
+    if serialized.chars().count() > MAX_TOOL_CONTENT_CHARS {
+        Some(serde_json::Value::String(truncate_chars(
+            &serialized,
+            MAX_TOOL_CONTENT_CHARS,
+        )))
+    } else {
+        Some(value.clone())
+    }
+}
+
+/// Resolve an agent-supplied path and make sure it stays inside the notes
+/// directory or one of the read directories after symlinks are followed
+fn resolve_note_path(
+    notes_directory: &Path,
+    read_roots: &[PathBuf],
+    path: &Path,
+) -> Result<PathBuf, String> {
+    if !path.is_absolute() {
+        return Err(format!("Path must be absolute: {}", path.display()));
+    }
+    if let Ok(in_notes) = validate_path_in_notes_dir(path, notes_directory) {
+        return Ok(in_notes);
+    }
+    let canonical_path = std::fs::canonicalize(path)
+        .map_err(|e| format!("Failed to resolve {}: {e}", path.display()))?;
+    if !within_read_roots(&canonical_path, read_roots) {
+        return Err(format!(
+            "Path {} is outside the notes and read directories",
+            path.display()
+        ));
+    }
+    Ok(canonical_path)
+}
+
+/// Whether every location of a tool call lies in the notes directory or a
+/// read directory. Paths are canonicalized first, so symlinks can't be used
+/// to reach outside them.
+fn locations_allowed(
+    tool_name: &str,
+    locations: &[ToolCallLocation],
+    notes_directory: &Path,
+    read_roots: &[PathBuf],
+) -> bool {
+    let canonical_notes = match std::fs::canonicalize(notes_directory) {
+        Ok(p) => p,
+        Err(e) => {
+            warn!("Failed to canonicalize notes directory: {}", e);
+            return false;
+        }
+    };
+
+    locations.iter().all(|loc| {
+        // Canonicalize the requested path to resolve symlinks
+        let canonical_loc = match std::fs::canonicalize(&loc.path) {
+            Ok(p) => p,
+            Err(e) => {
+                warn!(
+                    "Tool '{}' denied - failed to canonicalize path {:?}: {}",
+                    tool_name, loc.path, e
+                );
+                return false;
+            }
+        };
+        let allowed = canonical_loc.starts_with(&canonical_notes)
+            || within_read_roots(&canonical_loc, read_roots);
+        if !allowed {
+            warn!(
+                "Tool '{}' denied - path {:?} is outside notes and read directories",
+                tool_name, loc.path
+            );
+        }
+        allowed
+    })
+}
+
+/// Apply the optional 1-based `line` offset and `limit` of a read request
+fn select_lines(content: &str, line: Option<u32>, limit: Option<u32>) -> String {
+    if line.is_none() && limit.is_none() {
+        return content.to_string();
+    }
+    let skip = line.map(|l| l.saturating_sub(1) as usize).unwrap_or(0);
+    let take = limit.map(|l| l as usize).unwrap_or(usize::MAX);
+    content
+        .lines()
+        .skip(skip)
+        .take(take)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Serialize an ACP enum (e.g. tool status) to its wire name, like `in_progress`
+fn wire_name<T: serde::Serialize>(value: &T) -> Option<String> {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(String::from))
+}
+
+/// Batched `stream-chunk` emitter for one node, shared with the flush timer task
+struct ChunkStream {
+    app_handle: AppHandle,
+    node_id: String,
+    batcher: std::sync::Mutex<ChunkBatcher>,
+    replay: Arc<StreamReplay>,
+    /// Copy of the answer on disk in case the app crashes mid-stream
+    journal: Option<RecoveryJournal>,
+}
+
+impl ChunkStream {
+    fn emit(&self, chunk: String) {
+        if let Some(journal) = &self.journal {
+            journal.append(&chunk);
+        }
+        let payload = self.replay.record(&self.node_id, &chunk);
+        if let Err(e) = self.app_handle.emit("stream-chunk", payload) {
+            error!("Failed to emit chunk: {:?}", e);
+        }
+    }
+
+    fn push(self: &Arc<Self>, text: &str) {
+        let action = match self.batcher.lock() {
+            Ok(mut batcher) => batcher.push(text),
+            Err(_) => BatchAction::FlushNow(text.to_string()),
+        };
+        match action {
+            BatchAction::FlushNow(chunk) => self.emit(chunk),
+            BatchAction::ScheduleFlush => {
+                let stream = self.clone();
+                tokio::task::spawn_local(async move {
+                    tokio::time::sleep(CHUNK_FLUSH_INTERVAL).await;
+                    stream.flush();
+                });
+            }
+            BatchAction::Buffered => {}
+        }
+    }
+
+    fn flush(&self) {
+        let pending = self.batcher.lock().ok().and_then(|mut b| b.take());
+        if let Some(chunk) = pending {
+            self.emit(chunk);
+        }
+    }
+}
 
 /// ACP Client that streams to frontend and handles permissions via UI
 pub(crate) struct StreamingClient {
     app_handle: AppHandle,
     node_id: String,
-    pending_permissions: Arc<Mutex<HashMap<String, oneshot::Sender<String>>>>,
+    chunks: Arc<ChunkStream>,
+    pending_permissions: Arc<PendingPermissions>,
     notes_directory: PathBuf,
+    /// Directories besides the notes that read-only tools may use
+    read_roots: Vec<PathBuf>,
+    project_permissions: ProjectPermissions,
+    first_chunk_at: Arc<OnceLock<Instant>>,
+    /// Answer chunks and characters received, for throughput metrics
+    chunk_count: AtomicUsize,
+    response_chars: AtomicUsize,
+    /// Thought text streamed so far, recorded in the audit log at the end
+    thoughts: std::sync::Mutex<String>,
 }
 
+/// Thought text kept per generation for the audit log
+const MAX_RECORDED_THOUGHT_CHARS: usize = 4000;
+
 impl StreamingClient {
     pub(crate) fn new(
         app_handle: AppHandle,
         node_id: String,
-        pending_permissions: Arc<Mutex<HashMap<String, oneshot::Sender<String>>>>,
+        pending_permissions: Arc<PendingPermissions>,
         notes_directory: PathBuf,
+        read_roots: Vec<PathBuf>,
+        project_permissions: ProjectPermissions,
+        stream_replay: Arc<StreamReplay>,
     ) -> Self {
+        stream_replay.start_generation(&node_id);
+        let journal = recovery_dir(&app_handle)
+            .and_then(|dir| RecoveryJournal::start(&dir, &node_id))
+            .inspect_err(|e| warn!("Crash recovery disabled for node {}: {}", node_id, e))
+            .ok();
         Self {
+            chunks: Arc::new(ChunkStream {
+                app_handle: app_handle.clone(),
+                node_id: node_id.clone(),
+                batcher: std::sync::Mutex::new(ChunkBatcher::default()),
+                replay: stream_replay,
+                journal,
+            }),
             app_handle,
             node_id,
             pending_permissions,
             notes_directory,
+            read_roots,
+            project_permissions,
+            first_chunk_at: Arc::new(OnceLock::new()),
+            chunk_count: AtomicUsize::new(0),
+            response_chars: AtomicUsize::new(0),
+            thoughts: std::sync::Mutex::new(String::new()),
+        }
+    }
+
+    /// Record the generation's thoughts (truncated) in the audit log, so
+    /// transcripts can show how an answer was reached
+    pub(crate) fn record_thoughts(&self) {
+        let thoughts =
+            std::mem::take(&mut *self.thoughts.lock().unwrap_or_else(|e| e.into_inner()));
+        let thoughts = thoughts.trim();
+        if thoughts.is_empty() {
+            return;
+        }
+        let mut detail: String = thoughts.chars().take(MAX_RECORDED_THOUGHT_CHARS).collect();
+        if detail.len() < thoughts.len() {
+            detail.push('…');
+        }
+        audit::record(
+            &self.app_handle,
+            AuditEntry::new(
+                AuditKind::Thought,
+                &self.node_id,
+                "",
+                "",
+                Vec::new(),
+                String::new(),
+            )
+            .with_detail(detail),
+        );
+    }
+
+    /// Emit any answer text still waiting in the chunk batch
+    pub(crate) fn flush_chunks(&self) {
+        self.chunks.flush();
+    }
+
+    /// When the first answer chunk arrived (unset until then)
+    pub(crate) fn first_chunk_at(&self) -> Arc<OnceLock<Instant>> {
+        self.first_chunk_at.clone()
+    }
+
+    /// Answer chunks and characters received so far
+    pub(crate) fn output_counts(&self) -> (usize, usize) {
+        (
+            self.chunk_count.load(Ordering::Relaxed),
+            self.response_chars.load(Ordering::Relaxed),
+        )
+    }
+
+    fn emit_tool_activity(&self, payload: ToolActivityPayload) {
+        if let Err(e) = self.app_handle.emit("tool-activity", payload) {
+            error!("Failed to emit tool activity: {:?}", e);
         }
     }
 
@@ -49,10 +356,8 @@ impl StreamingClient {
         let (tx, rx) = oneshot::channel();
 
         // Store sender for later
-        {
-            let mut pending = self.pending_permissions.lock().await;
-            pending.insert(request_id.clone(), tx);
-        }
+        self.pending_permissions
+            .insert(&self.node_id, &request_id, tx);
 
         // Build description from tool call
         let tool_type = args.tool_call.tool_call_id.0.to_string();
@@ -63,17 +368,25 @@ impl StreamingClient {
             .clone()
             .unwrap_or_else(|| "Unknown tool".to_string());
 
+        let locations = args
+            .tool_call
+            .fields
+            .locations
+            .as_deref()
+            .map(tool_activity_locations)
+            .unwrap_or_default();
+        let raw_input = permission_raw_input(args.tool_call.fields.raw_input.as_ref());
+        let content = args
+            .tool_call
+            .fields
+            .content
+            .as_deref()
+            .map(tool_activity_content)
+            .unwrap_or_default();
+
         // Format locations or other details as description
-        let description = if let Some(locations) = &args.tool_call.fields.locations {
-            if !locations.is_empty() {
-                locations
-                    .iter()
-                    .map(|loc| loc.path.display().to_string())
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            } else {
-                "No additional details".to_string()
-            }
+        let description = if !locations.is_empty() {
+            locations.join(", ")
         } else {
             "No additional details".to_string()
         };
@@ -95,19 +408,43 @@ impl StreamingClient {
             tool_name,
             description,
             options,
+            locations,
+            raw_input,
+            content,
         };
 
         if let Err(e) = self.app_handle.emit("permission-request", payload) {
             error!("Failed to emit permission request: {:?}", e);
-            let mut pending = self.pending_permissions.lock().await;
-            pending.remove(&request_id);
+            self.pending_permissions.remove(&self.node_id, &request_id);
             return Ok(RequestPermissionResponse::new(
                 RequestPermissionOutcome::Cancelled,
             ));
         }
 
-        // Wait for response from frontend
-        match rx.await {
+        // Wait for response from frontend. An unanswered request (e.g. the
+        // window was closed) is cancelled after the timeout so the agent
+        // doesn't hang.
+        let timeout_secs = config::get_permission_timeout_secs(&self.app_handle)
+            .unwrap_or(DEFAULT_PERMISSION_TIMEOUT_SECS);
+        let Ok(response) = tokio::time::timeout(Duration::from_secs(timeout_secs), rx).await else {
+            warn!(
+                "Permission request {} expired after {}s",
+                request_id, timeout_secs
+            );
+            self.pending_permissions.remove(&self.node_id, &request_id);
+            let payload = PermissionExpiredPayload {
+                id: request_id,
+                node_id: self.node_id.clone(),
+                timeout_secs,
+            };
+            if let Err(e) = self.app_handle.emit("permission-expired", payload) {
+                error!("Failed to emit permission-expired: {:?}", e);
+            }
+            return Ok(RequestPermissionResponse::new(
+                RequestPermissionOutcome::Cancelled,
+            ));
+        };
+        match response {
             Ok(option_id_str) => {
                 info!("Permission response received: {}", option_id_str);
                 Ok(RequestPermissionResponse::new(
@@ -124,11 +461,10 @@ impl StreamingClient {
             }
         }
     }
-}
 
-#[async_trait(?Send)]
-impl Client for StreamingClient {
-    async fn request_permission(
+    /// Apply the permission policy: deny writes/execution, auto-approve read-only
+    /// tools inside the notes directory, prompt for WebFetch, deny everything else
+    async fn decide_permission(
         &self,
         args: RequestPermissionRequest,
     ) -> agent_client_protocol::Result<RequestPermissionResponse> {
@@ -166,46 +502,32 @@ impl Client for StreamingClient {
             ));
         }
 
-        // AUTO-APPROVE: Read-only search tools (within notes directory) and Skills
+        // PROJECT OVERRIDES: can deny more tools or auto-approve extra ones,
+        // but never re-enable the tools denied above
+        let project_decision = project_tool_decision(&self.project_permissions, tool_name);
+        if project_decision == ProjectToolDecision::Deny {
+            warn!("Tool '{}' denied by project permissions", tool_name);
+            return Ok(RequestPermissionResponse::new(
+                RequestPermissionOutcome::Cancelled,
+            ));
+        }
+
+        // AUTO-APPROVE: Read-only search tools (within notes directory or the
+        // configured read directories) and Skills
         let auto_approve_patterns = ["Read", "Grep", "Glob", "WebSearch", "Skill"];
-        if auto_approve_patterns.iter().any(|p| tool_name.contains(p)) {
-            // For file operations, validate they're within notes_directory using canonicalization
-            // This prevents symlink-based path traversal attacks
+        if project_decision == ProjectToolDecision::Allow
+            || auto_approve_patterns.iter().any(|p| tool_name.contains(p))
+        {
             if let Some(locations) = &args.tool_call.fields.locations {
-                let canonical_notes = match std::fs::canonicalize(&self.notes_directory) {
-                    Ok(p) => p,
-                    Err(e) => {
-                        warn!("Failed to canonicalize notes directory: {}", e);
-                        return Ok(RequestPermissionResponse::new(
-                            RequestPermissionOutcome::Cancelled,
-                        ));
-                    }
-                };
-
-                for loc in locations {
-                    // Canonicalize the requested path to resolve symlinks
-                    let canonical_loc = match std::fs::canonicalize(&loc.path) {
-                        Ok(p) => p,
-                        Err(e) => {
-                            warn!(
-                                "Tool '{}' denied - failed to canonicalize path {:?}: {}",
-                                tool_name, loc.path, e
-                            );
-                            return Ok(RequestPermissionResponse::new(
-                                RequestPermissionOutcome::Cancelled,
-                            ));
-                        }
-                    };
-
-                    if !canonical_loc.starts_with(&canonical_notes) {
-                        warn!(
-                            "Tool '{}' denied - path {:?} is outside notes directory",
-                            tool_name, loc.path
-                        );
-                        return Ok(RequestPermissionResponse::new(
-                            RequestPermissionOutcome::Cancelled,
-                        ));
-                    }
+                if !locations_allowed(
+                    tool_name,
+                    locations,
+                    &self.notes_directory,
+                    &self.read_roots,
+                ) {
+                    return Ok(RequestPermissionResponse::new(
+                        RequestPermissionOutcome::Cancelled,
+                    ));
                 }
             }
 
@@ -232,6 +554,102 @@ impl Client for StreamingClient {
             RequestPermissionOutcome::Cancelled,
         ))
     }
+}
+
+#[async_trait(?Send)]
+impl Client for StreamingClient {
+    async fn request_permission(
+        &self,
+        args: RequestPermissionRequest,
+    ) -> agent_client_protocol::Result<RequestPermissionResponse> {
+        let tool_name = args
+            .tool_call
+            .fields
+            .title
+            .clone()
+            .unwrap_or_else(|| "Unknown".to_string());
+        let tool_id = args.tool_call.tool_call_id.0.to_string();
+        let paths = args
+            .tool_call
+            .fields
+            .locations
+            .as_deref()
+            .map(tool_activity_locations)
+            .unwrap_or_default();
+
+        let response = self.decide_permission(args).await?;
+
+        let outcome = match &response.outcome {
+            RequestPermissionOutcome::Selected(selected) => {
+                format!("selected:{}", selected.option_id.0)
+            }
+            _ => "cancelled".to_string(),
+        };
+        audit::record(
+            &self.app_handle,
+            AuditEntry::new(
+                AuditKind::Permission,
+                &self.node_id,
+                &tool_id,
+                &tool_name,
+                paths,
+                outcome,
+            ),
+        );
+
+        Ok(response)
+    }
+
+    /// Serve note reads ourselves so agents go through our path validation
+    async fn read_text_file(
+        &self,
+        args: ReadTextFileRequest,
+    ) -> agent_client_protocol::Result<ReadTextFileResponse> {
+        let requested = args.path.display().to_string();
+        let result = match resolve_note_path(&self.notes_directory, &self.read_roots, &args.path) {
+            Ok(path) => {
+                tokio::task::spawn_blocking(move || read_text_prefix(&path, MAX_READ_NOTE_BYTES))
+                    .await
+                    .map_err(|e| format!("Failed to read {requested}: {e}"))
+                    .and_then(|read| read)
+            }
+            Err(e) => Err(e),
+        };
+
+        audit::record(
+            &self.app_handle,
+            AuditEntry::new(
+                AuditKind::FileRead,
+                &self.node_id,
+                "",
+                "read_text_file",
+                vec![requested],
+                if result.is_ok() { "allowed" } else { "denied" }.to_string(),
+            ),
+        );
+
+        match result {
+            Ok((mut content, truncated)) => {
+                if truncated {
+                    warn!(
+                        "read_text_file cut {} at {} bytes",
+                        args.path.display(),
+                        MAX_READ_NOTE_BYTES
+                    );
+                    content.push_str(&format!(
+                        "\n[Truncated: only the first {MAX_READ_NOTE_BYTES} bytes were read]"
+                    ));
+                }
+                Ok(ReadTextFileResponse::new(select_lines(
+                    &content, args.line, args.limit,
+                )))
+            }
+            Err(e) => {
+                warn!("read_text_file denied: {}", e);
+                Err(agent_client_protocol::Error::invalid_params().data(e))
+            }
+        }
+    }
 
     async fn session_notification(
         &self,
@@ -240,26 +658,66 @@ impl Client for StreamingClient {
         match args.update {
             SessionUpdate::AgentMessageChunk(chunk) => {
                 if let ContentBlock::Text(text) = chunk.content {
-                    // Send chunk to frontend
-                    let payload = ChunkPayload {
-                        node_id: self.node_id.clone(),
-                        chunk: text.text,
-                    };
-                    if let Err(e) = self.app_handle.emit("stream-chunk", payload) {
-                        error!("Failed to emit chunk: {:?}", e);
-                    }
+                    let _ = self.first_chunk_at.set(Instant::now());
+                    self.chunk_count.fetch_add(1, Ordering::Relaxed);
+                    self.response_chars
+                        .fetch_add(text.text.chars().count(), Ordering::Relaxed);
+                    // Send chunk to frontend (batched)
+                    self.chunks.push(&text.text);
                 }
             }
             SessionUpdate::AgentThoughtChunk(chunk) => {
                 if let ContentBlock::Text(text) = chunk.content {
                     debug!("[Thought] {}", text.text);
+                    let mut thoughts = self.thoughts.lock().unwrap_or_else(|e| e.into_inner());
+                    // Stop collecting well past what is recorded
+                    if thoughts.len() < MAX_RECORDED_THOUGHT_CHARS * 4 {
+                        thoughts.push_str(&text.text);
+                    }
                 }
             }
             SessionUpdate::ToolCall(tc) => {
                 info!("[Tool Call] {:?}", tc);
+                audit::record(
+                    &self.app_handle,
+                    AuditEntry::new(
+                        AuditKind::ToolCall,
+                        &self.node_id,
+                        &tc.tool_call_id.0,
+                        &tc.title,
+                        tool_activity_locations(&tc.locations),
+                        wire_name(&tc.status).unwrap_or_default(),
+                    ),
+                );
+                self.emit_tool_activity(ToolActivityPayload {
+                    node_id: self.node_id.clone(),
+                    tool_call_id: tc.tool_call_id.0.to_string(),
+                    title: Some(tc.title.clone()),
+                    status: wire_name(&tc.status),
+                    locations: tool_activity_locations(&tc.locations),
+                    content: tool_activity_content(&tc.content),
+                });
             }
             SessionUpdate::ToolCallUpdate(update) => {
                 debug!("[Tool Update] {:?}", update);
+                self.emit_tool_activity(ToolActivityPayload {
+                    node_id: self.node_id.clone(),
+                    tool_call_id: update.tool_call_id.0.to_string(),
+                    title: update.fields.title.clone(),
+                    status: update.fields.status.as_ref().and_then(wire_name),
+                    locations: update
+                        .fields
+                        .locations
+                        .as_deref()
+                        .map(tool_activity_locations)
+                        .unwrap_or_default(),
+                    content: update
+                        .fields
+                        .content
+                        .as_deref()
+                        .map(tool_activity_content)
+                        .unwrap_or_default(),
+                });
             }
             SessionUpdate::Plan(plan) => {
                 debug!("[Plan] {:?}", plan);
@@ -272,7 +730,7 @@ impl Client for StreamingClient {
     }
 }
 
-/// Minimal ACP client just for model discovery - no streaming or permissions needed
+/// Minimal ACP client for model discovery and authentication - no streaming or permissions needed
 pub(crate) struct ModelDiscoveryClient;
 
 #[async_trait(?Send)]
@@ -296,6 +754,54 @@ impl Client for ModelDiscoveryClient {
     }
 }
 
+/// ACP client for capability probing - records advertised slash commands
+pub(crate) struct CapabilityProbeClient {
+    pub available_commands: Arc<Mutex<Vec<AgentCommandInfo>>>,
+}
+
+impl CapabilityProbeClient {
+    pub fn new() -> Self {
+        Self {
+            available_commands: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl Client for CapabilityProbeClient {
+    async fn request_permission(
+        &self,
+        _args: RequestPermissionRequest,
+    ) -> agent_client_protocol::Result<RequestPermissionResponse> {
+        // No prompts are sent while probing
+        Ok(RequestPermissionResponse::new(
+            RequestPermissionOutcome::Cancelled,
+        ))
+    }
+
+    async fn session_notification(
+        &self,
+        args: SessionNotification,
+    ) -> agent_client_protocol::Result<()> {
+        if let SessionUpdate::AvailableCommandsUpdate(update) = args.update {
+            let mut commands = self.available_commands.lock().await;
+            *commands = update
+                .available_commands
+                .into_iter()
+                .map(|command| AgentCommandInfo {
+                    input_hint: match command.input {
+                        Some(AvailableCommandInput::Unstructured(input)) => Some(input.hint),
+                        _ => None,
+                    },
+                    name: command.name,
+                    description: command.description,
+                })
+                .collect();
+        }
+        Ok(())
+    }
+}
+
 /// Simple ACP client for summarization - collects response text, auto-approves all tools
 pub(crate) struct SummaryClient {
     pub response_text: Arc<Mutex<String>>,
@@ -318,32 +824,86 @@ pub(crate) fn is_allowed_summary_tool(tool_name: &str) -> bool {
         .any(|pattern| tool_name.contains(pattern))
 }
 
+/// Answer a permission request without asking anyone: read-only tools
+/// (see [`is_allowed_summary_tool`]) are allowed, everything else denied
+fn read_only_permission(tag: &str, args: &RequestPermissionRequest) -> RequestPermissionResponse {
+    let tool_name = args.tool_call.fields.title.as_deref().unwrap_or("Unknown");
+    if !is_allowed_summary_tool(tool_name) {
+        warn!("[{}] denying tool request: {}", tag, tool_name);
+        return RequestPermissionResponse::new(RequestPermissionOutcome::Cancelled);
+    }
+
+    // For explicitly allowed read-only tools, select the first option (typically Allow).
+    if let Some(first_opt) = args.options.first() {
+        return RequestPermissionResponse::new(RequestPermissionOutcome::Selected(
+            SelectedPermissionOutcome::new(first_opt.option_id.clone()),
+        ));
+    }
+
+    RequestPermissionResponse::new(RequestPermissionOutcome::Cancelled)
+}
+
 #[async_trait(?Send)]
 impl Client for SummaryClient {
     async fn request_permission(
         &self,
         args: RequestPermissionRequest,
     ) -> agent_client_protocol::Result<RequestPermissionResponse> {
-        let tool_name = args.tool_call.fields.title.as_deref().unwrap_or("Unknown");
-        if !is_allowed_summary_tool(tool_name) {
-            warn!("[summary] denying tool request: {}", tool_name);
-            return Ok(RequestPermissionResponse::new(
-                RequestPermissionOutcome::Cancelled,
-            ));
+        Ok(read_only_permission("summary", &args))
+    }
+
+    async fn session_notification(
+        &self,
+        args: SessionNotification,
+    ) -> agent_client_protocol::Result<()> {
+        if let SessionUpdate::AgentMessageChunk(chunk) = args.update {
+            if let ContentBlock::Text(text) = chunk.content {
+                let mut response = self.response_text.lock().await;
+                response.push_str(&text.text);
+            }
         }
+        Ok(())
+    }
+}
 
-        // For explicitly allowed read-only tools, select the first option (typically Allow).
-        if let Some(first_opt) = args.options.first() {
-            return Ok(RequestPermissionResponse::new(
-                RequestPermissionOutcome::Selected(SelectedPermissionOutcome::new(
-                    first_opt.option_id.clone(),
-                )),
-            ));
+/// ACP client for the `acp` terminal REPL: prints answer chunks to stdout
+/// as they arrive. Nobody can be asked about tools there, so it follows the
+/// summary policy of read-only tools only, limited like the app's to the
+/// notes and read directories.
+pub(crate) struct TerminalClient {
+    notes_directory: PathBuf,
+    read_roots: Vec<PathBuf>,
+}
+
+impl TerminalClient {
+    pub(crate) fn new(notes_directory: PathBuf, read_roots: Vec<PathBuf>) -> Self {
+        Self {
+            notes_directory,
+            read_roots,
         }
+    }
+}
 
-        Ok(RequestPermissionResponse::new(
-            RequestPermissionOutcome::Cancelled,
-        ))
+#[async_trait(?Send)]
+impl Client for TerminalClient {
+    async fn request_permission(
+        &self,
+        args: RequestPermissionRequest,
+    ) -> agent_client_protocol::Result<RequestPermissionResponse> {
+        let tool_name = args.tool_call.fields.title.as_deref().unwrap_or("Unknown");
+        if let Some(locations) = &args.tool_call.fields.locations {
+            if !locations_allowed(
+                tool_name,
+                locations,
+                &self.notes_directory,
+                &self.read_roots,
+            ) {
+                return Ok(RequestPermissionResponse::new(
+                    RequestPermissionOutcome::Cancelled,
+                ));
+            }
+        }
+        Ok(read_only_permission("repl", &args))
     }
 
     async fn session_notification(
@@ -352,8 +912,11 @@ impl Client for SummaryClient {
     ) -> agent_client_protocol::Result<()> {
         if let SessionUpdate::AgentMessageChunk(chunk) = args.update {
             if let ContentBlock::Text(text) = chunk.content {
-                let mut response = self.response_text.lock().await;
-                response.push_str(&text.text);
+                use std::io::Write;
+                let mut stdout = std::io::stdout().lock();
+                let _ = stdout
+                    .write_all(text.text.as_bytes())
+                    .and_then(|_| stdout.flush());
             }
         }
         Ok(())
@@ -362,7 +925,18 @@ impl Client for SummaryClient {
 
 #[cfg(test)]
 mod tests {
-    use super::is_allowed_summary_tool;
+    use super::{
+        is_allowed_summary_tool, locations_allowed, permission_raw_input, resolve_note_path,
+        select_lines, truncate_chars,
+    };
+    use crate::backend::test_util::TempDir;
+    use agent_client_protocol::ToolCallLocation;
+
+    #[test]
+    fn test_truncate_chars_is_utf8_safe() {
+        assert_eq!(truncate_chars("short", 10), "short");
+        assert_eq!(truncate_chars("ääääää", 3), "äää…");
+    }
 
     #[test]
     fn test_summary_tool_allowlist_only_allows_read_tools() {
@@ -373,4 +947,78 @@ mod tests {
         assert!(!is_allowed_summary_tool("Write"));
         assert!(!is_allowed_summary_tool("WebFetch"));
     }
+
+    #[test]
+    fn test_permission_raw_input_caps_large_input() {
+        let small = serde_json::json!({ "url": "https://example.com" });
+        assert_eq!(permission_raw_input(Some(&small)), Some(small.clone()));
+        assert_eq!(permission_raw_input(None), None);
+
+        let large = serde_json::json!({ "content": "x".repeat(5000) });
+        let capped = permission_raw_input(Some(&large)).unwrap();
+        assert!(capped.as_str().unwrap().ends_with('…'));
+    }
+
+    #[test]
+    fn test_select_lines() {
+        let content = "one\ntwo\nthree\nfour";
+        assert_eq!(select_lines(content, None, None), content);
+        assert_eq!(select_lines(content, Some(2), Some(2)), "two\nthree");
+        assert_eq!(select_lines(content, Some(3), None), "three\nfour");
+        assert_eq!(select_lines(content, None, Some(1)), "one");
+    }
+
+    #[test]
+    fn test_resolve_note_path_rejects_outside_and_relative() {
+        let notes = TempDir::new("notes");
+        let inside = notes.join("note.md");
+        std::fs::write(&inside, "hi").unwrap();
+
+        assert!(resolve_note_path(&notes, &[], &inside).is_ok());
+        assert!(resolve_note_path(&notes, &[], std::path::Path::new("note.md")).is_err());
+        assert!(resolve_note_path(&notes, &[], &notes.join("../")).is_err());
+    }
+
+    #[test]
+    fn test_resolve_note_path_accepts_read_roots() {
+        let notes = TempDir::new("notes");
+        let papers = TempDir::new("papers");
+        let paper = papers.join("paper.md");
+        std::fs::write(&paper, "abstract").unwrap();
+        let roots = [std::fs::canonicalize(&papers).unwrap()];
+
+        assert!(resolve_note_path(&notes, &[], &paper).is_err());
+        let resolved = resolve_note_path(&notes, &roots, &paper).unwrap();
+        assert_eq!(std::fs::read_to_string(resolved).unwrap(), "abstract");
+    }
+
+    #[test]
+    fn test_locations_allowed_in_notes_and_read_roots() {
+        let notes = TempDir::new("notes");
+        let papers = TempDir::new("papers");
+        let elsewhere = TempDir::new("elsewhere");
+        let roots = [std::fs::canonicalize(&papers).unwrap()];
+        let location = |dir: &std::path::Path| [ToolCallLocation::new(dir.to_path_buf())];
+
+        assert!(locations_allowed("Read", &location(&notes), &notes, &[]));
+        assert!(locations_allowed(
+            "Read",
+            &location(&papers),
+            &notes,
+            &roots
+        ));
+        assert!(!locations_allowed("Read", &location(&papers), &notes, &[]));
+        assert!(!locations_allowed(
+            "Grep",
+            &location(&elsewhere),
+            &notes,
+            &roots
+        ));
+        assert!(!locations_allowed(
+            "Read",
+            &location(&notes.join("missing.md")),
+            &notes,
+            &[]
+        ));
+    }
 }
@@ -1,3 +1,8 @@
+pub(crate) mod batch_cli;
+pub(crate) mod batching;
+pub(crate) mod children;
 pub(crate) mod clients;
 pub(crate) mod process;
+pub(crate) mod repl;
+pub(crate) mod sandbox;
 pub(crate) mod sessions;
@@ -1,50 +1,101 @@
+use std::env::consts::EXE_SUFFIX;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
 
 use tokio::process::Command;
 use tracing::{info, warn};
 
-use crate::backend::types::{AgentProvider, ProviderPaths};
+use crate::backend::acp::children::TrackedChild;
+use crate::backend::acp::sandbox::{
+    sandbox_profile, sandbox_supported, sandboxed_command, SandboxPaths,
+};
+use crate::backend::agent_env::apply_agent_env;
+use crate::backend::gemini_cli::{GeminiSettings, API_KEY_VARIABLE, SETTINGS_PATH_VARIABLE};
+use crate::backend::proxy::apply_proxy_env;
+use crate::backend::secrets::{self, GEMINI_API_KEY_SECRET};
+use crate::backend::types::{AgentProvider, SpawnConfig};
+
+/// npm package the bundled sidecar is built from, run via npx as a fallback.
+/// Pinned to the sidecar's version (see `scripts/build-sidecar.sh`) so the
+/// fallback never runs whatever was last published.
+const CLAUDE_CODE_ACP_PACKAGE: &str = "@zed-industries/claude-code-acp@0.12.6";
+
+/// How the claude-code-acp adapter will be launched
+pub(crate) enum ClaudeAcpLauncher {
+    /// Bundled (or dev-built) sidecar binary
+    Sidecar(PathBuf),
+    /// `npx` executable that will fetch and run the npm package (slower startup)
+    Npx(PathBuf),
+}
+
+/// Resolve the claude-code-acp launcher: the sidecar if present, otherwise npx
+/// when the fallback is enabled in settings
+pub(crate) fn find_claude_acp_launcher(npx_fallback: bool) -> Option<ClaudeAcpLauncher> {
+    if let Some(sidecar) = find_sidecar_path() {
+        return Some(ClaudeAcpLauncher::Sidecar(sidecar));
+    }
+    if npx_fallback {
+        if let Some(npx) = find_npx_executable() {
+            warn!(
+                "claude-code-acp sidecar not found, falling back to npx at {:?}",
+                npx
+            );
+            return Some(ClaudeAcpLauncher::Npx(npx));
+        }
+    }
+    None
+}
+
+/// Target triple the sidecar is suffixed with in `src-tauri/binaries`
+/// (must match the triples produced by `scripts/build-sidecar.sh`)
+fn sidecar_target_triple() -> Option<&'static str> {
+    if cfg!(all(target_os = "macos", target_arch = "aarch64")) {
+        Some("aarch64-apple-darwin")
+    } else if cfg!(all(target_os = "macos", target_arch = "x86_64")) {
+        Some("x86_64-apple-darwin")
+    } else if cfg!(all(target_os = "linux", target_arch = "x86_64")) {
+        Some("x86_64-unknown-linux-gnu")
+    } else if cfg!(all(target_os = "linux", target_arch = "aarch64")) {
+        Some("aarch64-unknown-linux-gnu")
+    } else if cfg!(all(target_os = "windows", target_arch = "x86_64")) {
+        Some("x86_64-pc-windows-msvc")
+    } else {
+        None
+    }
+}
 
 /// Find the bundled claude-code-acp sidecar binary
 pub(crate) fn find_sidecar_path() -> Option<PathBuf> {
     let exe = std::env::current_exe().ok()?;
     let exe_dir = exe.parent()?;
+    let sidecar_name = format!("claude-code-acp{EXE_SUFFIX}");
 
     // Standard location: next to the main executable
-    let sidecar = exe_dir.join("claude-code-acp");
+    let sidecar = exe_dir.join(&sidecar_name);
     if sidecar.exists() {
         return Some(sidecar);
     }
 
     // Development: check src-tauri/binaries with target triple
-    // Get the target triple for the current platform
-    #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
-    let target_triple = "aarch64-apple-darwin";
-    #[cfg(all(target_os = "macos", target_arch = "x86_64"))]
-    let target_triple = "x86_64-apple-darwin";
-    #[cfg(not(target_os = "macos"))]
-    let target_triple = "";
-
-    if !target_triple.is_empty() {
+    if let Some(target_triple) = sidecar_target_triple() {
         // Try to find in development location
         // Walk up from exe to find src-tauri/binaries
         let mut current = exe_dir.to_path_buf();
         for _ in 0..10 {
             let dev_sidecar = current
                 .join("src-tauri/binaries")
-                .join(format!("claude-code-acp-{target_triple}"));
+                .join(format!("claude-code-acp-{target_triple}{EXE_SUFFIX}"));
             if dev_sidecar.exists() {
                 return Some(dev_sidecar);
             }
 
             // Also check Cargo build outputs in dev workflows
             let dev_target = current.join("src-tauri/target");
-            let dev_debug = dev_target.join("debug/claude-code-acp");
+            let dev_debug = dev_target.join("debug").join(&sidecar_name);
             if dev_debug.exists() {
                 return Some(dev_debug);
             }
-            let dev_release = dev_target.join("release/claude-code-acp");
+            let dev_release = dev_target.join("release").join(&sidecar_name);
             if dev_release.exists() {
                 return Some(dev_release);
             }
@@ -58,6 +109,130 @@ pub(crate) fn find_sidecar_path() -> Option<PathBuf> {
     None
 }
 
+/// File names an installed CLI may have on this platform.
+/// On Windows, npm installs `.cmd` shims while native installers ship `.exe`.
+fn executable_names(base: &str) -> Vec<String> {
+    if cfg!(target_os = "windows") {
+        vec![format!("{base}.exe"), format!("{base}.cmd")]
+    } else {
+        vec![base.to_string()]
+    }
+}
+
+/// System-wide install directories for agent CLIs (in order of preference)
+fn system_bin_dirs() -> Vec<PathBuf> {
+    let mut dirs_list = Vec::new();
+
+    if cfg!(target_os = "macos") {
+        // Homebrew on Apple Silicon
+        dirs_list.push(PathBuf::from("/opt/homebrew/bin"));
+        // Homebrew on Intel Mac
+        dirs_list.push(PathBuf::from("/usr/local/bin"));
+    } else if cfg!(target_os = "linux") {
+        dirs_list.push(PathBuf::from("/usr/local/bin"));
+        // Distribution packages
+        dirs_list.push(PathBuf::from("/usr/bin"));
+        // Snap packages
+        dirs_list.push(PathBuf::from("/snap/bin"));
+        // Homebrew on Linux
+        dirs_list.push(PathBuf::from("/home/linuxbrew/.linuxbrew/bin"));
+    } else if cfg!(target_os = "windows") {
+        // %LOCALAPPDATA%: winget links and per-user program installs
+        if let Some(local_app_data) = dirs::data_local_dir() {
+            dirs_list.push(local_app_data.join("Microsoft/WinGet/Links"));
+            dirs_list.push(local_app_data.join("Programs/claude"));
+        }
+        // %APPDATA%\npm: npm global installs
+        if let Some(app_data) = dirs::data_dir() {
+            dirs_list.push(app_data.join("npm"));
+        }
+    }
+
+    dirs_list
+}
+
+/// Per-user install directories shared by all agent CLIs (in order of preference)
+fn user_bin_dirs(home: &Path) -> Vec<PathBuf> {
+    vec![
+        // XDG-style local bin (also used by the native installer on Windows)
+        home.join(".local/bin"),
+        // bun global install
+        home.join(".bun/bin"),
+        // npm global install (standard location)
+        home.join(".npm-global/bin"),
+        // Scoop shims on Windows
+        home.join("scoop/shims"),
+    ]
+}
+
+/// Return the first existing `base` executable in `dirs_list`, logging where it was found
+fn find_in_dirs(dirs_list: &[PathBuf], base: &str, label: &str) -> Option<PathBuf> {
+    let names = executable_names(base);
+    for dir in dirs_list {
+        for name in &names {
+            let path = dir.join(name);
+            if path.exists() {
+                // Log canonical path for debugging, but return original path for execution
+                // (Homebrew symlinks point to wrapper scripts that must be executed directly)
+                if let Ok(canonical) = std::fs::canonicalize(&path) {
+                    info!(
+                        "Found {} at {:?} (resolves to: {:?})",
+                        label, path, canonical
+                    );
+                } else {
+                    info!("Found {} at {:?}", label, path);
+                }
+                return Some(path);
+            }
+        }
+    }
+    None
+}
+
+/// Find `base` under any nvm-managed Node version (no globbing)
+fn find_in_nvm(home: &Path, base: &str, label: &str) -> Option<PathBuf> {
+    let nvm_base = home.join(".nvm/versions/node");
+    let entries = std::fs::read_dir(&nvm_base).ok()?;
+    let bin_dirs: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path().join("bin"))
+        .collect();
+    find_in_dirs(&bin_dirs, base, label)
+}
+
+/// Find npx in known install locations (used for the sidecar fallback)
+/// Security: Same known-paths policy as the agent CLIs, no PATH lookup
+fn find_npx_executable() -> Option<PathBuf> {
+    if let Some(path) = find_in_dirs(&system_bin_dirs(), "npx", "npx") {
+        return Some(path);
+    }
+    let home = dirs::home_dir()?;
+    find_in_dirs(&user_bin_dirs(&home), "npx", "npx")
+        .or_else(|| find_in_nvm(&home, "npx", "npx in nvm path"))
+}
+
+/// Resolve `base` against the `PATH` of the app process, `which`-style.
+/// Security: Only used on explicit user opt-in; the result is returned to the
+/// frontend for confirmation and never used for spawning until persisted.
+pub(crate) fn find_on_path(base: &str) -> Option<PathBuf> {
+    let path_var = std::env::var_os("PATH")?;
+    let names = executable_names(base);
+    for dir in std::env::split_paths(&path_var) {
+        // Relative PATH entries depend on the cwd and are never trusted
+        if !dir.is_absolute() {
+            continue;
+        }
+        for name in &names {
+            let candidate = dir.join(name);
+            if candidate.is_file() {
+                info!("Resolved {} on PATH at {:?}", base, candidate);
+                return Some(candidate);
+            }
+        }
+    }
+    None
+}
+
 /// Find the Claude Code CLI executable
 /// Security: Only checks known installation paths
 /// If custom_path is provided, it's checked first (after env var)
@@ -101,70 +276,23 @@ pub(crate) fn find_claude_code_executable(custom_path: Option<&str>) -> Option<P
         }
     }
 
-    // Known installation paths (in order of preference)
-    let known_paths = [
-        // Homebrew on Apple Silicon
-        "/opt/homebrew/bin/claude",
-        // Homebrew on Intel Mac
-        "/usr/local/bin/claude",
-    ];
-
-    for path_str in known_paths {
-        let path = PathBuf::from(path_str);
-        if path.exists() {
-            // Log canonical path for debugging, but return original path for execution
-            // (Homebrew symlinks point to wrapper scripts that must be executed directly)
-            if let Ok(canonical) = std::fs::canonicalize(&path) {
-                info!(
-                    "Found Claude CLI at {:?} (resolves to: {:?})",
-                    path, canonical
-                );
-            } else {
-                info!("Found Claude CLI at {:?}", path);
-            }
-            return Some(path);
-        }
+    // Known system-wide installation paths
+    if let Some(path) = find_in_dirs(&system_bin_dirs(), "claude", "Claude CLI") {
+        return Some(path);
     }
 
     // Native install script location and common user-local installs
     // Use dirs crate pattern for home directory (more reliable than HOME env var)
     if let Some(home) = dirs::home_dir() {
-        let native_install = home.join(".claude/local/claude");
-        let local_bin = home.join(".local/bin/claude"); // XDG-style local bin
-        let bun_install = home.join(".bun/bin/claude");
-        let npm_global = home.join(".npm-global/bin/claude");
-
-        for path in [native_install, local_bin, bun_install, npm_global] {
-            if path.exists() {
-                if let Ok(canonical) = std::fs::canonicalize(&path) {
-                    info!(
-                        "Found Claude CLI at {:?} (resolves to: {:?})",
-                        path, canonical
-                    );
-                } else {
-                    info!("Found Claude CLI at {:?}", path);
-                }
-                return Some(path);
-            }
+        let mut user_dirs = vec![home.join(".claude/local")];
+        user_dirs.extend(user_bin_dirs(&home));
+        if let Some(path) = find_in_dirs(&user_dirs, "claude", "Claude CLI") {
+            return Some(path);
         }
 
         // nvm-managed npm globals: iterate known Node versions (no globbing)
-        let nvm_base = home.join(".nvm/versions/node");
-        if let Ok(entries) = std::fs::read_dir(&nvm_base) {
-            for entry in entries.flatten() {
-                let candidate = entry.path().join("bin/claude");
-                if candidate.exists() {
-                    if let Ok(canonical) = std::fs::canonicalize(&candidate) {
-                        info!(
-                            "Found Claude CLI in nvm path {:?} (resolves to: {:?})",
-                            candidate, canonical
-                        );
-                    } else {
-                        info!("Found Claude CLI in nvm path {:?}", candidate);
-                    }
-                    return Some(candidate);
-                }
-            }
+        if let Some(path) = find_in_nvm(&home, "claude", "Claude CLI in nvm path") {
+            return Some(path);
         }
     }
 
@@ -177,6 +305,27 @@ pub(crate) fn find_claude_code_executable(custom_path: Option<&str>) -> Option<P
 /// Find the Gemini CLI executable
 /// Security: Only checks known installation paths
 /// If custom_path is provided, it's checked first
+/// Find whisper.cpp's `whisper-stream`, used for dictation. Like the agent
+/// CLIs, it is only looked up in known install locations, never on PATH.
+pub(crate) fn find_whisper_stream_executable(custom_path: Option<&str>) -> Option<PathBuf> {
+    if let Some(custom) = custom_path {
+        let candidate = PathBuf::from(custom);
+        if candidate.is_file() {
+            info!("Using custom whisper-stream path at {:?}", candidate);
+            return Some(candidate);
+        }
+        warn!(
+            "Custom whisper-stream path does not exist at {:?}",
+            candidate
+        );
+    }
+    if let Some(path) = find_in_dirs(&system_bin_dirs(), "whisper-stream", "whisper-stream") {
+        return Some(path);
+    }
+    let home = dirs::home_dir()?;
+    find_in_dirs(&user_bin_dirs(&home), "whisper-stream", "whisper-stream")
+}
+
 pub(crate) fn find_gemini_cli_executable(custom_path: Option<&str>) -> Option<PathBuf> {
     // First priority: user-configured custom path from settings
     if let Some(custom) = custom_path {
@@ -196,58 +345,20 @@ pub(crate) fn find_gemini_cli_executable(custom_path: Option<&str>) -> Option<Pa
         }
     }
 
-    // Known installation paths (in order of preference)
-    let known_paths = [
-        // Homebrew on Apple Silicon
-        "/opt/homebrew/bin/gemini",
-        // Homebrew on Intel Mac
-        "/usr/local/bin/gemini",
-    ];
-
-    for path_str in known_paths {
-        let path = PathBuf::from(path_str);
-        if path.exists() {
-            // Log canonical path for debugging, but return original path for execution
-            // (Homebrew symlinks point to wrapper scripts that must be executed directly)
-            if let Ok(canonical) = std::fs::canonicalize(&path) {
-                info!(
-                    "Found Gemini CLI at {:?} (resolves to: {:?})",
-                    path, canonical
-                );
-            } else {
-                info!("Found Gemini CLI at {:?}", path);
-            }
-            return Some(path);
-        }
+    // Known system-wide installation paths
+    if let Some(path) = find_in_dirs(&system_bin_dirs(), "gemini", "Gemini CLI") {
+        return Some(path);
     }
 
     // Check user-local installation paths
     if let Some(home) = dirs::home_dir() {
-        let user_paths = [
-            // bun global install
-            home.join(".bun/bin/gemini"),
-            // npm global install (standard location)
-            home.join(".npm-global/bin/gemini"),
-            // nvm-managed npm global
-            home.join(".nvm/versions/node").join("*/bin/gemini"),
-        ];
-
-        for path in user_paths {
-            // Skip glob patterns (nvm path) - would need expansion
-            if path.to_string_lossy().contains('*') {
-                continue;
-            }
-            if path.exists() {
-                if let Ok(canonical) = std::fs::canonicalize(&path) {
-                    info!(
-                        "Found Gemini CLI at {:?} (resolves to: {:?})",
-                        path, canonical
-                    );
-                } else {
-                    info!("Found Gemini CLI at {:?}", path);
-                }
-                return Some(path);
-            }
+        if let Some(path) = find_in_dirs(&user_bin_dirs(&home), "gemini", "Gemini CLI") {
+            return Some(path);
+        }
+
+        // nvm-managed npm globals: iterate known Node versions (no globbing)
+        if let Some(path) = find_in_nvm(&home, "gemini", "Gemini CLI in nvm path") {
+            return Some(path);
         }
     }
 
@@ -257,53 +368,163 @@ pub(crate) fn find_gemini_cli_executable(custom_path: Option<&str>) -> Option<Pa
     None
 }
 
-/// Spawn the claude-code-acp sidecar
+/// Spawn the claude-code-acp sidecar (or its npx fallback)
 pub(crate) async fn spawn_claude_code_acp(
     notes_directory: &Path,
-    custom_path: Option<&str>,
-) -> anyhow::Result<tokio::process::Child> {
-    let sidecar_path = find_sidecar_path().ok_or_else(|| {
+    spawn_config: &SpawnConfig,
+) -> anyhow::Result<TrackedChild> {
+    let launcher = find_claude_acp_launcher(spawn_config.npx_fallback).ok_or_else(|| {
         anyhow::anyhow!(
             "claude-code-acp sidecar not found.\n\
              For development: run 'bun run build:sidecar' first.\n\
-             For users: the app bundle may be corrupted."
+             For users: the app bundle may be corrupted.\n\
+             Enabling the npx fallback in settings can work around this."
         )
     })?;
 
     // Find Claude Code CLI for the sidecar to use
-    let claude_cli_path = find_claude_code_executable(custom_path).ok_or_else(|| {
-        anyhow::anyhow!(
-            "Claude Code CLI not found.\n\
-             Please install it: brew install --cask claude-code\n\
-             Or: npm install -g @anthropic-ai/claude-code"
-        )
-    })?;
-
-    info!(
-        "Spawning claude-code-acp sidecar: {:?} in {:?}",
-        sidecar_path, notes_directory
-    );
+    let claude_cli_path =
+        find_claude_code_executable(spawn_config.provider_paths.claude_code.as_deref())
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Claude Code CLI not found.\n\
+                     Please install it: brew install --cask claude-code\n\
+                     Or: npm install -g @anthropic-ai/claude-code"
+                )
+            })?;
+
+    let mut command = match &launcher {
+        ClaudeAcpLauncher::Sidecar(sidecar_path) => {
+            info!(
+                "Spawning claude-code-acp sidecar: {:?} in {:?}",
+                sidecar_path, notes_directory
+            );
+            agent_command(
+                sidecar_path,
+                &[sidecar_path, &claude_cli_path],
+                notes_directory,
+                spawn_config,
+            )?
+        }
+        ClaudeAcpLauncher::Npx(npx_path) => {
+            info!(
+                "Spawning claude-code-acp via npx: {:?} in {:?}",
+                npx_path, notes_directory
+            );
+            let mut command = agent_command(
+                npx_path,
+                &[npx_path, &claude_cli_path],
+                notes_directory,
+                spawn_config,
+            )?;
+            command.args(["--yes", CLAUDE_CODE_ACP_PACKAGE]);
+            // npx is a node script; make sure its own node is resolvable even when
+            // the app was launched with a minimal GUI PATH
+            if let Some(bin_dir) = npx_path.parent() {
+                let mut path_dirs = vec![bin_dir.to_path_buf()];
+                if let Some(existing) = std::env::var_os("PATH") {
+                    path_dirs.extend(std::env::split_paths(&existing));
+                }
+                if let Ok(joined) = std::env::join_paths(path_dirs) {
+                    command.env("PATH", joined);
+                }
+            }
+            command
+        }
+    };
     info!("Using Claude Code CLI at: {:?}", claude_cli_path);
 
-    let child = Command::new(&sidecar_path)
+    command
         .current_dir(notes_directory)
         .env("CLAUDE_CODE_EXECUTABLE", &claude_cli_path)
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
-        .kill_on_drop(true)
+        .kill_on_drop(true);
+    own_process_group(&mut command);
+    let child = command
         .spawn()
         .map_err(|e| anyhow::anyhow!("Failed to spawn sidecar: {e}"))?;
 
-    Ok(child)
+    Ok(spawn_config.children.track(child))
+}
+
+/// Start the agent in its own process group so it and everything it spawns
+/// can be killed together on app exit
+fn own_process_group(command: &mut Command) {
+    #[cfg(unix)]
+    command.process_group(0);
+    #[cfg(not(unix))]
+    let _ = command;
+}
+
+/// A command running `program` with the agent environment, under the
+/// sandbox when it is enabled. `executables` are the programs the agent will
+/// run, which the sandbox must leave readable.
+fn agent_command(
+    program: &Path,
+    executables: &[&Path],
+    notes_directory: &Path,
+    spawn_config: &SpawnConfig,
+) -> anyhow::Result<Command> {
+    let mut command = if spawn_config.sandbox {
+        if !sandbox_supported() {
+            anyhow::bail!("Agent sandboxing is only available on macOS");
+        }
+        let home = dirs::home_dir()
+            .ok_or_else(|| anyhow::anyhow!("Cannot sandbox agent: no home directory"))?;
+        let profile = sandbox_profile(&SandboxPaths {
+            home,
+            notes_directory: notes_directory.to_path_buf(),
+            temp_dir: std::env::temp_dir(),
+            executables: executables.iter().map(|p| p.to_path_buf()).collect(),
+            read_roots: spawn_config.read_roots.clone(),
+        });
+        info!("Sandboxing agent {:?}", program);
+        sandboxed_command(program, &profile)
+    } else {
+        Command::new(program)
+    };
+    apply_agent_env(&mut command, &spawn_config.env, &spawn_config.env_allowlist);
+    apply_proxy_env(&mut command, &spawn_config.proxy);
+    Ok(command)
+}
+
+/// Point Gemini CLI at its settings file and pass it the API key saved in
+/// the keychain, unless the agent environment already sets one. Settings
+/// that no longer validate (e.g. the file was moved) are skipped.
+fn apply_gemini_settings(command: &mut Command, settings: &GeminiSettings) {
+    match settings.validate() {
+        Ok(()) => {
+            if let Some(path) = &settings.settings_path {
+                command.env(SETTINGS_PATH_VARIABLE, path);
+            }
+        }
+        Err(e) => warn!("Ignoring Gemini settings: {}", e),
+    }
+
+    let has_key = command
+        .as_std()
+        .get_envs()
+        .any(|(name, value)| name == API_KEY_VARIABLE && value.is_some());
+    if !has_key {
+        match secrets::get_secret(GEMINI_API_KEY_SECRET) {
+            Ok(Some(key)) => {
+                command.env(API_KEY_VARIABLE, key);
+            }
+            Ok(None) => {}
+            Err(e) => warn!("{}", e),
+        }
+    }
 }
 
 /// Spawn Gemini CLI in ACP mode
 pub(crate) async fn spawn_gemini_cli_acp(
     notes_directory: &Path,
-    custom_path: Option<&str>,
+    spawn_config: &SpawnConfig,
     model_id: Option<&str>,
 ) -> anyhow::Result<tokio::process::Child> {
+    let custom_path = spawn_config.provider_paths.gemini_cli.as_deref();
     let gemini_path = find_gemini_cli_executable(custom_path).ok_or_else(|| {
         anyhow::anyhow!(
             "Gemini CLI not found.\n\
@@ -320,13 +541,18 @@ pub(crate) async fn spawn_gemini_cli_acp(
         gemini_path, notes_directory, model
     );
 
-    let child = Command::new(&gemini_path)
+    let mut command = agent_command(&gemini_path, &[&gemini_path], notes_directory, spawn_config)?;
+    apply_gemini_settings(&mut command, &spawn_config.gemini);
+    command
         .args(["--experimental-acp", "--model", model])
+        .args(spawn_config.gemini.args())
         .current_dir(notes_directory)
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
-        .kill_on_drop(true)
+        .kill_on_drop(true);
+    own_process_group(&mut command);
+    let child = command
         .spawn()
         .map_err(|e| anyhow::anyhow!("Failed to spawn Gemini CLI: {e}"))?;
 
@@ -337,16 +563,30 @@ pub(crate) async fn spawn_gemini_cli_acp(
 pub(crate) async fn spawn_agent_subprocess(
     provider: &AgentProvider,
     notes_directory: &Path,
-    paths: &ProviderPaths,
+    spawn_config: &SpawnConfig,
     model_id: Option<&str>,
-) -> anyhow::Result<tokio::process::Child> {
+) -> anyhow::Result<TrackedChild> {
     match provider {
-        AgentProvider::ClaudeCode => {
-            spawn_claude_code_acp(notes_directory, paths.claude_code.as_deref()).await
-        }
+        AgentProvider::ClaudeCode => spawn_claude_code_acp(notes_directory, spawn_config).await,
         AgentProvider::GeminiCli => {
             // Gemini CLI requires model to be specified at spawn time via --model flag
-            spawn_gemini_cli_acp(notes_directory, paths.gemini_cli.as_deref(), model_id).await
+            let child = spawn_gemini_cli_acp(notes_directory, spawn_config, model_id).await?;
+            Ok(spawn_config.children.track(child))
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::CLAUDE_CODE_ACP_PACKAGE;
+
+    #[test]
+    fn test_npx_fallback_matches_sidecar_version() {
+        let (_, version) = CLAUDE_CODE_ACP_PACKAGE.rsplit_once('@').unwrap();
+        let script = include_str!("../../../../scripts/build-sidecar.sh");
+        assert!(
+            script.contains(&format!("CLAUDE_CODE_ACP_VERSION=\"{version}\"")),
+            "build-sidecar.sh doesn't pin {version}"
+        );
+    }
+}
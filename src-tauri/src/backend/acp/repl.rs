@@ -0,0 +1,404 @@
+//! `acp --repl`: a terminal front end over the same ACP sessions the app
+//! uses, to exercise providers and models without the UI. It reads the app's
+//! settings, so agents are spawned with the same paths, environment, proxy,
+//! sandbox and read directories. One session stays open, so the agent keeps
+//! the conversation from prompt to prompt. Answers stream to stdout; the
+//! input marker, notices and logs go to stderr.
+
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use tokio::sync::mpsc;
+use tracing_subscriber::EnvFilter;
+
+use super::batch_cli::{batch, parse_batch_args, BatchOptions};
+use crate::backend::acp::sessions::TerminalSession;
+use crate::backend::config_file::ConfigFile;
+use crate::backend::types::{AgentProvider, SpawnConfig, StopReason};
+
+const USAGE: &str = "\
+Usage: acp --repl [--provider claude-code|gemini-cli] [--model ID] [--notes DIR] [--config FILE]
+       acp batch PROJECT --nodes ID[,ID...] --template TEXT [--provider claude-code|gemini-cli]
+           [--model ID] [--config FILE]";
+
+const HELP: &str = "\
+/model          list the agent's models
+/model ID       switch to a model
+/provider NAME  start a new session with claude-code or gemini-cli
+/quit           end the session
+Other lines are sent as prompts, including the agent's own slash commands.";
+
+/// Command-line options; what isn't given comes from the app's settings
+#[derive(Debug, Default, PartialEq, Eq)]
+struct ReplOptions {
+    provider: Option<AgentProvider>,
+    model_id: Option<String>,
+    /// The active workspace, or else the current directory, when not given
+    notes_directory: Option<PathBuf>,
+    /// The app's `config.json` when not given
+    config_path: Option<PathBuf>,
+}
+
+pub(super) fn parse_provider(name: &str) -> Result<AgentProvider, String> {
+    serde_json::from_value(serde_json::Value::String(name.to_string()))
+        .map_err(|_| format!("Unknown provider {name:?}; use claude-code or gemini-cli"))
+}
+
+/// Parse the command line, without the program name
+fn parse_args(args: &[String]) -> Result<ReplOptions, String> {
+    let mut options = ReplOptions::default();
+    let mut repl = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .map(String::as_str)
+                .ok_or_else(|| format!("{arg} needs a value"))
+        };
+        match arg.as_str() {
+            "--repl" => repl = true,
+            "--provider" => options.provider = Some(parse_provider(value()?)?),
+            "--model" => options.model_id = Some(value()?.to_string()),
+            "--notes" => options.notes_directory = Some(PathBuf::from(value()?)),
+            "--config" => options.config_path = Some(PathBuf::from(value()?)),
+            _ => return Err(format!("Unknown argument {arg:?}")),
+        }
+    }
+    if !repl {
+        return Err("Only the --repl mode is available".to_string());
+    }
+    Ok(options)
+}
+
+/// A line typed into the REPL
+#[derive(Debug, PartialEq, Eq)]
+enum ReplLine {
+    Empty,
+    Prompt(String),
+    /// `/model` alone lists the models
+    Model(Option<String>),
+    Provider(AgentProvider),
+    Help,
+    Quit,
+}
+
+fn parse_line(line: &str) -> Result<ReplLine, String> {
+    let line = line.trim();
+    if line.is_empty() {
+        return Ok(ReplLine::Empty);
+    }
+    let Some(command) = line.strip_prefix('/') else {
+        return Ok(ReplLine::Prompt(line.to_string()));
+    };
+    let (name, argument) = command
+        .split_once(char::is_whitespace)
+        .map_or((command, ""), |(name, argument)| (name, argument.trim()));
+    Ok(match name {
+        "model" => ReplLine::Model(Some(argument.to_string()).filter(|a| !a.is_empty())),
+        "provider" => ReplLine::Provider(parse_provider(argument)?),
+        "help" => ReplLine::Help,
+        "quit" | "exit" => ReplLine::Quit,
+        // Passed on, e.g. Claude Code's /compact
+        _ => ReplLine::Prompt(line.to_string()),
+    })
+}
+
+/// Lines typed on stdin. They are read on a thread of their own so that
+/// waiting for input doesn't hold up the agent connection's tasks.
+fn stdin_lines() -> mpsc::UnboundedReceiver<String> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+async fn open_session(
+    provider: &AgentProvider,
+    notes_directory: &Path,
+    spawn_config: &SpawnConfig,
+    model_id: Option<&str>,
+) -> Result<TerminalSession, String> {
+    let session = TerminalSession::open(provider, notes_directory, spawn_config, model_id)
+        .await
+        .map_err(|e| format!("Failed to start {}: {e}", provider.display_name()))?;
+    eprintln!(
+        "Connected to {} ({}) in {}. /help lists the commands.",
+        provider.display_name(),
+        session.model_id.as_deref().unwrap_or("default model"),
+        notes_directory.display()
+    );
+    Ok(session)
+}
+
+/// The app's settings from `path`, or from where the app keeps them
+pub(super) fn load_config(path: Option<&Path>) -> Result<ConfigFile, String> {
+    match path {
+        Some(path) if !path.is_file() => Err(format!("Config not found: {}", path.display())),
+        Some(path) => ConfigFile::load(path),
+        None => match ConfigFile::default_path() {
+            Some(path) => ConfigFile::load(&path),
+            None => Err("No app data directory to read settings from".to_string()),
+        },
+    }
+}
+
+async fn repl(options: ReplOptions) -> Result<(), String> {
+    let config = load_config(options.config_path.as_deref())?;
+    let notes_directory = match options.notes_directory.or_else(|| config.notes_directory()) {
+        Some(dir) => dir,
+        None => std::env::current_dir()
+            .map_err(|e| format!("Failed to read the current directory: {e}"))?,
+    };
+    let notes_directory = std::fs::canonicalize(&notes_directory)
+        .ok()
+        .filter(|dir| dir.is_dir())
+        .ok_or_else(|| format!("Not a directory: {}", notes_directory.display()))?;
+    let spawn_config = config.spawn_config();
+
+    let mut provider = options
+        .provider
+        .unwrap_or_else(|| config.default_provider());
+    let model_id = options
+        .model_id
+        .or_else(|| config.preferred_model(&provider));
+    let mut session = open_session(
+        &provider,
+        &notes_directory,
+        &spawn_config,
+        model_id.as_deref(),
+    )
+    .await?;
+    let mut lines = stdin_lines();
+    loop {
+        eprint!("> ");
+        let _ = std::io::stderr().flush();
+        // End of input quits like /quit
+        let Some(line) = lines.recv().await else {
+            break;
+        };
+        let line = match parse_line(&line) {
+            Ok(line) => line,
+            Err(e) => {
+                eprintln!("{e}");
+                continue;
+            }
+        };
+        match line {
+            ReplLine::Empty => {}
+            ReplLine::Help => eprintln!("{HELP}"),
+            ReplLine::Quit => break,
+            ReplLine::Prompt(text) => match session.prompt(text).await {
+                Ok(stop_reason) => {
+                    println!();
+                    if stop_reason != StopReason::EndTurn {
+                        eprintln!("[stopped: {stop_reason:?}]");
+                    }
+                }
+                Err(e) => eprintln!("\n{e}\n/provider NAME starts a new session."),
+            },
+            ReplLine::Model(None) => {
+                let current = session.model_id.as_deref();
+                for model in &session.available_models {
+                    let marker = if Some(model.as_str()) == current {
+                        '*'
+                    } else {
+                        ' '
+                    };
+                    eprintln!("{marker} {model}");
+                }
+                if session.available_models.is_empty() {
+                    eprintln!("The agent doesn't list its models");
+                }
+            }
+            // Gemini CLI only takes a model at spawn time, so it gets a new session
+            ReplLine::Model(Some(model_id)) if provider == AgentProvider::GeminiCli => {
+                match open_session(&provider, &notes_directory, &spawn_config, Some(&model_id))
+                    .await
+                {
+                    Ok(new_session) => std::mem::replace(&mut session, new_session).close().await,
+                    Err(e) => eprintln!("{e}"),
+                }
+            }
+            ReplLine::Model(Some(model_id)) => match session.set_model(&model_id).await {
+                Ok(()) => eprintln!("Switched to {model_id}"),
+                Err(e) => eprintln!("{e}"),
+            },
+            ReplLine::Provider(new_provider) => {
+                let model_id = config.preferred_model(&new_provider);
+                match open_session(
+                    &new_provider,
+                    &notes_directory,
+                    &spawn_config,
+                    model_id.as_deref(),
+                )
+                .await
+                {
+                    Ok(new_session) => {
+                        std::mem::replace(&mut session, new_session).close().await;
+                        provider = new_provider;
+                    }
+                    Err(e) => eprintln!("{e}"),
+                }
+            }
+        }
+    }
+    session.close().await;
+    Ok(())
+}
+
+/// What the `acp` binary was asked to do
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    Repl(ReplOptions),
+    Batch(BatchOptions),
+}
+
+fn parse_command(args: &[String]) -> Result<Command, String> {
+    match args.split_first() {
+        Some((first, rest)) if first == "batch" => parse_batch_args(rest).map(Command::Batch),
+        _ => parse_args(args).map(Command::Repl),
+    }
+}
+
+/// Logs go to stderr at warning level, or as `RUST_LOG` says
+fn init_logging() {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn"));
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(filter)
+        .init();
+}
+
+/// Run the `acp` binary with `args` (without the program name) and return
+/// its exit code
+pub(crate) fn run_cli(args: &[String]) -> i32 {
+    let command = match parse_command(args) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("{e}\n{USAGE}");
+            return 2;
+        }
+    };
+    init_logging();
+
+    let runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Failed to create runtime: {e}");
+            return 1;
+        }
+    };
+    // ACP connections run their I/O on local tasks
+    let local = tokio::task::LocalSet::new();
+    let result = match command {
+        Command::Repl(options) => local.block_on(&runtime, repl(options)),
+        Command::Batch(options) => local.block_on(&runtime, batch(options)),
+    };
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{e}");
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(
+            parse_args(&args(&["--repl"])).unwrap(),
+            ReplOptions::default()
+        );
+        assert_eq!(
+            parse_args(&args(&[
+                "--repl",
+                "--provider",
+                "gemini-cli",
+                "--model",
+                "gemini-2.5-pro",
+                "--notes",
+                "/notes"
+            ]))
+            .unwrap(),
+            ReplOptions {
+                provider: Some(AgentProvider::GeminiCli),
+                model_id: Some("gemini-2.5-pro".to_string()),
+                notes_directory: Some(PathBuf::from("/notes")),
+                config_path: None,
+            }
+        );
+        assert_eq!(
+            parse_args(&args(&["--config", "/tmp/config.json", "--repl"]))
+                .unwrap()
+                .config_path,
+            Some(PathBuf::from("/tmp/config.json"))
+        );
+        assert!(parse_args(&[]).is_err());
+        assert!(parse_args(&args(&["--repl", "--model"])).is_err());
+        assert!(parse_args(&args(&["--repl", "--provider", "gpt"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_command() {
+        assert!(matches!(
+            parse_command(&args(&["--repl"])).unwrap(),
+            Command::Repl(_)
+        ));
+        assert!(matches!(
+            parse_command(&args(&[
+                "batch",
+                "a.thoughttree",
+                "--nodes",
+                "n1",
+                "--template",
+                "{{content}}"
+            ]))
+            .unwrap(),
+            Command::Batch(_)
+        ));
+        assert!(parse_command(&args(&["batch", "--repl"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(parse_line("  ").unwrap(), ReplLine::Empty);
+        assert_eq!(
+            parse_line(" why is the sky blue?\n").unwrap(),
+            ReplLine::Prompt("why is the sky blue?".to_string())
+        );
+        assert_eq!(parse_line("/model").unwrap(), ReplLine::Model(None));
+        assert_eq!(
+            parse_line("/model  claude-haiku-4-5 ").unwrap(),
+            ReplLine::Model(Some("claude-haiku-4-5".to_string()))
+        );
+        assert_eq!(
+            parse_line("/provider claude-code").unwrap(),
+            ReplLine::Provider(AgentProvider::ClaudeCode)
+        );
+        assert!(parse_line("/provider").is_err());
+        assert_eq!(parse_line("/quit").unwrap(), ReplLine::Quit);
+        assert_eq!(
+            parse_line("/compact keep the decisions").unwrap(),
+            ReplLine::Prompt("/compact keep the decisions".to_string())
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use std::path::{Path, PathBuf};
+
+use tokio::process::Command;
+
+/// macOS sandbox launcher; deprecated but still the only way to apply a
+/// Seatbelt profile to a child process
+const SANDBOX_EXEC: &str = "/usr/bin/sandbox-exec";
+
+/// System locations agents and their toolchains read outside the home
+/// directory: the OS, command line tools, Homebrew/MacPorts installs, and
+/// `/etc`, timezone and DNS configuration (as real paths)
+const SYSTEM_READ_PATHS: [&str; 17] = [
+    "/System",
+    "/usr",
+    "/bin",
+    "/sbin",
+    "/dev",
+    "/Library/Apple",
+    "/Library/Frameworks",
+    "/Library/Preferences",
+    "/Library/Developer/CommandLineTools",
+    "/Applications/Xcode.app",
+    "/opt/homebrew",
+    "/opt/local",
+    "/private/etc",
+    "/private/var/db/timezone",
+    "/private/var/db/dyld",
+    "/private/var/select",
+    "/private/var/run/resolv.conf",
+];
+
+/// Home subpaths agents and their toolchains read: CLI config and session
+/// state, Node/Bun installs, and git and gcloud config. The keychain is
+/// deliberately left out.
+const HOME_READ_PATHS: [&str; 15] = [
+    ".claude",
+    ".gemini",
+    ".config/claude",
+    ".config/gcloud",
+    ".config/git",
+    ".gitconfig",
+    ".npmrc",
+    ".npm",
+    ".nvm",
+    ".bun",
+    ".volta",
+    ".fnm",
+    ".asdf",
+    ".cache",
+    "Library/Preferences/.GlobalPreferences.plist",
+];
+
+/// Home subpaths agents write session state and caches to
+const HOME_WRITE_PATHS: [&str; 4] = [".claude", ".gemini", ".npm", ".cache"];
+
+/// Paths a sandbox profile is built from
+pub(crate) struct SandboxPaths {
+    pub home: PathBuf,
+    pub notes_directory: PathBuf,
+    pub temp_dir: PathBuf,
+    /// Programs the agent runs (sidecar, npx, CLI); their install prefixes
+    /// stay readable even when they live under the home directory
+    pub executables: Vec<PathBuf>,
+    /// Extra directories agents may read, see [`crate::backend::read_roots`]
+    pub read_roots: Vec<PathBuf>,
+}
+
+/// Resolve symlinks (e.g. `/var` to `/private/var`), since Seatbelt matches
+/// real paths; fall back to the path as given
+fn real_path(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// `text` as an SBPL string literal
+fn sbpl_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn subpath(path: &Path) -> String {
+    format!("(subpath {})", sbpl_string(&path.to_string_lossy()))
+}
+
+/// A regex matching `path` itself and any sibling that starts with its
+/// name, e.g. `~/.claude.json` and `~/.claude.json.backup`
+fn path_prefix_regex(path: &Path) -> String {
+    let escaped: String = path
+        .to_string_lossy()
+        .chars()
+        .flat_map(|c| {
+            let escape = "\\^$.|?*+()[]{}".contains(c);
+            escape.then_some('\\').into_iter().chain([c])
+        })
+        .collect();
+    format!("(regex {})", sbpl_string(&format!("^{escaped}")))
+}
+
+/// Seatbelt profile that allows everything except writes outside the temp
+/// directories and agent state, and reads outside the notes and read
+/// directories, agent config, the programs being run and the system and
+/// toolchain paths they need. File metadata stays readable everywhere, so
+/// paths can still be resolved.
+pub(crate) fn sandbox_profile(paths: &SandboxPaths) -> String {
+    let home = real_path(&paths.home);
+    let temp_paths = [
+        subpath(Path::new("/private/tmp")),
+        subpath(Path::new("/private/var/folders")),
+        subpath(&real_path(&paths.temp_dir)),
+    ];
+    let mut writable = temp_paths.to_vec();
+    writable.extend([
+        path_prefix_regex(&home.join(".claude.json")),
+        "(literal \"/dev/null\")".to_string(),
+        "(literal \"/dev/tty\")".to_string(),
+        "(literal \"/dev/dtracehelper\")".to_string(),
+        "(regex #\"^/dev/fd/\")".to_string(),
+    ]);
+    writable.extend(HOME_WRITE_PATHS.iter().map(|p| subpath(&home.join(p))));
+
+    let mut readable = temp_paths.to_vec();
+    readable.extend(SYSTEM_READ_PATHS.iter().map(|p| subpath(Path::new(p))));
+    readable.extend([
+        format!("(literal {})", sbpl_string(&home.to_string_lossy())),
+        subpath(&real_path(&paths.notes_directory)),
+        path_prefix_regex(&home.join(".claude.json")),
+    ]);
+    readable.extend(HOME_READ_PATHS.iter().map(|p| subpath(&home.join(p))));
+    readable.extend(paths.read_roots.iter().map(|p| subpath(&real_path(p))));
+    // Neither a filesystem root nor anything containing the home directory
+    let confined = |dir: &Path| dir.parent().is_some() && !home.starts_with(dir);
+    for executable in &paths.executables {
+        // `<prefix>/bin/<name>`: the prefix also holds the package itself.
+        // Programs directly in e.g. `~/bin` only get their own directory.
+        let executable = real_path(executable);
+        let Some(dir) = executable.parent() else {
+            continue;
+        };
+        match dir.parent() {
+            Some(prefix) if confined(prefix) => readable.push(subpath(prefix)),
+            _ if confined(dir) => readable.push(subpath(dir)),
+            _ => {}
+        }
+    }
+
+    format!(
+        "(version 1)\n\
+         (allow default)\n\
+         (deny file-write*)\n\
+         (allow file-write*\n    {}\n)\n\
+         (deny file-read*)\n\
+         (allow file-read-metadata)\n\
+         (allow file-read*\n    {}\n)\n",
+        writable.join("\n    "),
+        readable.join("\n    "),
+    )
+}
+
+/// Whether agents can be sandboxed on this platform
+pub(crate) fn sandbox_supported() -> bool {
+    cfg!(target_os = "macos") && Path::new(SANDBOX_EXEC).exists()
+}
+
+/// A command that runs `program` under `profile`; add the program's own
+/// arguments to it as usual
+pub(crate) fn sandboxed_command(program: &Path, profile: &str) -> Command {
+    let mut command = Command::new(SANDBOX_EXEC);
+    command.arg("-p").arg(profile).arg(program);
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sandbox_profile_denies_reads_by_default() {
+        let paths = SandboxPaths {
+            home: PathBuf::from("/Users/ada"),
+            notes_directory: PathBuf::from("/Users/ada/Notes \"2025\""),
+            temp_dir: PathBuf::from("/nonexistent-tmp"),
+            executables: vec![
+                PathBuf::from("/Users/ada/.local/share/claude/bin/claude"),
+                PathBuf::from("/Users/ada/bin/gemini"),
+                PathBuf::from("/Applications/ThoughtTree.app/Contents/MacOS/claude-code-acp"),
+                PathBuf::from("/usr/local/bin/npx"),
+                PathBuf::from("/claude"),
+            ],
+            read_roots: vec![PathBuf::from("/Users/ada/Papers")],
+        };
+        let profile = sandbox_profile(&paths);
+        assert!(profile.starts_with("(version 1)\n(allow default)\n(deny file-write*)\n"));
+        assert!(profile.contains("(deny file-read*)\n(allow file-read-metadata)\n"));
+        let reads = &profile[profile.find("(deny file-read*)").unwrap()..];
+        assert!(reads.contains("(subpath \"/Users/ada/Notes \\\"2025\\\"\")"));
+        assert!(reads.contains("(subpath \"/nonexistent-tmp\")"));
+        assert!(reads.contains("(subpath \"/usr\")"));
+        assert!(reads.contains("(regex \"^/Users/ada/\\\\.claude\\\\.json\")"));
+        assert!(reads.contains("(subpath \"/Users/ada/.local/share/claude\")"));
+        assert!(reads.contains("(subpath \"/Users/ada/bin\")"));
+        assert!(reads.contains("(subpath \"/Applications/ThoughtTree.app/Contents\")"));
+        assert!(reads.contains("(subpath \"/Users/ada/Papers\")"));
+        // Credentials and the rest of the home directory stay unreadable
+        assert!(!reads.contains("Keychains"));
+        assert!(!reads.contains("(subpath \"/Users/ada\")"));
+        assert!(!reads.contains("(subpath \"/Users\")"));
+        assert!(!reads.contains("(subpath \"/\")"));
+        // Writes in the notes directory are not allowed
+        let writes = &profile[..profile.find("(deny file-read*)").unwrap()];
+        assert!(!writes.contains("Notes"));
+    }
+}
@@ -1,41 +1,100 @@
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use agent_client_protocol::{
-    Agent, Client, ClientSideConnection, ContentBlock, ImageContent, Implementation,
-    InitializeRequest, InitializeResponse, NewSessionRequest, PromptRequest, ProtocolVersion,
-    SetSessionModelRequest, TextContent,
+    Agent, AudioContent, AuthMethodId, AuthenticateRequest, BlobResourceContents, Client,
+    ClientCapabilities, ClientSideConnection, ContentBlock, EmbeddedResource,
+    EmbeddedResourceResource, FileSystemCapability, ImageContent, Implementation,
+    InitializeRequest, InitializeResponse, NewSessionRequest, NewSessionResponse, PromptRequest,
+    ProtocolVersion, ResourceLink, SessionId, SessionModeId, SetSessionModeRequest,
+    SetSessionModelRequest, TextContent, TextResourceContents,
 };
-use chrono::Local;
 use futures::lock::Mutex;
-use tokio::sync::oneshot;
+use tauri::Emitter;
 use tokio::task::JoinHandle;
 use tokio_util::compat::{TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt};
-use tracing::{error, info, warn};
+use tracing::{error, info, warn, Instrument};
 
-use crate::backend::acp::clients::{ModelDiscoveryClient, StreamingClient, SummaryClient};
-use crate::backend::acp::process::{spawn_agent_subprocess, spawn_claude_code_acp};
-use crate::backend::types::{AgentProvider, Message, ModelInfo, ProviderPaths};
+use crate::backend::acp::children::{ChildGuard, TrackedChild};
+use crate::backend::acp::clients::{
+    CapabilityProbeClient, ModelDiscoveryClient, StreamingClient, SummaryClient, TerminalClient,
+};
+use crate::backend::acp::process::spawn_agent_subprocess;
+use crate::backend::attachments::{self, PreparedAttachment};
+use crate::backend::claude_options::ClaudeOptions;
+use crate::backend::compaction::{
+    compaction_prompt, compaction_split, compaction_transcript, summary_message, CompactionCache,
+};
+use crate::backend::context::{file_uri, inline_context_notes};
+use crate::backend::gemini_models::{gemini_api_key, list_gemini_models};
+use crate::backend::images;
+use crate::backend::metrics::{elapsed_ms, Throughput};
+use crate::backend::permissions::PendingPermissions;
+use crate::backend::proxy::ProxySettings;
+use crate::backend::replay::StreamReplay;
+use crate::backend::retry::{backoff_delay, classify_transient, RetryReason, TransientError};
+use crate::backend::summaries::{clean_summary, summary_prompt, SummaryStyle};
+use crate::backend::tokens::{context_budget, estimate_tokens, fit_to_budget};
+use crate::backend::types::{
+    AgentCommandInfo, AgentCrashedPayload, AgentProvider, AuthMethodInfo, AuthRequiredPayload,
+    GenerationErrorCategory, GenerationErrorPayload, GenerationRetryingPayload, GenerationStatus,
+    GenerationStatusPayload, GenerationStoppedPayload, Message, MessageImage, ModelInfo,
+    ProjectPermissions, PromptResult, PromptTimings, ProviderFeatures, SessionModeInfo,
+    SessionModes, SpawnConfig, StopReason, SummaryRequest, SummaryResult,
+};
 
 /// How long to wait for the agent subprocess to answer `initialize` before
 /// giving up. A broken sidecar otherwise hangs the request forever.
 const INIT_TIMEOUT: Duration = Duration::from_secs(15);
 
+/// How long to wait after session creation for the agent to advertise its
+/// slash commands (sent as an asynchronous notification)
+const COMMANDS_SETTLE_DELAY: Duration = Duration::from_millis(500);
+
 /// How long to wait for the subprocess to exit on its own after stdin closes,
 /// before killing it.
 const EXIT_TIMEOUT: Duration = Duration::from_secs(2);
 
+/// Number of recent stderr lines kept for crash reports
+const STDERR_TAIL_LINES: usize = 20;
+
 /// An ACP agent subprocess together with its stderr-logging and connection
 /// I/O tasks, so teardown can wait for all of them instead of leaking.
 struct AgentProcess {
     child: tokio::process::Child,
+    /// Keeps the child registered for exit cleanup until it has been reaped
+    _guard: ChildGuard,
     stderr_task: Option<JoinHandle<()>>,
     io_task: JoinHandle<()>,
+    stderr_tail: StderrTail,
+}
+
+/// The last lines an agent subprocess wrote to stderr
+type StderrTail = Arc<std::sync::Mutex<VecDeque<String>>>;
+
+fn stderr_lines(tail: &StderrTail) -> Vec<String> {
+    tail.lock()
+        .map(|lines| lines.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// `message` followed by the agent's last stderr lines, which usually say
+/// why a CLI failed to start
+fn with_stderr_tail(message: String, lines: &[String]) -> String {
+    if lines.is_empty() {
+        return message;
+    }
+    format!("{message}\nAgent stderr:\n{}", lines.join("\n"))
 }
 
 impl AgentProcess {
+    /// The last lines the subprocess wrote to stderr
+    fn stderr_tail(&self) -> String {
+        stderr_lines(&self.stderr_tail).join("\n")
+    }
+
     /// Gracefully shut down: the caller must drop the connection first (which
     /// closes the subprocess's stdin), then this waits for exit and drains the
     /// I/O and stderr tasks. Kills the process if it doesn't exit in time.
@@ -63,12 +122,25 @@ impl AgentProcess {
 }
 
 /// Wire up an ACP connection over the child's stdio and start the stderr
-/// logger and connection I/O tasks.
+/// logger and connection I/O tasks. Both run in the caller's tracing span so
+/// agent stderr carries the generation's trace ID.
 fn connect_agent(
-    mut child: tokio::process::Child,
+    tracked: TrackedChild,
     client: Arc<impl Client + 'static>,
     tag: &'static str,
 ) -> anyhow::Result<(ClientSideConnection, AgentProcess)> {
+    connect_agent_with_tail(tracked, client, tag, StderrTail::default())
+}
+
+/// `connect_agent`, keeping the last stderr lines in `stderr_tail` so they
+/// outlive the process
+fn connect_agent_with_tail(
+    tracked: TrackedChild,
+    client: Arc<impl Client + 'static>,
+    tag: &'static str,
+    stderr_tail: StderrTail,
+) -> anyhow::Result<(ClientSideConnection, AgentProcess)> {
+    let TrackedChild { mut child, guard } = tracked;
     let stdin = child
         .stdin
         .take()
@@ -79,33 +151,48 @@ fn connect_agent(
         .ok_or_else(|| anyhow::anyhow!("Failed to get stdout handle"))?;
 
     let stderr_task = child.stderr.take().map(|stderr| {
-        tokio::task::spawn_local(async move {
-            use tokio::io::AsyncBufReadExt;
-            let reader = tokio::io::BufReader::new(stderr);
-            let mut lines = reader.lines();
-            while let Ok(Some(line)) = lines.next_line().await {
-                warn!("[{} stderr] {}", tag, line);
+        let stderr_tail = stderr_tail.clone();
+        tokio::task::spawn_local(
+            async move {
+                use tokio::io::AsyncBufReadExt;
+                let reader = tokio::io::BufReader::new(stderr);
+                let mut lines = reader.lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    warn!("[{} stderr] {}", tag, line);
+                    if let Ok(mut tail) = stderr_tail.lock() {
+                        if tail.len() == STDERR_TAIL_LINES {
+                            tail.pop_front();
+                        }
+                        tail.push_back(line);
+                    }
+                }
             }
-        })
+            .instrument(tracing::Span::current()),
+        )
     });
 
     let (connection, io_future) =
         ClientSideConnection::new(client, stdin.compat_write(), stdout.compat(), |f| {
-            tokio::task::spawn_local(f);
+            tokio::task::spawn_local(f.instrument(tracing::Span::current()));
         });
 
-    let io_task = tokio::task::spawn_local(async move {
-        if let Err(e) = io_future.await {
-            error!("[{}] I/O error: {:?}", tag, e);
+    let io_task = tokio::task::spawn_local(
+        async move {
+            if let Err(e) = io_future.await {
+                error!("[{}] I/O error: {:?}", tag, e);
+            }
         }
-    });
+        .instrument(tracing::Span::current()),
+    );
 
     Ok((
         connection,
         AgentProcess {
             child,
+            _guard: guard,
             stderr_task,
             io_task,
+            stderr_tail,
         },
     ))
 }
@@ -114,11 +201,15 @@ fn connect_agent(
 async fn initialize_with_timeout(
     connection: &ClientSideConnection,
     client_info: Implementation,
+    client_capabilities: ClientCapabilities,
 ) -> anyhow::Result<InitializeResponse> {
     tokio::time::timeout(
         INIT_TIMEOUT,
-        connection
-            .initialize(InitializeRequest::new(ProtocolVersion::LATEST).client_info(client_info)),
+        connection.initialize(
+            InitializeRequest::new(ProtocolVersion::LATEST)
+                .client_capabilities(client_capabilities)
+                .client_info(client_info),
+        ),
     )
     .await
     .map_err(|_| {
@@ -130,58 +221,356 @@ async fn initialize_with_timeout(
     .map_err(|e| anyhow::anyhow!("Failed to initialize: {e:?}"))
 }
 
-/// Parameters for [`run_prompt_session`]
+fn auth_methods(init_response: &InitializeResponse) -> Vec<AuthMethodInfo> {
+    init_response
+        .auth_methods
+        .iter()
+        .map(|m| AuthMethodInfo {
+            id: m.id.0.to_string(),
+            name: m.name.clone(),
+            description: m.description.clone(),
+        })
+        .collect()
+}
+
+fn is_auth_required(error: &agent_client_protocol::Error) -> bool {
+    error.code == agent_client_protocol::Error::auth_required().code
+}
+
+/// The agent subprocess exited while a prompt was in flight
+#[derive(Debug)]
+pub(crate) struct AgentCrashed {
+    pub exit_status: String,
+    pub stderr_tail: String,
+    /// Whether any answer text was streamed before the crash
+    pub produced_output: bool,
+}
+
+impl std::fmt::Display for AgentCrashed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Agent exited unexpectedly ({})", self.exit_status)?;
+        if !self.stderr_tail.is_empty() {
+            write!(f, ": {}", self.stderr_tail)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for AgentCrashed {}
+
+/// The agent refused to start a session or prompt until the user logs in
+#[derive(Debug)]
+struct AuthenticationRequired {
+    provider_name: String,
+}
+
+impl std::fmt::Display for AuthenticationRequired {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} requires authentication", self.provider_name)
+    }
+}
+
+impl std::error::Error for AuthenticationRequired {}
+
+/// The prompt failed with an error that may pass, like a rate limit
+#[derive(Debug)]
+struct TransientFailure {
+    kind: TransientError,
+    message: String,
+    /// Whether any answer text was streamed before the failure
+    produced_output: bool,
+}
+
+impl std::fmt::Display for TransientFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for TransientFailure {}
+
+/// How far a prompt session got, to tell what a failure was about
+#[derive(Default)]
+struct SessionProgress {
+    phase: std::sync::Mutex<Option<GenerationStatus>>,
+    stderr_tail: StderrTail,
+}
+
+impl SessionProgress {
+    /// Enter a phase and report it as a `generation-status` event
+    fn enter(
+        &self,
+        app_handle: &AppHandle,
+        node_id: &str,
+        trace_id: &str,
+        status: GenerationStatus,
+    ) {
+        *self.phase.lock().unwrap_or_else(|e| e.into_inner()) = Some(status);
+        let payload = GenerationStatusPayload {
+            node_id: node_id.to_string(),
+            trace_id: trace_id.to_string(),
+            status,
+            error: None,
+        };
+        if let Err(e) = app_handle.emit("generation-status", payload) {
+            error!("Failed to emit generation-status: {:?}", e);
+        }
+    }
+
+    fn category(&self, error: &anyhow::Error) -> GenerationErrorCategory {
+        if error.downcast_ref::<AgentCrashed>().is_some() {
+            GenerationErrorCategory::Crashed
+        } else if error.downcast_ref::<AuthenticationRequired>().is_some() {
+            GenerationErrorCategory::Auth
+        } else {
+            let phase = *self.phase.lock().unwrap_or_else(|e| e.into_inner());
+            GenerationErrorCategory::from_phase(phase)
+        }
+    }
+}
+
+/// Parameters for [`run_prompt_session_with_retry`]
+#[derive(Clone)]
 pub(crate) struct PromptSessionParams {
     pub app_handle: tauri::AppHandle,
     pub node_id: String,
+    /// Identifies this generation in logs, events and errors
+    pub trace_id: String,
     pub messages: Vec<Message>,
-    pub pending_permissions: Arc<Mutex<HashMap<String, oneshot::Sender<String>>>>,
+    pub pending_permissions: Arc<PendingPermissions>,
     pub notes_directory: PathBuf,
     pub provider: AgentProvider,
     pub model_id: Option<String>,
-    pub provider_paths: ProviderPaths,
+    /// Session mode to switch to (e.g. "plan"), if the agent offers it
+    pub session_mode: Option<String>,
+    /// Run this agent slash command (without the `/`) instead of a normal prompt;
+    /// the last message is passed as its argument
+    pub agent_command: Option<String>,
+    pub spawn_config: SpawnConfig,
+    /// Permission overrides from the project being generated for
+    pub project_permissions: ProjectPermissions,
+    pub stream_replay: Arc<StreamReplay>,
+    /// Longest edge images are scaled down to before sending
+    pub image_max_dimension: u32,
+    /// Instructions sent as their own text block ahead of the prompt
+    pub system_prompt: Option<String>,
+    /// Date line and other details put in front of the conversation
+    pub preamble: String,
+    /// Summarize older ancestor messages instead of replaying them; `None`
+    /// when the user hasn't opted in
+    pub compaction: Option<AncestorCompaction>,
+    /// Passed in the session's `_meta`; only Claude Code reads them
+    pub claude_options: ClaudeOptions,
+}
+
+/// Opt-in summarizing of older ancestor messages, see
+/// [`crate::backend::compaction`]
+#[derive(Clone)]
+pub(crate) struct AncestorCompaction {
+    pub cache: Arc<CompactionCache>,
+    /// Bulk model override of the prompt's provider, for the summarizer
+    pub model_override: Option<String>,
+}
+
+/// Replace the older messages with a summary when that's worthwhile.
+/// Returns how many messages were compacted; when summarizing fails the
+/// messages are left as they are.
+async fn compact_ancestors(
+    messages: &mut Vec<Message>,
+    compaction: &AncestorCompaction,
+    provider: &AgentProvider,
+    notes_directory: &Path,
+    spawn_config: &SpawnConfig,
+) -> usize {
+    let Some(split) = compaction_split(messages) else {
+        return 0;
+    };
+    let mut older = messages[..split].to_vec();
+    // Keep the summarizer's input within its window
+    fit_to_budget(&mut older, context_budget(provider, None));
+    let transcript = compaction_transcript(&older);
+    let key = CompactionCache::key(&transcript);
+
+    let summary = match compaction.cache.get(&key) {
+        Some(summary) => summary,
+        None => match run_bulk_prompt(
+            compaction_prompt(&transcript),
+            provider,
+            notes_directory,
+            spawn_config,
+            compaction.model_override.as_deref(),
+            "compaction-acp",
+        )
+        .await
+        {
+            Ok((summary, _)) if !summary.is_empty() => {
+                compaction.cache.insert(key, summary.clone());
+                summary
+            }
+            Ok(_) => {
+                warn!("Compaction returned no summary; sending ancestors verbatim");
+                return 0;
+            }
+            Err(e) => {
+                warn!("Compaction failed; sending ancestors verbatim: {}", e);
+                return 0;
+            }
+        },
+    };
+    info!("Compacted {} ancestor messages into a summary", split);
+    messages.splice(..split, [summary_message(&summary, split)]);
+    split
+}
+
+/// Report how a prompt session ended: failures get a `generation-error`
+/// event with the agent's last stderr lines, then the `completed`/`failed`
+/// `generation-status`
+fn report_outcome(
+    params: &PromptSessionParams,
+    progress: &SessionProgress,
+    result: &anyhow::Result<PromptResult>,
+) {
+    if let Err(e) = result {
+        let payload = GenerationErrorPayload {
+            node_id: params.node_id.clone(),
+            trace_id: params.trace_id.clone(),
+            category: progress.category(e),
+            message: e.to_string(),
+            stderr_tail: stderr_lines(&progress.stderr_tail),
+        };
+        if let Err(emit_err) = params.app_handle.emit("generation-error", payload) {
+            error!("Failed to emit generation-error: {:?}", emit_err);
+        }
+    }
+    let payload = GenerationStatusPayload {
+        node_id: params.node_id.clone(),
+        trace_id: params.trace_id.clone(),
+        status: if result.is_ok() {
+            GenerationStatus::Completed
+        } else {
+            GenerationStatus::Failed
+        },
+        error: result.as_ref().err().map(|e| e.to_string()),
+    };
+    if let Err(e) = params.app_handle.emit("generation-status", payload) {
+        error!("Failed to emit generation-status: {:?}", e);
+    }
 }
 
-/// Run a prompt session with ACP
-pub(crate) async fn run_prompt_session(params: PromptSessionParams) -> anyhow::Result<String> {
+fn emit_retrying(params: &PromptSessionParams, payload: GenerationRetryingPayload) {
+    if let Err(e) = params.app_handle.emit("generation-retrying", payload) {
+        error!("Failed to emit generation-retrying: {:?}", e);
+    }
+}
+
+async fn prompt_session(
+    params: PromptSessionParams,
+    progress: &SessionProgress,
+) -> anyhow::Result<PromptResult> {
     let PromptSessionParams {
         app_handle,
         node_id,
-        messages,
+        trace_id,
+        mut messages,
         pending_permissions,
         notes_directory,
         provider,
         model_id,
-        provider_paths,
+        session_mode,
+        agent_command,
+        spawn_config,
+        project_permissions,
+        stream_replay,
+        image_max_dimension,
+        system_prompt,
+        preamble,
+        compaction,
+        claude_options,
     } = params;
+    let started = Instant::now();
+    let mut timings = PromptTimings::default();
+
+    let mut compacted_messages = 0;
+    if let (Some(compaction), None) = (&compaction, &agent_command) {
+        if compaction_split(&messages).is_some() {
+            progress.enter(
+                &app_handle,
+                &node_id,
+                &trace_id,
+                GenerationStatus::Compacting,
+            );
+        }
+        compacted_messages = compact_ancestors(
+            &mut messages,
+            compaction,
+            &provider,
+            &notes_directory,
+            &spawn_config,
+        )
+        .await;
+    }
+
     // Spawn the ACP subprocess in the notes directory so skills are loaded
     // For Gemini, model_id is passed at spawn time via --model flag
+    progress.enter(&app_handle, &node_id, &trace_id, GenerationStatus::Spawning);
     let child = spawn_agent_subprocess(
         &provider,
         &notes_directory,
-        &provider_paths,
+        &spawn_config,
         model_id.as_deref(),
     )
     .await?;
 
     // Create client with notes directory for permission filtering
     let client = Arc::new(StreamingClient::new(
-        app_handle,
-        node_id,
+        app_handle.clone(),
+        node_id.clone(),
         pending_permissions,
         notes_directory.clone(),
+        spawn_config.read_roots.clone(),
+        project_permissions,
+        stream_replay,
     ));
+    let first_chunk_at = client.first_chunk_at();
+    timings.spawn_ms = elapsed_ms(started);
 
     info!("Creating ACP connection...");
-    let (connection, process) = connect_agent(child, client, "claude-code-acp")?;
+    let (connection, mut process) = connect_agent_with_tail(
+        child,
+        client.clone(),
+        "claude-code-acp",
+        progress.stderr_tail.clone(),
+    )?;
 
     // Initialize
     info!("Initializing connection...");
+    progress.enter(
+        &app_handle,
+        &node_id,
+        &trace_id,
+        GenerationStatus::Initializing,
+    );
+    let phase_started = Instant::now();
+    // Notes are read through StreamingClient::read_text_file
+    let client_capabilities =
+        ClientCapabilities::new().fs(FileSystemCapability::new().read_text_file(true));
     let init_response = initialize_with_timeout(
         &connection,
         Implementation::new("thoughttree", env!("CARGO_PKG_VERSION")).title("ThoughtTree"),
+        client_capabilities.clone(),
     )
-    .await?;
+    .await;
+    let init_response = match init_response {
+        Ok(response) => response,
+        Err(e) => {
+            // Wait for the process so its last stderr lines are in
+            drop(connection);
+            process.shutdown("claude-code-acp").await;
+            let lines = stderr_lines(&progress.stderr_tail);
+            return Err(anyhow::anyhow!(with_stderr_tail(e.to_string(), &lines)));
+        }
+    };
+    timings.initialize_ms = elapsed_ms(phase_started);
 
     info!(
         "Connected to agent: {:?} (protocol: {})",
@@ -190,16 +579,58 @@ pub(crate) async fn run_prompt_session(params: PromptSessionParams) -> anyhow::R
 
     // Create session with notes directory as cwd
     info!("Creating session with cwd: {:?}", notes_directory);
-    let session_response = connection
-        .new_session(NewSessionRequest::new(notes_directory))
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to create session: {e:?}"))?;
+    let phase_started = Instant::now();
+    let mut session_request = NewSessionRequest::new(notes_directory);
+    if matches!(provider, AgentProvider::ClaudeCode) {
+        if let Some(meta) = claude_options.session_meta() {
+            info!("Claude Code options: {:?}", claude_options);
+            session_request = session_request.meta(meta);
+        }
+    }
+    let session_response = match connection.new_session(session_request).await {
+        Ok(response) => response,
+        Err(e) if is_auth_required(&e) => {
+            // Let the frontend offer the agent's login methods
+            let payload = AuthRequiredPayload {
+                node_id,
+                trace_id: trace_id.clone(),
+                provider: provider.clone(),
+                methods: auth_methods(&init_response),
+            };
+            if let Err(emit_err) = app_handle.emit("auth-required", payload) {
+                error!("Failed to emit auth-required: {:?}", emit_err);
+            }
+            drop(connection);
+            process.shutdown("claude-code-acp").await;
+            return Err(AuthenticationRequired {
+                provider_name: provider.display_name().to_string(),
+            }
+            .into());
+        }
+        Err(e) => {
+            drop(connection);
+            process.shutdown("claude-code-acp").await;
+            let lines = stderr_lines(&progress.stderr_tail);
+            return Err(anyhow::anyhow!(with_stderr_tail(
+                format!("Failed to create session: {e:?}"),
+                &lines
+            )));
+        }
+    };
+    timings.new_session_ms = elapsed_ms(phase_started);
 
     info!("Session created: {}", session_response.session_id);
+    progress.enter(
+        &app_handle,
+        &node_id,
+        &trace_id,
+        GenerationStatus::SessionCreated,
+    );
 
     // Switch model if specified
     if let Some(ref model) = model_id {
         info!("Switching to model: {}", model);
+        let phase_started = Instant::now();
         connection
             .set_session_model(SetSessionModelRequest::new(
                 session_response.session_id.clone(),
@@ -207,37 +638,178 @@ pub(crate) async fn run_prompt_session(params: PromptSessionParams) -> anyhow::R
             ))
             .await
             .map_err(|e| anyhow::anyhow!("Failed to set model: {e:?}"))?;
+        timings.model_switch_ms = Some(elapsed_ms(phase_started));
+        progress.enter(&app_handle, &node_id, &trace_id, GenerationStatus::ModelSet);
     }
 
-    // Get current date and format it
-    let current_date = Local::now().format("%B %d, %Y").to_string();
-    let date_prefix = format!("Current date: {current_date}\n\n");
+    // Switch session mode if requested and offered by the agent
+    if let Some(ref mode) = session_mode {
+        let offered = session_response.modes.as_ref().is_some_and(|state| {
+            state
+                .available_modes
+                .iter()
+                .any(|m| &*m.id.0 == mode.as_str())
+        });
+        if offered {
+            info!("Switching to session mode: {}", mode);
+            connection
+                .set_session_mode(SetSessionModeRequest::new(
+                    session_response.session_id.clone(),
+                    SessionModeId::new(mode.clone()),
+                ))
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to set session mode: {e:?}"))?;
+        } else {
+            warn!(
+                "Session mode '{}' not offered by {:?}; using default",
+                mode, provider
+            );
+        }
+    }
 
-    // Build prompt from conversation messages
-    let prompt_text = messages
-        .iter()
-        .map(|msg| format!("{}: {}", msg.role, msg.content))
-        .collect::<Vec<_>>()
-        .join("\n\n");
+    // Referenced notes go as resource links, which every agent accepts
+    // (`embedded_context` only covers embedded resources); the agent reads
+    // them back through `read_text_file`. Without that the text goes inline.
+    let prompt_capabilities = &init_response.agent_capabilities.prompt_capabilities;
+    if !client_capabilities.fs.read_text_file {
+        inline_context_notes(&mut messages);
+    }
+
+    // Drop the oldest messages when the branch outgrows the model's context
+    let mut context_truncation = None;
+    if agent_command.is_none() {
+        let fixed =
+            estimate_tokens(&preamble) + system_prompt.as_deref().map_or(0, estimate_tokens);
+        let budget = context_budget(&provider, model_id.as_deref()).saturating_sub(fixed);
+        context_truncation = fit_to_budget(&mut messages, budget);
+        if let Some(report) = &context_truncation {
+            warn!(
+                "Prompt for node {} exceeds context budget: {:?}",
+                node_id, report
+            );
+        }
+    }
 
-    // Prepend current date to the prompt
-    let prompt_text = format!("{date_prefix}{prompt_text}");
+    let prompt_text = if let Some(ref command) = agent_command {
+        // Slash commands are sent as plain `/name args` text
+        slash_command_prompt(command, messages.last().map(|m| m.content.as_str()))?
+    } else {
+        // Build prompt from conversation messages
+        let prompt_text = messages
+            .iter()
+            .map(|msg| format!("{}: {}", msg.role, msg.content))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        // Prepend the configured preamble to the prompt
+        format!("{preamble}{prompt_text}")
+    };
 
     // Build content blocks: images first, then text
     // Claude processes images before text for better understanding
     let mut content_blocks: Vec<ContentBlock> = Vec::new();
 
-    // Add all images from all messages
-    for msg in &messages {
-        if let Some(images) = &msg.images {
-            for img in images {
-                info!("Adding image: mime_type={}", img.mime_type);
-                content_blocks.push(ContentBlock::Image(ImageContent::new(
-                    img.data.clone(),
-                    img.mime_type.clone(),
-                )));
-            }
+    // Add all images from all messages, downscaled off the async runtime
+    let attached: Vec<MessageImage> = messages
+        .iter()
+        .flat_map(|msg| msg.images.iter().flatten().cloned())
+        .collect();
+    let prepared = tokio::task::spawn_blocking(move || {
+        attached
+            .iter()
+            .map(|img| images::prepare_message_image(img, image_max_dimension))
+            .collect::<Vec<_>>()
+    })
+    .await?;
+    for img in prepared {
+        info!("Adding image: mime_type={}", img.mime_type);
+        content_blocks.push(ContentBlock::Image(ImageContent::new(
+            img.data,
+            img.mime_type,
+        )));
+    }
+
+    // Then links to referenced notes, once each
+    let mut linked = HashSet::new();
+    for note in messages.iter().flat_map(|msg| &msg.context_notes) {
+        if !linked.insert(&note.path) {
+            continue;
         }
+        info!("Adding note link: {}", note.rel_path);
+        content_blocks.push(ContentBlock::ResourceLink(
+            ResourceLink::new(note.rel_path.clone(), file_uri(&note.path))
+                .mime_type("text/markdown".to_string())
+                .size(note.text.len() as i64),
+        ));
+    }
+
+    // Then other attachments, in the forms the agent accepts
+    for attachment in messages
+        .iter()
+        .flat_map(|msg| msg.attachments.iter().flatten())
+    {
+        let prepared =
+            attachments::prepare_attachment(attachment).map_err(|e| anyhow::anyhow!(e))?;
+        let block = match prepared {
+            PreparedAttachment::Text {
+                uri,
+                mime_type,
+                text,
+            } if prompt_capabilities.embedded_context => ContentBlock::Resource(
+                EmbeddedResource::new(EmbeddedResourceResource::TextResourceContents(
+                    TextResourceContents::new(text, uri).mime_type(mime_type),
+                )),
+            ),
+            PreparedAttachment::Text { uri, text, .. } => {
+                ContentBlock::Text(TextContent::new(attachments::inline_text(&uri, &text)))
+            }
+            PreparedAttachment::Blob {
+                uri,
+                mime_type,
+                data,
+            } if prompt_capabilities.embedded_context => ContentBlock::Resource(
+                EmbeddedResource::new(EmbeddedResourceResource::BlobResourceContents(
+                    BlobResourceContents::new(data, uri).mime_type(mime_type),
+                )),
+            ),
+            PreparedAttachment::Audio { mime_type, data } if prompt_capabilities.audio => {
+                ContentBlock::Audio(AudioContent::new(data, mime_type))
+            }
+            // PDFs go as their text layer, or are left out
+            PreparedAttachment::Blob { uri, data, .. } => {
+                let name = attachment.name.clone();
+                match tokio::task::spawn_blocking(move || {
+                    attachments::extract_pdf_text(&name, &data)
+                })
+                .await?
+                {
+                    Ok(text) => {
+                        ContentBlock::Text(TextContent::new(attachments::inline_text(&uri, &text)))
+                    }
+                    Err(e) => {
+                        warn!(
+                            "Skipping attachment {} for {}: {}",
+                            attachment.name,
+                            provider.display_name(),
+                            e
+                        );
+                        continue;
+                    }
+                }
+            }
+            PreparedAttachment::Audio { .. } => {
+                return Err(anyhow::anyhow!(
+                    "{} does not accept attachments like {}",
+                    provider.display_name(),
+                    attachment.name
+                ));
+            }
+        };
+        info!(
+            "Adding attachment: {} ({})",
+            attachment.name, attachment.mime_type
+        );
+        content_blocks.push(block);
     }
 
     // Validate we have content to send
@@ -250,6 +822,18 @@ pub(crate) async fn run_prompt_session(params: PromptSessionParams) -> anyhow::R
         content_blocks.push(ContentBlock::Text(TextContent::new(prompt_text)));
     }
 
+    // The system prompt leads as its own block. Slash commands must be the
+    // first text the agent sees, so they go without it.
+    if let Some(system_prompt) = system_prompt.filter(|_| agent_command.is_none()) {
+        content_blocks.insert(
+            0,
+            ContentBlock::Text(TextContent::new(format!(
+                "<system-prompt>\n{}\n</system-prompt>",
+                system_prompt.trim()
+            ))),
+        );
+    }
+
     // Send prompt
     info!(
         "Sending prompt with {} content blocks ({} images)...",
@@ -259,22 +843,204 @@ pub(crate) async fn run_prompt_session(params: PromptSessionParams) -> anyhow::R
             .filter(|b| matches!(b, ContentBlock::Image(_)))
             .count()
     );
-    let prompt_response = connection
-        .prompt(PromptRequest::new(
+    progress.enter(
+        &app_handle,
+        &node_id,
+        &trace_id,
+        GenerationStatus::Prompting,
+    );
+    let prompt_sent = Instant::now();
+    // Watch the subprocess alongside the prompt so a crash surfaces
+    // immediately instead of as a silent stall
+    let prompt_response = tokio::select! {
+        response = connection.prompt(PromptRequest::new(
             session_response.session_id,
             content_blocks,
-        ))
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to send prompt: {e:?}"))?;
+        )) => {
+            client.flush_chunks();
+            client.record_thoughts();
+            // An expired login can surface on the prompt itself
+            response.map_err(|e| {
+                if is_auth_required(&e) {
+                    anyhow::Error::new(AuthenticationRequired {
+                        provider_name: provider.display_name().to_string(),
+                    })
+                } else {
+                    let message = format!("Failed to send prompt: {e:?}");
+                    match classify_transient(&message) {
+                        Some(kind) => anyhow::Error::new(TransientFailure {
+                            kind,
+                            message,
+                            produced_output: first_chunk_at.get().is_some(),
+                        }),
+                        None => anyhow::anyhow!(message),
+                    }
+                }
+            })?
+        }
+        status = process.child.wait() => {
+            client.flush_chunks();
+            client.record_thoughts();
+            let crash = AgentCrashed {
+                exit_status: match status {
+                    Ok(status) => status.to_string(),
+                    Err(e) => format!("unknown ({e})"),
+                },
+                stderr_tail: process.stderr_tail(),
+                produced_output: first_chunk_at.get().is_some(),
+            };
+            error!("Agent exited mid-prompt: {}", crash);
+            let payload = AgentCrashedPayload {
+                node_id,
+                trace_id: trace_id.clone(),
+                provider,
+                exit_status: crash.exit_status.clone(),
+                stderr_tail: crash.stderr_tail.clone(),
+            };
+            if let Err(e) = app_handle.emit("agent-crashed", payload) {
+                error!("Failed to emit agent-crashed: {:?}", e);
+            }
+            return Err(crash.into());
+        }
+    };
+    timings.completion_ms = elapsed_ms(prompt_sent);
+    timings.first_chunk_ms = first_chunk_at
+        .get()
+        .map(|at| at.saturating_duration_since(prompt_sent).as_millis() as u64);
 
     info!("Stop reason: {:?}", prompt_response.stop_reason);
+    let stop_reason = serde_json::to_value(&prompt_response.stop_reason)
+        .ok()
+        .as_ref()
+        .and_then(serde_json::Value::as_str)
+        .map_or(StopReason::Error, StopReason::from_acp_name);
+    if stop_reason.needs_attention() {
+        let payload = GenerationStoppedPayload {
+            node_id: node_id.clone(),
+            trace_id: trace_id.clone(),
+            stop_reason,
+        };
+        if let Err(e) = app_handle.emit("generation-stopped", payload) {
+            error!("Failed to emit generation-stopped: {:?}", e);
+        }
+    }
 
     // Dropping the connection closes the subprocess's stdin; shutdown then
     // waits for exit and drains the I/O and stderr tasks.
     drop(connection);
     process.shutdown("claude-code-acp").await;
+    timings.total_ms = elapsed_ms(started);
+    let (chunk_count, response_chars) = client.output_counts();
+    let throughput = Throughput::new(chunk_count, response_chars, &timings);
+
+    info!(
+        "Prompt timings: {:?}, throughput: {:?}",
+        timings, throughput
+    );
 
-    Ok(format!("{:?}", prompt_response.stop_reason))
+    Ok(PromptResult {
+        stop_reason,
+        timings,
+        linked_notes: Vec::new(),
+        context_truncation,
+        compacted_messages,
+        throughput,
+        trace_id,
+    })
+}
+
+/// Build the prompt text for an agent slash command. Names are restricted to
+/// the characters agents use so the text can't smuggle in a different command.
+fn slash_command_prompt(name: &str, argument: Option<&str>) -> anyhow::Result<String> {
+    let name = name.trim_start_matches('/');
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':' | '.'));
+    if !valid {
+        return Err(anyhow::anyhow!("Invalid agent command name: {name:?}"));
+    }
+
+    Ok(match argument.map(str::trim).filter(|a| !a.is_empty()) {
+        Some(argument) => format!("/{name} {argument}"),
+        None => format!("/{name}"),
+    })
+}
+
+/// Run a prompt session with ACP, reporting each phase as a
+/// `generation-status` event for the node. Retries up to `max_retries` times
+/// with growing delays after rate-limit and overload errors, and once if the
+/// agent crashed, as long as no output was streamed yet (a retry after
+/// partial output would duplicate text). Each retry is announced with a
+/// `generation-retrying` event; only the last attempt reports
+/// `completed`/`failed` (see [`report_outcome`]). A session that is
+/// cancelled or times out ends without either; those have their own events.
+pub(crate) async fn run_prompt_session_with_retry(
+    params: PromptSessionParams,
+    retry_on_crash: bool,
+    max_retries: u32,
+) -> anyhow::Result<PromptResult> {
+    let mut retries = 0;
+    let mut crash_retried = false;
+    loop {
+        let progress = SessionProgress::default();
+        let error = match prompt_session(params.clone(), &progress).await {
+            Err(e) => e,
+            result => {
+                report_outcome(&params, &progress, &result);
+                return result;
+            }
+        };
+
+        if let Some(failure) = error
+            .downcast_ref::<TransientFailure>()
+            .filter(|failure| !failure.produced_output && retries < max_retries)
+        {
+            retries += 1;
+            let delay = backoff_delay(retries);
+            warn!(
+                "Transient error ({:?}); retry {}/{} in {}s",
+                failure.kind,
+                retries,
+                max_retries,
+                delay.as_secs()
+            );
+            let payload = GenerationRetryingPayload {
+                node_id: params.node_id.clone(),
+                trace_id: params.trace_id.clone(),
+                reason: failure.kind.into(),
+                attempt: retries,
+                max_retries,
+                delay_secs: delay.as_secs(),
+                message: failure.message.clone(),
+            };
+            emit_retrying(&params, payload);
+            tokio::time::sleep(delay).await;
+            continue;
+        }
+
+        if let Some(crash) = error
+            .downcast_ref::<AgentCrashed>()
+            .filter(|crash| retry_on_crash && !crash.produced_output && !crash_retried)
+        {
+            warn!("Agent crashed before answering; retrying once");
+            crash_retried = true;
+            let payload = GenerationRetryingPayload {
+                node_id: params.node_id.clone(),
+                trace_id: params.trace_id.clone(),
+                reason: RetryReason::Crashed,
+                attempt: 1,
+                max_retries: 1,
+                delay_secs: 0,
+                message: crash.to_string(),
+            };
+            emit_retrying(&params, payload);
+            continue;
+        }
+        let result = Err(error);
+        report_outcome(&params, &progress, &result);
+        return result;
+    }
 }
 
 /// Derive a display name from a model ID
@@ -329,35 +1095,103 @@ fn model_id_to_display_name(model_id: &str) -> String {
     }
 }
 
-pub(crate) async fn run_model_discovery_session(
-    notes_directory: PathBuf,
-    provider: AgentProvider,
-    provider_paths: ProviderPaths,
-) -> Result<Vec<ModelInfo>, String> {
-    // Spawn the ACP subprocess (model_id is None for discovery - we're just fetching available models)
-    let child = spawn_agent_subprocess(&provider, &notes_directory, &provider_paths, None)
+/// Relative cost tier of a model, inferred from its id (lower is cheaper).
+/// Returns `None` for ids that don't match a known model family.
+fn model_cost_tier(model_id: &str) -> Option<u8> {
+    let id_lower = model_id.to_lowercase();
+    if id_lower.contains("haiku") || id_lower.contains("flash-lite") {
+        Some(0)
+    } else if id_lower.contains("flash") {
+        Some(1)
+    } else if id_lower.contains("sonnet") {
+        Some(2)
+    } else if id_lower.contains("pro") {
+        Some(3)
+    } else if id_lower.contains("opus") {
+        Some(4)
+    } else {
+        None
+    }
+}
+
+/// Pick the model for bulk background jobs (summaries, tags, digests):
+/// the user's override if the agent offers it, otherwise the cheapest known model.
+/// Returns `None` to keep the agent's default model.
+pub(crate) fn select_bulk_model(
+    available: &[&str],
+    model_override: Option<&str>,
+) -> Option<String> {
+    if let Some(model_override) = model_override {
+        if available.contains(&model_override) {
+            return Some(model_override.to_string());
+        }
+        warn!(
+            "Bulk model override {} not offered by agent, falling back to cheapest model",
+            model_override
+        );
+    }
+
+    available
+        .iter()
+        .filter_map(|id| model_cost_tier(id).map(|tier| (tier, *id)))
+        .min_by_key(|(tier, _)| *tier)
+        .map(|(_, id)| id.to_string())
+}
+
+/// Spawn a provider, open a throwaway session and return what it advertised
+async fn run_discovery_session(
+    notes_directory: &Path,
+    provider: &AgentProvider,
+    spawn_config: &SpawnConfig,
+    tag: &str,
+) -> Result<NewSessionResponse, String> {
+    // Spawn the ACP subprocess (model_id is None for discovery - we're just fetching what's available)
+    let child = spawn_agent_subprocess(provider, notes_directory, spawn_config, None)
         .await
         .map_err(|e| format!("Failed to spawn agent: {e}"))?;
 
     // Create minimal client
     let client = Arc::new(ModelDiscoveryClient);
 
-    let (connection, process) =
-        connect_agent(child, client, "model-discovery").map_err(|e| e.to_string())?;
+    let (connection, process) = connect_agent(child, client, tag).map_err(|e| e.to_string())?;
 
-    // Initialize
-    let _init_response = initialize_with_timeout(
-        &connection,
-        Implementation::new("thoughttree", env!("CARGO_PKG_VERSION")).title("ThoughtTree"),
-    )
-    .await
-    .map_err(|e| e.to_string())?;
+    let session_response = async {
+        initialize_with_timeout(
+            &connection,
+            Implementation::new("thoughttree", env!("CARGO_PKG_VERSION")).title("ThoughtTree"),
+            ClientCapabilities::default(),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
 
+        connection
+            .new_session(NewSessionRequest::new(notes_directory))
+            .await
+            .map_err(|e| format!("Failed to create session: {e:?}"))
+    }
+    .await;
+
+    drop(connection);
+    // Shutdown drains stderr, so the tail is complete after it
+    let stderr_tail = process.stderr_tail.clone();
+    process.shutdown(tag).await;
+
+    session_response.map_err(|e| with_stderr_tail(e, &stderr_lines(&stderr_tail)))
+}
+
+pub(crate) async fn run_model_discovery_session(
+    notes_directory: PathBuf,
+    provider: AgentProvider,
+    spawn_config: SpawnConfig,
+) -> Result<Vec<ModelInfo>, String> {
     // Create session to get models
-    let session_response = connection
-        .new_session(NewSessionRequest::new(&notes_directory))
-        .await
-        .map_err(|e| format!("Failed to create session: {e:?}"))?;
+    let session_response = run_discovery_session(
+        &notes_directory,
+        &provider,
+        &spawn_config,
+        "model-discovery",
+    )
+    .await?;
 
     // Extract models from response
     let models: Vec<ModelInfo> = session_response
@@ -373,20 +1207,11 @@ pub(crate) async fn run_model_discovery_session(
         })
         .unwrap_or_default();
 
-    // Gemini CLI doesn't expose models via ACP, so provide fallback options
-    // These correspond to the --model flag values for `gemini` CLI
+    // Gemini CLI doesn't expose models via ACP; ask the Gemini API what the
+    // account's key can use, and only fall back to the CLI's auto-routing
+    // aliases when there is no key (Google sign-in, Vertex AI) or the API fails
     let models = if models.is_empty() && matches!(provider, AgentProvider::GeminiCli) {
-        info!("Gemini CLI returned no models via ACP, using fallback model list");
-        vec![
-            ModelInfo {
-                model_id: "gemini-3".to_string(),
-                display_name: "Gemini 3 (Auto)".to_string(),
-            },
-            ModelInfo {
-                model_id: "gemini-2.5".to_string(),
-                display_name: "Gemini 2.5 (Auto)".to_string(),
-            },
-        ]
+        gemini_models_or_fallback(&spawn_config.proxy).await
     } else {
         models
     };
@@ -398,109 +1223,673 @@ pub(crate) async fn run_model_discovery_session(
         models.iter().map(|m| &m.model_id).collect::<Vec<_>>()
     );
 
+    Ok(models)
+}
+
+/// Models the Gemini API lists for the configured key, or the `--model`
+/// aliases every Gemini CLI accepts
+async fn gemini_models_or_fallback(proxy: &ProxySettings) -> Vec<ModelInfo> {
+    if let Some(api_key) = gemini_api_key() {
+        match list_gemini_models(&api_key, proxy).await {
+            Ok(models) if !models.is_empty() => return models,
+            Ok(_) => warn!("Gemini API listed no chat models, using fallback model list"),
+            Err(e) => warn!("{}; using fallback model list", e),
+        }
+    } else {
+        info!("No Gemini API key configured, using fallback model list");
+    }
+    vec![
+        ModelInfo {
+            model_id: "gemini-3".to_string(),
+            display_name: "Gemini 3 (Auto)".to_string(),
+        },
+        ModelInfo {
+            model_id: "gemini-2.5".to_string(),
+            display_name: "Gemini 2.5 (Auto)".to_string(),
+        },
+    ]
+}
+
+/// List the session modes a provider offers
+pub(crate) async fn run_mode_discovery_session(
+    notes_directory: PathBuf,
+    provider: AgentProvider,
+    spawn_config: SpawnConfig,
+) -> Result<SessionModes, String> {
+    let session_response =
+        run_discovery_session(&notes_directory, &provider, &spawn_config, "mode-discovery").await?;
+
+    let modes = session_response
+        .modes
+        .map(|state| SessionModes {
+            current_mode_id: Some(state.current_mode_id.0.to_string()),
+            available_modes: state
+                .available_modes
+                .into_iter()
+                .map(|mode| SessionModeInfo {
+                    id: mode.id.0.to_string(),
+                    name: mode.name,
+                    description: mode.description,
+                })
+                .collect(),
+        })
+        .unwrap_or_default();
+
+    info!(
+        "Discovered {} session modes for {:?}",
+        modes.available_modes.len(),
+        provider
+    );
+
+    Ok(modes)
+}
+
+/// Spawn a provider, initialize and open a session, and report which app
+/// features its advertised capabilities support
+pub(crate) async fn run_capability_probe_session(
+    notes_directory: PathBuf,
+    provider: AgentProvider,
+    spawn_config: SpawnConfig,
+) -> Result<ProviderFeatures, String> {
+    let child = spawn_agent_subprocess(&provider, &notes_directory, &spawn_config, None)
+        .await
+        .map_err(|e| format!("Failed to spawn agent: {e}"))?;
+
+    let client = Arc::new(CapabilityProbeClient::new());
+    let available_commands = client.available_commands.clone();
+
+    let (connection, process) =
+        connect_agent(child, client, "capability-probe").map_err(|e| e.to_string())?;
+
+    let init_response = initialize_with_timeout(
+        &connection,
+        Implementation::new("thoughttree", env!("CARGO_PKG_VERSION")).title("ThoughtTree"),
+        ClientCapabilities::default(),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let session_response = connection
+        .new_session(NewSessionRequest::new(&notes_directory))
+        .await
+        .map_err(|e| format!("Failed to create session: {e:?}"))?;
+
+    // Commands arrive as a notification shortly after the session is created
+    tokio::time::sleep(COMMANDS_SETTLE_DELAY).await;
+    let slash_commands = !available_commands.lock().await.is_empty();
+
+    let capabilities = &init_response.agent_capabilities;
+    let features = ProviderFeatures {
+        provider: provider.clone(),
+        available: true,
+        error_message: None,
+        vision: capabilities.prompt_capabilities.image,
+        // ACP has no capability flag for thoughts; both supported CLIs stream them
+        thought_streaming: true,
+        // Gemini CLI takes its model at spawn time, so it can always be switched
+        model_switching: session_response.models.is_some()
+            || matches!(provider, AgentProvider::GeminiCli),
+        session_resume: capabilities.load_session,
+        slash_commands,
+    };
+
+    info!("Capabilities for {:?}: {:?}", provider, features);
+
     drop(connection);
-    process.shutdown("model-discovery").await;
+    process.shutdown("capability-probe").await;
 
-    Ok(models)
+    Ok(features)
 }
 
-/// Run a summarization session with Haiku model
-pub(crate) async fn run_summary_session(
-    content: String,
+/// Spawn a provider and run only the `initialize` handshake, to check that
+/// the agent starts and speaks ACP
+pub(crate) async fn run_handshake_session(
+    notes_directory: PathBuf,
+    provider: AgentProvider,
+    spawn_config: SpawnConfig,
+) -> Result<(), String> {
+    let child = spawn_agent_subprocess(&provider, &notes_directory, &spawn_config, None)
+        .await
+        .map_err(|e| format!("Failed to spawn agent: {e}"))?;
+
+    let client = Arc::new(CapabilityProbeClient::new());
+    let (connection, process) =
+        connect_agent(child, client, "health-check").map_err(|e| e.to_string())?;
+
+    let result = initialize_with_timeout(
+        &connection,
+        Implementation::new("thoughttree", env!("CARGO_PKG_VERSION")).title("ThoughtTree"),
+        ClientCapabilities::default(),
+    )
+    .await
+    .map(|_| ())
+    .map_err(|e| e.to_string());
+
+    drop(connection);
+    let stderr_tail = process.stderr_tail.clone();
+    process.shutdown("health-check").await;
+    result.map_err(|e| with_stderr_tail(e, &stderr_lines(&stderr_tail)))
+}
+
+/// List the agent's authentication methods and, when `method_id` is given,
+/// run that method (which may open a browser login for the CLI)
+pub(crate) async fn run_auth_session(
     notes_directory: PathBuf,
-    custom_path: Option<String>,
-) -> anyhow::Result<String> {
-    // Spawn ACP subprocess
-    let child = spawn_claude_code_acp(&notes_directory, custom_path.as_deref()).await?;
+    provider: AgentProvider,
+    spawn_config: SpawnConfig,
+    method_id: Option<String>,
+) -> Result<Vec<AuthMethodInfo>, String> {
+    let child = spawn_agent_subprocess(&provider, &notes_directory, &spawn_config, None)
+        .await
+        .map_err(|e| format!("Failed to spawn agent: {e}"))?;
 
-    let client = Arc::new(SummaryClient::new());
-    let response_text = client.response_text.clone();
+    let client = Arc::new(ModelDiscoveryClient);
 
-    let (connection, process) = connect_agent(child, client, "summary-acp")?;
+    let (connection, process) =
+        connect_agent(child, client, "authenticate").map_err(|e| e.to_string())?;
 
-    // Initialize. This doubles as the readiness handshake: stdin writes are
-    // buffered by the pipe, so no startup delay is needed.
-    info!("Summary session: initializing connection...");
     let init_response = initialize_with_timeout(
         &connection,
-        Implementation::new("thoughttree-summarizer", env!("CARGO_PKG_VERSION")),
+        Implementation::new("thoughttree", env!("CARGO_PKG_VERSION")).title("ThoughtTree"),
+        ClientCapabilities::default(),
     )
-    .await?;
+    .await
+    .map_err(|e| e.to_string())?;
+    let methods = auth_methods(&init_response);
 
-    info!(
-        "Summary session connected to: {:?}",
-        init_response.agent_info
-    );
+    let result = match method_id {
+        Some(method_id) if !methods.iter().any(|m| m.id == method_id) => {
+            Err(format!("Unknown authentication method: {method_id}"))
+        }
+        Some(method_id) => {
+            info!("Authenticating {:?} with method {}", provider, method_id);
+            connection
+                .authenticate(AuthenticateRequest::new(AuthMethodId::new(method_id)))
+                .await
+                .map(|_| methods)
+                .map_err(|e| format!("Authentication failed: {e:?}"))
+        }
+        None => Ok(methods),
+    };
 
-    // Create session
-    let session_response = connection
+    drop(connection);
+    process.shutdown("authenticate").await;
+
+    result
+}
+
+/// List the slash commands a provider advertises after session creation
+pub(crate) async fn run_command_discovery_session(
+    notes_directory: PathBuf,
+    provider: AgentProvider,
+    spawn_config: SpawnConfig,
+) -> Result<Vec<AgentCommandInfo>, String> {
+    let child = spawn_agent_subprocess(&provider, &notes_directory, &spawn_config, None)
+        .await
+        .map_err(|e| format!("Failed to spawn agent: {e}"))?;
+
+    let client = Arc::new(CapabilityProbeClient::new());
+    let available_commands = client.available_commands.clone();
+
+    let (connection, process) =
+        connect_agent(child, client, "command-discovery").map_err(|e| e.to_string())?;
+
+    initialize_with_timeout(
+        &connection,
+        Implementation::new("thoughttree", env!("CARGO_PKG_VERSION")).title("ThoughtTree"),
+        ClientCapabilities::default(),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let session_result = connection
         .new_session(NewSessionRequest::new(&notes_directory))
         .await
-        .map_err(|e| anyhow::anyhow!("Failed to create session: {e:?}"))?;
-
-    // Try to switch to Haiku if available
-    if let Some(models) = &session_response.models {
-        // Look for Haiku model
-        let haiku = models.available_models.iter().find(|m| {
-            let id = m.model_id.0.to_lowercase();
-            id.contains("haiku")
-        });
+        .map_err(|e| format!("Failed to create session: {e:?}"));
+    if session_result.is_ok() {
+        // Commands arrive as a notification shortly after the session is created
+        tokio::time::sleep(COMMANDS_SETTLE_DELAY).await;
+    }
 
-        if let Some(haiku_model) = haiku {
-            info!("Switching to Haiku model: {}", haiku_model.model_id.0);
-            let _ = connection
-                .set_session_model(SetSessionModelRequest::new(
-                    session_response.session_id.clone(),
-                    haiku_model.model_id.clone(),
-                ))
-                .await;
-        } else {
-            info!(
-                "Haiku not found, using default model: {}",
-                models.current_model_id.0
-            );
+    drop(connection);
+    process.shutdown("command-discovery").await;
+    session_result?;
+
+    let commands = available_commands.lock().await.clone();
+    info!("Discovered {} commands for {:?}", commands.len(), provider);
+    Ok(commands)
+}
+
+/// Heading produced by [`run_summary_session`], with the model that wrote it
+pub(crate) struct SummaryOutput {
+    pub summary: String,
+    pub model_id: Option<String>,
+}
+
+/// Model Gemini CLI is started with for bulk jobs unless overridden
+const GEMINI_BULK_MODEL: &str = "gemini-2.5-flash";
+
+/// Model to pass at spawn time for a bulk job. Gemini CLI takes its model
+/// as a `--model` flag; Claude switches once the session is open.
+fn bulk_spawn_model(provider: &AgentProvider, model_override: Option<&str>) -> Option<String> {
+    match provider {
+        AgentProvider::ClaudeCode => None,
+        AgentProvider::GeminiCli => Some(model_override.unwrap_or(GEMINI_BULK_MODEL).to_string()),
+    }
+}
+
+/// A session on the provider's cheapest model (or the user's bulk override)
+/// for background jobs, reusable across prompts
+struct BulkSession {
+    connection: ClientSideConnection,
+    process: AgentProcess,
+    session_id: SessionId,
+    response_text: Arc<Mutex<String>>,
+    /// Model the bulk model policy selected
+    model_id: Option<String>,
+    tag: &'static str,
+}
+
+impl BulkSession {
+    async fn open(
+        provider: &AgentProvider,
+        notes_directory: &Path,
+        spawn_config: &SpawnConfig,
+        model_override: Option<&str>,
+        tag: &'static str,
+    ) -> anyhow::Result<Self> {
+        // Spawn ACP subprocess
+        let spawn_model = bulk_spawn_model(provider, model_override);
+        let child = spawn_agent_subprocess(
+            provider,
+            notes_directory,
+            spawn_config,
+            spawn_model.as_deref(),
+        )
+        .await?;
+
+        let client = Arc::new(SummaryClient::new());
+        let response_text = client.response_text.clone();
+
+        let (connection, process) = connect_agent(child, client, tag)?;
+
+        // Initialize. This doubles as the readiness handshake: stdin writes are
+        // buffered by the pipe, so no startup delay is needed.
+        info!("Bulk session {}: initializing connection...", tag);
+        let init_response = initialize_with_timeout(
+            &connection,
+            Implementation::new("thoughttree-summarizer", env!("CARGO_PKG_VERSION")),
+            ClientCapabilities::default(),
+        )
+        .await?;
+
+        info!(
+            "Bulk session {} connected to: {:?}",
+            tag, init_response.agent_info
+        );
+
+        // Create session
+        let session_response = connection
+            .new_session(NewSessionRequest::new(notes_directory))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to create session: {e:?}"))?;
+
+        // Switch to the cheapest model (or the user's bulk override) if available
+        let mut model_id = spawn_model.clone();
+        if let (None, Some(models)) = (&spawn_model, &session_response.models) {
+            let available: Vec<&str> = models
+                .available_models
+                .iter()
+                .map(|m| &*m.model_id.0)
+                .collect();
+
+            if let Some(bulk_model) = select_bulk_model(&available, model_override) {
+                info!("Switching to bulk model: {}", bulk_model);
+                let _ = connection
+                    .set_session_model(SetSessionModelRequest::new(
+                        session_response.session_id.clone(),
+                        agent_client_protocol::ModelId::new(bulk_model.clone()),
+                    ))
+                    .await;
+                model_id = Some(bulk_model);
+            } else {
+                info!(
+                    "No cheaper model found, using default model: {}",
+                    models.current_model_id.0
+                );
+                model_id = Some(models.current_model_id.0.to_string());
+            }
         }
+
+        Ok(Self {
+            connection,
+            process,
+            session_id: session_response.session_id,
+            response_text,
+            model_id,
+            tag,
+        })
     }
 
-    // Truncate content to avoid huge inputs
-    let truncated_content = if content.len() > 2000 {
-        format!("{}...", &content[..2000])
-    } else {
-        content
-    };
+    /// Send one prompt and wait for the response text, trimmed; empty when
+    /// the prompt failed
+    async fn prompt(&self, prompt_text: String) -> String {
+        self.response_text.lock().await.clear();
+        let prompt_result = self
+            .connection
+            .prompt(PromptRequest::new(
+                self.session_id.clone(),
+                vec![ContentBlock::Text(TextContent::new(prompt_text))],
+            ))
+            .await;
 
-    // Build summarization prompt
-    let prompt_text = format!(
-        "Write a 3-5 word heading that describes what this text is about. \
-         Be specific and concise. Do not call any tools. Return ONLY the heading, nothing else:\n\n{truncated_content}"
-    );
+        if let Err(e) = prompt_result {
+            warn!("Bulk prompt {} failed: {:?}", self.tag, e);
+        }
+        self.response_text.lock().await.trim().to_string()
+    }
 
-    // Send prompt and wait for completion
-    let prompt_result = connection
-        .prompt(PromptRequest::new(
-            session_response.session_id,
-            vec![ContentBlock::Text(TextContent::new(prompt_text))],
-        ))
+    async fn close(self) {
+        drop(self.connection);
+        self.process.shutdown(self.tag).await;
+    }
+}
+
+/// Send one prompt in a fresh bulk session and return the response text
+/// with the model that wrote it
+pub(crate) async fn run_bulk_prompt(
+    prompt_text: String,
+    provider: &AgentProvider,
+    notes_directory: &Path,
+    spawn_config: &SpawnConfig,
+    model_override: Option<&str>,
+    tag: &'static str,
+) -> anyhow::Result<(String, Option<String>)> {
+    let session =
+        BulkSession::open(provider, notes_directory, spawn_config, model_override, tag).await?;
+    let response = session.prompt(prompt_text).await;
+    let model_id = session.model_id.clone();
+    session.close().await;
+    Ok((response, model_id))
+}
+
+/// Run a summarization session on the provider's cheapest available model
+pub(crate) async fn run_summary_session(
+    content: String,
+    style: SummaryStyle,
+    provider: AgentProvider,
+    notes_directory: PathBuf,
+    spawn_config: SpawnConfig,
+    model_override: Option<String>,
+) -> anyhow::Result<SummaryOutput> {
+    let (result, model_id) = run_bulk_prompt(
+        summary_prompt(&content, &style),
+        &provider,
+        &notes_directory,
+        &spawn_config,
+        model_override.as_deref(),
+        "summary-acp",
+    )
+    .await?;
+
+    Ok(SummaryOutput {
+        summary: clean_summary(&result, &style),
+        model_id,
+    })
+}
+
+/// Summarize several nodes one after another in a single session, calling
+/// `on_ready` as each heading arrives. Nodes whose prompt produced no text
+/// are left out of the results.
+pub(crate) async fn run_summary_batch(
+    nodes: Vec<SummaryRequest>,
+    style: SummaryStyle,
+    provider: AgentProvider,
+    notes_directory: PathBuf,
+    spawn_config: SpawnConfig,
+    model_override: Option<String>,
+    mut on_ready: impl FnMut(&SummaryResult),
+) -> anyhow::Result<Vec<SummaryResult>> {
+    let session = BulkSession::open(
+        &provider,
+        &notes_directory,
+        &spawn_config,
+        model_override.as_deref(),
+        "summary-batch-acp",
+    )
+    .await?;
+
+    let mut results = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        let response = session.prompt(summary_prompt(&node.content, &style)).await;
+        let summary = clean_summary(&response, &style);
+        if summary.is_empty() {
+            warn!("No summary produced for node {}", node.node_id);
+            continue;
+        }
+        let result = SummaryResult {
+            node_id: node.node_id,
+            summary,
+            model_id: session.model_id.clone(),
+        };
+        on_ready(&result);
+        results.push(result);
+    }
+
+    session.close().await;
+    Ok(results)
+}
+
+/// One session kept open across prompts for the `acp` terminal REPL (see
+/// [`crate::backend::acp::repl`]); answers stream to stdout
+pub(crate) struct TerminalSession {
+    connection: ClientSideConnection,
+    process: AgentProcess,
+    session_id: SessionId,
+    /// Model the session runs on, if the agent reports one
+    pub model_id: Option<String>,
+    /// Models the agent offers
+    pub available_models: Vec<String>,
+}
+
+impl TerminalSession {
+    /// Spawn `provider` in `notes_directory` and open a session on
+    /// `model_id`, or the agent's default model
+    pub(crate) async fn open(
+        provider: &AgentProvider,
+        notes_directory: &Path,
+        spawn_config: &SpawnConfig,
+        model_id: Option<&str>,
+    ) -> anyhow::Result<Self> {
+        // Gemini CLI takes its model as a flag at spawn time
+        let spawn_model = match provider {
+            AgentProvider::ClaudeCode => None,
+            AgentProvider::GeminiCli => model_id,
+        };
+        let child =
+            spawn_agent_subprocess(provider, notes_directory, spawn_config, spawn_model).await?;
+        let client = TerminalClient::new(
+            notes_directory.to_path_buf(),
+            spawn_config.read_roots.clone(),
+        );
+        let (connection, process) = connect_agent(child, Arc::new(client), "repl-acp")?;
+
+        let opened = async {
+            initialize_with_timeout(
+                &connection,
+                Implementation::new("thoughttree-repl", env!("CARGO_PKG_VERSION")),
+                ClientCapabilities::default(),
+            )
+            .await?;
+            connection
+                .new_session(NewSessionRequest::new(notes_directory))
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to create session: {e:?}"))
+        }
         .await;
+        let session_response = match opened {
+            Ok(response) => response,
+            Err(e) => {
+                drop(connection);
+                let stderr_tail = process.stderr_tail.clone();
+                process.shutdown("repl-acp").await;
+                return Err(anyhow::anyhow!(with_stderr_tail(
+                    e.to_string(),
+                    &stderr_lines(&stderr_tail)
+                )));
+            }
+        };
 
-    if let Err(e) = prompt_result {
-        warn!("Summary prompt failed: {:?}", e);
+        let (current_model, available_models) = match &session_response.models {
+            Some(models) => (
+                Some(models.current_model_id.0.to_string()),
+                models
+                    .available_models
+                    .iter()
+                    .map(|m| m.model_id.0.to_string())
+                    .collect(),
+            ),
+            None => (spawn_model.map(String::from), Vec::new()),
+        };
+        let mut session = Self {
+            connection,
+            process,
+            session_id: session_response.session_id,
+            model_id: current_model,
+            available_models,
+        };
+        if let (AgentProvider::ClaudeCode, Some(model_id)) = (provider, model_id) {
+            session.set_model(model_id).await?;
+        }
+        Ok(session)
     }
 
-    // Clean up
-    drop(connection);
-    process.shutdown("summary-acp").await;
+    /// Switch the open session to another model, keeping the conversation
+    pub(crate) async fn set_model(&mut self, model_id: &str) -> anyhow::Result<()> {
+        self.connection
+            .set_session_model(SetSessionModelRequest::new(
+                self.session_id.clone(),
+                agent_client_protocol::ModelId::new(model_id.to_string()),
+            ))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to switch to model {model_id}: {e:?}"))?;
+        self.model_id = Some(model_id.to_string());
+        Ok(())
+    }
 
-    // Get result and clean it up
-    let result = response_text.lock().await.trim().to_string();
+    /// Send one prompt; the answer is printed while it streams. A crash of
+    /// the agent ends the prompt with an error instead of a stall.
+    pub(crate) async fn prompt(&mut self, text: String) -> anyhow::Result<StopReason> {
+        let request = PromptRequest::new(
+            self.session_id.clone(),
+            vec![ContentBlock::Text(TextContent::new(text))],
+        );
+        let response = tokio::select! {
+            response = self.connection.prompt(request) => {
+                response.map_err(|e| anyhow::anyhow!("Failed to send prompt: {e:?}"))?
+            }
+            status = self.process.child.wait() => {
+                let status = status.map_or_else(|e| e.to_string(), |s| s.to_string());
+                return Err(anyhow::anyhow!(with_stderr_tail(
+                    format!("Agent exited mid-prompt ({status})"),
+                    &stderr_lines(&self.process.stderr_tail)
+                )));
+            }
+        };
+        Ok(serde_json::to_value(&response.stop_reason)
+            .ok()
+            .as_ref()
+            .and_then(serde_json::Value::as_str)
+            .map_or(StopReason::Error, StopReason::from_acp_name))
+    }
 
-    // Remove any quotes the model might have added
-    let result = result.trim_matches('"').trim_matches('\'').trim();
+    pub(crate) async fn close(self) {
+        drop(self.connection);
+        self.process.shutdown("repl-acp").await;
+    }
+}
 
-    // Truncate if too long (aim for ~40 chars max)
-    if result.len() > 40 {
-        Ok(format!("{}…", &result[..37]))
-    } else {
-        Ok(result.to_string())
+#[cfg(test)]
+mod tests {
+    use super::{
+        bulk_spawn_model, select_bulk_model, slash_command_prompt, with_stderr_tail,
+        GEMINI_BULK_MODEL,
+    };
+    use crate::backend::types::AgentProvider;
+
+    #[test]
+    fn test_select_bulk_model_prefers_cheapest_known_model() {
+        let available = ["claude-opus-4-5", "claude-sonnet-4-5", "claude-haiku-4-5"];
+        assert_eq!(
+            select_bulk_model(&available, None),
+            Some("claude-haiku-4-5".to_string())
+        );
+
+        let gemini = ["gemini-2.5-pro", "gemini-2.5-flash"];
+        assert_eq!(
+            select_bulk_model(&gemini, None),
+            Some("gemini-2.5-flash".to_string())
+        );
+    }
+
+    #[test]
+    fn test_select_bulk_model_honors_available_override() {
+        let available = ["claude-sonnet-4-5", "claude-haiku-4-5"];
+        assert_eq!(
+            select_bulk_model(&available, Some("claude-sonnet-4-5")),
+            Some("claude-sonnet-4-5".to_string())
+        );
+        assert_eq!(
+            select_bulk_model(&available, Some("claude-opus-4-5")),
+            Some("claude-haiku-4-5".to_string())
+        );
+    }
+
+    #[test]
+    fn test_bulk_spawn_model_only_for_gemini() {
+        assert_eq!(
+            bulk_spawn_model(&AgentProvider::ClaudeCode, Some("x")),
+            None
+        );
+        assert_eq!(
+            bulk_spawn_model(&AgentProvider::GeminiCli, None).as_deref(),
+            Some(GEMINI_BULK_MODEL)
+        );
+        assert_eq!(
+            bulk_spawn_model(&AgentProvider::GeminiCli, Some("gemini-2.5-flash-lite")).as_deref(),
+            Some("gemini-2.5-flash-lite")
+        );
+    }
+
+    #[test]
+    fn test_select_bulk_model_keeps_default_for_unknown_models() {
+        assert_eq!(select_bulk_model(&["default", "custom"], None), None);
+    }
+
+    #[test]
+    fn test_slash_command_prompt() {
+        assert_eq!(slash_command_prompt("compact", None).unwrap(), "/compact");
+        assert_eq!(
+            slash_command_prompt("/memory", Some("  remember this ")).unwrap(),
+            "/memory remember this"
+        );
+        assert!(slash_command_prompt("", None).is_err());
+        assert!(slash_command_prompt("compact\n/other", None).is_err());
+    }
+
+    #[test]
+    fn test_with_stderr_tail() {
+        assert_eq!(
+            with_stderr_tail("Failed to initialize".to_string(), &[]),
+            "Failed to initialize"
+        );
+        let lines = [
+            "Error: Cannot find module 'zod'".to_string(),
+            "Node.js v22".to_string(),
+        ];
+        assert_eq!(
+            with_stderr_tail("Failed to initialize".to_string(), &lines),
+            "Failed to initialize\nAgent stderr:\nError: Cannot find module 'zod'\nNode.js v22"
+        );
     }
 }
@@ -0,0 +1,246 @@
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+
+use tokio::process::Command;
+
+/// Most variables kept in the passthrough map
+const MAX_AGENT_ENV_VARS: usize = 64;
+
+/// Longest accepted variable value, in bytes
+const MAX_AGENT_ENV_VALUE_BYTES: usize = 8192;
+
+/// Prefixes of variables that change how the dynamic loader starts a
+/// process. They would let a setting inject code into every agent, so they
+/// can't be passed through.
+const LOADER_PREFIXES: [&str; 2] = ["LD_", "DYLD_"];
+
+/// Variables the app sets itself when spawning an agent
+const RESERVED_NAMES: [&str; 1] = ["CLAUDE_CODE_EXECUTABLE"];
+
+/// Parent environment variables agents inherit unless the allowlist is
+/// changed: what shells, Node and the CLIs need to run, locate their config
+/// and credentials, and reach the network. A trailing `*` matches any suffix.
+pub(crate) const DEFAULT_ENV_ALLOWLIST: &[&str] = &[
+    // Process basics
+    "HOME",
+    "USER",
+    "LOGNAME",
+    "SHELL",
+    "PATH",
+    "TMPDIR",
+    "TEMP",
+    "TMP",
+    "LANG",
+    "LC_*",
+    "TERM",
+    "TZ",
+    "XDG_*",
+    "SSH_AUTH_SOCK",
+    // Windows
+    "SYSTEMROOT",
+    "SYSTEMDRIVE",
+    "WINDIR",
+    "COMSPEC",
+    "PATHEXT",
+    "USERPROFILE",
+    "APPDATA",
+    "LOCALAPPDATA",
+    "PROGRAMDATA",
+    "PROGRAMFILES",
+    // Network
+    "HTTP_PROXY",
+    "HTTPS_PROXY",
+    "NO_PROXY",
+    "ALL_PROXY",
+    "NODE_EXTRA_CA_CERTS",
+    "SSL_CERT_FILE",
+    "SSL_CERT_DIR",
+    // Providers
+    "ANTHROPIC_*",
+    "CLAUDE_*",
+    "GEMINI_*",
+    "GOOGLE_API_KEY",
+    "GOOGLE_APPLICATION_CREDENTIALS",
+    "GOOGLE_CLOUD_*",
+    "GOOGLE_GENAI_*",
+    "CLOUDSDK_CONFIG",
+    "AWS_PROFILE",
+    "AWS_REGION",
+    "AWS_DEFAULT_REGION",
+];
+
+/// Longest accepted allowlist
+const MAX_ENV_ALLOWLIST_ENTRIES: usize = 256;
+
+/// Check one allowlist entry: a variable name, optionally ending in `*`
+pub(crate) fn validate_allowlist_entry(entry: &str) -> Result<(), String> {
+    let name = entry.strip_suffix('*').unwrap_or(entry);
+    let valid = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(format!("Invalid allowlist entry: {entry:?}"));
+    }
+    Ok(())
+}
+
+pub(crate) fn validate_allowlist(allowlist: &[String]) -> Result<(), String> {
+    if allowlist.len() > MAX_ENV_ALLOWLIST_ENTRIES {
+        return Err(format!(
+            "The allowlist can hold at most {MAX_ENV_ALLOWLIST_ENTRIES} entries"
+        ));
+    }
+    allowlist
+        .iter()
+        .try_for_each(|entry| validate_allowlist_entry(entry))
+}
+
+pub(crate) fn default_allowlist() -> Vec<String> {
+    DEFAULT_ENV_ALLOWLIST
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+/// Whether `name` matches an allowlist entry. Case is ignored, since Windows
+/// variable names are case-insensitive and proxies are often lowercase.
+fn is_allowed(name: &OsStr, allowlist: &[String]) -> bool {
+    let Some(name) = name.to_str() else {
+        return false;
+    };
+    let name = name.to_ascii_uppercase();
+    allowlist.iter().any(|entry| {
+        let entry = entry.to_ascii_uppercase();
+        match entry.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => name == entry,
+        }
+    })
+}
+
+/// Extra environment variables for spawned agents, e.g. `HTTPS_PROXY` or
+/// `ANTHROPIC_BASE_URL`, by name
+pub(crate) type AgentEnv = BTreeMap<String, String>;
+
+fn is_loader_variable(name: &OsStr) -> bool {
+    let name = name.to_string_lossy().to_ascii_uppercase();
+    LOADER_PREFIXES
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+}
+
+/// Reject names the shell couldn't export, loader variables, the app's own
+/// variables and values that can't be passed to a process
+pub(crate) fn validate_agent_env_var(name: &str, value: &str) -> Result<(), String> {
+    let mut chars = name.chars();
+    let valid_name = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid_name {
+        return Err(format!("Invalid environment variable name: {name:?}"));
+    }
+    if is_loader_variable(OsStr::new(name)) {
+        return Err(format!("{name} can't be passed to agents"));
+    }
+    let upper = name.to_ascii_uppercase();
+    if RESERVED_NAMES.contains(&upper.as_str()) {
+        return Err(format!("{name} is set by ThoughtTree"));
+    }
+    if value.contains('\0') {
+        return Err(format!("Value of {name} contains a NUL byte"));
+    }
+    if value.len() > MAX_AGENT_ENV_VALUE_BYTES {
+        return Err(format!(
+            "Value of {name} is longer than {MAX_AGENT_ENV_VALUE_BYTES} bytes"
+        ));
+    }
+    Ok(())
+}
+
+/// Add or replace `name` in `env` after validating it
+pub(crate) fn set_agent_env_var(env: &mut AgentEnv, name: &str, value: &str) -> Result<(), String> {
+    validate_agent_env_var(name, value)?;
+    if !env.contains_key(name) && env.len() >= MAX_AGENT_ENV_VARS {
+        return Err(format!(
+            "At most {MAX_AGENT_ENV_VARS} environment variables can be passed to agents"
+        ));
+    }
+    env.insert(name.to_string(), value.to_string());
+    Ok(())
+}
+
+/// Give an agent command a clean environment: only the parent's variables on
+/// `allowlist`, so stray secrets in the desktop session don't reach agent
+/// tools, plus the passthrough variables. Passthrough entries that no
+/// longer validate (e.g. hand-edited settings) are skipped. Only names are
+/// logged, since values are often credentials.
+pub(crate) fn apply_agent_env(command: &mut Command, env: &AgentEnv, allowlist: &[String]) {
+    command.env_clear();
+    for (name, value) in std::env::vars_os() {
+        if is_allowed(&name, allowlist) && !is_loader_variable(&name) {
+            command.env(name, value);
+        }
+    }
+    for (name, value) in env {
+        match validate_agent_env_var(name, value) {
+            Ok(()) => {
+                command.env(name, value);
+            }
+            Err(e) => tracing::warn!("Skipping agent environment variable: {}", e),
+        }
+    }
+    if !env.is_empty() {
+        tracing::info!(
+            "Passing environment variables to agent: {:?}",
+            env.keys().collect::<Vec<_>>()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_agent_env_var() {
+        assert!(validate_agent_env_var("HTTPS_PROXY", "http://proxy:8080").is_ok());
+        assert!(validate_agent_env_var("_private1", "").is_ok());
+        assert!(validate_agent_env_var("1ABC", "x").is_err());
+        assert!(validate_agent_env_var("A-B", "x").is_err());
+        assert!(validate_agent_env_var("", "x").is_err());
+        assert!(validate_agent_env_var("LD_PRELOAD", "/tmp/x.so").is_err());
+        assert!(validate_agent_env_var("dyld_insert_libraries", "x").is_err());
+        assert!(validate_agent_env_var("CLAUDE_CODE_EXECUTABLE", "/bin/sh").is_err());
+        assert!(validate_agent_env_var("A", "x\0y").is_err());
+    }
+
+    #[test]
+    fn test_allowlist_matching() {
+        let allowlist = vec!["PATH".to_string(), "LC_*".to_string()];
+        assert!(is_allowed(OsStr::new("PATH"), &allowlist));
+        assert!(is_allowed(OsStr::new("Path"), &allowlist));
+        assert!(is_allowed(OsStr::new("LC_ALL"), &allowlist));
+        assert!(!is_allowed(OsStr::new("PATHX"), &allowlist));
+        assert!(!is_allowed(
+            OsStr::new("AWS_SECRET_ACCESS_KEY"),
+            &default_allowlist()
+        ));
+        assert!(is_allowed(OsStr::new("https_proxy"), &default_allowlist()));
+
+        assert!(validate_allowlist(&default_allowlist()).is_ok());
+        assert!(validate_allowlist(&["MY_VAR".to_string()]).is_ok());
+        assert!(validate_allowlist(&["*".to_string()]).is_err());
+        assert!(validate_allowlist(&["A*B".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_set_agent_env_var_caps_entries() {
+        let mut env = AgentEnv::new();
+        for i in 0..MAX_AGENT_ENV_VARS {
+            set_agent_env_var(&mut env, &format!("VAR_{i}"), "1").unwrap();
+        }
+        assert!(set_agent_env_var(&mut env, "ONE_MORE", "1").is_err());
+        // Replacing an existing variable is still allowed
+        set_agent_env_var(&mut env, "VAR_0", "2").unwrap();
+        assert_eq!(env["VAR_0"], "2");
+    }
+}
@@ -0,0 +1,98 @@
+use crate::backend::types::Message;
+
+/// Most alternatives generated in one `generate_alternatives` call
+pub(crate) const MAX_ALTERNATIVES: usize = 8;
+
+/// How far an alternative should stray from the obvious answer. Agents don't
+/// expose sampling temperature, so the hint becomes an instruction.
+fn divergence_note(temperature_hint: Option<f32>) -> &'static str {
+    match temperature_hint {
+        None => "",
+        Some(hint) if hint < 0.34 => " Stay close to the most likely answer.",
+        Some(hint) if hint < 0.67 => " Feel free to approach it from a different angle.",
+        Some(_) => " Take a distinctly different approach than the obvious answer.",
+    }
+}
+
+pub(crate) fn validate_alternatives(
+    count: usize,
+    temperature_hint: Option<f32>,
+) -> Result<(), String> {
+    if !(2..=MAX_ALTERNATIVES).contains(&count) {
+        return Err(format!(
+            "Alternative count must be between 2 and {MAX_ALTERNATIVES} (got {count})"
+        ));
+    }
+    if let Some(hint) = temperature_hint {
+        if !(0.0..=1.0).contains(&hint) {
+            return Err(format!(
+                "Temperature hint must be between 0 and 1 (got {hint})"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// `messages` with a note on the last user message telling the agent this
+/// is alternative `index` (from 1) of `count`, so answers don't all start
+/// the same way
+pub(crate) fn alternative_messages(
+    messages: &[Message],
+    index: usize,
+    count: usize,
+    temperature_hint: Option<f32>,
+) -> Vec<Message> {
+    let mut messages = messages.to_vec();
+    if let Some(last) = messages.iter_mut().rev().find(|m| m.role == "user") {
+        last.content = format!(
+            "{}\n\n(This is alternative answer {index} of {count}.{} Don't mention this note.)",
+            last.content,
+            divergence_note(temperature_hint)
+        );
+    }
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: &str, content: &str) -> Message {
+        serde_json::from_value(serde_json::json!({
+            "role": role,
+            "content": content,
+            "images": null,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_alternative_messages_annotate_last_user_message() {
+        let messages = [
+            message("user", "First question"),
+            message("assistant", "An answer"),
+            message("user", "Name the project"),
+        ];
+        let alternative = alternative_messages(&messages, 2, 3, Some(0.9));
+        assert_eq!(alternative[0].content, "First question");
+        assert_eq!(alternative[1].content, "An answer");
+        assert!(alternative[2]
+            .content
+            .starts_with("Name the project\n\n(This is alternative answer 2 of 3."));
+        assert!(alternative[2].content.contains("distinctly different"));
+
+        let plain = alternative_messages(&messages, 1, 2, None);
+        assert!(plain[2]
+            .content
+            .ends_with("1 of 2. Don't mention this note.)"));
+    }
+
+    #[test]
+    fn test_validate_alternatives() {
+        assert!(validate_alternatives(3, Some(0.5)).is_ok());
+        assert!(validate_alternatives(1, None).is_err());
+        assert!(validate_alternatives(MAX_ALTERNATIVES + 1, None).is_err());
+        assert!(validate_alternatives(2, Some(1.5)).is_err());
+        assert!(validate_alternatives(2, Some(f32::NAN)).is_err());
+    }
+}
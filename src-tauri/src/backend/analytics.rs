@@ -0,0 +1,314 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{Days, Local, NaiveDate};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::backend::config;
+use crate::backend::types::AgentProvider;
+
+const ANALYTICS_DB_FILE: &str = "analytics.sqlite3";
+
+/// Only counts and durations are stored; never prompt or answer text
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS generations (
+    id INTEGER PRIMARY KEY,
+    timestamp TEXT NOT NULL,
+    day TEXT NOT NULL,
+    provider TEXT NOT NULL,
+    model_id TEXT,
+    duration_ms INTEGER NOT NULL,
+    first_chunk_ms INTEGER,
+    succeeded INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS generations_day ON generations(day);
+";
+
+/// One finished or failed generation
+#[derive(Clone, Debug)]
+pub(crate) struct GenerationRecord {
+    pub provider: AgentProvider,
+    pub model_id: Option<String>,
+    pub duration_ms: u64,
+    pub first_chunk_ms: Option<u64>,
+    pub succeeded: bool,
+}
+
+/// Period a usage report covers, ending today
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum UsageRange {
+    Week,
+    Month,
+    Year,
+    All,
+}
+
+impl UsageRange {
+    /// First day in the range, `None` for all time
+    fn first_day(self, today: NaiveDate) -> Option<NaiveDate> {
+        let days = match self {
+            UsageRange::Week => 7,
+            UsageRange::Month => 30,
+            UsageRange::Year => 365,
+            UsageRange::All => return None,
+        };
+        today.checked_sub_days(Days::new(days - 1))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct DayUsage {
+    /// `YYYY-MM-DD`, local time
+    pub day: String,
+    pub prompts: u64,
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct UsageCount {
+    pub name: String,
+    pub prompts: u64,
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub(crate) struct UsageReport {
+    pub total_prompts: u64,
+    pub failed_prompts: u64,
+    /// Days with at least one prompt, oldest first
+    pub days: Vec<DayUsage>,
+    /// Most used first
+    pub providers: Vec<UsageCount>,
+    /// Most used first; the agent's default model is `"default"`
+    pub models: Vec<UsageCount>,
+    pub mean_duration_ms: Option<f64>,
+    pub mean_first_chunk_ms: Option<f64>,
+}
+
+/// Location of the analytics database in the app data dir
+pub(crate) fn analytics_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {e}"))?;
+    Ok(dir.join(ANALYTICS_DB_FILE))
+}
+
+/// Open (and create if needed) the analytics database
+pub(crate) fn open_analytics(path: &Path) -> Result<Connection, String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create analytics directory: {e}"))?;
+    }
+    let conn =
+        Connection::open(path).map_err(|e| format!("Failed to open analytics database: {e}"))?;
+    init_schema(&conn)?;
+    Ok(conn)
+}
+
+fn init_schema(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(SCHEMA)
+        .map_err(|e| format!("Failed to create analytics tables: {e}"))
+}
+
+fn sql_error(e: rusqlite::Error) -> String {
+    format!("Analytics query failed: {e}")
+}
+
+pub(crate) fn record_generation(
+    conn: &Connection,
+    record: &GenerationRecord,
+) -> Result<(), String> {
+    let now = Local::now();
+    conn.execute(
+        "INSERT INTO generations
+             (timestamp, day, provider, model_id, duration_ms, first_chunk_ms, succeeded)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            now.to_rfc3339(),
+            now.format("%Y-%m-%d").to_string(),
+            record.provider.display_name(),
+            record.model_id,
+            record.duration_ms as i64,
+            record.first_chunk_ms.map(|ms| ms as i64),
+            record.succeeded,
+        ],
+    )
+    .map_err(sql_error)?;
+    Ok(())
+}
+
+/// Counts of `column` over the range, most used first
+fn usage_counts(conn: &Connection, column: &str, since: &str) -> Result<Vec<UsageCount>, String> {
+    // `column` is one of two constants below, never user input
+    let mut statement = conn
+        .prepare(&format!(
+            "SELECT COALESCE({column}, 'default'), COUNT(*) FROM generations
+             WHERE day >= ?1 GROUP BY 1 ORDER BY 2 DESC, 1"
+        ))
+        .map_err(sql_error)?;
+    let rows = statement
+        .query_map([since], |row| {
+            Ok(UsageCount {
+                name: row.get(0)?,
+                prompts: row.get::<_, i64>(1)? as u64,
+            })
+        })
+        .map_err(sql_error)?;
+    rows.collect::<Result<_, _>>().map_err(sql_error)
+}
+
+/// Aggregate the generations in `range`, ending `today`
+pub(crate) fn usage_report(
+    conn: &Connection,
+    range: UsageRange,
+    today: NaiveDate,
+) -> Result<UsageReport, String> {
+    // Days are `YYYY-MM-DD`, so string order is date order
+    let since = range
+        .first_day(today)
+        .map(|day| day.format("%Y-%m-%d").to_string())
+        .unwrap_or_default();
+
+    let (total, failed, mean_duration, mean_first_chunk) = conn
+        .query_row(
+            "SELECT COUNT(*), COALESCE(SUM(succeeded = 0), 0), AVG(duration_ms),
+                    AVG(first_chunk_ms)
+             FROM generations WHERE day >= ?1",
+            [&since],
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, Option<f64>>(2)?,
+                    row.get::<_, Option<f64>>(3)?,
+                ))
+            },
+        )
+        .map_err(sql_error)?;
+
+    let mut statement = conn
+        .prepare("SELECT day, COUNT(*) FROM generations WHERE day >= ?1 GROUP BY day ORDER BY day")
+        .map_err(sql_error)?;
+    let days = statement
+        .query_map([&since], |row| {
+            Ok(DayUsage {
+                day: row.get(0)?,
+                prompts: row.get::<_, i64>(1)? as u64,
+            })
+        })
+        .map_err(sql_error)?
+        .collect::<Result<_, _>>()
+        .map_err(sql_error)?;
+
+    Ok(UsageReport {
+        total_prompts: total as u64,
+        failed_prompts: failed as u64,
+        days,
+        providers: usage_counts(conn, "provider", &since)?,
+        models: usage_counts(conn, "model_id", &since)?,
+        mean_duration_ms: mean_duration,
+        mean_first_chunk_ms: mean_first_chunk,
+    })
+}
+
+/// Record a generation if the user opted in. Failures are logged, never
+/// surfaced: analytics must not break prompting.
+pub(crate) fn record(app: &AppHandle, generation: &GenerationRecord) {
+    match config::get_analytics_enabled(app) {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(e) => {
+            tracing::warn!("Failed to read analytics setting: {}", e);
+            return;
+        }
+    }
+    let result = analytics_db_path(app)
+        .and_then(|path| open_analytics(&path))
+        .and_then(|conn| record_generation(&conn, generation));
+    if let Err(e) = result {
+        tracing::warn!("Failed to record analytics: {}", e);
+    }
+}
+
+/// Delete everything recorded so far
+pub(crate) fn clear_usage(conn: &Connection) -> Result<(), String> {
+    conn.execute("DELETE FROM generations", [])
+        .map_err(sql_error)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(
+        provider: AgentProvider,
+        model_id: Option<&str>,
+        succeeded: bool,
+    ) -> GenerationRecord {
+        GenerationRecord {
+            provider,
+            model_id: model_id.map(String::from),
+            duration_ms: 2000,
+            first_chunk_ms: succeeded.then_some(500),
+            succeeded,
+        }
+    }
+
+    #[test]
+    fn test_usage_report_counts_providers_models_and_failures() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        record_generation(
+            &conn,
+            &record(AgentProvider::ClaudeCode, Some("opus"), true),
+        )
+        .unwrap();
+        record_generation(&conn, &record(AgentProvider::ClaudeCode, None, true)).unwrap();
+        record_generation(&conn, &record(AgentProvider::GeminiCli, None, false)).unwrap();
+        conn.execute(
+            "INSERT INTO generations (timestamp, day, provider, duration_ms, succeeded)
+             VALUES ('', '2000-01-01', 'Claude Code', 100, 1)",
+            [],
+        )
+        .unwrap();
+
+        let today = Local::now().date_naive();
+        let report = usage_report(&conn, UsageRange::Week, today).unwrap();
+        assert_eq!(report.total_prompts, 3);
+        assert_eq!(report.failed_prompts, 1);
+        assert_eq!(report.days.len(), 1);
+        assert_eq!(report.days[0].prompts, 3);
+        assert_eq!(
+            report.providers[0],
+            UsageCount {
+                name: "Claude Code".to_string(),
+                prompts: 2
+            }
+        );
+        assert_eq!(report.models[0].name, "default");
+        assert_eq!(report.models[0].prompts, 2);
+        assert_eq!(report.mean_duration_ms, Some(2000.0));
+        assert_eq!(report.mean_first_chunk_ms, Some(500.0));
+
+        let all = usage_report(&conn, UsageRange::All, today).unwrap();
+        assert_eq!(all.total_prompts, 4);
+        assert_eq!(all.days[0].day, "2000-01-01");
+
+        clear_usage(&conn).unwrap();
+        let empty = usage_report(&conn, UsageRange::All, today).unwrap();
+        assert_eq!(empty.total_prompts, 0);
+        assert_eq!(empty.mean_duration_ms, None);
+    }
+
+    #[test]
+    fn test_usage_range_first_day() {
+        let today = NaiveDate::from_ymd_opt(2025, 3, 7).unwrap();
+        assert_eq!(
+            UsageRange::Week.first_day(today),
+            NaiveDate::from_ymd_opt(2025, 3, 1)
+        );
+        assert_eq!(UsageRange::All.first_day(today), None);
+    }
+}
@@ -0,0 +1,333 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use crate::backend::note_edit::write_atomic;
+use crate::backend::project_file::ProjectFile;
+
+/// Folder beside an export that holds its extracted images
+pub(crate) const ASSETS_DIR: &str = "assets";
+
+/// Extension of the folder beside a project that holds its node images
+const PROJECT_ASSETS_EXTENSION: &str = "assets";
+
+/// Extensions `extension` maps image types to
+const ASSET_EXTENSIONS: [&str; 7] = ["png", "jpg", "gif", "webp", "avif", "bmp", "svg"];
+
+const DATA_URI_PREFIX: &str = "data:image/";
+const BASE64_MARKER: &str = ";base64,";
+
+/// An image pulled out of an export's text
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Asset {
+    /// `<content hash>.<ext>`, so identical images share one file
+    pub file_name: String,
+    pub bytes: Vec<u8>,
+}
+
+/// File extension for an image MIME subtype; other subtypes aren't extracted
+fn extension(subtype: &str) -> Option<&'static str> {
+    match subtype.to_ascii_lowercase().as_str() {
+        "png" => Some("png"),
+        "jpeg" | "jpg" => Some("jpg"),
+        "gif" => Some("gif"),
+        "webp" => Some("webp"),
+        "avif" => Some("avif"),
+        "bmp" => Some("bmp"),
+        "svg+xml" => Some("svg"),
+        _ => None,
+    }
+}
+
+/// Decode the data URI at the start of `uri`. Returns its length in bytes,
+/// the file extension and the image bytes.
+fn decode_data_uri(uri: &str) -> Option<(usize, &'static str, Vec<u8>)> {
+    let after_prefix = &uri[DATA_URI_PREFIX.len()..];
+    let subtype_len = after_prefix.find(';')?;
+    let extension = extension(&after_prefix[..subtype_len])?;
+    let payload = after_prefix[subtype_len..].strip_prefix(BASE64_MARKER)?;
+    let payload_len = payload
+        .bytes()
+        .take_while(|b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'/' | b'='))
+        .count();
+    let bytes = STANDARD
+        .decode(&payload[..payload_len])
+        .ok()
+        .filter(|bytes| !bytes.is_empty())?;
+    let len = DATA_URI_PREFIX.len() + subtype_len + BASE64_MARKER.len() + payload_len;
+    Some((len, extension, bytes))
+}
+
+/// Whether `name` has the form of an asset file name (`<hash>.<ext>`), so a
+/// reference in a project can't point outside its assets folder
+fn is_asset_name(name: &str) -> bool {
+    let Some((hash, extension)) = name.split_once('.') else {
+        return false;
+    };
+    hash.len() == 16
+        && hash.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+        && ASSET_EXTENSIONS.contains(&extension)
+}
+
+fn asset_name(bytes: &[u8], extension: &str) -> String {
+    let hash = Sha256::digest(bytes);
+    let mut name = String::with_capacity(20);
+    for byte in &hash[..8] {
+        let _ = write!(name, "{byte:02x}");
+    }
+    format!("{name}.{extension}")
+}
+
+/// Replace each base64 `data:image/...` URI in `text` with a relative
+/// `assets/<hash>.<ext>` path and return the decoded images, one per
+/// distinct image. URIs that don't decode are left as they are.
+pub(crate) fn extract_assets(text: &str) -> (String, Vec<Asset>) {
+    let mut out = String::with_capacity(text.len());
+    let mut assets = Vec::new();
+    let mut seen = HashSet::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(DATA_URI_PREFIX) {
+        out.push_str(&rest[..start]);
+        let uri = &rest[start..];
+        match decode_data_uri(uri) {
+            Some((len, extension, bytes)) => {
+                let file_name = asset_name(&bytes, extension);
+                let _ = write!(out, "{ASSETS_DIR}/{file_name}");
+                if seen.insert(file_name.clone()) {
+                    assets.push(Asset { file_name, bytes });
+                }
+                rest = &uri[len..];
+            }
+            None => {
+                out.push_str(DATA_URI_PREFIX);
+                rest = &uri[DATA_URI_PREFIX.len()..];
+            }
+        }
+    }
+    out.push_str(rest);
+    (out, assets)
+}
+
+/// Write `assets` into the `assets/` folder beside `export_path`. Names are
+/// content hashes, so files already there are left alone.
+pub(crate) fn write_assets(export_path: &Path, assets: &[Asset]) -> Result<(), String> {
+    if assets.is_empty() {
+        return Ok(());
+    }
+    let dir = export_path
+        .parent()
+        .ok_or("Export path has no parent directory")?
+        .join(ASSETS_DIR);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create assets folder: {e}"))?;
+    for asset in assets {
+        let path = dir.join(&asset.file_name);
+        if !path.exists() {
+            std::fs::write(&path, &asset.bytes)
+                .map_err(|e| format!("Failed to write {}: {e}", asset.file_name))?;
+        }
+    }
+    Ok(())
+}
+
+/// Folder holding a project's node images: `plan.thoughttree` ->
+/// `plan.assets` beside it
+pub(crate) fn project_assets_dir(project_path: &Path) -> PathBuf {
+    project_path.with_extension(PROJECT_ASSETS_EXTENSION)
+}
+
+/// Move the base64 images of the project's nodes into its assets folder,
+/// leaving `asset` references so the project file stays small. Identical
+/// images share a file. Returns how many images were moved.
+pub(crate) fn externalize_images(
+    project_path: &Path,
+    project: &mut ProjectFile,
+) -> Result<usize, String> {
+    let dir = project_assets_dir(project_path);
+    let mut moved = 0;
+    for image in project
+        .graph
+        .nodes
+        .iter_mut()
+        .flat_map(|node| node.images.iter_mut().flatten())
+    {
+        let Some(extension) = image.mime_type.strip_prefix("image/").and_then(extension) else {
+            continue;
+        };
+        let Ok(bytes) = STANDARD.decode(&image.data) else {
+            continue;
+        };
+        if bytes.is_empty() {
+            continue;
+        }
+        let file_name = asset_name(&bytes, extension);
+        let path = dir.join(&file_name);
+        if !path.exists() {
+            std::fs::create_dir_all(&dir)
+                .map_err(|e| format!("Failed to create assets folder: {e}"))?;
+            // Atomic, since a file that exists is trusted to be complete
+            write_atomic(&path, &bytes)?;
+        }
+        image.data.clear();
+        image.asset = Some(file_name);
+        moved += 1;
+    }
+    Ok(moved)
+}
+
+/// Load the images the project references from its assets folder back into
+/// its nodes. References that are malformed, missing or lead outside the
+/// folder are left as they are. Returns how many images were loaded.
+pub(crate) fn inline_images(project_path: &Path, project: &mut ProjectFile) -> usize {
+    let Ok(dir) = std::fs::canonicalize(project_assets_dir(project_path)) else {
+        return 0;
+    };
+    let mut loaded = 0;
+    for image in project
+        .graph
+        .nodes
+        .iter_mut()
+        .flat_map(|node| node.images.iter_mut().flatten())
+    {
+        let Some(name) = image.asset.as_deref() else {
+            continue;
+        };
+        if !is_asset_name(name) {
+            warn!("Ignoring invalid image asset reference {:?}", name);
+            continue;
+        }
+        // Assets could have been replaced by symlinks
+        let bytes = std::fs::canonicalize(dir.join(name))
+            .map_err(|e| e.to_string())
+            .and_then(|path| {
+                if path.starts_with(&dir) {
+                    std::fs::read(&path).map_err(|e| e.to_string())
+                } else {
+                    Err("outside the assets folder".to_string())
+                }
+            });
+        match bytes {
+            Ok(bytes) => {
+                image.data = STANDARD.encode(bytes);
+                image.asset = None;
+                loaded += 1;
+            }
+            Err(e) => warn!("Failed to load image asset {}: {}", name, e),
+        }
+    }
+    loaded
+}
+
+/// Project JSON `data` to save at `project_path`, with its images moved to
+/// the assets folder
+pub(crate) fn externalize_project_data(
+    project_path: &Path,
+    data: String,
+) -> Result<String, String> {
+    if !data.contains("\"images\"") {
+        return Ok(data);
+    }
+    let mut project = ProjectFile::parse(&data)?;
+    if externalize_images(project_path, &mut project)? == 0 {
+        return Ok(data);
+    }
+    serde_json::to_string(&project.to_value()?)
+        .map_err(|e| format!("Failed to serialize project: {e}"))
+}
+
+/// Project JSON `data` read from `project_path`, with the images it keeps in
+/// the assets folder inlined again
+pub(crate) fn inline_project_data(project_path: &Path, data: String) -> Result<String, String> {
+    if !data.contains("\"asset\"") {
+        return Ok(data);
+    }
+    let mut project = ProjectFile::parse(&data)?;
+    if inline_images(project_path, &mut project) == 0 {
+        return Ok(data);
+    }
+    serde_json::to_string(&project.to_value()?)
+        .map_err(|e| format!("Failed to serialize project: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_util::TempDir;
+
+    #[test]
+    fn test_extract_assets_rewrites_and_dedupes() {
+        let png = STANDARD.encode(b"fake png");
+        let text = format!(
+            "![a](data:image/png;base64,{png})\n<img src=\"data:image/png;base64,{png}\">\n\
+             ![b](data:image/tiff;base64,AAAA) ![c](data:image/gif;base64,!!)"
+        );
+        let (rewritten, assets) = extract_assets(&text);
+
+        assert_eq!(assets.len(), 1);
+        assert_eq!(assets[0].bytes, b"fake png");
+        assert!(assets[0].file_name.ends_with(".png"));
+        assert_eq!(assets[0].file_name.len(), "0123456789abcdef.png".len());
+        let path = format!("{ASSETS_DIR}/{}", assets[0].file_name);
+        assert!(rewritten.starts_with(&format!("![a]({path})\n<img src=\"{path}\">\n")));
+        // Unsupported types and undecodable data stay inline
+        assert!(rewritten.contains("data:image/tiff;base64,AAAA"));
+        assert!(rewritten.contains("data:image/gif;base64,!!"));
+    }
+
+    #[test]
+    fn test_write_assets_beside_export() {
+        let dir = TempDir::new("assets");
+        let asset = Asset {
+            file_name: "abc.png".to_string(),
+            bytes: b"data".to_vec(),
+        };
+        write_assets(&dir.join("export.md"), &[asset]).unwrap();
+        assert_eq!(std::fs::read(dir.join("assets/abc.png")).unwrap(), b"data");
+    }
+
+    #[test]
+    fn test_project_images_round_trip_through_assets() {
+        let dir = TempDir::new("assets");
+        let project_path = dir.join("plan.thoughttree");
+        let png = STANDARD.encode(b"fake png");
+        let data = serde_json::json!({
+            "version": 3,
+            "graph": {
+                "version": 3,
+                "nodes": [
+                    {"id": "a", "role": "user", "content": "Look", "timestamp": 1,
+                     "images": [{"data": png, "mimeType": "image/png"},
+                                {"data": png, "mimeType": "image/png", "name": "again"}]},
+                    {"id": "b", "role": "user", "content": "Escape", "timestamp": 2,
+                     "images": [{"mimeType": "image/png", "asset": "../secret.png"}]}
+                ],
+                "edges": []
+            }
+        })
+        .to_string();
+
+        let stored = externalize_project_data(&project_path, data).unwrap();
+        assert!(!stored.contains(&png));
+        let project = ProjectFile::parse(&stored).unwrap();
+        let images = project.graph.nodes[0].images.as_ref().unwrap();
+        let file_name = images[0].asset.clone().unwrap();
+        assert_eq!(images[1].asset.as_ref(), Some(&file_name));
+        assert_eq!(
+            std::fs::read(dir.join("plan.assets").join(&file_name)).unwrap(),
+            b"fake png"
+        );
+
+        let loaded =
+            ProjectFile::parse(&inline_project_data(&project_path, stored).unwrap()).unwrap();
+        let images = loaded.graph.nodes[0].images.as_ref().unwrap();
+        assert!(images.iter().all(|i| i.data == png && i.asset.is_none()));
+        // References outside the assets folder are not followed
+        let escaped = &loaded.graph.nodes[1].images.as_ref().unwrap()[0];
+        assert!(escaped.data.is_empty());
+        assert_eq!(escaped.asset.as_deref(), Some("../secret.png"));
+    }
+}
@@ -0,0 +1,253 @@
+use std::path::Path;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+use crate::backend::types::MessageAttachment;
+
+/// Decoded size limits per attachment type
+const MAX_TEXT_ATTACHMENT_BYTES: usize = 1024 * 1024;
+const MAX_PDF_ATTACHMENT_BYTES: usize = 20 * 1024 * 1024;
+const MAX_AUDIO_ATTACHMENT_BYTES: usize = 25 * 1024 * 1024;
+
+/// Extensions sent as text when the MIME type doesn't say so
+const TEXT_EXTENSIONS: &[&str] = &[
+    "txt", "md", "markdown", "csv", "tsv", "json", "yaml", "yml", "toml", "xml", "log", "html",
+    "css", "js", "ts", "py", "rs", "sh", "sql",
+];
+
+/// How an attachment is sent to the agent
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum AttachmentKind {
+    /// UTF-8 text, embedded as a text resource (or inline text)
+    Text,
+    /// PDF, embedded as a blob resource for agents that read documents, or
+    /// else sent as its extracted text
+    Pdf,
+    /// Audio content block
+    Audio,
+}
+
+/// An attachment checked and ready to become an ACP content block
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum PreparedAttachment {
+    Text {
+        uri: String,
+        mime_type: String,
+        text: String,
+    },
+    /// Base64 data, passed through as received
+    Blob {
+        uri: String,
+        mime_type: String,
+        data: String,
+    },
+    Audio {
+        mime_type: String,
+        data: String,
+    },
+}
+
+impl AttachmentKind {
+    fn max_bytes(self) -> usize {
+        match self {
+            Self::Text => MAX_TEXT_ATTACHMENT_BYTES,
+            Self::Pdf => MAX_PDF_ATTACHMENT_BYTES,
+            Self::Audio => MAX_AUDIO_ATTACHMENT_BYTES,
+        }
+    }
+}
+
+/// Classify by MIME type, falling back to the file extension
+pub(crate) fn attachment_kind(name: &str, mime_type: &str) -> Option<AttachmentKind> {
+    let mime_type = mime_type.to_ascii_lowercase();
+    if mime_type == "application/pdf" {
+        return Some(AttachmentKind::Pdf);
+    }
+    if mime_type.starts_with("audio/") {
+        return Some(AttachmentKind::Audio);
+    }
+    if mime_type.starts_with("text/")
+        || matches!(
+            mime_type.as_str(),
+            "application/json" | "application/xml" | "application/x-yaml" | "application/toml"
+        )
+    {
+        return Some(AttachmentKind::Text);
+    }
+    let extension = Path::new(name)
+        .extension()?
+        .to_string_lossy()
+        .to_ascii_lowercase();
+    if extension == "pdf" {
+        Some(AttachmentKind::Pdf)
+    } else if TEXT_EXTENSIONS.contains(&extension.as_str()) {
+        Some(AttachmentKind::Text)
+    } else {
+        None
+    }
+}
+
+/// Resource URI naming the attachment; only the file name is kept
+fn attachment_uri(name: &str) -> String {
+    let file_name = Path::new(name)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "attachment".to_string());
+    let encoded: String = file_name
+        .bytes()
+        .map(|b| match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect();
+    format!("attachment:///{encoded}")
+}
+
+/// Check an attachment's type and size and decode what needs decoding
+pub(crate) fn prepare_attachment(
+    attachment: &MessageAttachment,
+) -> Result<PreparedAttachment, String> {
+    let name = &attachment.name;
+    let kind = attachment_kind(name, &attachment.mime_type).ok_or_else(|| {
+        format!(
+            "Unsupported attachment type for {name}: {}",
+            attachment.mime_type
+        )
+    })?;
+
+    // Base64 is 4 characters per 3 bytes; check before decoding anything
+    let size = attachment.data.len() / 4 * 3;
+    if size > kind.max_bytes() {
+        return Err(format!(
+            "Attachment {name} is too large ({size} bytes, limit {})",
+            kind.max_bytes()
+        ));
+    }
+    let bytes = STANDARD
+        .decode(&attachment.data)
+        .map_err(|e| format!("Attachment {name} is not valid base64: {e}"))?;
+
+    let uri = attachment_uri(name);
+    Ok(match kind {
+        AttachmentKind::Text => PreparedAttachment::Text {
+            uri,
+            mime_type: if attachment.mime_type.is_empty() {
+                "text/plain".to_string()
+            } else {
+                attachment.mime_type.clone()
+            },
+            text: String::from_utf8(bytes)
+                .map_err(|_| format!("Attachment {name} is not UTF-8 text"))?,
+        },
+        AttachmentKind::Pdf => PreparedAttachment::Blob {
+            uri,
+            mime_type: "application/pdf".to_string(),
+            data: attachment.data.clone(),
+        },
+        AttachmentKind::Audio => PreparedAttachment::Audio {
+            mime_type: attachment.mime_type.clone(),
+            data: attachment.data.clone(),
+        },
+    })
+}
+
+/// Text of a PDF attachment (base64 `data`), for agents without embedded
+/// context. Fails for PDFs without a text layer, such as scans, and for
+/// ones the parser can't read. Cut at the text attachment limit.
+pub(crate) fn extract_pdf_text(name: &str, data: &str) -> Result<String, String> {
+    let bytes = STANDARD
+        .decode(data)
+        .map_err(|e| format!("Attachment {name} is not valid base64: {e}"))?;
+    // The parser panics on some malformed files
+    let extracted = std::panic::catch_unwind(|| pdf_extract::extract_text_from_mem(&bytes))
+        .map_err(|_| format!("Failed to read {name}"))?
+        .map_err(|e| format!("Failed to read {name}: {e}"))?;
+
+    let text = extracted.trim();
+    if text.is_empty() {
+        return Err(format!("{name} has no text to extract"));
+    }
+    let mut end = text.len().min(MAX_TEXT_ATTACHMENT_BYTES);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    Ok(text[..end].to_string())
+}
+
+/// Inline form of a text attachment, for agents without embedded context
+pub(crate) fn inline_text(uri: &str, text: &str) -> String {
+    let name = uri.strip_prefix("attachment:///").unwrap_or(uri);
+    format!("<attachment name=\"{name}\">\n{text}\n</attachment>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attachment(name: &str, mime_type: &str, bytes: &[u8]) -> MessageAttachment {
+        MessageAttachment {
+            name: name.to_string(),
+            mime_type: mime_type.to_string(),
+            data: STANDARD.encode(bytes),
+        }
+    }
+
+    #[test]
+    fn test_attachment_kind_uses_mime_then_extension() {
+        assert_eq!(
+            attachment_kind("a.bin", "application/pdf"),
+            Some(AttachmentKind::Pdf)
+        );
+        assert_eq!(
+            attachment_kind("a", "audio/wav"),
+            Some(AttachmentKind::Audio)
+        );
+        assert_eq!(attachment_kind("data.CSV", ""), Some(AttachmentKind::Text));
+        assert_eq!(attachment_kind("a.zip", "application/zip"), None);
+    }
+
+    #[test]
+    fn test_prepare_attachment_decodes_text_and_checks_size() {
+        let prepared = prepare_attachment(&attachment("../my data.csv", "text/csv", b"a,b\n1,2"));
+        assert_eq!(
+            prepared,
+            Ok(PreparedAttachment::Text {
+                uri: "attachment:///my%20data.csv".to_string(),
+                mime_type: "text/csv".to_string(),
+                text: "a,b\n1,2".to_string(),
+            })
+        );
+
+        let big = vec![b'a'; MAX_TEXT_ATTACHMENT_BYTES + 3];
+        assert!(
+            prepare_attachment(&attachment("big.txt", "text/plain", &big))
+                .unwrap_err()
+                .contains("too large")
+        );
+        assert!(prepare_attachment(&attachment("bad.txt", "", &[0xff, 0xfe])).is_err());
+    }
+
+    #[test]
+    fn test_extract_pdf_text_for_agents_without_embedded_context() {
+        use printpdf::{BuiltinFont, Mm, PdfDocument};
+
+        let (doc, page, layer) = PdfDocument::new("Review", Mm(210.0), Mm(297.0), "Text");
+        let font = doc.add_builtin_font(BuiltinFont::Helvetica).unwrap();
+        doc.get_page(page).get_layer(layer).use_text(
+            "Quarterly review",
+            12.0,
+            Mm(20.0),
+            Mm(270.0),
+            &font,
+        );
+        let pdf = doc.save_to_bytes().unwrap();
+        let text = extract_pdf_text("review.pdf", &STANDARD.encode(pdf)).unwrap();
+        assert!(text.contains("Quarterly review"), "extracted {text:?}");
+
+        assert!(extract_pdf_text("scan.pdf", &STANDARD.encode(b"%PDF-1.4 garbage")).is_err());
+        assert!(extract_pdf_text("bad.pdf", "not base64!").is_err());
+    }
+}
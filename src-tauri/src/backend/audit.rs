@@ -0,0 +1,277 @@
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+const AUDIT_LOG_FILE: &str = "audit.jsonl";
+
+/// Size at which the audit log is rotated to `audit.1.jsonl`
+const MAX_AUDIT_LOG_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Audit log files kept, the current one included, before the oldest is deleted
+const MAX_AUDIT_LOG_FILES: usize = 3;
+
+/// Bytes read at a time when reading the log back from its end
+const TAIL_BLOCK_BYTES: u64 = 64 * 1024;
+
+/// What an audit entry records
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum AuditKind {
+    /// A permission request and the decision taken (auto or by the user)
+    Permission,
+    /// A tool call reported by the agent
+    ToolCall,
+    /// A file read served through the ACP `fs/read_text_file` capability
+    FileRead,
+    /// The agent's reasoning for a generation, recorded when it finishes
+    Thought,
+}
+
+/// One line of the append-only audit log
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct AuditEntry {
+    pub timestamp: String,
+    pub kind: AuditKind,
+    pub node_id: String,
+    pub tool_call_id: String,
+    pub tool_name: String,
+    pub paths: Vec<String>,
+    pub outcome: String,
+    /// Free text, such as the (truncated) thought of a `Thought` entry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl AuditEntry {
+    pub(crate) fn new(
+        kind: AuditKind,
+        node_id: &str,
+        tool_call_id: &str,
+        tool_name: &str,
+        paths: Vec<String>,
+        outcome: String,
+    ) -> Self {
+        Self {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            kind,
+            node_id: node_id.to_string(),
+            tool_call_id: tool_call_id.to_string(),
+            tool_name: tool_name.to_string(),
+            paths,
+            outcome,
+            detail: None,
+        }
+    }
+
+    pub(crate) fn with_detail(mut self, detail: String) -> Self {
+        self.detail = Some(detail);
+        self
+    }
+}
+
+/// Location of the audit log in the app data dir
+pub(crate) fn audit_log_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {e}"))?;
+    Ok(dir.join(AUDIT_LOG_FILE))
+}
+
+/// The `n`th rotated log next to `path`, e.g. `audit.1.jsonl`; 0 is `path`
+fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    if n == 0 {
+        path.to_path_buf()
+    } else {
+        path.with_extension(format!("{n}.jsonl"))
+    }
+}
+
+/// Shift the log files along once the current one reaches `max_bytes`,
+/// dropping the oldest
+fn rotate_if_full(path: &Path, max_bytes: u64) -> Result<(), String> {
+    let full = std::fs::metadata(path).is_ok_and(|m| m.len() >= max_bytes);
+    if !full {
+        return Ok(());
+    }
+    for n in (1..MAX_AUDIT_LOG_FILES).rev() {
+        match std::fs::rename(rotated_path(path, n - 1), rotated_path(path, n)) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Failed to rotate audit log: {e}")),
+        }
+    }
+    Ok(())
+}
+
+/// Append one entry as a JSON line
+pub(crate) fn append_entry(path: &Path, entry: &AuditEntry) -> Result<(), String> {
+    append_entry_with_limit(path, entry, MAX_AUDIT_LOG_BYTES)
+}
+
+fn append_entry_with_limit(path: &Path, entry: &AuditEntry, max_bytes: u64) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create audit log directory: {e}"))?;
+    }
+    rotate_if_full(path, max_bytes)?;
+
+    let mut line =
+        serde_json::to_string(entry).map_err(|e| format!("Failed to serialize entry: {e}"))?;
+    line.push('\n');
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open audit log: {e}"))?;
+    file.write_all(line.as_bytes())
+        .map_err(|e| format!("Failed to write audit log: {e}"))
+}
+
+/// The last `limit` lines of the file at `path`, read back from its end so
+/// a large log isn't read whole
+fn tail_lines(path: &Path, limit: usize) -> std::io::Result<Vec<String>> {
+    let mut file = File::open(path)?;
+    let mut start = file.metadata()?.len();
+    let mut tail: Vec<u8> = Vec::new();
+    let mut newlines = 0;
+    // One newline more than `limit`, since the last line ends in one
+    while start > 0 && newlines <= limit {
+        let len = TAIL_BLOCK_BYTES.min(start);
+        start -= len;
+        let mut block = vec![0; len as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut block)?;
+        newlines += block.iter().filter(|&&b| b == b'\n').count();
+        block.extend(tail);
+        tail = block;
+    }
+
+    let text = String::from_utf8_lossy(&tail);
+    let mut lines: Vec<&str> = text.lines().collect();
+    if start > 0 && !lines.is_empty() {
+        // Cut off mid-line
+        lines.remove(0);
+    }
+    let skip = lines.len().saturating_sub(limit);
+    Ok(lines[skip..].iter().map(|line| line.to_string()).collect())
+}
+
+/// Read the most recent `limit` entries, oldest first, going back through
+/// rotated logs as needed. Malformed lines are skipped.
+pub(crate) fn read_entries(path: &Path, limit: usize) -> Result<Vec<AuditEntry>, String> {
+    let mut lines: Vec<String> = Vec::new();
+    for n in 0..MAX_AUDIT_LOG_FILES {
+        if lines.len() >= limit {
+            break;
+        }
+        let mut older = match tail_lines(&rotated_path(path, n), limit - lines.len()) {
+            Ok(older) => older,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => break,
+            Err(e) => return Err(format!("Failed to read audit log: {e}")),
+        };
+        older.append(&mut lines);
+        lines = older;
+    }
+    Ok(lines
+        .iter()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+pub(crate) fn clear(path: &Path) -> Result<(), String> {
+    for n in 0..MAX_AUDIT_LOG_FILES {
+        match std::fs::remove_file(rotated_path(path, n)) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Failed to clear audit log: {e}")),
+        }
+    }
+    Ok(())
+}
+
+/// Best-effort append used from ACP clients: failures are logged, never fatal
+pub(crate) fn record(app: &AppHandle, entry: AuditEntry) {
+    let result = audit_log_path(app).and_then(|path| append_entry(&path, &entry));
+    if let Err(e) = result {
+        tracing::warn!("Failed to record audit entry: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_util::TempDir;
+
+    fn entry(tool_name: &str) -> AuditEntry {
+        AuditEntry::new(
+            AuditKind::Permission,
+            "node-1",
+            "call-1",
+            tool_name,
+            vec!["/notes/a.md".to_string()],
+            "cancelled".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_audit_log_appends_reads_tail_and_clears() {
+        let dir = TempDir::new("audit");
+        let path = dir.join(AUDIT_LOG_FILE);
+
+        assert!(read_entries(&path, 10).unwrap().is_empty());
+
+        append_entry(&path, &entry("Read")).unwrap();
+        append_entry(&path, &entry("Bash")).unwrap();
+        append_entry(&path, &entry("WebFetch")).unwrap();
+
+        let tail = read_entries(&path, 2).unwrap();
+        assert_eq!(tail.len(), 2);
+        assert_eq!(tail[0].tool_name, "Bash");
+        assert_eq!(tail[1].tool_name, "WebFetch");
+
+        clear(&path).unwrap();
+        assert!(read_entries(&path, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_audit_log_rotates_and_reads_across_files() {
+        let dir = TempDir::new("audit-rotate");
+        let path = dir.join(AUDIT_LOG_FILE);
+        let line_len = serde_json::to_string(&entry("Tool0")).unwrap().len() as u64 + 1;
+
+        // Two entries per file
+        for i in 0..9 {
+            append_entry_with_limit(&path, &entry(&format!("Tool{i}")), 2 * line_len).unwrap();
+        }
+        assert!(rotated_path(&path, MAX_AUDIT_LOG_FILES - 1).exists());
+        assert!(!rotated_path(&path, MAX_AUDIT_LOG_FILES).exists());
+
+        let all = read_entries(&path, usize::MAX).unwrap();
+        let names: Vec<&str> = all.iter().map(|e| e.tool_name.as_str()).collect();
+        assert_eq!(names, ["Tool4", "Tool5", "Tool6", "Tool7", "Tool8"]);
+        let tail = read_entries(&path, 2).unwrap();
+        assert_eq!(tail[0].tool_name, "Tool7");
+
+        clear(&path).unwrap();
+        assert!(!rotated_path(&path, 1).exists());
+    }
+
+    #[test]
+    fn test_tail_lines_reads_back_across_blocks() {
+        let dir = TempDir::new("audit-tail");
+        let path = dir.join(AUDIT_LOG_FILE);
+        let lines: Vec<String> = (0..20_000).map(|i| format!("line {i}")).collect();
+        std::fs::write(&path, lines.join("\n") + "\n").unwrap();
+
+        assert_eq!(
+            tail_lines(&path, 3).unwrap(),
+            ["line 19997", "line 19998", "line 19999"]
+        );
+        assert_eq!(tail_lines(&path, usize::MAX).unwrap(), lines);
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Quiet period after the last update before a project is written
+pub(crate) const AUTOSAVE_DEBOUNCE: Duration = Duration::from_millis(1500);
+
+/// A project that keeps changing is still written at least this often
+pub(crate) const AUTOSAVE_MAX_DELAY: Duration = Duration::from_secs(10);
+
+struct PendingSave {
+    data: String,
+    /// Bumped on every update so a stale debounce timer can tell it was superseded
+    generation: u64,
+    first_queued: Instant,
+}
+
+/// Latest unsaved data per project path. Rapid updates replace each other,
+/// so only the newest snapshot is written once the project goes quiet.
+#[derive(Default)]
+pub(crate) struct AutosaveQueue {
+    pending: Mutex<HashMap<PathBuf, PendingSave>>,
+    next_generation: AtomicU64,
+    /// Held while writing so two saves of one project can't land out of order
+    pub(crate) write_lock: tokio::sync::Mutex<()>,
+}
+
+impl AutosaveQueue {
+    fn lock(&self) -> MutexGuard<'_, HashMap<PathBuf, PendingSave>> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Queue `data` for `path`, replacing any unsaved snapshot. Returns the
+    /// generation to pass to [`Self::take_if_due`] after the debounce delay.
+    pub(crate) fn push(&self, path: &Path, data: String, now: Instant) -> u64 {
+        let generation = self.next_generation.fetch_add(1, Ordering::Relaxed);
+
+        let mut pending = self.lock();
+        let first_queued = pending
+            .get(path)
+            .map_or(now, |existing| existing.first_queued);
+        pending.insert(
+            path.to_path_buf(),
+            PendingSave {
+                data,
+                generation,
+                first_queued,
+            },
+        );
+        generation
+    }
+
+    /// Take the snapshot for `path` if it is due: nothing newer was queued
+    /// since `generation`, or it has been waiting longer than the max delay
+    pub(crate) fn take_if_due(&self, path: &Path, generation: u64, now: Instant) -> Option<String> {
+        let mut pending = self.lock();
+        let entry = pending.get(path)?;
+        let quiet = entry.generation == generation;
+        let overdue = now.duration_since(entry.first_queued) >= AUTOSAVE_MAX_DELAY;
+        if quiet || overdue {
+            pending.remove(path).map(|entry| entry.data)
+        } else {
+            None
+        }
+    }
+
+    /// Drop the unsaved snapshot for `path`, e.g. because an explicit save
+    /// just wrote newer data. Returns whether there was one.
+    pub(crate) fn discard(&self, path: &Path) -> bool {
+        self.lock().remove(path).is_some()
+    }
+
+    /// Take every unsaved snapshot, e.g. to write them before the app exits
+    pub(crate) fn drain(&self) -> Vec<(PathBuf, String)> {
+        self.lock()
+            .drain()
+            .map(|(path, entry)| (path, entry.data))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rapid_updates_coalesce_to_latest() {
+        let queue = AutosaveQueue::default();
+        let path = Path::new("/notes/a.thoughttree");
+        let start = Instant::now();
+
+        let first = queue.push(path, "v1".to_string(), start);
+        let second = queue.push(path, "v2".to_string(), start);
+
+        assert_eq!(queue.take_if_due(path, first, start), None);
+        assert_eq!(
+            queue.take_if_due(path, second, start),
+            Some("v2".to_string())
+        );
+        assert_eq!(queue.take_if_due(path, second, start), None);
+    }
+
+    #[test]
+    fn test_continuous_updates_still_save_after_max_delay() {
+        let queue = AutosaveQueue::default();
+        let path = Path::new("/notes/a.thoughttree");
+        let start = Instant::now();
+
+        let stale = queue.push(path, "v1".to_string(), start);
+        queue.push(path, "v2".to_string(), start + AUTOSAVE_MAX_DELAY);
+        assert_eq!(
+            queue.take_if_due(path, stale, start + AUTOSAVE_MAX_DELAY),
+            Some("v2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_discard_drops_pending_snapshot() {
+        let queue = AutosaveQueue::default();
+        let path = Path::new("/notes/a.thoughttree");
+        let other = Path::new("/notes/b.thoughttree");
+        let start = Instant::now();
+
+        let generation = queue.push(path, "stale".to_string(), start);
+        let other_generation = queue.push(other, "b".to_string(), start);
+        assert!(queue.discard(path));
+        assert!(!queue.discard(path));
+
+        // The debounce timer of the discarded snapshot finds nothing to write
+        assert_eq!(queue.take_if_due(path, generation, start), None);
+        assert_eq!(
+            queue.take_if_due(other, other_generation, start),
+            Some("b".to_string())
+        );
+    }
+
+    #[test]
+    fn test_drain_returns_unsaved_projects() {
+        let queue = AutosaveQueue::default();
+        queue.push(Path::new("/a"), "a".to_string(), Instant::now());
+        assert_eq!(queue.drain().len(), 1);
+        assert!(queue.drain().is_empty());
+    }
+}
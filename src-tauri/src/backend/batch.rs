@@ -0,0 +1,328 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::backend::acp::sessions::run_bulk_prompt;
+use crate::backend::note_edit::write_atomic;
+use crate::backend::project_file::{
+    decode_project_bytes, encode_project_data, is_compressed, LayoutEntry, Position, ProjectEdge,
+    ProjectFile, ProjectNode,
+};
+use crate::backend::queue::GenerationQueue;
+use crate::backend::runtime::run_localset_blocking;
+use crate::backend::types::{AgentProvider, SpawnConfig};
+
+/// Placeholder in a batch prompt template replaced by each node's content
+pub(crate) const CONTENT_PLACEHOLDER: &str = "{{content}}";
+
+/// Most nodes one `batch_generate` call may run over
+pub(crate) const MAX_BATCH_NODES: usize = 500;
+
+/// Longest prompt template accepted, in bytes
+const MAX_TEMPLATE_BYTES: usize = 16 * 1024;
+
+/// Offset of a result node from its parent, and between siblings
+const RESULT_NODE_OFFSET_Y: f64 = 250.0;
+const RESULT_NODE_SPACING_X: f64 = 400.0;
+
+/// Outcome for one node of a batch: the id of the answer node added under
+/// it, or why none was
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct BatchResult {
+    pub node_id: String,
+    pub result_node_id: Option<String>,
+    pub model_id: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Payload of `batch-progress`, emitted as each node finishes
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct BatchProgress {
+    pub completed: usize,
+    pub total: usize,
+    pub node_id: String,
+    pub error: Option<String>,
+}
+
+pub(crate) fn validate_batch_template(template: &str) -> Result<(), String> {
+    if template.trim().is_empty() {
+        return Err("Prompt template is empty".to_string());
+    }
+    if template.len() > MAX_TEMPLATE_BYTES {
+        return Err(format!(
+            "Prompt template is too long ({} bytes, limit {MAX_TEMPLATE_BYTES})",
+            template.len()
+        ));
+    }
+    if !template.contains(CONTENT_PLACEHOLDER) {
+        return Err(format!(
+            "Prompt template must contain {CONTENT_PLACEHOLDER} where the node's content goes"
+        ));
+    }
+    Ok(())
+}
+
+/// The prompt for one node: `template` with its content filled in
+pub(crate) fn render_batch_prompt(template: &str, content: &str) -> String {
+    template.replace(CONTENT_PLACEHOLDER, content)
+}
+
+/// `(node_id, content)` of each requested node, in request order with
+/// duplicates dropped. Fails on ids the project doesn't have.
+pub(crate) fn batch_inputs(
+    project: &ProjectFile,
+    node_ids: &[String],
+) -> Result<Vec<(String, String)>, String> {
+    let mut seen = HashSet::new();
+    node_ids
+        .iter()
+        .filter(|id| seen.insert(id.as_str()))
+        .map(|id| {
+            project
+                .graph
+                .node(id)
+                .map(|node| (node.id.clone(), node.content.clone()))
+                .ok_or_else(|| format!("Node not found in project: {id}"))
+        })
+        .collect()
+}
+
+/// Add `content` as an assistant node under `parent_id`, placed below it
+/// to the right of its existing children. Returns the new node's id, or
+/// `None` when the parent is gone.
+pub(crate) fn add_result_node(
+    project: &mut ProjectFile,
+    parent_id: &str,
+    content: &str,
+    provider: &str,
+    model_id: Option<&str>,
+    timestamp: i64,
+) -> Option<String> {
+    let graph = &mut project.graph;
+    graph.node(parent_id)?;
+    let parent = graph.position(parent_id).unwrap_or_default();
+    let siblings = graph.children(parent_id).count();
+    let id = uuid::Uuid::new_v4().to_string();
+
+    graph.nodes.push(ProjectNode {
+        id: id.clone(),
+        role: "assistant".to_string(),
+        content: content.to_string(),
+        timestamp,
+        content_updated_at: None,
+        summary: None,
+        summary_timestamp: None,
+        images: None,
+        provider: Some(provider.to_string()),
+        model: model_id.map(String::from),
+        pinned_provider: None,
+        pinned_model: None,
+        extra: Default::default(),
+    });
+    graph.edges.push(ProjectEdge {
+        id: uuid::Uuid::new_v4().to_string(),
+        source: parent_id.to_string(),
+        target: id.clone(),
+    });
+    graph.layout.push(LayoutEntry {
+        id: id.clone(),
+        position: Position {
+            x: parent.x + siblings as f64 * RESULT_NODE_SPACING_X,
+            y: parent.y + RESULT_NODE_OFFSET_Y,
+        },
+    });
+    Some(id)
+}
+
+pub(crate) fn read_project(project: &Path) -> Result<ProjectFile, String> {
+    let raw = std::fs::read(project).map_err(|e| format!("Failed to load project: {e}"))?;
+    ProjectFile::parse(&decode_project_bytes(&raw)?)
+}
+
+/// Add each answer under its node. The project is read again so edits
+/// saved while the batch ran are kept; fills in `result_node_id`, or an
+/// error for nodes deleted in the meantime.
+pub(crate) fn write_results(
+    project: &Path,
+    answers: &mut [(BatchResult, Option<String>)],
+    provider: &str,
+) -> Result<(), String> {
+    let raw = std::fs::read(project).map_err(|e| format!("Failed to load project: {e}"))?;
+    let mut file = ProjectFile::parse(&decode_project_bytes(&raw)?)?;
+    let timestamp = chrono::Utc::now().timestamp_millis();
+    for (result, answer) in answers.iter_mut() {
+        let Some(answer) = answer else {
+            continue;
+        };
+        result.result_node_id = add_result_node(
+            &mut file,
+            &result.node_id,
+            answer,
+            provider,
+            result.model_id.as_deref(),
+            timestamp,
+        );
+        if result.result_node_id.is_none() {
+            result.error = Some("Node was deleted while the batch ran".to_string());
+        }
+    }
+    let data = serde_json::to_string(&file.to_value()?)
+        .map_err(|e| format!("Failed to serialize project: {e}"))?;
+    // Like quick capture, written behind the project watch: an open
+    // project notices the change and offers to reload
+    write_atomic(project, &encode_project_data(&data, is_compressed(&raw))?)
+}
+
+/// The agent a batch runs on, and where
+pub(crate) struct BatchAgent {
+    pub provider: AgentProvider,
+    /// Bulk model override; the cheapest model otherwise
+    pub model_override: Option<String>,
+    pub notes_directory: PathBuf,
+    pub spawn_config: SpawnConfig,
+    /// Longest one node's prompt may run
+    pub timeout: Duration,
+}
+
+/// Run `template` over `inputs` (see [`batch_inputs`]), each node waiting
+/// for a slot in `queue`, and call `on_progress` as each one finishes.
+/// Returns every node's result with its answer, in input order.
+pub(crate) async fn run_batch(
+    agent: &BatchAgent,
+    template: &str,
+    inputs: Vec<(String, String)>,
+    queue: &Arc<GenerationQueue>,
+    on_progress: impl Fn(&BatchProgress),
+) -> Vec<(BatchResult, Option<String>)> {
+    let total = inputs.len();
+    let completed = AtomicUsize::new(0);
+    let runs = inputs.into_iter().map(|(node_id, content)| {
+        let prompt = render_batch_prompt(template, &content);
+        let (provider, notes_directory, spawn_config, model_override) = (
+            agent.provider.clone(),
+            agent.notes_directory.clone(),
+            agent.spawn_config.clone(),
+            agent.model_override.clone(),
+        );
+        let timeout = agent.timeout;
+        let (completed, on_progress) = (&completed, &on_progress);
+        async move {
+            let _slot = queue.acquire(&node_id, |_| {}).await;
+            let outcome = run_localset_blocking(move || async move {
+                let run = run_bulk_prompt(
+                    prompt,
+                    &provider,
+                    &notes_directory,
+                    &spawn_config,
+                    model_override.as_deref(),
+                    "batch-acp",
+                );
+                match tokio::time::timeout(timeout, run).await {
+                    Ok(result) => result.map_err(|e| e.to_string()),
+                    Err(_) => Err(format!("Timed out after {}s", timeout.as_secs())),
+                }
+            })
+            .await
+            .and_then(|(response, model_id)| {
+                if response.is_empty() {
+                    Err("No response generated".to_string())
+                } else {
+                    Ok((response, model_id))
+                }
+            });
+
+            let (answer, model_id, error) = match outcome {
+                Ok((response, model_id)) => (Some(response), model_id, None),
+                Err(e) => {
+                    tracing::warn!("Batch generation failed for {}: {}", node_id, e);
+                    (None, None, Some(e))
+                }
+            };
+            on_progress(&BatchProgress {
+                completed: completed.fetch_add(1, Ordering::SeqCst) + 1,
+                total,
+                node_id: node_id.clone(),
+                error: error.clone(),
+            });
+            let result = BatchResult {
+                node_id,
+                result_node_id: None,
+                model_id,
+                error,
+            };
+            (result, answer)
+        }
+    });
+    futures::future::join_all(runs).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project() -> ProjectFile {
+        ProjectFile::parse(
+            r#"{"version": 3, "graph": {"version": 3,
+                "nodes": [
+                    {"id": "a", "role": "user", "content": "Root"},
+                    {"id": "b", "role": "assistant", "content": "Leaf"}
+                ],
+                "edges": [{"id": "1", "source": "a", "target": "b"}],
+                "layout": [
+                    {"id": "a", "position": {"x": 0, "y": 0}},
+                    {"id": "b", "position": {"x": 0, "y": 250}}
+                ]}}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validate_and_render_template() {
+        assert!(validate_batch_template("Summarize:\n\n{{content}}").is_ok());
+        assert!(validate_batch_template("  ").is_err());
+        assert!(validate_batch_template("Summarize this").is_err());
+        assert_eq!(
+            render_batch_prompt("Summarize: {{content}}", "text"),
+            "Summarize: text"
+        );
+    }
+
+    #[test]
+    fn test_batch_inputs_dedupes_and_rejects_unknown_ids() {
+        let project = project();
+        let ids = ["b", "a", "b"].map(String::from);
+        assert_eq!(
+            batch_inputs(&project, &ids).unwrap(),
+            [
+                ("b".to_string(), "Leaf".to_string()),
+                ("a".to_string(), "Root".to_string())
+            ]
+        );
+        assert!(batch_inputs(&project, &["x".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_add_result_node_places_it_beside_siblings() {
+        let mut project = project();
+        let id = add_result_node(
+            &mut project,
+            "a",
+            "Summary",
+            "claude-code",
+            Some("claude-haiku-4-5"),
+            1,
+        )
+        .unwrap();
+        let node = project.graph.node(&id).unwrap();
+        assert_eq!(node.role, "assistant");
+        assert_eq!(node.model.as_deref(), Some("claude-haiku-4-5"));
+        assert_eq!(project.graph.parents(&id).collect::<Vec<_>>(), ["a"]);
+        let position = project.graph.position(&id).unwrap();
+        assert_eq!((position.x, position.y), (RESULT_NODE_SPACING_X, 250.0));
+        assert!(add_result_node(&mut project, "gone", "x", "claude-code", None, 1).is_none());
+    }
+}
@@ -0,0 +1,132 @@
+use serde::Serialize;
+
+use crate::backend::project_file::ProjectGraph;
+
+/// Card size on the canvas; height grows with content up to the maximum
+const CARD_WIDTH: i64 = 400;
+const CARD_MIN_HEIGHT: i64 = 120;
+const CARD_MAX_HEIGHT: i64 = 600;
+const LINE_HEIGHT: i64 = 24;
+/// Rough characters per rendered line at `CARD_WIDTH`
+const CHARS_PER_LINE: usize = 50;
+
+/// Obsidian's preset "cyan", used to tell responses from prompts
+const ASSISTANT_COLOR: &str = "5";
+
+/// A text card in a JSON Canvas (https://jsoncanvas.org)
+#[derive(Clone, Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CanvasNode {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub text: String,
+    pub x: i64,
+    pub y: i64,
+    pub width: i64,
+    pub height: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<&'static str>,
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CanvasEdge {
+    pub id: String,
+    pub from_node: String,
+    pub from_side: &'static str,
+    pub to_node: String,
+    pub to_side: &'static str,
+}
+
+#[derive(Clone, Debug, Default, Serialize, PartialEq)]
+pub(crate) struct Canvas {
+    pub nodes: Vec<CanvasNode>,
+    pub edges: Vec<CanvasEdge>,
+}
+
+fn card_height(text: &str) -> i64 {
+    let lines: usize = text
+        .lines()
+        .map(|line| line.chars().count().div_ceil(CHARS_PER_LINE).max(1))
+        .sum();
+    let lines = i64::try_from(lines).unwrap_or(i64::MAX);
+    lines
+        .saturating_mul(LINE_HEIGHT)
+        .saturating_add(2 * LINE_HEIGHT)
+        .clamp(CARD_MIN_HEIGHT, CARD_MAX_HEIGHT)
+}
+
+/// Convert the graph to a canvas, keeping the node positions from the
+/// ThoughtTree layout. Canvas coordinates are integers.
+pub(crate) fn to_canvas(graph: &ProjectGraph) -> Canvas {
+    let nodes = graph
+        .nodes
+        .iter()
+        .map(|node| {
+            let position = graph.position(&node.id).unwrap_or_default();
+            CanvasNode {
+                id: node.id.clone(),
+                kind: "text",
+                text: node.content.clone(),
+                x: position.x.round() as i64,
+                y: position.y.round() as i64,
+                width: CARD_WIDTH,
+                height: card_height(&node.content),
+                color: (node.role == "assistant").then_some(ASSISTANT_COLOR),
+            }
+        })
+        .collect();
+    let edges = graph
+        .edges
+        .iter()
+        .filter(|e| graph.node(&e.source).is_some() && graph.node(&e.target).is_some())
+        .map(|e| CanvasEdge {
+            id: e.id.clone(),
+            from_node: e.source.clone(),
+            from_side: "bottom",
+            to_node: e.target.clone(),
+            to_side: "top",
+        })
+        .collect();
+    Canvas { nodes, edges }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::project_file::ProjectFile;
+
+    #[test]
+    fn test_to_canvas_maps_nodes_edges_and_positions() {
+        let graph = ProjectFile::parse(
+            r#"{"version": 3, "graph": {"version": 3, "nodes": [
+                {"id": "q", "role": "user", "content": "Question"},
+                {"id": "a", "role": "assistant", "content": "Answer"}
+            ], "edges": [
+                {"id": "q->a", "source": "q", "target": "a"},
+                {"id": "q->gone", "source": "q", "target": "gone"}
+            ], "layout": [
+                {"id": "a", "position": {"x": 10.6, "y": 260}}
+            ]}}"#,
+        )
+        .unwrap()
+        .graph;
+
+        let canvas = serde_json::to_value(to_canvas(&graph)).unwrap();
+        assert_eq!(canvas["nodes"][0]["type"], "text");
+        assert!(canvas["nodes"][0].get("color").is_none());
+        assert_eq!(canvas["nodes"][1]["x"], 11);
+        assert_eq!(canvas["nodes"][1]["y"], 260);
+        assert_eq!(canvas["nodes"][1]["color"], ASSISTANT_COLOR);
+        assert_eq!(canvas["edges"].as_array().unwrap().len(), 1);
+        assert_eq!(canvas["edges"][0]["fromNode"], "q");
+        assert_eq!(canvas["edges"][0]["toNode"], "a");
+    }
+
+    #[test]
+    fn test_card_height_is_clamped() {
+        assert_eq!(card_height("short"), CARD_MIN_HEIGHT);
+        assert_eq!(card_height(&"line\n".repeat(100)), CARD_MAX_HEIGHT);
+    }
+}
@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+/// Thinking budget used when extended thinking is on without one
+pub(crate) const DEFAULT_THINKING_BUDGET: u32 = 10_000;
+
+/// Smallest thinking budget the API accepts
+const MIN_THINKING_BUDGET: u32 = 1024;
+
+const MAX_THINKING_BUDGET: u32 = 64_000;
+
+const MAX_TURNS_LIMIT: u32 = 100;
+
+/// Claude Code session options, set globally or per project (a project's
+/// `claudeOptions` replace the global ones). claude-code-acp takes them from
+/// the new session's `_meta` and hands them to the Claude Agent SDK.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ClaudeOptions {
+    #[serde(default)]
+    pub extended_thinking: bool,
+    /// Tokens Claude may think for per turn; `DEFAULT_THINKING_BUDGET` when
+    /// unset
+    #[serde(default)]
+    pub thinking_budget: Option<u32>,
+    /// Most agentic turns (model calls) a prompt may take; no limit when unset
+    #[serde(default)]
+    pub max_turns: Option<u32>,
+}
+
+impl ClaudeOptions {
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        if let Some(budget) = self.thinking_budget {
+            if !(MIN_THINKING_BUDGET..=MAX_THINKING_BUDGET).contains(&budget) {
+                return Err(format!(
+                    "Thinking budget must be between {MIN_THINKING_BUDGET} and {MAX_THINKING_BUDGET} tokens"
+                ));
+            }
+        }
+        if let Some(turns) = self.max_turns {
+            if !(1..=MAX_TURNS_LIMIT).contains(&turns) {
+                return Err(format!("Max turns must be between 1 and {MAX_TURNS_LIMIT}"));
+            }
+        }
+        Ok(())
+    }
+
+    /// `_meta` for `session/new`, or `None` when nothing differs from
+    /// Claude Code's defaults
+    pub(crate) fn session_meta(&self) -> Option<Map<String, Value>> {
+        let mut options = Map::new();
+        if self.extended_thinking {
+            let budget = self.thinking_budget.unwrap_or(DEFAULT_THINKING_BUDGET);
+            options.insert("maxThinkingTokens".to_string(), json!(budget));
+        }
+        if let Some(turns) = self.max_turns {
+            options.insert("maxTurns".to_string(), json!(turns));
+        }
+        if options.is_empty() {
+            return None;
+        }
+        let mut meta = Map::new();
+        meta.insert("claudeCode".to_string(), json!({ "options": options }));
+        Some(meta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_meta() {
+        assert_eq!(ClaudeOptions::default().session_meta(), None);
+
+        let options = ClaudeOptions {
+            extended_thinking: true,
+            thinking_budget: None,
+            max_turns: Some(5),
+        };
+        assert_eq!(
+            Value::Object(options.session_meta().unwrap()),
+            json!({"claudeCode": {"options": {"maxThinkingTokens": 10_000, "maxTurns": 5}}})
+        );
+
+        // A budget without thinking turned on is kept but not sent
+        let off = ClaudeOptions {
+            thinking_budget: Some(2048),
+            ..Default::default()
+        };
+        assert_eq!(off.session_meta(), None);
+    }
+
+    #[test]
+    fn test_validate_and_project_format() {
+        let options: ClaudeOptions = serde_json::from_value(
+            json!({"extendedThinking": true, "thinkingBudget": 4096, "maxTurns": 10}),
+        )
+        .unwrap();
+        assert!(options.validate().is_ok());
+
+        let too_small = ClaudeOptions {
+            thinking_budget: Some(100),
+            ..Default::default()
+        };
+        assert!(too_small.validate().is_err());
+        let no_turns = ClaudeOptions {
+            max_turns: Some(0),
+            ..Default::default()
+        };
+        assert!(no_turns.validate().is_err());
+    }
+}
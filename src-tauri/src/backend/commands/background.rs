@@ -0,0 +1,276 @@
+use std::path::{Path, PathBuf};
+
+use tauri::{AppHandle, Manager};
+
+use super::git::spawn_autocommit;
+use super::projects::{spawn_spotlight_update, validate_project_path};
+use super::summary::bulk_provider;
+use crate::backend::acp::sessions::run_summary_batch;
+use crate::backend::config;
+use crate::backend::history::orphaned_history_files;
+use crate::backend::indexer;
+use crate::backend::note_edit::write_atomic;
+use crate::backend::project_file::{
+    decode_project_bytes, encode_project_data, is_compressed, ProjectFile,
+};
+use crate::backend::runtime::run_localset_blocking;
+use crate::backend::safe_mode;
+use crate::backend::scheduler::{
+    BackgroundJob, BackgroundJobSettings, BackgroundJobStatus, SCHEDULER_TICK,
+};
+use crate::backend::state::AppState;
+use crate::backend::summaries::{apply_summaries, needs_summary, short_summary};
+use crate::backend::types::SummaryRequest;
+
+/// Nodes titled on the model per summary backfill run, so a run stays short
+const MAX_BACKFILL_NODES: usize = 20;
+
+/// Drop recent projects whose file is gone
+fn clean_recent_projects(app: &AppHandle) -> Result<String, String> {
+    let recent = config::get_recent_projects(app)?;
+    let kept: Vec<String> = recent
+        .iter()
+        .filter(|path| Path::new(path).is_file())
+        .cloned()
+        .collect();
+    let removed = recent.len() - kept.len();
+    if removed > 0 {
+        config::set_recent_projects(app, &kept)?;
+    }
+    Ok(format!("{removed} missing projects removed"))
+}
+
+/// Delete history files left behind by deleted or renamed projects
+fn prune_history(app: &AppHandle) -> Result<String, String> {
+    let dirs = config::get_workspace_directories(app)?;
+    let state = app.state::<AppState>();
+    let _writing = state.history_lock.lock().unwrap_or_else(|e| e.into_inner());
+    let mut removed = 0;
+    for path in orphaned_history_files(&dirs) {
+        match std::fs::remove_file(&path) {
+            Ok(()) => removed += 1,
+            Err(e) => tracing::warn!("Failed to remove {:?}: {}", path, e),
+        }
+    }
+    Ok(format!("{removed} orphaned history files removed"))
+}
+
+/// Read `project`, let `update` change it, and write it back with the same
+/// compression if `update` returns a count above zero
+fn update_project_file(
+    project: &Path,
+    update: impl FnOnce(&mut ProjectFile) -> usize,
+) -> Result<usize, String> {
+    let raw = std::fs::read(project).map_err(|e| format!("Failed to load project: {e}"))?;
+    let mut file = ProjectFile::parse(&decode_project_bytes(&raw)?)?;
+    let changed = update(&mut file);
+    if changed > 0 {
+        let data = serde_json::to_string(&file.to_value()?)
+            .map_err(|e| format!("Failed to serialize project: {e}"))?;
+        write_atomic(project, &encode_project_data(&data, is_compressed(&raw))?)?;
+    }
+    Ok(changed)
+}
+
+/// Recent projects that may be written in the background: not open here,
+/// and not locked by another instance
+fn closed_recent_projects(app: &AppHandle) -> Result<Vec<PathBuf>, String> {
+    let locks = app.state::<AppState>().project_locks.clone();
+    Ok(config::get_recent_projects(app)?
+        .iter()
+        .filter_map(|path| validate_project_path(app, path).ok())
+        .filter(|path| !locks.is_held(path) && locks.check_writable(path).is_ok())
+        .collect())
+}
+
+/// Re-index and commit a project the backfill wrote
+fn sync_written_project(app: &AppHandle, project: PathBuf) {
+    spawn_spotlight_update(project.clone());
+    indexer::spawn_project_update(app, project.clone());
+    spawn_autocommit(app, project, "summaries");
+}
+
+/// Give headings to nodes of recent projects that have none. Open projects
+/// are left to the frontend, which titles nodes as they are shown; writing
+/// them here would race its saves.
+async fn backfill_summaries(app: &AppHandle) -> Result<String, String> {
+    let projects = closed_recent_projects(app)?;
+    // Short nodes are their own heading; long ones wait for the model
+    let handle = app.clone();
+    let (pending, mut titled) = tokio::task::spawn_blocking(move || {
+        let mut pending: Vec<(PathBuf, Vec<SummaryRequest>)> = Vec::new();
+        let mut titled = 0;
+        let mut queued = 0;
+        for project in projects {
+            let mut requests = Vec::new();
+            let now = chrono::Utc::now().timestamp_millis();
+            let result = update_project_file(&project, |file| {
+                let mut short = Vec::new();
+                for node in file.graph.nodes.iter().filter(|n| needs_summary(n)) {
+                    match short_summary(&node.content) {
+                        Some(summary) => {
+                            short.push((node.id.clone(), node.content.clone(), summary.to_string()))
+                        }
+                        None if queued < MAX_BACKFILL_NODES => {
+                            queued += 1;
+                            requests.push(SummaryRequest {
+                                node_id: node.id.clone(),
+                                content: node.content.clone(),
+                            });
+                        }
+                        None => {}
+                    }
+                }
+                apply_summaries(file, &short, now)
+            });
+            match result {
+                Ok(0) => {}
+                Ok(count) => {
+                    titled += count;
+                    sync_written_project(&handle, project.clone());
+                }
+                Err(e) => tracing::warn!("Summary backfill skipped {:?}: {}", project, e),
+            }
+            if !requests.is_empty() {
+                pending.push((project, requests));
+            }
+        }
+        (pending, titled)
+    })
+    .await
+    .map_err(|e| format!("Summary backfill failed: {e}"))?;
+
+    if !pending.is_empty() {
+        let notes_directory = config::get_notes_directory_required(app)?;
+        let spawn_config = config::get_spawn_config(app)?;
+        let style = config::get_summary_style(app)?;
+        let (provider, model_override) = bulk_provider(app)?;
+        for (project, requests) in pending {
+            let contents: Vec<(String, String)> = requests
+                .iter()
+                .map(|r| (r.node_id.clone(), r.content.clone()))
+                .collect();
+            let (style, provider, notes_directory, spawn_config, model_override) = (
+                style.clone(),
+                provider.clone(),
+                notes_directory.clone(),
+                spawn_config.clone(),
+                model_override.clone(),
+            );
+            let results = run_localset_blocking(move || async move {
+                run_summary_batch(
+                    requests,
+                    style,
+                    provider,
+                    notes_directory,
+                    spawn_config,
+                    model_override,
+                    |_| {},
+                )
+                .await
+                .map_err(|e| e.to_string())
+            })
+            .await?;
+            let summaries: Vec<(String, String, String)> = results
+                .into_iter()
+                .filter_map(|result| {
+                    let (_, content) = contents.iter().find(|(id, _)| *id == result.node_id)?;
+                    Some((result.node_id, content.clone(), result.summary))
+                })
+                .collect();
+            let target = project.clone();
+            let now = chrono::Utc::now().timestamp_millis();
+            let count = tokio::task::spawn_blocking(move || {
+                update_project_file(&target, |file| apply_summaries(file, &summaries, now))
+            })
+            .await
+            .map_err(|e| format!("Summary backfill failed: {e}"))??;
+            if count > 0 {
+                titled += count;
+                sync_written_project(app, project);
+            }
+        }
+    }
+    Ok(format!("{titled} nodes titled"))
+}
+
+async fn run_job(app: &AppHandle, job: BackgroundJob) -> Result<String, String> {
+    match job {
+        BackgroundJob::SearchIndex => Ok(match indexer::maintain(app).await? {
+            Some(stats) => format!(
+                "{} indexed, {} removed, {} total",
+                stats.indexed, stats.removed, stats.total_documents
+            ),
+            None => "No index built yet".to_string(),
+        }),
+        BackgroundJob::SummaryBackfill => backfill_summaries(app).await,
+        BackgroundJob::HistoryPruning => {
+            let app = app.clone();
+            tokio::task::spawn_blocking(move || prune_history(&app))
+                .await
+                .map_err(|e| format!("History pruning failed: {e}"))?
+        }
+        BackgroundJob::RecentProjectsCleanup => clean_recent_projects(app),
+    }
+}
+
+/// Run maintenance jobs in the background at the intervals set in
+/// `set_background_job_settings`. Nothing runs in safe mode.
+pub(crate) fn start_background_jobs(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(SCHEDULER_TICK);
+        loop {
+            interval.tick().await;
+            if safe_mode::is_enabled(&app) {
+                continue;
+            }
+            let settings = match config::get_background_job_settings(&app) {
+                Ok(settings) => settings,
+                Err(e) => {
+                    tracing::warn!("Failed to read background job settings: {}", e);
+                    continue;
+                }
+            };
+            let scheduler = app.state::<AppState>().background_jobs.clone();
+            let now = chrono::Utc::now().timestamp_millis();
+            for job in scheduler.take_due(&settings, now) {
+                let app = app.clone();
+                let scheduler = scheduler.clone();
+                tauri::async_runtime::spawn(async move {
+                    let outcome = run_job(&app, job).await;
+                    match &outcome {
+                        Ok(result) => tracing::debug!("Background job {:?}: {}", job, result),
+                        Err(e) => tracing::warn!("Background job {:?} failed: {}", job, e),
+                    }
+                    scheduler.finish(job, chrono::Utc::now().timestamp_millis(), outcome);
+                });
+            }
+        }
+    });
+}
+
+/// Interval, last run and outcome of each background job
+#[tauri::command]
+pub(crate) async fn get_background_jobs(
+    app: AppHandle,
+) -> Result<Vec<BackgroundJobStatus>, String> {
+    let settings = config::get_background_job_settings(&app)?;
+    Ok(app.state::<AppState>().background_jobs.statuses(&settings))
+}
+
+#[tauri::command]
+pub(crate) async fn get_background_job_settings(
+    app: AppHandle,
+) -> Result<BackgroundJobSettings, String> {
+    config::get_background_job_settings(&app)
+}
+
+/// Minutes between runs per job; 0 turns a job off
+#[tauri::command]
+pub(crate) async fn set_background_job_settings(
+    app: AppHandle,
+    settings: BackgroundJobSettings,
+) -> Result<(), String> {
+    settings.validate()?;
+    config::set_background_job_settings(&app, &settings)
+}
@@ -0,0 +1,105 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use tauri::{AppHandle, Emitter, State};
+
+use super::git::spawn_autocommit;
+use super::projects::{spawn_spotlight_update, validate_project_path};
+use super::summary::bulk_provider;
+use crate::backend::batch::{
+    batch_inputs, read_project, run_batch, validate_batch_template, write_results, BatchAgent,
+    BatchResult, MAX_BATCH_NODES,
+};
+use crate::backend::config;
+use crate::backend::indexer;
+use crate::backend::project::{project_notes_directory, read_project_settings};
+use crate::backend::state::AppState;
+
+/// Run `prompt_template` (with `{{content}}` standing for each node's
+/// content) over `node_ids` of a saved project on the bulk model, and add
+/// every answer as a child of its node in the project file. Runs share the
+/// generation queue, so at most the configured number of agents work at
+/// once. A `batch-progress` event follows each node.
+#[tauri::command]
+pub(crate) async fn batch_generate(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    project_path: String,
+    node_ids: Vec<String>,
+    prompt_template: String,
+) -> Result<Vec<BatchResult>, String> {
+    validate_batch_template(&prompt_template)?;
+    if node_ids.len() > MAX_BATCH_NODES {
+        return Err(format!(
+            "Too many nodes for one batch ({}, limit {MAX_BATCH_NODES})",
+            node_ids.len()
+        ));
+    }
+    let project: PathBuf = validate_project_path(&app, &project_path)?;
+    state.project_locks.check_writable(&project)?;
+    let target = project.clone();
+    let file = tokio::task::spawn_blocking(move || read_project(&target))
+        .await
+        .map_err(|e| format!("Batch failed: {e}"))??;
+    let inputs = batch_inputs(&file, &node_ids)?;
+    if inputs.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // The same working directory as prompts in this project
+    let target = project.clone();
+    let settings = tokio::task::spawn_blocking(move || read_project_settings(&target))
+        .await
+        .map_err(|e| format!("Batch failed: {e}"))??;
+    let notes_directory = project_notes_directory(
+        &settings,
+        config::get_notes_directory_required(&app)?,
+        &config::get_workspace_directories(&app)?,
+    );
+    let (provider, model_override) = bulk_provider(&app)?;
+    let provider_id = serde_json::to_value(&provider)
+        .ok()
+        .and_then(|v| v.as_str().map(String::from))
+        .unwrap_or_default();
+    let agent = BatchAgent {
+        provider,
+        model_override,
+        notes_directory,
+        spawn_config: config::get_spawn_config(&app)?,
+        timeout: Duration::from_secs(config::get_prompt_timeout_secs(&app)?),
+    };
+
+    tracing::info!(
+        "Batch generating {} nodes of {:?} with {:?}",
+        inputs.len(),
+        project,
+        agent.provider
+    );
+    let mut answers = run_batch(
+        &agent,
+        &prompt_template,
+        inputs,
+        &state.generation_queue,
+        |progress| {
+            if let Err(e) = app.emit("batch-progress", progress) {
+                tracing::warn!("Failed to emit batch-progress: {}", e);
+            }
+        },
+    )
+    .await;
+
+    if answers.iter().any(|(_, answer)| answer.is_some()) {
+        state.project_locks.check_writable(&project)?;
+        let target = project.clone();
+        answers = tokio::task::spawn_blocking(move || {
+            write_results(&target, &mut answers, &provider_id).map(|()| answers)
+        })
+        .await
+        .map_err(|e| format!("Batch failed: {e}"))??;
+        tracing::info!("Batch results written to {:?}", project);
+        spawn_spotlight_update(project.clone());
+        indexer::spawn_project_update(&app, project.clone());
+        spawn_autocommit(&app, project, "batch");
+    }
+    Ok(answers.into_iter().map(|(result, _)| result).collect())
+}
@@ -0,0 +1,148 @@
+use std::path::Path;
+
+use tauri::{AppHandle, State};
+
+use super::git::spawn_autocommit;
+use super::notes::{ensure_note_writes_enabled, resolve_note, MAX_APPEND_TARGET_BYTES};
+use super::projects::{spawn_spotlight_update, validate_project_path};
+use crate::backend::config;
+use crate::backend::indexer;
+use crate::backend::links::is_markdown;
+use crate::backend::note_edit::write_atomic;
+use crate::backend::project_file::{
+    decode_project_bytes, encode_project_data, is_compressed, ProjectFile,
+};
+use crate::backend::quick_capture::{
+    add_capture_node, append_capture_to_note, validate_capture, CaptureTarget,
+};
+use crate::backend::safe_mode;
+use crate::backend::state::AppState;
+use crate::backend::tray;
+
+fn capture_to_note(note: &Path, text: &str) -> Result<(), String> {
+    let existing = match std::fs::metadata(note) {
+        Ok(metadata) if !metadata.is_file() => return Err("Inbox note is not a file".to_string()),
+        Ok(metadata) if metadata.len() > MAX_APPEND_TARGET_BYTES => {
+            return Err("Inbox note is too large to modify".to_string())
+        }
+        Ok(_) => {
+            std::fs::read_to_string(note).map_err(|e| format!("Failed to read inbox note: {e}"))?
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("Failed to read inbox note: {e}")),
+    };
+    let updated = append_capture_to_note(&existing, text, chrono::Local::now());
+    write_atomic(note, updated.as_bytes())
+}
+
+fn capture_to_project(project: &Path, text: &str) -> Result<(), String> {
+    let raw = std::fs::read(project).map_err(|e| format!("Failed to load inbox project: {e}"))?;
+    let mut file = ProjectFile::parse(&decode_project_bytes(&raw)?)?;
+    add_capture_node(&mut file, text, chrono::Local::now());
+    let data = serde_json::to_string(&file.to_value()?)
+        .map_err(|e| format!("Failed to serialize project: {e}"))?;
+    // Not written through the project watch: if the inbox project is open,
+    // the app notices the change and offers to reload it
+    write_atomic(project, &encode_project_data(&data, is_compressed(&raw))?)
+}
+
+/// Check that `target` points at a markdown note in the notes directory or a
+/// project in a workspace. A note inbox needs the note-writes setting.
+fn validate_target(app: &AppHandle, target: &CaptureTarget) -> Result<(), String> {
+    match target {
+        CaptureTarget::Note { path } => {
+            ensure_note_writes_enabled(app)?;
+            let (_, validated, _) = resolve_note(app, path)?;
+            if !is_markdown(&validated) {
+                return Err("The inbox must be a markdown note".to_string());
+            }
+        }
+        CaptureTarget::Project { path } => {
+            if !validate_project_path(app, path)?.is_file() {
+                return Err("Inbox project not found".to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Add a thought to the configured inbox: a timestamped entry in the inbox
+/// note (only while note writes are enabled), or a new root node in the
+/// inbox project
+#[tauri::command]
+pub(crate) async fn quick_capture(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    text: String,
+) -> Result<(), String> {
+    let text = validate_capture(&text)?.to_string();
+    match config::get_quick_capture_target(&app)? {
+        CaptureTarget::Note { path } => {
+            // Writes may have been turned off since the inbox was chosen
+            ensure_note_writes_enabled(&app)?;
+            let (_, note, rel_path) = resolve_note(&app, &path)?;
+            if !is_markdown(&note) {
+                return Err("The inbox must be a markdown note".to_string());
+            }
+            tokio::task::spawn_blocking(move || capture_to_note(&note, &text))
+                .await
+                .map_err(|e| format!("Quick capture failed: {e}"))??;
+            tracing::info!("Captured thought to note: {}", rel_path);
+        }
+        CaptureTarget::Project { path } => {
+            let project = validate_project_path(&app, &path)?;
+            state.project_locks.check_writable(&project)?;
+            let target = project.clone();
+            tokio::task::spawn_blocking(move || capture_to_project(&target, &text))
+                .await
+                .map_err(|e| format!("Quick capture failed: {e}"))??;
+            tracing::info!("Captured thought to project: {:?}", project);
+            spawn_spotlight_update(project.clone());
+            indexer::spawn_project_update(&app, project.clone());
+            spawn_autocommit(&app, project, "capture");
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) async fn get_quick_capture_target(app: AppHandle) -> Result<CaptureTarget, String> {
+    config::get_quick_capture_target(&app)
+}
+
+#[tauri::command]
+pub(crate) async fn set_quick_capture_target(
+    app: AppHandle,
+    target: CaptureTarget,
+) -> Result<(), String> {
+    validate_target(&app, &target)?;
+    config::set_quick_capture_target(&app, &target)?;
+    tracing::info!("Quick capture target set to: {:?}", target);
+    Ok(())
+}
+
+/// The global quick capture shortcut, or `None` when it is turned off
+#[tauri::command]
+pub(crate) async fn get_quick_capture_shortcut(app: AppHandle) -> Result<Option<String>, String> {
+    config::get_quick_capture_shortcut(&app)
+}
+
+/// Change the global quick capture shortcut (e.g. `Alt+Shift+N`) and register
+/// it right away; `None` turns it off. In safe mode it is only saved, since
+/// no shortcuts are registered then.
+#[tauri::command]
+pub(crate) async fn set_quick_capture_shortcut(
+    app: AppHandle,
+    shortcut: Option<String>,
+) -> Result<(), String> {
+    let shortcut = shortcut
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+    if let Some(shortcut) = &shortcut {
+        tray::parse_shortcut(shortcut)?;
+    }
+    if !safe_mode::is_enabled(&app) {
+        tray::register_capture_shortcut(&app, shortcut.as_deref())?;
+    }
+    config::set_quick_capture_shortcut(&app, shortcut.as_deref())
+}
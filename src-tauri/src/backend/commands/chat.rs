@@ -1,11 +1,57 @@
-use tauri::{AppHandle, State};
+use std::path::Path;
+use std::time::{Duration, Instant};
 
-use crate::backend::acp::process::find_sidecar_path;
-use crate::backend::acp::sessions::{run_prompt_session, PromptSessionParams};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tracing::Instrument;
+
+use crate::backend::acp::process::find_claude_acp_launcher;
+use crate::backend::acp::sessions::{
+    run_prompt_session_with_retry, AncestorCompaction, PromptSessionParams,
+};
+use crate::backend::alternatives::{alternative_messages, validate_alternatives};
+use crate::backend::analytics::{self, GenerationRecord};
+use crate::backend::commands::projects::{node_generation_config, validate_project_path};
 use crate::backend::config;
+use crate::backend::context::{
+    append_linked_notes, prepend_context, ContextNote, LinkExpansion, MAX_CONTEXT_NOTES,
+    MAX_CONTEXT_NOTE_BYTES, MAX_LINKED_CONTEXT_BYTES,
+};
+use crate::backend::images::MIN_IMAGE_MAX_DIMENSION;
+use crate::backend::metrics::{self, elapsed_ms, LatencyReport, PerformanceStats, PromptSample};
+use crate::backend::note_files::{read_text_prefix, validate_path_in_notes_dir};
+use crate::backend::preamble::{
+    render_preamble, validate_preamble, PreambleSettings, PreambleValues,
+};
+use crate::backend::project;
+use crate::backend::queue::GenerationQueueSnapshot;
+use crate::backend::recovery::{self, RecoveredContent};
+use crate::backend::retry::MAX_RETRIES_LIMIT;
 use crate::backend::runtime::run_localset_blocking;
 use crate::backend::state::AppState;
-use crate::backend::types::{AgentProvider, Message};
+use crate::backend::tokens::{self, TokenCount};
+use crate::backend::types::{
+    AgentProvider, AlternativeOutcome, AlternativesCreatedPayload, ChunkPayload, LinkedNoteReport,
+    Message, ModelPreset, MultiPromptOutcome, PromptResult, PromptTarget, QueuePositionPayload,
+    StreamTimeoutPayload,
+};
+
+const GENERATION_CANCELLED: &str = "Generation cancelled";
+
+/// Largest system prompt accepted, in bytes
+const MAX_SYSTEM_PROMPT_BYTES: usize = 32 * 1024;
+
+/// Everything needed to generate one node's answer
+struct NodePrompt {
+    node_id: String,
+    messages: Vec<Message>,
+    provider: Option<AgentProvider>,
+    model_id: Option<String>,
+    /// Resolved to a model id when `model_id` is unset
+    preset: Option<ModelPreset>,
+    project_path: Option<String>,
+    session_mode: Option<String>,
+    agent_command: Option<String>,
+}
 
 #[tauri::command]
 pub(crate) async fn send_prompt(
@@ -15,36 +61,565 @@ pub(crate) async fn send_prompt(
     messages: Vec<Message>,
     provider: Option<AgentProvider>,
     model_id: Option<String>,
-) -> Result<String, String> {
+    preset: Option<ModelPreset>,
+    project_path: Option<String>,
+    session_mode: Option<String>,
+    agent_command: Option<String>,
+) -> Result<PromptResult, String> {
+    prompt_node(
+        app_handle,
+        &state,
+        NodePrompt {
+            node_id,
+            messages,
+            provider,
+            model_id,
+            preset,
+            project_path,
+            session_mode,
+            agent_command,
+        },
+    )
+    .await
+}
+
+/// Send the same conversation to several providers at once, streaming each
+/// answer into its own node so they can be compared side by side
+#[tauri::command]
+pub(crate) async fn send_prompt_multi(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    messages: Vec<Message>,
+    targets: Vec<PromptTarget>,
+    project_path: Option<String>,
+) -> Result<Vec<MultiPromptOutcome>, String> {
+    if targets.is_empty() {
+        return Err("No providers selected".to_string());
+    }
+    let mut node_ids: Vec<&str> = targets.iter().map(|t| t.node_id.as_str()).collect();
+    node_ids.sort_unstable();
+    node_ids.dedup();
+    if node_ids.len() != targets.len() {
+        return Err("Each provider needs its own node".to_string());
+    }
+
+    let runs = targets.into_iter().map(|target| {
+        let request = NodePrompt {
+            node_id: target.node_id.clone(),
+            messages: messages.clone(),
+            provider: Some(target.provider.clone()),
+            model_id: target.model_id,
+            preset: None,
+            project_path: project_path.clone(),
+            session_mode: None,
+            agent_command: None,
+        };
+        let app_handle = app_handle.clone();
+        let state = &*state;
+        async move {
+            let result = prompt_node(app_handle, state, request).await;
+            MultiPromptOutcome {
+                node_id: target.node_id,
+                provider: target.provider,
+                error: result.as_ref().err().cloned(),
+                result: result.ok(),
+            }
+        }
+    });
+
+    Ok(futures::future::join_all(runs).await)
+}
+
+/// Generate `count` alternative answers to the same conversation, each
+/// streaming into a new child node of `node_id`. The child ids are sent in
+/// an `alternatives-created` event before anything streams. The generation
+/// queue decides how many run at once.
+#[tauri::command]
+pub(crate) async fn generate_alternatives(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    node_id: String,
+    messages: Vec<Message>,
+    count: usize,
+    temperature_hint: Option<f32>,
+    provider: Option<AgentProvider>,
+    model_id: Option<String>,
+    project_path: Option<String>,
+) -> Result<Vec<AlternativeOutcome>, String> {
+    validate_alternatives(count, temperature_hint)?;
+
+    let node_ids: Vec<String> = (0..count)
+        .map(|_| uuid::Uuid::new_v4().to_string())
+        .collect();
+    let payload = AlternativesCreatedPayload {
+        parent_node_id: node_id,
+        node_ids: node_ids.clone(),
+    };
+    if let Err(e) = app_handle.emit("alternatives-created", payload) {
+        tracing::error!("Failed to emit alternatives-created: {:?}", e);
+    }
+
+    let runs = node_ids.into_iter().enumerate().map(|(index, child_id)| {
+        let request = NodePrompt {
+            node_id: child_id.clone(),
+            messages: alternative_messages(&messages, index + 1, count, temperature_hint),
+            provider: provider.clone(),
+            model_id: model_id.clone(),
+            preset: None,
+            project_path: project_path.clone(),
+            session_mode: None,
+            agent_command: None,
+        };
+        let app_handle = app_handle.clone();
+        let state = &*state;
+        async move {
+            let result = prompt_node(app_handle, state, request).await;
+            AlternativeOutcome {
+                node_id: child_id,
+                error: result.as_ref().err().cloned(),
+                result: result.ok(),
+            }
+        }
+    });
+
+    Ok(futures::future::join_all(runs).await)
+}
+
+/// Re-run a node with a different provider/model, cancelling its current
+/// generation first so the two never stream into the node at once. Without
+/// a provider or model, the node's saved configuration is used (see
+/// `get_node_generation_config`), so old answers regenerate with the model
+/// that wrote them.
+#[tauri::command]
+pub(crate) async fn regenerate_node(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    node_id: String,
+    messages: Vec<Message>,
+    provider: Option<AgentProvider>,
+    model_id: Option<String>,
+    project_path: Option<String>,
+) -> Result<PromptResult, String> {
+    if state.generations.cancel(&node_id).await {
+        tracing::info!("Cancelled in-flight generation for node {}", node_id);
+    }
+
+    let (provider, model_id) = match (&provider, &model_id, project_path.as_deref()) {
+        (None, None, Some(path)) => {
+            match node_generation_config(&app_handle, &state, path, node_id.clone()).await {
+                Ok(config) => {
+                    tracing::info!(
+                        "Regenerating node {} with {:?} {:?} ({:?})",
+                        node_id,
+                        config.provider,
+                        config.model_id,
+                        config.source
+                    );
+                    (Some(config.provider), config.model_id)
+                }
+                Err(e) => {
+                    tracing::warn!("No saved configuration for node {}: {}", node_id, e);
+                    (provider, model_id)
+                }
+            }
+        }
+        _ => (provider, model_id),
+    };
+
+    prompt_node(
+        app_handle,
+        &state,
+        NodePrompt {
+            node_id,
+            messages,
+            provider,
+            model_id,
+            preset: None,
+            project_path,
+            session_mode: None,
+            agent_command: None,
+        },
+    )
+    .await
+}
+
+/// Read each message's `context_paths` and prepend the notes to its
+/// content, or with `as_links` keep them in `context_notes` for the session
+/// to send as resource links. Every path must resolve inside the notes
+/// directory.
+async fn with_note_context(
+    notes_directory: &Path,
+    messages: Vec<Message>,
+    as_links: bool,
+) -> Result<Vec<Message>, String> {
+    if messages
+        .iter()
+        .all(|m| m.context_paths.as_ref().is_none_or(Vec::is_empty))
+    {
+        return Ok(messages);
+    }
+    let notes_directory = notes_directory.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let canonical_notes = std::fs::canonicalize(&notes_directory)
+            .map_err(|e| format!("Failed to resolve notes directory: {e}"))?;
+        messages
+            .into_iter()
+            .map(|mut message| {
+                let paths = message.context_paths.take().unwrap_or_default();
+                if paths.len() > MAX_CONTEXT_NOTES {
+                    return Err(format!(
+                        "A message can reference at most {MAX_CONTEXT_NOTES} notes"
+                    ));
+                }
+                let mut notes = Vec::with_capacity(paths.len());
+                for path in paths {
+                    let validated =
+                        validate_path_in_notes_dir(&notes_directory.join(&path), &notes_directory)?;
+                    if !validated.is_file() {
+                        return Err(format!("Referenced note not found: {path}"));
+                    }
+                    let (content, truncated) =
+                        read_text_prefix(&validated, MAX_CONTEXT_NOTE_BYTES)?;
+                    let rel_path = validated
+                        .strip_prefix(&canonical_notes)
+                        .map(|p| p.to_string_lossy().replace('\\', "/"))
+                        .unwrap_or(path);
+                    notes.push(ContextNote {
+                        rel_path,
+                        path: validated,
+                        text: content,
+                        truncated,
+                    });
+                }
+                if as_links {
+                    message.context_notes = notes;
+                } else {
+                    let blocks: Vec<String> = notes.iter().map(ContextNote::block).collect();
+                    message.content = prepend_context(&message.content, &blocks);
+                }
+                Ok(message)
+            })
+            .collect::<Result<Vec<_>, String>>()
+    })
+    .await
+    .map_err(|e| format!("Failed to read referenced notes: {e}"))?
+}
+
+/// Append the notes that user messages link with `[[wikilinks]]`, resolved
+/// by path, name, title or alias, within one budget for the whole prompt.
+/// Returns the messages and a report of what was included.
+async fn with_linked_notes(
+    app: &AppHandle,
+    notes_directory: &Path,
+    mut messages: Vec<Message>,
+) -> Result<(Vec<Message>, Vec<LinkedNoteReport>), String> {
+    if !messages
+        .iter()
+        .any(|m| m.role == "user" && m.content.contains("[["))
+    {
+        return Ok((messages, Vec::new()));
+    }
+    let app = app.clone();
+    let notes_directory = notes_directory.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let mut graph = state.link_graph.lock().unwrap_or_else(|e| e.into_inner());
+        graph.refresh(&notes_directory);
+
+        let read = |path: &str, max_bytes: u64| {
+            let validated =
+                validate_path_in_notes_dir(&notes_directory.join(path), &notes_directory)?;
+            read_text_prefix(&validated, max_bytes)
+        };
+        let mut expansion = LinkExpansion::new(MAX_LINKED_CONTEXT_BYTES);
+        for message in messages.iter_mut().filter(|m| m.role == "user") {
+            let blocks = expansion.expand(&message.content, |target| graph.resolve(target), read);
+            message.content = append_linked_notes(&message.content, &blocks);
+        }
+        for linked in &expansion.report {
+            tracing::info!(
+                "Linked note [[{}]] -> {:?}: {} bytes{}",
+                linked.target,
+                linked.path,
+                linked.included_bytes,
+                if linked.truncated { " (truncated)" } else { "" }
+            );
+        }
+        Ok((messages, expansion.report))
+    })
+    .await
+    .map_err(|e| format!("Failed to read linked notes: {e}"))?
+}
+
+/// Run `request` under a fresh trace ID. Every log line of the generation,
+/// agent stderr included, is in a `generation` span carrying the ID; its
+/// events carry it as `trace_id` and its error message ends with it.
+async fn prompt_node(
+    app_handle: AppHandle,
+    state: &AppState,
+    request: NodePrompt,
+) -> Result<PromptResult, String> {
+    let trace_id = uuid::Uuid::new_v4().to_string();
+    let span = tracing::info_span!("generation", trace_id = %trace_id, node_id = %request.node_id);
+    prompt_node_traced(app_handle, state, request, &trace_id)
+        .instrument(span)
+        .await
+        .map_err(|e| with_trace_id(e, &trace_id))
+}
+
+/// Tag an error with its generation's trace ID; cancellation is left as is
+/// so callers can still recognize it
+fn with_trace_id(error: String, trace_id: &str) -> String {
+    if error == GENERATION_CANCELLED {
+        error
+    } else {
+        format!("{error} (trace {trace_id})")
+    }
+}
+
+async fn prompt_node_traced(
+    app_handle: AppHandle,
+    state: &AppState,
+    request: NodePrompt,
+    trace_id: &str,
+) -> Result<PromptResult, String> {
+    let NodePrompt {
+        node_id,
+        messages,
+        provider,
+        model_id,
+        preset,
+        project_path,
+        session_mode,
+        agent_command,
+    } = request;
     let pending_permissions = state.pending_permissions.clone();
+    let stream_replay = state.stream_replay.clone();
 
-    let notes_directory = config::get_notes_directory_required(&app_handle)?;
+    let mut notes_directory = config::get_notes_directory_required(&app_handle)?;
     let default_provider = config::get_default_provider(&app_handle)?;
-    let provider_paths = config::get_provider_paths(&app_handle)?;
+    let spawn_config = config::get_spawn_config(&app_handle)?;
+    let timeout_secs = config::get_prompt_timeout_secs(&app_handle)?;
+    let retry_on_crash = config::get_retry_on_crash(&app_handle)?;
+    let max_retries = config::get_max_retries(&app_handle)?;
+    let image_max_dimension = config::get_image_max_dimension(&app_handle)?;
+    let preamble_settings = config::get_prompt_preamble(&app_handle)?;
+    let project_title = project_path
+        .as_deref()
+        .and_then(|path| Path::new(path).file_stem())
+        .map(|stem| stem.to_string_lossy().into_owned());
 
     let active_provider = provider.unwrap_or(default_provider);
+    let model_id = match (model_id, preset) {
+        (None, Some(preset)) => config::get_model_presets(&app_handle)?
+            .get(preset)
+            .get(&active_provider)
+            .map(String::from),
+        (model_id, _) => model_id,
+    };
+    let compaction = if config::get_compact_ancestors(&app_handle)? {
+        Some(AncestorCompaction {
+            cache: state.compaction_cache.clone(),
+            model_override: config::get_bulk_model_overrides(&app_handle)?
+                .get(&active_provider)
+                .map(String::from),
+        })
+    } else {
+        None
+    };
+    let session_mode = match session_mode {
+        Some(mode) => Some(mode),
+        None => config::get_session_mode_preferences(&app_handle)?
+            .get(&active_provider)
+            .map(String::from),
+    };
+
+    let settings = match project_path {
+        Some(path) => {
+            let validated = validate_project_path(&app_handle, &path)?;
+            let settings = project::read_project_settings(&validated)?;
+            if settings.notes_directory.is_some() {
+                notes_directory = project::project_notes_directory(
+                    &settings,
+                    notes_directory,
+                    &config::get_workspace_directories(&app_handle)?,
+                );
+            }
+            settings
+        }
+        None => project::ProjectSettings::default(),
+    };
+    let project_permissions = settings.permissions.unwrap_or_default();
+    let claude_options = match settings.claude_options {
+        Some(options) => match options.validate() {
+            Ok(()) => options,
+            Err(e) => {
+                tracing::warn!("Ignoring Claude options of project: {}", e);
+                config::get_claude_options(&app_handle)?
+            }
+        },
+        None => config::get_claude_options(&app_handle)?,
+    };
+    let system_prompt = settings
+        .system_prompt
+        .filter(|prompt| !prompt.trim().is_empty())
+        .or(config::get_system_prompt(&app_handle)?.filter(|prompt| !prompt.trim().is_empty()));
+
+    let note_links = config::get_note_resource_links(&app_handle)?;
+    let messages = with_note_context(&notes_directory, messages, note_links).await?;
+    let (messages, linked_notes) =
+        with_linked_notes(&app_handle, &notes_directory, messages).await?;
+
+    let (_generation, mut cancelled) = state.generations.register(&node_id);
+
+    let queue = state.generation_queue.clone();
+    let _slot = tokio::select! {
+        slot = queue.acquire(&node_id, |position| {
+            tracing::info!(
+                "Node {} waiting for generation slot ({})",
+                node_id,
+                position
+            );
+            emit_queue_position(&app_handle, &node_id, trace_id, position);
+        }) => slot,
+        Ok(()) = &mut cancelled => return Err(GENERATION_CANCELLED.to_string()),
+    };
+    emit_queue_position(&app_handle, &node_id, trace_id, 0);
+
+    let timezone_name = iana_time_zone::get_timezone().unwrap_or_else(|_| "Local".to_string());
+    let preamble = render_preamble(
+        &preamble_settings,
+        &PreambleValues::new(
+            &preamble_settings,
+            &chrono::Local::now(),
+            &timezone_name,
+            project_title.as_deref(),
+        ),
+    );
 
     tracing::info!(
         "Using provider: {:?}, notes directory: {:?}",
         active_provider,
         notes_directory
     );
+    let (sample_provider, sample_model) = (active_provider.clone(), model_id.clone());
+    let generation_started = Instant::now();
+    let analytics_app = app_handle.clone();
+    let trace_id = trace_id.to_string();
+    let generating_node = node_id.clone();
+    // The session runs on another thread, which doesn't inherit the span
+    let span = tracing::Span::current();
 
-    run_localset_blocking(move || async move {
-        run_prompt_session(PromptSessionParams {
-            app_handle,
-            node_id,
-            messages,
-            pending_permissions,
-            notes_directory,
-            provider: active_provider,
-            model_id,
-            provider_paths,
-        })
-        .await
-        .map_err(|e| e.to_string())
+    let result = run_localset_blocking(move || {
+        async move {
+            let session = run_prompt_session_with_retry(
+                PromptSessionParams {
+                    app_handle: app_handle.clone(),
+                    node_id: node_id.clone(),
+                    trace_id: trace_id.clone(),
+                    messages,
+                    pending_permissions,
+                    notes_directory,
+                    provider: active_provider,
+                    model_id,
+                    session_mode,
+                    agent_command,
+                    spawn_config,
+                    project_permissions,
+                    stream_replay,
+                    image_max_dimension,
+                    system_prompt,
+                    preamble,
+                    compaction,
+                    claude_options,
+                },
+                retry_on_crash,
+                max_retries,
+            );
+
+            // Dropping a timed-out or cancelled session kills the agent (kill_on_drop)
+            let timed = tokio::select! {
+                timed = tokio::time::timeout(Duration::from_secs(timeout_secs), session) => timed,
+                Ok(()) = cancelled => {
+                    tracing::info!("Generation for node {} cancelled", node_id);
+                    return Err(GENERATION_CANCELLED.to_string());
+                }
+            };
+            match timed {
+                Ok(result) => result.map_err(|e| e.to_string()),
+                Err(_) => {
+                    tracing::warn!(
+                        "Prompt for node {} timed out after {}s",
+                        node_id,
+                        timeout_secs
+                    );
+                    let payload = StreamTimeoutPayload {
+                        node_id,
+                        trace_id,
+                        timeout_secs,
+                    };
+                    if let Err(e) = app_handle.emit("stream-timeout", payload) {
+                        tracing::error!("Failed to emit stream-timeout: {:?}", e);
+                    }
+                    Err(format!("Prompt timed out after {timeout_secs}s"))
+                }
+            }
+        }
+        .instrument(span)
     })
-    .await
+    .await;
+
+    // However the generation ended, its unanswered permission requests are
+    // moot; dropping them cancels their dialogs' channels
+    let purged = state.pending_permissions.purge_node(&generating_node);
+    if purged > 0 {
+        tracing::info!(
+            "Dropped {} pending permission requests of node {}",
+            purged,
+            generating_node
+        );
+    }
+
+    if result.as_ref().err().map(String::as_str) != Some(GENERATION_CANCELLED) {
+        let record = GenerationRecord {
+            provider: sample_provider.clone(),
+            model_id: sample_model.clone(),
+            duration_ms: result
+                .as_ref()
+                .map_or_else(|_| elapsed_ms(generation_started), |r| r.timings.total_ms),
+            first_chunk_ms: result.as_ref().ok().and_then(|r| r.timings.first_chunk_ms),
+            succeeded: result.is_ok(),
+        };
+        tokio::task::spawn_blocking(move || analytics::record(&analytics_app, &record));
+    }
+    let result = result?;
+
+    let mut samples = state.latency_samples.lock().await;
+    metrics::push_latency_sample(
+        &mut samples,
+        PromptSample {
+            provider: sample_provider,
+            model_id: sample_model,
+            timings: result.timings.clone(),
+            throughput: result.throughput.clone(),
+        },
+    );
+
+    Ok(PromptResult {
+        linked_notes,
+        ..result
+    })
+}
+
+fn emit_queue_position(app_handle: &AppHandle, node_id: &str, trace_id: &str, position: usize) {
+    let payload = QueuePositionPayload {
+        node_id: node_id.to_string(),
+        trace_id: trace_id.to_string(),
+        position,
+    };
+    if let Err(e) = app_handle.emit("queue-position", payload) {
+        tracing::error!("Failed to emit queue position: {:?}", e);
+    }
 }
 
 #[tauri::command]
@@ -53,9 +628,7 @@ pub(crate) async fn respond_to_permission(
     request_id: String,
     option_id: String,
 ) -> Result<(), String> {
-    let mut pending = state.pending_permissions.lock().await;
-
-    if let Some(sender) = pending.remove(&request_id) {
+    if let Some(sender) = state.pending_permissions.take(&request_id) {
         sender
             .send(option_id)
             .map_err(|_| "Failed to send permission response")?;
@@ -68,6 +641,251 @@ pub(crate) async fn respond_to_permission(
 }
 
 #[tauri::command]
-pub(crate) async fn check_acp_available() -> Result<bool, String> {
-    Ok(find_sidecar_path().is_some())
+pub(crate) async fn check_acp_available(app_handle: AppHandle) -> Result<bool, String> {
+    let npx_fallback = config::get_npx_fallback_enabled(&app_handle)?;
+    Ok(find_claude_acp_launcher(npx_fallback).is_some())
+}
+
+/// Per-phase latency percentiles over recent prompts
+#[tauri::command]
+pub(crate) async fn get_latency_report(
+    state: State<'_, AppState>,
+) -> Result<LatencyReport, String> {
+    let samples = state.latency_samples.lock().await;
+    Ok(metrics::latency_report(&samples))
+}
+
+/// Time to first chunk, total duration and streaming speed per provider and
+/// model over recent prompts
+#[tauri::command]
+pub(crate) async fn get_performance_stats(
+    state: State<'_, AppState>,
+) -> Result<PerformanceStats, String> {
+    let samples = state.latency_samples.lock().await;
+    Ok(metrics::performance_stats(&samples))
+}
+
+#[tauri::command]
+pub(crate) async fn get_prompt_timeout_secs(app: AppHandle) -> Result<u64, String> {
+    config::get_prompt_timeout_secs(&app)
+}
+
+/// Set how long a prompt may run before the agent is killed; `None` restores the default
+#[tauri::command]
+pub(crate) async fn set_prompt_timeout_secs(
+    app: AppHandle,
+    secs: Option<u64>,
+) -> Result<(), String> {
+    if secs == Some(0) {
+        return Err("Prompt timeout must be at least 1 second".to_string());
+    }
+    config::set_prompt_timeout_secs(&app, secs)
+}
+
+#[tauri::command]
+pub(crate) async fn get_permission_timeout_secs(app: AppHandle) -> Result<u64, String> {
+    config::get_permission_timeout_secs(&app)
+}
+
+/// Set how long a permission request waits for an answer before it is
+/// cancelled; `None` restores the default
+#[tauri::command]
+pub(crate) async fn set_permission_timeout_secs(
+    app: AppHandle,
+    secs: Option<u64>,
+) -> Result<(), String> {
+    if secs == Some(0) {
+        return Err("Permission timeout must be at least 1 second".to_string());
+    }
+    config::set_permission_timeout_secs(&app, secs)
+}
+
+#[tauri::command]
+pub(crate) async fn get_note_resource_links(app: AppHandle) -> Result<bool, String> {
+    config::get_note_resource_links(&app)
+}
+
+/// Send notes referenced by a message as resource links the agent reads on
+/// demand, instead of prepending their text. Sessions that can't serve
+/// the reads still send the text.
+#[tauri::command]
+pub(crate) async fn set_note_resource_links(app: AppHandle, enabled: bool) -> Result<(), String> {
+    config::set_note_resource_links(&app, enabled)
+}
+
+#[tauri::command]
+pub(crate) async fn get_system_prompt(app: AppHandle) -> Result<Option<String>, String> {
+    config::get_system_prompt(&app)
+}
+
+/// Set the instructions sent ahead of every prompt; `None` or blank clears them
+#[tauri::command]
+pub(crate) async fn set_system_prompt(
+    app: AppHandle,
+    prompt: Option<String>,
+) -> Result<(), String> {
+    if prompt
+        .as_ref()
+        .is_some_and(|p| p.len() > MAX_SYSTEM_PROMPT_BYTES)
+    {
+        return Err(format!(
+            "System prompt is too long (limit {MAX_SYSTEM_PROMPT_BYTES} bytes)"
+        ));
+    }
+    config::set_system_prompt(&app, prompt.filter(|p| !p.trim().is_empty()))
+}
+
+#[tauri::command]
+pub(crate) async fn get_prompt_preamble(app: AppHandle) -> Result<PreambleSettings, String> {
+    config::get_prompt_preamble(&app)
+}
+
+/// Configure the date line and other details sent ahead of every prompt
+#[tauri::command]
+pub(crate) async fn set_prompt_preamble(
+    app: AppHandle,
+    settings: PreambleSettings,
+) -> Result<(), String> {
+    validate_preamble(&settings)?;
+    config::set_prompt_preamble(&app, &settings)
+}
+
+/// Approximate tokens of `text` for the given (or default) provider's
+/// model family, with that model's context window for a size meter
+#[tauri::command]
+pub(crate) async fn count_tokens(
+    app: AppHandle,
+    text: String,
+    provider: Option<AgentProvider>,
+    model: Option<String>,
+) -> Result<TokenCount, String> {
+    let provider = match provider {
+        Some(provider) => provider,
+        None => config::get_default_provider(&app)?,
+    };
+    Ok(TokenCount {
+        tokens: tokens::count_tokens(&text, &provider),
+        context_window: tokens::context_window(&provider, model.as_deref()),
+    })
+}
+
+#[tauri::command]
+pub(crate) async fn get_image_max_dimension(app: AppHandle) -> Result<u32, String> {
+    config::get_image_max_dimension(&app)
+}
+
+/// Set the longest edge images are scaled down to before sending; `None`
+/// restores the default
+#[tauri::command]
+pub(crate) async fn set_image_max_dimension(
+    app: AppHandle,
+    pixels: Option<u32>,
+) -> Result<(), String> {
+    if pixels.is_some_and(|p| p < MIN_IMAGE_MAX_DIMENSION) {
+        return Err(format!(
+            "Image size limit must be at least {MIN_IMAGE_MAX_DIMENSION} pixels"
+        ));
+    }
+    config::set_image_max_dimension(&app, pixels)
+}
+
+#[tauri::command]
+pub(crate) async fn get_retry_on_crash(app: AppHandle) -> Result<bool, String> {
+    config::get_retry_on_crash(&app)
+}
+
+/// Retry a prompt once automatically when the agent crashes before answering
+#[tauri::command]
+pub(crate) async fn set_retry_on_crash(app: AppHandle, enabled: bool) -> Result<(), String> {
+    config::set_retry_on_crash(&app, enabled)
+}
+
+#[tauri::command]
+pub(crate) async fn get_max_retries(app: AppHandle) -> Result<u32, String> {
+    config::get_max_retries(&app)
+}
+
+/// How often a prompt is retried, with growing delays, after rate-limit
+/// and overload errors; 0 turns retries off and `None` restores the default
+#[tauri::command]
+pub(crate) async fn set_max_retries(app: AppHandle, retries: Option<u32>) -> Result<(), String> {
+    if retries.is_some_and(|retries| retries > MAX_RETRIES_LIMIT) {
+        return Err(format!("At most {MAX_RETRIES_LIMIT} retries are allowed"));
+    }
+    config::set_max_retries(&app, retries)
+}
+
+#[tauri::command]
+pub(crate) async fn get_compact_ancestors(app: AppHandle) -> Result<bool, String> {
+    config::get_compact_ancestors(&app)
+}
+
+/// Summarize older ancestor messages of deep branches instead of replaying
+/// them verbatim
+#[tauri::command]
+pub(crate) async fn set_compact_ancestors(app: AppHandle, enabled: bool) -> Result<(), String> {
+    config::set_compact_ancestors(&app, enabled)
+}
+
+/// Running and waiting generations
+#[tauri::command]
+pub(crate) async fn get_generation_queue(
+    state: State<'_, AppState>,
+) -> Result<GenerationQueueSnapshot, String> {
+    Ok(state.generation_queue.snapshot())
+}
+
+/// Limit how many prompts may generate at once; extra prompts wait in a queue
+#[tauri::command]
+pub(crate) async fn set_max_concurrent_generations(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    max: usize,
+) -> Result<(), String> {
+    if max == 0 {
+        return Err("At least one generation must be allowed".to_string());
+    }
+    config::set_max_concurrent_generations(&app, max)?;
+    state.generation_queue.set_max_concurrent(max);
+    Ok(())
+}
+
+/// Stream chunks for `node_id` with sequence numbers from `from_seq` on, so a
+/// reloaded webview can recover a generation that is still in progress
+#[tauri::command]
+pub(crate) async fn replay_stream(
+    state: State<'_, AppState>,
+    node_id: String,
+    from_seq: u64,
+) -> Result<Vec<ChunkPayload>, String> {
+    Ok(state.stream_replay.replay(&node_id, from_seq))
+}
+
+/// Answer text of generations a crash cut short in an earlier run, oldest
+/// first, so the frontend can offer to restore it into the nodes
+#[tauri::command]
+pub(crate) async fn recover_pending_content(
+    app: AppHandle,
+) -> Result<Vec<RecoveredContent>, String> {
+    let dir = recovery::recovery_dir(&app)?;
+    tokio::task::spawn_blocking(move || {
+        recovery::pending_content(&dir)
+            .into_iter()
+            .map(|recovered| recovered.content)
+            .collect()
+    })
+    .await
+    .map_err(|e| format!("Recovery task failed: {e}"))
+}
+
+/// Forget recovered content for `node_ids` once it has been restored or declined
+#[tauri::command]
+pub(crate) async fn dismiss_recovered_content(
+    app: AppHandle,
+    node_ids: Vec<String>,
+) -> Result<(), String> {
+    let dir = recovery::recovery_dir(&app)?;
+    tokio::task::spawn_blocking(move || recovery::dismiss(&dir, &node_ids))
+        .await
+        .map_err(|e| format!("Recovery task failed: {e}"))?
 }
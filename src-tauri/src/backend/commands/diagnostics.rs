@@ -0,0 +1,302 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+
+use tauri::{AppHandle, State};
+
+use super::providers::{
+    check_provider_availability, check_provider_version, find_provider_executable,
+};
+use crate::backend::acp::process::{find_claude_acp_launcher, ClaudeAcpLauncher};
+use crate::backend::acp::sessions::run_handshake_session;
+use crate::backend::analytics::{self, UsageRange, UsageReport};
+use crate::backend::audit::{self, AuditEntry};
+use crate::backend::config;
+use crate::backend::logging::{self, LogLine};
+use crate::backend::metrics::elapsed_ms;
+use crate::backend::runtime::run_localset_blocking;
+use crate::backend::safe_mode;
+use crate::backend::state::AppState;
+use crate::backend::types::{
+    AgentProvider, HealthCheck, HealthCheckKind, HealthReport, HealthStatus, SpawnConfig,
+};
+
+#[tauri::command]
+pub(crate) async fn get_safe_mode(app: AppHandle) -> Result<bool, String> {
+    Ok(safe_mode::is_enabled(&app))
+}
+
+/// Toggle safe mode now and for future launches (until turned off again)
+#[tauri::command]
+pub(crate) async fn set_safe_mode(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    enabled: bool,
+) -> Result<(), String> {
+    config::set_safe_mode(&app, enabled)?;
+    state.safe_mode.store(enabled, Ordering::SeqCst);
+    tracing::info!("Safe mode set to: {}", enabled);
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) async fn get_audit_log(
+    app: AppHandle,
+    limit: Option<usize>,
+) -> Result<Vec<AuditEntry>, String> {
+    let path = audit::audit_log_path(&app)?;
+    audit::read_entries(&path, limit.unwrap_or(500))
+}
+
+#[tauri::command]
+pub(crate) async fn clear_audit_log(app: AppHandle) -> Result<(), String> {
+    let path = audit::audit_log_path(&app)?;
+    audit::clear(&path)?;
+    tracing::info!("Audit log cleared");
+    Ok(())
+}
+
+/// The last `lines` log events at `level` (default `info`) or more severe,
+/// oldest first, for the debug panel. `trace_id` (from a failed generation's
+/// error or events) keeps only that generation's events.
+#[tauri::command]
+pub(crate) async fn get_recent_logs(
+    app: AppHandle,
+    lines: Option<usize>,
+    level: Option<String>,
+    trace_id: Option<String>,
+) -> Result<Vec<LogLine>, String> {
+    let min_level = match level.as_deref() {
+        Some(level) => level
+            .parse()
+            .map_err(|_| format!("Unknown log level: {level}"))?,
+        None => tracing::Level::INFO,
+    };
+    let dir = logging::log_dir(&app)?;
+    tokio::task::spawn_blocking(move || {
+        logging::recent_logs(&dir, lines.unwrap_or(200), min_level, trace_id.as_deref())
+    })
+    .await
+    .map_err(|e| format!("Log read task failed: {e}"))
+}
+
+#[tauri::command]
+pub(crate) async fn get_analytics_enabled(app: AppHandle) -> Result<bool, String> {
+    config::get_analytics_enabled(&app)
+}
+
+/// Opt in to (or out of) local usage analytics. Turning it off keeps what
+/// was recorded; `clear_usage_data` deletes it.
+#[tauri::command]
+pub(crate) async fn set_analytics_enabled(app: AppHandle, enabled: bool) -> Result<(), String> {
+    config::set_analytics_enabled(&app, enabled)?;
+    tracing::info!("Usage analytics set to: {}", enabled);
+    Ok(())
+}
+
+/// Prompts per day, providers, models and durations over `range`. The data
+/// lives only in the app data dir.
+#[tauri::command]
+pub(crate) async fn get_usage_report(
+    app: AppHandle,
+    range: UsageRange,
+) -> Result<UsageReport, String> {
+    let path = analytics::analytics_db_path(&app)?;
+    tokio::task::spawn_blocking(move || {
+        let conn = analytics::open_analytics(&path)?;
+        analytics::usage_report(&conn, range, chrono::Local::now().date_naive())
+    })
+    .await
+    .map_err(|e| format!("Usage report task failed: {e}"))?
+}
+
+#[tauri::command]
+pub(crate) async fn clear_usage_data(app: AppHandle) -> Result<(), String> {
+    let path = analytics::analytics_db_path(&app)?;
+    tokio::task::spawn_blocking(move || analytics::clear_usage(&analytics::open_analytics(&path)?))
+        .await
+        .map_err(|e| format!("Clear usage task failed: {e}"))??;
+    tracing::info!("Usage analytics cleared");
+    Ok(())
+}
+
+fn health_check(
+    kind: HealthCheckKind,
+    provider: Option<&AgentProvider>,
+    status: HealthStatus,
+    detail: impl Into<String>,
+    started: Instant,
+) -> HealthCheck {
+    HealthCheck {
+        kind,
+        provider: provider.cloned(),
+        status,
+        detail: detail.into(),
+        duration_ms: elapsed_ms(started),
+    }
+}
+
+fn sidecar_check(npx_fallback: bool) -> HealthCheck {
+    let started = Instant::now();
+    let (status, detail) = match find_claude_acp_launcher(npx_fallback) {
+        Some(ClaudeAcpLauncher::Sidecar(path)) => (HealthStatus::Ok, path.display().to_string()),
+        Some(ClaudeAcpLauncher::Npx(path)) => (
+            HealthStatus::Warning,
+            format!(
+                "Sidecar not found; using the slower npx fallback ({})",
+                path.display()
+            ),
+        ),
+        None => (
+            HealthStatus::Error,
+            "claude-code-acp sidecar not found".to_string(),
+        ),
+    };
+    health_check(HealthCheckKind::Sidecar, None, status, detail, started)
+}
+
+async fn notes_directory_check(notes_directory: Option<&Path>) -> HealthCheck {
+    let started = Instant::now();
+    let (status, detail) = match notes_directory {
+        None => (
+            HealthStatus::Error,
+            "No notes directory configured".to_string(),
+        ),
+        Some(dir) => match tokio::fs::read_dir(dir).await {
+            Ok(_) => (HealthStatus::Ok, dir.display().to_string()),
+            Err(e) => (
+                HealthStatus::Error,
+                format!("Cannot read {}: {e}", dir.display()),
+            ),
+        },
+    };
+    health_check(
+        HealthCheckKind::NotesDirectory,
+        None,
+        status,
+        detail,
+        started,
+    )
+}
+
+/// CLI discovery, version and `initialize` handshake for one provider; each
+/// check is skipped once one before it fails
+async fn provider_checks(
+    provider: AgentProvider,
+    spawn_config: SpawnConfig,
+    notes_directory: Option<PathBuf>,
+) -> Vec<HealthCheck> {
+    let checked = Some(&provider);
+    let skipped = |kind, reason: &str| {
+        health_check(kind, checked, HealthStatus::Skipped, reason, Instant::now())
+    };
+
+    let started = Instant::now();
+    let Some(executable) = find_provider_executable(&provider, &spawn_config.provider_paths) else {
+        return vec![
+            health_check(
+                HealthCheckKind::Cli,
+                checked,
+                HealthStatus::Error,
+                format!("{} not found", provider.display_name()),
+                started,
+            ),
+            skipped(HealthCheckKind::Version, "CLI not found"),
+            skipped(HealthCheckKind::Handshake, "CLI not found"),
+        ];
+    };
+    let mut checks = vec![health_check(
+        HealthCheckKind::Cli,
+        checked,
+        HealthStatus::Ok,
+        executable.display().to_string(),
+        started,
+    )];
+
+    let started = Instant::now();
+    let version = check_provider_version(provider.clone(), &spawn_config.provider_paths).await;
+    let (status, detail) = match (version.meets_minimum, version.error_message) {
+        (Some(true), _) => (HealthStatus::Ok, version.version.unwrap_or_default()),
+        (Some(false), message) => (HealthStatus::Error, message.unwrap_or_default()),
+        (None, message) => (
+            HealthStatus::Warning,
+            message.unwrap_or_else(|| "Version unknown".to_string()),
+        ),
+    };
+    let version_failed = status == HealthStatus::Error;
+    checks.push(health_check(
+        HealthCheckKind::Version,
+        checked,
+        status,
+        detail,
+        started,
+    ));
+
+    let availability = check_provider_availability(&provider, &spawn_config);
+    let handshake_check = match (notes_directory, version_failed) {
+        (_, true) => skipped(HealthCheckKind::Handshake, "CLI too old"),
+        _ if !availability.available => skipped(
+            HealthCheckKind::Handshake,
+            &availability
+                .error_message
+                .unwrap_or_else(|| "Provider unavailable".to_string()),
+        ),
+        (None, _) => skipped(HealthCheckKind::Handshake, "No notes directory"),
+        (Some(dir), false) => {
+            let started = Instant::now();
+            let handshake_provider = provider.clone();
+            let result = run_localset_blocking(move || async move {
+                run_handshake_session(dir, handshake_provider, spawn_config).await
+            })
+            .await;
+            match result {
+                Ok(()) => health_check(
+                    HealthCheckKind::Handshake,
+                    checked,
+                    HealthStatus::Ok,
+                    format!("Initialized in {} ms", elapsed_ms(started)),
+                    started,
+                ),
+                Err(e) => health_check(
+                    HealthCheckKind::Handshake,
+                    checked,
+                    HealthStatus::Error,
+                    e,
+                    started,
+                ),
+            }
+        }
+    };
+    checks.push(handshake_check);
+    checks
+}
+
+/// Check the sidecar, each provider's CLI (presence, version and an
+/// `initialize` handshake) and the notes directory, for the Diagnostics page.
+/// Providers are checked concurrently; a handshake can take a few seconds.
+#[tauri::command]
+pub(crate) async fn run_health_check(app: AppHandle) -> Result<HealthReport, String> {
+    let spawn_config = config::get_spawn_config(&app)?;
+    let notes_directory = config::get_notes_directory_optional(&app)?.map(PathBuf::from);
+
+    let mut checks = vec![sidecar_check(spawn_config.npx_fallback)];
+    let notes_check = notes_directory_check(notes_directory.as_deref()).await;
+    let readable_notes = notes_directory.filter(|_| notes_check.status == HealthStatus::Ok);
+    checks.push(notes_check);
+
+    let (claude, gemini) = futures::future::join(
+        provider_checks(
+            AgentProvider::ClaudeCode,
+            spawn_config.clone(),
+            readable_notes.clone(),
+        ),
+        provider_checks(AgentProvider::GeminiCli, spawn_config, readable_notes),
+    )
+    .await;
+    checks.extend(claude);
+    checks.extend(gemini);
+
+    let report = HealthReport::new(checks);
+    tracing::info!("Health check finished, healthy: {}", report.healthy);
+    Ok(report)
+}
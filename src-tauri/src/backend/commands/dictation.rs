@@ -0,0 +1,83 @@
+use tauri::{AppHandle, Emitter, State};
+
+use crate::backend::acp::process::find_whisper_stream_executable;
+use crate::backend::config;
+use crate::backend::dictation::{
+    resolve_model, validate_dictation_settings, DictationSession, DictationSettings,
+};
+use crate::backend::state::AppState;
+use crate::backend::types::TranscriptionPayload;
+
+fn emit_transcription(app: &AppHandle, text: &str, is_final: bool) {
+    let payload = TranscriptionPayload {
+        text: text.to_string(),
+        is_final,
+    };
+    if let Err(e) = app.emit("transcription-partial", payload) {
+        tracing::error!("Failed to emit transcription-partial: {:?}", e);
+    }
+}
+
+/// Start recording from the default microphone and transcribing locally.
+/// The transcript so far arrives in `transcription-partial` events.
+#[tauri::command]
+pub(crate) async fn start_dictation(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let mut dictation = state.dictation.lock().await;
+    if dictation.is_some() {
+        return Err("Dictation is already running".to_string());
+    }
+    let settings = config::get_dictation_settings(&app)?;
+    validate_dictation_settings(&settings)?;
+    let model = resolve_model(&settings)?;
+    let binary = find_whisper_stream_executable(settings.binary.as_deref()).ok_or_else(|| {
+        "whisper-stream not found.\n\
+         Install via: brew install whisper-cpp"
+            .to_string()
+    })?;
+
+    let events = app.clone();
+    *dictation = Some(DictationSession::start(
+        &binary,
+        &model,
+        settings.language.as_deref(),
+        &state.children,
+        move |text| emit_transcription(&events, text, false),
+    )?);
+    Ok(())
+}
+
+/// Stop recording; returns the final transcript, which is also emitted with
+/// `is_final` set
+#[tauri::command]
+pub(crate) async fn stop_dictation(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let session = state
+        .dictation
+        .lock()
+        .await
+        .take()
+        .ok_or_else(|| "Dictation is not running".to_string())?;
+    let text = session.stop().await?;
+    tracing::info!("Dictation stopped ({} characters)", text.len());
+    emit_transcription(&app, &text, true);
+    Ok(text)
+}
+
+#[tauri::command]
+pub(crate) async fn get_dictation_settings(app: AppHandle) -> Result<DictationSettings, String> {
+    config::get_dictation_settings(&app)
+}
+
+#[tauri::command]
+pub(crate) async fn set_dictation_settings(
+    app: AppHandle,
+    settings: DictationSettings,
+) -> Result<(), String> {
+    validate_dictation_settings(&settings)?;
+    config::set_dictation_settings(&app, &settings)
+}
@@ -0,0 +1,277 @@
+use std::path::{Path, PathBuf};
+
+use tauri::AppHandle;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use super::git::spawn_autocommit;
+use super::projects::{
+    export_file_name, project_for_export, save_export, save_export_with_assets,
+    validate_project_path, ExportKind, MARKDOWN_EXPORT,
+};
+use crate::backend::audit;
+use crate::backend::canvas;
+use crate::backend::config;
+use crate::backend::conversation_import::{self, ConversationFormat};
+use crate::backend::diagram::{self, DiagramFormat};
+use crate::backend::export::{
+    branch, node_metadata, tree_markdown, tree_order, MarkdownExportOptions,
+};
+use crate::backend::note_edit::write_atomic;
+use crate::backend::note_files::validate_path_in_notes_dir;
+use crate::backend::opml;
+use crate::backend::pdf::{self, PdfDocument, PdfSection};
+use crate::backend::project_file::{node_title, ProjectFile};
+use crate::backend::transcript;
+
+/// Imported files larger than this are refused. Chat service exports of
+/// long-time users run to a few hundred megabytes.
+const MAX_IMPORT_BYTES: u64 = 512 * 1024 * 1024;
+
+const OPML_EXPORT: ExportKind = ExportKind {
+    name: "OPML",
+    extension: "opml",
+};
+
+const PDF_EXPORT: ExportKind = ExportKind {
+    name: "PDF",
+    extension: "pdf",
+};
+
+/// Unsupported characters named in the PDF export error
+const MAX_LISTED_CHARS: usize = 10;
+
+/// Project name for export titles: the file stem, or "Untitled"
+fn project_title(path: Option<&str>) -> String {
+    path.and_then(|p| Path::new(p).file_stem())
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "Untitled".to_string())
+}
+
+/// Read a file to import; it must live in a configured workspace
+fn read_import_file(app: &AppHandle, path: &str) -> Result<String, String> {
+    let path = validate_project_path(app, path)?;
+    let size = std::fs::metadata(&path)
+        .map_err(|e| format!("Failed to read import file: {e}"))?
+        .len();
+    if size > MAX_IMPORT_BYTES {
+        return Err(format!(
+            "Import file is too large ({size} bytes, limit {MAX_IMPORT_BYTES})"
+        ));
+    }
+    std::fs::read_to_string(&path).map_err(|e| format!("Failed to read import file: {e}"))
+}
+
+/// Export the project at `path` (or `data`, when it has unsaved edits) as an
+/// OPML outline
+#[tauri::command]
+pub(crate) async fn export_opml(
+    app: AppHandle,
+    path: Option<String>,
+    data: Option<String>,
+    default_name: String,
+) -> Result<Option<String>, String> {
+    let project = project_for_export(&app, data, path.as_deref())?;
+    let file_name = export_file_name(&app, &default_name, path.clone(), None, "opml")?;
+    let xml = opml::to_opml(&project, &project_title(path.as_deref()));
+    save_export(&app, &OPML_EXPORT, xml.as_bytes(), &file_name)
+}
+
+/// Convert the OPML file at `path` into project JSON. The frontend opens it
+/// as a new, unsaved project.
+#[tauri::command]
+pub(crate) async fn import_opml(app: AppHandle, path: String) -> Result<String, String> {
+    let xml = read_import_file(&app, &path)?;
+    let project = opml::from_opml(&xml, chrono::Utc::now().timestamp_millis())?;
+    tracing::info!(
+        "Imported {} nodes from OPML {}",
+        project.graph.nodes.len(),
+        path
+    );
+    project_json(&project)
+}
+
+fn project_json(project: &ProjectFile) -> Result<String, String> {
+    serde_json::to_string_pretty(&project.to_value()?)
+        .map_err(|e| format!("Failed to serialize project: {e}"))
+}
+
+/// Convert a ChatGPT or Claude `conversations.json` export into project
+/// JSON, one tree per conversation. The frontend opens it as a new, unsaved
+/// project.
+#[tauri::command]
+pub(crate) async fn import_conversation(
+    app: AppHandle,
+    path: String,
+    format: ConversationFormat,
+) -> Result<String, String> {
+    let data = read_import_file(&app, &path)?;
+    tokio::task::spawn_blocking(move || {
+        let project = conversation_import::import_conversations(
+            &data,
+            format,
+            chrono::Utc::now().timestamp_millis(),
+        )?;
+        tracing::info!(
+            "Imported {} nodes from {:?} export",
+            project.graph.nodes.len(),
+            format
+        );
+        project_json(&project)
+    })
+    .await
+    .map_err(|e| format!("Conversation import failed: {e}"))?
+}
+
+/// Write the project as an Obsidian `.canvas` file into the notes directory,
+/// replacing an earlier export of the same name. Returns the written path.
+#[tauri::command]
+pub(crate) async fn export_json_canvas(
+    app: AppHandle,
+    path: Option<String>,
+    data: Option<String>,
+    default_name: String,
+) -> Result<String, String> {
+    let notes_dir = config::get_notes_directory_optional(&app)?
+        .map(PathBuf::from)
+        .ok_or_else(|| "Notes directory not configured. Please set it in settings.".to_string())?;
+    let project = project_for_export(&app, data, path.as_deref())?;
+    let file_name = export_file_name(&app, &default_name, path, None, "canvas")?;
+    let target = validate_path_in_notes_dir(&notes_dir.join(file_name), &notes_dir)?;
+
+    let json = serde_json::to_string_pretty(&canvas::to_canvas(&project.graph))
+        .map_err(|e| format!("Failed to serialize canvas: {e}"))?;
+    write_atomic(&target, json.as_bytes())?;
+    tracing::info!("Exported JSON Canvas to: {:?}", target);
+    spawn_autocommit(&app, target.clone(), "export");
+    Ok(target.to_string_lossy().into_owned())
+}
+
+/// Export the conversation leading to `node` (or the whole tree) as a
+/// paginated PDF with a title page and per-node metadata. Text with
+/// characters the embedded fonts lack (CJK, emoji, ...) is refused unless
+/// `allow_replacements` accepts them printing as `?`.
+#[tauri::command]
+pub(crate) async fn export_pdf(
+    app: AppHandle,
+    path: Option<String>,
+    data: Option<String>,
+    node: Option<String>,
+    default_name: String,
+    allow_replacements: bool,
+) -> Result<Option<String>, String> {
+    let project = project_for_export(&app, data, path.as_deref())?;
+    let graph = &project.graph;
+    let (nodes, branch_title) = match &node {
+        Some(id) => {
+            let leaf = graph
+                .node(id)
+                .ok_or_else(|| format!("Node not found: {id}"))?;
+            (branch(graph, id), Some(node_title(leaf)))
+        }
+        None => (tree_order(graph), None),
+    };
+
+    let mut subtitle = Vec::new();
+    if let Some(title) = &branch_title {
+        subtitle.push(format!("Branch: {title}"));
+    }
+    subtitle.push(format!(
+        "{} nodes · exported {}",
+        nodes.len(),
+        chrono::Local::now().format("%Y-%m-%d %H:%M")
+    ));
+    let doc = PdfDocument {
+        title: project_title(path.as_deref()),
+        subtitle,
+        sections: nodes
+            .iter()
+            .map(|node| PdfSection {
+                title: Some(node_title(node))
+                    .filter(|t| !t.is_empty())
+                    .unwrap_or_else(|| "Untitled".to_string()),
+                metadata: node_metadata(node),
+                body: node.content.clone(),
+            })
+            .collect(),
+    };
+
+    let unsupported = pdf::unsupported_chars(&doc);
+    if !unsupported.is_empty() {
+        let sample: String = unsupported.iter().take(MAX_LISTED_CHARS).collect();
+        if !allow_replacements {
+            return Err(format!(
+                "{} characters in this export can't be shown in a PDF and would print as ? \
+                 (such as {sample}). Export as markdown to keep them, or export anyway.",
+                unsupported.len()
+            ));
+        }
+        tracing::warn!(
+            "PDF export replaces {} unsupported characters ({}) with ?",
+            unsupported.len(),
+            sample
+        );
+    }
+
+    let bytes = tokio::task::spawn_blocking(move || pdf::render_pdf(&doc))
+        .await
+        .map_err(|e| format!("PDF rendering failed: {e}"))??;
+    let file_name = export_file_name(&app, &default_name, path, branch_title, "pdf")?;
+    save_export(&app, &PDF_EXPORT, &bytes, &file_name)
+}
+
+/// The tree's shape as Mermaid or DOT text, for embedding in documents
+#[tauri::command]
+pub(crate) async fn export_graph(
+    app: AppHandle,
+    path: Option<String>,
+    data: Option<String>,
+    format: DiagramFormat,
+) -> Result<String, String> {
+    let project = project_for_export(&app, data, path.as_deref())?;
+    Ok(diagram::render_diagram(&project.graph, format))
+}
+
+/// Export the conversation leading to `node_id` as annotated markdown:
+/// prompts, responses, and the thoughts and tool calls (with their targets)
+/// recorded in the audit log for each response
+#[tauri::command]
+pub(crate) async fn export_transcript(
+    app: AppHandle,
+    node_id: String,
+    path: Option<String>,
+    data: Option<String>,
+    default_name: String,
+) -> Result<Option<String>, String> {
+    let project = project_for_export(&app, data, path.as_deref())?;
+    let graph = &project.graph;
+    let leaf = graph
+        .node(&node_id)
+        .ok_or_else(|| format!("Node not found: {node_id}"))?;
+    let nodes = branch(graph, &node_id);
+
+    let activity = audit::read_entries(&audit::audit_log_path(&app)?, usize::MAX)?;
+    let content =
+        transcript::transcript_markdown(&project_title(path.as_deref()), &nodes, &activity);
+    let file_name = export_file_name(&app, &default_name, path, Some(node_title(leaf)), "md")?;
+    save_export_with_assets(&app, &MARKDOWN_EXPORT, &content, &file_name)
+}
+
+/// Copy `node_id` and its descendants to the system clipboard as
+/// nested-heading markdown. Written natively because the webview clipboard
+/// API fails on large trees.
+#[tauri::command]
+pub(crate) async fn copy_subtree_markdown(
+    app: AppHandle,
+    project_json: String,
+    node_id: String,
+    options: Option<MarkdownExportOptions>,
+) -> Result<(), String> {
+    let project = ProjectFile::parse(&project_json)?;
+    if project.graph.node(&node_id).is_none() {
+        return Err(format!("Node not found: {node_id}"));
+    }
+    let content = tree_markdown(&project.graph, Some(&node_id), options.unwrap_or_default());
+    app.clipboard()
+        .write_text(content)
+        .map_err(|e| format!("Failed to copy to clipboard: {e}"))
+}
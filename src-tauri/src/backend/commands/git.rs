@@ -0,0 +1,54 @@
+use std::path::PathBuf;
+
+use tauri::AppHandle;
+
+use super::projects::validate_project_path;
+use crate::backend::config;
+use crate::backend::git::{commit_file, commit_message, file_log, GitLogEntry};
+use crate::backend::safe_mode;
+
+const DEFAULT_LOG_LIMIT: usize = 50;
+
+/// Commit `path` in the background if git auto-commit is enabled. Failures
+/// (no identity configured, hooks rejecting the commit) are only logged so
+/// they never fail the save itself.
+pub(super) fn spawn_autocommit(app: &AppHandle, path: PathBuf, action: &'static str) {
+    if safe_mode::is_enabled(app) || !config::get_git_autocommit_enabled(app).unwrap_or(false) {
+        return;
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        match commit_file(&path, &commit_message(action, &path)) {
+            Ok(true) => tracing::info!("Committed {:?} to git", path),
+            Ok(false) => {}
+            Err(e) => tracing::warn!("Git auto-commit of {:?} failed: {}", path, e),
+        }
+    });
+}
+
+#[tauri::command]
+pub(crate) async fn get_git_autocommit_enabled(app: AppHandle) -> Result<bool, String> {
+    config::get_git_autocommit_enabled(&app)
+}
+
+#[tauri::command]
+pub(crate) async fn set_git_autocommit_enabled(
+    app: AppHandle,
+    enabled: bool,
+) -> Result<(), String> {
+    config::set_git_autocommit_enabled(&app, enabled)
+}
+
+/// Commit history of a project file, newest first; empty if the project is
+/// not in a git repository
+#[tauri::command]
+pub(crate) async fn get_project_git_log(
+    app: AppHandle,
+    path: String,
+    limit: Option<usize>,
+) -> Result<Vec<GitLogEntry>, String> {
+    let validated_path = validate_project_path(&app, &path)?;
+    let limit = limit.unwrap_or(DEFAULT_LOG_LIMIT);
+    tokio::task::spawn_blocking(move || file_log(&validated_path, limit))
+        .await
+        .map_err(|e| format!("Git log task failed: {e}"))?
+}
@@ -0,0 +1,82 @@
+use std::path::{Path, PathBuf};
+
+use tauri::{AppHandle, Manager};
+
+use super::projects::validate_project_path;
+use crate::backend::history::{history_path, NodeVersion, ProjectHistory};
+use crate::backend::project_file::{read_project_data, ProjectFile};
+use crate::backend::safe_mode;
+use crate::backend::state::AppState;
+
+fn load_history(project: &Path) -> Result<ProjectHistory, String> {
+    let path = history_path(project).ok_or_else(|| "Invalid project path".to_string())?;
+    ProjectHistory::load(&path)
+}
+
+/// Record changed node contents of a just-saved project in its history
+/// file, in the background. Nodes still generating are left until their
+/// answer is complete. Failures are only logged.
+pub(super) fn spawn_history_update(app: &AppHandle, project: PathBuf) {
+    if safe_mode::is_enabled(app) {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let _writing = state.history_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let result = (|| {
+            let Some(path) = history_path(&project) else {
+                return Ok(());
+            };
+            let file = ProjectFile::parse(&read_project_data(&project)?)?;
+            let mut history = ProjectHistory::load(&path)?;
+            let generating = state.generations.active_nodes();
+            let now = chrono::Utc::now().timestamp_millis();
+            if history.record(&file, &generating, now) {
+                history.save(&path)?;
+            }
+            Ok::<_, String>(())
+        })();
+        if let Err(e) = result {
+            tracing::warn!("Node history of {:?} not updated: {}", project, e);
+        }
+    });
+}
+
+/// Earlier contents of a node, oldest first. Versions are recorded as the
+/// project is saved, after edits and regenerations.
+#[tauri::command]
+pub(crate) async fn get_node_history(
+    app: AppHandle,
+    project_path: String,
+    node_id: String,
+) -> Result<Vec<NodeVersion>, String> {
+    let project = validate_project_path(&app, &project_path)?;
+    tokio::task::spawn_blocking(move || Ok(load_history(&project)?.versions(&node_id).to_vec()))
+        .await
+        .map_err(|e| format!("Failed to read node history: {e}"))?
+}
+
+/// Content of an earlier version of a node, to put back in the open
+/// project. Once saved, the restored text becomes the newest version, so
+/// the text it replaces stays in the history too.
+#[tauri::command]
+pub(crate) async fn restore_node_version(
+    app: AppHandle,
+    project_path: String,
+    node_id: String,
+    version: u32,
+) -> Result<String, String> {
+    let project = validate_project_path(&app, &project_path)?;
+    let id = node_id.clone();
+    let content = tokio::task::spawn_blocking(move || {
+        load_history(&project)?
+            .version(&id, version)
+            .map(|v| v.content.clone())
+            .ok_or_else(|| format!("Version {version} of node {id} not found"))
+    })
+    .await
+    .map_err(|e| format!("Failed to read node history: {e}"))??;
+    tracing::info!("Restoring version {} of node {}", version, node_id);
+    Ok(content)
+}
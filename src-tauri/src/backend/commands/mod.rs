@@ -1,17 +1,95 @@
+pub(crate) mod background;
+pub(crate) mod batch;
+pub(crate) mod capture;
 pub(crate) mod chat;
+pub(crate) mod diagnostics;
+pub(crate) mod dictation;
+pub(crate) mod export;
+pub(crate) mod git;
+pub(crate) mod history;
+pub(crate) mod notes;
+pub(crate) mod pins;
 pub(crate) mod projects;
 pub(crate) mod providers;
+pub(crate) mod related;
+pub(crate) mod secrets;
 pub(crate) mod summary;
+pub(crate) mod templates;
+pub(crate) mod updates;
+pub(crate) mod workspaces;
 
-pub(crate) use chat::{check_acp_available, respond_to_permission, send_prompt};
+pub(crate) use background::{
+    get_background_job_settings, get_background_jobs, set_background_job_settings,
+    start_background_jobs,
+};
+pub(crate) use batch::batch_generate;
+pub(crate) use capture::{
+    get_quick_capture_shortcut, get_quick_capture_target, quick_capture,
+    set_quick_capture_shortcut, set_quick_capture_target,
+};
+pub(crate) use chat::{
+    check_acp_available, count_tokens, dismiss_recovered_content, generate_alternatives,
+    get_compact_ancestors, get_generation_queue, get_image_max_dimension, get_latency_report,
+    get_max_retries, get_note_resource_links, get_performance_stats, get_permission_timeout_secs,
+    get_prompt_preamble, get_prompt_timeout_secs, get_retry_on_crash, get_system_prompt,
+    recover_pending_content, regenerate_node, replay_stream, respond_to_permission, send_prompt,
+    send_prompt_multi, set_compact_ancestors, set_image_max_dimension,
+    set_max_concurrent_generations, set_max_retries, set_note_resource_links,
+    set_permission_timeout_secs, set_prompt_preamble, set_prompt_timeout_secs, set_retry_on_crash,
+    set_system_prompt,
+};
+pub(crate) use diagnostics::{
+    clear_audit_log, clear_usage_data, get_analytics_enabled, get_audit_log, get_recent_logs,
+    get_safe_mode, get_usage_report, run_health_check, set_analytics_enabled, set_safe_mode,
+};
+pub(crate) use dictation::{
+    get_dictation_settings, set_dictation_settings, start_dictation, stop_dictation,
+};
+pub(crate) use export::{
+    copy_subtree_markdown, export_graph, export_json_canvas, export_opml, export_pdf,
+    export_transcript, import_conversation, import_opml,
+};
+pub(crate) use git::{get_git_autocommit_enabled, get_project_git_log, set_git_autocommit_enabled};
+pub(crate) use history::{get_node_history, restore_node_version};
+pub(crate) use notes::{
+    append_to_note, get_all_tags, get_backlinks, get_note_metadata, get_note_writes_enabled,
+    get_notes_by_tag, get_outgoing_links, read_note, set_note_writes_enabled,
+};
+pub(crate) use pins::{list_pinned, pin_node, unpin_node};
 pub(crate) use projects::{
-    add_recent_project, export_markdown, get_notes_directory, get_recent_projects, load_project,
-    new_project_dialog, open_project_dialog, pick_notes_directory, remove_recent_project,
-    save_project, search_files, set_notes_directory,
+    add_recent_project, close_project, diff_nodes, diff_texts, export_markdown,
+    export_tree_markdown, flush_autosaves, force_unlock_project, get_compress_projects,
+    get_export_filename_template, get_image_assets, get_node_generation_config,
+    get_notes_directory, get_project_stats, get_recent_projects, load_node_content, load_project,
+    load_project_manifest, migrate_project, new_project_dialog, open_project_dialog,
+    pick_notes_directory, query_project_index, query_search_index, queue_autosave,
+    rebuild_search_index, reindex_projects, reload_project_if_changed, remove_recent_project,
+    save_project, search_files, search_note_contents, search_projects, set_compress_projects,
+    set_export_filename_template, set_image_assets, set_notes_directory, start_project_watch,
 };
 pub(crate) use providers::{
-    get_available_models, get_available_providers, get_default_provider, get_model_preferences,
-    get_provider_paths, pick_provider_executable, set_default_provider, set_model_preference,
-    set_provider_path, validate_provider_path,
+    authenticate_provider, get_agent_commands, get_agent_env, get_agent_sandbox_enabled,
+    get_auth_methods, get_available_models, get_available_providers, get_claude_options,
+    get_default_provider, get_env_allowlist, get_feature_matrix, get_gemini_settings,
+    get_model_preferences, get_model_presets, get_npx_fallback_enabled, get_path_lookup_enabled,
+    get_provider_paths, get_provider_versions, get_proxy_settings, get_read_roots,
+    get_session_mode_preferences, get_session_modes, lookup_provider_on_path,
+    pick_provider_executable, remove_agent_env_var, set_agent_env_var, set_agent_sandbox_enabled,
+    set_claude_options, set_default_provider, set_env_allowlist, set_gemini_settings,
+    set_model_preference, set_model_preset, set_npx_fallback_enabled, set_path_lookup_enabled,
+    set_provider_path, set_proxy_settings, set_read_roots, set_session_mode_preference, test_proxy,
+    validate_provider_path,
+};
+pub(crate) use related::{find_similar_nodes, suggest_context};
+pub(crate) use secrets::{delete_secret, has_secret, set_secret};
+pub(crate) use summary::{
+    generate_abstract, generate_summaries, generate_summary, get_bulk_model_overrides,
+    get_summary_style, set_bulk_model_override, set_summary_style, suggest_tags,
+};
+pub(crate) use templates::{create_project_from_template, list_project_templates};
+pub(crate) use updates::{
+    check_for_updates, get_check_updates_on_launch, set_check_updates_on_launch, start_update_check,
+};
+pub(crate) use workspaces::{
+    add_workspace, list_workspaces, remove_workspace, set_active_workspace,
 };
-pub(crate) use summary::generate_summary;
@@ -0,0 +1,238 @@
+use std::path::PathBuf;
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::backend::config;
+use crate::backend::frontmatter::{note_metadata, NoteMetadata};
+use crate::backend::links::{is_markdown, Backlink, LinkGraph, OutgoingLink};
+use crate::backend::note_edit::{append_to_text, write_atomic};
+use crate::backend::note_files::{
+    read_text_prefix, validate_path_in_notes_dir, MAX_READ_NOTE_BYTES,
+};
+use crate::backend::state::AppState;
+use crate::backend::tags::{self, TagCount};
+
+/// Resolve a note path (absolute, or relative to the notes directory) with the
+/// same validation as `load_project`. Returns the notes directory, the
+/// validated absolute path, and the path relative to the notes directory
+/// with `/` separators.
+pub(super) fn resolve_note(
+    app: &AppHandle,
+    note_path: &str,
+) -> Result<(PathBuf, PathBuf, String), String> {
+    let notes_directory = config::get_notes_directory_required(app)?;
+    let validated = validate_path_in_notes_dir(&notes_directory.join(note_path), &notes_directory)?;
+
+    let canonical_notes = std::fs::canonicalize(&notes_directory)
+        .map_err(|e| format!("Failed to resolve notes directory: {e}"))?;
+    let rel_path = validated
+        .strip_prefix(&canonical_notes)
+        .map_err(|_| "Security error: path is outside the notes directory".to_string())?
+        .to_string_lossy()
+        .replace('\\', "/");
+
+    Ok((notes_directory, validated, rel_path))
+}
+
+/// Metadata is parsed from at most this much of a note
+const MAX_METADATA_BYTES: u64 = 1024 * 1024;
+
+/// `read_note` returns at most this much unless the caller asks for less
+const DEFAULT_READ_NOTE_BYTES: u64 = 1024 * 1024;
+
+/// Largest block of text `append_to_note` accepts in one call
+const MAX_APPEND_BYTES: usize = 1024 * 1024;
+
+/// Notes larger than this are not rewritten by `append_to_note`
+pub(super) const MAX_APPEND_TARGET_BYTES: u64 = 8 * 1024 * 1024;
+
+/// A note's text and file metadata
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct NoteContent {
+    /// Path relative to the notes directory
+    pub path: String,
+    pub content: String,
+    /// Whether `content` was cut at the byte limit
+    pub truncated: bool,
+    /// Full file size in bytes
+    pub size: u64,
+    /// Last modification time (RFC 3339), if the platform reports one
+    pub modified: Option<String>,
+}
+
+/// Bring the cached link graph up to date and run `f` against it
+async fn with_link_graph<T: Send + 'static>(
+    app: AppHandle,
+    notes_directory: PathBuf,
+    f: impl FnOnce(&LinkGraph) -> T + Send + 'static,
+) -> Result<T, String> {
+    tokio::task::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let mut graph = state.link_graph.lock().unwrap_or_else(|e| e.into_inner());
+        graph.refresh(&notes_directory);
+        f(&graph)
+    })
+    .await
+    .map_err(|e| format!("Link scan failed: {e}"))
+}
+
+/// Notes whose `[[wikilinks]]` resolve to `note_path`
+#[tauri::command]
+pub(crate) async fn get_backlinks(
+    app: AppHandle,
+    note_path: String,
+) -> Result<Vec<Backlink>, String> {
+    let (notes_directory, _, rel_path) = resolve_note(&app, &note_path)?;
+    with_link_graph(app, notes_directory, move |graph| {
+        graph.backlinks(&rel_path)
+    })
+    .await
+}
+
+/// `[[wikilinks]]` in `note_path`, with their resolved note paths
+#[tauri::command]
+pub(crate) async fn get_outgoing_links(
+    app: AppHandle,
+    note_path: String,
+) -> Result<Vec<OutgoingLink>, String> {
+    let (notes_directory, _, rel_path) = resolve_note(&app, &note_path)?;
+    with_link_graph(app, notes_directory, move |graph| graph.outgoing(&rel_path)).await
+}
+
+/// Tags used across the notes directory, from frontmatter and inline `#tags`,
+/// with the number of notes carrying each
+#[tauri::command]
+pub(crate) async fn get_all_tags(app: AppHandle) -> Result<Vec<TagCount>, String> {
+    let notes_directory = config::get_notes_directory_required(&app)?;
+    tokio::task::spawn_blocking(move || tags::count_tags(&tags::scan_note_tags(&notes_directory)))
+        .await
+        .map_err(|e| format!("Tag scan failed: {e}"))
+}
+
+/// Notes tagged `tag`, including nested tags such as `tag/sub`
+#[tauri::command]
+pub(crate) async fn get_notes_by_tag(app: AppHandle, tag: String) -> Result<Vec<String>, String> {
+    let notes_directory = config::get_notes_directory_required(&app)?;
+    tokio::task::spawn_blocking(move || {
+        tags::notes_with_tag(&tags::scan_note_tags(&notes_directory), &tag)
+    })
+    .await
+    .map_err(|e| format!("Tag scan failed: {e}"))
+}
+
+/// Frontmatter-derived metadata (title, aliases, tags, dates) of a note, for
+/// rich previews when attaching context
+#[tauri::command]
+pub(crate) async fn get_note_metadata(
+    app: AppHandle,
+    path: String,
+) -> Result<NoteMetadata, String> {
+    let (_, validated, _) = resolve_note(&app, &path)?;
+    let fallback_title = validated
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    tokio::task::spawn_blocking(move || {
+        let (text, _) = read_text_prefix(&validated, MAX_METADATA_BYTES)?;
+        Ok(note_metadata(&text, &fallback_title))
+    })
+    .await
+    .map_err(|e| format!("Failed to read note: {e}"))?
+}
+
+/// Read a note inside the notes directory, truncated to `max_bytes`
+#[tauri::command]
+pub(crate) async fn read_note(
+    app: AppHandle,
+    path: String,
+    max_bytes: Option<u64>,
+) -> Result<NoteContent, String> {
+    let (_, validated, rel_path) = resolve_note(&app, &path)?;
+    let max_bytes = max_bytes
+        .unwrap_or(DEFAULT_READ_NOTE_BYTES)
+        .min(MAX_READ_NOTE_BYTES);
+
+    tokio::task::spawn_blocking(move || {
+        let metadata =
+            std::fs::metadata(&validated).map_err(|e| format!("Failed to read note: {e}"))?;
+        if !metadata.is_file() {
+            return Err("Not a file".to_string());
+        }
+
+        let (content, truncated) = read_text_prefix(&validated, max_bytes)?;
+        Ok(NoteContent {
+            path: rel_path,
+            content,
+            truncated,
+            size: metadata.len(),
+            modified: metadata
+                .modified()
+                .ok()
+                .map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339()),
+        })
+    })
+    .await
+    .map_err(|e| format!("Failed to read note: {e}"))?
+}
+
+#[tauri::command]
+pub(crate) async fn get_note_writes_enabled(app: AppHandle) -> Result<bool, String> {
+    config::get_note_writes_enabled(&app)
+}
+
+/// Allow `append_to_note` to modify notes in the vault
+#[tauri::command]
+pub(crate) async fn set_note_writes_enabled(app: AppHandle, enabled: bool) -> Result<(), String> {
+    config::set_note_writes_enabled(&app, enabled)
+}
+
+/// Refuse to modify vault notes unless the note-writes setting is on
+pub(super) fn ensure_note_writes_enabled(app: &AppHandle) -> Result<(), String> {
+    if !config::get_note_writes_enabled(app)? {
+        return Err("Writing to notes is disabled. Enable it in settings first.".to_string());
+    }
+    Ok(())
+}
+
+/// Append `content` to an existing markdown note, optionally at the end of
+/// the section under `heading`. Requires the note-writes setting; the note is
+/// replaced atomically.
+#[tauri::command]
+pub(crate) async fn append_to_note(
+    app: AppHandle,
+    path: String,
+    content: String,
+    heading: Option<String>,
+) -> Result<(), String> {
+    ensure_note_writes_enabled(&app)?;
+    if content.len() > MAX_APPEND_BYTES {
+        return Err("Content is too large to append".to_string());
+    }
+
+    let (_, validated, rel_path) = resolve_note(&app, &path)?;
+    if !is_markdown(&validated) {
+        return Err("Only markdown notes can be appended to".to_string());
+    }
+
+    tokio::task::spawn_blocking(move || {
+        let metadata =
+            std::fs::metadata(&validated).map_err(|e| format!("Failed to read note: {e}"))?;
+        if !metadata.is_file() {
+            return Err("Not a file".to_string());
+        }
+        if metadata.len() > MAX_APPEND_TARGET_BYTES {
+            return Err("Note is too large to modify".to_string());
+        }
+
+        let text =
+            std::fs::read_to_string(&validated).map_err(|e| format!("Failed to read note: {e}"))?;
+        let updated = append_to_text(&text, &content, heading.as_deref());
+        write_atomic(&validated, updated.as_bytes())?;
+        tracing::info!("Appended {} bytes to note: {}", content.len(), rel_path);
+        Ok(())
+    })
+    .await
+    .map_err(|e| format!("Failed to write note: {e}"))?
+}
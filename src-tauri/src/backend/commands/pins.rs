@@ -0,0 +1,71 @@
+use tauri::AppHandle;
+
+use crate::backend::config;
+use crate::backend::types::PinnedNode;
+
+/// Keep quick-open results for pins within the same order of magnitude as recent projects
+const MAX_PINNED_NODES: usize = 200;
+
+fn matches_query(pin: &PinnedNode, query_lower: &str) -> bool {
+    pin.title
+        .as_deref()
+        .is_some_and(|title| title.to_lowercase().contains(query_lower))
+        || pin.project.to_lowercase().contains(query_lower)
+        || pin.node_id.to_lowercase() == query_lower
+}
+
+#[tauri::command]
+pub(crate) async fn pin_node(
+    app: AppHandle,
+    project: String,
+    node_id: String,
+    title: Option<String>,
+) -> Result<(), String> {
+    let mut pins = config::get_pinned_nodes(&app)?;
+
+    pins.retain(|pin| !(pin.project == project && pin.node_id == node_id));
+    pins.insert(
+        0,
+        PinnedNode {
+            project,
+            node_id,
+            title,
+            pinned_at: chrono::Utc::now().to_rfc3339(),
+        },
+    );
+    pins.truncate(MAX_PINNED_NODES);
+
+    config::set_pinned_nodes(&app, &pins)
+}
+
+#[tauri::command]
+pub(crate) async fn unpin_node(
+    app: AppHandle,
+    project: String,
+    node_id: String,
+) -> Result<(), String> {
+    let mut pins = config::get_pinned_nodes(&app)?;
+    pins.retain(|pin| !(pin.project == project && pin.node_id == node_id));
+
+    config::set_pinned_nodes(&app, &pins)
+}
+
+/// Pinned nodes across all projects, most recently pinned first. A non-empty
+/// `query` filters by title or project path, for inclusion in quick-open results.
+#[tauri::command]
+pub(crate) async fn list_pinned(
+    app: AppHandle,
+    query: Option<String>,
+) -> Result<Vec<PinnedNode>, String> {
+    let pins = config::get_pinned_nodes(&app)?;
+
+    let query_lower = query.unwrap_or_default().trim().to_lowercase();
+    if query_lower.is_empty() {
+        return Ok(pins);
+    }
+
+    Ok(pins
+        .into_iter()
+        .filter(|pin| matches_query(pin, &query_lower))
+        .collect())
+}
@@ -1,34 +1,57 @@
 use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
-use tauri::AppHandle;
+use tauri::{AppHandle, Emitter, Manager, State};
 use tauri_plugin_dialog::DialogExt;
-use walkdir::WalkDir;
 
+use super::git::spawn_autocommit;
+use super::history::spawn_history_update;
+use crate::backend::assets;
+use crate::backend::autosave::AUTOSAVE_DEBOUNCE;
 use crate::backend::config;
-
-fn validate_path_in_notes_dir(path: &Path, notes_dir: &Path) -> Result<PathBuf, String> {
-    let canonical_notes = std::fs::canonicalize(notes_dir)
-        .map_err(|e| format!("Failed to resolve notes directory: {e}"))?;
-
-    let canonical_path = if path.exists() {
-        std::fs::canonicalize(path).map_err(|e| format!("Failed to resolve path: {e}"))?
-    } else {
-        let parent = path
-            .parent()
-            .ok_or_else(|| "Invalid path: no parent directory".to_string())?;
-        let filename = path
-            .file_name()
-            .ok_or_else(|| "Invalid path: no filename".to_string())?;
-        let canonical_parent = std::fs::canonicalize(parent)
-            .map_err(|e| format!("Failed to resolve parent directory: {e}"))?;
-        canonical_parent.join(filename)
-    };
-
-    if !canonical_path.starts_with(&canonical_notes) {
-        return Err("Security error: path is outside the notes directory".to_string());
+use crate::backend::diff::{self, TextDiffResult};
+use crate::backend::export::{
+    expand_filename_template, tree_markdown, FilenameVars, MarkdownExportOptions,
+    DEFAULT_FILENAME_TEMPLATE,
+};
+use crate::backend::indexer;
+use crate::backend::migrations::{migrate_data, MigrationReport};
+use crate::backend::note_edit::write_atomic;
+use crate::backend::note_files::validate_path_in_notes_dir;
+use crate::backend::project::{self, NodeGenerationConfig, ProjectSettings};
+use crate::backend::project_file::{
+    decode_project_bytes, encode_project_data, find_projects, is_compressed, node_title,
+    read_project_data, ProjectFile, ProjectManifest, ProjectNode,
+};
+use crate::backend::project_index::ProjectIndexHit;
+use crate::backend::project_lock::{self, LockError};
+use crate::backend::project_stats::{project_stats, ProjectStats};
+use crate::backend::project_watch::{Fingerprint, PROJECT_WATCH_INTERVAL};
+use crate::backend::safe_mode;
+use crate::backend::search::{self, ContentMatch, FileMatch, ProjectMatch};
+use crate::backend::search_index::{IndexHit, IndexStats};
+use crate::backend::spotlight;
+use crate::backend::state::AppState;
+use crate::backend::types::{
+    AutosaveCompletePayload, AutosaveFailedPayload, ProjectChangedPayload,
+};
+
+/// Validate a project path against every configured workspace, so projects
+/// from another vault can be opened without switching workspaces first
+pub(super) fn validate_project_path(app: &AppHandle, path: &str) -> Result<PathBuf, String> {
+    let dirs = config::get_workspace_directories(app)?;
+    if dirs.is_empty() {
+        return Err("Notes directory not configured. Please set it in settings.".to_string());
     }
 
-    Ok(canonical_path)
+    let mut last_error = String::new();
+    for dir in &dirs {
+        match validate_path_in_notes_dir(Path::new(path), dir) {
+            Ok(validated) => return Ok(validated),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
 }
 
 #[tauri::command]
@@ -54,27 +77,504 @@ pub(crate) async fn pick_notes_directory(app: AppHandle) -> Result<Option<String
     Ok(path.map(|p| p.to_string()))
 }
 
-#[tauri::command]
-pub(crate) async fn save_project(app: AppHandle, path: String, data: String) -> Result<(), String> {
-    let notes_directory = config::get_notes_directory_required(&app)?;
-    let validated_path = validate_path_in_notes_dir(Path::new(&path), &notes_directory)?;
+/// Bytes to write when saving project JSON `data` to `path`: node images
+/// moved to the project's assets folder and the JSON gzipped, if enabled
+fn project_save_bytes(
+    path: &Path,
+    data: String,
+    image_assets: bool,
+    compress: bool,
+) -> Result<Vec<u8>, String> {
+    let data = if image_assets {
+        assets::externalize_project_data(path, data)?
+    } else {
+        data
+    };
+    encode_project_data(&data, compress)
+}
 
-    std::fs::write(&validated_path, &data).map_err(|e| format!("Failed to save project: {e}"))?;
+#[tauri::command]
+pub(crate) async fn save_project(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    data: String,
+) -> Result<(), String> {
+    let validated_path = validate_project_path(&app, &path)?;
+    state.project_locks.check_writable(&validated_path)?;
+    // A queued autosave holds older data than this save; waiting for any
+    // autosave being written and dropping the queued one keeps it from
+    // landing on top
+    let _writing = state.autosave.write_lock.lock().await;
+    if state.autosave.discard(&validated_path) {
+        tracing::debug!("Dropped queued autosave of {:?}", validated_path);
+    }
+    let bytes = project_save_bytes(
+        &validated_path,
+        data,
+        config::get_image_assets(&app)?,
+        config::get_compress_projects(&app)?,
+    )?;
+
+    // An explicit save is the user choosing this version, so it may overwrite
+    // changes made on disk
+    state
+        .project_watch
+        .write_tracked(&validated_path, true, || {
+            std::fs::write(&validated_path, &bytes)
+                .map_err(|e| format!("Failed to save project: {e}"))
+        })?;
     tracing::info!("Project saved to: {:?}", validated_path);
+    spawn_spotlight_update(validated_path.clone());
+    indexer::spawn_project_update(&app, validated_path.clone());
+    spawn_history_update(&app, validated_path.clone());
+    spawn_autocommit(&app, validated_path, "save");
     Ok(())
 }
 
+/// Refresh the Spotlight metadata of a saved project in the background, so
+/// it can be found by its node titles. Failures are only logged.
+pub(super) fn spawn_spotlight_update(path: PathBuf) {
+    if !spotlight::is_supported() {
+        return;
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = spotlight::reindex_project(&path) {
+            tracing::warn!("Spotlight metadata of {:?} not updated: {}", path, e);
+        }
+    });
+}
+
+/// Rewrite the Spotlight metadata of every project in the workspaces, e.g.
+/// for projects saved before it was written. Returns how many were updated.
 #[tauri::command]
-pub(crate) async fn load_project(app: AppHandle, path: String) -> Result<String, String> {
-    let notes_directory = config::get_notes_directory_required(&app)?;
-    let validated_path = validate_path_in_notes_dir(Path::new(&path), &notes_directory)?;
+pub(crate) async fn reindex_projects(app: AppHandle) -> Result<usize, String> {
+    if !spotlight::is_supported() {
+        return Err("Spotlight metadata is only available on macOS".to_string());
+    }
+    let dirs = config::get_workspace_directories(&app)?;
+    tokio::task::spawn_blocking(move || {
+        let mut updated = 0;
+        for path in find_projects(&dirs) {
+            match spotlight::reindex_project(&path) {
+                Ok(()) => updated += 1,
+                Err(e) => tracing::warn!("Spotlight metadata of {:?} not updated: {}", path, e),
+            }
+        }
+        tracing::info!("Spotlight metadata updated for {} projects", updated);
+        updated
+    })
+    .await
+    .map_err(|e| format!("Reindexing failed: {e}"))
+}
+
+/// Queue a background save of `data` to `path`. Rapid calls for the same
+/// project are coalesced and written atomically once it has been quiet for
+/// [`AUTOSAVE_DEBOUNCE`]; the outcome arrives as an `autosave-complete` or
+/// `autosave-failed` event.
+#[tauri::command]
+pub(crate) async fn queue_autosave(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    data: String,
+) -> Result<(), String> {
+    let validated_path = validate_project_path(&app, &path)?;
+    let queue = state.autosave.clone();
+    let watch = state.project_watch.clone();
+    let locks = state.project_locks.clone();
+    let generation = queue.push(&validated_path, data, Instant::now());
+
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(AUTOSAVE_DEBOUNCE).await;
+
+        let _writing = queue.write_lock.lock().await;
+        let Some(data) = queue.take_if_due(&validated_path, generation, Instant::now()) else {
+            // Superseded by a newer update, whose own timer will save it
+            return;
+        };
+
+        let target = validated_path.clone();
+        let compress = config::get_compress_projects(&app).unwrap_or(false);
+        let image_assets = config::get_image_assets(&app).unwrap_or(false);
+        // Never autosave over changes made outside the app
+        let result = tokio::task::spawn_blocking(move || {
+            locks.check_writable(&target)?;
+            let bytes = project_save_bytes(&target, data, image_assets, compress)?;
+            watch.write_tracked(&target, false, || write_atomic(&target, &bytes))
+        })
+        .await
+        .map_err(|e| format!("Autosave task failed: {e}"))
+        .and_then(|r| r);
+
+        let emitted = match result {
+            Ok(()) => {
+                tracing::info!("Project autosaved to: {:?}", validated_path);
+                spawn_spotlight_update(validated_path.clone());
+                indexer::spawn_project_update(&app, validated_path.clone());
+                spawn_history_update(&app, validated_path.clone());
+                spawn_autocommit(&app, validated_path, "autosave");
+                let saved_at = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_millis() as u64)
+                    .unwrap_or_default();
+                app.emit(
+                    "autosave-complete",
+                    AutosaveCompletePayload { path, saved_at },
+                )
+            }
+            Err(error) => {
+                tracing::error!("Autosave of {:?} failed: {}", validated_path, error);
+                app.emit("autosave-failed", AutosaveFailedPayload { path, error })
+            }
+        };
+        if let Err(e) = emitted {
+            tracing::error!("Failed to emit autosave result: {:?}", e);
+        }
+    });
+
+    Ok(())
+}
+
+/// Write any autosaves still waiting out their debounce, so quitting right
+/// after an edit doesn't lose it
+pub(crate) fn flush_autosaves(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let compress = config::get_compress_projects(app).unwrap_or(false);
+    let image_assets = config::get_image_assets(app).unwrap_or(false);
+    for (path, data) in state.autosave.drain() {
+        let written = state
+            .project_locks
+            .check_writable(&path)
+            .and_then(|_| project_save_bytes(&path, data, image_assets, compress))
+            .and_then(|bytes| {
+                state
+                    .project_watch
+                    .write_tracked(&path, false, || write_atomic(&path, &bytes))
+            });
+        if let Err(e) = written {
+            tracing::error!("Failed to flush autosave of {:?}: {}", path, e);
+        }
+    }
+}
+
+/// Poll the open project file and emit `project-changed-externally` when
+/// another program changes it. Nothing is watched in safe mode.
+pub(crate) fn start_project_watch(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(PROJECT_WATCH_INTERVAL);
+        loop {
+            interval.tick().await;
+            if safe_mode::is_enabled(&app) {
+                continue;
+            }
+            let watch = app.state::<AppState>().project_watch.clone();
+            let Ok(Some(path)) = tokio::task::spawn_blocking(move || watch.poll()).await else {
+                continue;
+            };
+            tracing::info!("Project changed on disk: {}", path);
+            if let Err(e) = app.emit("project-changed-externally", ProjectChangedPayload { path }) {
+                tracing::error!("Failed to emit project-changed-externally: {:?}", e);
+            }
+        }
+    });
+}
+
+/// Contents of the open project if it changed on disk since the app last
+/// read or wrote it, otherwise `None`
+#[tauri::command]
+pub(crate) async fn reload_project_if_changed(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<Option<String>, String> {
+    let validated_path = validate_project_path(&app, &path)?;
+    let watch = state.project_watch.clone();
+    tokio::task::spawn_blocking(move || {
+        watch
+            .reload_if_changed(&validated_path)
+            .map(|raw| decode_project_bytes(&raw))
+            .transpose()
+    })
+    .await
+    .map_err(|e| format!("Reload task failed: {e}"))?
+}
+
+/// Release the lock on a project and stop watching it
+#[tauri::command]
+pub(crate) async fn close_project(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<(), String> {
+    let validated_path = validate_project_path(&app, &path)?;
+    state.project_locks.release(&validated_path);
+    state.project_watch.unwatch(&validated_path);
+    Ok(())
+}
+
+/// Remove a project's lock file regardless of which instance holds it, for
+/// locks left by another machine or a hung app
+#[tauri::command]
+pub(crate) async fn force_unlock_project(app: AppHandle, path: String) -> Result<(), String> {
+    let validated_path = validate_project_path(&app, &path)?;
+    project_lock::force_unlock(&validated_path)?;
+    tracing::warn!("Project lock force-removed: {:?}", validated_path);
+    Ok(())
+}
+
+/// Validate, lock and read a project, and start watching it. Shared by the
+/// whole-file and chunked loaders.
+fn open_project(
+    app: &AppHandle,
+    state: &AppState,
+    path: &str,
+) -> Result<(PathBuf, String), String> {
+    let validated_path = validate_project_path(app, path)?;
+
+    // Only another instance's live lock keeps the project closed. In a
+    // folder the lock can't be written to (read-only sync folder or mount)
+    // it opens unlocked, and saves are left to `check_writable`.
+    match state.project_locks.acquire(&validated_path) {
+        Ok(()) => {}
+        Err(LockError::Held(e)) => return Err(e),
+        Err(LockError::Io(e)) => {
+            tracing::warn!("Opening {:?} without a lock: {}", validated_path, e)
+        }
+    }
+    let read = std::fs::read(&validated_path)
+        .map_err(|e| format!("Failed to load project: {e}"))
+        .and_then(|raw| Ok((decode_project_bytes(&raw)?, raw)))
+        .and_then(|(data, raw)| {
+            // Older formats are upgraded in memory; the file follows on the next save
+            let (data, report) = migrate_data(data)?;
+            let data = assets::inline_project_data(&validated_path, data)?;
+            if !report.applied.is_empty() {
+                tracing::info!(
+                    "Upgraded project {:?} from format {} to {}",
+                    validated_path,
+                    report.from_version,
+                    report.to_version
+                );
+            }
+            Ok((data, raw))
+        });
+    let (data, raw) = match read {
+        Ok(read) => read,
+        Err(e) => {
+            state.project_locks.release(&validated_path);
+            return Err(e);
+        }
+    };
+    // One project is open at a time, so this replaces the previous one
+    state.project_locks.release_others(&validated_path);
+    state.project_watch.watch(
+        &validated_path,
+        path,
+        Fingerprint::of_data(&validated_path, &raw),
+    );
+
+    let notes_override = serde_json::from_str::<ProjectSettings>(&data)
+        .ok()
+        .and_then(|settings| settings.notes_directory);
+    if let Some(dir) = notes_override {
+        let workspace_dirs = config::get_workspace_directories(app)?;
+        if project::resolve_notes_override(&dir, &workspace_dirs).is_none() {
+            tracing::warn!(
+                "Project {:?} pins notes directory {:?}, which is not a configured workspace; the active workspace will be used",
+                validated_path,
+                dir
+            );
+        }
+    }
 
-    let data = std::fs::read_to_string(&validated_path)
-        .map_err(|e| format!("Failed to load project: {e}"))?;
     tracing::info!("Project loaded from: {:?}", validated_path);
+    Ok((validated_path, data))
+}
+
+/// Upgrade a project file on disk to the current format, keeping a copy of
+/// the original next to it. Reports which migrations ran.
+#[tauri::command]
+pub(crate) async fn migrate_project(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<MigrationReport, String> {
+    let validated_path = validate_project_path(&app, &path)?;
+    state.project_locks.check_writable(&validated_path)?;
+
+    let raw = std::fs::read(&validated_path).map_err(|e| format!("Failed to load project: {e}"))?;
+    let (data, mut report) = migrate_data(decode_project_bytes(&raw)?)?;
+    if report.applied.is_empty() {
+        return Ok(report);
+    }
+
+    let backup = backup_path(&validated_path, report.from_version);
+    std::fs::write(&backup, &raw).map_err(|e| format!("Failed to back up project: {e}"))?;
+    report.backup_path = Some(backup.to_string_lossy().into_owned());
+
+    let bytes = encode_project_data(&data, is_compressed(&raw))?;
+    state
+        .project_watch
+        .write_tracked(&validated_path, false, || {
+            write_atomic(&validated_path, &bytes)
+        })?;
+    tracing::info!(
+        "Migrated project {:?} from format {} to {} (backup at {:?})",
+        validated_path,
+        report.from_version,
+        report.to_version,
+        backup
+    );
+    Ok(report)
+}
+
+/// `plan.thoughttree` -> `plan.thoughttree.v2.bak`, or a timestamped name if
+/// that backup already exists
+fn backup_path(project: &Path, version: u32) -> PathBuf {
+    let name = project
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let backup = project.with_file_name(format!("{name}.v{version}.bak"));
+    if !backup.exists() {
+        return backup;
+    }
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    project.with_file_name(format!("{name}.v{version}-{stamp}.bak"))
+}
+
+#[tauri::command]
+pub(crate) async fn load_project(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<String, String> {
+    let (_, data) = open_project(&app, &state, &path)?;
     Ok(data)
 }
 
+/// Open a project like `load_project`, but return only its skeleton (node
+/// ids, titles, parent links, layout and settings). Bodies are fetched with
+/// `load_node_content` as they are needed.
+#[tauri::command]
+pub(crate) async fn load_project_manifest(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<ProjectManifest, String> {
+    let (validated_path, data) = open_project(&app, &state, &path)?;
+    let cache = state.project_cache.clone();
+    tokio::task::spawn_blocking(move || {
+        let modified = std::fs::metadata(&validated_path)
+            .and_then(|m| m.modified())
+            .ok();
+        let project = ProjectFile::parse(&data)?;
+        Ok(cache.store(&validated_path, modified, project).manifest())
+    })
+    .await
+    .map_err(|e| format!("Project parse task failed: {e}"))?
+}
+
+/// Full data of the requested nodes, in request order; unknown ids are skipped
+#[tauri::command]
+pub(crate) async fn load_node_content(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    ids: Vec<String>,
+) -> Result<Vec<ProjectNode>, String> {
+    let validated_path = validate_project_path(&app, &path)?;
+    let cache = state.project_cache.clone();
+    tokio::task::spawn_blocking(move || {
+        let project = cache.load(&validated_path)?;
+        Ok(ids
+            .iter()
+            .filter_map(|id| project.graph.node(id).cloned())
+            .collect())
+    })
+    .await
+    .map_err(|e| format!("Node load task failed: {e}"))?
+}
+
+/// Provider and model `node_id` of the saved project is regenerated with
+pub(super) async fn node_generation_config(
+    app: &AppHandle,
+    state: &AppState,
+    path: &str,
+    node_id: String,
+) -> Result<NodeGenerationConfig, String> {
+    let validated_path = validate_project_path(app, path)?;
+    let default_provider = config::get_default_provider(app)?;
+    let global_models = config::get_model_preferences(app)?;
+    let cache = state.project_cache.clone();
+    tokio::task::spawn_blocking(move || {
+        let project = cache.load(&validated_path)?;
+        let node = project
+            .graph
+            .node(&node_id)
+            .ok_or_else(|| format!("Node not found: {node_id}"))?;
+        Ok(project::resolve_node_generation(
+            node,
+            &project::project_model_preferences(&project),
+            &default_provider,
+            &global_models,
+        ))
+    })
+    .await
+    .map_err(|e| format!("Node load task failed: {e}"))?
+}
+
+/// Resolve the provider and model a node would be regenerated with: its
+/// pin, else what generated it, else the project's and then the global
+/// preferences
+#[tauri::command]
+pub(crate) async fn get_node_generation_config(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    node_id: String,
+) -> Result<NodeGenerationConfig, String> {
+    node_generation_config(&app, &state, &path, node_id).await
+}
+
+/// Word counts, reading time, depth and branching of a project, for the
+/// stats panel
+#[tauri::command]
+pub(crate) async fn get_project_stats(project_json: String) -> Result<ProjectStats, String> {
+    tokio::task::spawn_blocking(move || {
+        let project = ProjectFile::parse(&project_json)?;
+        Ok(project_stats(&project.graph))
+    })
+    .await
+    .map_err(|e| format!("Failed to compute project stats: {e}"))?
+}
+
+/// Line-by-line diff of two texts in hunks, with changed words marked, for
+/// a side-by-side comparison
+#[tauri::command]
+pub(crate) async fn diff_texts(a: String, b: String) -> Result<TextDiffResult, String> {
+    tokio::task::spawn_blocking(move || diff::diff_texts(&a, &b))
+        .await
+        .map_err(|e| format!("Diff failed: {e}"))?
+}
+
+/// Diff the content of nodes `a` and `b` of a project, e.g. two alternative
+/// answers to the same prompt
+#[tauri::command]
+pub(crate) async fn diff_nodes(
+    project_json: String,
+    a: String,
+    b: String,
+) -> Result<TextDiffResult, String> {
+    tokio::task::spawn_blocking(move || {
+        let project = ProjectFile::parse(&project_json)?;
+        diff::diff_nodes(&project, &a, &b)
+    })
+    .await
+    .map_err(|e| format!("Diff failed: {e}"))?
+}
+
 #[tauri::command]
 pub(crate) async fn new_project_dialog(app: AppHandle) -> Result<Option<String>, String> {
     let default_dir = config::get_notes_directory_optional(&app)?.map(PathBuf::from);
@@ -134,70 +634,292 @@ pub(crate) async fn remove_recent_project(app: AppHandle, path: String) -> Resul
     config::set_recent_projects(&app, &recent_projects)
 }
 
+/// Suggested export filename: the configured template expanded for this
+/// project/node, or the caller-provided name when no template is set
+pub(crate) fn export_file_name(
+    app: &AppHandle,
+    default_name: &str,
+    project: Option<String>,
+    node: Option<String>,
+    extension: &str,
+) -> Result<String, String> {
+    let Some(template) = config::get_export_filename_template(app)? else {
+        return Ok(default_name.to_string());
+    };
+
+    Ok(expand_filename_template(
+        &template,
+        &FilenameVars {
+            project: project.as_deref(),
+            node: node.as_deref(),
+            now: chrono::Local::now(),
+        },
+        extension,
+    ))
+}
+
+#[tauri::command]
+pub(crate) async fn get_export_filename_template(app: AppHandle) -> Result<String, String> {
+    Ok(config::get_export_filename_template(&app)?
+        .unwrap_or_else(|| DEFAULT_FILENAME_TEMPLATE.to_string()))
+}
+
+#[tauri::command]
+pub(crate) async fn set_export_filename_template(
+    app: AppHandle,
+    template: Option<String>,
+) -> Result<(), String> {
+    let template = template.filter(|t| !t.trim().is_empty());
+    config::set_export_filename_template(&app, template.as_deref())?;
+    tracing::info!("Export filename template set to: {:?}", template);
+    Ok(())
+}
+
 #[tauri::command]
 pub(crate) async fn export_markdown(
     app: AppHandle,
     content: String,
     default_name: String,
+    project: Option<String>,
+    node: Option<String>,
+) -> Result<Option<String>, String> {
+    let file_name = export_file_name(&app, &default_name, project, node, "md")?;
+    save_export_with_assets(&app, &MARKDOWN_EXPORT, &content, &file_name)
+}
+
+/// A file type offered by the export save dialog
+pub(super) struct ExportKind {
+    pub name: &'static str,
+    pub extension: &'static str,
+}
+
+pub(super) const MARKDOWN_EXPORT: ExportKind = ExportKind {
+    name: "Markdown",
+    extension: "md",
+};
+
+/// Ask where to save an export, starting in the notes directory. `None` if
+/// the user cancelled.
+fn pick_export_path(
+    app: &AppHandle,
+    kind: &ExportKind,
+    file_name: &str,
 ) -> Result<Option<String>, String> {
     let mut dialog = app
         .dialog()
         .file()
-        .set_title("Export as Markdown")
-        .add_filter("Markdown", &["md"])
-        .set_file_name(&default_name);
+        .set_title(format!("Export as {}", kind.name))
+        .add_filter(kind.name, &[kind.extension])
+        .set_file_name(file_name);
 
-    if let Some(dir) = config::get_notes_directory_optional(&app)?.map(PathBuf::from) {
+    if let Some(dir) = config::get_notes_directory_optional(app)?.map(PathBuf::from) {
         dialog = dialog.set_directory(dir);
     }
 
-    if let Some(path) = dialog.blocking_save_file() {
-        let path_str = path.to_string();
-        std::fs::write(&path_str, &content)
-            .map_err(|e| format!("Failed to export markdown: {e}"))?;
-        tracing::info!("Exported markdown to: {}", path_str);
-        Ok(Some(path_str))
-    } else {
-        Ok(None)
+    Ok(dialog.blocking_save_file().map(|path| path.to_string()))
+}
+
+fn write_export(
+    app: &AppHandle,
+    kind: &ExportKind,
+    path: String,
+    content: &[u8],
+) -> Result<Option<String>, String> {
+    std::fs::write(&path, content).map_err(|e| format!("Failed to export {}: {e}", kind.name))?;
+    tracing::info!("Exported {} to: {}", kind.name, path);
+    spawn_autocommit(app, PathBuf::from(&path), "export");
+    Ok(Some(path))
+}
+
+/// Ask where to save an export (starting in the notes directory) and write
+/// it there. Returns the chosen path, or `None` if the user cancelled.
+pub(super) fn save_export(
+    app: &AppHandle,
+    kind: &ExportKind,
+    content: &[u8],
+    file_name: &str,
+) -> Result<Option<String>, String> {
+    match pick_export_path(app, kind, file_name)? {
+        Some(path) => write_export(app, kind, path, content),
+        None => Ok(None),
+    }
+}
+
+/// `save_export` for text that may embed base64 images: they are written to
+/// an `assets/` folder beside the export and referenced from there
+pub(super) fn save_export_with_assets(
+    app: &AppHandle,
+    kind: &ExportKind,
+    content: &str,
+    file_name: &str,
+) -> Result<Option<String>, String> {
+    let Some(path) = pick_export_path(app, kind, file_name)? else {
+        return Ok(None);
+    };
+    let (content, extracted) = assets::extract_assets(content);
+    assets::write_assets(Path::new(&path), &extracted)?;
+    write_export(app, kind, path, content.as_bytes())
+}
+
+/// Parse the project to export: `data` as sent by the frontend (which may
+/// have unsaved edits), or else the saved file at `path`
+pub(super) fn project_for_export(
+    app: &AppHandle,
+    data: Option<String>,
+    path: Option<&str>,
+) -> Result<ProjectFile, String> {
+    let data = match (data, path) {
+        (Some(data), _) => data,
+        (None, Some(path)) => {
+            let path = validate_project_path(app, path)?;
+            let mut project = ProjectFile::parse(&read_project_data(&path)?)?;
+            assets::inline_images(&path, &mut project);
+            return Ok(project);
+        }
+        (None, None) => return Err("Nothing to export: no project data or path".to_string()),
+    };
+    ProjectFile::parse(&data)
+}
+
+/// Export the whole tree, or the subtree under `root`, as nested-heading
+/// markdown
+#[tauri::command]
+pub(crate) async fn export_tree_markdown(
+    app: AppHandle,
+    data: Option<String>,
+    path: Option<String>,
+    root: Option<String>,
+    options: Option<MarkdownExportOptions>,
+    default_name: String,
+) -> Result<Option<String>, String> {
+    let project = project_for_export(&app, data, path.as_deref())?;
+    if let Some(root) = &root {
+        if project.graph.node(root).is_none() {
+            return Err(format!("Node not found: {root}"));
+        }
     }
+    let content = tree_markdown(&project.graph, root.as_deref(), options.unwrap_or_default());
+    let node = root
+        .as_deref()
+        .and_then(|id| project.graph.node(id))
+        .map(node_title);
+    let file_name = export_file_name(&app, &default_name, path, node, "md")?;
+    save_export_with_assets(&app, &MARKDOWN_EXPORT, &content, &file_name)
 }
 
+/// Fuzzy-search note paths; results are ranked and carry the matched
+/// character indices for highlighting, plus the first `preview_lines` lines
+/// of each note when requested
 #[tauri::command]
 pub(crate) async fn search_files(
     app: AppHandle,
     query: String,
     limit: Option<usize>,
-) -> Result<Vec<String>, String> {
+    preview_lines: Option<usize>,
+) -> Result<Vec<FileMatch>, String> {
     let notes_directory = config::get_notes_directory_required(&app)?;
     let max_results = limit.unwrap_or(20);
+    let query = query.chars().take(100).collect::<String>();
+    let preview_lines = preview_lines.unwrap_or(0);
 
+    tokio::task::spawn_blocking(move || {
+        search::search_file_names(&notes_directory, &query, max_results, preview_lines)
+    })
+    .await
+    .map_err(|e| format!("Search failed: {e}"))
+}
+
+/// Search note contents (not just paths); returns the path, line number,
+/// column, snippet with highlighted matches, and the file's total number of
+/// matching lines for each matching line
+#[tauri::command]
+pub(crate) async fn search_note_contents(
+    app: AppHandle,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<ContentMatch>, String> {
+    let notes_directory = config::get_notes_directory_required(&app)?;
+    let max_results = limit.unwrap_or(50).min(500);
     let query = query.chars().take(100).collect::<String>();
-    let query_lower = query.to_lowercase();
-
-    let mut files = Vec::new();
-
-    for entry in WalkDir::new(&notes_directory)
-        .follow_links(false)
-        .max_depth(20)
-        .into_iter()
-        .filter_map(|entry| entry.ok())
-    {
-        if !entry.file_type().is_file() {
-            continue;
-        }
 
-        let rel_path = match entry.path().strip_prefix(&notes_directory) {
-            Ok(path) => path.to_string_lossy().to_string(),
-            Err(_) => continue,
-        };
+    tokio::task::spawn_blocking(move || {
+        search::search_note_contents(&notes_directory, &query, max_results)
+    })
+    .await
+    .map_err(|e| format!("Search failed: {e}"))
+}
 
-        if query.is_empty() || rel_path.to_lowercase().contains(&query_lower) {
-            files.push(rel_path);
-            if files.len() >= max_results {
-                break;
-            }
-        }
-    }
+/// Search node text in the saved projects under the notes directory;
+/// returns the project path, node id and a snippet for each matching node
+#[tauri::command]
+pub(crate) async fn search_projects(
+    app: AppHandle,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<ProjectMatch>, String> {
+    let notes_directory = config::get_notes_directory_required(&app)?;
+    let max_results = limit.unwrap_or(50).min(500);
+    let query = query.chars().take(100).collect::<String>();
+
+    tokio::task::spawn_blocking(move || {
+        search::search_projects(&notes_directory, &query, max_results)
+    })
+    .await
+    .map_err(|e| format!("Search failed: {e}"))
+}
+
+/// Rebuild the persistent full-text index of the notes directory
+#[tauri::command]
+pub(crate) async fn rebuild_search_index(app: AppHandle) -> Result<IndexStats, String> {
+    indexer::rebuild(app).await
+}
 
-    Ok(files)
+/// Ranked search over the persistent index; the index is brought up to date
+/// (or built on first use) before querying
+#[tauri::command]
+pub(crate) async fn query_search_index(
+    app: AppHandle,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<IndexHit>, String> {
+    let max_results = limit.unwrap_or(50).min(500);
+    let query = query.chars().take(100).collect::<String>();
+    indexer::query(app, query, max_results).await
+}
+
+/// Ranked nodes across every project in the workspaces. The project index
+/// is kept on disk, updated as projects are saved, and brought up to date
+/// (or built on first use) before querying.
+#[tauri::command]
+pub(crate) async fn query_project_index(
+    app: AppHandle,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<ProjectIndexHit>, String> {
+    let max_results = limit.unwrap_or(50).min(500);
+    let query = query.chars().take(100).collect::<String>();
+    indexer::query_projects(app, query, max_results).await
+}
+
+#[tauri::command]
+pub(crate) async fn get_image_assets(app: AppHandle) -> Result<bool, String> {
+    config::get_image_assets(&app)
+}
+
+/// Keep node images as files in a `<project>.assets` folder beside the
+/// project instead of base64 in it. Projects open either way.
+#[tauri::command]
+pub(crate) async fn set_image_assets(app: AppHandle, enabled: bool) -> Result<(), String> {
+    config::set_image_assets(&app, enabled)
+}
+
+#[tauri::command]
+pub(crate) async fn get_compress_projects(app: AppHandle) -> Result<bool, String> {
+    config::get_compress_projects(&app)
+}
+
+/// Gzip project files on save. Compressed and plain files both open either way.
+#[tauri::command]
+pub(crate) async fn set_compress_projects(app: AppHandle, enabled: bool) -> Result<(), String> {
+    config::set_compress_projects(&app, enabled)
 }
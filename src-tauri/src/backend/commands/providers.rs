@@ -1,30 +1,103 @@
 use std::path::{Path, PathBuf};
 
-use tauri::AppHandle;
+use tauri::{AppHandle, State};
 use tauri_plugin_dialog::DialogExt;
 use tokio::process::Command;
 
 use crate::backend::acp::process::{
-    find_claude_code_executable, find_gemini_cli_executable, find_sidecar_path,
+    find_claude_acp_launcher, find_claude_code_executable, find_gemini_cli_executable,
+    find_on_path, ClaudeAcpLauncher,
 };
-use crate::backend::acp::sessions::run_model_discovery_session;
+use crate::backend::acp::sandbox::sandbox_supported;
+use crate::backend::acp::sessions::{
+    run_auth_session, run_capability_probe_session, run_command_discovery_session,
+    run_mode_discovery_session, run_model_discovery_session,
+};
+use crate::backend::agent_env::{self, AgentEnv};
+use crate::backend::claude_options::ClaudeOptions;
 use crate::backend::config;
+use crate::backend::gemini_cli::{detect_gemini_auth, GeminiSettings};
+use crate::backend::gemini_models::gemini_api_key;
+use crate::backend::proxy::{self, ProxySettings, ProxyTestResult};
+use crate::backend::read_roots;
 use crate::backend::runtime::run_localset_blocking;
+use crate::backend::state::AppState;
 use crate::backend::types::{
-    AgentProvider, ModelInfo, ModelPreferences, ProviderPaths, ProviderStatus,
+    AgentCommandInfo, AgentProvider, AuthMethodInfo, ModelInfo, ModelPreferences, ModelPreset,
+    ModelPresets, ProviderFeatures, ProviderPaths, ProviderStatus, ProviderVersion, SessionModes,
+    SpawnConfig,
 };
 
-fn check_provider_availability(provider: &AgentProvider, paths: &ProviderPaths) -> ProviderStatus {
+/// Oldest CLI versions known to speak ACP (Claude Code via the sidecar,
+/// Gemini CLI via `--experimental-acp`)
+const MIN_CLAUDE_CODE_VERSION: (u64, u64, u64) = (1, 0, 0);
+const MIN_GEMINI_CLI_VERSION: (u64, u64, u64) = (0, 2, 0);
+
+fn minimum_version(provider: &AgentProvider) -> (u64, u64, u64) {
+    match provider {
+        AgentProvider::ClaudeCode => MIN_CLAUDE_CODE_VERSION,
+        AgentProvider::GeminiCli => MIN_GEMINI_CLI_VERSION,
+    }
+}
+
+/// Extract the first `major.minor[.patch]` version from `--version` output,
+/// e.g. "2.0.14 (Claude Code)" or "gemini 0.9.0-nightly"
+fn parse_version(output: &str) -> Option<(u64, u64, u64)> {
+    output.split_whitespace().find_map(|token| {
+        let token = token.trim_start_matches('v');
+        let core = token.split(['-', '+']).next()?;
+        let mut parts = core.split('.').map(|part| part.parse::<u64>().ok());
+        let major = parts.next()??;
+        let minor = parts.next()??;
+        let patch = parts.next().flatten().unwrap_or(0);
+        Some((major, minor, patch))
+    })
+}
+
+fn format_version(version: (u64, u64, u64)) -> String {
+    format!("{}.{}.{}", version.0, version.1, version.2)
+}
+
+pub(super) fn find_provider_executable(
+    provider: &AgentProvider,
+    paths: &ProviderPaths,
+) -> Option<PathBuf> {
+    match provider {
+        AgentProvider::ClaudeCode => find_claude_code_executable(paths.claude_code.as_deref()),
+        AgentProvider::GeminiCli => find_gemini_cli_executable(paths.gemini_cli.as_deref()),
+    }
+}
+
+/// Whether Gemini CLI would find credentials, looking at the agent
+/// environment much as it would be passed on spawn
+fn gemini_signed_in(spawn_config: &SpawnConfig) -> bool {
+    let env = |name: &str| {
+        spawn_config
+            .env
+            .get(name)
+            .cloned()
+            .or_else(|| std::env::var(name).ok())
+    };
+    let gemini_dir = dirs::home_dir().map(|home| home.join(".gemini"));
+    detect_gemini_auth(env, gemini_api_key().is_some(), gemini_dir.as_deref()).is_some()
+}
+
+pub(super) fn check_provider_availability(
+    provider: &AgentProvider,
+    spawn_config: &SpawnConfig,
+) -> ProviderStatus {
+    let paths = &spawn_config.provider_paths;
     match provider {
         AgentProvider::ClaudeCode => {
-            let sidecar_available = find_sidecar_path().is_some();
+            let launcher = find_claude_acp_launcher(spawn_config.npx_fallback);
             let custom_path = paths.claude_code.as_deref();
             let cli_available = find_claude_code_executable(custom_path).is_some();
 
             ProviderStatus {
                 provider: provider.clone(),
-                available: sidecar_available && cli_available,
-                error_message: if !sidecar_available {
+                available: launcher.is_some() && cli_available,
+                authenticated: None,
+                error_message: if launcher.is_none() {
                     Some(
                         "claude-code-acp sidecar not found (dev: run bun run build:sidecar)"
                             .to_string(),
@@ -34,6 +107,11 @@ fn check_provider_availability(provider: &AgentProvider, paths: &ProviderPaths)
                         "Claude Code CLI not found. Install via: brew install --cask claude-code"
                             .to_string(),
                     )
+                } else if matches!(launcher, Some(ClaudeAcpLauncher::Npx(_))) {
+                    Some(
+                        "Sidecar not found; using the slower npx fallback for claude-code-acp"
+                            .to_string(),
+                    )
                 } else {
                     None
                 },
@@ -42,12 +120,21 @@ fn check_provider_availability(provider: &AgentProvider, paths: &ProviderPaths)
         AgentProvider::GeminiCli => {
             let custom_path = paths.gemini_cli.as_deref();
             let cli_available = find_gemini_cli_executable(custom_path).is_some();
+            // Not signed in is only a warning: credentials can live where
+            // they can't be checked, e.g. Application Default Credentials
+            let authenticated = cli_available.then(|| gemini_signed_in(spawn_config));
 
             ProviderStatus {
                 provider: provider.clone(),
                 available: cli_available,
+                authenticated,
                 error_message: if !cli_available {
                     Some("Gemini CLI not found. Install via: brew install gemini-cli".to_string())
+                } else if authenticated == Some(false) {
+                    Some(
+                        "Gemini CLI is not signed in: add an API key or run gemini to log in"
+                            .to_string(),
+                    )
                 } else {
                     None
                 },
@@ -98,12 +185,84 @@ async fn validate_executable(path: &Path, provider: &AgentProvider) -> Result<St
 }
 
 #[tauri::command]
-pub(crate) async fn get_available_providers(app: AppHandle) -> Result<Vec<ProviderStatus>, String> {
+pub(crate) async fn get_available_providers(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<ProviderStatus>, String> {
+    let spawn_config = config::get_spawn_config(&app)?;
+    let errors = state
+        .provider_errors
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+
+    Ok([AgentProvider::ClaudeCode, AgentProvider::GeminiCli]
+        .into_iter()
+        .map(|provider| {
+            let mut status = check_provider_availability(&provider, &spawn_config);
+            // Installed, but the agent failed to start last time it was asked
+            // for models
+            if status.available && status.error_message.is_none() {
+                status.error_message = errors.get(&provider).cloned();
+            }
+            status
+        })
+        .collect())
+}
+
+pub(super) async fn check_provider_version(
+    provider: AgentProvider,
+    paths: &ProviderPaths,
+) -> ProviderVersion {
+    let minimum = minimum_version(&provider);
+    let mut result = ProviderVersion {
+        provider: provider.clone(),
+        executable_path: None,
+        version: None,
+        minimum_version: format_version(minimum),
+        meets_minimum: None,
+        error_message: None,
+    };
+
+    let Some(path) = find_provider_executable(&provider, paths) else {
+        result.error_message = Some(format!("{} not found", provider.display_name()));
+        return result;
+    };
+    result.executable_path = Some(path.to_string_lossy().to_string());
+
+    match validate_executable(&path, &provider).await {
+        Ok(version_line) => match parse_version(&version_line) {
+            Some(version) => {
+                let meets_minimum = version >= minimum;
+                result.version = Some(format_version(version));
+                result.meets_minimum = Some(meets_minimum);
+                if !meets_minimum {
+                    result.error_message = Some(format!(
+                        "{} {} is too old for ACP; version {} or newer is required",
+                        provider.display_name(),
+                        format_version(version),
+                        format_version(minimum)
+                    ));
+                }
+            }
+            None => {
+                result.error_message =
+                    Some(format!("Could not parse version from: {version_line}"));
+            }
+        },
+        Err(e) => result.error_message = Some(e),
+    }
+
+    result
+}
+
+#[tauri::command]
+pub(crate) async fn get_provider_versions(app: AppHandle) -> Result<Vec<ProviderVersion>, String> {
     let paths = config::get_provider_paths(&app)?;
 
     Ok(vec![
-        check_provider_availability(&AgentProvider::ClaudeCode, &paths),
-        check_provider_availability(&AgentProvider::GeminiCli, &paths),
+        check_provider_version(AgentProvider::ClaudeCode, &paths).await,
+        check_provider_version(AgentProvider::GeminiCli, &paths).await,
     ])
 }
 
@@ -141,6 +300,34 @@ pub(crate) async fn set_model_preference(
     Ok(())
 }
 
+#[tauri::command]
+pub(crate) async fn get_model_presets(app: AppHandle) -> Result<ModelPresets, String> {
+    config::get_model_presets(&app)
+}
+
+/// Point a preset at another model for one provider; `None` leaves that
+/// provider on its agent's default model
+#[tauri::command]
+pub(crate) async fn set_model_preset(
+    app: AppHandle,
+    preset: ModelPreset,
+    provider: AgentProvider,
+    model_id: Option<String>,
+) -> Result<(), String> {
+    let model_id = model_id.filter(|id| !id.trim().is_empty());
+    let mut presets = config::get_model_presets(&app)?;
+    presets.get_mut(preset).set(&provider, model_id.clone());
+    config::set_model_presets(&app, &presets)?;
+
+    tracing::info!(
+        "Model preset {:?} for {:?} set to: {:?}",
+        preset,
+        provider,
+        model_id
+    );
+    Ok(())
+}
+
 #[tauri::command]
 pub(crate) async fn get_provider_paths(app: AppHandle) -> Result<ProviderPaths, String> {
     config::get_provider_paths(&app)
@@ -184,16 +371,387 @@ pub(crate) async fn pick_provider_executable(
     Ok(path.map(|p| p.to_string()))
 }
 
+#[tauri::command]
+pub(crate) async fn get_npx_fallback_enabled(app: AppHandle) -> Result<bool, String> {
+    config::get_npx_fallback_enabled(&app)
+}
+
+#[tauri::command]
+pub(crate) async fn set_npx_fallback_enabled(app: AppHandle, enabled: bool) -> Result<(), String> {
+    config::set_npx_fallback_enabled(&app, enabled)?;
+    tracing::info!("npx fallback for claude-code-acp set to: {}", enabled);
+    Ok(())
+}
+
+/// Environment variables passed to every spawned agent
+#[tauri::command]
+pub(crate) async fn get_agent_env(app: AppHandle) -> Result<AgentEnv, String> {
+    config::get_agent_env(&app)
+}
+
+/// Pass `name=value` to agents spawned from now on, e.g. `HTTPS_PROXY` or
+/// `GOOGLE_CLOUD_PROJECT`
+#[tauri::command]
+pub(crate) async fn set_agent_env_var(
+    app: AppHandle,
+    name: String,
+    value: String,
+) -> Result<(), String> {
+    let mut env = config::get_agent_env(&app)?;
+    agent_env::set_agent_env_var(&mut env, &name, &value)?;
+    config::set_agent_env(&app, &env)?;
+    tracing::info!("Agent environment variable {} set", name);
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) async fn remove_agent_env_var(app: AppHandle, name: String) -> Result<(), String> {
+    let mut env = config::get_agent_env(&app)?;
+    if env.remove(&name).is_some() {
+        config::set_agent_env(&app, &env)?;
+        tracing::info!("Agent environment variable {} removed", name);
+    }
+    Ok(())
+}
+
+/// Parent environment variables agents inherit (`*` ends a prefix match)
+#[tauri::command]
+pub(crate) async fn get_env_allowlist(app: AppHandle) -> Result<Vec<String>, String> {
+    config::get_env_allowlist(&app)
+}
+
+/// Replace which parent environment variables agents inherit; `None`
+/// restores the built-in list
+#[tauri::command]
+pub(crate) async fn set_env_allowlist(
+    app: AppHandle,
+    allowlist: Option<Vec<String>>,
+) -> Result<(), String> {
+    if let Some(allowlist) = &allowlist {
+        agent_env::validate_allowlist(allowlist)?;
+    }
+    config::set_env_allowlist(&app, allowlist.as_deref())?;
+    tracing::info!("Agent environment allowlist set to: {:?}", allowlist);
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) async fn get_agent_sandbox_enabled(app: AppHandle) -> Result<bool, String> {
+    config::get_agent_sandbox_enabled(&app)
+}
+
+/// Run agents under a macOS sandbox profile that blocks writes outside the
+/// temp directories and agent state, and reads outside the notes and read
+/// directories, agent config and system and toolchain paths. The keychain
+/// isn't readable inside it. macOS only.
+#[tauri::command]
+pub(crate) async fn set_agent_sandbox_enabled(app: AppHandle, enabled: bool) -> Result<(), String> {
+    if enabled && !sandbox_supported() {
+        return Err("Agent sandboxing is only available on macOS".to_string());
+    }
+    config::set_agent_sandbox_enabled(&app, enabled)?;
+    tracing::info!("Agent sandbox set to: {}", enabled);
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) async fn get_read_roots(app: AppHandle) -> Result<Vec<PathBuf>, String> {
+    config::get_read_roots(&app)
+}
+
+/// Directories outside the notes that agents' read-only tools may use
+/// without asking, e.g. a folder of reference PDFs. They are also readable
+/// under the agent sandbox.
+#[tauri::command]
+pub(crate) async fn set_read_roots(app: AppHandle, roots: Vec<String>) -> Result<(), String> {
+    let roots = read_roots::validate_read_roots(&roots, dirs::home_dir().as_deref())?;
+    config::set_read_roots(&app, &roots)?;
+    tracing::info!("Agent read directories set to: {:?}", roots);
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) async fn get_claude_options(app: AppHandle) -> Result<ClaudeOptions, String> {
+    config::get_claude_options(&app)
+}
+
+/// Extended thinking and turn limit for Claude Code generations. A project
+/// can replace them with its own `claudeOptions`.
+#[tauri::command]
+pub(crate) async fn set_claude_options(
+    app: AppHandle,
+    options: ClaudeOptions,
+) -> Result<(), String> {
+    options.validate()?;
+    config::set_claude_options(&app, &options)?;
+    tracing::info!("Claude options updated: {:?}", options);
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) async fn get_gemini_settings(app: AppHandle) -> Result<GeminiSettings, String> {
+    config::get_gemini_settings(&app)
+}
+
+/// Settings file and sandbox flags Gemini CLI is started with. The API key
+/// is stored separately with `set_secret("gemini-api-key", ...)`.
+#[tauri::command]
+pub(crate) async fn set_gemini_settings(
+    app: AppHandle,
+    settings: GeminiSettings,
+) -> Result<(), String> {
+    settings.validate()?;
+    config::set_gemini_settings(&app, &settings)?;
+    tracing::info!(
+        "Gemini settings updated (settings file: {}, sandbox: {})",
+        settings.settings_path.is_some(),
+        settings.sandbox
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) async fn get_proxy_settings(app: AppHandle) -> Result<ProxySettings, String> {
+    config::get_proxy_settings(&app)
+}
+
+/// Proxies for agent subprocesses (as `HTTP_PROXY` and friends) and the
+/// app's own provider requests. Credentials in the URLs are rejected, since
+/// the settings are stored in plain text.
+#[tauri::command]
+pub(crate) async fn set_proxy_settings(
+    app: AppHandle,
+    settings: ProxySettings,
+) -> Result<(), String> {
+    proxy::validate_proxy_settings(&settings)?;
+    config::set_proxy_settings(&app, &settings)?;
+    tracing::info!(
+        "Proxy settings updated (http: {}, https: {}, socks: {})",
+        settings.http.is_some(),
+        settings.https.is_some(),
+        settings.socks.is_some()
+    );
+    Ok(())
+}
+
+/// Check that the providers' APIs can be reached through `settings`, or the
+/// saved proxy settings when none are given
+#[tauri::command]
+pub(crate) async fn test_proxy(
+    app: AppHandle,
+    settings: Option<ProxySettings>,
+) -> Result<Vec<ProxyTestResult>, String> {
+    let settings = match settings {
+        Some(settings) => settings,
+        None => config::get_proxy_settings(&app)?,
+    };
+    proxy::test_proxy(&settings).await
+}
+
+#[tauri::command]
+pub(crate) async fn get_path_lookup_enabled(app: AppHandle) -> Result<bool, String> {
+    config::get_path_lookup_enabled(&app)
+}
+
+#[tauri::command]
+pub(crate) async fn set_path_lookup_enabled(app: AppHandle, enabled: bool) -> Result<(), String> {
+    config::set_path_lookup_enabled(&app, enabled)?;
+    tracing::info!("PATH lookup for provider executables set to: {}", enabled);
+    Ok(())
+}
+
+/// Resolve a provider executable via PATH (opt-in). The path is only returned
+/// for the user to confirm; persisting it goes through `set_provider_path`.
+#[tauri::command]
+pub(crate) async fn lookup_provider_on_path(
+    app: AppHandle,
+    provider: AgentProvider,
+) -> Result<Option<String>, String> {
+    if !config::get_path_lookup_enabled(&app)? {
+        return Err("PATH lookup is disabled. Enable it in settings first.".to_string());
+    }
+
+    let binary = match provider {
+        AgentProvider::ClaudeCode => "claude",
+        AgentProvider::GeminiCli => "gemini",
+    };
+
+    let Some(path) = find_on_path(binary) else {
+        return Ok(None);
+    };
+
+    validate_executable(&path, &provider).await?;
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
 #[tauri::command]
 pub(crate) async fn get_available_models(
     app: AppHandle,
+    state: State<'_, AppState>,
     provider: AgentProvider,
 ) -> Result<Vec<ModelInfo>, String> {
     let notes_directory = config::get_notes_directory_required(&app)?;
-    let provider_paths = config::get_provider_paths(&app)?;
+    let spawn_config = config::get_spawn_config(&app)?;
+
+    let discovery_provider = provider.clone();
+    let result = run_localset_blocking(move || async move {
+        run_model_discovery_session(notes_directory, discovery_provider, spawn_config).await
+    })
+    .await;
+
+    let mut errors = state
+        .provider_errors
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    match &result {
+        Ok(_) => errors.remove(&provider),
+        Err(e) => errors.insert(provider, e.clone()),
+    };
+    result
+}
+
+/// Slash commands (e.g. /compact, /memory) the provider's agent advertises;
+/// run one by passing its name as `agent_command` to `send_prompt`
+#[tauri::command]
+pub(crate) async fn get_agent_commands(
+    app: AppHandle,
+    provider: AgentProvider,
+) -> Result<Vec<AgentCommandInfo>, String> {
+    let notes_directory = config::get_notes_directory_required(&app)?;
+    let spawn_config = config::get_spawn_config(&app)?;
 
     run_localset_blocking(move || async move {
-        run_model_discovery_session(notes_directory, provider, provider_paths).await
+        run_command_discovery_session(notes_directory, provider, spawn_config).await
     })
     .await
 }
+
+/// Session modes (e.g. "plan") the provider's agent offers
+#[tauri::command]
+pub(crate) async fn get_session_modes(
+    app: AppHandle,
+    provider: AgentProvider,
+) -> Result<SessionModes, String> {
+    let notes_directory = config::get_notes_directory_required(&app)?;
+    let spawn_config = config::get_spawn_config(&app)?;
+
+    run_localset_blocking(move || async move {
+        run_mode_discovery_session(notes_directory, provider, spawn_config).await
+    })
+    .await
+}
+
+#[tauri::command]
+pub(crate) async fn get_session_mode_preferences(
+    app: AppHandle,
+) -> Result<ModelPreferences, String> {
+    config::get_session_mode_preferences(&app)
+}
+
+/// Session mode new prompts start in for `provider`; `None` keeps the agent's default
+#[tauri::command]
+pub(crate) async fn set_session_mode_preference(
+    app: AppHandle,
+    provider: AgentProvider,
+    mode_id: Option<String>,
+) -> Result<(), String> {
+    let mut preferences = config::get_session_mode_preferences(&app)?;
+    preferences.set(&provider, mode_id.clone());
+    config::set_session_mode_preferences(&app, &preferences)?;
+
+    tracing::info!("Session mode for {:?} set to: {:?}", provider, mode_id);
+    Ok(())
+}
+
+/// Authentication methods the provider's agent offers
+#[tauri::command]
+pub(crate) async fn get_auth_methods(
+    app: AppHandle,
+    provider: AgentProvider,
+) -> Result<Vec<AuthMethodInfo>, String> {
+    let notes_directory = config::get_notes_directory_required(&app)?;
+    let spawn_config = config::get_spawn_config(&app)?;
+
+    run_localset_blocking(move || async move {
+        run_auth_session(notes_directory, provider, spawn_config, None).await
+    })
+    .await
+}
+
+/// Run one of the provider's authentication methods (e.g. its CLI login)
+#[tauri::command]
+pub(crate) async fn authenticate_provider(
+    app: AppHandle,
+    provider: AgentProvider,
+    method_id: String,
+) -> Result<(), String> {
+    let notes_directory = config::get_notes_directory_required(&app)?;
+    let spawn_config = config::get_spawn_config(&app)?;
+
+    run_localset_blocking(move || async move {
+        run_auth_session(notes_directory, provider, spawn_config, Some(method_id))
+            .await
+            .map(|_| ())
+    })
+    .await
+}
+
+async fn probe_provider_features(
+    notes_directory: PathBuf,
+    provider: AgentProvider,
+    spawn_config: SpawnConfig,
+) -> ProviderFeatures {
+    let status = check_provider_availability(&provider, &spawn_config);
+    if !status.available {
+        return ProviderFeatures {
+            provider,
+            error_message: status.error_message,
+            ..Default::default()
+        };
+    }
+
+    let probe_provider = provider.clone();
+    let result = run_localset_blocking(move || async move {
+        run_capability_probe_session(notes_directory, probe_provider, spawn_config).await
+    })
+    .await;
+
+    result.unwrap_or_else(|e| ProviderFeatures {
+        provider,
+        error_message: Some(e),
+        ..Default::default()
+    })
+}
+
+/// Report, per provider, which features will work so the UI can hide or
+/// explain unavailable actions instead of failing at runtime
+#[tauri::command]
+pub(crate) async fn get_feature_matrix(app: AppHandle) -> Result<Vec<ProviderFeatures>, String> {
+    let notes_directory = config::get_notes_directory_required(&app)?;
+    let spawn_config = config::get_spawn_config(&app)?;
+
+    let (claude, gemini) = futures::future::join(
+        probe_provider_features(
+            notes_directory.clone(),
+            AgentProvider::ClaudeCode,
+            spawn_config.clone(),
+        ),
+        probe_provider_features(notes_directory, AgentProvider::GeminiCli, spawn_config),
+    )
+    .await;
+
+    Ok(vec![claude, gemini])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_version;
+
+    #[test]
+    fn test_parse_version_from_cli_output() {
+        assert_eq!(parse_version("2.0.14 (Claude Code)"), Some((2, 0, 14)));
+        assert_eq!(parse_version("gemini v0.9.0-nightly"), Some((0, 9, 0)));
+        assert_eq!(parse_version("version 1.4"), Some((1, 4, 0)));
+        assert_eq!(parse_version("Unknown version"), None);
+    }
+}
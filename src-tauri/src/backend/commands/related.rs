@@ -0,0 +1,124 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use tauri::{AppHandle, State};
+
+use super::notes::resolve_note;
+use super::projects::validate_project_path;
+use crate::backend::config;
+use crate::backend::indexer;
+use crate::backend::links::is_markdown;
+use crate::backend::note_files::read_text_prefix;
+use crate::backend::project_file::{read_project_data, ProjectFile};
+use crate::backend::related::{
+    context_query, find_related_nodes, note_suggestion, NoteSuggestion, RelatedNode,
+};
+use crate::backend::state::AppState;
+
+/// Recent projects searched besides the current one
+const MAX_RELATED_PROJECTS: usize = 20;
+
+const DEFAULT_RELATED_LIMIT: usize = 10;
+const MAX_RELATED_LIMIT: usize = 50;
+
+const DEFAULT_SUGGESTIONS: usize = 5;
+const MAX_SUGGESTIONS: usize = 20;
+
+/// Bytes of each suggested note read for its title and snippet
+const SUGGESTION_READ_BYTES: u64 = 256 * 1024;
+
+/// Nodes of the current project and recently opened ones that discuss
+/// something like `content`, best match first, so earlier explorations of
+/// the same idea can be surfaced. `node_id` (the node `content` comes from)
+/// is left out. Recent projects that can't be read are skipped.
+#[tauri::command]
+pub(crate) async fn find_similar_nodes(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    node_id: String,
+    content: String,
+    project_path: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<RelatedNode>, String> {
+    let limit = limit
+        .unwrap_or(DEFAULT_RELATED_LIMIT)
+        .clamp(1, MAX_RELATED_LIMIT);
+    let current = project_path
+        .as_deref()
+        .map(|path| validate_project_path(&app, path))
+        .transpose()?;
+    let recent: Vec<_> = config::get_recent_projects(&app)?
+        .iter()
+        .filter_map(|path| validate_project_path(&app, path).ok())
+        .filter(|path| Some(path) != current.as_ref())
+        .take(MAX_RELATED_PROJECTS)
+        .collect();
+    let cache = state.project_cache.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let mut projects: Vec<(String, Arc<ProjectFile>)> = Vec::new();
+        if let Some(path) = &current {
+            projects.push((path.to_string_lossy().into_owned(), cache.load(path)?));
+        }
+        // Read directly: the cache only holds the open project
+        for path in recent {
+            match read_project_data(&path).and_then(|data| ProjectFile::parse(&data)) {
+                Ok(project) => {
+                    projects.push((path.to_string_lossy().into_owned(), Arc::new(project)))
+                }
+                Err(e) => tracing::debug!("Skipping {:?} for similar nodes: {}", path, e),
+            }
+        }
+        let projects: Vec<(String, &ProjectFile)> = projects
+            .iter()
+            .map(|(path, project)| (path.clone(), project.as_ref()))
+            .collect();
+        let exclude = projects
+            .first()
+            .filter(|_| current.is_some())
+            .map(|(path, _)| (path.as_str(), node_id.as_str()));
+        Ok(find_related_nodes(&content, &projects, exclude, limit))
+    })
+    .await
+    .map_err(|e| format!("Similar node search failed: {e}"))?
+}
+
+/// Notes in the notes directory most relevant to a node's `content`, best
+/// first, with the line that matches best, so related notes can be offered
+/// as context before prompting. Ranked by the search index.
+#[tauri::command]
+pub(crate) async fn suggest_context(
+    app: AppHandle,
+    content: String,
+    k: Option<usize>,
+) -> Result<Vec<NoteSuggestion>, String> {
+    let k = k.unwrap_or(DEFAULT_SUGGESTIONS).clamp(1, MAX_SUGGESTIONS);
+    let query = context_query(&content);
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    // Over-fetch: project files and other non-notes are in the index too
+    let hits = indexer::query(app.clone(), query.clone(), k * 4).await?;
+
+    tokio::task::spawn_blocking(move || {
+        Ok(hits
+            .into_iter()
+            .filter(|hit| is_markdown(Path::new(&hit.path)))
+            .filter_map(|hit| {
+                let (_, validated, rel_path) = resolve_note(&app, &hit.path).ok()?;
+                let (text, _) = read_text_prefix(&validated, SUGGESTION_READ_BYTES).ok()?;
+                let fallback_title = validated.file_stem()?.to_string_lossy().into_owned();
+                Some(note_suggestion(
+                    &rel_path,
+                    &fallback_title,
+                    &text,
+                    &query,
+                    hit.score,
+                ))
+            })
+            .take(k)
+            .collect())
+    })
+    .await
+    .map_err(|e| format!("Context suggestion failed: {e}"))?
+}
@@ -0,0 +1,33 @@
+use crate::backend::secrets;
+
+/// Store a secret (e.g. an API key) in the OS keychain. Secrets never leave
+/// the backend: there is no command that returns one.
+#[tauri::command]
+pub(crate) async fn set_secret(name: String, value: String) -> Result<(), String> {
+    let stored_name = name.clone();
+    tokio::task::spawn_blocking(move || secrets::set_secret(&name, &value))
+        .await
+        .map_err(|e| format!("Keychain task failed: {e}"))??;
+    tracing::info!("Secret {} stored", stored_name);
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) async fn has_secret(name: String) -> Result<bool, String> {
+    tokio::task::spawn_blocking(move || secrets::has_secret(&name))
+        .await
+        .map_err(|e| format!("Keychain task failed: {e}"))?
+}
+
+/// Remove a secret from the keychain; returns whether there was one
+#[tauri::command]
+pub(crate) async fn delete_secret(name: String) -> Result<bool, String> {
+    let deleted_name = name.clone();
+    let deleted = tokio::task::spawn_blocking(move || secrets::delete_secret(&name))
+        .await
+        .map_err(|e| format!("Keychain task failed: {e}"))??;
+    if deleted {
+        tracing::info!("Secret {} deleted", deleted_name);
+    }
+    Ok(deleted)
+}
@@ -1,9 +1,28 @@
-use tauri::AppHandle;
+use tauri::{AppHandle, Emitter};
 
-use crate::backend::acp::sessions::run_summary_session;
+use crate::backend::acp::sessions::{run_bulk_prompt, run_summary_batch, run_summary_session};
 use crate::backend::config;
 use crate::backend::runtime::run_localset_blocking;
-use crate::backend::types::SummaryResult;
+use crate::backend::summaries::{
+    abstract_prompt, clean_abstract, validate_summary_style, SummaryStyle,
+};
+use crate::backend::tags;
+use crate::backend::types::{
+    AgentProvider, ModelPreferences, SummaryRequest, SummaryResult, TagSuggestions,
+};
+
+/// Most nodes one `generate_summaries` call may title
+const MAX_SUMMARY_BATCH: usize = 50;
+
+/// Provider for background jobs (the default one) and the user's bulk model
+/// override for it
+pub(super) fn bulk_provider(app: &AppHandle) -> Result<(AgentProvider, Option<String>), String> {
+    let provider = config::get_default_provider(app)?;
+    let model_override = config::get_bulk_model_overrides(app)?
+        .get(&provider)
+        .map(String::from);
+    Ok((provider, model_override))
+}
 
 #[tauri::command]
 pub(crate) async fn generate_summary(
@@ -12,22 +31,43 @@ pub(crate) async fn generate_summary(
     content: String,
 ) -> Result<SummaryResult, String> {
     let notes_directory = config::get_notes_directory_required(&app)?;
-    let provider_paths = config::get_provider_paths(&app)?;
-    let custom_path = provider_paths.claude_code;
+    let spawn_config = config::get_spawn_config(&app)?;
+    let style = config::get_summary_style(&app)?;
+    let (provider, model_override) = bulk_provider(&app)?;
 
-    tracing::info!("Generating summary for node: {}", node_id);
+    tracing::info!(
+        "Generating summary for node {} with {:?}",
+        node_id,
+        provider
+    );
 
     let result = run_localset_blocking(move || async move {
-        run_summary_session(content, notes_directory, custom_path)
-            .await
-            .map_err(|e| e.to_string())
+        run_summary_session(
+            content,
+            style,
+            provider,
+            notes_directory,
+            spawn_config,
+            model_override,
+        )
+        .await
+        .map_err(|e| e.to_string())
     })
     .await;
 
     match result {
-        Ok(summary) => {
-            tracing::info!("Generated summary for {}: {}", node_id, summary);
-            Ok(SummaryResult { node_id, summary })
+        Ok(output) => {
+            tracing::info!(
+                "Generated summary for {} with {:?}: {}",
+                node_id,
+                output.model_id,
+                output.summary
+            );
+            Ok(SummaryResult {
+                node_id,
+                summary: output.summary,
+                model_id: output.model_id,
+            })
         }
         Err(error_message) => {
             tracing::warn!(
@@ -39,3 +79,181 @@ pub(crate) async fn generate_summary(
         }
     }
 }
+
+/// Title several nodes over one agent session. A `summary-ready` event
+/// carries each result as it completes; nodes that got no heading are
+/// missing from the returned list.
+#[tauri::command]
+pub(crate) async fn generate_summaries(
+    app: AppHandle,
+    nodes: Vec<SummaryRequest>,
+) -> Result<Vec<SummaryResult>, String> {
+    if nodes.is_empty() {
+        return Ok(Vec::new());
+    }
+    if nodes.len() > MAX_SUMMARY_BATCH {
+        return Err(format!(
+            "Too many nodes to summarize at once ({}, limit {MAX_SUMMARY_BATCH})",
+            nodes.len()
+        ));
+    }
+    let notes_directory = config::get_notes_directory_required(&app)?;
+    let spawn_config = config::get_spawn_config(&app)?;
+    let style = config::get_summary_style(&app)?;
+    let (provider, model_override) = bulk_provider(&app)?;
+
+    tracing::info!(
+        "Generating summaries for {} nodes with {:?}",
+        nodes.len(),
+        provider
+    );
+
+    run_localset_blocking(move || async move {
+        run_summary_batch(
+            nodes,
+            style,
+            provider,
+            notes_directory,
+            spawn_config,
+            model_override,
+            |result| {
+                if let Err(e) = app.emit("summary-ready", result) {
+                    tracing::warn!("Failed to emit summary-ready: {}", e);
+                }
+            },
+        )
+        .await
+        .map_err(|e| e.to_string())
+    })
+    .await
+}
+
+/// A 1-2 sentence abstract of a node for collapsed-branch previews, longer
+/// than its heading. Returned in `summary`.
+#[tauri::command]
+pub(crate) async fn generate_abstract(
+    app: AppHandle,
+    node_id: String,
+    content: String,
+) -> Result<SummaryResult, String> {
+    let notes_directory = config::get_notes_directory_required(&app)?;
+    let spawn_config = config::get_spawn_config(&app)?;
+    let style = config::get_summary_style(&app)?;
+    let (provider, model_override) = bulk_provider(&app)?;
+    let prompt = abstract_prompt(&content, &style);
+
+    tracing::info!(
+        "Generating abstract for node {} with {:?}",
+        node_id,
+        provider
+    );
+
+    let (response, model_id) = run_localset_blocking(move || async move {
+        run_bulk_prompt(
+            prompt,
+            &provider,
+            &notes_directory,
+            &spawn_config,
+            model_override.as_deref(),
+            "abstract-acp",
+        )
+        .await
+        .map_err(|e| e.to_string())
+    })
+    .await?;
+
+    let summary = clean_abstract(&response);
+    if summary.is_empty() {
+        return Err(format!("No abstract generated for node {node_id}"));
+    }
+    Ok(SummaryResult {
+        node_id,
+        summary,
+        model_id,
+    })
+}
+
+/// Suggest 2-4 of the vault's existing tags for a node, most fitting first.
+/// Returns no tags without spawning an agent when the vault has none.
+#[tauri::command]
+pub(crate) async fn suggest_tags(
+    app: AppHandle,
+    node_id: String,
+    content: String,
+) -> Result<TagSuggestions, String> {
+    let notes_directory = config::get_notes_directory_required(&app)?;
+    let scan_directory = notes_directory.clone();
+    let known = tokio::task::spawn_blocking(move || {
+        tags::count_tags(&tags::scan_note_tags(&scan_directory))
+    })
+    .await
+    .map_err(|e| format!("Tag scan failed: {e}"))?;
+    if known.is_empty() {
+        return Ok(TagSuggestions {
+            node_id,
+            tags: Vec::new(),
+            model_id: None,
+        });
+    }
+
+    let spawn_config = config::get_spawn_config(&app)?;
+    let (provider, model_override) = bulk_provider(&app)?;
+    let prompt = tags::tag_suggestion_prompt(&content, &known);
+
+    tracing::info!("Suggesting tags for node {} with {:?}", node_id, provider);
+
+    let (response, model_id) = run_localset_blocking(move || async move {
+        run_bulk_prompt(
+            prompt,
+            &provider,
+            &notes_directory,
+            &spawn_config,
+            model_override.as_deref(),
+            "tags-acp",
+        )
+        .await
+        .map_err(|e| e.to_string())
+    })
+    .await?;
+
+    Ok(TagSuggestions {
+        node_id,
+        tags: tags::parse_tag_suggestions(&response, &known),
+        model_id,
+    })
+}
+
+#[tauri::command]
+pub(crate) async fn get_summary_style(app: AppHandle) -> Result<SummaryStyle, String> {
+    config::get_summary_style(&app)
+}
+
+/// Set how node headings are written (length, language, tone)
+#[tauri::command]
+pub(crate) async fn set_summary_style(app: AppHandle, style: SummaryStyle) -> Result<(), String> {
+    validate_summary_style(&style)?;
+    config::set_summary_style(&app, &style)
+}
+
+#[tauri::command]
+pub(crate) async fn get_bulk_model_overrides(app: AppHandle) -> Result<ModelPreferences, String> {
+    config::get_bulk_model_overrides(&app)
+}
+
+#[tauri::command]
+pub(crate) async fn set_bulk_model_override(
+    app: AppHandle,
+    provider: AgentProvider,
+    model_id: Option<String>,
+) -> Result<(), String> {
+    let mut overrides = config::get_bulk_model_overrides(&app)?;
+    overrides.set(&provider, model_id.clone());
+    config::set_bulk_model_overrides(&app, &overrides)?;
+
+    tracing::info!(
+        "Bulk model override for {:?} set to: {:?}",
+        provider,
+        model_id
+    );
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use std::path::PathBuf;
+
+use tauri::{AppHandle, Manager, State};
+
+use super::projects::validate_project_path;
+use crate::backend::config;
+use crate::backend::note_edit::write_atomic;
+use crate::backend::project_file::encode_project_data;
+use crate::backend::state::AppState;
+use crate::backend::templates::{self, TemplateInfo};
+
+/// User templates live in `<app data>/templates/*.json`
+fn user_templates_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {e}"))?;
+    Ok(dir.join("templates"))
+}
+
+#[tauri::command]
+pub(crate) async fn list_project_templates(app: AppHandle) -> Result<Vec<TemplateInfo>, String> {
+    let dir = user_templates_dir(&app)?;
+    tokio::task::spawn_blocking(move || templates::list_templates(&dir))
+        .await
+        .map_err(|e| format!("Template listing failed: {e}"))
+}
+
+/// Write a new project at `path` scaffolded from a template. The frontend
+/// opens it with `load_project` afterwards.
+#[tauri::command]
+pub(crate) async fn create_project_from_template(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    template_id: String,
+    path: String,
+) -> Result<(), String> {
+    let validated_path = validate_project_path(&app, &path)?;
+    state.project_locks.check_writable(&validated_path)?;
+
+    let template = templates::find_template(&template_id, &user_templates_dir(&app)?)
+        .ok_or_else(|| format!("Unknown template: {template_id}"))?;
+    let project = templates::build_project(&template, chrono::Utc::now().timestamp_millis());
+    let json = serde_json::to_string_pretty(&project.to_value()?)
+        .map_err(|e| format!("Failed to serialize project: {e}"))?;
+    let bytes = encode_project_data(&json, config::get_compress_projects(&app)?)?;
+
+    write_atomic(&validated_path, &bytes)?;
+    tracing::info!(
+        "Created project {:?} from template {}",
+        validated_path,
+        template_id
+    );
+    Ok(())
+}
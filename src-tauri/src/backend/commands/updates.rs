@@ -0,0 +1,64 @@
+use tauri::{AppHandle, Emitter};
+
+use crate::backend::config;
+use crate::backend::safe_mode;
+use crate::backend::updates::{self, UpdateInfo};
+
+/// Compare this build with the latest GitHub release. Nothing is downloaded;
+/// the frontend offers the returned link.
+#[tauri::command]
+pub(crate) async fn check_for_updates(app: AppHandle) -> Result<UpdateInfo, String> {
+    let proxy = config::get_proxy_settings(&app)?;
+    updates::check_for_updates(&proxy).await
+}
+
+#[tauri::command]
+pub(crate) async fn get_check_updates_on_launch(app: AppHandle) -> Result<bool, String> {
+    config::get_check_updates_on_launch(&app)
+}
+
+/// Opt in to (or out of) checking for a new release at startup
+#[tauri::command]
+pub(crate) async fn set_check_updates_on_launch(
+    app: AppHandle,
+    enabled: bool,
+) -> Result<(), String> {
+    config::set_check_updates_on_launch(&app, enabled)?;
+    tracing::info!("Update check on launch set to: {}", enabled);
+    Ok(())
+}
+
+/// Check for updates in the background if the user opted in, and emit
+/// `update-available` when there is one. Skipped in safe mode.
+pub(crate) fn start_update_check(app: AppHandle) {
+    if safe_mode::is_enabled(&app) {
+        return;
+    }
+    match config::get_check_updates_on_launch(&app) {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(e) => {
+            tracing::warn!("Failed to read update check setting: {}", e);
+            return;
+        }
+    }
+    tauri::async_runtime::spawn(async move {
+        let proxy = match config::get_proxy_settings(&app) {
+            Ok(proxy) => proxy,
+            Err(e) => {
+                tracing::warn!("Skipping update check: {}", e);
+                return;
+            }
+        };
+        match updates::check_for_updates(&proxy).await {
+            Ok(info) if info.update_available => {
+                tracing::info!("Update available: {}", info.latest_version);
+                if let Err(e) = app.emit("update-available", info) {
+                    tracing::error!("Failed to emit update-available: {:?}", e);
+                }
+            }
+            Ok(_) => tracing::info!("ThoughtTree is up to date"),
+            Err(e) => tracing::warn!("{}", e),
+        }
+    });
+}
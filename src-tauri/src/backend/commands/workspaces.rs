@@ -0,0 +1,55 @@
+use std::path::Path;
+
+use tauri::AppHandle;
+
+use crate::backend::config;
+use crate::backend::workspaces::WorkspaceConfig;
+
+#[tauri::command]
+pub(crate) async fn list_workspaces(app: AppHandle) -> Result<WorkspaceConfig, String> {
+    config::get_workspaces(&app)
+}
+
+/// Register a notes directory under a unique name. The first workspace added
+/// becomes active.
+#[tauri::command]
+pub(crate) async fn add_workspace(
+    app: AppHandle,
+    name: String,
+    path: String,
+) -> Result<WorkspaceConfig, String> {
+    if !Path::new(&path).is_dir() {
+        return Err(format!("Not a directory: {path}"));
+    }
+
+    let mut workspaces = config::get_workspaces(&app)?;
+    workspaces.add(&name, &path)?;
+    config::set_workspaces(&app, &workspaces)?;
+    tracing::info!("Added workspace {:?} at {}", name.trim(), path);
+    Ok(workspaces)
+}
+
+/// Switch the notes directory used by prompts, permission checks and search
+#[tauri::command]
+pub(crate) async fn set_active_workspace(
+    app: AppHandle,
+    name: String,
+) -> Result<WorkspaceConfig, String> {
+    let mut workspaces = config::get_workspaces(&app)?;
+    workspaces.set_active(&name)?;
+    config::set_workspaces(&app, &workspaces)?;
+    tracing::info!("Active workspace set to {:?}", name);
+    Ok(workspaces)
+}
+
+/// Forget a workspace; its notes directory is left untouched
+#[tauri::command]
+pub(crate) async fn remove_workspace(
+    app: AppHandle,
+    name: String,
+) -> Result<WorkspaceConfig, String> {
+    let mut workspaces = config::get_workspaces(&app)?;
+    workspaces.remove(&name)?;
+    config::set_workspaces(&app, &workspaces)?;
+    Ok(workspaces)
+}
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+
+use sha2::{Digest, Sha256};
+
+use crate::backend::tokens::message_tokens;
+use crate::backend::types::Message;
+
+/// Most recent messages always sent verbatim
+pub(crate) const COMPACTION_KEEP_RECENT: usize = 4;
+
+/// Older messages smaller than this aren't worth an extra agent session
+pub(crate) const MIN_COMPACTION_TOKENS: usize = 4_000;
+
+/// Summaries kept for reuse; deep branches share their older ancestors
+const MAX_CACHED_SUMMARIES: usize = 64;
+
+/// Role of the message that stands in for the compacted ancestors
+pub(crate) const SUMMARY_ROLE: &str = "summary";
+
+/// Where the recent messages start when the ones before them are worth
+/// compacting
+pub(crate) fn compaction_split(messages: &[Message]) -> Option<usize> {
+    let split = messages.len().checked_sub(COMPACTION_KEEP_RECENT)?;
+    let older: usize = messages[..split].iter().map(message_tokens).sum();
+    (split > 1 && older >= MIN_COMPACTION_TOKENS).then_some(split)
+}
+
+/// The older messages as the summarizer reads them. Images and attachments
+/// are only mentioned, not described.
+pub(crate) fn compaction_transcript(messages: &[Message]) -> String {
+    let mut out = String::new();
+    for message in messages {
+        let _ = write!(out, "{}: {}", message.role, message.content.trim());
+        let images = message.images.as_ref().map_or(0, Vec::len);
+        if images > 0 {
+            let _ = write!(out, " [{images} image(s)]");
+        }
+        for attachment in message.attachments.iter().flatten() {
+            let _ = write!(out, " [attachment: {}]", attachment.name);
+        }
+        out.push_str("\n\n");
+    }
+    out
+}
+
+/// Instructions for the summarizer
+pub(crate) fn compaction_prompt(transcript: &str) -> String {
+    format!(
+        "Summarize the conversation below so the summary can replace it as context for \
+         continuing the conversation. Keep facts, decisions, names, numbers, open questions \
+         and anything the user asked to remember; drop pleasantries and repetition. \
+         Do not call any tools. Return ONLY the summary:\n\n{transcript}"
+    )
+}
+
+/// The message sent in place of the compacted ones
+pub(crate) fn summary_message(summary: &str, compacted: usize) -> Message {
+    Message {
+        role: SUMMARY_ROLE.to_string(),
+        content: format!(
+            "Summary of the {compacted} earlier messages of this conversation:\n\n{}",
+            summary.trim()
+        ),
+        images: None,
+        attachments: None,
+        context_paths: None,
+        context_notes: Vec::new(),
+    }
+}
+
+/// Summaries of older ancestors by transcript hash
+#[derive(Default)]
+pub(crate) struct CompactionCache {
+    summaries: Mutex<HashMap<String, String>>,
+}
+
+impl CompactionCache {
+    pub(crate) fn key(transcript: &str) -> String {
+        Sha256::digest(transcript.as_bytes()).iter().fold(
+            String::with_capacity(64),
+            |mut key, byte| {
+                let _ = write!(key, "{byte:02x}");
+                key
+            },
+        )
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<String> {
+        self.summaries.lock().ok()?.get(key).cloned()
+    }
+
+    pub(crate) fn insert(&self, key: String, summary: String) {
+        if let Ok(mut summaries) = self.summaries.lock() {
+            if summaries.len() >= MAX_CACHED_SUMMARIES {
+                summaries.clear();
+            }
+            summaries.insert(key, summary);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::types::MessageAttachment;
+
+    fn message(role: &str, content: &str) -> Message {
+        Message {
+            role: role.to_string(),
+            content: content.to_string(),
+            images: None,
+            attachments: None,
+            context_paths: None,
+            context_notes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_compaction_split_needs_enough_older_text() {
+        let long = "x".repeat(MIN_COMPACTION_TOKENS * 4);
+        let mut messages: Vec<Message> = (0..COMPACTION_KEEP_RECENT + 2)
+            .map(|_| message("user", "short"))
+            .collect();
+        assert_eq!(compaction_split(&messages), None);
+
+        messages[0].content = long;
+        assert_eq!(compaction_split(&messages), Some(2));
+        assert_eq!(compaction_split(&messages[1..]), None);
+    }
+
+    #[test]
+    fn test_transcript_mentions_attachments_and_cache_keys_are_stable() {
+        let mut first = message("user", "Look at this ");
+        first.attachments = Some(vec![MessageAttachment {
+            name: "data.csv".to_string(),
+            mime_type: "text/csv".to_string(),
+            data: String::new(),
+        }]);
+        let transcript = compaction_transcript(&[first, message("assistant", "Done.")]);
+        assert_eq!(
+            transcript,
+            "user: Look at this [attachment: data.csv]\n\nassistant: Done.\n\n"
+        );
+
+        let cache = CompactionCache::default();
+        let key = CompactionCache::key(&transcript);
+        assert_eq!(key, CompactionCache::key(&transcript));
+        assert_eq!(key.len(), 64);
+        cache.insert(key.clone(), "A summary".to_string());
+        assert_eq!(cache.get(&key).as_deref(), Some("A summary"));
+        assert_eq!(
+            summary_message("A summary", 3).content,
+            "Summary of the 3 earlier messages of this conversation:\n\nA summary"
+        );
+    }
+}
@@ -1,12 +1,34 @@
 use std::path::PathBuf;
 
+use serde::de::DeserializeOwned;
 use serde::Serialize;
-use tauri::AppHandle;
+use tauri::{AppHandle, Manager};
 use tauri_plugin_store::StoreExt;
 
-use crate::backend::types::{AgentProvider, ModelPreferences, ProviderPaths};
+use crate::backend::agent_env::{default_allowlist, AgentEnv};
+use crate::backend::claude_options::ClaudeOptions;
+use crate::backend::config_file::{
+    decode_value, spawn_config_from, workspaces_from, CONFIG_STORE, DEFAULT_PROMPT_TIMEOUT_SECS,
+};
+use crate::backend::dictation::DictationSettings;
+use crate::backend::gemini_cli::GeminiSettings;
+use crate::backend::images::DEFAULT_IMAGE_MAX_DIMENSION;
+use crate::backend::preamble::PreambleSettings;
+use crate::backend::proxy::ProxySettings;
+use crate::backend::queue::DEFAULT_MAX_CONCURRENT_GENERATIONS;
+use crate::backend::quick_capture::{CaptureTarget, DEFAULT_CAPTURE_SHORTCUT};
+use crate::backend::retry::DEFAULT_MAX_RETRIES;
+use crate::backend::safe_mode;
+use crate::backend::scheduler::BackgroundJobSettings;
+use crate::backend::state::AppState;
+use crate::backend::summaries::SummaryStyle;
+use crate::backend::types::{
+    AgentProvider, ModelPreferences, ModelPresets, PinnedNode, ProviderPaths, SpawnConfig,
+};
+use crate::backend::workspaces::WorkspaceConfig;
 
-const CONFIG_STORE: &str = "config.json";
+/// How long a permission request waits for an answer before it is cancelled
+pub(crate) const DEFAULT_PERMISSION_TIMEOUT_SECS: u64 = 300;
 
 fn save_serialized_value<T: Serialize + ?Sized>(
     app: &AppHandle,
@@ -26,14 +48,57 @@ fn save_serialized_value<T: Serialize + ?Sized>(
         .map_err(|e| format!("Failed to save config: {e}"))
 }
 
-pub(crate) fn get_notes_directory_optional(app: &AppHandle) -> Result<Option<String>, String> {
+fn load_deserialized_value<T: DeserializeOwned + Default>(
+    app: &AppHandle,
+    key: &str,
+) -> Result<T, String> {
     let store = app
         .store(CONFIG_STORE)
         .map_err(|e| format!("Failed to open config store: {e}"))?;
 
-    Ok(store
-        .get("notes_directory")
-        .and_then(|v| v.as_str().map(String::from)))
+    Ok(decode_value(store.get(key)))
+}
+
+/// Configured workspaces. A config from before workspaces existed is read as
+/// a single default workspace holding the old `notes_directory`.
+pub(crate) fn get_workspaces(app: &AppHandle) -> Result<WorkspaceConfig, String> {
+    let store = app
+        .store(CONFIG_STORE)
+        .map_err(|e| format!("Failed to open config store: {e}"))?;
+
+    Ok(workspaces_from(
+        store.get("workspaces"),
+        store.get("notes_directory"),
+    ))
+}
+
+/// Save workspaces, dropping the legacy `notes_directory` key they replace
+pub(crate) fn set_workspaces(app: &AppHandle, workspaces: &WorkspaceConfig) -> Result<(), String> {
+    let store = app
+        .store(CONFIG_STORE)
+        .map_err(|e| format!("Failed to open config store: {e}"))?;
+    store.delete("notes_directory");
+    save_serialized_value(app, "workspaces", workspaces)
+}
+
+/// Directories of all workspaces, the active one first
+pub(crate) fn get_workspace_directories(app: &AppHandle) -> Result<Vec<PathBuf>, String> {
+    let workspaces = get_workspaces(app)?;
+    let active = workspaces.active_workspace().map(|w| w.name.clone());
+    let mut dirs: Vec<(bool, PathBuf)> = workspaces
+        .workspaces
+        .into_iter()
+        .map(|w| (Some(&w.name) != active.as_ref(), PathBuf::from(w.path)))
+        .collect();
+    dirs.sort_by_key(|(inactive, _)| *inactive);
+    Ok(dirs.into_iter().map(|(_, dir)| dir).collect())
+}
+
+/// Notes directory of the active workspace
+pub(crate) fn get_notes_directory_optional(app: &AppHandle) -> Result<Option<String>, String> {
+    Ok(get_workspaces(app)?
+        .active_workspace()
+        .map(|workspace| workspace.path.clone()))
 }
 
 pub(crate) fn get_notes_directory_required(app: &AppHandle) -> Result<PathBuf, String> {
@@ -42,8 +107,12 @@ pub(crate) fn get_notes_directory_required(app: &AppHandle) -> Result<PathBuf, S
         .ok_or_else(|| "Notes directory not configured. Please set it in settings.".to_string())
 }
 
+/// Point the active workspace at `path` (creating a default workspace if
+/// there is none)
 pub(crate) fn set_notes_directory(app: &AppHandle, path: &str) -> Result<(), String> {
-    save_serialized_value(app, "notes_directory", &path)
+    let mut workspaces = get_workspaces(app)?;
+    workspaces.set_active_path(path);
+    set_workspaces(app, &workspaces)
 }
 
 pub(crate) fn get_default_provider(app: &AppHandle) -> Result<AgentProvider, String> {
@@ -82,6 +151,39 @@ pub(crate) fn set_model_preferences(
     save_serialized_value(app, "model_preferences", preferences)
 }
 
+/// Model ids behind the fast/balanced/deep presets
+pub(crate) fn get_model_presets(app: &AppHandle) -> Result<ModelPresets, String> {
+    load_deserialized_value(app, "model_presets")
+}
+
+pub(crate) fn set_model_presets(app: &AppHandle, presets: &ModelPresets) -> Result<(), String> {
+    save_serialized_value(app, "model_presets", presets)
+}
+
+/// Per-provider session mode (e.g. "plan") applied to new prompt sessions
+pub(crate) fn get_session_mode_preferences(app: &AppHandle) -> Result<ModelPreferences, String> {
+    load_deserialized_value(app, "session_mode_preferences")
+}
+
+pub(crate) fn set_session_mode_preferences(
+    app: &AppHandle,
+    preferences: &ModelPreferences,
+) -> Result<(), String> {
+    save_serialized_value(app, "session_mode_preferences", preferences)
+}
+
+/// Per-provider model overrides for bulk jobs; unset providers use the cheapest model
+pub(crate) fn get_bulk_model_overrides(app: &AppHandle) -> Result<ModelPreferences, String> {
+    load_deserialized_value(app, "bulk_model_overrides")
+}
+
+pub(crate) fn set_bulk_model_overrides(
+    app: &AppHandle,
+    overrides: &ModelPreferences,
+) -> Result<(), String> {
+    save_serialized_value(app, "bulk_model_overrides", overrides)
+}
+
 pub(crate) fn get_provider_paths(app: &AppHandle) -> Result<ProviderPaths, String> {
     let store = app
         .store(CONFIG_STORE)
@@ -117,3 +219,345 @@ pub(crate) fn get_recent_projects(app: &AppHandle) -> Result<Vec<String>, String
 pub(crate) fn set_recent_projects(app: &AppHandle, projects: &[String]) -> Result<(), String> {
     save_serialized_value(app, "recent_projects", projects)
 }
+
+pub(crate) fn get_path_lookup_enabled(app: &AppHandle) -> Result<bool, String> {
+    load_deserialized_value(app, "path_lookup_enabled")
+}
+
+pub(crate) fn set_path_lookup_enabled(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    save_serialized_value(app, "path_lookup_enabled", &enabled)
+}
+
+pub(crate) fn get_npx_fallback_enabled(app: &AppHandle) -> Result<bool, String> {
+    load_deserialized_value(app, "npx_fallback_enabled")
+}
+
+pub(crate) fn set_npx_fallback_enabled(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    save_serialized_value(app, "npx_fallback_enabled", &enabled)
+}
+
+/// Collect every setting that affects agent subprocess spawning
+pub(crate) fn get_spawn_config(app: &AppHandle) -> Result<SpawnConfig, String> {
+    let store = app
+        .store(CONFIG_STORE)
+        .map_err(|e| format!("Failed to open config store: {e}"))?;
+    Ok(spawn_config_from(
+        |key| store.get(key),
+        safe_mode::is_enabled(app),
+        app.state::<AppState>().children.clone(),
+    ))
+}
+
+/// Claude Code options for projects that don't set their own
+pub(crate) fn get_claude_options(app: &AppHandle) -> Result<ClaudeOptions, String> {
+    load_deserialized_value(app, "claude_options")
+}
+
+pub(crate) fn set_claude_options(app: &AppHandle, options: &ClaudeOptions) -> Result<(), String> {
+    save_serialized_value(app, "claude_options", options)
+}
+
+pub(crate) fn get_gemini_settings(app: &AppHandle) -> Result<GeminiSettings, String> {
+    load_deserialized_value(app, "gemini_settings")
+}
+
+pub(crate) fn set_gemini_settings(
+    app: &AppHandle,
+    settings: &GeminiSettings,
+) -> Result<(), String> {
+    save_serialized_value(app, "gemini_settings", settings)
+}
+
+pub(crate) fn get_proxy_settings(app: &AppHandle) -> Result<ProxySettings, String> {
+    load_deserialized_value(app, "proxy_settings")
+}
+
+pub(crate) fn set_proxy_settings(app: &AppHandle, settings: &ProxySettings) -> Result<(), String> {
+    save_serialized_value(app, "proxy_settings", settings)
+}
+
+pub(crate) fn get_quick_capture_target(app: &AppHandle) -> Result<CaptureTarget, String> {
+    load_deserialized_value(app, "quick_capture_target")
+}
+
+pub(crate) fn set_quick_capture_target(
+    app: &AppHandle,
+    target: &CaptureTarget,
+) -> Result<(), String> {
+    save_serialized_value(app, "quick_capture_target", target)
+}
+
+/// The global quick capture shortcut; the default until the user changes
+/// it, `None` once they turn it off
+pub(crate) fn get_quick_capture_shortcut(app: &AppHandle) -> Result<Option<String>, String> {
+    let shortcut: Option<String> = load_deserialized_value(app, "quick_capture_shortcut")?;
+    Ok(match shortcut {
+        None => Some(DEFAULT_CAPTURE_SHORTCUT.to_string()),
+        Some(shortcut) if shortcut.is_empty() => None,
+        Some(shortcut) => Some(shortcut),
+    })
+}
+
+pub(crate) fn set_quick_capture_shortcut(
+    app: &AppHandle,
+    shortcut: Option<&str>,
+) -> Result<(), String> {
+    // Stored as "" when off, so it isn't mistaken for never having been set
+    save_serialized_value(app, "quick_capture_shortcut", shortcut.unwrap_or(""))
+}
+
+pub(crate) fn get_dictation_settings(app: &AppHandle) -> Result<DictationSettings, String> {
+    load_deserialized_value(app, "dictation_settings")
+}
+
+pub(crate) fn set_dictation_settings(
+    app: &AppHandle,
+    settings: &DictationSettings,
+) -> Result<(), String> {
+    save_serialized_value(app, "dictation_settings", settings)
+}
+
+pub(crate) fn get_check_updates_on_launch(app: &AppHandle) -> Result<bool, String> {
+    load_deserialized_value(app, "check_updates_on_launch")
+}
+
+pub(crate) fn set_check_updates_on_launch(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    save_serialized_value(app, "check_updates_on_launch", &enabled)
+}
+
+pub(crate) fn get_agent_sandbox_enabled(app: &AppHandle) -> Result<bool, String> {
+    load_deserialized_value(app, "agent_sandbox_enabled")
+}
+
+pub(crate) fn set_agent_sandbox_enabled(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    save_serialized_value(app, "agent_sandbox_enabled", &enabled)
+}
+
+/// Directories outside the notes agents may read, canonicalized when set
+pub(crate) fn get_read_roots(app: &AppHandle) -> Result<Vec<PathBuf>, String> {
+    load_deserialized_value(app, "read_roots")
+}
+
+pub(crate) fn set_read_roots(app: &AppHandle, roots: &[PathBuf]) -> Result<(), String> {
+    save_serialized_value(app, "read_roots", roots)
+}
+
+/// Parent environment variables agents inherit; the built-in list unless
+/// the user changed it
+pub(crate) fn get_env_allowlist(app: &AppHandle) -> Result<Vec<String>, String> {
+    let allowlist: Option<Vec<String>> = load_deserialized_value(app, "env_allowlist")?;
+    Ok(allowlist.unwrap_or_else(default_allowlist))
+}
+
+/// `None` restores the built-in list
+pub(crate) fn set_env_allowlist(
+    app: &AppHandle,
+    allowlist: Option<&[String]>,
+) -> Result<(), String> {
+    save_serialized_value(app, "env_allowlist", &allowlist)
+}
+
+pub(crate) fn get_agent_env(app: &AppHandle) -> Result<AgentEnv, String> {
+    load_deserialized_value(app, "agent_env")
+}
+
+pub(crate) fn set_agent_env(app: &AppHandle, env: &AgentEnv) -> Result<(), String> {
+    save_serialized_value(app, "agent_env", env)
+}
+
+pub(crate) fn get_pinned_nodes(app: &AppHandle) -> Result<Vec<PinnedNode>, String> {
+    load_deserialized_value(app, "pinned_nodes")
+}
+
+pub(crate) fn set_pinned_nodes(app: &AppHandle, pins: &[PinnedNode]) -> Result<(), String> {
+    save_serialized_value(app, "pinned_nodes", pins)
+}
+
+pub(crate) fn get_export_filename_template(app: &AppHandle) -> Result<Option<String>, String> {
+    load_deserialized_value(app, "export_filename_template")
+}
+
+pub(crate) fn set_export_filename_template(
+    app: &AppHandle,
+    template: Option<&str>,
+) -> Result<(), String> {
+    save_serialized_value(app, "export_filename_template", &template)
+}
+
+pub(crate) fn get_safe_mode(app: &AppHandle) -> Result<bool, String> {
+    load_deserialized_value(app, "safe_mode")
+}
+
+pub(crate) fn set_safe_mode(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    save_serialized_value(app, "safe_mode", &enabled)
+}
+
+pub(crate) fn get_prompt_timeout_secs(app: &AppHandle) -> Result<u64, String> {
+    let timeout: Option<u64> = load_deserialized_value(app, "prompt_timeout_secs")?;
+    Ok(timeout.unwrap_or(DEFAULT_PROMPT_TIMEOUT_SECS))
+}
+
+pub(crate) fn set_prompt_timeout_secs(app: &AppHandle, secs: Option<u64>) -> Result<(), String> {
+    save_serialized_value(app, "prompt_timeout_secs", &secs)
+}
+
+pub(crate) fn get_permission_timeout_secs(app: &AppHandle) -> Result<u64, String> {
+    let timeout: Option<u64> = load_deserialized_value(app, "permission_timeout_secs")?;
+    Ok(timeout.unwrap_or(DEFAULT_PERMISSION_TIMEOUT_SECS))
+}
+
+pub(crate) fn set_permission_timeout_secs(
+    app: &AppHandle,
+    secs: Option<u64>,
+) -> Result<(), String> {
+    save_serialized_value(app, "permission_timeout_secs", &secs)
+}
+
+/// Whether notes referenced by a message go to the agent as resource links
+pub(crate) fn get_note_resource_links(app: &AppHandle) -> Result<bool, String> {
+    load_deserialized_value(app, "note_resource_links")
+}
+
+pub(crate) fn set_note_resource_links(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    save_serialized_value(app, "note_resource_links", &enabled)
+}
+
+/// Instructions sent ahead of every prompt, unless the project sets its own
+pub(crate) fn get_system_prompt(app: &AppHandle) -> Result<Option<String>, String> {
+    load_deserialized_value(app, "system_prompt")
+}
+
+pub(crate) fn set_system_prompt(app: &AppHandle, prompt: Option<String>) -> Result<(), String> {
+    save_serialized_value(app, "system_prompt", &prompt)
+}
+
+/// How node headings are written
+pub(crate) fn get_summary_style(app: &AppHandle) -> Result<SummaryStyle, String> {
+    let style: Option<SummaryStyle> = load_deserialized_value(app, "summary_style")?;
+    Ok(style.unwrap_or_default())
+}
+
+pub(crate) fn set_summary_style(app: &AppHandle, style: &SummaryStyle) -> Result<(), String> {
+    save_serialized_value(app, "summary_style", style)
+}
+
+/// Date line and other details sent ahead of every prompt
+pub(crate) fn get_prompt_preamble(app: &AppHandle) -> Result<PreambleSettings, String> {
+    let settings: Option<PreambleSettings> = load_deserialized_value(app, "prompt_preamble")?;
+    Ok(settings.unwrap_or_default())
+}
+
+pub(crate) fn set_prompt_preamble(
+    app: &AppHandle,
+    settings: &PreambleSettings,
+) -> Result<(), String> {
+    save_serialized_value(app, "prompt_preamble", settings)
+}
+
+/// Longest edge, in pixels, that images are scaled down to before being sent
+pub(crate) fn get_image_max_dimension(app: &AppHandle) -> Result<u32, String> {
+    let dimension: Option<u32> = load_deserialized_value(app, "image_max_dimension")?;
+    Ok(dimension.unwrap_or(DEFAULT_IMAGE_MAX_DIMENSION))
+}
+
+pub(crate) fn set_image_max_dimension(app: &AppHandle, pixels: Option<u32>) -> Result<(), String> {
+    save_serialized_value(app, "image_max_dimension", &pixels)
+}
+
+/// Retry a prompt once when the agent crashes before producing any output
+pub(crate) fn get_retry_on_crash(app: &AppHandle) -> Result<bool, String> {
+    load_deserialized_value(app, "retry_on_crash")
+}
+
+pub(crate) fn set_retry_on_crash(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    save_serialized_value(app, "retry_on_crash", &enabled)
+}
+
+/// Retries of a prompt after rate-limit and overload errors
+pub(crate) fn get_max_retries(app: &AppHandle) -> Result<u32, String> {
+    let retries: Option<u32> = load_deserialized_value(app, "max_retries")?;
+    Ok(retries.unwrap_or(DEFAULT_MAX_RETRIES))
+}
+
+pub(crate) fn set_max_retries(app: &AppHandle, retries: Option<u32>) -> Result<(), String> {
+    save_serialized_value(app, "max_retries", &retries)
+}
+
+/// Record prompt counts and durations in the local analytics database.
+/// Off unless the user opts in.
+pub(crate) fn get_analytics_enabled(app: &AppHandle) -> Result<bool, String> {
+    load_deserialized_value(app, "analytics_enabled")
+}
+
+pub(crate) fn set_analytics_enabled(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    save_serialized_value(app, "analytics_enabled", &enabled)
+}
+
+/// Summarize older ancestor messages instead of replaying them verbatim
+pub(crate) fn get_compact_ancestors(app: &AppHandle) -> Result<bool, String> {
+    load_deserialized_value(app, "compact_ancestors")
+}
+
+pub(crate) fn set_compact_ancestors(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    save_serialized_value(app, "compact_ancestors", &enabled)
+}
+
+/// Whether `append_to_note` may modify notes; off by default so the vault
+/// stays read-only unless the user opts in
+pub(crate) fn get_note_writes_enabled(app: &AppHandle) -> Result<bool, String> {
+    load_deserialized_value(app, "note_writes_enabled")
+}
+
+pub(crate) fn set_note_writes_enabled(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    save_serialized_value(app, "note_writes_enabled", &enabled)
+}
+
+/// Whether project saves and markdown exports are committed to the git
+/// repository they live in
+pub(crate) fn get_git_autocommit_enabled(app: &AppHandle) -> Result<bool, String> {
+    load_deserialized_value(app, "git_autocommit_enabled")
+}
+
+pub(crate) fn set_git_autocommit_enabled(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    save_serialized_value(app, "git_autocommit_enabled", &enabled)
+}
+
+/// Whether project files are gzipped on save
+pub(crate) fn get_compress_projects(app: &AppHandle) -> Result<bool, String> {
+    load_deserialized_value(app, "compress_projects")
+}
+
+pub(crate) fn set_compress_projects(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    save_serialized_value(app, "compress_projects", &enabled)
+}
+
+/// Whether saves move node images into the project's assets folder
+pub(crate) fn get_image_assets(app: &AppHandle) -> Result<bool, String> {
+    load_deserialized_value(app, "image_assets")
+}
+
+pub(crate) fn set_image_assets(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    save_serialized_value(app, "image_assets", &enabled)
+}
+
+pub(crate) fn get_max_concurrent_generations(app: &AppHandle) -> Result<usize, String> {
+    let max: Option<usize> = load_deserialized_value(app, "max_concurrent_generations")?;
+    Ok(max.unwrap_or(DEFAULT_MAX_CONCURRENT_GENERATIONS))
+}
+
+pub(crate) fn set_max_concurrent_generations(app: &AppHandle, max: usize) -> Result<(), String> {
+    save_serialized_value(app, "max_concurrent_generations", &max)
+}
+
+pub(crate) fn get_background_job_settings(
+    app: &AppHandle,
+) -> Result<BackgroundJobSettings, String> {
+    load_deserialized_value(app, "background_jobs")
+}
+
+pub(crate) fn set_background_job_settings(
+    app: &AppHandle,
+    settings: &BackgroundJobSettings,
+) -> Result<(), String> {
+    save_serialized_value(app, "background_jobs", settings)
+}
@@ -0,0 +1,242 @@
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use crate::backend::acp::children::ChildRegistry;
+use crate::backend::agent_env::default_allowlist;
+use crate::backend::gemini_cli::GeminiSettings;
+use crate::backend::queue::DEFAULT_MAX_CONCURRENT_GENERATIONS;
+use crate::backend::types::{AgentProvider, ModelPreferences, SpawnConfig};
+use crate::backend::workspaces::WorkspaceConfig;
+
+/// Settings store in the app data directory
+pub(crate) const CONFIG_STORE: &str = "config.json";
+
+/// How long a prompt may run before the agent is killed
+pub(crate) const DEFAULT_PROMPT_TIMEOUT_SECS: u64 = 600;
+
+/// Bundle identifier from `tauri.conf.json`, which names the app data directory
+const APP_IDENTIFIER: &str = "com.david.thoughttree";
+
+/// A stored setting, or the default when it is missing or doesn't parse
+pub(crate) fn decode_value<T: DeserializeOwned + Default>(value: Option<Value>) -> T {
+    value
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Workspaces from the stored `workspaces` value, or a single default
+/// workspace holding the `notes_directory` of a config from before them
+pub(crate) fn workspaces_from(
+    workspaces: Option<Value>,
+    legacy_notes_directory: Option<Value>,
+) -> WorkspaceConfig {
+    workspaces
+        .and_then(|v| serde_json::from_value::<WorkspaceConfig>(v).ok())
+        .unwrap_or_else(|| {
+            WorkspaceConfig::from_legacy(
+                legacy_notes_directory.and_then(|v| v.as_str().map(String::from)),
+            )
+        })
+}
+
+/// Spawn settings from the stored values `value` looks up. Safe mode ignores
+/// custom provider paths, fallbacks, environment and Gemini settings files,
+/// but never turns a sandbox off, and keeps the proxies so agents can still
+/// reach the network.
+pub(crate) fn spawn_config_from(
+    value: impl Fn(&str) -> Option<Value>,
+    safe_mode: bool,
+    children: ChildRegistry,
+) -> SpawnConfig {
+    let gemini: GeminiSettings = decode_value(value("gemini_settings"));
+    if safe_mode {
+        return SpawnConfig {
+            children,
+            sandbox: decode_value(value("agent_sandbox_enabled")),
+            proxy: decode_value(value("proxy_settings")),
+            gemini: GeminiSettings {
+                sandbox: gemini.sandbox,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+    }
+
+    let env_allowlist: Option<Vec<String>> = decode_value(value("env_allowlist"));
+    SpawnConfig {
+        provider_paths: decode_value(value("provider_paths")),
+        npx_fallback: decode_value(value("npx_fallback_enabled")),
+        children,
+        env: decode_value(value("agent_env")),
+        env_allowlist: env_allowlist.unwrap_or_else(default_allowlist),
+        sandbox: decode_value(value("agent_sandbox_enabled")),
+        proxy: decode_value(value("proxy_settings")),
+        gemini,
+        read_roots: decode_value(value("read_roots")),
+    }
+}
+
+/// The app's settings read straight from `config.json`, for the `acp` CLI,
+/// which runs without the app and its store
+pub(crate) struct ConfigFile(Map<String, Value>);
+
+impl ConfigFile {
+    /// Where the app keeps its settings
+    pub(crate) fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join(APP_IDENTIFIER).join(CONFIG_STORE))
+    }
+
+    /// Read the settings at `path`. A missing file is no settings, like a
+    /// fresh install.
+    pub(crate) fn load(path: &Path) -> Result<Self, String> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self(Map::new())),
+            Err(e) => return Err(format!("Failed to read {}: {e}", path.display())),
+        };
+        serde_json::from_str(&text)
+            .map(Self)
+            .map_err(|e| format!("Failed to parse {}: {e}", path.display()))
+    }
+
+    fn value(&self, key: &str) -> Option<Value> {
+        self.0.get(key).cloned()
+    }
+
+    /// Spawn settings as the app uses them, honoring the safe mode setting
+    pub(crate) fn spawn_config(&self) -> SpawnConfig {
+        spawn_config_from(
+            |key| self.value(key),
+            decode_value(self.value("safe_mode")),
+            ChildRegistry::default(),
+        )
+    }
+
+    pub(crate) fn default_provider(&self) -> AgentProvider {
+        decode_value(self.value("default_provider"))
+    }
+
+    /// The model chosen for `provider` in settings
+    pub(crate) fn preferred_model(&self, provider: &AgentProvider) -> Option<String> {
+        let preferences: ModelPreferences = decode_value(self.value("model_preferences"));
+        preferences.get(provider).map(String::from)
+    }
+
+    /// Per-provider bulk model override, see `config::get_bulk_model_overrides`
+    pub(crate) fn bulk_model_override(&self, provider: &AgentProvider) -> Option<String> {
+        let overrides: ModelPreferences = decode_value(self.value("bulk_model_overrides"));
+        overrides.get(provider).map(String::from)
+    }
+
+    fn workspaces(&self) -> WorkspaceConfig {
+        workspaces_from(self.value("workspaces"), self.value("notes_directory"))
+    }
+
+    /// Notes directory of the active workspace
+    pub(crate) fn notes_directory(&self) -> Option<PathBuf> {
+        self.workspaces()
+            .active_workspace()
+            .map(|workspace| PathBuf::from(&workspace.path))
+    }
+
+    /// Directories of all workspaces
+    pub(crate) fn workspace_directories(&self) -> Vec<PathBuf> {
+        self.workspaces()
+            .workspaces
+            .into_iter()
+            .map(|workspace| PathBuf::from(workspace.path))
+            .collect()
+    }
+
+    pub(crate) fn prompt_timeout_secs(&self) -> u64 {
+        let timeout: Option<u64> = decode_value(self.value("prompt_timeout_secs"));
+        timeout.unwrap_or(DEFAULT_PROMPT_TIMEOUT_SECS)
+    }
+
+    pub(crate) fn max_concurrent_generations(&self) -> usize {
+        let max: Option<usize> = decode_value(self.value("max_concurrent_generations"));
+        max.unwrap_or(DEFAULT_MAX_CONCURRENT_GENERATIONS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_util::TempDir;
+
+    #[test]
+    fn test_app_identifier_matches_tauri_conf() {
+        let conf: Value = serde_json::from_str(include_str!("../../tauri.conf.json")).unwrap();
+        assert_eq!(conf["identifier"], APP_IDENTIFIER);
+    }
+
+    #[test]
+    fn test_config_file_reads_app_settings() {
+        let dir = TempDir::new("config-file");
+        let path = dir.join(CONFIG_STORE);
+        std::fs::write(
+            &path,
+            serde_json::json!({
+                "notes_directory": "/notes",
+                "default_provider": "gemini-cli",
+                "model_preferences": { "gemini-cli": "gemini-2.5-pro" },
+                "bulk_model_overrides": { "gemini-cli": "gemini-2.5-flash" },
+                "prompt_timeout_secs": 60,
+                "agent_sandbox_enabled": true,
+                "read_roots": ["/papers"],
+                "provider_paths": "not a provider paths object",
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let config = ConfigFile::load(&path).unwrap();
+        assert_eq!(config.default_provider(), AgentProvider::GeminiCli);
+        assert_eq!(
+            config.preferred_model(&AgentProvider::GeminiCli).as_deref(),
+            Some("gemini-2.5-pro")
+        );
+        assert_eq!(config.preferred_model(&AgentProvider::ClaudeCode), None);
+        assert_eq!(
+            config
+                .bulk_model_override(&AgentProvider::GeminiCli)
+                .as_deref(),
+            Some("gemini-2.5-flash")
+        );
+        assert_eq!(config.notes_directory(), Some(PathBuf::from("/notes")));
+        assert_eq!(config.workspace_directories(), [PathBuf::from("/notes")]);
+        assert_eq!(config.prompt_timeout_secs(), 60);
+        assert_eq!(
+            config.max_concurrent_generations(),
+            DEFAULT_MAX_CONCURRENT_GENERATIONS
+        );
+        let spawn_config = config.spawn_config();
+        assert!(spawn_config.sandbox);
+        assert_eq!(spawn_config.read_roots, [PathBuf::from("/papers")]);
+        assert_eq!(spawn_config.env_allowlist, default_allowlist());
+
+        // A fresh install has no config file yet
+        let missing = ConfigFile::load(&dir.join("missing.json")).unwrap();
+        assert_eq!(missing.default_provider(), AgentProvider::ClaudeCode);
+        assert_eq!(missing.notes_directory(), None);
+        assert_eq!(missing.prompt_timeout_secs(), DEFAULT_PROMPT_TIMEOUT_SECS);
+
+        std::fs::write(&path, "{").unwrap();
+        assert!(ConfigFile::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_safe_mode_drops_custom_spawn_settings() {
+        let values = serde_json::json!({
+            "agent_sandbox_enabled": true,
+            "read_roots": ["/papers"],
+            "npx_fallback_enabled": true,
+        });
+        let config = spawn_config_from(|key| values.get(key).cloned(), true, Default::default());
+        assert!(config.sandbox);
+        assert!(!config.npx_fallback);
+        assert!(config.read_roots.is_empty());
+    }
+}
@@ -0,0 +1,239 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::backend::links::extract_wikilinks;
+use crate::backend::types::{LinkedNoteReport, Message};
+
+/// At most this much of each referenced note is included
+pub(crate) const MAX_CONTEXT_NOTE_BYTES: u64 = 256 * 1024;
+
+/// Notes one message may reference
+pub(crate) const MAX_CONTEXT_NOTES: usize = 20;
+
+/// Per-prompt budget for notes pulled in through `[[wikilinks]]`
+pub(crate) const MAX_LINKED_CONTEXT_BYTES: usize = 512 * 1024;
+
+/// A note's text wrapped so the agent can tell it apart from the prompt.
+/// `path` is relative to the notes directory.
+pub(crate) fn context_block(path: &str, content: &str, truncated: bool) -> String {
+    let path = path.replace('"', "'");
+    let suffix = if truncated { " (truncated)" } else { "" };
+    format!(
+        "<note path=\"{path}\">\n{}\n</note>{suffix}",
+        content.trim_end()
+    )
+}
+
+/// `content` preceded by the context blocks
+pub(crate) fn prepend_context(content: &str, blocks: &[String]) -> String {
+    if blocks.is_empty() {
+        return content.to_string();
+    }
+    format!("Referenced notes:\n\n{}\n\n{content}", blocks.join("\n\n"))
+}
+
+/// A note referenced by a message, read ahead of the session so it can be
+/// sent as a resource link or, when the agent can't read it back, inline
+#[derive(Clone, Debug)]
+pub(crate) struct ContextNote {
+    /// Relative to the notes directory
+    pub rel_path: String,
+    /// Canonical absolute path, inside the notes directory
+    pub path: PathBuf,
+    pub text: String,
+    pub truncated: bool,
+}
+
+impl ContextNote {
+    pub(crate) fn block(&self) -> String {
+        context_block(&self.rel_path, &self.text, self.truncated)
+    }
+}
+
+/// Prepend each message's referenced notes to its text, the fallback for
+/// sessions whose client can't serve the reads resource links lead to
+pub(crate) fn inline_context_notes(messages: &mut [Message]) {
+    for msg in messages {
+        let notes = std::mem::take(&mut msg.context_notes);
+        let blocks: Vec<String> = notes.iter().map(ContextNote::block).collect();
+        msg.content = prepend_context(&msg.content, &blocks);
+    }
+}
+
+/// `file://` URI of an absolute path, percent-encoded
+pub(crate) fn file_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let encoded: String = path
+        .bytes()
+        .map(|b| match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect();
+    if encoded.starts_with('/') {
+        format!("file://{encoded}")
+    } else {
+        // Windows drive paths
+        format!("file:///{encoded}")
+    }
+}
+
+/// `content` followed by the linked notes' context blocks
+pub(crate) fn append_linked_notes(content: &str, blocks: &[String]) -> String {
+    if blocks.is_empty() {
+        return content.to_string();
+    }
+    format!("{content}\n\nLinked notes:\n\n{}", blocks.join("\n\n"))
+}
+
+/// Resolves the `[[wikilinks]]` of a prompt's messages into context blocks,
+/// each note once, until the byte budget is spent
+pub(crate) struct LinkExpansion {
+    remaining: usize,
+    seen: HashSet<String>,
+    pub report: Vec<LinkedNoteReport>,
+}
+
+impl LinkExpansion {
+    pub(crate) fn new(budget: usize) -> Self {
+        Self {
+            remaining: budget,
+            seen: HashSet::new(),
+            report: Vec::new(),
+        }
+    }
+
+    /// Context blocks for the notes linked from `content` that earlier
+    /// messages didn't already include. `resolve` maps a link target to a
+    /// note path; `read` returns up to the given number of bytes of a note
+    /// and whether it was cut.
+    pub(crate) fn expand(
+        &mut self,
+        content: &str,
+        resolve: impl Fn(&str) -> Option<String>,
+        mut read: impl FnMut(&str, u64) -> Result<(String, bool), String>,
+    ) -> Vec<String> {
+        let mut blocks = Vec::new();
+        for link in extract_wikilinks(content) {
+            let path = resolve(&link.target);
+            let key = path.clone().unwrap_or_else(|| link.target.to_lowercase());
+            if !self.seen.insert(key) {
+                continue;
+            }
+            let mut report = LinkedNoteReport {
+                target: link.target,
+                path: path.clone(),
+                included_bytes: 0,
+                truncated: false,
+            };
+            match path {
+                Some(_) if self.remaining == 0 => report.truncated = true,
+                Some(path) => match read(&path, self.remaining as u64) {
+                    Ok((text, truncated)) => {
+                        self.remaining = self.remaining.saturating_sub(text.len());
+                        report.included_bytes = text.len();
+                        report.truncated = truncated;
+                        blocks.push(context_block(&path, &text, truncated));
+                    }
+                    Err(e) => tracing::warn!("Could not read linked note {}: {}", path, e),
+                },
+                None => {}
+            }
+            self.report.push(report);
+        }
+        blocks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_blocks_are_delimited_and_prepended() {
+        let blocks = vec![
+            context_block("a \"b\".md", "Alpha\n\n", false),
+            context_block("big.md", "Beta", true),
+        ];
+        assert_eq!(
+            prepend_context("Summarize these", &blocks),
+            "Referenced notes:\n\n<note path=\"a 'b'.md\">\nAlpha\n</note>\n\n\
+             <note path=\"big.md\">\nBeta\n</note> (truncated)\n\nSummarize these"
+        );
+        assert_eq!(prepend_context("Hi", &[]), "Hi");
+    }
+
+    #[test]
+    fn test_inline_context_notes_fallback() {
+        let note = ContextNote {
+            rel_path: "ideas.md".to_string(),
+            path: PathBuf::from("/notes/ideas.md"),
+            text: "Alpha".to_string(),
+            truncated: false,
+        };
+        let message = |content: &str, context_notes: Vec<ContextNote>| Message {
+            role: "user".to_string(),
+            content: content.to_string(),
+            images: None,
+            attachments: None,
+            context_paths: None,
+            context_notes,
+        };
+        let mut messages = vec![message("Summarize", vec![note]), message("Hi", Vec::new())];
+
+        inline_context_notes(&mut messages);
+        assert!(messages.iter().all(|m| m.context_notes.is_empty()));
+        assert_eq!(
+            messages[0].content,
+            "Referenced notes:\n\n<note path=\"ideas.md\">\nAlpha\n</note>\n\nSummarize"
+        );
+        assert_eq!(messages[1].content, "Hi");
+    }
+
+    #[test]
+    fn test_file_uri_encodes_path() {
+        assert_eq!(
+            file_uri(Path::new("/Users/me/My Notes/ideas #1.md")),
+            "file:///Users/me/My%20Notes/ideas%20%231.md"
+        );
+        assert_eq!(
+            file_uri(Path::new("C:\\vault\\a.md")),
+            "file:///C:/vault/a.md"
+        );
+    }
+
+    #[test]
+    fn test_link_expansion_dedupes_and_respects_budget() {
+        let resolve = |target: &str| match target {
+            "Alpha" | "alpha alias" => Some("alpha.md".to_string()),
+            "Beta" => Some("dir/beta.md".to_string()),
+            _ => None,
+        };
+        let read = |path: &str, max: u64| {
+            let text = if path == "alpha.md" {
+                "0123456789"
+            } else {
+                "abcdef"
+            };
+            let max = max as usize;
+            Ok((text[..text.len().min(max)].to_string(), text.len() > max))
+        };
+
+        let mut expansion = LinkExpansion::new(14);
+        let blocks = expansion.expand("See [[Alpha]], [[Nope]] and [[alpha alias]]", resolve, read);
+        assert_eq!(blocks, vec![context_block("alpha.md", "0123456789", false)]);
+        let blocks = expansion.expand("Then [[Beta]] and [[Alpha]]", resolve, read);
+        assert_eq!(blocks, vec![context_block("dir/beta.md", "abcd", true)]);
+
+        let report = &expansion.report;
+        assert_eq!(report.len(), 3);
+        assert_eq!(report[1].path, None);
+        assert_eq!((report[2].included_bytes, report[2].truncated), (4, true));
+        assert_eq!(
+            append_linked_notes("Q", &blocks),
+            format!("Q\n\nLinked notes:\n\n{}", blocks[0])
+        );
+    }
+}
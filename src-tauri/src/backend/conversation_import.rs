@@ -0,0 +1,293 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::backend::project_file::ProjectFile;
+use crate::backend::templates::{self, ProjectTemplate, TemplateNode};
+
+/// Chat services whose data exports can be imported
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ConversationFormat {
+    /// `conversations.json` from a ChatGPT data export
+    Chatgpt,
+    /// `conversations.json` from a Claude data export
+    Claude,
+}
+
+fn message_node(role: &str, content: String, timestamp: Option<i64>) -> TemplateNode {
+    TemplateNode {
+        role: role.to_string(),
+        content,
+        provider: None,
+        model: None,
+        timestamp,
+        children: Vec::new(),
+    }
+}
+
+/// Conversations are a single object or an array of them
+fn conversations(root: &Value) -> Vec<&Value> {
+    match root {
+        Value::Array(items) => items.iter().collect(),
+        other => vec![other],
+    }
+}
+
+/// ChatGPT keeps each conversation as a `mapping` of message id to
+/// `{ message, parent, children }`, so regenerated answers and edited
+/// prompts become branches. System, tool and empty messages are dropped and
+/// their children attached to the nearest kept ancestor.
+struct ChatgptConversation<'a> {
+    mapping: &'a serde_json::Map<String, Value>,
+    visited: HashSet<&'a str>,
+}
+
+impl<'a> ChatgptConversation<'a> {
+    fn message(entry: &Value) -> Option<TemplateNode> {
+        let message = entry.get("message")?;
+        let role = match message.pointer("/author/role")?.as_str()? {
+            "user" => "user",
+            "assistant" => "assistant",
+            _ => return None,
+        };
+        let content = message.get("content")?;
+        let text = match content.get("parts").and_then(Value::as_array) {
+            Some(parts) => parts
+                .iter()
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>()
+                .join("\n"),
+            None => content
+                .get("text")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+        };
+        if text.trim().is_empty() {
+            return None;
+        }
+        let timestamp = message
+            .get("create_time")
+            .and_then(Value::as_f64)
+            .map(|secs| (secs * 1000.0) as i64);
+        Some(message_node(role, text, timestamp))
+    }
+
+    /// The kept nodes for `id`: itself with its subtree, or the kept nodes
+    /// of its children when it is dropped
+    fn convert(&mut self, id: &'a str) -> Vec<TemplateNode> {
+        if !self.visited.insert(id) {
+            return Vec::new();
+        }
+        let Some(entry) = self.mapping.get(id) else {
+            return Vec::new();
+        };
+        let mapping = self.mapping;
+        let children: Vec<TemplateNode> = entry
+            .get("children")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .filter_map(|child| mapping.get_key_value(child).map(|(k, _)| k.as_str()))
+            .flat_map(|child| self.convert(child))
+            .collect();
+        match Self::message(entry) {
+            Some(mut node) => {
+                node.children = children;
+                vec![node]
+            }
+            None => children,
+        }
+    }
+}
+
+fn import_chatgpt(root: &Value) -> Vec<TemplateNode> {
+    let mut nodes = Vec::new();
+    for conversation in conversations(root) {
+        let Some(mapping) = conversation.get("mapping").and_then(Value::as_object) else {
+            continue;
+        };
+        let mut converter = ChatgptConversation {
+            mapping,
+            visited: HashSet::new(),
+        };
+        for (id, entry) in mapping {
+            let parent = entry.get("parent").and_then(Value::as_str);
+            if parent.is_none_or(|p| !mapping.contains_key(p)) {
+                nodes.extend(converter.convert(id));
+            }
+        }
+    }
+    nodes
+}
+
+fn claude_text(message: &Value) -> String {
+    let blocks: Vec<&str> = message
+        .get("content")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|block| block.get("type").and_then(Value::as_str) == Some("text"))
+        .filter_map(|block| block.get("text").and_then(Value::as_str))
+        .collect();
+    if blocks.is_empty() {
+        message
+            .get("text")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    } else {
+        blocks.join("\n\n")
+    }
+}
+
+/// Claude lists `chat_messages` in order. Messages name their parent with
+/// `parent_message_uuid` when the conversation branched; without it each
+/// message follows the previous one.
+fn import_claude(root: &Value) -> Vec<TemplateNode> {
+    let mut roots = Vec::new();
+    for conversation in conversations(root) {
+        let Some(messages) = conversation.get("chat_messages").and_then(Value::as_array) else {
+            continue;
+        };
+
+        let mut nodes: Vec<Option<TemplateNode>> = Vec::new();
+        let mut parents: Vec<Option<usize>> = Vec::new();
+        // Message uuid -> index of its node, or of the nearest kept
+        // ancestor for skipped messages
+        let mut index_of: HashMap<&str, usize> = HashMap::new();
+        for message in messages {
+            let parent = match message.get("parent_message_uuid").and_then(Value::as_str) {
+                Some(uuid) => index_of.get(uuid).copied(),
+                None => nodes.len().checked_sub(1),
+            };
+            let uuid = message.get("uuid").and_then(Value::as_str);
+            let role = match message.get("sender").and_then(Value::as_str) {
+                Some("human") => Some("user"),
+                Some("assistant") => Some("assistant"),
+                _ => None,
+            };
+            let text = claude_text(message);
+            let Some(role) = role.filter(|_| !text.trim().is_empty()) else {
+                if let (Some(uuid), Some(parent)) = (uuid, parent) {
+                    index_of.insert(uuid, parent);
+                }
+                continue;
+            };
+
+            let timestamp = message
+                .get("created_at")
+                .and_then(Value::as_str)
+                .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                .map(|t| t.timestamp_millis());
+            if let Some(uuid) = uuid {
+                index_of.insert(uuid, nodes.len());
+            }
+            nodes.push(Some(message_node(role, text, timestamp)));
+            parents.push(parent);
+        }
+
+        // Parents always come before their children, so attaching from the
+        // end moves each finished subtree into its parent
+        let mut conversation_roots = Vec::new();
+        for i in (0..nodes.len()).rev() {
+            let Some(node) = nodes[i].take() else {
+                continue;
+            };
+            match parents[i].and_then(|p| nodes[p].as_mut()) {
+                Some(parent) => parent.children.insert(0, node),
+                None => conversation_roots.push(node),
+            }
+        }
+        roots.extend(conversation_roots.into_iter().rev());
+    }
+    roots
+}
+
+/// Convert a chat export into a new project with one tree per conversation
+pub(crate) fn import_conversations(
+    data: &str,
+    format: ConversationFormat,
+    now_ms: i64,
+) -> Result<ProjectFile, String> {
+    let root: Value =
+        serde_json::from_str(data).map_err(|e| format!("Invalid conversation export: {e}"))?;
+    let nodes = match format {
+        ConversationFormat::Chatgpt => import_chatgpt(&root),
+        ConversationFormat::Claude => import_claude(&root),
+    };
+    if nodes.is_empty() {
+        return Err("No conversations found in the export".to_string());
+    }
+    let template = ProjectTemplate {
+        name: String::new(),
+        description: String::new(),
+        nodes,
+    };
+    Ok(templates::build_project(&template, now_ms))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contents(project: &ProjectFile) -> Vec<&str> {
+        project
+            .graph
+            .nodes
+            .iter()
+            .map(|n| n.content.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_chatgpt_branches_and_skips_hidden_messages() {
+        let data = r#"[{"title": "Sky", "mapping": {
+            "root": {"id": "root", "message": null, "parent": null, "children": ["sys"]},
+            "sys": {"id": "sys", "message": {"author": {"role": "system"}, "content": {"parts": [""]}},
+                    "parent": "root", "children": ["q"]},
+            "q": {"id": "q", "message": {"author": {"role": "user"}, "create_time": 1700000000.5,
+                  "content": {"content_type": "text", "parts": ["Why blue?"]}},
+                  "parent": "sys", "children": ["a1", "a2"]},
+            "a1": {"id": "a1", "message": {"author": {"role": "assistant"},
+                   "content": {"parts": ["Scattering."]}}, "parent": "q", "children": []},
+            "a2": {"id": "a2", "message": {"author": {"role": "assistant"},
+                   "content": {"parts": ["Rayleigh."]}}, "parent": "q", "children": []}
+        }}]"#;
+        let project = import_conversations(data, ConversationFormat::Chatgpt, 0).unwrap();
+        assert_eq!(
+            contents(&project),
+            ["Why blue?", "Scattering.", "Rayleigh."]
+        );
+        assert_eq!(project.graph.nodes[0].timestamp, 1_700_000_000_500);
+        assert_eq!(project.graph.edges.len(), 2);
+        assert_eq!(project.graph.roots().count(), 1);
+    }
+
+    #[test]
+    fn test_claude_linear_and_branched_messages() {
+        let data = r#"[{"name": "Plan", "chat_messages": [
+            {"uuid": "1", "sender": "human", "text": "Plan a trip",
+             "created_at": "2024-05-01T10:00:00Z"},
+            {"uuid": "2", "sender": "assistant", "content": [{"type": "text", "text": "Where to?"}]},
+            {"uuid": "3", "sender": "human", "text": "Rome", "parent_message_uuid": "2"},
+            {"uuid": "x", "sender": "assistant", "text": "", "parent_message_uuid": "2"},
+            {"uuid": "4", "sender": "human", "text": "Oslo", "parent_message_uuid": "x"}
+        ]}, {"name": "Second", "chat_messages": [{"uuid": "5", "sender": "human", "text": "Next"}]}]"#;
+        let project = import_conversations(data, ConversationFormat::Claude, 0).unwrap();
+        let graph = &project.graph;
+        assert_eq!(
+            contents(&project),
+            ["Plan a trip", "Where to?", "Rome", "Oslo", "Next"]
+        );
+        assert_eq!(graph.nodes[0].timestamp, 1_714_557_600_000);
+        assert_eq!(graph.nodes[1].role, "assistant");
+        let answer = &graph.nodes[1].id;
+        assert_eq!(graph.children(answer).count(), 2);
+
+        assert!(import_conversations("[]", ConversationFormat::Claude, 0).is_err());
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use serde::Deserialize;
+
+use crate::backend::project_file::{node_title, ProjectGraph};
+
+/// Text formats for the shape of a tree
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum DiagramFormat {
+    /// A Mermaid flowchart, renderable in GitHub and Obsidian markdown
+    Mermaid,
+    /// Graphviz DOT
+    Dot,
+}
+
+/// Mermaid entity codes for characters that would end or break a label
+fn mermaid_label(text: &str) -> String {
+    text.replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
+
+fn dot_string(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn label(graph: &ProjectGraph, id: &str) -> String {
+    let title = graph.node(id).map(node_title).unwrap_or_default();
+    if title.is_empty() {
+        "Untitled".to_string()
+    } else {
+        title
+    }
+}
+
+/// Render nodes (titles as labels) and parent→child edges. Responses are
+/// drawn rounded, prompts square.
+pub(crate) fn render_diagram(graph: &ProjectGraph, format: DiagramFormat) -> String {
+    // Node ids are UUIDs; short generated ids keep the output readable
+    let short_ids: HashMap<&str, String> = graph
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (n.id.as_str(), format!("n{i}")))
+        .collect();
+    let edges = graph.edges.iter().filter_map(|e| {
+        Some((
+            short_ids.get(e.source.as_str())?,
+            short_ids.get(e.target.as_str())?,
+        ))
+    });
+
+    let mut out = String::new();
+    match format {
+        DiagramFormat::Mermaid => {
+            out.push_str("flowchart TD\n");
+            for node in &graph.nodes {
+                let label = mermaid_label(&label(graph, &node.id));
+                let id = &short_ids[node.id.as_str()];
+                if node.role == "assistant" {
+                    let _ = writeln!(out, "    {id}(\"{label}\")");
+                } else {
+                    let _ = writeln!(out, "    {id}[\"{label}\"]");
+                }
+            }
+            for (source, target) in edges {
+                let _ = writeln!(out, "    {source} --> {target}");
+            }
+        }
+        DiagramFormat::Dot => {
+            out.push_str("digraph thoughttree {\n    rankdir=TB;\n    node [shape=box];\n");
+            for node in &graph.nodes {
+                let label = dot_string(&label(graph, &node.id));
+                let id = &short_ids[node.id.as_str()];
+                if node.role == "assistant" {
+                    let _ = writeln!(out, "    {id} [label=\"{label}\", style=rounded];");
+                } else {
+                    let _ = writeln!(out, "    {id} [label=\"{label}\"];");
+                }
+            }
+            for (source, target) in edges {
+                let _ = writeln!(out, "    {source} -> {target};");
+            }
+            out.push_str("}\n");
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::project_file::ProjectFile;
+
+    fn graph() -> ProjectGraph {
+        ProjectFile::parse(
+            r#"{"version": 3, "graph": {"version": 3, "nodes": [
+                {"id": "q", "role": "user", "content": "Is \"x\" <y>?"},
+                {"id": "a", "role": "assistant", "content": "Yes\nbecause"}
+            ], "edges": [{"id": "q->a", "source": "q", "target": "a"}]}}"#,
+        )
+        .unwrap()
+        .graph
+    }
+
+    #[test]
+    fn test_mermaid_escapes_labels() {
+        assert_eq!(
+            render_diagram(&graph(), DiagramFormat::Mermaid),
+            "flowchart TD\n    n0[\"Is #quot;x#quot; #lt;y#gt;?\"]\n    n1(\"Yes\")\n    n0 --> n1\n"
+        );
+    }
+
+    #[test]
+    fn test_dot_escapes_labels() {
+        let dot = render_diagram(&graph(), DiagramFormat::Dot);
+        assert!(dot.contains("n0 [label=\"Is \\\"x\\\" <y>?\"];"));
+        assert!(dot.contains("n1 [label=\"Yes\", style=rounded];"));
+        assert!(dot.contains("n0 -> n1;"));
+        assert!(dot.ends_with("}\n"));
+    }
+}
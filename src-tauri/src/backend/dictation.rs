@@ -0,0 +1,277 @@
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncReadExt;
+use tokio::process::Command;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+use crate::backend::acp::children::ChildRegistry;
+
+/// Milliseconds of audio between partial transcriptions, and the window
+/// each one covers
+const STEP_MS: u32 = 500;
+const LENGTH_MS: u32 = 5000;
+
+/// Longest transcript kept for one dictation, in bytes
+const MAX_TRANSCRIPT_BYTES: usize = 64 * 1024;
+
+/// Local transcription settings; `whisper-stream` from whisper.cpp does the
+/// recording and transcribing
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub(crate) struct DictationSettings {
+    /// `whisper-stream` executable; found in the usual install locations
+    /// when unset
+    pub binary: Option<String>,
+    /// ggml model file, e.g. `ggml-base.en.bin`
+    pub model: Option<String>,
+    /// Spoken language code like `en` or `de`, or `auto`; English when unset
+    pub language: Option<String>,
+}
+
+fn validate_language(language: &str) -> Result<(), String> {
+    let valid = language == "auto"
+        || ((2..=3).contains(&language.len()) && language.chars().all(|c| c.is_ascii_lowercase()));
+    if !valid {
+        return Err(format!("Invalid language code: {language:?}"));
+    }
+    Ok(())
+}
+
+pub(crate) fn validate_dictation_settings(settings: &DictationSettings) -> Result<(), String> {
+    if let Some(model) = &settings.model {
+        if !Path::new(model).is_file() {
+            return Err(format!("Whisper model not found: {model}"));
+        }
+    }
+    if let Some(binary) = &settings.binary {
+        if !Path::new(binary).is_file() {
+            return Err(format!("whisper-stream not found: {binary}"));
+        }
+    }
+    if let Some(language) = &settings.language {
+        validate_language(language)?;
+    }
+    Ok(())
+}
+
+/// Remove terminal escape sequences (`ESC [ ... letter`), which
+/// `whisper-stream` uses to redraw the line it is transcribing
+fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+        if chars.next() == Some('[') {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Drop non-speech markers such as `[BLANK_AUDIO]` or `(wind blowing)`
+fn remove_noise_markers(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut closing = None;
+    for c in text.chars() {
+        match (closing, c) {
+            (None, '[') => closing = Some(']'),
+            (None, '(') => closing = Some(')'),
+            (None, c) => out.push(c),
+            (Some(end), c) if c == end => closing = None,
+            (Some(_), _) => {}
+        }
+    }
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Transcript assembled from `whisper-stream` output: lines ended by a
+/// newline are final, while the current line is redrawn (after a carriage
+/// return) as the transcription of the latest audio improves
+#[derive(Debug, Default)]
+pub(crate) struct Transcript {
+    committed: Vec<String>,
+    current: String,
+    bytes: usize,
+}
+
+impl Transcript {
+    pub(crate) fn push_output(&mut self, output: &str) {
+        for c in strip_ansi(output).chars() {
+            match c {
+                '\n' => {
+                    let line = remove_noise_markers(&std::mem::take(&mut self.current));
+                    if !line.is_empty() && self.bytes + line.len() <= MAX_TRANSCRIPT_BYTES {
+                        self.bytes += line.len() + 1;
+                        self.committed.push(line);
+                    }
+                }
+                '\r' => self.current.clear(),
+                c if self.current.len() < MAX_TRANSCRIPT_BYTES => self.current.push(c),
+                _ => {}
+            }
+        }
+    }
+
+    /// Everything transcribed so far, including the line still in progress
+    pub(crate) fn text(&self) -> String {
+        let current = remove_noise_markers(&self.current);
+        self.committed
+            .iter()
+            .map(String::as_str)
+            .chain((!current.is_empty()).then_some(current.as_str()))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// A running dictation; [`DictationSession::stop`] ends it
+pub(crate) struct DictationSession {
+    stop: oneshot::Sender<()>,
+    task: JoinHandle<String>,
+}
+
+impl DictationSession {
+    /// Start `whisper-stream` on the default microphone. `on_update` gets the
+    /// whole transcript so far each time it changes.
+    pub(crate) fn start(
+        binary: &Path,
+        model: &Path,
+        language: Option<&str>,
+        children: &ChildRegistry,
+        on_update: impl Fn(&str) + Send + 'static,
+    ) -> Result<Self, String> {
+        let mut command = Command::new(binary);
+        command
+            .arg("--model")
+            .arg(model)
+            .arg("--language")
+            .arg(language.unwrap_or("en"))
+            .arg("--step")
+            .arg(STEP_MS.to_string())
+            .arg("--length")
+            .arg(LENGTH_MS.to_string())
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true);
+        let mut child = children.track(
+            command
+                .spawn()
+                .map_err(|e| format!("Failed to start whisper-stream: {e}"))?,
+        );
+        let mut stdout = child
+            .child
+            .stdout
+            .take()
+            .ok_or_else(|| "whisper-stream has no output".to_string())?;
+        tracing::info!("Dictation started with model {:?}", model);
+
+        let (stop, mut stopped) = oneshot::channel();
+        let task = tokio::spawn(async move {
+            let mut transcript = Transcript::default();
+            let mut pending = Vec::new();
+            let mut buf = [0u8; 4096];
+            loop {
+                tokio::select! {
+                    _ = &mut stopped => break,
+                    read = stdout.read(&mut buf) => {
+                        let n = match read {
+                            Ok(0) | Err(_) => break,
+                            Ok(n) => n,
+                        };
+                        pending.extend_from_slice(&buf[..n]);
+                        // Keep a character split across reads for the next one
+                        let valid = match std::str::from_utf8(&pending) {
+                            Ok(text) => text.len(),
+                            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+                            Err(_) => pending.len(),
+                        };
+                        let text = String::from_utf8_lossy(&pending[..valid]).into_owned();
+                        pending.drain(..valid);
+                        let before = transcript.text();
+                        transcript.push_output(&text);
+                        let after = transcript.text();
+                        if after != before {
+                            on_update(&after);
+                        }
+                    }
+                }
+            }
+            if let Err(e) = child.child.kill().await {
+                tracing::warn!("Failed to stop whisper-stream: {}", e);
+            }
+            // The line in progress counts once recording stops
+            transcript.push_output("\n");
+            transcript.text()
+        });
+        Ok(Self { stop, task })
+    }
+
+    /// Stop recording and return the final transcript
+    pub(crate) async fn stop(self) -> Result<String, String> {
+        let _ = self.stop.send(());
+        self.task
+            .await
+            .map_err(|e| format!("Dictation failed: {e}"))
+    }
+}
+
+/// The model to transcribe with, checked to exist
+pub(crate) fn resolve_model(settings: &DictationSettings) -> Result<PathBuf, String> {
+    let model = settings
+        .model
+        .as_deref()
+        .ok_or_else(|| "Choose a Whisper model (ggml-*.bin) in settings first".to_string())?;
+    let model = PathBuf::from(model);
+    if !model.is_file() {
+        return Err(format!("Whisper model not found: {}", model.display()));
+    }
+    Ok(model)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transcript_follows_redrawn_lines() {
+        let mut transcript = Transcript::default();
+        transcript.push_output("\x1b[2K\r Hello");
+        assert_eq!(transcript.text(), "Hello");
+        transcript.push_output("\x1b[2K\r Hello there [BLANK_AUDIO]");
+        assert_eq!(transcript.text(), "Hello there");
+        transcript.push_output("\n\x1b[2K\r (wind blowing) General");
+        transcript.push_output(" Kenobi");
+        assert_eq!(transcript.text(), "Hello there General Kenobi");
+        transcript.push_output("\n\x1b[2K\r [BLANK_AUDIO]\n");
+        assert_eq!(transcript.text(), "Hello there General Kenobi");
+    }
+
+    #[test]
+    fn test_validate_dictation_settings() {
+        assert!(validate_dictation_settings(&DictationSettings::default()).is_ok());
+        let settings = |language: &str| DictationSettings {
+            language: Some(language.to_string()),
+            ..Default::default()
+        };
+        assert!(validate_dictation_settings(&settings("de")).is_ok());
+        assert!(validate_dictation_settings(&settings("auto")).is_ok());
+        assert!(validate_dictation_settings(&settings("en --foo")).is_err());
+        assert!(validate_dictation_settings(&DictationSettings {
+            model: Some("/nonexistent/ggml-base.bin".to_string()),
+            ..Default::default()
+        })
+        .is_err());
+        assert!(resolve_model(&DictationSettings::default()).is_err());
+    }
+}
@@ -0,0 +1,233 @@
+use std::time::Duration;
+
+use serde::Serialize;
+use similar::{Algorithm, ChangeTag, TextDiff};
+
+use crate::backend::project_file::ProjectFile;
+
+/// Unchanged lines kept around each change
+const CONTEXT_LINES: usize = 3;
+
+/// Longest text compared, in bytes per side
+const MAX_DIFF_BYTES: usize = 1024 * 1024;
+
+/// After this, the diff falls back to a coarser (still correct) result
+const DIFF_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum LineChange {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Part of a changed line; `changed` marks the words that differ from the
+/// other side
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct DiffSegment {
+    pub text: String,
+    pub changed: bool,
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct DiffLine {
+    pub change: LineChange,
+    /// 1-based line number in the first text; `None` for inserted lines
+    pub old_line: Option<usize>,
+    /// 1-based line number in the second text; `None` for deleted lines
+    pub new_line: Option<usize>,
+    /// Without the line ending
+    pub text: String,
+    /// Word-level breakdown of deleted and inserted lines
+    pub segments: Vec<DiffSegment>,
+}
+
+/// A run of changes with its surrounding context. Starts are 1-based.
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct DiffHunk {
+    pub old_start: usize,
+    pub old_len: usize,
+    pub new_start: usize,
+    pub new_len: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub(crate) struct TextDiffResult {
+    pub hunks: Vec<DiffHunk>,
+    pub insertions: usize,
+    pub deletions: usize,
+    /// 0.0 (nothing shared) to 1.0 (identical)
+    pub similarity: f32,
+}
+
+fn trim_line_ending(text: &str) -> String {
+    text.strip_suffix('\n')
+        .map(|t| t.strip_suffix('\r').unwrap_or(t))
+        .unwrap_or(text)
+        .to_string()
+}
+
+/// Line diff of `old` and `new` in hunks, with changed words marked
+pub(crate) fn diff_texts(old: &str, new: &str) -> Result<TextDiffResult, String> {
+    if old.len() > MAX_DIFF_BYTES || new.len() > MAX_DIFF_BYTES {
+        return Err(format!(
+            "Text is too large to compare (limit {} KB)",
+            MAX_DIFF_BYTES / 1024
+        ));
+    }
+    let diff = TextDiff::configure()
+        .algorithm(Algorithm::Patience)
+        .timeout(DIFF_TIMEOUT)
+        .diff_lines(old, new);
+
+    let (mut insertions, mut deletions) = (0, 0);
+    let hunks = diff
+        .grouped_ops(CONTEXT_LINES)
+        .iter()
+        .filter_map(|ops| {
+            let (first, last) = (ops.first()?, ops.last()?);
+            let lines = ops
+                .iter()
+                .flat_map(|op| diff.iter_inline_changes(op))
+                .map(|change| {
+                    let change_kind = match change.tag() {
+                        ChangeTag::Equal => LineChange::Equal,
+                        ChangeTag::Delete => LineChange::Delete,
+                        ChangeTag::Insert => LineChange::Insert,
+                    };
+                    let segments: Vec<DiffSegment> = match change_kind {
+                        LineChange::Equal => Vec::new(),
+                        _ => change
+                            .iter_strings_lossy()
+                            .map(|(changed, text)| DiffSegment {
+                                text: trim_line_ending(&text),
+                                changed,
+                            })
+                            .filter(|segment| !segment.text.is_empty())
+                            .collect(),
+                    };
+                    match change_kind {
+                        LineChange::Insert => insertions += 1,
+                        LineChange::Delete => deletions += 1,
+                        LineChange::Equal => {}
+                    }
+                    DiffLine {
+                        change: change_kind,
+                        old_line: change.old_index().map(|i| i + 1),
+                        new_line: change.new_index().map(|i| i + 1),
+                        text: trim_line_ending(
+                            &change
+                                .iter_strings_lossy()
+                                .map(|(_, text)| text)
+                                .collect::<String>(),
+                        ),
+                        segments,
+                    }
+                })
+                .collect();
+            let (old_start, new_start) = (first.old_range().start, first.new_range().start);
+            Some(DiffHunk {
+                old_start: old_start + 1,
+                old_len: last.old_range().end - old_start,
+                new_start: new_start + 1,
+                new_len: last.new_range().end - new_start,
+                lines,
+            })
+        })
+        .collect();
+
+    Ok(TextDiffResult {
+        hunks,
+        insertions,
+        deletions,
+        similarity: diff.ratio(),
+    })
+}
+
+/// Diff the content of two nodes of a project
+pub(crate) fn diff_nodes(
+    project: &ProjectFile,
+    old_id: &str,
+    new_id: &str,
+) -> Result<TextDiffResult, String> {
+    let content = |id: &str| {
+        project
+            .graph
+            .node(id)
+            .map(|node| node.content.as_str())
+            .ok_or_else(|| format!("Node not found: {id}"))
+    };
+    diff_texts(content(old_id)?, content(new_id)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_texts_groups_changes_into_hunks() {
+        let old: String = (1..=12).map(|i| format!("line {i}\n")).collect();
+        let new = old.replace("line 4\n", "line four\n") + "line 13\n";
+        let diff = diff_texts(&old, &new).unwrap();
+        assert_eq!((diff.insertions, diff.deletions), (2, 1));
+        assert_eq!(diff.hunks.len(), 2);
+
+        let first = &diff.hunks[0];
+        assert_eq!((first.old_start, first.old_len), (1, 7));
+        assert_eq!((first.new_start, first.new_len), (1, 7));
+        let changed: Vec<_> = first
+            .lines
+            .iter()
+            .filter(|l| l.change != LineChange::Equal)
+            .map(|l| (l.change, l.old_line, l.new_line, l.text.as_str()))
+            .collect();
+        assert_eq!(
+            changed,
+            [
+                (LineChange::Delete, Some(4), None, "line 4"),
+                (LineChange::Insert, None, Some(4), "line four")
+            ]
+        );
+        assert_eq!(diff.hunks[1].lines.last().unwrap().text, "line 13");
+    }
+
+    #[test]
+    fn test_diff_texts_marks_changed_words() {
+        let diff = diff_texts("The sky is blue.", "The sky is grey.").unwrap();
+        let inserted = diff.hunks[0]
+            .lines
+            .iter()
+            .find(|l| l.change == LineChange::Insert)
+            .unwrap();
+        let changed: Vec<&str> = inserted
+            .segments
+            .iter()
+            .filter(|s| s.changed)
+            .map(|s| s.text.as_str())
+            .collect();
+        assert_eq!(changed, ["grey."]);
+        assert!(diff.similarity < 1.0);
+
+        let same = diff_texts("same\n", "same\n").unwrap();
+        assert!(same.hunks.is_empty());
+        assert_eq!(same.similarity, 1.0);
+    }
+
+    #[test]
+    fn test_diff_nodes() {
+        let project = ProjectFile::parse(
+            r#"{"version": 3, "graph": {"version": 3,
+                "nodes": [
+                    {"id": "a", "role": "assistant", "content": "Answer one"},
+                    {"id": "b", "role": "assistant", "content": "Answer two"}
+                ],
+                "edges": []}}"#,
+        )
+        .unwrap();
+        let diff = diff_nodes(&project, "a", "b").unwrap();
+        assert_eq!((diff.insertions, diff.deletions), (1, 1));
+        assert!(diff_nodes(&project, "a", "missing").is_err());
+    }
+}
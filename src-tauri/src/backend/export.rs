@@ -0,0 +1,363 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::path::Path;
+
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+
+use crate::backend::project_file::{node_title, ProjectGraph, ProjectImage, ProjectNode};
+
+/// Template used when the user hasn't configured one
+pub(crate) const DEFAULT_FILENAME_TEMPLATE: &str = "{project}-{node}-{date}";
+
+/// Values substituted into an export filename template
+pub(crate) struct FilenameVars<'a> {
+    /// Project file path or name; only the file stem is used
+    pub project: Option<&'a str>,
+    /// Node title (or id) being exported
+    pub node: Option<&'a str>,
+    pub now: DateTime<Local>,
+}
+
+/// Replace characters that are invalid or risky in filenames on any platform
+fn sanitize_filename_part(part: &str) -> String {
+    let sanitized: String = part
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '-',
+            c if c.is_control() => '-',
+            c => c,
+        })
+        .collect();
+    sanitized.trim().trim_matches('.').to_string()
+}
+
+/// Expand `{project}`, `{node}`, `{date}` and `{time}` in `template` and make sure
+/// the result ends with `.{extension}`. Unknown placeholders are kept verbatim.
+pub(crate) fn expand_filename_template(
+    template: &str,
+    vars: &FilenameVars<'_>,
+    extension: &str,
+) -> String {
+    let project = vars
+        .project
+        .map(|p| {
+            Path::new(p)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_else(|| p.to_string())
+        })
+        .unwrap_or_else(|| "untitled".to_string());
+    let node = vars.node.unwrap_or("export");
+
+    let expanded = template
+        .replace("{project}", &sanitize_filename_part(&project))
+        .replace("{node}", &sanitize_filename_part(node))
+        .replace("{date}", &vars.now.format("%Y-%m-%d").to_string())
+        .replace("{time}", &vars.now.format("%H%M%S").to_string());
+
+    let mut filename = sanitize_filename_part(&expanded);
+    if filename.is_empty() {
+        filename = "export".to_string();
+    }
+
+    let suffix = format!(".{extension}");
+    if !filename.to_lowercase().ends_with(&suffix) {
+        filename.push_str(&suffix);
+    }
+    filename
+}
+
+/// What a tree markdown export includes
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default)]
+pub(crate) struct MarkdownExportOptions {
+    /// Render user prompts; when off only responses are exported
+    pub include_prompts: bool,
+    /// Annotate each node with its role, provider, model and time
+    pub include_metadata: bool,
+    /// Embed attached images as data URIs; file exports move them into an
+    /// `assets/` folder
+    pub include_images: bool,
+}
+
+impl Default for MarkdownExportOptions {
+    fn default() -> Self {
+        Self {
+            include_prompts: true,
+            include_metadata: true,
+            include_images: true,
+        }
+    }
+}
+
+/// "Assistant · provider · model · date" for annotating exported nodes
+pub(crate) fn node_metadata(node: &ProjectNode) -> String {
+    let mut parts = vec![if node.role == "assistant" {
+        "Assistant".to_string()
+    } else {
+        "User".to_string()
+    }];
+    parts.extend(node.provider.clone());
+    parts.extend(node.model.clone());
+    if let Some(time) =
+        DateTime::from_timestamp_millis(node.timestamp).filter(|_| node.timestamp > 0)
+    {
+        parts.push(
+            time.with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string(),
+        );
+    }
+    parts.join(" · ")
+}
+
+/// An attached image as a markdown image with a data URI, or `None` when its
+/// MIME type isn't a plain `image/*` type
+fn image_markdown(image: &ProjectImage) -> Option<String> {
+    // An asset that couldn't be loaded
+    if image.data.is_empty() {
+        return None;
+    }
+    let subtype = image.mime_type.strip_prefix("image/")?;
+    let valid = !subtype.is_empty()
+        && subtype
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'-' | b'.'));
+    if !valid {
+        return None;
+    }
+    let alt: String = image
+        .name
+        .as_deref()
+        .unwrap_or("image")
+        .chars()
+        .filter(|c| !matches!(c, '[' | ']' | '\n' | '\r'))
+        .collect();
+    Some(format!(
+        "![{alt}](data:{};base64,{})",
+        image.mime_type, image.data
+    ))
+}
+
+/// Order nodes left to right as laid out on the canvas
+fn sort_by_position(graph: &ProjectGraph, ids: &mut [&str]) {
+    let x = |id: &str| graph.position(id).map_or(0.0, |p| p.x);
+    ids.sort_by(|a, b| x(a).total_cmp(&x(b)));
+}
+
+struct TreeWriter<'a> {
+    graph: &'a ProjectGraph,
+    options: MarkdownExportOptions,
+    visited: HashSet<&'a str>,
+    out: String,
+}
+
+impl<'a> TreeWriter<'a> {
+    fn write(&mut self, id: &'a str, depth: usize) {
+        // Nodes reachable from several parents are written once
+        if !self.visited.insert(id) {
+            return;
+        }
+        let Some(node) = self.graph.node(id) else {
+            return;
+        };
+
+        let rendered = self.options.include_prompts || node.role == "assistant";
+        if rendered {
+            let level = "#".repeat((depth + 1).min(6));
+            let title = node_title(node);
+            let title = if title.is_empty() { "Untitled" } else { &title };
+            let _ = writeln!(self.out, "{level} {title}\n");
+            if self.options.include_metadata {
+                let _ = writeln!(self.out, "*{}*\n", node_metadata(node));
+            }
+            let content = node.content.trim();
+            if !content.is_empty() {
+                let _ = writeln!(self.out, "{content}\n");
+            }
+            if self.options.include_images {
+                for image in node.images.iter().flatten() {
+                    if let Some(image) = image_markdown(image) {
+                        let _ = writeln!(self.out, "{image}\n");
+                    }
+                }
+            }
+        }
+
+        let child_depth = if rendered { depth + 1 } else { depth };
+        let mut children: Vec<&str> = self.graph.children(id).collect();
+        sort_by_position(self.graph, &mut children);
+        for child in children {
+            self.write(child, child_depth);
+        }
+    }
+}
+
+/// The conversation leading to `id`: its first-parent ancestors from the
+/// root down, then the node itself
+pub(crate) fn branch<'a>(graph: &'a ProjectGraph, id: &str) -> Vec<&'a ProjectNode> {
+    let mut nodes = Vec::new();
+    let mut seen = HashSet::new();
+    let mut current = graph.node(id);
+    while let Some(node) = current {
+        if !seen.insert(node.id.as_str()) {
+            break;
+        }
+        nodes.push(node);
+        current = graph.parents(&node.id).next().and_then(|p| graph.node(p));
+    }
+    nodes.reverse();
+    nodes
+}
+
+/// Every node, depth first from the roots, siblings left to right
+pub(crate) fn tree_order(graph: &ProjectGraph) -> Vec<&ProjectNode> {
+    let mut roots: Vec<&str> = graph.roots().map(|n| n.id.as_str()).collect();
+    sort_by_position(graph, &mut roots);
+    roots.reverse();
+
+    let mut stack = roots;
+    let mut seen = HashSet::new();
+    let mut nodes = Vec::new();
+    while let Some(id) = stack.pop() {
+        if !seen.insert(id) {
+            continue;
+        }
+        let Some(node) = graph.node(id) else {
+            continue;
+        };
+        nodes.push(node);
+        let mut children: Vec<&str> = graph.children(id).collect();
+        sort_by_position(graph, &mut children);
+        stack.extend(children.into_iter().rev());
+    }
+    nodes
+}
+
+/// Render the tree under `root` (or every tree in the graph) as markdown
+/// with one heading per node, nested by depth. Headings below level 6 stay
+/// at level 6.
+pub(crate) fn tree_markdown(
+    graph: &ProjectGraph,
+    root: Option<&str>,
+    options: MarkdownExportOptions,
+) -> String {
+    let mut writer = TreeWriter {
+        graph,
+        options,
+        visited: HashSet::new(),
+        out: String::new(),
+    };
+    match root {
+        Some(root) => writer.write(root, 0),
+        None => {
+            let mut roots: Vec<&str> = graph.roots().map(|n| n.id.as_str()).collect();
+            sort_by_position(graph, &mut roots);
+            for root in roots {
+                writer.write(root, 0);
+            }
+        }
+    }
+    let mut out = writer.out.trim_end().to_string();
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::project_file::ProjectFile;
+    use chrono::TimeZone;
+
+    fn vars<'a>(project: Option<&'a str>, node: Option<&'a str>) -> FilenameVars<'a> {
+        FilenameVars {
+            project,
+            node,
+            now: Local.with_ymd_and_hms(2025, 3, 14, 9, 26, 53).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_expand_filename_template_substitutes_all_variables() {
+        let name = expand_filename_template(
+            "{project}-{node}-{date}-{time}",
+            &vars(Some("/notes/research.thoughttree"), Some("Key insight")),
+            "md",
+        );
+        assert_eq!(name, "research-Key insight-2025-03-14-092653.md");
+    }
+
+    #[test]
+    fn test_expand_filename_template_sanitizes_path_separators() {
+        let name = expand_filename_template("{node}", &vars(None, Some("../../etc/passwd")), "md");
+        assert!(!name.contains('/'));
+        assert!(name.ends_with(".md"));
+    }
+
+    #[test]
+    fn test_expand_filename_template_keeps_existing_extension() {
+        let name = expand_filename_template("{project}.md", &vars(Some("tree"), None), "md");
+        assert_eq!(name, "tree.md");
+    }
+
+    fn sample_graph() -> ProjectGraph {
+        ProjectFile::parse(
+            r#"{"version": 3, "graph": {"version": 3, "nodes": [
+                {"id": "q", "role": "user", "content": "Why is the sky blue?"},
+                {"id": "a", "role": "assistant", "content": "Rayleigh scattering.", "provider": "claude-code", "model": "opus"},
+                {"id": "q2", "role": "user", "content": "And sunsets?",
+                 "images": [{"data": "AAAA", "mimeType": "image/png", "name": "sun[1].png"}]},
+                {"id": "a2", "role": "assistant", "content": "Longer path through air.", "summary": "Sunsets"}
+            ], "edges": [
+                {"id": "q->a", "source": "q", "target": "a"},
+                {"id": "a->q2", "source": "a", "target": "q2"},
+                {"id": "q2->a2", "source": "q2", "target": "a2"}
+            ]}}"#,
+        )
+        .unwrap()
+        .graph
+    }
+
+    #[test]
+    fn test_tree_markdown_nests_headings_with_metadata() {
+        let markdown = tree_markdown(
+            &sample_graph(),
+            None,
+            MarkdownExportOptions {
+                include_prompts: true,
+                include_metadata: true,
+                include_images: true,
+            },
+        );
+        assert!(markdown.starts_with("# Why is the sky blue?\n"));
+        assert!(markdown.contains("## Rayleigh scattering.\n\n*Assistant · claude-code · opus*"));
+        assert!(markdown.contains("#### Sunsets\n"));
+        assert!(markdown.contains("And sunsets?\n\n![sun1.png](data:image/png;base64,AAAA)\n"));
+    }
+
+    #[test]
+    fn test_branch_and_tree_order() {
+        let graph = sample_graph();
+        let ids = |nodes: Vec<&ProjectNode>| nodes.iter().map(|n| n.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(branch(&graph, "q2")), ["q", "a", "q2"]);
+        assert_eq!(ids(tree_order(&graph)), ["q", "a", "q2", "a2"]);
+    }
+
+    #[test]
+    fn test_tree_markdown_can_skip_prompts() {
+        let markdown = tree_markdown(
+            &sample_graph(),
+            Some("q"),
+            MarkdownExportOptions {
+                include_prompts: false,
+                include_metadata: false,
+                include_images: false,
+            },
+        );
+        assert_eq!(
+            markdown,
+            "# Rayleigh scattering.\n\nRayleigh scattering.\n\n## Sunsets\n\nLonger path through air.\n"
+        );
+    }
+}
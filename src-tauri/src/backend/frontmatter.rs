@@ -0,0 +1,224 @@
+use serde::Serialize;
+
+use crate::backend::tags::note_tags;
+
+/// A frontmatter value: notes only use scalars and flat lists in practice
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum FrontmatterValue {
+    Scalar(String),
+    List(Vec<String>),
+}
+
+/// Top-level `key: value` pairs of a note's YAML frontmatter block
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Frontmatter {
+    entries: Vec<(String, FrontmatterValue)>,
+}
+
+impl Frontmatter {
+    pub(crate) fn get(&self, key: &str) -> Option<&FrontmatterValue> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v)
+    }
+
+    /// The value as a list; a scalar becomes a one-item list
+    pub(crate) fn list(&self, key: &str) -> Vec<String> {
+        match self.get(key) {
+            Some(FrontmatterValue::List(items)) => items.clone(),
+            Some(FrontmatterValue::Scalar(value)) if !value.is_empty() => vec![value.clone()],
+            _ => Vec::new(),
+        }
+    }
+}
+
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    let quoted = value.len() >= 2
+        && ((value.starts_with('"') && value.ends_with('"'))
+            || (value.starts_with('\'') && value.ends_with('\'')));
+    if quoted {
+        value[1..value.len() - 1].to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Drop a trailing ` # comment` outside of quotes
+fn strip_comment(value: &str) -> &str {
+    if value.trim_start().starts_with(['"', '\'']) {
+        return value;
+    }
+    match value.find(" #") {
+        Some(idx) => &value[..idx],
+        None => value,
+    }
+}
+
+fn parse_value(raw: &str) -> FrontmatterValue {
+    let raw = strip_comment(raw).trim();
+    if let Some(inner) = raw.strip_prefix('[').and_then(|r| r.strip_suffix(']')) {
+        FrontmatterValue::List(
+            inner
+                .split(',')
+                .map(unquote)
+                .filter(|item| !item.is_empty())
+                .collect(),
+        )
+    } else {
+        FrontmatterValue::Scalar(unquote(raw))
+    }
+}
+
+/// Split a note into its frontmatter and body. Only a leading block delimited
+/// by `---` lines counts; notes without one get empty frontmatter and the
+/// whole text as body. This is a deliberately small YAML subset: top-level
+/// scalars, `[a, b]` flow lists and `- item` block lists.
+pub(crate) fn split_frontmatter(text: &str) -> (Frontmatter, &str) {
+    let stripped = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let Some(rest) = stripped
+        .strip_prefix("---\n")
+        .or_else(|| stripped.strip_prefix("---\r\n"))
+    else {
+        return (Frontmatter::default(), text);
+    };
+
+    let mut entries: Vec<(String, FrontmatterValue)> = Vec::new();
+    let mut offset = 0;
+    let mut closed = false;
+
+    for line in rest.split_inclusive('\n') {
+        offset += line.len();
+        let line = line.trim_end_matches(['\n', '\r']);
+        if line == "---" || line == "..." {
+            closed = true;
+            break;
+        }
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        if line.starts_with([' ', '\t']) {
+            // Block list item belonging to the previous key
+            let Some(item) = line.trim_start().strip_prefix('-') else {
+                continue;
+            };
+            let item = unquote(strip_comment(item));
+            if let Some((_, value)) = entries.last_mut() {
+                match value {
+                    FrontmatterValue::List(items) => items.push(item),
+                    FrontmatterValue::Scalar(s) if s.is_empty() => {
+                        *value = FrontmatterValue::List(vec![item]);
+                    }
+                    FrontmatterValue::Scalar(_) => {}
+                }
+            }
+            continue;
+        }
+
+        if let Some((key, value)) = line.split_once(':') {
+            entries.push((key.trim().to_string(), parse_value(value)));
+        }
+    }
+
+    if !closed {
+        return (Frontmatter::default(), text);
+    }
+    (Frontmatter { entries }, &rest[offset..])
+}
+
+/// Frontmatter keys read as a note's creation date, in order of preference
+const CREATED_KEYS: [&str; 3] = ["created", "date", "created_at"];
+
+/// Frontmatter keys read as a note's last-updated date
+const UPDATED_KEYS: [&str; 4] = ["updated", "modified", "last_modified", "updated_at"];
+
+/// What the UI shows when previewing a note as prompt context
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct NoteMetadata {
+    /// Frontmatter `title`, else the first `# heading`, else the file name
+    pub title: String,
+    pub aliases: Vec<String>,
+    /// Frontmatter and inline tags, normalized
+    pub tags: Vec<String>,
+    /// Dates exactly as written in the frontmatter
+    pub created: Option<String>,
+    pub updated: Option<String>,
+    pub has_frontmatter: bool,
+}
+
+fn first_scalar(frontmatter: &Frontmatter, keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .find_map(|key| frontmatter.list(key).into_iter().next())
+}
+
+/// Build a note's metadata; `fallback_title` is used when neither the
+/// frontmatter nor the body provides a title
+pub(crate) fn note_metadata(text: &str, fallback_title: &str) -> NoteMetadata {
+    let (frontmatter, body) = split_frontmatter(text);
+
+    let title = first_scalar(&frontmatter, &["title"])
+        .or_else(|| {
+            body.lines()
+                .find_map(|line| line.strip_prefix("# "))
+                .map(|heading| heading.trim().to_string())
+        })
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| fallback_title.to_string());
+
+    NoteMetadata {
+        title,
+        aliases: [frontmatter.list("aliases"), frontmatter.list("alias")].concat(),
+        tags: note_tags(text).into_iter().collect(),
+        created: first_scalar(&frontmatter, &CREATED_KEYS),
+        updated: first_scalar(&frontmatter, &UPDATED_KEYS),
+        has_frontmatter: frontmatter != Frontmatter::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_scalars_and_lists() {
+        let text = "---\ntitle: \"My Note\"\ntags: [rust, 'ideas']\naliases:\n  - First\n  - Second # comment\ncreated: 2024-05-01\n---\nBody text\n";
+        let (fm, body) = split_frontmatter(text);
+        assert_eq!(
+            fm.get("title"),
+            Some(&FrontmatterValue::Scalar("My Note".to_string()))
+        );
+        assert_eq!(fm.list("tags"), vec!["rust", "ideas"]);
+        assert_eq!(fm.list("aliases"), vec!["First", "Second"]);
+        assert_eq!(fm.list("created"), vec!["2024-05-01"]);
+        assert_eq!(body, "Body text\n");
+    }
+
+    #[test]
+    fn test_note_metadata_falls_back_to_heading_and_file_name() {
+        let meta = note_metadata(
+            "---\naliases: [A]\ndate: 2024-01-02\n---\n# Big Idea\n#tag\n",
+            "file",
+        );
+        assert_eq!(meta.title, "Big Idea");
+        assert_eq!(meta.aliases, vec!["A"]);
+        assert_eq!(meta.tags, vec!["tag"]);
+        assert_eq!(meta.created.as_deref(), Some("2024-01-02"));
+        assert!(meta.has_frontmatter);
+
+        let plain = note_metadata("just text", "file");
+        assert_eq!(plain.title, "file");
+        assert!(!plain.has_frontmatter);
+    }
+
+    #[test]
+    fn test_missing_or_unclosed_frontmatter_keeps_whole_text() {
+        let (fm, body) = split_frontmatter("# Heading\n---\n");
+        assert_eq!(fm, Frontmatter::default());
+        assert_eq!(body, "# Heading\n---\n");
+
+        let unclosed = "---\ntitle: x\nno end";
+        assert_eq!(split_frontmatter(unclosed).1, unclosed);
+    }
+}
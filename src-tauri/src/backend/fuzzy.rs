@@ -0,0 +1,167 @@
+/// Score for each matched character
+const SCORE_MATCH: i64 = 16;
+/// Extra score when a match directly follows the previous one
+const BONUS_CONSECUTIVE: i64 = 8;
+/// Extra score for matching the first character of the candidate
+const BONUS_FIRST_CHAR: i64 = 10;
+/// Extra score for matching right after a path or word separator
+const BONUS_BOUNDARY: i64 = 8;
+/// Extra score for matching the upper-case start of a camelCase word
+const BONUS_CAMEL: i64 = 7;
+/// Penalty per skipped character between two matches
+const PENALTY_GAP: i64 = 1;
+
+const UNREACHABLE: i64 = i64::MIN / 2;
+
+/// A successful fuzzy match of a query against a candidate string
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct FuzzyMatch {
+    pub score: i64,
+    /// Char indices into the candidate of the matched characters, ascending
+    pub indices: Vec<usize>,
+}
+
+fn fold_case(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+fn is_separator(c: char) -> bool {
+    matches!(c, '/' | '\\' | '-' | '_' | '.' | ' ')
+}
+
+fn position_bonus(chars: &[char], idx: usize) -> i64 {
+    if idx == 0 {
+        return BONUS_FIRST_CHAR;
+    }
+    let prev = chars[idx - 1];
+    if is_separator(prev) {
+        BONUS_BOUNDARY
+    } else if prev.is_lowercase() && chars[idx].is_uppercase() {
+        BONUS_CAMEL
+    } else {
+        0
+    }
+}
+
+/// fzf-style fuzzy match: every non-whitespace query character must appear in
+/// `candidate` in order (case-insensitively). Among all such alignments the
+/// best-scoring one is chosen, favouring consecutive runs and matches at word
+/// or path boundaries. Returns `None` when the query is not a subsequence.
+pub(crate) fn fuzzy_match(candidate: &str, query: &str) -> Option<FuzzyMatch> {
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(fold_case)
+        .collect();
+    if query.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            indices: Vec::new(),
+        });
+    }
+
+    let original: Vec<char> = candidate.chars().collect();
+    let folded: Vec<char> = original.iter().copied().map(fold_case).collect();
+    let (n, m) = (folded.len(), query.len());
+
+    // Cheap subsequence check before the quadratic-memory pass
+    let mut remaining = query.iter().peekable();
+    for c in &folded {
+        if remaining.peek() == Some(&c) {
+            remaining.next();
+        }
+    }
+    if remaining.peek().is_some() {
+        return None;
+    }
+
+    let bonus: Vec<i64> = (0..n).map(|j| position_bonus(&original, j)).collect();
+
+    // scores[i][j]: best score for query[..=i] with query[i] matched at j;
+    // back[i][j]: where query[i - 1] was matched in that alignment
+    let mut scores = vec![vec![UNREACHABLE; n]; m];
+    let mut back = vec![vec![0usize; n]; m];
+
+    for j in 0..n {
+        if folded[j] == query[0] {
+            scores[0][j] = SCORE_MATCH + bonus[j];
+        }
+    }
+
+    for i in 1..m {
+        // Best (score - gap penalty, position) over non-adjacent predecessors
+        let mut run = (UNREACHABLE, 0usize);
+        for j in i..n {
+            if j >= 2 {
+                let skipped = scores[i - 1][j - 2] - PENALTY_GAP;
+                run = if skipped >= run.0 - PENALTY_GAP {
+                    (skipped, j - 2)
+                } else {
+                    (run.0 - PENALTY_GAP, run.1)
+                };
+            }
+            if folded[j] != query[i] {
+                continue;
+            }
+
+            let adjacent = scores[i - 1][j - 1] + BONUS_CONSECUTIVE;
+            let (prev_score, prev_idx) = if adjacent >= run.0 {
+                (adjacent, j - 1)
+            } else {
+                run
+            };
+            if prev_score > UNREACHABLE / 2 {
+                scores[i][j] = prev_score + SCORE_MATCH + bonus[j];
+                back[i][j] = prev_idx;
+            }
+        }
+    }
+
+    let (mut idx, score) = scores[m - 1]
+        .iter()
+        .copied()
+        .enumerate()
+        .max_by_key(|&(j, score)| (score, std::cmp::Reverse(j)))?;
+    if score <= UNREACHABLE / 2 {
+        return None;
+    }
+
+    let mut indices = vec![0; m];
+    for i in (0..m).rev() {
+        indices[i] = idx;
+        idx = back[i][idx];
+    }
+
+    Some(FuzzyMatch { score, indices })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_abbreviated_words_with_indices() {
+        let found = fuzzy_match("2024-meeting-notes.md", "mtg notes").unwrap();
+        let matched: String = found
+            .indices
+            .iter()
+            .map(|&i| "2024-meeting-notes.md".chars().nth(i).unwrap())
+            .collect();
+        assert_eq!(matched, "mtgnotes");
+        assert!(fuzzy_match("2024-meeting-notes.md", "notes mtg").is_none());
+    }
+
+    #[test]
+    fn test_prefers_consecutive_and_boundary_matches() {
+        let tight = fuzzy_match("projects/notes.md", "note").unwrap();
+        let scattered = fuzzy_match("anxoxtxe.md", "note").unwrap();
+        assert!(tight.score > scattered.score);
+        assert_eq!(tight.indices, vec![9, 10, 11, 12]);
+    }
+
+    #[test]
+    fn test_is_case_insensitive_and_empty_query_matches() {
+        assert!(fuzzy_match("ReadMe.md", "README").is_some());
+        assert!(fuzzy_match("anything", "  ").unwrap().indices.is_empty());
+    }
+}
@@ -0,0 +1,185 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// Variable Gemini CLI reads an extra settings file from. Its values take
+/// precedence over `~/.gemini/settings.json` and the workspace settings.
+pub(crate) const SETTINGS_PATH_VARIABLE: &str = "GEMINI_CLI_SYSTEM_SETTINGS_PATH";
+
+/// Variable the app passes the keychain API key to Gemini CLI in
+pub(crate) const API_KEY_VARIABLE: &str = "GEMINI_API_KEY";
+
+/// Gemini CLI also accepts a Google Cloud API key
+const GOOGLE_API_KEY_VARIABLE: &str = "GOOGLE_API_KEY";
+
+/// Longest accepted sandbox image reference
+const MAX_SANDBOX_IMAGE_LEN: usize = 256;
+
+/// How Gemini CLI is launched, beyond its executable path. The API key is
+/// not part of this; it lives in the OS keychain.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct GeminiSettings {
+    /// Settings file (JSON) passed to Gemini CLI, e.g. one kept next to the
+    /// notes. Under the agent sandbox it must be somewhere agents can read,
+    /// such as `~/.gemini` or `~/.config`.
+    #[serde(default)]
+    pub settings_path: Option<String>,
+    /// Run Gemini CLI's tools in its own container sandbox (`--sandbox`)
+    #[serde(default)]
+    pub sandbox: bool,
+    /// Image for that sandbox instead of Gemini CLI's default
+    #[serde(default)]
+    pub sandbox_image: Option<String>,
+}
+
+impl GeminiSettings {
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        if let Some(path) = &self.settings_path {
+            let path = Path::new(path);
+            if !path.is_absolute() {
+                return Err("Gemini settings path must be absolute".to_string());
+            }
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                return Err("Gemini settings must be a .json file".to_string());
+            }
+            if !path.is_file() {
+                return Err(format!(
+                    "Gemini settings file not found: {}",
+                    path.display()
+                ));
+            }
+        }
+        if let Some(image) = &self.sandbox_image {
+            // Passed as an argument, so it must not look like a flag
+            let valid = (1..=MAX_SANDBOX_IMAGE_LEN).contains(&image.len())
+                && image
+                    .chars()
+                    .next()
+                    .is_some_and(|c| c.is_ascii_alphanumeric())
+                && image
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "./:_@-".contains(c));
+            if !valid {
+                return Err(format!("Invalid sandbox image: {image:?}"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Command-line flags for these settings
+    pub(crate) fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if self.sandbox {
+            args.push("--sandbox".to_string());
+            if let Some(image) = &self.sandbox_image {
+                args.push("--sandbox-image".to_string());
+                args.push(image.clone());
+            }
+        }
+        args
+    }
+}
+
+/// How Gemini CLI would sign in
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum GeminiAuth {
+    ApiKey,
+    VertexAi,
+    GoogleLogin,
+}
+
+/// Which credentials Gemini CLI would find: an API key (`has_api_key` covers
+/// the keychain and `~/.gemini/.env`), Vertex AI settings in the agent
+/// environment (`env`), or a Google login cached in `gemini_dir`
+/// (`~/.gemini`). `None` means it isn't signed in.
+pub(crate) fn detect_gemini_auth(
+    env: impl Fn(&str) -> Option<String>,
+    has_api_key: bool,
+    gemini_dir: Option<&Path>,
+) -> Option<GeminiAuth> {
+    let is_set = |name: &str| env(name).is_some_and(|value| !value.trim().is_empty());
+    if has_api_key || is_set(API_KEY_VARIABLE) || is_set(GOOGLE_API_KEY_VARIABLE) {
+        return Some(GeminiAuth::ApiKey);
+    }
+    let use_vertex = env("GOOGLE_GENAI_USE_VERTEXAI")
+        .is_some_and(|value| value.trim().eq_ignore_ascii_case("true"));
+    if use_vertex && is_set("GOOGLE_CLOUD_PROJECT") {
+        return Some(GeminiAuth::VertexAi);
+    }
+    gemini_dir
+        .is_some_and(|dir| dir.join("oauth_creds.json").is_file())
+        .then_some(GeminiAuth::GoogleLogin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_util::TempDir;
+
+    #[test]
+    fn test_validate_and_args() {
+        let dir = TempDir::new("gemini");
+        let settings_file = dir.join("settings.json");
+        std::fs::write(&settings_file, "{}").unwrap();
+
+        let settings = GeminiSettings {
+            settings_path: Some(settings_file.to_string_lossy().into_owned()),
+            sandbox: true,
+            sandbox_image: Some("us-docker.pkg.dev/gemini/sandbox:0.9".to_string()),
+        };
+        assert!(settings.validate().is_ok());
+        assert_eq!(
+            settings.args(),
+            [
+                "--sandbox",
+                "--sandbox-image",
+                "us-docker.pkg.dev/gemini/sandbox:0.9"
+            ]
+        );
+        assert!(GeminiSettings::default().args().is_empty());
+
+        let invalid = [
+            ("relative.json", None),
+            ("/no/such/settings.json", None),
+            ("", Some("--privileged")),
+            ("", Some("image name")),
+        ];
+        for (path, image) in invalid {
+            let settings = GeminiSettings {
+                settings_path: (!path.is_empty()).then(|| path.to_string()),
+                sandbox: true,
+                sandbox_image: image.map(String::from),
+            };
+            assert!(settings.validate().is_err(), "{path:?} {image:?}");
+        }
+    }
+
+    #[test]
+    fn test_detect_gemini_auth() {
+        let no_env = |_: &str| None;
+        assert_eq!(detect_gemini_auth(no_env, false, None), None);
+        assert_eq!(
+            detect_gemini_auth(no_env, true, None),
+            Some(GeminiAuth::ApiKey)
+        );
+
+        let vertex = |name: &str| match name {
+            "GOOGLE_GENAI_USE_VERTEXAI" => Some("True".to_string()),
+            "GOOGLE_CLOUD_PROJECT" => Some("notes-123".to_string()),
+            _ => None,
+        };
+        assert_eq!(
+            detect_gemini_auth(vertex, false, None),
+            Some(GeminiAuth::VertexAi)
+        );
+
+        let dir = TempDir::new("gemini");
+        assert_eq!(detect_gemini_auth(no_env, false, Some(&dir)), None);
+        std::fs::write(dir.join("oauth_creds.json"), "{}").unwrap();
+        assert_eq!(
+            detect_gemini_auth(no_env, false, Some(&dir)),
+            Some(GeminiAuth::GoogleLogin)
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::backend::proxy::{http_client_builder, ProxySettings};
+use crate::backend::secrets::{self, GEMINI_API_KEY_SECRET};
+use crate::backend::types::ModelInfo;
+
+/// Gemini API endpoint listing the models an API key can use
+const GEMINI_MODELS_URL: &str = "https://generativelanguage.googleapis.com/v1beta/models";
+
+const MODEL_LIST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Variables Gemini CLI reads its API key from, in its order of precedence
+const API_KEY_VARIABLES: [&str; 2] = ["GEMINI_API_KEY", "GOOGLE_API_KEY"];
+
+/// Model families listed by the API that can't hold a text conversation
+const NON_CHAT_MARKERS: [&str; 5] = ["embedding", "-tts", "-image", "-live", "aqa"];
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiModel {
+    name: String,
+    #[serde(default)]
+    display_name: Option<String>,
+    #[serde(default)]
+    supported_generation_methods: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ApiModelList {
+    #[serde(default)]
+    models: Vec<ApiModel>,
+}
+
+/// Value of `key` in a dotenv file, without quotes
+fn dotenv_value(contents: &str, key: &str) -> Option<String> {
+    contents.lines().find_map(|line| {
+        let line = line.trim();
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (name, value) = line.split_once('=')?;
+        (name.trim() == key)
+            .then(|| value.trim().trim_matches(['"', '\'']).to_string())
+            .filter(|value| !value.is_empty())
+    })
+}
+
+/// The API key Gemini CLI would use: from the environment, then the key
+/// saved in the OS keychain, then `~/.gemini/.env`. `None` for accounts
+/// signed in with Google or Vertex AI.
+pub(crate) fn gemini_api_key() -> Option<String> {
+    let from_env = API_KEY_VARIABLES.iter().find_map(|name| {
+        std::env::var(name)
+            .ok()
+            .filter(|key| !key.trim().is_empty())
+    });
+    let from_keychain = || {
+        secrets::get_secret(GEMINI_API_KEY_SECRET)
+            .inspect_err(|e| tracing::warn!("{}", e))
+            .ok()
+            .flatten()
+    };
+    from_env.or_else(from_keychain).or_else(|| {
+        let contents = std::fs::read_to_string(dirs::home_dir()?.join(".gemini/.env")).ok()?;
+        API_KEY_VARIABLES
+            .iter()
+            .find_map(|name| dotenv_value(&contents, name))
+    })
+}
+
+/// Chat models in a `models.list` response, newest version first
+fn parse_model_list(body: &str) -> Result<Vec<ModelInfo>, String> {
+    let list: ApiModelList =
+        serde_json::from_str(body).map_err(|e| format!("Unexpected model list: {e}"))?;
+    let mut models: Vec<ModelInfo> = list
+        .models
+        .into_iter()
+        .filter(|model| {
+            model
+                .supported_generation_methods
+                .iter()
+                .any(|method| method == "generateContent")
+        })
+        .filter_map(|model| {
+            let model_id = model.name.strip_prefix("models/")?.to_string();
+            let is_chat = model_id.starts_with("gemini-")
+                && !NON_CHAT_MARKERS.iter().any(|m| model_id.contains(m));
+            is_chat.then(|| ModelInfo {
+                display_name: model.display_name.unwrap_or_else(|| model_id.clone()),
+                model_id,
+            })
+        })
+        .collect();
+    models.sort_by(|a, b| b.model_id.cmp(&a.model_id));
+    models.dedup_by(|a, b| a.model_id == b.model_id);
+    Ok(models)
+}
+
+/// Ask the Gemini API which models `api_key` can use. The key goes in a
+/// header so it never ends up in a URL or log line.
+pub(crate) async fn list_gemini_models(
+    api_key: &str,
+    proxy: &ProxySettings,
+) -> Result<Vec<ModelInfo>, String> {
+    let client = http_client_builder(proxy)?
+        .timeout(MODEL_LIST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {e}"))?;
+    let response = client
+        .get(GEMINI_MODELS_URL)
+        .query(&[("pageSize", "1000")])
+        .header("x-goog-api-key", api_key)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to list Gemini models: {}", e.without_url()))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("Gemini model list request failed: {status}"));
+    }
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read Gemini model list: {}", e.without_url()))?;
+    parse_model_list(&body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_model_list_keeps_chat_models() {
+        let body = r#"{"models": [
+            {"name": "models/gemini-2.5-flash", "displayName": "Gemini 2.5 Flash",
+             "supportedGenerationMethods": ["generateContent", "countTokens"]},
+            {"name": "models/gemini-3-pro-preview", "displayName": "Gemini 3 Pro Preview",
+             "supportedGenerationMethods": ["generateContent"]},
+            {"name": "models/gemini-embedding-001",
+             "supportedGenerationMethods": ["embedContent"]},
+            {"name": "models/gemini-2.5-flash-preview-tts",
+             "supportedGenerationMethods": ["generateContent"]},
+            {"name": "models/gemma-3-27b-it",
+             "supportedGenerationMethods": ["generateContent"]}
+        ]}"#;
+        let models = parse_model_list(body).unwrap();
+        let ids: Vec<&str> = models.iter().map(|m| m.model_id.as_str()).collect();
+        assert_eq!(ids, ["gemini-3-pro-preview", "gemini-2.5-flash"]);
+        assert_eq!(models[1].display_name, "Gemini 2.5 Flash");
+        assert!(parse_model_list("{}").unwrap().is_empty());
+        assert!(parse_model_list("<html>").is_err());
+    }
+
+    #[test]
+    fn test_dotenv_value() {
+        let contents = "# keys\nexport GEMINI_API_KEY=\"abc123\"\nOTHER=1\nGOOGLE_API_KEY=\n";
+        assert_eq!(
+            dotenv_value(contents, "GEMINI_API_KEY").as_deref(),
+            Some("abc123")
+        );
+        assert_eq!(dotenv_value(contents, "GOOGLE_API_KEY"), None);
+        assert_eq!(dotenv_value(contents, "MISSING"), None);
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::oneshot;
+
+struct ActiveGeneration {
+    id: u64,
+    cancel: oneshot::Sender<()>,
+    done: oneshot::Receiver<()>,
+}
+
+/// In-flight generations by node id, so a node's generation can be cancelled
+#[derive(Default)]
+pub(crate) struct GenerationRegistry {
+    active: Mutex<HashMap<String, ActiveGeneration>>,
+    next_id: AtomicU64,
+}
+
+impl GenerationRegistry {
+    /// Track a generation for `node_id`. The returned receiver resolves when
+    /// the generation should stop; the guard unregisters it when dropped.
+    pub(crate) fn register(
+        self: &Arc<Self>,
+        node_id: &str,
+    ) -> (GenerationGuard, oneshot::Receiver<()>) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (cancel, cancelled) = oneshot::channel();
+        let (done_tx, done) = oneshot::channel();
+
+        if let Ok(mut active) = self.active.lock() {
+            active.insert(node_id.to_string(), ActiveGeneration { id, cancel, done });
+        }
+
+        (
+            GenerationGuard {
+                registry: self.clone(),
+                node_id: node_id.to_string(),
+                id,
+                _done: done_tx,
+            },
+            cancelled,
+        )
+    }
+
+    /// Ids of the nodes generating right now
+    pub(crate) fn active_nodes(&self) -> HashSet<String> {
+        self.active
+            .lock()
+            .map(|active| active.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Cancel the node's in-flight generation, if any, and wait until it has
+    /// stopped. Returns whether there was one.
+    pub(crate) async fn cancel(&self, node_id: &str) -> bool {
+        let entry = self
+            .active
+            .lock()
+            .ok()
+            .and_then(|mut active| active.remove(node_id));
+        match entry {
+            Some(entry) => {
+                let _ = entry.cancel.send(());
+                // Resolves (with an error) once the generation drops its guard
+                let _ = entry.done.await;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Keeps a generation registered; dropping it signals that it has stopped
+pub(crate) struct GenerationGuard {
+    registry: Arc<GenerationRegistry>,
+    node_id: String,
+    id: u64,
+    _done: oneshot::Sender<()>,
+}
+
+impl Drop for GenerationGuard {
+    fn drop(&mut self) {
+        if let Ok(mut active) = self.registry.active.lock() {
+            // A newer generation for the same node may have replaced this one
+            if active.get(&self.node_id).is_some_and(|g| g.id == self.id) {
+                active.remove(&self.node_id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::FutureExt;
+
+    use super::*;
+
+    #[test]
+    fn test_cancel_signals_generation_and_waits_for_it() {
+        let registry = Arc::new(GenerationRegistry::default());
+        let (guard, mut cancelled) = registry.register("node");
+
+        let mut cancel = Box::pin(registry.cancel("node"));
+        assert!(cancel.as_mut().now_or_never().is_none());
+        assert!((&mut cancelled).now_or_never().is_some());
+
+        drop(guard);
+        assert_eq!(cancel.now_or_never(), Some(true));
+        assert_eq!(registry.cancel("node").now_or_never(), Some(false));
+    }
+
+    #[test]
+    fn test_stale_guard_keeps_newer_generation() {
+        let registry = Arc::new(GenerationRegistry::default());
+        let (old, _) = registry.register("node");
+        let (_new, _) = registry.register("node");
+
+        drop(old);
+        assert!(registry.active.lock().unwrap().contains_key("node"));
+    }
+}
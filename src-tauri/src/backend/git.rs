@@ -0,0 +1,145 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use serde::Serialize;
+
+/// Field separator for `git log` output; can't appear in commit subjects
+const FIELD_SEP: char = '\u{1f}';
+
+const MAX_LOG_ENTRIES: usize = 200;
+
+/// One commit touching a project file
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct GitLogEntry {
+    pub hash: String,
+    pub author: String,
+    /// Unix timestamp in seconds
+    pub timestamp: i64,
+    pub message: String,
+}
+
+/// Run git in `dir`. Arguments are passed directly (no shell), and git is
+/// told never to prompt, since there is no terminal to answer it.
+fn git(dir: &Path, args: &[&str]) -> Result<Output, String> {
+    Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()
+        .map_err(|e| format!("Failed to run git: {e}"))
+}
+
+fn git_checked(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = git(dir, args)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("git {} failed: {}", args[0], stderr.trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Root of the git work tree containing `path`, if any
+pub(crate) fn find_repo_root(path: &Path) -> Option<PathBuf> {
+    let dir = if path.is_dir() { path } else { path.parent()? };
+    let output = git(dir, &["rev-parse", "--show-toplevel"]).ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let root = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!root.is_empty()).then(|| PathBuf::from(root))
+}
+
+/// Commit `file` in its repository with `message`, leaving anything else
+/// the user has staged untouched. Returns `Ok(false)` when the file isn't in
+/// a repository or has no changes.
+pub(crate) fn commit_file(file: &Path, message: &str) -> Result<bool, String> {
+    // git reports the resolved root, so the file must be resolved the same way
+    let file = std::fs::canonicalize(file).map_err(|e| format!("Failed to resolve path: {e}"))?;
+    let Some(root) = find_repo_root(&file) else {
+        return Ok(false);
+    };
+    let file = file.to_string_lossy();
+
+    git_checked(&root, &["add", "--", &file])?;
+    let unchanged = git(&root, &["diff", "--cached", "--quiet", "--", &file])?
+        .status
+        .success();
+    if unchanged {
+        return Ok(false);
+    }
+    git_checked(
+        &root,
+        &["commit", "--quiet", "-m", message, "--only", "--", &file],
+    )?;
+    Ok(true)
+}
+
+/// Commit message for an automatic commit of `file`, e.g. `"thoughttree: autosave plan"`
+pub(crate) fn commit_message(action: &str, file: &Path) -> String {
+    let name = file
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "project".to_string());
+    format!("thoughttree: {action} {name}")
+}
+
+fn parse_log(stdout: &str) -> Vec<GitLogEntry> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(4, FIELD_SEP);
+            Some(GitLogEntry {
+                hash: fields.next()?.to_string(),
+                author: fields.next()?.to_string(),
+                timestamp: fields.next()?.parse().ok()?,
+                message: fields.next()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Most recent commits touching `file`, newest first. Empty when the file
+/// isn't in a repository.
+pub(crate) fn file_log(file: &Path, limit: usize) -> Result<Vec<GitLogEntry>, String> {
+    let Some(root) = find_repo_root(file) else {
+        return Ok(Vec::new());
+    };
+    let max_count = format!("--max-count={}", limit.clamp(1, MAX_LOG_ENTRIES));
+    let stdout = git_checked(
+        &root,
+        &[
+            "log",
+            &max_count,
+            "--format=%H%x1f%an%x1f%at%x1f%s",
+            "--",
+            &file.to_string_lossy(),
+        ],
+    )?;
+    Ok(parse_log(&stdout))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_log() {
+        let stdout = "abc123\u{1f}Ada\u{1f}1700000000\u{1f}thoughttree: autosave plan\n\
+                      def456\u{1f}Bob\u{1f}1690000000\u{1f}Fix: a\u{1f}b\n\
+                      garbage line\n";
+        let entries = parse_log(stdout);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].hash, "abc123");
+        assert_eq!(entries[0].timestamp, 1_700_000_000);
+        assert_eq!(entries[1].message, "Fix: a\u{1f}b");
+    }
+
+    #[test]
+    fn test_commit_message_uses_file_stem() {
+        assert_eq!(
+            commit_message("autosave", Path::new("/notes/Research plan.thoughttree")),
+            "thoughttree: autosave Research plan"
+        );
+    }
+}
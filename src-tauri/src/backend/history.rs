@@ -0,0 +1,250 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::backend::note_edit::write_atomic;
+use crate::backend::project_file::{
+    decode_project_bytes, encode_project_data, is_project_file, ProjectFile,
+};
+
+/// Versions kept per node; the oldest are dropped first
+const MAX_VERSIONS_PER_NODE: usize = 50;
+
+/// Nodes with more content than this are not tracked
+const MAX_VERSION_BYTES: usize = 1024 * 1024;
+
+/// Saves this soon after the previous version update it instead of adding
+/// one, so autosaves while typing don't flood the history. The first
+/// version of a node is never replaced.
+const COALESCE_WINDOW_MS: i64 = 2 * 60 * 1000;
+
+/// `notes/plan.thoughttree` -> `notes/.plan.thoughttree.history`, beside the
+/// project like its lock file
+pub(crate) fn history_path(project: &Path) -> Option<PathBuf> {
+    let name = project.file_name()?.to_string_lossy();
+    Some(project.with_file_name(format!(".{name}.history")))
+}
+
+/// The project a history file belongs to, the reverse of `history_path`
+fn history_project(path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?.to_str()?;
+    let project = path.with_file_name(name.strip_prefix('.')?.strip_suffix(".history")?);
+    is_project_file(&project).then_some(project)
+}
+
+/// History files under `dirs` whose project no longer exists. Like
+/// `find_projects`, hidden directories are skipped and symlinks are not
+/// followed.
+pub(crate) fn orphaned_history_files(dirs: &[PathBuf]) -> Vec<PathBuf> {
+    dirs.iter()
+        .flat_map(|dir| {
+            WalkDir::new(dir)
+                .follow_links(false)
+                .max_depth(20)
+                .into_iter()
+                .filter_entry(|entry| {
+                    entry.depth() == 0
+                        || !entry.file_type().is_dir()
+                        || !entry.file_name().to_string_lossy().starts_with('.')
+                })
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_type().is_file())
+                .filter(|entry| {
+                    history_project(entry.path())
+                        .is_some_and(|project| std::fs::symlink_metadata(project).is_err())
+                })
+                .map(|entry| entry.into_path())
+        })
+        .collect()
+}
+
+/// One saved phrasing of a node
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct NodeVersion {
+    /// Counts up from 1 per node
+    pub version: u32,
+    pub content: String,
+    /// Unix milliseconds when this version was last saved
+    pub timestamp: i64,
+}
+
+/// Earlier contents of a project's nodes, stored gzipped beside it
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub(crate) struct ProjectHistory {
+    #[serde(default)]
+    nodes: HashMap<String, Vec<NodeVersion>>,
+}
+
+impl ProjectHistory {
+    /// Read a history file; a missing one is empty
+    pub(crate) fn load(path: &Path) -> Result<Self, String> {
+        let raw = match std::fs::read(path) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(format!("Failed to read node history: {e}")),
+        };
+        serde_json::from_str(&decode_project_bytes(&raw)?)
+            .map_err(|e| format!("Invalid node history: {e}"))
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> Result<(), String> {
+        let data = serde_json::to_string(self)
+            .map_err(|e| format!("Failed to serialize node history: {e}"))?;
+        write_atomic(path, &encode_project_data(&data, true)?)
+    }
+
+    /// Versions of a node, oldest first
+    pub(crate) fn versions(&self, node_id: &str) -> &[NodeVersion] {
+        self.nodes.get(node_id).map_or(&[], Vec::as_slice)
+    }
+
+    pub(crate) fn version(&self, node_id: &str, version: u32) -> Option<&NodeVersion> {
+        self.versions(node_id).iter().find(|v| v.version == version)
+    }
+
+    /// Add a version for every node whose content differs from its latest
+    /// one, except nodes in `skip` (e.g. still generating). History of nodes
+    /// no longer in the project is dropped. Returns whether anything changed.
+    pub(crate) fn record(
+        &mut self,
+        project: &ProjectFile,
+        skip: &HashSet<String>,
+        now_ms: i64,
+    ) -> bool {
+        let mut changed = false;
+        let ids: HashSet<&str> = project.graph.nodes.iter().map(|n| n.id.as_str()).collect();
+        let before = self.nodes.len();
+        self.nodes.retain(|id, _| ids.contains(id.as_str()));
+        changed |= self.nodes.len() != before;
+
+        for node in &project.graph.nodes {
+            if skip.contains(&node.id)
+                || node.content.trim().is_empty()
+                || node.content.len() > MAX_VERSION_BYTES
+            {
+                continue;
+            }
+            let versions = self.nodes.entry(node.id.clone()).or_default();
+            let coalesce = versions.len() > 1;
+            match versions.last_mut() {
+                Some(last) if last.content == node.content => continue,
+                Some(last) if coalesce && now_ms - last.timestamp < COALESCE_WINDOW_MS => {
+                    last.content = node.content.clone();
+                    last.timestamp = now_ms;
+                }
+                last => {
+                    let version = last.map_or(1, |v| v.version + 1);
+                    versions.push(NodeVersion {
+                        version,
+                        content: node.content.clone(),
+                        timestamp: now_ms,
+                    });
+                    let excess = versions.len().saturating_sub(MAX_VERSIONS_PER_NODE);
+                    versions.drain(..excess);
+                }
+            }
+            changed = true;
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_util::TempDir;
+
+    fn project(contents: &[(&str, &str)]) -> ProjectFile {
+        let mut project = ProjectFile::default();
+        project.graph.nodes = contents
+            .iter()
+            .map(|(id, content)| {
+                serde_json::from_value(serde_json::json!({
+                    "id": id,
+                    "role": "assistant",
+                    "content": content,
+                }))
+                .unwrap()
+            })
+            .collect();
+        project
+    }
+
+    #[test]
+    fn test_record_adds_versions_and_coalesces_quick_edits() {
+        let mut history = ProjectHistory::default();
+        let none = HashSet::new();
+        let minute = 60 * 1000;
+
+        assert!(history.record(&project(&[("a", "First")]), &none, 0));
+        assert!(!history.record(&project(&[("a", "First")]), &none, minute));
+        // The first version is kept even for a quick edit
+        assert!(history.record(&project(&[("a", "Second")]), &none, minute));
+        assert!(history.record(&project(&[("a", "Second!")]), &none, 2 * minute));
+        assert!(history.record(&project(&[("a", "Third")]), &none, 10 * minute));
+
+        let versions: Vec<(u32, &str)> = history
+            .versions("a")
+            .iter()
+            .map(|v| (v.version, v.content.as_str()))
+            .collect();
+        assert_eq!(versions, [(1, "First"), (2, "Second!"), (3, "Third")]);
+        assert_eq!(history.version("a", 2).unwrap().timestamp, 2 * minute);
+    }
+
+    #[test]
+    fn test_record_skips_generating_nodes_and_drops_deleted_ones() {
+        let mut history = ProjectHistory::default();
+        let none = HashSet::new();
+        history.record(&project(&[("a", "Kept"), ("b", "Gone soon")]), &none, 0);
+
+        let generating = HashSet::from(["a".to_string()]);
+        history.record(&project(&[("a", "Partial answ")]), &generating, 1);
+        assert_eq!(history.versions("a").len(), 1);
+        assert!(history.versions("b").is_empty());
+    }
+
+    #[test]
+    fn test_record_caps_versions_and_round_trips() {
+        let mut history = ProjectHistory::default();
+        let none = HashSet::new();
+        for i in 0..(MAX_VERSIONS_PER_NODE as i64 + 5) {
+            let content = format!("Version {i}");
+            history.record(&project(&[("a", &content)]), &none, i * COALESCE_WINDOW_MS);
+        }
+        let versions = history.versions("a");
+        assert_eq!(versions.len(), MAX_VERSIONS_PER_NODE);
+        assert_eq!(versions[0].version, 6);
+
+        let dir = TempDir::new("history");
+        let path = history_path(&dir.join("plan.thoughttree")).unwrap();
+        assert_eq!(path, dir.join(".plan.thoughttree.history"));
+        assert_eq!(
+            ProjectHistory::load(&path).unwrap(),
+            ProjectHistory::default()
+        );
+        history.save(&path).unwrap();
+        assert_eq!(ProjectHistory::load(&path).unwrap(), history);
+    }
+
+    #[test]
+    fn test_orphaned_history_files() {
+        let dir = TempDir::new("history");
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("kept.thoughttree"), "{}").unwrap();
+        for name in [
+            ".kept.thoughttree.history",
+            "sub/.gone.thoughttree.history",
+            ".notes.md.history",
+        ] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+
+        assert_eq!(
+            orphaned_history_files(&[dir.to_path_buf()]),
+            [dir.join("sub/.gone.thoughttree.history")]
+        );
+    }
+}
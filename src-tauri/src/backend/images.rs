@@ -0,0 +1,152 @@
+use std::io::Cursor;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType;
+use image::DynamicImage;
+use tracing::{info, warn};
+
+use crate::backend::types::MessageImage;
+
+/// Longest edge of images sent to agents. Claude scales larger images down
+/// to about this size anyway, so the extra pixels only cost upload time.
+pub(crate) const DEFAULT_IMAGE_MAX_DIMENSION: u32 = 1568;
+
+/// Smallest accepted `image_max_dimension` setting
+pub(crate) const MIN_IMAGE_MAX_DIMENSION: u32 = 256;
+
+const JPEG_QUALITY: u8 = 85;
+
+/// An image after downscaling and re-encoding
+#[derive(Debug)]
+pub(crate) struct ShrunkImage {
+    pub bytes: Vec<u8>,
+    pub mime_type: &'static str,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Whether any pixel is not fully opaque
+fn has_transparency(image: &DynamicImage) -> bool {
+    image.color().has_alpha() && image.to_rgba8().pixels().any(|p| p.0[3] < u8::MAX)
+}
+
+fn encode(image: &DynamicImage) -> Result<(Vec<u8>, &'static str), image::ImageError> {
+    let mut bytes = Vec::new();
+    if has_transparency(image) {
+        // The WebP encoder is lossless only, but keeps the alpha channel
+        image
+            .to_rgba8()
+            .write_with_encoder(WebPEncoder::new_lossless(Cursor::new(&mut bytes)))?;
+        Ok((bytes, "image/webp"))
+    } else {
+        image
+            .to_rgb8()
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut bytes, JPEG_QUALITY))?;
+        Ok((bytes, "image/jpeg"))
+    }
+}
+
+/// Scale `bytes` down so neither side exceeds `max_dimension` and re-encode
+/// it: JPEG when opaque, lossless WebP when transparency matters. `None`
+/// when the image can't be decoded or the result wouldn't be smaller. GIFs
+/// are left alone since they may be animated.
+pub(crate) fn shrink_image(
+    bytes: &[u8],
+    mime_type: &str,
+    max_dimension: u32,
+) -> Option<ShrunkImage> {
+    if mime_type == "image/gif" {
+        return None;
+    }
+    let image = image::load_from_memory(bytes)
+        .inspect_err(|e| warn!("Could not decode {} image: {}", mime_type, e))
+        .ok()?;
+    let image = if image.width() > max_dimension || image.height() > max_dimension {
+        image.resize(max_dimension, max_dimension, FilterType::Lanczos3)
+    } else {
+        image
+    };
+    let (encoded, mime_type) = encode(&image)
+        .inspect_err(|e| warn!("Could not re-encode image: {}", e))
+        .ok()?;
+    (encoded.len() < bytes.len()).then(|| ShrunkImage {
+        bytes: encoded,
+        mime_type,
+        width: image.width(),
+        height: image.height(),
+    })
+}
+
+/// A message image ready to send: shrunk when that saves space, otherwise
+/// unchanged
+pub(crate) fn prepare_message_image(image: &MessageImage, max_dimension: u32) -> MessageImage {
+    let Ok(bytes) = STANDARD.decode(&image.data) else {
+        warn!("Image is not valid base64; sending it unchanged");
+        return image.clone();
+    };
+    match shrink_image(&bytes, &image.mime_type, max_dimension) {
+        Some(shrunk) => {
+            info!(
+                "Shrunk {} image from {} to {} bytes ({}x{} {})",
+                image.mime_type,
+                bytes.len(),
+                shrunk.bytes.len(),
+                shrunk.width,
+                shrunk.height,
+                shrunk.mime_type
+            );
+            MessageImage {
+                data: STANDARD.encode(&shrunk.bytes),
+                mime_type: shrunk.mime_type.to_string(),
+            }
+        }
+        None => image.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, Rgb, RgbImage, Rgba, RgbaImage};
+
+    fn png(image: DynamicImage) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_shrink_image_downscales_opaque_image_to_jpeg() {
+        let noisy = RgbImage::from_fn(2000, 500, |x, y| {
+            Rgb([(x * 7) as u8, (y * 13) as u8, (x ^ y) as u8])
+        });
+        let shrunk = shrink_image(&png(noisy.into()), "image/png", 1000).unwrap();
+        assert_eq!((shrunk.width, shrunk.height), (1000, 250));
+        assert_eq!(shrunk.mime_type, "image/jpeg");
+        assert_eq!(
+            image::guess_format(&shrunk.bytes).unwrap(),
+            ImageFormat::Jpeg
+        );
+    }
+
+    #[test]
+    fn test_shrink_image_keeps_transparency_as_webp() {
+        let translucent = RgbaImage::from_fn(800, 800, |x, y| {
+            Rgba([x as u8, y as u8, 0, (x % 200) as u8])
+        });
+        let shrunk = shrink_image(&png(translucent.into()), "image/png", 400).unwrap();
+        assert_eq!((shrunk.width, shrunk.height), (400, 400));
+        assert_eq!(shrunk.mime_type, "image/webp");
+    }
+
+    #[test]
+    fn test_shrink_image_skips_gifs_and_garbage() {
+        assert!(shrink_image(b"GIF89a", "image/gif", 100).is_none());
+        assert!(shrink_image(b"not an image", "image/png", 100).is_none());
+    }
+}
@@ -0,0 +1,185 @@
+use std::path::PathBuf;
+
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+
+use crate::backend::config;
+use crate::backend::project_index::{ProjectIndex, ProjectIndexHit};
+use crate::backend::safe_mode;
+use crate::backend::search_index::{IndexHit, IndexStats, SearchIndex};
+use crate::backend::state::AppState;
+
+const SEARCH_INDEX_FILE: &str = "search-index.json";
+const PROJECT_INDEX_FILE: &str = "project-index.json";
+
+/// Location of a persisted index in the app data dir
+fn data_file(app: &AppHandle, name: &str) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {e}"))?;
+    Ok(dir.join(name))
+}
+
+fn index_path(app: &AppHandle) -> Result<PathBuf, String> {
+    data_file(app, SEARCH_INDEX_FILE)
+}
+
+/// Refresh the index for the current notes directory, loading it from disk
+/// first if needed. With `rebuild`, existing entries are discarded.
+fn refresh_blocking(app: &AppHandle, rebuild: bool) -> Result<IndexStats, String> {
+    let notes_directory = config::get_notes_directory_required(app)?;
+    let path = index_path(app)?;
+    let state = app.state::<AppState>();
+    let mut slot = state.search_index.lock().unwrap_or_else(|e| e.into_inner());
+
+    let reusable = !rebuild
+        && slot
+            .as_ref()
+            .is_some_and(|i| i.is_usable_for(&notes_directory));
+    let index = match slot.take() {
+        Some(index) if reusable => index,
+        _ if rebuild => SearchIndex::new(&notes_directory),
+        _ => SearchIndex::load(&path)
+            .filter(|i| i.is_usable_for(&notes_directory))
+            .unwrap_or_else(|| SearchIndex::new(&notes_directory)),
+    };
+    let index = slot.insert(index);
+
+    let stats = index.refresh();
+    if rebuild || stats.indexed > 0 || stats.removed > 0 {
+        index.save(&path)?;
+    }
+    Ok(stats)
+}
+
+/// Rebuild the index from scratch
+pub(crate) async fn rebuild(app: AppHandle) -> Result<IndexStats, String> {
+    tokio::task::spawn_blocking(move || refresh_blocking(&app, true))
+        .await
+        .map_err(|e| format!("Indexing failed: {e}"))?
+}
+
+/// Query the index, bringing it up to date first so results never point at
+/// deleted notes
+pub(crate) async fn query(
+    app: AppHandle,
+    query: String,
+    limit: usize,
+) -> Result<Vec<IndexHit>, String> {
+    tokio::task::spawn_blocking(move || {
+        refresh_blocking(&app, false)?;
+        let state = app.state::<AppState>();
+        let slot = state.search_index.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(slot
+            .as_ref()
+            .map(|index| index.query(&query, limit))
+            .unwrap_or_default())
+    })
+    .await
+    .map_err(|e| format!("Search failed: {e}"))?
+}
+
+/// Refresh the project index for the current workspaces, loading it from
+/// disk first if needed
+fn refresh_projects_blocking(app: &AppHandle) -> Result<IndexStats, String> {
+    let dirs = config::get_workspace_directories(app)?;
+    let path = data_file(app, PROJECT_INDEX_FILE)?;
+    let state = app.state::<AppState>();
+    let mut slot = state
+        .project_index
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    let index = match slot.take() {
+        Some(index) => index,
+        None => ProjectIndex::load(&path).unwrap_or_else(ProjectIndex::new),
+    };
+    let index = slot.insert(index);
+
+    let stats = index.refresh(&dirs);
+    if stats.indexed > 0 || stats.removed > 0 || !path.exists() {
+        index.save(&path)?;
+    }
+    Ok(stats)
+}
+
+/// Ranked nodes across every project in the workspaces, bringing the
+/// project index up to date (or building it) first
+pub(crate) async fn query_projects(
+    app: AppHandle,
+    query: String,
+    limit: usize,
+) -> Result<Vec<ProjectIndexHit>, String> {
+    tokio::task::spawn_blocking(move || {
+        refresh_projects_blocking(&app)?;
+        let state = app.state::<AppState>();
+        let slot = state
+            .project_index
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        Ok(slot
+            .as_ref()
+            .map(|index| index.query(&query, limit))
+            .unwrap_or_default())
+    })
+    .await
+    .map_err(|e| format!("Search failed: {e}"))?
+}
+
+/// Re-index a project that was just saved. Nothing happens until the
+/// project index has been built by a first query, or in safe mode.
+pub(crate) fn spawn_project_update(app: &AppHandle, project: PathBuf) {
+    if safe_mode::is_enabled(app) {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let Ok(path) = data_file(&app, PROJECT_INDEX_FILE) else {
+            return;
+        };
+        let state = app.state::<AppState>();
+        let mut slot = state
+            .project_index
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if slot.is_none() {
+            *slot = ProjectIndex::load(&path);
+        }
+        let Some(index) = slot.as_mut() else {
+            return;
+        };
+        let result = index
+            .update_project(&project)
+            .and_then(|()| index.save(&path));
+        if let Err(e) = result {
+            warn!("Project index not updated for {:?}: {}", project, e);
+        }
+    });
+}
+
+/// Refresh the index if one was already built, for the background
+/// scheduler. Returns `None` when there is no index to maintain.
+pub(crate) async fn maintain(app: &AppHandle) -> Result<Option<IndexStats>, String> {
+    let has_index = app
+        .state::<AppState>()
+        .search_index
+        .lock()
+        .map(|slot| slot.is_some())
+        .unwrap_or(false)
+        || index_path(app).is_ok_and(|path| path.exists());
+    if !has_index {
+        return Ok(None);
+    }
+
+    let handle = app.clone();
+    let stats = tokio::task::spawn_blocking(move || refresh_blocking(&handle, false))
+        .await
+        .map_err(|e| format!("Search index maintenance task failed: {e}"))??;
+    if stats.indexed > 0 || stats.removed > 0 {
+        info!(
+            "Search index updated: {} indexed, {} removed, {} total",
+            stats.indexed, stats.removed, stats.total_documents
+        );
+    }
+    Ok(Some(stats))
+}
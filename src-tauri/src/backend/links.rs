@@ -0,0 +1,368 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::Serialize;
+use walkdir::WalkDir;
+
+use crate::backend::frontmatter::split_frontmatter;
+
+/// Notes larger than this are not scanned for links
+const MAX_LINKED_FILE_BYTES: u64 = 1024 * 1024;
+
+/// A `[[wikilink]]` occurrence in a note
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct WikiLink {
+    /// Link target with any `#heading` and `|alias` removed
+    pub target: String,
+    /// 1-based line number
+    pub line_number: usize,
+}
+
+/// A link from the queried note to another note
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct OutgoingLink {
+    pub target: String,
+    /// Resolved path relative to the notes directory; `None` if no note matches
+    pub path: Option<String>,
+    pub line_number: usize,
+}
+
+/// A note that links to the queried note
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct Backlink {
+    /// Path of the linking note, relative to the notes directory
+    pub path: String,
+    pub line_number: usize,
+}
+
+/// Whether `path` is a markdown note that can contain wikilinks
+pub(crate) fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("markdown"))
+}
+
+/// Extract `[[target]]`, `[[target|alias]]` and `[[target#heading]]` links,
+/// ignoring fenced code blocks. Embeds (`![[...]]`) count as links.
+pub(crate) fn extract_wikilinks(text: &str) -> Vec<WikiLink> {
+    let mut links = Vec::new();
+    let mut in_fence = false;
+
+    for (idx, line) in text.lines().enumerate() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        let mut rest = line;
+        while let Some(start) = rest.find("[[") {
+            let after = &rest[start + 2..];
+            let Some(end) = after.find("]]") else {
+                break;
+            };
+            let inner = &after[..end];
+            rest = &after[end + 2..];
+            if inner.contains('[') || inner.contains(']') {
+                continue;
+            }
+
+            let target = inner.split('|').next().unwrap_or_default();
+            let target = target.split('#').next().unwrap_or_default().trim();
+            if !target.is_empty() {
+                links.push(WikiLink {
+                    target: target.to_string(),
+                    line_number: idx + 1,
+                });
+            }
+        }
+    }
+
+    links
+}
+
+fn normalize_rel_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// Frontmatter title and aliases, which links may use instead of the file name
+fn frontmatter_names(text: &str) -> Vec<String> {
+    let (frontmatter, _) = split_frontmatter(text);
+    [
+        frontmatter.list("title"),
+        frontmatter.list("aliases"),
+        frontmatter.list("alias"),
+    ]
+    .concat()
+}
+
+struct LinkedNote {
+    modified_ms: u64,
+    links: Vec<WikiLink>,
+    /// Frontmatter title and aliases
+    names: Vec<String>,
+}
+
+/// Maps link targets to note paths the way note apps resolve them: an exact
+/// relative path (with or without `.md`), or otherwise a bare note name,
+/// where the shortest matching path wins. Frontmatter titles and aliases are
+/// tried last.
+struct Resolver {
+    by_path: HashMap<String, String>,
+    by_name: HashMap<String, String>,
+    by_alias: HashMap<String, String>,
+}
+
+impl Resolver {
+    fn new<'a>(notes: impl Iterator<Item = (&'a String, &'a LinkedNote)>) -> Self {
+        let mut by_path = HashMap::new();
+        let mut by_name: HashMap<String, String> = HashMap::new();
+        let mut by_alias: HashMap<String, String> = HashMap::new();
+
+        for (path, note) in notes {
+            for name in &note.names {
+                let name = name.trim().to_lowercase();
+                let keep_existing = by_alias
+                    .get(&name)
+                    .is_some_and(|existing| existing.len() <= path.len());
+                if !name.is_empty() && !keep_existing {
+                    by_alias.insert(name, path.clone());
+                }
+            }
+
+            let lower = path.to_lowercase();
+            let without_ext = Path::new(&lower).with_extension("");
+            by_path.insert(lower.clone(), path.clone());
+            by_path.insert(normalize_rel_path(&without_ext), path.clone());
+
+            if let Some(stem) = without_ext
+                .file_name()
+                .map(|s| s.to_string_lossy().to_string())
+            {
+                let keep_existing = by_name
+                    .get(&stem)
+                    .is_some_and(|existing| existing.len() <= path.len());
+                if !keep_existing {
+                    by_name.insert(stem, path.clone());
+                }
+            }
+        }
+
+        Self {
+            by_path,
+            by_name,
+            by_alias,
+        }
+    }
+
+    fn resolve(&self, target: &str) -> Option<String> {
+        let key = target.trim_start_matches('/').to_lowercase();
+        if let Some(path) = self.by_path.get(&key) {
+            return Some(path.clone());
+        }
+        if key.contains('/') {
+            return None;
+        }
+        let name = Path::new(&key)
+            .with_extension("")
+            .to_string_lossy()
+            .to_string();
+        self.by_name
+            .get(&key)
+            .or_else(|| self.by_name.get(&name))
+            .or_else(|| self.by_alias.get(&key))
+            .cloned()
+    }
+}
+
+/// Link graph of the markdown notes under one notes directory, rescanning
+/// only notes whose modification time changed
+#[derive(Default)]
+pub(crate) struct LinkGraph {
+    root: Option<PathBuf>,
+    notes: HashMap<String, LinkedNote>,
+}
+
+impl LinkGraph {
+    /// Rescan new and modified notes and drop deleted ones. Symlinks are not
+    /// followed, so only notes inside `notes_directory` are read.
+    pub(crate) fn refresh(&mut self, notes_directory: &Path) {
+        if self.root.as_deref() != Some(notes_directory) {
+            self.root = Some(notes_directory.to_path_buf());
+            self.notes.clear();
+        }
+        let mut seen = HashSet::new();
+
+        for entry in WalkDir::new(notes_directory)
+            .follow_links(false)
+            .max_depth(20)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+        {
+            if !entry.file_type().is_file() || !is_markdown(entry.path()) {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let rel_path = match entry.path().strip_prefix(notes_directory) {
+                Ok(path) => normalize_rel_path(path),
+                Err(_) => continue,
+            };
+            let modified_ms = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0);
+
+            seen.insert(rel_path.clone());
+            if self
+                .notes
+                .get(&rel_path)
+                .is_some_and(|note| note.modified_ms == modified_ms)
+            {
+                continue;
+            }
+
+            let text = if metadata.len() <= MAX_LINKED_FILE_BYTES {
+                std::fs::read_to_string(entry.path()).unwrap_or_default()
+            } else {
+                String::new()
+            };
+            self.notes.insert(
+                rel_path,
+                LinkedNote {
+                    modified_ms,
+                    links: extract_wikilinks(&text),
+                    names: frontmatter_names(&text),
+                },
+            );
+        }
+
+        self.notes.retain(|path, _| seen.contains(path));
+    }
+
+    fn resolver(&self) -> Resolver {
+        Resolver::new(self.notes.iter())
+    }
+
+    /// The note a `[[target]]` link points to, relative to the notes
+    /// directory
+    pub(crate) fn resolve(&self, target: &str) -> Option<String> {
+        self.resolver().resolve(target)
+    }
+
+    /// Links in `rel_path`, in document order
+    pub(crate) fn outgoing(&self, rel_path: &str) -> Vec<OutgoingLink> {
+        let resolver = self.resolver();
+        self.notes
+            .get(rel_path)
+            .map(|note| {
+                note.links
+                    .iter()
+                    .map(|link| OutgoingLink {
+                        target: link.target.clone(),
+                        path: resolver.resolve(&link.target),
+                        line_number: link.line_number,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Notes linking to `rel_path`, sorted by path and line
+    pub(crate) fn backlinks(&self, rel_path: &str) -> Vec<Backlink> {
+        let resolver = self.resolver();
+        let mut backlinks: Vec<Backlink> = self
+            .notes
+            .iter()
+            .filter(|(source, _)| source.as_str() != rel_path)
+            .flat_map(|(source, note)| {
+                note.links
+                    .iter()
+                    .filter(|link| resolver.resolve(&link.target).as_deref() == Some(rel_path))
+                    .map(|link| Backlink {
+                        path: source.clone(),
+                        line_number: link.line_number,
+                    })
+            })
+            .collect();
+        backlinks.sort_by(|a, b| {
+            a.path
+                .cmp(&b.path)
+                .then_with(|| a.line_number.cmp(&b.line_number))
+        });
+        backlinks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_util::TempDir;
+
+    impl LinkGraph {
+        fn set_note_links(&mut self, rel_path: &str, links: Vec<WikiLink>) {
+            self.notes.insert(
+                rel_path.to_string(),
+                LinkedNote {
+                    modified_ms: 0,
+                    links,
+                    names: Vec::new(),
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn test_extract_wikilinks_strips_alias_and_heading() {
+        let text = "See [[Alpha]] and [[dir/Beta#Intro|the beta]].\n```\n[[not a link]]\n```\n![[Gamma]] [[#local]]";
+        let links = extract_wikilinks(text);
+        let targets: Vec<_> = links.iter().map(|l| l.target.as_str()).collect();
+        assert_eq!(targets, vec!["Alpha", "dir/Beta", "Gamma"]);
+        assert_eq!(links[2].line_number, 5);
+    }
+
+    #[test]
+    fn test_backlinks_and_outgoing_resolve_names_and_paths() {
+        let mut graph = LinkGraph::default();
+        graph.set_note_links("alpha.md", extract_wikilinks("[[Beta]] [[missing]]"));
+        graph.set_note_links("dir/beta.md", Vec::new());
+        graph.set_note_links("archive/dir/beta.md", Vec::new());
+        graph.set_note_links("gamma.md", extract_wikilinks("\n[[archive/dir/beta]]"));
+
+        let outgoing = graph.outgoing("alpha.md");
+        assert_eq!(outgoing[0].path.as_deref(), Some("dir/beta.md"));
+        assert_eq!(outgoing[1].path, None);
+
+        assert_eq!(
+            graph.backlinks("dir/beta.md"),
+            vec![Backlink {
+                path: "alpha.md".to_string(),
+                line_number: 1
+            }]
+        );
+        assert_eq!(graph.backlinks("archive/dir/beta.md")[0].path, "gamma.md");
+    }
+
+    #[test]
+    fn test_refresh_scans_markdown_and_drops_deleted_notes() {
+        let dir = TempDir::new("links");
+        std::fs::write(dir.join("a.md"), "[[b]]").unwrap();
+        std::fs::write(dir.join("b.md"), "---\naliases: [Bee]\n---\n").unwrap();
+        std::fs::write(dir.join("c.txt"), "[[b]]").unwrap();
+
+        let mut graph = LinkGraph::default();
+        graph.refresh(&dir);
+        assert_eq!(graph.backlinks("b.md").len(), 1);
+        assert_eq!(graph.resolve("bee").as_deref(), Some("b.md"));
+
+        std::fs::remove_file(dir.join("a.md")).unwrap();
+        graph.refresh(&dir);
+        assert!(graph.backlinks("b.md").is_empty());
+    }
+}
@@ -0,0 +1,242 @@
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use tracing::Level;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter, Layer};
+
+/// Subdirectory of the app data dir holding the log files
+const LOG_DIR: &str = "logs";
+const LOG_FILE_PREFIX: &str = "thoughttree";
+const LOG_FILE_SUFFIX: &str = "log";
+
+/// Daily files kept before the oldest is deleted
+const MAX_LOG_FILES: usize = 7;
+
+/// Most lines `recent_logs` returns
+pub(crate) const MAX_RECENT_LOG_LINES: usize = 5000;
+
+/// Keeps the background log writer alive until the process exits
+static LOG_GUARD: OnceLock<WorkerGuard> = OnceLock::new();
+
+/// One log event; continuation lines of a multi-line message are included
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct LogLine {
+    /// `ERROR`, `WARN`, `INFO`, `DEBUG` or `TRACE`
+    pub level: String,
+    pub text: String,
+}
+
+/// Directory of the rotating log files
+pub(crate) fn log_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {e}"))?;
+    Ok(dir.join(LOG_DIR))
+}
+
+fn filter() -> EnvFilter {
+    EnvFilter::from_default_env().add_directive("info".parse().unwrap())
+}
+
+/// Log to stderr and, when `dir` is given and writable, to a daily rotated
+/// file there. Falls back to stderr only.
+pub(crate) fn init(dir: Option<&Path>) {
+    let file_layer = dir.and_then(|dir| {
+        let appender = RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix(LOG_FILE_PREFIX)
+            .filename_suffix(LOG_FILE_SUFFIX)
+            .max_log_files(MAX_LOG_FILES)
+            .build(dir)
+            .inspect_err(|e| eprintln!("File logging disabled: {e}"))
+            .ok()?;
+        let (writer, guard) = tracing_appender::non_blocking(appender);
+        let _ = LOG_GUARD.set(guard);
+        Some(
+            fmt::layer()
+                .with_ansi(false)
+                .with_writer(writer)
+                .with_filter(filter()),
+        )
+    });
+
+    tracing_subscriber::registry()
+        .with(fmt::layer().with_filter(filter()))
+        .with(file_layer)
+        .init();
+}
+
+/// Level of a line written by the fmt layer (`<timestamp>  INFO target: ...`);
+/// `None` for continuation lines
+fn line_level(line: &str) -> Option<Level> {
+    let mut words = line.split_whitespace();
+    words.next()?;
+    Level::from_str(words.next()?).ok()
+}
+
+/// Log files in `dir`, newest first. Daily files are named
+/// `thoughttree.YYYY-MM-DD.log`, so name order is date order.
+fn log_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(LOG_FILE_PREFIX) && n.ends_with(LOG_FILE_SUFFIX))
+        })
+        .collect();
+    files.sort();
+    files.reverse();
+    files
+}
+
+/// Whether `line` has the span field `field` (`name=value`) as a whole,
+/// not as a prefix of a longer value
+fn has_span_field(line: &str, field: &str) -> bool {
+    line.match_indices(field).any(|(start, _)| {
+        matches!(
+            line[start + field.len()..].chars().next(),
+            Some(' ' | '}' | ':')
+        )
+    })
+}
+
+/// Events of one file at `min_level` or more severe, oldest first. With a
+/// `trace_id`, only events logged inside that generation's span.
+fn parse_log(contents: &str, min_level: Level, trace_id: Option<&str>) -> Vec<LogLine> {
+    let trace_field = trace_id.map(|id| format!("trace_id={id}"));
+    let mut lines: Vec<LogLine> = Vec::new();
+    let mut keep_continuation = false;
+    for line in contents.lines() {
+        match line_level(line) {
+            Some(level) => {
+                // Level's order runs from TRACE (greatest) to ERROR (least)
+                keep_continuation = level <= min_level
+                    && trace_field
+                        .as_deref()
+                        .is_none_or(|field| has_span_field(line, field));
+                if keep_continuation {
+                    lines.push(LogLine {
+                        level: level.to_string(),
+                        text: line.to_string(),
+                    });
+                }
+            }
+            None if keep_continuation => {
+                if let Some(last) = lines.last_mut() {
+                    last.text.push('\n');
+                    last.text.push_str(line);
+                }
+            }
+            None => {}
+        }
+    }
+    lines
+}
+
+/// The last `count` events at `min_level` or more severe, oldest first,
+/// reading back through older files as needed. `trace_id` narrows them to
+/// one generation.
+pub(crate) fn recent_logs(
+    dir: &Path,
+    count: usize,
+    min_level: Level,
+    trace_id: Option<&str>,
+) -> Vec<LogLine> {
+    let count = count.min(MAX_RECENT_LOG_LINES);
+    let mut recent: VecDeque<LogLine> = VecDeque::with_capacity(count);
+    for file in log_files(dir) {
+        if recent.len() >= count {
+            break;
+        }
+        let Ok(contents) = std::fs::read_to_string(&file) else {
+            continue;
+        };
+        for line in parse_log(&contents, min_level, trace_id).into_iter().rev() {
+            if recent.len() >= count {
+                break;
+            }
+            recent.push_front(line);
+        }
+    }
+    recent.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_util::TempDir;
+
+    #[test]
+    fn test_recent_logs_filters_levels_across_files() {
+        let dir = TempDir::new("logs");
+        std::fs::write(
+            dir.join("thoughttree.2025-03-06.log"),
+            "2025-03-06T10:00:00Z ERROR app: old failure\n\
+             2025-03-06T10:00:01Z  INFO app: old info\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("thoughttree.2025-03-07.log"),
+            "2025-03-07T09:00:00Z  WARN app: retrying\n\
+             2025-03-07T09:00:01Z DEBUG app: detail\n\
+             2025-03-07T09:00:02Z ERROR app: failed:\n  caused by: exit 1\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("notes.txt"), "2025-03-07T09:00:00Z ERROR x\n").unwrap();
+
+        let warnings = recent_logs(&dir, 10, Level::WARN, None);
+        let texts: Vec<&str> = warnings.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            [
+                "2025-03-06T10:00:00Z ERROR app: old failure",
+                "2025-03-07T09:00:00Z  WARN app: retrying",
+                "2025-03-07T09:00:02Z ERROR app: failed:\n  caused by: exit 1",
+            ]
+        );
+        assert_eq!(warnings[1].level, "WARN");
+
+        let last_two = recent_logs(&dir, 2, Level::TRACE, None);
+        assert_eq!(last_two.len(), 2);
+        assert_eq!(last_two[0].level, "DEBUG");
+        assert!(recent_logs(&dir.join("missing"), 10, Level::INFO, None).is_empty());
+    }
+
+    #[test]
+    fn test_recent_logs_filters_by_trace_id() {
+        let dir = TempDir::new("logs");
+        std::fs::write(
+            dir.join("thoughttree.2025-03-08.log"),
+            "2025-03-08T08:00:00Z  INFO generation{trace_id=abc node_id=n1}: app: start\n\
+             2025-03-08T08:00:01Z  WARN generation{trace_id=def node_id=n2}: app: other\n\
+             2025-03-08T08:00:02Z  WARN generation{trace_id=abc node_id=n1}: app: stderr\n  boom\n\
+             2025-03-08T08:00:03Z  INFO app: idle\n",
+        )
+        .unwrap();
+
+        let traced = recent_logs(&dir, 10, Level::INFO, Some("abc"));
+        let texts: Vec<&str> = traced.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            [
+                "2025-03-08T08:00:00Z  INFO generation{trace_id=abc node_id=n1}: app: start",
+                "2025-03-08T08:00:02Z  WARN generation{trace_id=abc node_id=n1}: app: stderr\n  boom",
+            ]
+        );
+        assert!(recent_logs(&dir, 10, Level::INFO, Some("ab")).is_empty());
+    }
+}
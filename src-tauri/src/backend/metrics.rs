@@ -0,0 +1,294 @@
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use serde::Serialize;
+
+use crate::backend::types::{AgentProvider, PromptTimings};
+
+/// Number of recent prompts kept for aggregate latency reports
+pub(crate) const MAX_LATENCY_SAMPLES: usize = 200;
+
+/// How an answer streamed
+#[derive(Clone, Debug, Serialize, Default, PartialEq)]
+pub(crate) struct Throughput {
+    /// Answer chunks the agent sent, before batching for the UI
+    pub chunk_count: usize,
+    pub response_chars: usize,
+    /// From the first chunk to the final response; `None` when the answer
+    /// arrived in one go
+    pub chars_per_sec: Option<f64>,
+}
+
+impl Throughput {
+    pub(crate) fn new(chunk_count: usize, response_chars: usize, timings: &PromptTimings) -> Self {
+        let streaming_ms = timings
+            .first_chunk_ms
+            .map(|first| timings.completion_ms.saturating_sub(first))
+            .filter(|ms| *ms > 0);
+        Self {
+            chunk_count,
+            response_chars,
+            chars_per_sec: streaming_ms.map(|ms| response_chars as f64 * 1000.0 / ms as f64),
+        }
+    }
+}
+
+/// One finished prompt, kept for the latency and performance reports
+#[derive(Clone, Debug)]
+pub(crate) struct PromptSample {
+    pub provider: AgentProvider,
+    /// `None` when the agent's default model was used
+    pub model_id: Option<String>,
+    pub timings: PromptTimings,
+    pub throughput: Throughput,
+}
+
+/// Typical speed of one provider/model over recent prompts
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub(crate) struct ModelPerformance {
+    pub provider: AgentProvider,
+    pub model_id: Option<String>,
+    pub samples: usize,
+    /// Median time to first chunk; `None` if no prompt streamed anything
+    pub first_chunk_p50_ms: Option<u64>,
+    pub total_p50_ms: u64,
+    pub total_p90_ms: u64,
+    /// Median streaming speed over prompts that streamed
+    pub chars_per_sec_p50: Option<f64>,
+    pub mean_chunk_count: f64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct PerformanceStats {
+    pub sample_count: usize,
+    pub models: Vec<ModelPerformance>,
+}
+
+/// Milliseconds elapsed since `since`
+pub(crate) fn elapsed_ms(since: Instant) -> u64 {
+    since.elapsed().as_millis() as u64
+}
+
+/// Percentiles of one `prompt_session` phase across recent prompts
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub(crate) struct PhasePercentiles {
+    pub phase: String,
+    pub samples: usize,
+    pub p50_ms: u64,
+    pub p90_ms: u64,
+    pub p99_ms: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct LatencyReport {
+    pub sample_count: usize,
+    pub phases: Vec<PhasePercentiles>,
+}
+
+/// Nearest-rank percentile of an ascending-sorted slice
+fn percentile(sorted: &[u64], pct: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn phase_percentiles(phase: &str, mut values: Vec<u64>) -> PhasePercentiles {
+    values.sort_unstable();
+    PhasePercentiles {
+        phase: phase.to_string(),
+        samples: values.len(),
+        p50_ms: percentile(&values, 50.0),
+        p90_ms: percentile(&values, 90.0),
+        p99_ms: percentile(&values, 99.0),
+    }
+}
+
+/// Aggregate per-phase percentiles over recorded prompt timings
+pub(crate) fn latency_report(samples: &VecDeque<PromptSample>) -> LatencyReport {
+    let collect = |f: fn(&PromptTimings) -> Option<u64>| {
+        samples.iter().filter_map(|s| f(&s.timings)).collect()
+    };
+
+    LatencyReport {
+        sample_count: samples.len(),
+        phases: vec![
+            phase_percentiles("spawn", collect(|t| Some(t.spawn_ms))),
+            phase_percentiles("initialize", collect(|t| Some(t.initialize_ms))),
+            phase_percentiles("new_session", collect(|t| Some(t.new_session_ms))),
+            phase_percentiles("model_switch", collect(|t| t.model_switch_ms)),
+            phase_percentiles("first_chunk", collect(|t| t.first_chunk_ms)),
+            phase_percentiles("completion", collect(|t| Some(t.completion_ms))),
+            phase_percentiles("total", collect(|t| Some(t.total_ms))),
+        ],
+    }
+}
+
+fn model_performance(group: &[&PromptSample]) -> ModelPerformance {
+    let mut first_chunks: Vec<u64> = group
+        .iter()
+        .filter_map(|s| s.timings.first_chunk_ms)
+        .collect();
+    first_chunks.sort_unstable();
+    let mut totals: Vec<u64> = group.iter().map(|s| s.timings.total_ms).collect();
+    totals.sort_unstable();
+    let mut speeds: Vec<f64> = group
+        .iter()
+        .filter_map(|s| s.throughput.chars_per_sec)
+        .collect();
+    speeds.sort_unstable_by(f64::total_cmp);
+    let chunks: usize = group.iter().map(|s| s.throughput.chunk_count).sum();
+
+    ModelPerformance {
+        provider: group[0].provider.clone(),
+        model_id: group[0].model_id.clone(),
+        samples: group.len(),
+        first_chunk_p50_ms: (!first_chunks.is_empty()).then(|| percentile(&first_chunks, 50.0)),
+        total_p50_ms: percentile(&totals, 50.0),
+        total_p90_ms: percentile(&totals, 90.0),
+        // Lower median, matching `percentile`'s nearest rank
+        chars_per_sec_p50: (!speeds.is_empty()).then(|| speeds[(speeds.len() - 1) / 2]),
+        mean_chunk_count: chunks as f64 / group.len() as f64,
+    }
+}
+
+/// Speed per provider and model over recorded prompts, in the order each
+/// model was first used
+pub(crate) fn performance_stats(samples: &VecDeque<PromptSample>) -> PerformanceStats {
+    let mut groups: Vec<Vec<&PromptSample>> = Vec::new();
+    for sample in samples {
+        match groups
+            .iter_mut()
+            .find(|g| g[0].provider == sample.provider && g[0].model_id == sample.model_id)
+        {
+            Some(group) => group.push(sample),
+            None => groups.push(vec![sample]),
+        }
+    }
+    PerformanceStats {
+        sample_count: samples.len(),
+        models: groups.iter().map(|g| model_performance(g)).collect(),
+    }
+}
+
+/// Record a sample, dropping the oldest once the buffer is full
+pub(crate) fn push_latency_sample(samples: &mut VecDeque<PromptSample>, sample: PromptSample) {
+    if samples.len() >= MAX_LATENCY_SAMPLES {
+        samples.pop_front();
+    }
+    samples.push_back(sample);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(timings: PromptTimings) -> PromptSample {
+        PromptSample {
+            provider: AgentProvider::ClaudeCode,
+            model_id: None,
+            throughput: Throughput::new(0, 0, &timings),
+            timings,
+        }
+    }
+
+    #[test]
+    fn test_percentile_nearest_rank() {
+        let values: Vec<u64> = (1..=10).collect();
+        assert_eq!(percentile(&values, 50.0), 5);
+        assert_eq!(percentile(&values, 90.0), 9);
+        assert_eq!(percentile(&values, 99.0), 10);
+        assert_eq!(percentile(&[], 50.0), 0);
+    }
+
+    #[test]
+    fn test_latency_report_skips_missing_optional_phases() {
+        let mut samples = VecDeque::new();
+        push_latency_sample(
+            &mut samples,
+            sample(PromptTimings {
+                spawn_ms: 100,
+                first_chunk_ms: Some(800),
+                total_ms: 2000,
+                ..Default::default()
+            }),
+        );
+        push_latency_sample(
+            &mut samples,
+            sample(PromptTimings {
+                spawn_ms: 300,
+                total_ms: 4000,
+                ..Default::default()
+            }),
+        );
+
+        let report = latency_report(&samples);
+        assert_eq!(report.sample_count, 2);
+        let first_chunk = report
+            .phases
+            .iter()
+            .find(|p| p.phase == "first_chunk")
+            .unwrap();
+        assert_eq!(first_chunk.samples, 1);
+        assert_eq!(first_chunk.p50_ms, 800);
+        let spawn = report.phases.iter().find(|p| p.phase == "spawn").unwrap();
+        assert_eq!(spawn.p90_ms, 300);
+    }
+
+    #[test]
+    fn test_push_latency_sample_is_bounded() {
+        let mut samples = VecDeque::new();
+        for _ in 0..MAX_LATENCY_SAMPLES + 5 {
+            push_latency_sample(&mut samples, sample(PromptTimings::default()));
+        }
+        assert_eq!(samples.len(), MAX_LATENCY_SAMPLES);
+    }
+
+    #[test]
+    fn test_performance_stats_group_by_provider_and_model() {
+        let timings = |first_chunk_ms, completion_ms| PromptTimings {
+            first_chunk_ms,
+            completion_ms,
+            total_ms: completion_ms + 1000,
+            ..Default::default()
+        };
+        let streamed = timings(Some(500), 2500);
+        let throughput = Throughput::new(40, 1000, &streamed);
+        assert_eq!(throughput.chars_per_sec, Some(500.0));
+        assert_eq!(
+            Throughput::new(1, 10, &timings(Some(700), 700)).chars_per_sec,
+            None
+        );
+
+        let mut samples = VecDeque::new();
+        push_latency_sample(
+            &mut samples,
+            PromptSample {
+                throughput,
+                ..sample(streamed)
+            },
+        );
+        push_latency_sample(
+            &mut samples,
+            PromptSample {
+                provider: AgentProvider::GeminiCli,
+                model_id: Some("gemini-2.5-flash".to_string()),
+                ..sample(timings(None, 100))
+            },
+        );
+        push_latency_sample(&mut samples, sample(timings(Some(300), 1300)));
+
+        let stats = performance_stats(&samples);
+        assert_eq!(stats.sample_count, 3);
+        assert_eq!(stats.models.len(), 2);
+        let claude = &stats.models[0];
+        assert_eq!(claude.samples, 2);
+        assert_eq!(claude.first_chunk_p50_ms, Some(300));
+        assert_eq!(claude.total_p90_ms, 3500);
+        assert_eq!(claude.mean_chunk_count, 20.0);
+        let gemini = &stats.models[1];
+        assert_eq!(gemini.first_chunk_p50_ms, None);
+        assert_eq!(gemini.chars_per_sec_p50, None);
+    }
+}
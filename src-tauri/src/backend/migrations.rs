@@ -0,0 +1,131 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::backend::project_file::{ProjectFile, GRAPH_VERSION};
+use crate::backend::types::AgentProvider;
+
+/// Newest project format this build reads and writes
+pub(crate) const CURRENT_PROJECT_VERSION: u32 = GRAPH_VERSION;
+
+/// One upgrade step. Steps run in order for every file older than `to`.
+struct Migration {
+    to: u32,
+    description: &'static str,
+    apply: fn(Value) -> Result<Value, String>,
+}
+
+const MIGRATIONS: &[Migration] = &[Migration {
+    to: 3,
+    description: "Moved ReactFlow nodes and nodeData into a single graph; filled in contentUpdatedAt and missing providers",
+    apply: migrate_to_v3,
+}];
+
+/// What `migrate` changed
+#[derive(Clone, Debug, Default, Serialize, PartialEq, Eq)]
+pub(crate) struct MigrationReport {
+    pub from_version: u32,
+    pub to_version: u32,
+    /// Descriptions of the steps applied, oldest first
+    pub applied: Vec<String>,
+    /// Copy of the original file, when one was written
+    pub backup_path: Option<String>,
+}
+
+/// Format version of a parsed project. Files from before versioning have none.
+pub(crate) fn project_version(root: &Value) -> u32 {
+    root.get("version")
+        .and_then(Value::as_u64)
+        .and_then(|v| u32::try_from(v).ok())
+        .unwrap_or(1)
+}
+
+/// Upgrade `root` to [`CURRENT_PROJECT_VERSION`]. Files from a newer build are
+/// refused rather than guessed at, so saving can't silently drop their data.
+pub(crate) fn migrate(root: Value) -> Result<(Value, MigrationReport), String> {
+    let from_version = project_version(&root);
+    if from_version > CURRENT_PROJECT_VERSION {
+        return Err(format!(
+            "Project format version {from_version} is newer than this app supports ({CURRENT_PROJECT_VERSION}). Please update ThoughtTree."
+        ));
+    }
+
+    let mut report = MigrationReport {
+        from_version,
+        to_version: from_version,
+        ..Default::default()
+    };
+    let mut root = root;
+    for migration in MIGRATIONS.iter().filter(|m| m.to > from_version) {
+        root = (migration.apply)(root)?;
+        report.to_version = migration.to;
+        report.applied.push(migration.description.to_string());
+    }
+    Ok((root, report))
+}
+
+/// [`migrate`] for project JSON text. Current files are returned as-is
+/// without reserializing.
+pub(crate) fn migrate_data(data: String) -> Result<(String, MigrationReport), String> {
+    let root: Value =
+        serde_json::from_str(&data).map_err(|e| format!("Invalid project file: {e}"))?;
+    let (migrated, report) = migrate(root)?;
+    if report.applied.is_empty() {
+        return Ok((data, report));
+    }
+    let data = serde_json::to_string_pretty(&migrated)
+        .map_err(|e| format!("Failed to serialize project: {e}"))?;
+    Ok((data, report))
+}
+
+fn migrate_to_v3(root: Value) -> Result<Value, String> {
+    let json = serde_json::to_string(&root).map_err(|e| format!("Invalid project: {e}"))?;
+    let mut project = ProjectFile::parse(&json)?;
+    let default_provider = serde_json::to_value(AgentProvider::default())
+        .ok()
+        .and_then(|v| v.as_str().map(String::from));
+
+    for node in &mut project.graph.nodes {
+        node.content_updated_at.get_or_insert(node.timestamp);
+        if node.role == "assistant" && node.provider.is_none() {
+            node.provider = default_provider.clone();
+        }
+    }
+    project.to_value()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_legacy_project_is_upgraded() {
+        let legacy = json!({
+            "version": 2,
+            "nodes": [{"id": "a", "position": {"x": 1, "y": 2}}],
+            "edges": [],
+            "nodeData": {"a": {"id": "a", "role": "assistant", "content": "Hi", "timestamp": 7}},
+            "projectModelPreferences": null
+        });
+        let (migrated, report) = migrate(legacy).unwrap();
+        assert_eq!(report.from_version, 2);
+        assert_eq!(report.to_version, CURRENT_PROJECT_VERSION);
+        assert_eq!(report.applied.len(), 1);
+
+        let node = &migrated["graph"]["nodes"][0];
+        assert_eq!(node["contentUpdatedAt"], 7);
+        assert_eq!(node["provider"], "claude-code");
+        assert_eq!(migrated["version"], 3);
+        assert!(migrated.get("nodeData").is_none());
+    }
+
+    #[test]
+    fn test_current_project_is_untouched_and_newer_is_refused() {
+        let current = json!({"version": 3, "graph": {"version": 3, "nodes": [], "edges": []}});
+        let (migrated, report) = migrate(current.clone()).unwrap();
+        assert_eq!(migrated, current);
+        assert!(report.applied.is_empty());
+
+        assert!(migrate(json!({"version": 99})).is_err());
+    }
+}
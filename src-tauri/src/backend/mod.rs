@@ -1,6 +1,73 @@
 pub(crate) mod acp;
+pub(crate) mod agent_env;
+pub(crate) mod alternatives;
+pub(crate) mod analytics;
+pub(crate) mod assets;
+pub(crate) mod attachments;
+pub(crate) mod audit;
+pub(crate) mod autosave;
+pub(crate) mod batch;
+pub(crate) mod canvas;
+pub(crate) mod claude_options;
 pub(crate) mod commands;
+pub(crate) mod compaction;
 pub(crate) mod config;
+pub(crate) mod config_file;
+pub(crate) mod context;
+pub(crate) mod conversation_import;
+pub(crate) mod diagram;
+pub(crate) mod dictation;
+pub(crate) mod diff;
+pub(crate) mod export;
+pub(crate) mod frontmatter;
+pub(crate) mod fuzzy;
+pub(crate) mod gemini_cli;
+pub(crate) mod gemini_models;
+pub(crate) mod generations;
+pub(crate) mod git;
+pub(crate) mod history;
+pub(crate) mod images;
+pub(crate) mod indexer;
+pub(crate) mod links;
+pub(crate) mod logging;
+pub(crate) mod metrics;
+pub(crate) mod migrations;
+pub(crate) mod note_edit;
+pub(crate) mod note_files;
+pub(crate) mod opml;
+pub(crate) mod pdf;
+pub(crate) mod permissions;
+pub(crate) mod preamble;
+pub(crate) mod project;
+pub(crate) mod project_file;
+pub(crate) mod project_index;
+pub(crate) mod project_lock;
+pub(crate) mod project_stats;
+pub(crate) mod project_watch;
+pub(crate) mod proxy;
+pub(crate) mod queue;
+pub(crate) mod quick_capture;
+pub(crate) mod read_roots;
+pub(crate) mod recovery;
+pub(crate) mod related;
+pub(crate) mod replay;
+pub(crate) mod retry;
 pub(crate) mod runtime;
+pub(crate) mod safe_mode;
+pub(crate) mod scheduler;
+pub(crate) mod search;
+pub(crate) mod search_index;
+pub(crate) mod secrets;
+pub(crate) mod spotlight;
 pub(crate) mod state;
+pub(crate) mod summaries;
+pub(crate) mod tags;
+pub(crate) mod templates;
+#[cfg(test)]
+pub(crate) mod test_util;
+pub(crate) mod tokens;
+pub(crate) mod transcript;
+pub(crate) mod tray;
 pub(crate) mod types;
+pub(crate) mod updates;
+pub(crate) mod workspaces;
@@ -0,0 +1,146 @@
+use std::io::Write;
+use std::path::Path;
+
+/// Level and text of a markdown ATX heading line (`## Title` -> `(2, "Title")`)
+fn parse_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+    Some((level, rest.trim().trim_end_matches('#').trim_end()))
+}
+
+/// Join `before` and `after` with `content` in between, separated by blank lines
+fn splice(before: &str, content: &str, after: &str) -> String {
+    let before = before.trim_end_matches(['\n', '\r']);
+    let content = content.trim_end();
+
+    let mut out = String::with_capacity(before.len() + content.len() + after.len() + 4);
+    if !before.is_empty() {
+        out.push_str(before);
+        out.push_str("\n\n");
+    }
+    out.push_str(content);
+    out.push('\n');
+    if !after.is_empty() {
+        out.push('\n');
+        out.push_str(after);
+    }
+    out
+}
+
+/// Append `content` to a note. With `heading`, it goes at the end of that
+/// heading's section (before the next heading of the same or a higher
+/// level); a missing heading is created at the end of the note.
+pub(crate) fn append_to_text(text: &str, content: &str, heading: Option<&str>) -> String {
+    let Some(heading) = heading.map(|h| h.trim().trim_start_matches('#').trim()) else {
+        return splice(text, content, "");
+    };
+    if heading.is_empty() {
+        return splice(text, content, "");
+    }
+
+    let mut offset = 0;
+    let mut in_fence = false;
+    let mut section_level = None;
+
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim_end_matches(['\n', '\r']);
+        if trimmed.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+        } else if !in_fence {
+            if let Some((level, title)) = parse_heading(trimmed) {
+                match section_level {
+                    Some(open) if level <= open => {
+                        return splice(&text[..offset], content, &text[offset..]);
+                    }
+                    None if title.eq_ignore_ascii_case(heading) => section_level = Some(level),
+                    _ => {}
+                }
+            }
+        }
+        offset += line.len();
+    }
+
+    if section_level.is_some() {
+        splice(text, content, "")
+    } else {
+        splice(text, &format!("## {heading}\n\n{}", content.trim_end()), "")
+    }
+}
+
+/// Replace `path` with `data` atomically: write a temp file next to it, sync,
+/// then rename over the original so readers never see a partial note
+pub(crate) fn write_atomic(path: &Path, data: &[u8]) -> Result<(), String> {
+    let dir = path
+        .parent()
+        .ok_or_else(|| "Invalid path: no parent directory".to_string())?;
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| "Invalid path: no filename".to_string())?
+        .to_string_lossy();
+    let tmp = dir.join(format!(".{file_name}.{}.tmp", uuid::Uuid::new_v4()));
+
+    let result = (|| {
+        let mut file =
+            std::fs::File::create(&tmp).map_err(|e| format!("Failed to create temp file: {e}"))?;
+        file.write_all(data)
+            .and_then(|_| file.sync_all())
+            .map_err(|e| format!("Failed to write temp file: {e}"))?;
+        std::fs::rename(&tmp, path).map_err(|e| format!("Failed to replace note: {e}"))
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_util::TempDir;
+
+    #[test]
+    fn test_append_at_end() {
+        assert_eq!(
+            append_to_text("# Note\nBody\n\n", "New", None),
+            "# Note\nBody\n\nNew\n"
+        );
+        assert_eq!(append_to_text("", "New\n", None), "New\n");
+    }
+
+    #[test]
+    fn test_append_under_existing_heading() {
+        let text = "# Note\n## Insights\nOld\n### Detail\nMore\n## Other\nX\n";
+        let updated = append_to_text(text, "Fresh", Some("## insights"));
+        assert_eq!(
+            updated,
+            "# Note\n## Insights\nOld\n### Detail\nMore\n\nFresh\n\n## Other\nX\n"
+        );
+    }
+
+    #[test]
+    fn test_missing_heading_is_created_and_fences_are_ignored() {
+        let text = "Intro\n```\n## Insights\n```\n";
+        let updated = append_to_text(text, "Fresh", Some("Insights"));
+        assert_eq!(
+            updated,
+            "Intro\n```\n## Insights\n```\n\n## Insights\n\nFresh\n"
+        );
+    }
+
+    #[test]
+    fn test_write_atomic_replaces_file() {
+        let dir = TempDir::new("edit");
+        let path = dir.join("note.md");
+        std::fs::write(&path, "old").unwrap();
+
+        write_atomic(&path, b"new").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,96 @@
+//! Path checks and bounded reads shared by the note commands and the ACP
+//! clients
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Most of a note one read returns, so a huge file can't be pulled into
+/// memory (or the webview) whole
+pub(crate) const MAX_READ_NOTE_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Resolve `path`, following symlinks, and make sure it lies inside
+/// `notes_dir`. A path that doesn't exist yet is resolved through its parent.
+pub(crate) fn validate_path_in_notes_dir(path: &Path, notes_dir: &Path) -> Result<PathBuf, String> {
+    let canonical_notes = std::fs::canonicalize(notes_dir)
+        .map_err(|e| format!("Failed to resolve notes directory: {e}"))?;
+
+    let canonical_path = if path.exists() {
+        std::fs::canonicalize(path).map_err(|e| format!("Failed to resolve path: {e}"))?
+    } else {
+        let parent = path
+            .parent()
+            .ok_or_else(|| "Invalid path: no parent directory".to_string())?;
+        let filename = path
+            .file_name()
+            .ok_or_else(|| "Invalid path: no filename".to_string())?;
+        let canonical_parent = std::fs::canonicalize(parent)
+            .map_err(|e| format!("Failed to resolve parent directory: {e}"))?;
+        canonical_parent.join(filename)
+    };
+
+    if !canonical_path.starts_with(&canonical_notes) {
+        return Err("Security error: path is outside the notes directory".to_string());
+    }
+
+    Ok(canonical_path)
+}
+
+/// Read up to `max_bytes` of a file as text, replacing invalid UTF-8 (including
+/// a character cut in half at the limit). Returns the text and whether it was cut.
+pub(crate) fn read_text_prefix(path: &Path, max_bytes: u64) -> Result<(String, bool), String> {
+    let file = std::fs::File::open(path).map_err(|e| format!("Failed to open note: {e}"))?;
+    let mut data = Vec::new();
+    file.take(max_bytes + 1)
+        .read_to_end(&mut data)
+        .map_err(|e| format!("Failed to read note: {e}"))?;
+
+    let truncated = data.len() as u64 > max_bytes;
+    data.truncate(max_bytes as usize);
+    Ok((String::from_utf8_lossy(&data).into_owned(), truncated))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_util::TempDir;
+
+    #[test]
+    fn test_validate_path_in_notes_dir() {
+        let dir = TempDir::new("note-files");
+        let notes = dir.join("notes");
+        std::fs::create_dir_all(&notes).unwrap();
+        std::fs::write(notes.join("a.md"), "a").unwrap();
+        std::fs::write(dir.join("secret.md"), "s").unwrap();
+
+        let canonical = std::fs::canonicalize(&notes).unwrap();
+        assert_eq!(
+            validate_path_in_notes_dir(&notes.join("a.md"), &notes).unwrap(),
+            canonical.join("a.md")
+        );
+        // Not created yet
+        assert_eq!(
+            validate_path_in_notes_dir(&notes.join("new.md"), &notes).unwrap(),
+            canonical.join("new.md")
+        );
+        assert!(validate_path_in_notes_dir(&notes.join("../secret.md"), &notes).is_err());
+        assert!(validate_path_in_notes_dir(&dir.join("secret.md"), &notes).is_err());
+    }
+
+    #[test]
+    fn test_read_text_prefix_cuts_at_limit() {
+        let dir = TempDir::new("note-files-prefix");
+        let path = dir.join("a.md");
+        std::fs::write(&path, "héllo").unwrap();
+
+        assert_eq!(
+            read_text_prefix(&path, 100).unwrap(),
+            ("héllo".to_string(), false)
+        );
+        // The limit falls inside "é"
+        assert_eq!(
+            read_text_prefix(&path, 2).unwrap(),
+            ("h\u{fffd}".to_string(), true)
+        );
+        assert!(read_text_prefix(&dir.join("missing.md"), 10).is_err());
+    }
+}
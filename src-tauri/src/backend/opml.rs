@@ -0,0 +1,248 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use crate::backend::project_file::{node_title, ProjectFile, ProjectGraph};
+use crate::backend::templates::{self, ProjectTemplate, TemplateNode};
+
+/// Outline attributes beyond `text` that ThoughtTree writes. `_note` is the
+/// convention outliners (OmniOutliner, Workflowy, Logseq) use for body text.
+const NOTE_ATTR: &str = "_note";
+const ROLE_ATTR: &str = "_role";
+const PROVIDER_ATTR: &str = "_provider";
+const MODEL_ATTR: &str = "_model";
+
+/// Escape for a double-quoted attribute; line breaks become character
+/// references so they survive attribute value normalization
+fn escape_attr(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\n' => out.push_str("&#10;"),
+            '\r' => out.push_str("&#13;"),
+            '\t' => out.push_str("&#9;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn write_outline<'a>(
+    graph: &'a ProjectGraph,
+    id: &'a str,
+    depth: usize,
+    visited: &mut HashSet<&'a str>,
+    out: &mut String,
+) {
+    // A node reachable from several parents is written under the first only
+    if !visited.insert(id) {
+        return;
+    }
+    let Some(node) = graph.node(id) else {
+        return;
+    };
+
+    let indent = "  ".repeat(depth + 2);
+    let _ = write!(
+        out,
+        r#"{indent}<outline text="{}" {NOTE_ATTR}="{}" {ROLE_ATTR}="{}""#,
+        escape_attr(&node_title(node)),
+        escape_attr(&node.content),
+        escape_attr(&node.role),
+    );
+    for (attr, value) in [(PROVIDER_ATTR, &node.provider), (MODEL_ATTR, &node.model)] {
+        if let Some(value) = value {
+            let _ = write!(out, r#" {attr}="{}""#, escape_attr(value));
+        }
+    }
+
+    let children: Vec<&str> = graph.children(id).collect();
+    if children.is_empty() {
+        out.push_str("/>\n");
+        return;
+    }
+    out.push_str(">\n");
+    for child in children {
+        write_outline(graph, child, depth + 1, visited, out);
+    }
+    let _ = writeln!(out, "{indent}</outline>");
+}
+
+/// Render the project as an OPML 2.0 document with one outline per node
+pub(crate) fn to_opml(project: &ProjectFile, title: &str) -> String {
+    let graph = &project.graph;
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<opml version=\"2.0\">\n");
+    let _ = writeln!(
+        out,
+        "  <head>\n    <title>{}</title>\n  </head>",
+        escape_attr(title)
+    );
+    out.push_str("  <body>\n");
+    let mut visited = HashSet::new();
+    for root in graph.roots() {
+        write_outline(graph, &root.id, 0, &mut visited, &mut out);
+    }
+    out.push_str("  </body>\n</opml>\n");
+    out
+}
+
+/// Map an `<outline>` element to a node. Outlines written by ThoughtTree
+/// carry the full content in `_note`; for other outliners the note is extra
+/// body text under the outline's text.
+fn outline_node(element: &BytesStart<'_>) -> Result<TemplateNode, String> {
+    let mut text = String::new();
+    let mut note = None;
+    let mut role = None;
+    let mut provider = None;
+    let mut model = None;
+    for attr in element.attributes() {
+        let attr = attr.map_err(|e| format!("Invalid OPML attribute: {e}"))?;
+        let value = attr
+            .unescape_value()
+            .map_err(|e| format!("Invalid OPML attribute: {e}"))?
+            .into_owned();
+        match std::str::from_utf8(attr.key.as_ref()) {
+            Ok("text") => text = value,
+            Ok(NOTE_ATTR) => note = Some(value),
+            Ok(ROLE_ATTR) => role = Some(value),
+            Ok(PROVIDER_ATTR) => provider = Some(value),
+            Ok(MODEL_ATTR) => model = Some(value),
+            _ => {}
+        }
+    }
+
+    let content = match (&role, note) {
+        (Some(_), Some(note)) => note,
+        (None, Some(note)) if !note.trim().is_empty() => format!("{text}\n\n{note}"),
+        _ => text,
+    };
+    Ok(TemplateNode {
+        role: role
+            .filter(|r| r == "assistant")
+            .unwrap_or_else(|| "user".to_string()),
+        content,
+        provider,
+        model,
+        timestamp: None,
+        children: Vec::new(),
+    })
+}
+
+/// Attach a finished outline to its parent, or to the roots at top level
+fn attach(stack: &mut [TemplateNode], roots: &mut Vec<TemplateNode>, node: TemplateNode) {
+    match stack.last_mut() {
+        Some(parent) => parent.children.push(node),
+        None => roots.push(node),
+    }
+}
+
+/// Parse an OPML document into a new project, one node per outline
+pub(crate) fn from_opml(xml: &str, now_ms: i64) -> Result<ProjectFile, String> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut stack: Vec<TemplateNode> = Vec::new();
+    let mut roots: Vec<TemplateNode> = Vec::new();
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) if e.local_name().as_ref() == b"outline" => {
+                stack.push(outline_node(&e)?);
+            }
+            Ok(Event::Empty(e)) if e.local_name().as_ref() == b"outline" => {
+                let node = outline_node(&e)?;
+                attach(&mut stack, &mut roots, node);
+            }
+            Ok(Event::End(e)) if e.local_name().as_ref() == b"outline" => {
+                if let Some(node) = stack.pop() {
+                    attach(&mut stack, &mut roots, node);
+                }
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => {}
+            Err(e) => {
+                return Err(format!(
+                    "Invalid OPML at position {}: {e}",
+                    reader.error_position()
+                ))
+            }
+        }
+    }
+
+    if !stack.is_empty() {
+        return Err("Invalid OPML: unclosed outline".to_string());
+    }
+    if roots.is_empty() {
+        return Err("OPML file contains no outlines".to_string());
+    }
+    let template = ProjectTemplate {
+        name: String::new(),
+        description: String::new(),
+        nodes: roots,
+    };
+    Ok(templates::build_project(&template, now_ms))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opml_round_trip_keeps_tree_and_metadata() {
+        let project = ProjectFile::parse(
+            r#"{"version": 3, "graph": {"version": 3, "nodes": [
+                {"id": "q", "role": "user", "content": "Compare \"A\" & <B>\nin detail"},
+                {"id": "a", "role": "assistant", "content": "A wins.", "provider": "gemini-cli", "model": "pro"},
+                {"id": "b", "role": "assistant", "content": "B wins."}
+            ], "edges": [
+                {"id": "q->a", "source": "q", "target": "a"},
+                {"id": "q->b", "source": "q", "target": "b"}
+            ]}}"#,
+        )
+        .unwrap();
+
+        let opml = to_opml(&project, "Comparison");
+        assert!(opml.contains("Compare &quot;A&quot; &amp; &lt;B&gt;&#10;in detail"));
+
+        let imported = from_opml(&opml, 1_000).unwrap().graph;
+        assert_eq!(imported.nodes.len(), 3);
+        assert_eq!(imported.edges.len(), 2);
+        let root = imported.roots().next().unwrap();
+        assert_eq!(root.content, "Compare \"A\" & <B>\nin detail");
+        let answer = imported
+            .nodes
+            .iter()
+            .find(|n| n.content == "A wins.")
+            .unwrap();
+        assert_eq!(answer.role, "assistant");
+        assert_eq!(answer.provider.as_deref(), Some("gemini-cli"));
+        assert_eq!(answer.model.as_deref(), Some("pro"));
+    }
+
+    #[test]
+    fn test_from_opml_reads_foreign_outlines() {
+        let opml = r#"<?xml version="1.0"?>
+            <opml version="2.0"><head><title>Ideas</title></head><body>
+              <outline text="Project ideas" _note="From the offsite">
+                <outline text="Garden planner"/>
+              </outline>
+              <outline text="Reading list"/>
+            </body></opml>"#;
+        let graph = from_opml(opml, 1_000).unwrap().graph;
+        assert_eq!(graph.nodes.len(), 3);
+        assert_eq!(graph.roots().count(), 2);
+        assert!(graph.nodes.iter().all(|n| n.role == "user"));
+        assert_eq!(graph.nodes[0].content, "Project ideas\n\nFrom the offsite");
+
+        assert!(from_opml("<opml><body></body></opml>", 0).is_err());
+        assert!(from_opml("<opml><body><outline text=\"x\">", 0).is_err());
+    }
+}
@@ -0,0 +1,511 @@
+//! A small markdown-to-PDF renderer for archiving trees, on printpdf. Text
+//! is set in the embedded DejaVu fonts, which cover Latin, Greek, Cyrillic
+//! and most symbols; characters they lack (CJK, emoji, ...) print as `?`.
+//! Use [`unsupported_chars`] to find them before rendering.
+
+use std::sync::OnceLock;
+
+use printpdf::{IndirectFontRef, Mm, PdfDocument as PdfFile, Pt};
+use ttf_parser::Face;
+
+/// A4 in points
+const PAGE_WIDTH: f64 = 595.0;
+const PAGE_HEIGHT: f64 = 842.0;
+const MARGIN: f64 = 56.0;
+const TEXT_WIDTH: f64 = PAGE_WIDTH - 2.0 * MARGIN;
+const LIST_INDENT: f64 = 14.0;
+
+const BODY_SIZE: f64 = 10.5;
+const CODE_SIZE: f64 = 9.0;
+const META_SIZE: f64 = 8.5;
+const SECTION_SIZE: f64 = 15.0;
+const LINE_SPACING: f64 = 1.35;
+
+/// Printed in place of characters the fonts lack
+const REPLACEMENT: char = '?';
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Font {
+    Regular,
+    Bold,
+    Mono,
+}
+
+impl Font {
+    const ALL: [Font; 3] = [Font::Regular, Font::Bold, Font::Mono];
+
+    fn data(self) -> &'static [u8] {
+        match self {
+            Font::Regular => include_bytes!("../../fonts/DejaVuSans.ttf"),
+            Font::Bold => include_bytes!("../../fonts/DejaVuSans-Bold.ttf"),
+            Font::Mono => include_bytes!("../../fonts/DejaVuSansMono.ttf"),
+        }
+    }
+
+    /// The parsed font, for glyph coverage and widths
+    fn face(self) -> &'static Face<'static> {
+        static FACES: OnceLock<Vec<Face<'static>>> = OnceLock::new();
+        let faces = FACES.get_or_init(|| {
+            Font::ALL
+                .iter()
+                .map(|font| Face::parse(font.data(), 0).expect("embedded font is valid"))
+                .collect()
+        });
+        &faces[self as usize]
+    }
+
+    fn has_glyph(self, c: char) -> bool {
+        self.face().glyph_index(c).is_some()
+    }
+
+    /// `c` as it is printed: control characters become spaces and
+    /// characters without a glyph the replacement
+    fn printable(self, c: char) -> char {
+        if c.is_control() {
+            ' '
+        } else if self.has_glyph(c) {
+            c
+        } else {
+            REPLACEMENT
+        }
+    }
+
+    /// Width of `text` in points, from the font's advance widths
+    fn width(self, text: &str, size: f64) -> f64 {
+        let face = self.face();
+        let units: u32 = text
+            .chars()
+            .filter_map(|c| face.glyph_index(self.printable(c)))
+            .filter_map(|glyph| face.glyph_hor_advance(glyph))
+            .map(u32::from)
+            .sum();
+        f64::from(units) * size / f64::from(face.units_per_em())
+    }
+}
+
+/// Split `text` into lines no wider than `width`. Words too long for a
+/// line on their own are broken by character.
+fn wrap(text: &str, font: Font, size: f64, width: f64) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let candidate = if line.is_empty() {
+            word.to_string()
+        } else {
+            format!("{line} {word}")
+        };
+        if font.width(&candidate, size) <= width {
+            line = candidate;
+            continue;
+        }
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        for c in word.chars() {
+            line.push(c);
+            if font.width(&line, size) > width {
+                line.pop();
+                lines.push(std::mem::take(&mut line));
+                line.push(c);
+            }
+        }
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Drop inline markdown markers and turn `[text](url)` into `text (url)`
+fn strip_inline(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('[') {
+        let link = rest[start..].find("](").and_then(|mid| {
+            let close = rest[start + mid..].find(')')?;
+            Some((start + mid, start + mid + close))
+        });
+        let Some((mid, close)) = link else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        out.push_str(&rest[start + 1..mid]);
+        out.push_str(" (");
+        out.push_str(&rest[mid + 2..close]);
+        out.push(')');
+        rest = &rest[close + 1..];
+    }
+    out.push_str(rest);
+    out.replace("**", "").replace("__", "").replace('`', "")
+}
+
+/// One exported node
+#[derive(Clone, Debug)]
+pub(crate) struct PdfSection {
+    pub title: String,
+    /// Role, provider, model and time
+    pub metadata: String,
+    /// Markdown
+    pub body: String,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct PdfDocument {
+    pub title: String,
+    /// Lines under the title on the title page
+    pub subtitle: Vec<String>,
+    pub sections: Vec<PdfSection>,
+}
+
+/// Characters in `doc` the fonts can't show, which would print as `?`; each
+/// listed once, in order of appearance
+pub(crate) fn unsupported_chars(doc: &PdfDocument) -> Vec<char> {
+    let texts = std::iter::once(&doc.title).chain(&doc.subtitle).chain(
+        doc.sections
+            .iter()
+            .flat_map(|s| [&s.title, &s.metadata, &s.body]),
+    );
+    let mut unsupported = Vec::new();
+    for c in texts.flat_map(|text| text.chars()) {
+        if Font::Regular.printable(c) == REPLACEMENT
+            && c != REPLACEMENT
+            && !unsupported.contains(&c)
+        {
+            unsupported.push(c);
+        }
+    }
+    unsupported
+}
+
+/// Text placed at a position on a page, in points from the bottom left
+struct TextRun {
+    font: Font,
+    size: f64,
+    x: f64,
+    y: f64,
+    text: String,
+}
+
+/// Lays text out top to bottom, starting new pages as needed
+struct Layout {
+    pages: Vec<Vec<TextRun>>,
+    current: Vec<TextRun>,
+    y: f64,
+}
+
+impl Layout {
+    fn new() -> Self {
+        Self {
+            pages: Vec::new(),
+            current: Vec::new(),
+            y: PAGE_HEIGHT - MARGIN,
+        }
+    }
+
+    fn new_page(&mut self) {
+        let page = std::mem::take(&mut self.current);
+        self.pages.push(page);
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    /// Start a new page unless `height` still fits on this one
+    fn ensure(&mut self, height: f64) {
+        if self.y - height < MARGIN {
+            self.new_page();
+        }
+    }
+
+    fn text_at(&mut self, font: Font, size: f64, x: f64, y: f64, text: &str) {
+        self.current.push(TextRun {
+            font,
+            size,
+            x,
+            y,
+            text: text.chars().map(|c| font.printable(c)).collect(),
+        });
+    }
+
+    /// Wrapped text; continuation lines of a bullet align with its text
+    fn paragraph(&mut self, font: Font, size: f64, indent: f64, bullet: bool, text: &str) {
+        let line_height = size * LINE_SPACING;
+        let text_indent = if bullet { indent + LIST_INDENT } else { indent };
+        for (i, line) in wrap(text, font, size, TEXT_WIDTH - text_indent)
+            .iter()
+            .enumerate()
+        {
+            self.ensure(line_height);
+            self.y -= line_height;
+            if bullet && i == 0 {
+                self.text_at(font, size, MARGIN + indent, self.y, "•");
+            }
+            self.text_at(font, size, MARGIN + text_indent, self.y, line);
+        }
+    }
+
+    fn space(&mut self, height: f64) {
+        self.y -= height;
+    }
+
+    fn title_page(&mut self, doc: &PdfDocument) {
+        self.y = PAGE_HEIGHT * 0.62;
+        for line in wrap(&doc.title, Font::Bold, 26.0, TEXT_WIDTH) {
+            self.y -= 26.0 * LINE_SPACING;
+            self.text_at(Font::Bold, 26.0, MARGIN, self.y, &line);
+        }
+        self.space(12.0);
+        for line in &doc.subtitle {
+            self.paragraph(Font::Regular, 12.0, 0.0, false, line);
+        }
+        self.new_page();
+    }
+
+    /// Render a markdown body: headings, lists, code blocks and paragraphs
+    fn markdown(&mut self, body: &str) {
+        let mut paragraph = String::new();
+        let mut in_code = false;
+
+        let flush = |layout: &mut Layout, paragraph: &mut String| {
+            if !paragraph.is_empty() {
+                layout.paragraph(
+                    Font::Regular,
+                    BODY_SIZE,
+                    0.0,
+                    false,
+                    &strip_inline(paragraph),
+                );
+                layout.space(BODY_SIZE * 0.5);
+                paragraph.clear();
+            }
+        };
+
+        for line in body.lines() {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") {
+                flush(self, &mut paragraph);
+                in_code = !in_code;
+                if !in_code {
+                    self.space(CODE_SIZE * 0.5);
+                }
+                continue;
+            }
+            if in_code {
+                let line_height = CODE_SIZE * LINE_SPACING;
+                for part in wrap_code(line) {
+                    self.ensure(line_height);
+                    self.y -= line_height;
+                    self.text_at(Font::Mono, CODE_SIZE, MARGIN + LIST_INDENT, self.y, &part);
+                }
+                continue;
+            }
+            if trimmed.is_empty() {
+                flush(self, &mut paragraph);
+                continue;
+            }
+
+            let heading_level = trimmed.chars().take_while(|&c| c == '#').count();
+            if (1..=6).contains(&heading_level) && trimmed[heading_level..].starts_with(' ') {
+                flush(self, &mut paragraph);
+                let size = (BODY_SIZE + 5.0 - heading_level as f64).max(BODY_SIZE);
+                self.space(size * 0.4);
+                self.paragraph(
+                    Font::Bold,
+                    size,
+                    0.0,
+                    false,
+                    &strip_inline(trimmed[heading_level..].trim()),
+                );
+                self.space(size * 0.3);
+                continue;
+            }
+
+            let indent = (line.len() - trimmed.len()) as f64 / 2.0 * LIST_INDENT;
+            let list_item = ["- ", "* ", "+ "]
+                .iter()
+                .find_map(|marker| trimmed.strip_prefix(marker))
+                .or_else(|| {
+                    let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+                    (digits > 0)
+                        .then(|| trimmed[digits..].strip_prefix(". "))
+                        .flatten()
+                });
+            if let Some(item) = list_item {
+                flush(self, &mut paragraph);
+                self.paragraph(Font::Regular, BODY_SIZE, indent, true, &strip_inline(item));
+                continue;
+            }
+
+            if !paragraph.is_empty() {
+                paragraph.push(' ');
+            }
+            paragraph.push_str(trimmed);
+        }
+        flush(self, &mut paragraph);
+    }
+
+    fn section(&mut self, section: &PdfSection) {
+        self.ensure(SECTION_SIZE * LINE_SPACING * 3.0);
+        self.space(SECTION_SIZE * 0.6);
+        self.paragraph(Font::Bold, SECTION_SIZE, 0.0, false, &section.title);
+        if !section.metadata.is_empty() {
+            self.paragraph(Font::Regular, META_SIZE, 0.0, false, &section.metadata);
+        }
+        self.space(BODY_SIZE * 0.6);
+        self.markdown(&section.body);
+    }
+
+    fn finish(mut self) -> Vec<Vec<TextRun>> {
+        if !self.current.is_empty() || self.pages.is_empty() {
+            self.new_page();
+        }
+        self.pages
+    }
+}
+
+/// Break a code line by character count; the mono font is fixed width
+fn wrap_code(line: &str) -> Vec<String> {
+    let per_line = ((TEXT_WIDTH - LIST_INDENT) / Font::Mono.width("M", CODE_SIZE)) as usize;
+    let chars: Vec<char> = line.chars().collect();
+    if chars.is_empty() {
+        return vec![String::new()];
+    }
+    chars
+        .chunks(per_line.max(1))
+        .map(|chunk| chunk.iter().collect())
+        .collect()
+}
+
+fn mm(points: f64) -> Mm {
+    Pt(points as f32).into()
+}
+
+/// Render `doc` to PDF bytes: a title page, then one section per node with
+/// page numbers in the footer
+pub(crate) fn render_pdf(doc: &PdfDocument) -> Result<Vec<u8>, String> {
+    let mut layout = Layout::new();
+    layout.title_page(doc);
+    for section in &doc.sections {
+        layout.section(section);
+    }
+    let mut pages = layout.finish();
+    let page_count = pages.len();
+    for (i, page) in pages.iter_mut().enumerate().skip(1) {
+        let label = format!("{} / {}", i + 1, page_count);
+        page.push(TextRun {
+            font: Font::Regular,
+            size: META_SIZE,
+            x: (PAGE_WIDTH - Font::Regular.width(&label, META_SIZE)) / 2.0,
+            y: MARGIN / 2.0,
+            text: label,
+        });
+    }
+
+    let (file, first_page, first_layer) =
+        PdfFile::new(&doc.title, mm(PAGE_WIDTH), mm(PAGE_HEIGHT), "Text");
+    let file = file.with_producer("ThoughtTree");
+    let fonts = Font::ALL
+        .iter()
+        .map(|font| file.add_external_font(font.data()))
+        .collect::<Result<Vec<IndirectFontRef>, _>>()
+        .map_err(|e| format!("Failed to embed PDF font: {e}"))?;
+
+    for (i, runs) in pages.iter().enumerate() {
+        let (page, layer) = if i == 0 {
+            (first_page, first_layer)
+        } else {
+            file.add_page(mm(PAGE_WIDTH), mm(PAGE_HEIGHT), "Text")
+        };
+        let layer = file.get_page(page).get_layer(layer);
+        for run in runs {
+            layer.use_text(
+                run.text.as_str(),
+                run.size as f32,
+                mm(run.x),
+                mm(run.y),
+                &fonts[run.font as usize],
+            );
+        }
+    }
+    file.save_to_bytes()
+        .map_err(|e| format!("Failed to write PDF: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_fits_width_and_breaks_long_words() {
+        let lines = wrap(&"word ".repeat(200), Font::Regular, BODY_SIZE, TEXT_WIDTH);
+        assert!(lines.len() > 1);
+        assert!(lines
+            .iter()
+            .all(|l| Font::Regular.width(l, BODY_SIZE) <= TEXT_WIDTH));
+
+        let long = "x".repeat(500);
+        let lines = wrap(&long, Font::Regular, BODY_SIZE, TEXT_WIDTH);
+        assert_eq!(lines.concat(), long);
+    }
+
+    #[test]
+    fn test_unsupported_chars_lists_glyphs_the_fonts_lack() {
+        let doc = PdfDocument {
+            title: "Plan – € ?".to_string(),
+            subtitle: Vec::new(),
+            sections: vec![PdfSection {
+                title: "Привет, Καλημέρα".to_string(),
+                metadata: String::new(),
+                body: "漢字 and ✓\tx 🙂 漢".to_string(),
+            }],
+        };
+        assert_eq!(unsupported_chars(&doc), ['漢', '字', '🙂']);
+        assert_eq!(Font::Mono.printable('\t'), ' ');
+        assert_eq!(Font::Regular.printable('漢'), REPLACEMENT);
+        assert_eq!(
+            strip_inline("**Bold** [site](https://x.y) `code`"),
+            "Bold site (https://x.y) code"
+        );
+    }
+
+    #[test]
+    fn test_render_pdf_paginates_with_valid_xref() {
+        let doc = PdfDocument {
+            title: "Decision: pick a database".to_string(),
+            subtitle: vec!["Exported 2025-03-14".to_string()],
+            sections: (0..40)
+                .map(|i| PdfSection {
+                    title: format!("Node {i}"),
+                    metadata: "Assistant · claude-code".to_string(),
+                    body: "# Heading\n\nSome *text* here.\n\n- item one\n- item two\n\n```\nfn main() {}\n```"
+                        .to_string(),
+                })
+                .collect(),
+        };
+        let pdf = render_pdf(&doc).unwrap();
+        assert!(pdf.starts_with(b"%PDF-"));
+
+        // Parses back, which checks the cross-reference table
+        let parsed = printpdf::lopdf::Document::load_mem(&pdf).unwrap();
+        let pages = parsed.get_pages().len();
+        assert!(pages > 2, "expected several pages, got {pages}");
+    }
+
+    #[test]
+    fn test_render_pdf_embeds_unicode_font() {
+        let doc = PdfDocument {
+            title: "Заметки".to_string(),
+            subtitle: Vec::new(),
+            sections: vec![PdfSection {
+                title: "Ελληνικά".to_string(),
+                metadata: String::new(),
+                body: "Текст – ✓".to_string(),
+            }],
+        };
+        let pdf = render_pdf(&doc).unwrap();
+        let parsed = printpdf::lopdf::Document::load_mem(&pdf).unwrap();
+        let embedded = parsed
+            .objects
+            .values()
+            .any(|object| object.as_dict().is_ok_and(|dict| dict.has(b"FontFile2")));
+        assert!(embedded, "expected an embedded TrueType font");
+    }
+}
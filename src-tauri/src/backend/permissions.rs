@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+
+use tokio::sync::oneshot;
+
+/// Permission requests waiting for the user's answer, keyed by
+/// `(node_id, request_id)` so a node's requests can be dropped when its
+/// generation ends
+#[derive(Default)]
+pub(crate) struct PendingPermissions {
+    senders: Mutex<HashMap<(String, String), oneshot::Sender<String>>>,
+}
+
+impl PendingPermissions {
+    fn senders(&self) -> MutexGuard<'_, HashMap<(String, String), oneshot::Sender<String>>> {
+        self.senders.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub(crate) fn insert(&self, node_id: &str, request_id: &str, sender: oneshot::Sender<String>) {
+        self.senders()
+            .insert((node_id.to_string(), request_id.to_string()), sender);
+    }
+
+    pub(crate) fn remove(&self, node_id: &str, request_id: &str) {
+        self.senders()
+            .remove(&(node_id.to_string(), request_id.to_string()));
+    }
+
+    /// Remove and return the sender of a request. Request ids are unique, so
+    /// the frontend doesn't need to know the node.
+    pub(crate) fn take(&self, request_id: &str) -> Option<oneshot::Sender<String>> {
+        let mut senders = self.senders();
+        let key = senders.keys().find(|(_, id)| id == request_id)?.clone();
+        senders.remove(&key)
+    }
+
+    /// Drop all requests of a node, which cancels them. Returns how many
+    /// there were.
+    pub(crate) fn purge_node(&self, node_id: &str) -> usize {
+        let mut senders = self.senders();
+        let before = senders.len();
+        senders.retain(|(node, _), _| node != node_id);
+        before - senders.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_purge_node_cancels_only_that_node() {
+        let pending = PendingPermissions::default();
+        let (a_tx, mut a_rx) = oneshot::channel::<String>();
+        let (b_tx, _b_rx) = oneshot::channel();
+        let (c_tx, mut c_rx) = oneshot::channel();
+        pending.insert("node-1", "req-a", a_tx);
+        pending.insert("node-1", "req-b", b_tx);
+        pending.insert("node-2", "req-c", c_tx);
+
+        assert_eq!(pending.purge_node("node-1"), 2);
+        assert_eq!(a_rx.try_recv(), Err(oneshot::error::TryRecvError::Closed));
+        assert_eq!(pending.purge_node("node-1"), 0);
+
+        let sender = pending.take("req-c").unwrap();
+        sender.send("allow".to_string()).unwrap();
+        assert_eq!(c_rx.try_recv().as_deref(), Ok("allow"));
+        assert!(pending.take("req-c").is_none());
+    }
+}
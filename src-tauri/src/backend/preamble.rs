@@ -0,0 +1,269 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Locale, TimeZone};
+use serde::{Deserialize, Serialize};
+
+/// Date format of the preamble unless the user picks another
+pub(crate) const DEFAULT_DATE_FORMAT: &str = "%B %d, %Y";
+
+/// Longest accepted preamble template
+pub(crate) const MAX_PREAMBLE_TEMPLATE_BYTES: usize = 4 * 1024;
+
+/// What is put in front of every prompt before the conversation
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub(crate) struct PreambleSettings {
+    /// Send no preamble at all when false
+    pub enabled: bool,
+    /// strftime-style format of `{date}`
+    pub date_format: String,
+    /// Locale for month and weekday names, e.g. `de_DE`; English when unset
+    pub locale: Option<String>,
+    pub user_name: Option<String>,
+    pub include_timezone: bool,
+    pub include_project_title: bool,
+    /// Replaces the default lines. `{date}`, `{time}`, `{timezone}`,
+    /// `{user_name}` and `{project_title}` are filled in.
+    pub template: Option<String>,
+}
+
+impl Default for PreambleSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            date_format: DEFAULT_DATE_FORMAT.to_string(),
+            locale: None,
+            user_name: None,
+            include_timezone: false,
+            include_project_title: false,
+            template: None,
+        }
+    }
+}
+
+/// The values a preamble can mention
+pub(crate) struct PreambleValues<'a> {
+    pub date: String,
+    pub time: String,
+    pub timezone: String,
+    pub project_title: Option<&'a str>,
+}
+
+/// An invalid specifier would make chrono panic while formatting
+fn is_valid_format(format: &str) -> bool {
+    !StrftimeItems::new(format).any(|item| matches!(item, Item::Error))
+}
+
+fn parse_locale(locale: &str) -> Result<Locale, String> {
+    Locale::try_from(locale).map_err(|_| format!("Unknown locale: {locale}"))
+}
+
+/// Reject settings that couldn't be rendered
+pub(crate) fn validate_preamble(settings: &PreambleSettings) -> Result<(), String> {
+    if !is_valid_format(&settings.date_format) {
+        return Err(format!("Invalid date format: {}", settings.date_format));
+    }
+    if let Some(locale) = &settings.locale {
+        parse_locale(locale)?;
+    }
+    if settings
+        .template
+        .as_ref()
+        .is_some_and(|t| t.len() > MAX_PREAMBLE_TEMPLATE_BYTES)
+    {
+        return Err(format!(
+            "Preamble template is too long (limit {MAX_PREAMBLE_TEMPLATE_BYTES} bytes)"
+        ));
+    }
+    Ok(())
+}
+
+impl<'a> PreambleValues<'a> {
+    /// Date and time of `now` formatted per `settings`. A format or locale
+    /// that doesn't parse (e.g. from a hand-edited config) falls back to the
+    /// defaults.
+    pub(crate) fn new<Tz: TimeZone>(
+        settings: &PreambleSettings,
+        now: &DateTime<Tz>,
+        timezone_name: &str,
+        project_title: Option<&'a str>,
+    ) -> Self
+    where
+        Tz::Offset: std::fmt::Display,
+    {
+        let format = if is_valid_format(&settings.date_format) {
+            settings.date_format.as_str()
+        } else {
+            tracing::warn!(
+                "Invalid date format {:?}; using default",
+                settings.date_format
+            );
+            DEFAULT_DATE_FORMAT
+        };
+        let locale = settings.locale.as_deref().and_then(|l| {
+            parse_locale(l)
+                .inspect_err(|e| tracing::warn!("{}", e))
+                .ok()
+        });
+        let date = match locale {
+            Some(locale) => now.format_localized(format, locale).to_string(),
+            None => now.format(format).to_string(),
+        };
+        Self {
+            date,
+            time: now.format("%H:%M").to_string(),
+            timezone: format!("{timezone_name} (UTC{})", now.format("%:z")),
+            project_title,
+        }
+    }
+
+    fn get(&self, name: &str, settings: &PreambleSettings) -> Option<String> {
+        match name {
+            "date" => Some(self.date.clone()),
+            "time" => Some(self.time.clone()),
+            "timezone" => Some(self.timezone.clone()),
+            "user_name" => Some(settings.user_name.clone().unwrap_or_default()),
+            "project_title" => Some(self.project_title.unwrap_or_default().to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// Fill `{name}` variables in one pass, so values containing braces are
+/// never expanded themselves. Unknown names are kept as written.
+fn fill_template(template: &str, values: &PreambleValues, settings: &PreambleSettings) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after
+            .find('}')
+            .and_then(|end| Some((end, values.get(&after[..end], settings)?)))
+        {
+            Some((end, value)) => {
+                out.push_str(&value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Text sent ahead of the conversation, ending in a blank line; empty when
+/// the preamble is disabled or has nothing to say
+pub(crate) fn render_preamble(settings: &PreambleSettings, values: &PreambleValues) -> String {
+    if !settings.enabled {
+        return String::new();
+    }
+    let text = match &settings.template {
+        Some(template) if !template.trim().is_empty() => {
+            fill_template(template.trim(), values, settings)
+        }
+        _ => {
+            let mut lines = vec![format!("Current date: {}", values.date)];
+            if settings.include_timezone {
+                lines.push(format!("Timezone: {}", values.timezone));
+            }
+            if let Some(name) = settings
+                .user_name
+                .as_deref()
+                .filter(|n| !n.trim().is_empty())
+            {
+                lines.push(format!("User: {}", name.trim()));
+            }
+            if settings.include_project_title {
+                if let Some(title) = values.project_title {
+                    lines.push(format!("Project: {title}"));
+                }
+            }
+            lines.join("\n")
+        }
+    };
+    if text.trim().is_empty() {
+        String::new()
+    } else {
+        format!("{text}\n\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    fn now() -> DateTime<FixedOffset> {
+        FixedOffset::east_opt(2 * 3600)
+            .unwrap()
+            .with_ymd_and_hms(2025, 3, 7, 14, 5, 0)
+            .unwrap()
+    }
+
+    fn render(settings: &PreambleSettings, title: Option<&str>) -> String {
+        let values = PreambleValues::new(settings, &now(), "Europe/Berlin", title);
+        render_preamble(settings, &values)
+    }
+
+    #[test]
+    fn test_default_preamble_matches_previous_date_prefix() {
+        let settings = PreambleSettings::default();
+        assert_eq!(
+            render(&settings, Some("Plans")),
+            "Current date: March 07, 2025\n\n"
+        );
+
+        let disabled = PreambleSettings {
+            enabled: false,
+            ..Default::default()
+        };
+        assert_eq!(render(&disabled, None), "");
+    }
+
+    #[test]
+    fn test_preamble_lines_locale_and_template() {
+        let settings = PreambleSettings {
+            date_format: "%A %e %B".to_string(),
+            locale: Some("de_DE".to_string()),
+            user_name: Some("Sam".to_string()),
+            include_timezone: true,
+            include_project_title: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            render(&settings, Some("Plans")),
+            "Current date: Freitag  7 März\nTimezone: Europe/Berlin (UTC+02:00)\n\
+             User: Sam\nProject: Plans\n\n"
+        );
+
+        let templated = PreambleSettings {
+            user_name: Some("{date}".to_string()),
+            template: Some(
+                "Hi {user_name}, it is {time} on {date} in {project_title}. {other}".to_string(),
+            ),
+            ..Default::default()
+        };
+        assert_eq!(
+            render(&templated, None),
+            "Hi {date}, it is 14:05 on March 07, 2025 in . {other}\n\n"
+        );
+    }
+
+    #[test]
+    fn test_invalid_format_is_rejected_and_falls_back() {
+        let settings = PreambleSettings {
+            date_format: "%Q".to_string(),
+            ..Default::default()
+        };
+        assert!(validate_preamble(&settings).is_err());
+        assert_eq!(render(&settings, None), "Current date: March 07, 2025\n\n");
+        let settings = PreambleSettings {
+            locale: Some("xx_YY".to_string()),
+            ..Default::default()
+        };
+        assert!(validate_preamble(&settings).is_err());
+    }
+}
@@ -0,0 +1,312 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::backend::claude_options::ClaudeOptions;
+use crate::backend::project_file::{read_project_data, ProjectFile, ProjectNode};
+use crate::backend::types::{AgentProvider, ModelPreferences, ProjectPermissions};
+
+/// Project-level decision for a tool, before the global policy is applied
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum ProjectToolDecision {
+    Deny,
+    Allow,
+    /// Not mentioned by the project; fall back to the global policy
+    Inherit,
+}
+
+/// The backend-relevant settings of a project file; the graph is ignored
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ProjectSettings {
+    /// Missing for projects that use the global policy only
+    #[serde(default)]
+    pub permissions: Option<ProjectPermissions>,
+    /// Notes directory the project was created in, used as the agent cwd
+    /// instead of the active workspace (see [`resolve_notes_override`])
+    #[serde(default)]
+    pub notes_directory: Option<String>,
+    /// Replaces the global system prompt for this project's generations
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// Replaces the global Claude Code options for this project
+    #[serde(default)]
+    pub claude_options: Option<ClaudeOptions>,
+}
+
+/// Read the permission overrides, notes directory, system prompt and Claude
+/// Code options from a project file
+pub(crate) fn read_project_settings(path: &Path) -> Result<ProjectSettings, String> {
+    let data = read_project_data(path)?;
+    serde_json::from_str(&data).map_err(|e| format!("Invalid project file: {e}"))
+}
+
+/// Accept a project's notes-directory override only if it is one of the
+/// configured workspace directories. A project file must not be able to widen
+/// what agents may read without asking, so anything else is rejected.
+pub(crate) fn resolve_notes_override(
+    notes_directory: &str,
+    workspace_dirs: &[PathBuf],
+) -> Option<PathBuf> {
+    let requested = std::fs::canonicalize(notes_directory).ok()?;
+    workspace_dirs
+        .iter()
+        .find(|dir| std::fs::canonicalize(dir).is_ok_and(|dir| dir == requested))
+        .cloned()
+}
+
+/// Notes directory agents work in for a project: the one it was created in
+/// when that is still a configured workspace, otherwise `default`
+pub(crate) fn project_notes_directory(
+    settings: &ProjectSettings,
+    default: PathBuf,
+    workspace_dirs: &[PathBuf],
+) -> PathBuf {
+    let Some(dir) = settings.notes_directory.as_deref() else {
+        return default;
+    };
+    resolve_notes_override(dir, workspace_dirs).unwrap_or_else(|| {
+        tracing::warn!(
+            "Ignoring notes directory {:?} of project: not a configured workspace",
+            dir
+        );
+        default
+    })
+}
+
+/// Look up `tool_name` in the project policy. Deny entries win over allow entries.
+pub(crate) fn project_tool_decision(
+    permissions: &ProjectPermissions,
+    tool_name: &str,
+) -> ProjectToolDecision {
+    let matches = |patterns: &[String]| {
+        patterns
+            .iter()
+            .any(|p| !p.is_empty() && tool_name.contains(p.as_str()))
+    };
+
+    if matches(&permissions.deny_tools) {
+        ProjectToolDecision::Deny
+    } else if matches(&permissions.allow_tools) {
+        ProjectToolDecision::Allow
+    } else {
+        ProjectToolDecision::Inherit
+    }
+}
+
+/// Which layer chose a node's provider and model
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum GenerationConfigSource {
+    /// The node's `pinnedProvider`/`pinnedModel`
+    Pinned,
+    /// The provider and model that generated the node
+    Node,
+    /// The project's `projectModelPreferences`
+    Project,
+    /// The app's default provider and model preferences
+    Global,
+}
+
+/// Provider and model a node is (re)generated with
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct NodeGenerationConfig {
+    pub provider: AgentProvider,
+    /// `None` keeps the agent's default model
+    pub model_id: Option<String>,
+    pub source: GenerationConfigSource,
+}
+
+fn parse_provider(provider: &str) -> Option<AgentProvider> {
+    serde_json::from_value(Value::String(provider.to_string()))
+        .inspect_err(|_| tracing::warn!("Ignoring unknown provider {:?} on node", provider))
+        .ok()
+}
+
+fn non_empty(value: Option<&str>) -> Option<String> {
+    value.filter(|v| !v.trim().is_empty()).map(String::from)
+}
+
+/// Resolve what `node` is regenerated with. A pin wins; otherwise the node
+/// keeps the provider and model that generated it, so changing preferences
+/// later doesn't change old answers. Nodes without either (user nodes, or
+/// answers from before models were recorded) follow the project's model
+/// preferences, then the global ones.
+pub(crate) fn resolve_node_generation(
+    node: &ProjectNode,
+    project_models: &ModelPreferences,
+    default_provider: &AgentProvider,
+    global_models: &ModelPreferences,
+) -> NodeGenerationConfig {
+    let preferred = |provider: &AgentProvider| {
+        non_empty(project_models.get(provider))
+            .map(|model| (model, GenerationConfigSource::Project))
+            .or_else(|| {
+                non_empty(global_models.get(provider))
+                    .map(|model| (model, GenerationConfigSource::Global))
+            })
+    };
+
+    if let Some(provider) = node.pinned_provider.as_deref().and_then(parse_provider) {
+        return NodeGenerationConfig {
+            provider,
+            model_id: non_empty(node.pinned_model.as_deref()),
+            source: GenerationConfigSource::Pinned,
+        };
+    }
+    if let Some(provider) = node.provider.as_deref().and_then(parse_provider) {
+        let model_id =
+            non_empty(node.model.as_deref()).or_else(|| preferred(&provider).map(|(m, _)| m));
+        return NodeGenerationConfig {
+            provider,
+            model_id,
+            source: GenerationConfigSource::Node,
+        };
+    }
+    let (model_id, source) = match preferred(default_provider) {
+        Some((model, source)) => (Some(model), source),
+        None => (None, GenerationConfigSource::Global),
+    };
+    NodeGenerationConfig {
+        provider: default_provider.clone(),
+        model_id,
+        source,
+    }
+}
+
+/// The project's `projectModelPreferences`, empty when unset
+pub(crate) fn project_model_preferences(project: &ProjectFile) -> ModelPreferences {
+    project
+        .settings
+        .get("projectModelPreferences")
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_util::TempDir;
+
+    fn policy(deny: &[&str], allow: &[&str]) -> ProjectPermissions {
+        ProjectPermissions {
+            deny_tools: deny.iter().map(|s| s.to_string()).collect(),
+            allow_tools: allow.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_project_tool_decision_layers() {
+        let p = policy(&["WebSearch"], &["WebFetch", "WebSearch"]);
+        assert_eq!(
+            project_tool_decision(&p, "WebSearch"),
+            ProjectToolDecision::Deny
+        );
+        assert_eq!(
+            project_tool_decision(&p, "WebFetch"),
+            ProjectToolDecision::Allow
+        );
+        assert_eq!(
+            project_tool_decision(&p, "Read"),
+            ProjectToolDecision::Inherit
+        );
+        assert_eq!(
+            project_tool_decision(&policy(&[""], &[]), "Read"),
+            ProjectToolDecision::Inherit
+        );
+    }
+
+    #[test]
+    fn test_resolve_node_generation_layers() {
+        let node: ProjectNode = serde_json::from_str(
+            r#"{"id": "b", "role": "assistant", "provider": "gemini-cli", "model": "gemini-2.5-pro"}"#,
+        )
+        .unwrap();
+        let project = ModelPreferences {
+            claude_code: Some("sonnet".to_string()),
+            gemini_cli: None,
+        };
+        let global = ModelPreferences {
+            claude_code: Some("opus".to_string()),
+            gemini_cli: Some("gemini-2.5-flash".to_string()),
+        };
+        let resolve = |node: &ProjectNode| {
+            resolve_node_generation(node, &project, &AgentProvider::ClaudeCode, &global)
+        };
+
+        let config = resolve(&node);
+        assert_eq!(config.provider, AgentProvider::GeminiCli);
+        assert_eq!(config.model_id.as_deref(), Some("gemini-2.5-pro"));
+        assert_eq!(config.source, GenerationConfigSource::Node);
+
+        let pinned = ProjectNode {
+            pinned_provider: Some("claude-code".to_string()),
+            pinned_model: Some("haiku".to_string()),
+            ..node.clone()
+        };
+        let config = resolve(&pinned);
+        assert_eq!(config.model_id.as_deref(), Some("haiku"));
+        assert_eq!(config.source, GenerationConfigSource::Pinned);
+
+        let unrecorded = ProjectNode {
+            provider: Some("someday-cli".to_string()),
+            model: None,
+            ..node
+        };
+        let config = resolve(&unrecorded);
+        assert_eq!(config.provider, AgentProvider::ClaudeCode);
+        assert_eq!(config.model_id.as_deref(), Some("sonnet"));
+        assert_eq!(config.source, GenerationConfigSource::Project);
+    }
+
+    #[test]
+    fn test_parse_project_settings() {
+        let json = r#"{"version":3,"graph":{},"permissions":{"denyTools":["WebSearch"]},"notesDirectory":"/vault","systemPrompt":"Be brief.","claudeOptions":{"maxTurns":3}}"#;
+        let parsed: ProjectSettings = serde_json::from_str(json).unwrap();
+        assert_eq!(parsed.permissions.unwrap(), policy(&["WebSearch"], &[]));
+        assert_eq!(parsed.notes_directory.as_deref(), Some("/vault"));
+        assert_eq!(parsed.system_prompt.as_deref(), Some("Be brief."));
+        assert_eq!(parsed.claude_options.unwrap().max_turns, Some(3));
+
+        let parsed: ProjectSettings = serde_json::from_str(r#"{"version":3}"#).unwrap();
+        assert!(parsed.permissions.is_none());
+        assert!(parsed.notes_directory.is_none());
+    }
+
+    #[test]
+    fn test_notes_override_must_be_a_workspace() {
+        let vault = TempDir::new("vault");
+        let other = TempDir::new("other");
+        let workspaces = vec![vault.to_path_buf()];
+
+        let dotted = vault.join(".").to_string_lossy().to_string();
+        assert_eq!(
+            resolve_notes_override(&dotted, &workspaces),
+            Some(vault.to_path_buf())
+        );
+        assert_eq!(
+            resolve_notes_override(&other.to_string_lossy(), &workspaces),
+            None
+        );
+        assert_eq!(resolve_notes_override("/does/not/exist", &workspaces), None);
+
+        let active = PathBuf::from("/active");
+        let pinned = |dir: &Path| ProjectSettings {
+            notes_directory: Some(dir.to_string_lossy().into_owned()),
+            ..Default::default()
+        };
+        assert_eq!(
+            project_notes_directory(&pinned(&vault), active.clone(), &workspaces),
+            vault.to_path_buf()
+        );
+        assert_eq!(
+            project_notes_directory(&pinned(&other), active.clone(), &workspaces),
+            active
+        );
+        assert_eq!(
+            project_notes_directory(&ProjectSettings::default(), active.clone(), &workspaces),
+            active
+        );
+    }
+}
@@ -0,0 +1,495 @@
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use walkdir::WalkDir;
+
+use crate::backend::assets;
+
+/// Graph format version written by the frontend (`GRAPH_JSON_VERSION`)
+pub(crate) const GRAPH_VERSION: u32 = 3;
+
+const MAX_TITLE_CHARS: usize = 80;
+
+/// Extension of project files
+const PROJECT_EXTENSION: &str = "thoughttree";
+
+/// An image attached to a user message
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ProjectImage {
+    /// Base64 without a `data:` prefix; empty while the image is in `asset`
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub data: String,
+    pub mime_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// File in the project's assets folder holding the image, see
+    /// [`crate::backend::assets::externalize_images`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub asset: Option<String>,
+}
+
+/// A message node as stored in the project file. Field names mirror the
+/// frontend's `MessageNodeData`; fields this side doesn't know are kept in
+/// `extra` so a round trip doesn't drop them.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ProjectNode {
+    pub id: String,
+    /// `"user"` or `"assistant"`
+    pub role: String,
+    #[serde(default)]
+    pub content: String,
+    /// Unix milliseconds
+    #[serde(default)]
+    pub timestamp: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_updated_at: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary_timestamp: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub images: Option<Vec<ProjectImage>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Provider every regeneration of this node uses, whatever the
+    /// preferences say
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pinned_provider: Option<String>,
+    /// Model pinned with `pinned_provider`; unset keeps the agent's default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pinned_model: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct ProjectEdge {
+    pub id: String,
+    pub source: String,
+    pub target: String,
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub(crate) struct Position {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct LayoutEntry {
+    pub id: String,
+    pub position: Position,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub(crate) struct ProjectGraph {
+    pub version: u32,
+    pub nodes: Vec<ProjectNode>,
+    pub edges: Vec<ProjectEdge>,
+    #[serde(default)]
+    pub layout: Vec<LayoutEntry>,
+}
+
+impl ProjectGraph {
+    pub(crate) fn node(&self, id: &str) -> Option<&ProjectNode> {
+        self.nodes.iter().find(|n| n.id == id)
+    }
+
+    /// Ids of the nodes with an edge into `id`
+    pub(crate) fn parents<'a>(&'a self, id: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.edges
+            .iter()
+            .filter(move |e| e.target == id)
+            .map(|e| e.source.as_str())
+    }
+
+    /// Ids of the nodes `id` has an edge into
+    pub(crate) fn children<'a>(&'a self, id: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.edges
+            .iter()
+            .filter(move |e| e.source == id)
+            .map(|e| e.target.as_str())
+    }
+
+    /// Nodes without parents, in file order
+    pub(crate) fn roots(&self) -> impl Iterator<Item = &ProjectNode> + '_ {
+        self.nodes
+            .iter()
+            .filter(|n| !self.edges.iter().any(|e| e.target == n.id))
+    }
+
+    pub(crate) fn position(&self, id: &str) -> Option<Position> {
+        self.layout.iter().find(|l| l.id == id).map(|l| l.position)
+    }
+}
+
+/// A whole project file: the graph plus project settings, which are kept
+/// as raw JSON (`projectModelPreferences`, `notesDirectory`, ...)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct ProjectFile {
+    pub graph: ProjectGraph,
+    pub settings: Map<String, Value>,
+}
+
+#[derive(Deserialize)]
+struct LegacyFlowNode {
+    id: String,
+    #[serde(default)]
+    position: Position,
+}
+
+impl ProjectFile {
+    /// Parse a current (version 3) project, or a version 2 file that kept
+    /// ReactFlow nodes and `nodeData` side by side
+    pub(crate) fn parse(data: &str) -> Result<Self, String> {
+        let mut root: Map<String, Value> =
+            serde_json::from_str(data).map_err(|e| format!("Invalid project file: {e}"))?;
+
+        if let Some(graph) = root.remove("graph") {
+            root.remove("version");
+            let graph =
+                serde_json::from_value(graph).map_err(|e| format!("Invalid project graph: {e}"))?;
+            return Ok(Self {
+                graph,
+                settings: root,
+            });
+        }
+
+        let take = |root: &mut Map<String, Value>, key: &str| root.remove(key).unwrap_or_default();
+        let flow_nodes: Vec<LegacyFlowNode> =
+            serde_json::from_value(take(&mut root, "nodes")).unwrap_or_default();
+        let edges: Vec<ProjectEdge> =
+            serde_json::from_value(take(&mut root, "edges")).unwrap_or_default();
+        let mut node_data: HashMap<String, ProjectNode> =
+            serde_json::from_value(take(&mut root, "nodeData"))
+                .map_err(|e| format!("Invalid project file: {e}"))?;
+        root.remove("version");
+
+        let mut graph = ProjectGraph {
+            version: GRAPH_VERSION,
+            ..Default::default()
+        };
+        for flow in flow_nodes {
+            if let Some(node) = node_data.remove(&flow.id) {
+                graph.nodes.push(node);
+                graph.layout.push(LayoutEntry {
+                    id: flow.id,
+                    position: flow.position,
+                });
+            }
+        }
+        graph.edges = edges;
+        Ok(Self {
+            graph,
+            settings: root,
+        })
+    }
+
+    /// The project as current-format JSON, as the frontend would save it
+    pub(crate) fn to_value(&self) -> Result<Value, String> {
+        let mut root = self.settings.clone();
+        root.insert("version".to_string(), Value::from(GRAPH_VERSION));
+        root.insert(
+            "graph".to_string(),
+            serde_json::to_value(&self.graph)
+                .map_err(|e| format!("Failed to serialize project: {e}"))?,
+        );
+        Ok(Value::Object(root))
+    }
+}
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Refuse to inflate a compressed project beyond this, so a crafted file
+/// can't exhaust memory
+const MAX_DECOMPRESSED_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Project JSON from the raw file bytes, which may be plain or gzipped
+/// (detected by magic bytes, so either kind opens regardless of settings)
+pub(crate) fn decode_project_bytes(raw: &[u8]) -> Result<String, String> {
+    if raw.starts_with(GZIP_MAGIC) {
+        let mut data = String::new();
+        flate2::read::GzDecoder::new(raw)
+            .take(MAX_DECOMPRESSED_BYTES + 1)
+            .read_to_string(&mut data)
+            .map_err(|e| format!("Failed to decompress project: {e}"))?;
+        if data.len() as u64 > MAX_DECOMPRESSED_BYTES {
+            return Err("Compressed project is too large to open".to_string());
+        }
+        return Ok(data);
+    }
+    if raw.starts_with(ZSTD_MAGIC) {
+        return Err("zstd-compressed projects are not supported; recompress with gzip".to_string());
+    }
+    String::from_utf8(raw.to_vec()).map_err(|e| format!("Project file is not valid UTF-8: {e}"))
+}
+
+pub(crate) fn is_compressed(raw: &[u8]) -> bool {
+    raw.starts_with(GZIP_MAGIC)
+}
+
+/// Bytes to write for project JSON `data`, gzipped when `compress` is set
+pub(crate) fn encode_project_data(data: &str, compress: bool) -> Result<Vec<u8>, String> {
+    if !compress {
+        return Ok(data.as_bytes().to_vec());
+    }
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder
+        .write_all(data.as_bytes())
+        .and_then(|_| encoder.finish())
+        .map_err(|e| format!("Failed to compress project: {e}"))
+}
+
+/// Read a project file's JSON, decompressing it if needed
+pub(crate) fn read_project_data(path: &Path) -> Result<String, String> {
+    let raw = std::fs::read(path).map_err(|e| format!("Failed to load project: {e}"))?;
+    decode_project_bytes(&raw)
+}
+
+pub(crate) fn is_project_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == PROJECT_EXTENSION)
+}
+
+/// Project files under `dirs`, skipping hidden directories. Symlinks are
+/// not followed.
+pub(crate) fn find_projects(dirs: &[PathBuf]) -> Vec<PathBuf> {
+    dirs.iter()
+        .flat_map(|dir| {
+            WalkDir::new(dir)
+                .follow_links(false)
+                .max_depth(20)
+                .into_iter()
+                .filter_entry(|entry| {
+                    entry.depth() == 0 || !entry.file_name().to_string_lossy().starts_with('.')
+                })
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_type().is_file() && is_project_file(entry.path()))
+                .map(|entry| entry.into_path())
+        })
+        .collect()
+}
+
+/// Short label for a node: its summary, or the first line of its content
+pub(crate) fn node_title(node: &ProjectNode) -> String {
+    let source = node
+        .summary
+        .as_deref()
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or(&node.content);
+    let line = source
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .unwrap_or("");
+    if line.chars().count() <= MAX_TITLE_CHARS {
+        return line.to_string();
+    }
+    let truncated: String = line.chars().take(MAX_TITLE_CHARS - 1).collect();
+    format!("{}…", truncated.trim_end())
+}
+
+/// Node skeleton without its body, for lazy loading
+#[derive(Clone, Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ManifestNode {
+    pub id: String,
+    pub role: String,
+    pub title: String,
+    pub parent_ids: Vec<String>,
+    pub timestamp: i64,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    /// Size of the content in bytes, so the UI can size placeholders
+    pub content_length: usize,
+    pub image_count: usize,
+}
+
+/// Everything needed to draw a project before any node body is loaded
+#[derive(Clone, Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ProjectManifest {
+    pub version: u32,
+    pub nodes: Vec<ManifestNode>,
+    pub edges: Vec<ProjectEdge>,
+    pub layout: Vec<LayoutEntry>,
+    pub settings: Map<String, Value>,
+}
+
+impl ProjectFile {
+    pub(crate) fn manifest(&self) -> ProjectManifest {
+        let nodes = self
+            .graph
+            .nodes
+            .iter()
+            .map(|node| ManifestNode {
+                id: node.id.clone(),
+                role: node.role.clone(),
+                title: node_title(node),
+                parent_ids: self.graph.parents(&node.id).map(String::from).collect(),
+                timestamp: node.timestamp,
+                provider: node.provider.clone(),
+                model: node.model.clone(),
+                content_length: node.content.len(),
+                image_count: node.images.as_ref().map_or(0, Vec::len),
+            })
+            .collect();
+        ProjectManifest {
+            version: self.graph.version,
+            nodes,
+            edges: self.graph.edges.clone(),
+            layout: self.graph.layout.clone(),
+            settings: self.settings.clone(),
+        }
+    }
+}
+
+struct CachedProject {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    project: Arc<ProjectFile>,
+}
+
+/// The most recently parsed project, so lazy node loads don't re-read and
+/// re-parse a large file for every batch
+#[derive(Default)]
+pub(crate) struct ProjectCache {
+    cached: Mutex<Option<CachedProject>>,
+}
+
+impl ProjectCache {
+    /// Parsed contents of `path`, reused while the file's mtime is unchanged
+    pub(crate) fn load(&self, path: &Path) -> Result<Arc<ProjectFile>, String> {
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        {
+            let cached = self.cached.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(entry) = cached.as_ref() {
+                if entry.path == path && modified.is_some() && entry.modified == modified {
+                    return Ok(entry.project.clone());
+                }
+            }
+        }
+
+        let mut project = ProjectFile::parse(&read_project_data(path)?)?;
+        assets::inline_images(path, &mut project);
+        Ok(self.store(path, modified, project))
+    }
+
+    /// Cache a project that was just read from `path`
+    pub(crate) fn store(
+        &self,
+        path: &Path,
+        modified: Option<SystemTime>,
+        project: ProjectFile,
+    ) -> Arc<ProjectFile> {
+        let project = Arc::new(project);
+        *self.cached.lock().unwrap_or_else(|e| e.into_inner()) = Some(CachedProject {
+            path: path.to_path_buf(),
+            modified,
+            project: project.clone(),
+        });
+        project
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_util::TempDir;
+
+    const V3: &str = r#"{
+        "version": 3,
+        "graph": {
+            "version": 3,
+            "nodes": [
+                {"id": "a", "role": "user", "content": "What is X?\nMore", "timestamp": 1},
+                {"id": "b", "role": "assistant", "content": "X is...", "timestamp": 2,
+                 "provider": "claude-code", "summary": "Explains X", "futureField": true}
+            ],
+            "edges": [{"id": "e1", "source": "a", "target": "b"}],
+            "layout": [{"id": "a", "position": {"x": 0, "y": 0}}]
+        },
+        "notesDirectory": "/notes"
+    }"#;
+
+    #[test]
+    fn test_parse_v3_keeps_unknown_fields() {
+        let project = ProjectFile::parse(V3).unwrap();
+        assert_eq!(project.graph.nodes.len(), 2);
+        assert_eq!(project.settings["notesDirectory"], "/notes");
+        assert_eq!(project.graph.parents("b").collect::<Vec<_>>(), vec!["a"]);
+
+        let node = project.graph.node("b").unwrap();
+        assert_eq!(node.extra["futureField"], true);
+        let json = serde_json::to_value(node).unwrap();
+        assert_eq!(json["futureField"], true);
+        assert!(json.get("images").is_none());
+    }
+
+    #[test]
+    fn test_parse_legacy_v2() {
+        let legacy = r#"{
+            "version": 2,
+            "nodes": [{"id": "a", "position": {"x": 5, "y": 6}}, {"id": "ghost"}],
+            "edges": [],
+            "nodeData": {"a": {"id": "a", "role": "user", "content": "Hi", "timestamp": 1}}
+        }"#;
+        let project = ProjectFile::parse(legacy).unwrap();
+        assert_eq!(project.graph.nodes.len(), 1);
+        assert_eq!(project.graph.layout[0].position.x, 5.0);
+        assert!(project.settings.is_empty());
+    }
+
+    #[test]
+    fn test_manifest_titles_and_parents() {
+        let manifest = ProjectFile::parse(V3).unwrap().manifest();
+        assert_eq!(manifest.nodes[0].title, "What is X?");
+        assert_eq!(manifest.nodes[1].title, "Explains X");
+        assert_eq!(manifest.nodes[1].parent_ids, vec!["a".to_string()]);
+
+        let long = ProjectNode {
+            content: "é".repeat(200),
+            ..ProjectFile::parse(V3).unwrap().graph.nodes[0].clone()
+        };
+        assert_eq!(node_title(&long).chars().count(), MAX_TITLE_CHARS);
+    }
+
+    #[test]
+    fn test_compressed_and_plain_projects_decode_alike() {
+        let gzipped = encode_project_data(V3, true).unwrap();
+        assert!(gzipped.starts_with(GZIP_MAGIC));
+        assert!(gzipped.len() < V3.len());
+        assert_eq!(decode_project_bytes(&gzipped).unwrap(), V3);
+
+        let plain = encode_project_data(V3, false).unwrap();
+        assert_eq!(decode_project_bytes(&plain).unwrap(), V3);
+        assert!(decode_project_bytes(&[0x28, 0xb5, 0x2f, 0xfd, 0]).is_err());
+    }
+
+    #[test]
+    fn test_find_projects_skips_hidden_directories() {
+        let dir = TempDir::new("projects");
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::create_dir_all(dir.join(".trash")).unwrap();
+        std::fs::write(dir.join("a.thoughttree"), "{}").unwrap();
+        std::fs::write(dir.join("sub/b.thoughttree"), "{}").unwrap();
+        std::fs::write(dir.join(".trash/c.thoughttree"), "{}").unwrap();
+        std::fs::write(dir.join("note.md"), "").unwrap();
+
+        let mut found = find_projects(&[dir.to_path_buf()]);
+        found.sort();
+        assert_eq!(
+            found,
+            [dir.join("a.thoughttree"), dir.join("sub/b.thoughttree")]
+        );
+    }
+}
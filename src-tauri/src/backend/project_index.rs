@@ -0,0 +1,261 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::backend::project_file::{find_projects, node_title, read_project_data, ProjectFile};
+use crate::backend::search_index::{
+    load_index, modified_ms, save_index, IndexStats, InvertedIndex,
+};
+
+/// Bump when the on-disk layout changes; older indexes are rebuilt
+const PROJECT_INDEX_FORMAT_VERSION: u32 = 1;
+
+/// Project files larger than this are not indexed
+const MAX_INDEXED_PROJECT_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Key of a node in the term index. Paths can't contain NUL, so the last
+/// one separates the node id.
+fn node_key(path: &str, node_id: &str) -> String {
+    format!("{path}\0{node_id}")
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct IndexedProject {
+    modified_ms: u64,
+    /// Node id -> title, to label hits without reading the project
+    titles: HashMap<String, String>,
+}
+
+/// Inverted index of the node text of every project in the workspaces,
+/// updated when a project is saved and by comparing modification times
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct ProjectIndex {
+    version: u32,
+    /// Keyed by absolute project path
+    projects: HashMap<String, IndexedProject>,
+    /// Keyed by [`node_key`]
+    nodes: InvertedIndex,
+}
+
+/// A ranked node from `query_project_index`
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub(crate) struct ProjectIndexHit {
+    /// Absolute project path
+    pub path: String,
+    pub node_id: String,
+    pub title: String,
+    pub score: f64,
+}
+
+impl ProjectIndex {
+    pub(crate) fn new() -> Self {
+        Self {
+            version: PROJECT_INDEX_FORMAT_VERSION,
+            projects: HashMap::new(),
+            nodes: InvertedIndex::default(),
+        }
+    }
+
+    pub(crate) fn is_current(&self) -> bool {
+        self.version == PROJECT_INDEX_FORMAT_VERSION
+    }
+
+    pub(crate) fn project_count(&self) -> usize {
+        self.projects.len()
+    }
+
+    fn remove_project(&mut self, path: &str) {
+        if let Some(project) = self.projects.remove(path) {
+            for node_id in project.titles.keys() {
+                self.nodes.remove_doc(&node_key(path, node_id));
+            }
+        }
+    }
+
+    /// Index (or re-index) every node of one project
+    pub(crate) fn index_project(&mut self, path: &str, project: &ProjectFile, modified_ms: u64) {
+        self.remove_project(path);
+        let mut titles = HashMap::new();
+        for node in &project.graph.nodes {
+            let text = match &node.summary {
+                Some(summary) => format!("{summary}\n{}", node.content),
+                None => node.content.clone(),
+            };
+            self.nodes
+                .index_text(&node_key(path, &node.id), &text, modified_ms);
+            titles.insert(node.id.clone(), node_title(node));
+        }
+        self.projects.insert(
+            path.to_string(),
+            IndexedProject {
+                modified_ms,
+                titles,
+            },
+        );
+    }
+
+    /// Read the project at `path` and index it; a project that's gone is
+    /// dropped from the index
+    pub(crate) fn update_project(&mut self, path: &Path) -> Result<(), String> {
+        let key = path.to_string_lossy().to_string();
+        let metadata = match std::fs::metadata(path) {
+            Ok(metadata) if metadata.len() <= MAX_INDEXED_PROJECT_BYTES => metadata,
+            _ => {
+                self.remove_project(&key);
+                return Ok(());
+            }
+        };
+        let project = ProjectFile::parse(&read_project_data(path)?)?;
+        self.index_project(&key, &project, modified_ms(&metadata));
+        Ok(())
+    }
+
+    /// Bring the index in line with the projects under `dirs`: index new or
+    /// modified projects and drop deleted ones. Projects that fail to parse
+    /// are left out.
+    pub(crate) fn refresh(&mut self, dirs: &[PathBuf]) -> IndexStats {
+        let mut stats = IndexStats::default();
+        let mut seen = HashSet::new();
+
+        for path in find_projects(dirs) {
+            let Ok(metadata) = std::fs::symlink_metadata(&path) else {
+                continue;
+            };
+            if metadata.len() > MAX_INDEXED_PROJECT_BYTES {
+                continue;
+            }
+            let key = path.to_string_lossy().to_string();
+            let modified = modified_ms(&metadata);
+            let unchanged = self
+                .projects
+                .get(&key)
+                .is_some_and(|project| project.modified_ms == modified);
+            if unchanged {
+                seen.insert(key);
+                continue;
+            }
+
+            match read_project_data(&path).and_then(|data| ProjectFile::parse(&data)) {
+                Ok(project) => {
+                    self.index_project(&key, &project, modified);
+                    stats.indexed += 1;
+                    seen.insert(key);
+                }
+                Err(e) => tracing::debug!("Not indexing project {:?}: {}", path, e),
+            }
+        }
+
+        let removed: Vec<String> = self
+            .projects
+            .keys()
+            .filter(|path| !seen.contains(*path))
+            .cloned()
+            .collect();
+        stats.removed = removed.len();
+        for path in removed {
+            self.remove_project(&path);
+        }
+
+        stats.total_documents = self.projects.len();
+        stats
+    }
+
+    /// Nodes of all projects ranked by length-normalized tf-idf
+    pub(crate) fn query(&self, query: &str, limit: usize) -> Vec<ProjectIndexHit> {
+        self.nodes
+            .query(query, limit)
+            .into_iter()
+            .filter_map(|hit| {
+                let (path, node_id) = hit.path.rsplit_once('\0')?;
+                let title = self.projects.get(path)?.titles.get(node_id)?.clone();
+                Some(ProjectIndexHit {
+                    path: path.to_string(),
+                    node_id: node_id.to_string(),
+                    title,
+                    score: hit.score,
+                })
+            })
+            .collect()
+    }
+
+    /// Load a persisted index; a missing, corrupt or outdated file yields `None`
+    pub(crate) fn load(path: &Path) -> Option<Self> {
+        load_index::<Self>(path).filter(Self::is_current)
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> Result<(), String> {
+        save_index(self, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_util::TempDir;
+
+    fn project(nodes: &str) -> String {
+        format!(r#"{{"version": 3, "graph": {{"version": 3, "nodes": [{nodes}], "edges": []}}}}"#)
+    }
+
+    #[test]
+    fn test_query_ranks_nodes_across_projects() {
+        let mut index = ProjectIndex::new();
+        let physics = ProjectFile::parse(&project(
+            r#"{"id": "a", "role": "user", "content": "Why do tides happen?"},
+               {"id": "b", "role": "assistant", "content": "Tides come from the Moon's gravity.", "summary": "Lunar tides"}"#,
+        ))
+        .unwrap();
+        let garden = ProjectFile::parse(&project(
+            r#"{"id": "c", "role": "user", "content": "Planting by the moon phases and tides of sap in spring gardens"}"#,
+        ))
+        .unwrap();
+        index.index_project("/notes/physics.thoughttree", &physics, 1);
+        index.index_project("/notes/garden.thoughttree", &garden, 1);
+
+        let hits = index.query("moon tides", 10);
+        assert_eq!(
+            hits.iter()
+                .map(|h| (h.path.as_str(), h.node_id.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                ("/notes/physics.thoughttree", "b"),
+                ("/notes/garden.thoughttree", "c"),
+                ("/notes/physics.thoughttree", "a")
+            ]
+        );
+        assert_eq!(hits[0].title, "Lunar tides");
+
+        index.index_project("/notes/physics.thoughttree", &ProjectFile::default(), 2);
+        assert_eq!(index.query("tides", 10).len(), 1);
+    }
+
+    #[test]
+    fn test_refresh_tracks_projects_and_persists() {
+        let dir = TempDir::new("project-index");
+        let one = dir.join("one.thoughttree");
+        std::fs::write(
+            &one,
+            project(r#"{"id": "a", "role": "user", "content": "entropy"}"#),
+        )
+        .unwrap();
+        std::fs::write(dir.join("broken.thoughttree"), "not json").unwrap();
+
+        let dirs = [dir.to_path_buf()];
+        let mut index = ProjectIndex::new();
+        let stats = index.refresh(&dirs);
+        assert_eq!((stats.indexed, stats.total_documents), (1, 1));
+        assert_eq!(index.refresh(&dirs).indexed, 0);
+
+        let saved = dir.join("index").join("project-index.json");
+        index.save(&saved).unwrap();
+        let loaded = ProjectIndex::load(&saved).unwrap();
+        assert_eq!(loaded.query("entropy", 10)[0].node_id, "a");
+
+        std::fs::remove_file(&one).unwrap();
+        assert_eq!(index.refresh(&dirs).removed, 1);
+        assert!(index.query("entropy", 10).is_empty());
+        index.update_project(&one).unwrap();
+        assert_eq!(index.project_count(), 0);
+    }
+}
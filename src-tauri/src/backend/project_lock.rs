@@ -0,0 +1,290 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use serde::{Deserialize, Serialize};
+
+/// Who holds a project open, stored as JSON in the lock file next to it
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct LockInfo {
+    pub pid: u32,
+    pub hostname: String,
+    /// RFC 3339 timestamp
+    pub acquired_at: String,
+    /// Distinguishes app runs, since PIDs are reused
+    pub instance_id: String,
+}
+
+/// `notes/plan.thoughttree` -> `notes/.plan.thoughttree.lock`. Kept beside
+/// the project so sync clients carry it to other machines.
+pub(crate) fn lock_path(project: &Path) -> Option<PathBuf> {
+    let name = project.file_name()?.to_string_lossy();
+    Some(project.with_file_name(format!(".{name}.lock")))
+}
+
+pub(crate) fn read_lock(project: &Path) -> Option<LockInfo> {
+    let data = std::fs::read_to_string(lock_path(project)?).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+#[cfg(unix)]
+pub(crate) fn hostname() -> String {
+    let mut buf = [0u8; 256];
+    // SAFETY: the buffer is valid for its length; gethostname NUL-terminates on success
+    if unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) } != 0 {
+        return String::new();
+    }
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..len]).into_owned()
+}
+
+#[cfg(windows)]
+pub(crate) fn hostname() -> String {
+    std::env::var("COMPUTERNAME").unwrap_or_default()
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // SAFETY: signal 0 only checks whether the process exists
+    let result = unsafe { libc::kill(pid, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(windows)]
+fn process_alive(pid: u32) -> bool {
+    use windows_sys::Win32::Foundation::{
+        CloseHandle, GetLastError, ERROR_ACCESS_DENIED, STILL_ACTIVE,
+    };
+    use windows_sys::Win32::System::Threading::{
+        GetExitCodeProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    // SAFETY: the handle is checked for null and closed before returning
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if handle.is_null() {
+            // Access denied means the process exists but isn't ours to query
+            return GetLastError() == ERROR_ACCESS_DENIED;
+        }
+        let mut exit_code = 0u32;
+        // If the exit code can't be read, keep the lock rather than take it over
+        let alive =
+            GetExitCodeProcess(handle, &mut exit_code) == 0 || exit_code == STILL_ACTIVE as u32;
+        CloseHandle(handle);
+        alive
+    }
+}
+
+impl LockInfo {
+    /// A lock left behind by a crashed app on this machine. Locks from other
+    /// hosts are never considered stale: their process can't be checked.
+    fn is_stale(&self, this_host: &str) -> bool {
+        self.hostname == this_host && !process_alive(self.pid)
+    }
+
+    pub(crate) fn describe(&self) -> String {
+        format!(
+            "process {} on {} since {}",
+            self.pid, self.hostname, self.acquired_at
+        )
+    }
+}
+
+/// Why a project couldn't be locked
+#[derive(Debug)]
+pub(crate) enum LockError {
+    /// Another live instance holds the lock
+    Held(String),
+    /// The lock file couldn't be written, e.g. in a read-only folder
+    Io(String),
+}
+
+/// Advisory locks on the projects this app instance has open
+pub(crate) struct ProjectLocks {
+    instance_id: String,
+    held: Mutex<Vec<PathBuf>>,
+}
+
+impl Default for ProjectLocks {
+    fn default() -> Self {
+        Self {
+            instance_id: uuid::Uuid::new_v4().to_string(),
+            held: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl ProjectLocks {
+    fn held(&self) -> MutexGuard<'_, Vec<PathBuf>> {
+        self.held.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn is_ours(&self, info: &LockInfo) -> bool {
+        info.instance_id == self.instance_id
+    }
+
+    /// Lock `project` for this instance. Fails if another live instance
+    /// holds it; stale locks from crashed instances are taken over.
+    pub(crate) fn acquire(&self, project: &Path) -> Result<(), LockError> {
+        let path =
+            lock_path(project).ok_or_else(|| LockError::Io("Invalid project path".to_string()))?;
+        let host = hostname();
+
+        if let Some(existing) = read_lock(project) {
+            if !self.is_ours(&existing) && !existing.is_stale(&host) {
+                return Err(LockError::Held(format!(
+                    "Project is open in another ThoughtTree instance ({}). Close it there or force unlock.",
+                    existing.describe()
+                )));
+            }
+            let _ = std::fs::remove_file(&path);
+        } else if path.exists() {
+            // Unreadable lock, e.g. a partial write; treat as stale
+            let _ = std::fs::remove_file(&path);
+        }
+
+        let info = LockInfo {
+            pid: std::process::id(),
+            hostname: host,
+            acquired_at: chrono::Utc::now().to_rfc3339(),
+            instance_id: self.instance_id.clone(),
+        };
+        let json = serde_json::to_string_pretty(&info)
+            .map_err(|e| LockError::Io(format!("Failed to serialize lock: {e}")))?;
+        // create_new so two instances racing for the same project can't both win
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .map_err(|e| LockError::Io(format!("Failed to create project lock: {e}")))?;
+        if let Err(e) = file.write_all(json.as_bytes()) {
+            let _ = std::fs::remove_file(&path);
+            return Err(LockError::Io(format!("Failed to write project lock: {e}")));
+        }
+
+        let mut held = self.held();
+        if !held.iter().any(|p| p == project) {
+            held.push(project.to_path_buf());
+        }
+        Ok(())
+    }
+
+    /// Whether this instance has `project` open
+    pub(crate) fn is_held(&self, project: &Path) -> bool {
+        self.held().iter().any(|p| p == project)
+    }
+
+    /// Refuse to write `project` if another instance has taken its lock
+    /// (e.g. after force-unlocking it)
+    pub(crate) fn check_writable(&self, project: &Path) -> Result<(), String> {
+        match read_lock(project) {
+            Some(info) if !self.is_ours(&info) => Err(format!(
+                "Project is locked by another ThoughtTree instance ({}); not saving",
+                info.describe()
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Release our lock on `project`; a lock owned by someone else is left alone
+    pub(crate) fn release(&self, project: &Path) {
+        self.held().retain(|p| p != project);
+        if read_lock(project).is_some_and(|info| self.is_ours(&info)) {
+            if let Some(path) = lock_path(project) {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+
+    /// Release every lock except the one on `keep` (the newly opened project)
+    pub(crate) fn release_others(&self, keep: &Path) {
+        let others: Vec<PathBuf> = self.held().iter().filter(|p| *p != keep).cloned().collect();
+        for project in others {
+            self.release(&project);
+        }
+    }
+
+    pub(crate) fn release_all(&self) {
+        let held = std::mem::take(&mut *self.held());
+        for project in held {
+            self.release(&project);
+        }
+    }
+}
+
+/// Remove the lock on `project` whoever holds it
+pub(crate) fn force_unlock(project: &Path) -> Result<(), String> {
+    let path = lock_path(project).ok_or_else(|| "Invalid project path".to_string())?;
+    match std::fs::remove_file(&path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to remove project lock: {e}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_util::TempDir;
+
+    fn temp_project() -> (TempDir, PathBuf) {
+        let dir = TempDir::new("lock");
+        let project = dir.join("plan.thoughttree");
+        std::fs::write(&project, "{}").unwrap();
+        (dir, project)
+    }
+
+    #[test]
+    fn test_second_instance_is_refused_until_released() {
+        let (dir, project) = temp_project();
+        let first = ProjectLocks::default();
+        let second = ProjectLocks::default();
+
+        first.acquire(&project).unwrap();
+        first.acquire(&project).unwrap();
+        assert_eq!(read_lock(&project).unwrap().pid, std::process::id());
+        assert!(matches!(second.acquire(&project), Err(LockError::Held(_))));
+        // A lock that can't be written isn't a conflict
+        assert!(matches!(
+            second.acquire(&dir.join("missing").join("plan.thoughttree")),
+            Err(LockError::Io(_))
+        ));
+        assert!(second.check_writable(&project).is_err());
+        assert!(first.check_writable(&project).is_ok());
+
+        second.release(&project);
+        assert!(read_lock(&project).is_some());
+        first.release_all();
+        assert!(read_lock(&project).is_none());
+        second.acquire(&project).unwrap();
+    }
+
+    #[test]
+    fn test_force_unlock_and_stale_locks() {
+        let (_dir, project) = temp_project();
+        let owner = ProjectLocks::default();
+        owner.acquire(&project).unwrap();
+
+        force_unlock(&project).unwrap();
+        force_unlock(&project).unwrap();
+        let other = ProjectLocks::default();
+        other.acquire(&project).unwrap();
+        assert!(owner.check_writable(&project).is_err());
+
+        let stale = LockInfo {
+            pid: u32::MAX,
+            hostname: hostname(),
+            acquired_at: String::new(),
+            instance_id: "crashed".to_string(),
+        };
+        std::fs::write(
+            lock_path(&project).unwrap(),
+            serde_json::to_string(&stale).unwrap(),
+        )
+        .unwrap();
+        owner.acquire(&project).unwrap();
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use serde::Serialize;
+
+use crate::backend::project_file::ProjectGraph;
+
+/// Average silent reading speed used for reading-time estimates
+const WORDS_PER_MINUTE: f64 = 230.0;
+
+/// Counts for one node
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub(crate) struct NodeStats {
+    pub id: String,
+    pub role: String,
+    pub words: usize,
+    /// Edges from the nearest root; 0 for roots
+    pub depth: usize,
+    pub children: usize,
+}
+
+/// Size and shape of a whole project
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub(crate) struct ProjectStats {
+    pub node_count: usize,
+    pub total_words: usize,
+    pub user_words: usize,
+    pub assistant_words: usize,
+    /// Minutes to read every node at [`WORDS_PER_MINUTE`]
+    pub reading_time_minutes: f64,
+    pub root_count: usize,
+    pub leaf_count: usize,
+    pub max_depth: usize,
+    /// Mean number of children of the nodes that have any; 0 without edges
+    pub branching_factor: f64,
+    pub max_children: usize,
+    /// In project file order
+    pub nodes: Vec<NodeStats>,
+}
+
+pub(crate) fn count_words(text: &str) -> usize {
+    text.split_whitespace().count()
+}
+
+/// Depth of every node reachable from a root, by breadth-first search. In a
+/// graph with several paths to a node, the shortest counts.
+fn node_depths(roots: &[&str], children: &HashMap<&str, Vec<&str>>) -> HashMap<String, usize> {
+    let mut depths: HashMap<String, usize> = HashMap::new();
+    let mut queue: VecDeque<(&str, usize)> = roots.iter().map(|id| (*id, 0)).collect();
+    while let Some((id, depth)) = queue.pop_front() {
+        if depths.contains_key(id) {
+            continue;
+        }
+        depths.insert(id.to_string(), depth);
+        for child in children.get(id).into_iter().flatten() {
+            if !depths.contains_key(*child) {
+                queue.push_back((child, depth + 1));
+            }
+        }
+    }
+    depths
+}
+
+/// Word counts, reading time and tree shape of `graph`. Linear in the size
+/// of the graph, so it stays fast for very large projects.
+pub(crate) fn project_stats(graph: &ProjectGraph) -> ProjectStats {
+    let mut children: HashMap<&str, Vec<&str>> = HashMap::new();
+    for edge in &graph.edges {
+        children
+            .entry(edge.source.as_str())
+            .or_default()
+            .push(edge.target.as_str());
+    }
+    let targets: HashSet<&str> = graph.edges.iter().map(|e| e.target.as_str()).collect();
+    let roots: Vec<&str> = graph
+        .nodes
+        .iter()
+        .map(|n| n.id.as_str())
+        .filter(|id| !targets.contains(id))
+        .collect();
+    let depths = node_depths(&roots, &children);
+
+    let nodes: Vec<NodeStats> = graph
+        .nodes
+        .iter()
+        .map(|node| NodeStats {
+            id: node.id.clone(),
+            role: node.role.clone(),
+            words: count_words(&node.content),
+            // Nodes only reachable through a cycle have no root to count from
+            depth: depths.get(&node.id).copied().unwrap_or(0),
+            children: children.get(node.id.as_str()).map_or(0, Vec::len),
+        })
+        .collect();
+
+    let words_by_role = |role: &str| -> usize {
+        nodes
+            .iter()
+            .filter(|n| n.role == role)
+            .map(|n| n.words)
+            .sum()
+    };
+    let total_words = nodes.iter().map(|n| n.words).sum();
+    let parents: Vec<&NodeStats> = nodes.iter().filter(|n| n.children > 0).collect();
+    let branching_factor = if parents.is_empty() {
+        0.0
+    } else {
+        parents.iter().map(|n| n.children).sum::<usize>() as f64 / parents.len() as f64
+    };
+
+    ProjectStats {
+        node_count: nodes.len(),
+        total_words,
+        user_words: words_by_role("user"),
+        assistant_words: words_by_role("assistant"),
+        reading_time_minutes: total_words as f64 / WORDS_PER_MINUTE,
+        root_count: roots.len(),
+        leaf_count: nodes.len() - parents.len(),
+        max_depth: nodes.iter().map(|n| n.depth).max().unwrap_or(0),
+        branching_factor,
+        max_children: nodes.iter().map(|n| n.children).max().unwrap_or(0),
+        nodes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::project_file::ProjectFile;
+
+    #[test]
+    fn test_project_stats() {
+        // a -> b -> d, a -> c, e (separate root)
+        let project = ProjectFile::parse(
+            r#"{"version": 3, "graph": {"version": 3,
+                "nodes": [
+                    {"id": "a", "role": "user", "content": "Why is the sky blue?"},
+                    {"id": "b", "role": "assistant", "content": "Rayleigh scattering of light."},
+                    {"id": "c", "role": "assistant", "content": "Because\nof physics"},
+                    {"id": "d", "role": "user", "content": ""},
+                    {"id": "e", "role": "user", "content": "Unrelated"}
+                ],
+                "edges": [
+                    {"id": "1", "source": "a", "target": "b"},
+                    {"id": "2", "source": "a", "target": "c"},
+                    {"id": "3", "source": "b", "target": "d"}
+                ]}}"#,
+        )
+        .unwrap();
+        let stats = project_stats(&project.graph);
+        assert_eq!(stats.node_count, 5);
+        assert_eq!(stats.user_words, 6);
+        assert_eq!(stats.assistant_words, 7);
+        assert_eq!(stats.total_words, 13);
+        assert!((stats.reading_time_minutes - 13.0 / WORDS_PER_MINUTE).abs() < 1e-9);
+        assert_eq!(stats.root_count, 2);
+        assert_eq!(stats.leaf_count, 3);
+        assert_eq!(stats.max_depth, 2);
+        assert_eq!(stats.max_children, 2);
+        assert!((stats.branching_factor - 1.5).abs() < 1e-9);
+        let depths: Vec<usize> = stats.nodes.iter().map(|n| n.depth).collect();
+        assert_eq!(depths, [0, 1, 1, 2, 0]);
+    }
+
+    #[test]
+    fn test_project_stats_of_empty_project_and_cycles() {
+        let stats = project_stats(&ProjectGraph::default());
+        assert_eq!(stats.node_count, 0);
+        assert_eq!(stats.branching_factor, 0.0);
+
+        let project = ProjectFile::parse(
+            r#"{"version": 3, "graph": {"version": 3,
+                "nodes": [{"id": "a", "role": "user"}, {"id": "b", "role": "user"}],
+                "edges": [
+                    {"id": "1", "source": "a", "target": "b"},
+                    {"id": "2", "source": "b", "target": "a"}
+                ]}}"#,
+        )
+        .unwrap();
+        let stats = project_stats(&project.graph);
+        assert_eq!(stats.root_count, 0);
+        assert_eq!(stats.max_depth, 0);
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, SystemTime};
+
+/// How often the open project file is checked for changes on disk
+pub(crate) const PROJECT_WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// What the app last knew a project file to contain. The mtime and length
+/// are a cheap first check; the hash decides when those differ, so a sync
+/// client touching the file without changing it isn't reported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Fingerprint {
+    modified: Option<SystemTime>,
+    len: u64,
+    hash: u64,
+}
+
+fn content_hash(data: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl Fingerprint {
+    pub(crate) fn of_file(path: &Path) -> Result<Self, String> {
+        let data = std::fs::read(path).map_err(|e| format!("Failed to read project: {e}"))?;
+        Ok(Self::of_data(path, &data))
+    }
+
+    pub(crate) fn of_data(path: &Path, data: &[u8]) -> Self {
+        Self {
+            modified: std::fs::metadata(path).and_then(|m| m.modified()).ok(),
+            len: data.len() as u64,
+            hash: content_hash(data),
+        }
+    }
+}
+
+struct Watched {
+    path: PathBuf,
+    /// The path as the frontend opened it, echoed back in events
+    label: String,
+    known: Fingerprint,
+    /// Set once a change has been reported, so it is reported only once
+    notified: bool,
+}
+
+impl Watched {
+    /// Current contents if they differ from what the app last read or wrote
+    fn changed_contents(&mut self) -> Option<Vec<u8>> {
+        let meta = std::fs::metadata(&self.path).ok()?;
+        if meta.modified().ok() == self.known.modified && meta.len() == self.known.len {
+            return None;
+        }
+        let data = std::fs::read(&self.path).ok()?;
+        let current = Fingerprint::of_data(&self.path, &data);
+        if current.hash == self.known.hash {
+            // Touched but not changed; remember the new mtime to skip rehashing
+            self.known = current;
+            return None;
+        }
+        Some(data)
+    }
+}
+
+/// Tracks the open project file so edits made by another program (or a sync
+/// client) are noticed instead of being silently overwritten
+#[derive(Default)]
+pub(crate) struct ProjectWatch {
+    watched: Mutex<Option<Watched>>,
+}
+
+impl ProjectWatch {
+    fn lock(&self) -> MutexGuard<'_, Option<Watched>> {
+        self.watched.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Start watching `path` (replacing any previously watched project),
+    /// taking `known` as its current contents
+    pub(crate) fn watch(&self, path: &Path, label: &str, known: Fingerprint) {
+        *self.lock() = Some(Watched {
+            path: path.to_path_buf(),
+            label: label.to_string(),
+            known,
+            notified: false,
+        });
+    }
+
+    pub(crate) fn unwatch(&self, path: &Path) {
+        let mut watched = self.lock();
+        if watched.as_ref().is_some_and(|w| w.path == path) {
+            *watched = None;
+        }
+    }
+
+    /// Run `write` for `path` and record the result as the known contents.
+    /// Unless `overwrite_external` is set, the write is refused when the file
+    /// has changed on disk since the app last read or wrote it.
+    pub(crate) fn write_tracked(
+        &self,
+        path: &Path,
+        overwrite_external: bool,
+        write: impl FnOnce() -> Result<(), String>,
+    ) -> Result<(), String> {
+        // Held across the write so polling can't mistake it for an external change
+        let mut guard = self.lock();
+        let watched = guard.as_mut().filter(|w| w.path == path);
+
+        let Some(watched) = watched else {
+            return write();
+        };
+        if !overwrite_external && watched.changed_contents().is_some() {
+            return Err(
+                "Project was changed by another program; reload it or save explicitly to overwrite"
+                    .to_string(),
+            );
+        }
+        write()?;
+        if let Ok(known) = Fingerprint::of_file(path) {
+            watched.known = known;
+            watched.notified = false;
+        }
+        Ok(())
+    }
+
+    /// Check the watched file and return its label the first time an
+    /// external change is seen
+    pub(crate) fn poll(&self) -> Option<String> {
+        let mut guard = self.lock();
+        let watched = guard.as_mut()?;
+        if watched.notified || watched.changed_contents().is_none() {
+            return None;
+        }
+        watched.notified = true;
+        Some(watched.label.clone())
+    }
+
+    /// The new raw contents of `path` if it changed on disk, which then become
+    /// the known contents. `None` when unchanged or not being watched.
+    pub(crate) fn reload_if_changed(&self, path: &Path) -> Option<Vec<u8>> {
+        let mut guard = self.lock();
+        let watched = guard.as_mut().filter(|w| w.path == path)?;
+        let data = watched.changed_contents()?;
+        watched.known = Fingerprint::of_data(path, &data);
+        watched.notified = false;
+        Some(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_util::TempDir;
+
+    fn temp_project(contents: &str) -> (TempDir, PathBuf) {
+        let dir = TempDir::new("watch");
+        let path = dir.join("project.thoughttree");
+        std::fs::write(&path, contents).unwrap();
+        (dir, path)
+    }
+
+    #[test]
+    fn test_external_change_is_reported_once_and_reloaded() {
+        let (_dir, path) = temp_project("v1");
+        let watch = ProjectWatch::default();
+        watch.watch(&path, "p", Fingerprint::of_file(&path).unwrap());
+        assert_eq!(watch.poll(), None);
+
+        std::fs::write(&path, "v2 from sync").unwrap();
+        assert_eq!(watch.poll(), Some("p".to_string()));
+        assert_eq!(watch.poll(), None);
+
+        assert_eq!(
+            watch.reload_if_changed(&path),
+            Some(b"v2 from sync".to_vec())
+        );
+        assert_eq!(watch.reload_if_changed(&path), None);
+    }
+
+    #[test]
+    fn test_own_writes_are_not_external_changes() {
+        let (_dir, path) = temp_project("v1");
+        let watch = ProjectWatch::default();
+        watch.watch(&path, "p", Fingerprint::of_file(&path).unwrap());
+
+        watch
+            .write_tracked(&path, false, || {
+                std::fs::write(&path, "v2 from app").map_err(|e| e.to_string())
+            })
+            .unwrap();
+        assert_eq!(watch.poll(), None);
+
+        std::fs::write(&path, "v3 from sync").unwrap();
+        let refused = watch.write_tracked(&path, false, || unreachable!());
+        assert!(refused.is_err());
+        watch
+            .write_tracked(&path, true, || {
+                std::fs::write(&path, "v4 forced").map_err(|e| e.to_string())
+            })
+            .unwrap();
+        assert_eq!(watch.poll(), None);
+    }
+}
@@ -0,0 +1,213 @@
+use std::time::{Duration, Instant};
+
+use reqwest::{NoProxy, Proxy, Url};
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+/// Endpoints `test_proxy` tries to reach, one per provider
+const PROXY_TEST_URLS: [&str; 2] = [
+    "https://api.anthropic.com",
+    "https://generativelanguage.googleapis.com",
+];
+
+const PROXY_TEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Proxies for agents and direct HTTP requests; unset ones aren't used
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub(crate) struct ProxySettings {
+    /// `http://host:port` for plain HTTP requests
+    pub http: Option<String>,
+    /// `http://host:port` (or `https://`) for HTTPS requests
+    pub https: Option<String>,
+    /// `socks5://host:port` for anything without a more specific proxy
+    pub socks: Option<String>,
+    /// Comma-separated hosts that bypass the proxies, e.g. `localhost,.corp`
+    pub no_proxy: Option<String>,
+}
+
+/// Outcome of reaching one endpoint through the proxies
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct ProxyTestResult {
+    pub url: String,
+    /// An HTTP response came back, whatever its status
+    pub reachable: bool,
+    pub status: Option<u16>,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+fn validate_proxy_url(kind: &str, value: &str, schemes: &[&str]) -> Result<(), String> {
+    let url = Url::parse(value).map_err(|e| format!("Invalid {kind} proxy URL: {e}"))?;
+    if !schemes.contains(&url.scheme()) {
+        return Err(format!(
+            "{kind} proxy must start with {}",
+            schemes
+                .iter()
+                .map(|s| format!("{s}://"))
+                .collect::<Vec<_>>()
+                .join(" or ")
+        ));
+    }
+    if url.host_str().is_none() {
+        return Err(format!("{kind} proxy URL has no host"));
+    }
+    // config.json is plain text, so it must not hold passwords
+    if !url.username().is_empty() || url.password().is_some() {
+        return Err(format!("{kind} proxy URL must not contain credentials"));
+    }
+    Ok(())
+}
+
+pub(crate) fn validate_proxy_settings(settings: &ProxySettings) -> Result<(), String> {
+    if let Some(http) = non_empty(&settings.http) {
+        validate_proxy_url("HTTP", http, &["http", "https"])?;
+    }
+    if let Some(https) = non_empty(&settings.https) {
+        validate_proxy_url("HTTPS", https, &["http", "https"])?;
+    }
+    if let Some(socks) = non_empty(&settings.socks) {
+        validate_proxy_url("SOCKS", socks, &["socks5", "socks5h"])?;
+    }
+    if let Some(no_proxy) = non_empty(&settings.no_proxy) {
+        if no_proxy
+            .chars()
+            .any(|c| c.is_control() || (c.is_whitespace() && c != ' '))
+        {
+            return Err("No-proxy list must be a single line".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Environment variables that route CLIs and Node through the proxies, in
+/// both the upper and lower case spellings tools look for
+pub(crate) fn proxy_env(settings: &ProxySettings) -> Vec<(String, String)> {
+    let variables = [
+        ("HTTP_PROXY", non_empty(&settings.http)),
+        ("HTTPS_PROXY", non_empty(&settings.https)),
+        ("ALL_PROXY", non_empty(&settings.socks)),
+        ("NO_PROXY", non_empty(&settings.no_proxy)),
+    ];
+    variables
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value?)))
+        .flat_map(|(name, value)| {
+            [
+                (name.to_string(), value.to_string()),
+                (name.to_ascii_lowercase(), value.to_string()),
+            ]
+        })
+        .collect()
+}
+
+/// Set the proxy variables on an agent command; they take precedence over
+/// the same variables inherited or passed through
+pub(crate) fn apply_proxy_env(command: &mut Command, settings: &ProxySettings) {
+    for (name, value) in proxy_env(settings) {
+        command.env(name, value);
+    }
+}
+
+/// An HTTP client builder that goes through the proxies. Without any set,
+/// reqwest's default of honoring the system proxy variables applies.
+pub(crate) fn http_client_builder(
+    settings: &ProxySettings,
+) -> Result<reqwest::ClientBuilder, String> {
+    let no_proxy = non_empty(&settings.no_proxy).and_then(NoProxy::from_string);
+    let with_no_proxy = |proxy: Proxy| proxy.no_proxy(no_proxy.clone());
+    let mut builder = reqwest::Client::builder();
+    if let Some(http) = non_empty(&settings.http) {
+        let proxy = Proxy::http(http).map_err(|e| format!("Invalid HTTP proxy: {e}"))?;
+        builder = builder.proxy(with_no_proxy(proxy));
+    }
+    if let Some(https) = non_empty(&settings.https) {
+        let proxy = Proxy::https(https).map_err(|e| format!("Invalid HTTPS proxy: {e}"))?;
+        builder = builder.proxy(with_no_proxy(proxy));
+    }
+    if let Some(socks) = non_empty(&settings.socks) {
+        let proxy = Proxy::all(socks).map_err(|e| format!("Invalid SOCKS proxy: {e}"))?;
+        builder = builder.proxy(with_no_proxy(proxy));
+    }
+    Ok(builder)
+}
+
+/// Try to reach each provider's API through the proxies
+pub(crate) async fn test_proxy(settings: &ProxySettings) -> Result<Vec<ProxyTestResult>, String> {
+    validate_proxy_settings(settings)?;
+    let client = http_client_builder(settings)?
+        .timeout(PROXY_TEST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {e}"))?;
+
+    let tests = PROXY_TEST_URLS.iter().map(|url| {
+        let client = &client;
+        async move {
+            let started = Instant::now();
+            let response = client.head(*url).send().await;
+            let duration_ms = started.elapsed().as_millis() as u64;
+            match response {
+                Ok(response) => ProxyTestResult {
+                    url: url.to_string(),
+                    reachable: true,
+                    status: Some(response.status().as_u16()),
+                    error: None,
+                    duration_ms,
+                },
+                Err(e) => ProxyTestResult {
+                    url: url.to_string(),
+                    reachable: false,
+                    status: None,
+                    error: Some(e.without_url().to_string()),
+                    duration_ms,
+                },
+            }
+        }
+    });
+    Ok(futures::future::join_all(tests).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(http: &str, socks: &str) -> ProxySettings {
+        ProxySettings {
+            http: Some(http.to_string()),
+            https: Some(http.to_string()),
+            socks: Some(socks.to_string()),
+            no_proxy: Some("localhost,.corp".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_validate_proxy_settings() {
+        assert!(validate_proxy_settings(&ProxySettings::default()).is_ok());
+        assert!(validate_proxy_settings(&settings("http://proxy:3128", "socks5://s:1080")).is_ok());
+        assert!(validate_proxy_settings(&settings("socks5://proxy:1", "socks5://s:1")).is_err());
+        assert!(validate_proxy_settings(&settings("http://proxy:3128", "http://s:1")).is_err());
+        assert!(validate_proxy_settings(&settings("http://u:pw@proxy:1", "")).is_err());
+        assert!(validate_proxy_settings(&settings("proxy:3128", "")).is_err());
+    }
+
+    #[test]
+    fn test_proxy_env_sets_both_spellings() {
+        let env = proxy_env(&ProxySettings {
+            https: Some("http://proxy:3128".to_string()),
+            http: Some("  ".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(
+            env,
+            [
+                ("HTTPS_PROXY".to_string(), "http://proxy:3128".to_string()),
+                ("https_proxy".to_string(), "http://proxy:3128".to_string()),
+            ]
+        );
+        assert!(http_client_builder(&settings("http://proxy:3128", "socks5://s:1080")).is_ok());
+    }
+}
@@ -0,0 +1,218 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use serde::Serialize;
+use tokio::sync::watch;
+
+/// Generations allowed to run at once unless configured otherwise
+pub(crate) const DEFAULT_MAX_CONCURRENT_GENERATIONS: usize = 3;
+
+/// Snapshot of the generation queue for `get_generation_queue`
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct GenerationQueueSnapshot {
+    pub max_concurrent: usize,
+    /// Node ids currently generating
+    pub running: Vec<String>,
+    /// Node ids waiting for a slot, in order
+    pub waiting: Vec<String>,
+}
+
+struct Entry {
+    ticket: u64,
+    node_id: String,
+}
+
+struct QueueState {
+    max_concurrent: usize,
+    next_ticket: u64,
+    running: Vec<Entry>,
+    waiting: VecDeque<Entry>,
+}
+
+/// FIFO queue that caps how many agent subprocesses generate at once
+pub(crate) struct GenerationQueue {
+    state: Mutex<QueueState>,
+    /// Bumped whenever a slot frees up or the limit changes so waiters re-check
+    changes: watch::Sender<u64>,
+}
+
+impl GenerationQueue {
+    pub(crate) fn new(max_concurrent: usize) -> Self {
+        Self {
+            state: Mutex::new(QueueState {
+                max_concurrent: max_concurrent.max(1),
+                next_ticket: 0,
+                running: Vec::new(),
+                waiting: VecDeque::new(),
+            }),
+            changes: watch::channel(0).0,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn notify(&self) {
+        self.changes.send_modify(|v| *v = v.wrapping_add(1));
+    }
+
+    pub(crate) fn set_max_concurrent(&self, max_concurrent: usize) {
+        self.lock().max_concurrent = max_concurrent.max(1);
+        self.notify();
+    }
+
+    pub(crate) fn snapshot(&self) -> GenerationQueueSnapshot {
+        let state = self.lock();
+        GenerationQueueSnapshot {
+            max_concurrent: state.max_concurrent,
+            running: state.running.iter().map(|e| e.node_id.clone()).collect(),
+            waiting: state.waiting.iter().map(|e| e.node_id.clone()).collect(),
+        }
+    }
+
+    /// Wait for a generation slot. `on_position` is called with the 1-based
+    /// queue position whenever it changes while waiting.
+    pub(crate) async fn acquire(
+        self: &Arc<Self>,
+        node_id: &str,
+        mut on_position: impl FnMut(usize),
+    ) -> QueueSlot {
+        let mut changes = self.changes.subscribe();
+        let ticket = {
+            let mut state = self.lock();
+            let ticket = state.next_ticket;
+            state.next_ticket += 1;
+            state.waiting.push_back(Entry {
+                ticket,
+                node_id: node_id.to_string(),
+            });
+            ticket
+        };
+        // Leaves the queue if this future is dropped before getting a slot
+        let mut waiting = WaitingTicket {
+            queue: self.clone(),
+            ticket: Some(ticket),
+        };
+
+        let mut last_position = None;
+        loop {
+            {
+                let mut state = self.lock();
+                let position = state.waiting.iter().position(|e| e.ticket == ticket);
+                if position == Some(0) && state.running.len() < state.max_concurrent {
+                    if let Some(entry) = state.waiting.pop_front() {
+                        state.running.push(entry);
+                    }
+                    drop(state);
+                    waiting.ticket = None;
+                    // The next waiter may fit in a remaining slot
+                    self.notify();
+                    return QueueSlot {
+                        queue: self.clone(),
+                        ticket,
+                    };
+                }
+                if let Some(position) = position.map(|p| p + 1) {
+                    if last_position != Some(position) {
+                        last_position = Some(position);
+                        on_position(position);
+                    }
+                }
+            }
+            if changes.changed().await.is_err() {
+                // Sender lives as long as the queue; this can't happen while we hold an Arc
+                continue;
+            }
+        }
+    }
+}
+
+impl Default for GenerationQueue {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CONCURRENT_GENERATIONS)
+    }
+}
+
+struct WaitingTicket {
+    queue: Arc<GenerationQueue>,
+    ticket: Option<u64>,
+}
+
+impl Drop for WaitingTicket {
+    fn drop(&mut self) {
+        if let Some(ticket) = self.ticket {
+            self.queue.lock().waiting.retain(|e| e.ticket != ticket);
+            self.queue.notify();
+        }
+    }
+}
+
+/// A running generation; frees its slot when dropped
+pub(crate) struct QueueSlot {
+    queue: Arc<GenerationQueue>,
+    ticket: u64,
+}
+
+impl Drop for QueueSlot {
+    fn drop(&mut self) {
+        self.queue
+            .lock()
+            .running
+            .retain(|e| e.ticket != self.ticket);
+        self.queue.notify();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::task::{Context, Poll};
+
+    use futures::FutureExt;
+
+    use super::*;
+
+    #[test]
+    fn test_queue_limits_concurrency_and_reports_positions() {
+        let queue = Arc::new(GenerationQueue::new(1));
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+
+        let first = queue.acquire("a", |_| {}).now_or_never().unwrap();
+
+        let mut positions = Vec::new();
+        let mut second = Box::pin(queue.acquire("b", |p| positions.push(p)));
+        assert!(second.poll_unpin(&mut cx).is_pending());
+        assert_eq!(
+            queue.snapshot(),
+            GenerationQueueSnapshot {
+                max_concurrent: 1,
+                running: vec!["a".to_string()],
+                waiting: vec!["b".to_string()],
+            }
+        );
+
+        drop(first);
+        let Poll::Ready(second_slot) = second.poll_unpin(&mut cx) else {
+            panic!("second generation should start once the slot frees");
+        };
+        drop(second);
+        assert_eq!(positions, vec![1]);
+        assert_eq!(queue.snapshot().running, vec!["b".to_string()]);
+
+        drop(second_slot);
+        assert!(queue.snapshot().running.is_empty());
+    }
+
+    #[test]
+    fn test_dropped_waiter_leaves_queue() {
+        let queue = Arc::new(GenerationQueue::new(1));
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+
+        let _running = queue.acquire("a", |_| {}).now_or_never().unwrap();
+        let mut waiter = Box::pin(queue.acquire("b", |_| {}));
+        assert!(waiter.poll_unpin(&mut cx).is_pending());
+        drop(waiter);
+
+        assert!(queue.snapshot().waiting.is_empty());
+    }
+}
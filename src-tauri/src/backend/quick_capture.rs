@@ -0,0 +1,146 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::backend::note_edit::append_to_text;
+use crate::backend::project_file::{LayoutEntry, Position, ProjectFile, ProjectNode};
+
+/// Longest thought accepted in one capture, in bytes
+pub(crate) const MAX_CAPTURE_BYTES: usize = 64 * 1024;
+
+/// Shortcut used until the user picks another
+pub(crate) const DEFAULT_CAPTURE_SHORTCUT: &str = "CommandOrControl+Shift+Space";
+
+/// Horizontal gap between a captured root node and the rightmost node
+const CAPTURE_NODE_SPACING: f64 = 400.0;
+
+/// Where quick captures go
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub(crate) enum CaptureTarget {
+    /// A markdown note, relative to the notes directory; created if missing.
+    /// Captures are listed under a heading per day.
+    Note { path: String },
+    /// A project in one of the workspaces; each capture becomes a new root
+    /// node
+    Project { path: String },
+}
+
+impl Default for CaptureTarget {
+    fn default() -> Self {
+        Self::Note {
+            path: "Inbox.md".to_string(),
+        }
+    }
+}
+
+/// The captured text with surrounding whitespace removed; rejects empty and
+/// oversized captures
+pub(crate) fn validate_capture(text: &str) -> Result<&str, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("Nothing to capture".to_string());
+    }
+    if text.len() > MAX_CAPTURE_BYTES {
+        return Err(format!(
+            "Captured text is longer than {MAX_CAPTURE_BYTES} bytes"
+        ));
+    }
+    Ok(text)
+}
+
+/// `note` with `text` added as a timestamped list item under today's heading
+pub(crate) fn append_capture_to_note(note: &str, text: &str, now: DateTime<Local>) -> String {
+    let mut lines = text.lines();
+    let mut entry = format!(
+        "- {} {}",
+        now.format("%H:%M"),
+        lines.next().unwrap_or_default()
+    );
+    for line in lines {
+        entry.push_str("\n  ");
+        entry.push_str(line);
+    }
+    append_to_text(note, &entry, Some(&now.format("%Y-%m-%d").to_string()))
+}
+
+/// Add `text` to `project` as a user node without parents, placed to the
+/// right of the existing nodes. Returns the new node's ID.
+pub(crate) fn add_capture_node(
+    project: &mut ProjectFile,
+    text: &str,
+    now: DateTime<Local>,
+) -> String {
+    let id = uuid::Uuid::new_v4().to_string();
+    let graph = &mut project.graph;
+    let position = graph
+        .layout
+        .iter()
+        .map(|l| l.position)
+        .reduce(|a, b| if b.x > a.x { b } else { a })
+        .map(|rightmost| Position {
+            x: rightmost.x + CAPTURE_NODE_SPACING,
+            y: rightmost.y,
+        })
+        .unwrap_or_default();
+
+    graph.nodes.push(ProjectNode {
+        id: id.clone(),
+        role: "user".to_string(),
+        content: text.to_string(),
+        timestamp: now.timestamp_millis(),
+        content_updated_at: None,
+        summary: None,
+        summary_timestamp: None,
+        images: None,
+        provider: None,
+        model: None,
+        pinned_provider: None,
+        pinned_model: None,
+        extra: Default::default(),
+    });
+    graph.layout.push(LayoutEntry {
+        id: id.clone(),
+        position,
+    });
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2025, 3, 7, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_validate_capture() {
+        assert_eq!(validate_capture("  idea \n").unwrap(), "idea");
+        assert!(validate_capture(" \n\t").is_err());
+        assert!(validate_capture(&"a".repeat(MAX_CAPTURE_BYTES + 1)).is_err());
+    }
+
+    #[test]
+    fn test_append_capture_to_note_groups_by_day() {
+        let note = append_capture_to_note("# Inbox\n", "First", at(9, 5));
+        let note = append_capture_to_note(&note, "Second\nwith detail", at(10, 30));
+        assert_eq!(
+            note,
+            "# Inbox\n\n## 2025-03-07\n\n- 09:05 First\n\n- 10:30 Second\n  with detail\n"
+        );
+    }
+
+    #[test]
+    fn test_add_capture_node_places_root_right_of_others() {
+        let mut project = ProjectFile::default();
+        let first = add_capture_node(&mut project, "One", at(9, 0));
+        let second = add_capture_node(&mut project, "Two", at(9, 1));
+        let graph = &project.graph;
+        assert_eq!(graph.nodes.len(), 2);
+        assert_eq!(graph.roots().count(), 2);
+        assert_eq!(graph.node(&second).unwrap().content, "Two");
+        assert_eq!(graph.position(&first), Some(Position::default()));
+        assert_eq!(graph.position(&second).unwrap().x, CAPTURE_NODE_SPACING);
+    }
+}
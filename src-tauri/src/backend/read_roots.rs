@@ -0,0 +1,100 @@
+use std::path::{Path, PathBuf};
+
+/// Most extra read directories that can be configured
+pub(crate) const MAX_READ_ROOTS: usize = 20;
+
+/// Check user-chosen read directories and return them canonicalized. Each
+/// must be an existing absolute directory, and none may be a filesystem
+/// root or contain the home directory, which would open up credentials.
+pub(crate) fn validate_read_roots(
+    roots: &[String],
+    home: Option<&Path>,
+) -> Result<Vec<PathBuf>, String> {
+    if roots.len() > MAX_READ_ROOTS {
+        return Err(format!(
+            "At most {MAX_READ_ROOTS} read directories can be added"
+        ));
+    }
+    let home = home.and_then(|home| std::fs::canonicalize(home).ok());
+    let mut canonical: Vec<PathBuf> = Vec::with_capacity(roots.len());
+    for root in roots {
+        let path = Path::new(root);
+        if !path.is_absolute() {
+            return Err(format!("Read directory must be absolute: {root}"));
+        }
+        let resolved = std::fs::canonicalize(path)
+            .map_err(|e| format!("Failed to resolve read directory {root}: {e}"))?;
+        if !resolved.is_dir() {
+            return Err(format!("Read directory is not a directory: {root}"));
+        }
+        if resolved.parent().is_none() {
+            return Err("The filesystem root can't be a read directory".to_string());
+        }
+        if home
+            .as_ref()
+            .is_some_and(|home| home.starts_with(&resolved))
+        {
+            return Err(format!(
+                "Read directory {root} contains the home directory; choose a subfolder"
+            ));
+        }
+        if !canonical.contains(&resolved) {
+            canonical.push(resolved);
+        }
+    }
+    Ok(canonical)
+}
+
+/// Whether `path` (canonical) lies inside one of the read directories.
+/// They are resolved again, so a directory swapped for a symlink since it
+/// was configured is judged by where it points now.
+pub(crate) fn within_read_roots(path: &Path, roots: &[PathBuf]) -> bool {
+    roots.iter().any(|root| {
+        std::fs::canonicalize(root)
+            .is_ok_and(|root| root.parent().is_some() && path.starts_with(root))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_util::TempDir;
+
+    #[test]
+    fn test_validate_and_match_read_roots() {
+        let dir = TempDir::new("read-roots");
+        let papers = dir.join("papers");
+        let home = dir.join("home");
+        std::fs::create_dir_all(&papers).unwrap();
+        std::fs::create_dir_all(&home).unwrap();
+        std::fs::write(papers.join("paper.pdf"), "%PDF").unwrap();
+        let papers_str = papers.to_string_lossy().into_owned();
+
+        let roots =
+            validate_read_roots(&[papers_str.clone(), papers_str.clone()], Some(&home)).unwrap();
+        assert_eq!(roots, [std::fs::canonicalize(&papers).unwrap()]);
+
+        let pdf = std::fs::canonicalize(papers.join("paper.pdf")).unwrap();
+        assert!(within_read_roots(&pdf, &roots));
+        assert!(!within_read_roots(
+            &std::fs::canonicalize(&home).unwrap(),
+            &roots
+        ));
+        assert!(!within_read_roots(&pdf, &[]));
+
+        let invalid = [
+            "relative/papers".to_string(),
+            dir.join("missing").to_string_lossy().into_owned(),
+            papers.join("paper.pdf").to_string_lossy().into_owned(),
+            "/".to_string(),
+            // Contains the home directory
+            dir.to_string_lossy().into_owned(),
+        ];
+        for root in invalid {
+            assert!(
+                validate_read_roots(std::slice::from_ref(&root), Some(&home)).is_err(),
+                "{root}"
+            );
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+/// Subdirectory of the app data dir holding in-progress generations
+const RECOVERY_DIR: &str = "recovery";
+const RECOVERY_FILE_SUFFIX: &str = "partial";
+
+/// Answer text journaled per generation; the rest still streams, it just
+/// can't be recovered
+const MAX_JOURNAL_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Tells this run's journals apart from ones left behind by a crashed run
+static LAUNCH_ID: OnceLock<String> = OnceLock::new();
+
+fn launch_id() -> &'static str {
+    LAUNCH_ID.get_or_init(|| uuid::Uuid::new_v4().to_string())
+}
+
+/// First line of a journal file
+#[derive(Serialize, Deserialize)]
+struct JournalHeader {
+    node_id: String,
+    launch_id: String,
+    started_at: String,
+}
+
+/// Answer text of a generation the app didn't live to finish
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct RecoveredContent {
+    pub node_id: String,
+    /// RFC 3339 start of the generation
+    pub started_at: String,
+    pub content: String,
+}
+
+/// Directory of the recovery journals
+pub(crate) fn recovery_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {e}"))?;
+    Ok(dir.join(RECOVERY_DIR))
+}
+
+/// Streamed answer text of one generation, appended as it arrives. The file
+/// is deleted when the journal is dropped, so only a generation cut short by
+/// a crash leaves one behind.
+pub(crate) struct RecoveryJournal {
+    file: File,
+    path: PathBuf,
+    written: std::sync::Mutex<u64>,
+}
+
+impl RecoveryJournal {
+    /// Start a journal for `node_id` in `dir`. The file name is random so the
+    /// node ID (which comes from the frontend) never becomes part of a path.
+    pub(crate) fn start(dir: &Path, node_id: &str) -> Result<Self, String> {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create recovery directory: {e}"))?;
+        let path = dir.join(format!("{}.{RECOVERY_FILE_SUFFIX}", uuid::Uuid::new_v4()));
+        let mut file =
+            File::create(&path).map_err(|e| format!("Failed to create recovery journal: {e}"))?;
+        let header = JournalHeader {
+            node_id: node_id.to_string(),
+            launch_id: launch_id().to_string(),
+            started_at: chrono::Local::now().to_rfc3339(),
+        };
+        let header = serde_json::to_string(&header)
+            .map_err(|e| format!("Failed to encode recovery header: {e}"))?;
+        writeln!(file, "{header}").map_err(|e| format!("Failed to write recovery journal: {e}"))?;
+        Ok(Self {
+            file,
+            path,
+            written: std::sync::Mutex::new(0),
+        })
+    }
+
+    /// Append a chunk. Writes go straight to the OS, which keeps them even if
+    /// the app dies; failures are logged and otherwise ignored.
+    pub(crate) fn append(&self, chunk: &str) {
+        let mut written = self.written.lock().unwrap_or_else(|e| e.into_inner());
+        if *written + chunk.len() as u64 > MAX_JOURNAL_BYTES {
+            return;
+        }
+        match (&self.file).write_all(chunk.as_bytes()) {
+            Ok(()) => *written += chunk.len() as u64,
+            Err(e) => tracing::warn!("Failed to append to recovery journal: {}", e),
+        }
+    }
+}
+
+impl Drop for RecoveryJournal {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            tracing::warn!("Failed to remove recovery journal {:?}: {}", self.path, e);
+        }
+    }
+}
+
+fn journal_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == RECOVERY_FILE_SUFFIX))
+        .collect()
+}
+
+/// Header and content of a journal file; `None` if it is unreadable
+fn read_journal(path: &Path) -> Option<(JournalHeader, String)> {
+    let mut reader = BufReader::new(File::open(path).ok()?);
+    let mut header = String::new();
+    reader.read_line(&mut header).ok()?;
+    let header: JournalHeader = serde_json::from_str(&header).ok()?;
+    let mut content = Vec::new();
+    reader
+        .take(MAX_JOURNAL_BYTES)
+        .read_to_end(&mut content)
+        .ok()?;
+    Some((header, String::from_utf8_lossy(&content).into_owned()))
+}
+
+/// A recovered journal and the file it came from
+pub(crate) struct Recovered {
+    pub path: PathBuf,
+    pub content: RecoveredContent,
+}
+
+/// Content left by generations of earlier runs, oldest first. Journals of
+/// generations still running in this one are skipped; earlier ones that
+/// never got any text are deleted.
+pub(crate) fn pending_content(dir: &Path) -> Vec<Recovered> {
+    let mut recovered: Vec<Recovered> = journal_files(dir)
+        .into_iter()
+        .filter_map(|path| {
+            let (header, content) = read_journal(&path)?;
+            if header.launch_id == launch_id() {
+                return None;
+            }
+            if content.is_empty() {
+                let _ = std::fs::remove_file(&path);
+                return None;
+            }
+            Some(Recovered {
+                path,
+                content: RecoveredContent {
+                    node_id: header.node_id,
+                    started_at: header.started_at,
+                    content,
+                },
+            })
+        })
+        .collect();
+    recovered.sort_by(|a, b| a.content.started_at.cmp(&b.content.started_at));
+    recovered
+}
+
+/// Delete the journals of earlier runs for `node_ids`, once their content
+/// has been restored or declined
+pub(crate) fn dismiss(dir: &Path, node_ids: &[String]) -> Result<(), String> {
+    for recovered in pending_content(dir) {
+        if node_ids.contains(&recovered.content.node_id) {
+            std::fs::remove_file(&recovered.path)
+                .map_err(|e| format!("Failed to remove recovery journal: {e}"))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_util::TempDir;
+
+    #[test]
+    fn test_journal_is_removed_when_dropped_and_skipped_while_running() {
+        let dir = TempDir::new("recovery");
+        let journal = RecoveryJournal::start(&dir, "node-1").unwrap();
+        journal.append("Hello ");
+        journal.append("world");
+        assert_eq!(journal_files(&dir).len(), 1);
+        // Still running in this launch
+        assert!(pending_content(&dir).is_empty());
+
+        drop(journal);
+        assert!(journal_files(&dir).is_empty());
+    }
+
+    #[test]
+    fn test_pending_content_of_earlier_launch() {
+        let dir = TempDir::new("recovery");
+        std::fs::write(
+            dir.join("b.partial"),
+            "{\"node_id\":\"n2\",\"launch_id\":\"old\",\"started_at\":\"2025-03-07T10:00:00Z\"}\n\
+             Second answer",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("a.partial"),
+            "{\"node_id\":\"n1\",\"launch_id\":\"old\",\"started_at\":\"2025-03-07T09:00:00Z\"}\n\
+             First\nanswer",
+        )
+        .unwrap();
+        std::fs::write(dir.join("c.partial"), "not a header\n").unwrap();
+        let empty = dir.join("d.partial");
+        std::fs::write(
+            &empty,
+            "{\"node_id\":\"n3\",\"launch_id\":\"old\",\"started_at\":\"2025-03-07T11:00:00Z\"}\n",
+        )
+        .unwrap();
+
+        let pending = pending_content(&dir);
+        let contents: Vec<&RecoveredContent> = pending.iter().map(|r| &r.content).collect();
+        assert_eq!(contents.len(), 2);
+        assert_eq!(contents[0].node_id, "n1");
+        assert_eq!(contents[0].content, "First\nanswer");
+        assert_eq!(contents[1].content, "Second answer");
+        assert!(!empty.exists());
+
+        dismiss(&dir, &["n1".to_string()]).unwrap();
+        let left: Vec<String> = pending_content(&dir)
+            .into_iter()
+            .map(|r| r.content.node_id)
+            .collect();
+        assert_eq!(left, ["n2"]);
+    }
+}
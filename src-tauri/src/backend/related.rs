@@ -0,0 +1,246 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::backend::frontmatter::note_metadata;
+use crate::backend::project_file::{node_title, ProjectFile};
+use crate::backend::search::best_snippet;
+use crate::backend::search_index::tokenize;
+
+/// Matches scoring below this cosine similarity are not worth showing
+const MIN_SIMILARITY: f64 = 0.15;
+
+/// Nodes with fewer terms than this carry too little meaning to compare
+const MIN_NODE_TERMS: usize = 3;
+
+/// Terms of a node's content used to look up related notes; the most
+/// frequent are kept so long answers don't turn into huge queries
+const MAX_CONTEXT_TERMS: usize = 40;
+
+/// A node that discusses something like the text being compared
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub(crate) struct RelatedNode {
+    pub project_path: String,
+    pub node_id: String,
+    pub title: String,
+    /// Unix milliseconds the node was written
+    pub timestamp: i64,
+    /// Cosine similarity, 0.0 to 1.0
+    pub score: f64,
+}
+
+fn term_counts(text: &str) -> HashMap<String, f64> {
+    let mut counts = HashMap::new();
+    for term in tokenize(text) {
+        *counts.entry(term).or_default() += 1.0;
+    }
+    counts
+}
+
+/// Weight each term by tf-idf and scale the vector to unit length
+fn tf_idf(counts: &HashMap<String, f64>, idf: &HashMap<&str, f64>) -> HashMap<String, f64> {
+    let mut vector: HashMap<String, f64> = counts
+        .iter()
+        .map(|(term, count)| {
+            let weight = (1.0 + count.ln()) * idf.get(term.as_str()).copied().unwrap_or(0.0);
+            (term.clone(), weight)
+        })
+        .collect();
+    let norm = vector.values().map(|w| w * w).sum::<f64>().sqrt();
+    if norm > 0.0 {
+        vector.values_mut().for_each(|w| *w /= norm);
+    }
+    vector
+}
+
+/// Nodes of `projects` (path, project) most similar to `text` by tf-idf
+/// cosine similarity, best first. `exclude` is a `(path, node_id)` to leave
+/// out, normally the node the text comes from.
+pub(crate) fn find_related_nodes(
+    text: &str,
+    projects: &[(String, &ProjectFile)],
+    exclude: Option<(&str, &str)>,
+    limit: usize,
+) -> Vec<RelatedNode> {
+    let query_counts = term_counts(text);
+    if query_counts.is_empty() {
+        return Vec::new();
+    }
+
+    let candidates: Vec<(&str, _, HashMap<String, f64>)> = projects
+        .iter()
+        .flat_map(|(path, project)| {
+            project
+                .graph
+                .nodes
+                .iter()
+                .map(move |node| (path.as_str(), node))
+        })
+        .filter(|(path, node)| exclude != Some((path, node.id.as_str())))
+        .map(|(path, node)| (path, node, term_counts(&node.content)))
+        .filter(|(_, _, counts)| counts.len() >= MIN_NODE_TERMS)
+        .collect();
+
+    let mut document_frequency: HashMap<&str, usize> = HashMap::new();
+    for (_, _, counts) in &candidates {
+        for term in counts.keys() {
+            *document_frequency.entry(term).or_default() += 1;
+        }
+    }
+    let total = candidates.len() as f64;
+    let idf: HashMap<&str, f64> = document_frequency
+        .into_iter()
+        .map(|(term, df)| (term, (1.0 + total / df as f64).ln()))
+        .collect();
+
+    let query = tf_idf(&query_counts, &idf);
+    let mut related: Vec<RelatedNode> = candidates
+        .iter()
+        .filter_map(|(path, node, counts)| {
+            let vector = tf_idf(counts, &idf);
+            let score: f64 = query
+                .iter()
+                .filter_map(|(term, weight)| vector.get(term).map(|w| w * weight))
+                .sum();
+            (score >= MIN_SIMILARITY).then(|| RelatedNode {
+                project_path: path.to_string(),
+                node_id: node.id.clone(),
+                title: node_title(node),
+                timestamp: node.timestamp,
+                score,
+            })
+        })
+        .collect();
+    related.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    related.truncate(limit);
+    related
+}
+
+/// A note suggested as context for a node
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub(crate) struct NoteSuggestion {
+    /// Path relative to the notes directory
+    pub path: String,
+    pub title: String,
+    pub score: f64,
+    /// The note line sharing the most terms with the node
+    pub snippet: Option<String>,
+}
+
+/// The most frequent terms of `content` (longer terms first on ties), as a
+/// search index query
+pub(crate) fn context_query(content: &str) -> String {
+    let mut terms: Vec<(String, f64)> = term_counts(content).into_iter().collect();
+    terms.sort_by(|(a, a_count), (b, b_count)| {
+        b_count
+            .partial_cmp(a_count)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| b.len().cmp(&a.len()))
+            .then_with(|| a.cmp(b))
+    });
+    terms
+        .into_iter()
+        .take(MAX_CONTEXT_TERMS)
+        .map(|(term, _)| term)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Describe a ranked note: its title and the snippet that best matches
+/// `query` (from `context_query`)
+pub(crate) fn note_suggestion(
+    path: &str,
+    fallback_title: &str,
+    text: &str,
+    query: &str,
+    score: f64,
+) -> NoteSuggestion {
+    let terms: HashSet<String> = tokenize(query).collect();
+    NoteSuggestion {
+        path: path.to_string(),
+        title: note_metadata(text, fallback_title).title,
+        score,
+        snippet: best_snippet(text, &terms),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(nodes: &[(&str, &str)]) -> ProjectFile {
+        let mut project = ProjectFile::default();
+        project.graph.nodes = nodes
+            .iter()
+            .map(|(id, content)| {
+                serde_json::from_value(serde_json::json!({
+                    "id": id,
+                    "role": "user",
+                    "content": content,
+                }))
+                .unwrap()
+            })
+            .collect();
+        project
+    }
+
+    #[test]
+    fn test_find_related_nodes_ranks_across_projects() {
+        let current = project(&[
+            ("a", "How do sourdough starters ferment flour and water?"),
+            ("b", "Which tent is best for alpine camping?"),
+        ]);
+        let older = project(&[
+            (
+                "c",
+                "Feeding a sourdough starter: flour, water and wild yeast ferment",
+            ),
+            ("d", "Short"),
+            ("e", "Budget spreadsheet for the renovation project"),
+        ]);
+        let projects = [
+            ("/notes/now.thoughttree".to_string(), &current),
+            ("/notes/old.thoughttree".to_string(), &older),
+        ];
+
+        let related = find_related_nodes(
+            "Why does my sourdough starter ferment so slowly?",
+            &projects,
+            Some(("/notes/now.thoughttree", "a")),
+            10,
+        );
+        assert_eq!(related.len(), 1);
+        assert_eq!(related[0].node_id, "c");
+        assert_eq!(related[0].project_path, "/notes/old.thoughttree");
+        assert!(related[0].score > MIN_SIMILARITY && related[0].score <= 1.0);
+
+        assert!(find_related_nodes("", &projects, None, 10).is_empty());
+    }
+
+    #[test]
+    fn test_context_query_keeps_frequent_terms() {
+        let content = "Rust borrow checker: the borrow checker rejects this borrow. A";
+        let query = context_query(content);
+        assert!(query.starts_with("borrow checker "));
+        assert!(!query.split(' ').any(|t| t == "a"));
+
+        let long: String = (0..100).map(|i| format!("term{i} ")).collect();
+        assert_eq!(context_query(&long).split(' ').count(), MAX_CONTEXT_TERMS);
+        assert_eq!(context_query("  "), "");
+    }
+
+    #[test]
+    fn test_note_suggestion_uses_title_and_snippet() {
+        let text = "# Bread\nIntro\nMy sourdough starter schedule";
+        let suggestion = note_suggestion("food/bread.md", "bread", text, "sourdough starter", 1.5);
+        assert_eq!(suggestion.title, "Bread");
+        assert_eq!(
+            suggestion.snippet.as_deref(),
+            Some("My sourdough starter schedule")
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, MutexGuard};
+
+use crate::backend::types::ChunkPayload;
+
+/// Chunks kept per node for replay; older ones are dropped first
+const MAX_REPLAY_CHUNKS_PER_NODE: usize = 2000;
+
+/// Nodes with replay buffers; the least recently streamed is evicted first
+const MAX_REPLAY_NODES: usize = 32;
+
+#[derive(Default)]
+struct NodeReplay {
+    next_seq: u64,
+    /// Bumped on every write so eviction can find the stalest node
+    last_touched: u64,
+    chunks: VecDeque<(u64, String)>,
+}
+
+#[derive(Default)]
+struct ReplayState {
+    clock: u64,
+    nodes: HashMap<String, NodeReplay>,
+}
+
+/// Per-node ring buffer of recently emitted `stream-chunk` events, so a
+/// reloaded webview can catch up on a generation that is still running
+#[derive(Default)]
+pub(crate) struct StreamReplay {
+    state: Mutex<ReplayState>,
+}
+
+impl StreamReplay {
+    fn lock(&self) -> MutexGuard<'_, ReplayState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Forget a node's buffered chunks when a new generation starts. Sequence
+    /// numbers keep increasing so stale `from_seq` values never match.
+    pub(crate) fn start_generation(&self, node_id: &str) {
+        if let Some(node) = self.lock().nodes.get_mut(node_id) {
+            node.chunks.clear();
+        }
+    }
+
+    /// Assign the next sequence number to `chunk` and buffer it
+    pub(crate) fn record(&self, node_id: &str, chunk: &str) -> ChunkPayload {
+        let mut state = self.lock();
+        state.clock += 1;
+        let clock = state.clock;
+
+        if !state.nodes.contains_key(node_id) && state.nodes.len() >= MAX_REPLAY_NODES {
+            let stalest = state
+                .nodes
+                .iter()
+                .min_by_key(|(_, node)| node.last_touched)
+                .map(|(id, _)| id.clone());
+            if let Some(stalest) = stalest {
+                state.nodes.remove(&stalest);
+            }
+        }
+
+        let node = state.nodes.entry(node_id.to_string()).or_default();
+        let seq = node.next_seq;
+        node.next_seq += 1;
+        node.last_touched = clock;
+        if node.chunks.len() == MAX_REPLAY_CHUNKS_PER_NODE {
+            node.chunks.pop_front();
+        }
+        node.chunks.push_back((seq, chunk.to_string()));
+
+        ChunkPayload {
+            node_id: node_id.to_string(),
+            seq,
+            chunk: chunk.to_string(),
+        }
+    }
+
+    /// Buffered chunks for `node_id` with a sequence number of at least `from_seq`
+    pub(crate) fn replay(&self, node_id: &str, from_seq: u64) -> Vec<ChunkPayload> {
+        self.lock()
+            .nodes
+            .get(node_id)
+            .map(|node| {
+                node.chunks
+                    .iter()
+                    .filter(|(seq, _)| *seq >= from_seq)
+                    .map(|(seq, chunk)| ChunkPayload {
+                        node_id: node_id.to_string(),
+                        seq: *seq,
+                        chunk: chunk.clone(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay_returns_chunks_from_sequence() {
+        let replay = StreamReplay::default();
+        assert_eq!(replay.record("n", "a").seq, 0);
+        assert_eq!(replay.record("n", "b").seq, 1);
+        assert_eq!(replay.record("other", "x").seq, 0);
+
+        let chunks: Vec<_> = replay.replay("n", 1).into_iter().map(|c| c.chunk).collect();
+        assert_eq!(chunks, vec!["b"]);
+        assert!(replay.replay("missing", 0).is_empty());
+    }
+
+    #[test]
+    fn test_new_generation_clears_chunks_but_keeps_sequence() {
+        let replay = StreamReplay::default();
+        replay.record("n", "old");
+        replay.start_generation("n");
+
+        assert!(replay.replay("n", 0).is_empty());
+        assert_eq!(replay.record("n", "new").seq, 1);
+    }
+
+    #[test]
+    fn test_evicts_stalest_node() {
+        let replay = StreamReplay::default();
+        for i in 0..=MAX_REPLAY_NODES {
+            replay.record(&format!("node-{i}"), "chunk");
+        }
+        assert!(replay.replay("node-0", 0).is_empty());
+        assert_eq!(
+            replay.replay(&format!("node-{MAX_REPLAY_NODES}"), 0).len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// Retries of a prompt after transient errors, unless configured otherwise
+pub(crate) const DEFAULT_MAX_RETRIES: u32 = 3;
+
+/// Highest retry limit that can be configured
+pub(crate) const MAX_RETRIES_LIMIT: u32 = 10;
+
+/// Wait before the first retry; each further retry waits twice as long
+const BASE_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// A kind of error that tends to pass if the prompt is sent again later
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TransientError {
+    /// The provider's rate limit or quota for the moment (HTTP 429)
+    RateLimited,
+    /// The provider's API is overloaded or briefly unavailable (HTTP 529/503)
+    Overloaded,
+}
+
+/// Why a prompt is sent again, reported with `generation-retrying`
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum RetryReason {
+    RateLimited,
+    Overloaded,
+    /// The agent crashed before answering
+    Crashed,
+}
+
+impl From<TransientError> for RetryReason {
+    fn from(error: TransientError) -> Self {
+        match error {
+            TransientError::RateLimited => Self::RateLimited,
+            TransientError::Overloaded => Self::Overloaded,
+        }
+    }
+}
+
+/// Whether `text` holds the status `code` as a number of its own, not as
+/// part of a longer one
+fn contains_status(text: &str, code: &str) -> bool {
+    text.match_indices(code).any(|(idx, _)| {
+        let before = text[..idx].chars().next_back();
+        let after = text[idx + code.len()..].chars().next();
+        !before.is_some_and(|c| c.is_ascii_digit()) && !after.is_some_and(|c| c.is_ascii_digit())
+    })
+}
+
+/// The transient error an agent's error message describes, if any
+pub(crate) fn classify_transient(message: &str) -> Option<TransientError> {
+    let lower = message.to_lowercase();
+    let rate_limited = [
+        "rate limit",
+        "rate_limit",
+        "too many requests",
+        "resource_exhausted",
+    ]
+    .iter()
+    .any(|pattern| lower.contains(pattern))
+        || contains_status(&lower, "429");
+    if rate_limited {
+        return Some(TransientError::RateLimited);
+    }
+    let overloaded = [
+        "overloaded",
+        "service unavailable",
+        "temporarily unavailable",
+    ]
+    .iter()
+    .any(|pattern| lower.contains(pattern))
+        || contains_status(&lower, "529")
+        || contains_status(&lower, "503");
+    overloaded.then_some(TransientError::Overloaded)
+}
+
+/// How long to wait before retry number `attempt` (from 1): 2s, 4s, 8s, ...
+/// up to a minute
+pub(crate) fn backoff_delay(attempt: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+    BASE_RETRY_DELAY.saturating_mul(factor).min(MAX_RETRY_DELAY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_transient() {
+        let cases = [
+            (
+                r#"Failed to send prompt: Error { code: -32603, message: "API Error: 529 {\"type\":\"overloaded_error\"}" }"#,
+                Some(TransientError::Overloaded),
+            ),
+            (
+                "API Error: 429 Too Many Requests",
+                Some(TransientError::RateLimited),
+            ),
+            (
+                "[GoogleGenerativeAI Error]: RESOURCE_EXHAUSTED: quota exceeded",
+                Some(TransientError::RateLimited),
+            ),
+            ("503 Service Unavailable", Some(TransientError::Overloaded)),
+            ("Failed to send prompt: invalid model", None),
+            // Numbers that merely contain a status code
+            ("Prompt is 15290 tokens, context 4290", None),
+        ];
+        for (message, expected) in cases {
+            assert_eq!(classify_transient(message), expected, "{message}");
+        }
+    }
+
+    #[test]
+    fn test_backoff_delay_doubles_up_to_a_minute() {
+        assert_eq!(backoff_delay(1), Duration::from_secs(2));
+        assert_eq!(backoff_delay(3), Duration::from_secs(8));
+        assert_eq!(backoff_delay(6), MAX_RETRY_DELAY);
+        assert_eq!(backoff_delay(40), MAX_RETRY_DELAY);
+    }
+}
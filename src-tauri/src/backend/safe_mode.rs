@@ -0,0 +1,37 @@
+use std::sync::atomic::Ordering;
+
+use tauri::{AppHandle, Manager};
+use tracing::warn;
+
+use crate::backend::config;
+use crate::backend::state::AppState;
+
+/// Command-line flag that starts the app in safe mode for this launch only
+pub(crate) const SAFE_MODE_FLAG: &str = "--safe-mode";
+
+/// Resolve safe mode at startup from the launch flag or the persisted setting.
+/// Safe mode loads only core project I/O: no background jobs, watchers, warm
+/// spawns, custom provider paths, tray icon or global shortcut.
+pub(crate) fn init(app: &AppHandle) {
+    let from_args = std::env::args().any(|arg| arg == SAFE_MODE_FLAG);
+    let persisted = config::get_safe_mode(app).unwrap_or_else(|e| {
+        warn!("Failed to read safe mode setting: {}", e);
+        false
+    });
+
+    let enabled = from_args || persisted;
+    if enabled {
+        warn!(
+            "Starting in safe mode ({}): background jobs, watchers, custom provider paths and the global shortcut are disabled",
+            if from_args { SAFE_MODE_FLAG } else { "settings" }
+        );
+    }
+    app.state::<AppState>()
+        .safe_mode
+        .store(enabled, Ordering::SeqCst);
+}
+
+/// Whether the app is currently running in safe mode
+pub(crate) fn is_enabled(app: &AppHandle) -> bool {
+    app.state::<AppState>().safe_mode.load(Ordering::SeqCst)
+}
@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// How often the scheduler looks for due jobs
+pub(crate) const SCHEDULER_TICK: Duration = Duration::from_secs(60);
+
+/// Longest configurable interval: a week
+const MAX_INTERVAL_MINS: u64 = 7 * 24 * 60;
+
+const MINUTE_MS: i64 = 60 * 1000;
+
+/// Maintenance work run in the background
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum BackgroundJob {
+    /// Bring the notes search index up to date
+    SearchIndex,
+    /// Title nodes of recent, closed projects that have no summary
+    SummaryBackfill,
+    /// Delete node history files whose project is gone
+    HistoryPruning,
+    /// Drop recent projects that no longer exist
+    RecentProjectsCleanup,
+}
+
+impl BackgroundJob {
+    pub(crate) const ALL: [Self; 4] = [
+        Self::SearchIndex,
+        Self::SummaryBackfill,
+        Self::HistoryPruning,
+        Self::RecentProjectsCleanup,
+    ];
+
+    /// Summary backfill spends model usage, so it is off until enabled
+    fn default_interval_mins(self) -> u64 {
+        match self {
+            Self::SearchIndex => 5,
+            Self::SummaryBackfill => 0,
+            Self::HistoryPruning | Self::RecentProjectsCleanup => 24 * 60,
+        }
+    }
+}
+
+/// Minutes between runs per job, 0 to turn a job off. Jobs not listed run
+/// at their default interval.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct BackgroundJobSettings {
+    #[serde(default)]
+    pub interval_mins: HashMap<BackgroundJob, u64>,
+}
+
+impl BackgroundJobSettings {
+    pub(crate) fn interval_mins(&self, job: BackgroundJob) -> u64 {
+        self.interval_mins
+            .get(&job)
+            .copied()
+            .unwrap_or_else(|| job.default_interval_mins())
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        match self
+            .interval_mins
+            .values()
+            .find(|m| **m > MAX_INTERVAL_MINS)
+        {
+            Some(mins) => Err(format!(
+                "Job interval must be at most {MAX_INTERVAL_MINS} minutes (got {mins})"
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// What `get_background_jobs` reports per job. Times are Unix milliseconds.
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct BackgroundJobStatus {
+    pub job: BackgroundJob,
+    /// 0 when the job is off
+    pub interval_mins: u64,
+    pub running: bool,
+    pub last_run_ms: Option<i64>,
+    pub last_duration_ms: Option<u64>,
+    /// Short description of what the last successful run did
+    pub last_result: Option<String>,
+    pub last_error: Option<String>,
+    /// `None` when the job is off
+    pub next_run_ms: Option<i64>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct JobRecord {
+    running: bool,
+    last_run_ms: Option<i64>,
+    last_duration_ms: Option<u64>,
+    last_result: Option<String>,
+    last_error: Option<String>,
+}
+
+/// Tracks when each background job last ran. A job runs one interval after
+/// launch, then one interval after its previous run started, and never
+/// overlaps itself.
+pub(crate) struct JobScheduler {
+    started_ms: i64,
+    jobs: Mutex<HashMap<BackgroundJob, JobRecord>>,
+}
+
+impl Default for JobScheduler {
+    fn default() -> Self {
+        Self::new(chrono::Utc::now().timestamp_millis())
+    }
+}
+
+impl JobScheduler {
+    pub(crate) fn new(started_ms: i64) -> Self {
+        Self {
+            started_ms,
+            jobs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn jobs(&self) -> MutexGuard<'_, HashMap<BackgroundJob, JobRecord>> {
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn next_run_ms(&self, record: &JobRecord, interval_mins: u64) -> Option<i64> {
+        let interval_ms = i64::try_from(interval_mins).ok()?.checked_mul(MINUTE_MS)?;
+        (interval_ms > 0).then(|| record.last_run_ms.unwrap_or(self.started_ms) + interval_ms)
+    }
+
+    /// Jobs due at `now_ms`, marked as running
+    pub(crate) fn take_due(
+        &self,
+        settings: &BackgroundJobSettings,
+        now_ms: i64,
+    ) -> Vec<BackgroundJob> {
+        let mut jobs = self.jobs();
+        BackgroundJob::ALL
+            .into_iter()
+            .filter(|job| {
+                let record = jobs.entry(*job).or_default();
+                let due = !record.running
+                    && self
+                        .next_run_ms(record, settings.interval_mins(*job))
+                        .is_some_and(|next| now_ms >= next);
+                if due {
+                    record.running = true;
+                    record.last_run_ms = Some(now_ms);
+                }
+                due
+            })
+            .collect()
+    }
+
+    /// Record the outcome of a run started by `take_due`
+    pub(crate) fn finish(&self, job: BackgroundJob, now_ms: i64, outcome: Result<String, String>) {
+        let mut jobs = self.jobs();
+        let record = jobs.entry(job).or_default();
+        record.running = false;
+        record.last_duration_ms = record
+            .last_run_ms
+            .map(|started| u64::try_from(now_ms - started).unwrap_or(0));
+        match outcome {
+            Ok(result) => {
+                record.last_result = Some(result);
+                record.last_error = None;
+            }
+            Err(e) => record.last_error = Some(e),
+        }
+    }
+
+    pub(crate) fn statuses(&self, settings: &BackgroundJobSettings) -> Vec<BackgroundJobStatus> {
+        let jobs = self.jobs();
+        BackgroundJob::ALL
+            .into_iter()
+            .map(|job| {
+                let record = jobs.get(&job).cloned().unwrap_or_default();
+                let interval_mins = settings.interval_mins(job);
+                BackgroundJobStatus {
+                    job,
+                    interval_mins,
+                    running: record.running,
+                    last_run_ms: record.last_run_ms,
+                    last_duration_ms: record.last_duration_ms,
+                    next_run_ms: if record.running {
+                        None
+                    } else {
+                        self.next_run_ms(&record, interval_mins)
+                    },
+                    last_result: record.last_result,
+                    last_error: record.last_error,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_due_follows_intervals_without_overlap() {
+        let scheduler = JobScheduler::new(0);
+        let settings = BackgroundJobSettings {
+            interval_mins: HashMap::from([(BackgroundJob::HistoryPruning, 0)]),
+        };
+
+        assert!(scheduler.take_due(&settings, 4 * MINUTE_MS).is_empty());
+        assert_eq!(
+            scheduler.take_due(&settings, 5 * MINUTE_MS),
+            [BackgroundJob::SearchIndex]
+        );
+        // Still running: not started again
+        assert!(scheduler.take_due(&settings, 11 * MINUTE_MS).is_empty());
+
+        scheduler.finish(
+            BackgroundJob::SearchIndex,
+            6 * MINUTE_MS,
+            Ok("3 indexed".to_string()),
+        );
+        assert!(scheduler.take_due(&settings, 9 * MINUTE_MS).is_empty());
+        assert_eq!(
+            scheduler.take_due(&settings, 10 * MINUTE_MS),
+            [BackgroundJob::SearchIndex]
+        );
+
+        let day = 24 * 60 * MINUTE_MS;
+        scheduler.finish(BackgroundJob::SearchIndex, day, Err("failed".to_string()));
+        let due = scheduler.take_due(&settings, day);
+        assert!(due.contains(&BackgroundJob::RecentProjectsCleanup));
+        assert!(!due.contains(&BackgroundJob::HistoryPruning));
+        assert!(!due.contains(&BackgroundJob::SummaryBackfill));
+    }
+
+    #[test]
+    fn test_statuses_report_last_run() {
+        let scheduler = JobScheduler::new(0);
+        let settings = BackgroundJobSettings::default();
+        scheduler.take_due(&settings, 5 * MINUTE_MS);
+        scheduler.finish(
+            BackgroundJob::SearchIndex,
+            5 * MINUTE_MS + 250,
+            Err("disk full".into()),
+        );
+
+        let statuses = scheduler.statuses(&settings);
+        let index = &statuses[0];
+        assert_eq!(index.job, BackgroundJob::SearchIndex);
+        assert_eq!(index.last_duration_ms, Some(250));
+        assert_eq!(index.last_error.as_deref(), Some("disk full"));
+        assert_eq!(index.next_run_ms, Some(10 * MINUTE_MS));
+        let backfill = &statuses[1];
+        assert_eq!((backfill.interval_mins, backfill.next_run_ms), (0, None));
+    }
+
+    #[test]
+    fn test_settings_round_trip_and_validate() {
+        let settings: BackgroundJobSettings =
+            serde_json::from_value(serde_json::json!({"interval_mins": {"summary-backfill": 60}}))
+                .unwrap();
+        assert_eq!(settings.interval_mins(BackgroundJob::SummaryBackfill), 60);
+        assert_eq!(settings.interval_mins(BackgroundJob::SearchIndex), 5);
+        assert!(settings.validate().is_ok());
+
+        let too_long = BackgroundJobSettings {
+            interval_mins: HashMap::from([(BackgroundJob::SearchIndex, MAX_INTERVAL_MINS + 1)]),
+        };
+        assert!(too_long.validate().is_err());
+    }
+}
@@ -0,0 +1,507 @@
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::Path;
+
+use serde::Serialize;
+use walkdir::WalkDir;
+
+use crate::backend::frontmatter::split_frontmatter;
+use crate::backend::fuzzy::fuzzy_match;
+use crate::backend::project_file::{find_projects, node_title, read_project_data, ProjectFile};
+use crate::backend::search_index::tokenize;
+
+/// Files larger than this are skipped by content search
+const MAX_SEARCH_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Project files larger than this are skipped by project search; embedded
+/// images make them much bigger than notes
+const MAX_SEARCH_PROJECT_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Characters of context kept before a match in a snippet
+const SNIPPET_LEAD_CHARS: usize = 60;
+
+/// Maximum snippet length in characters
+const MAX_SNIPPET_CHARS: usize = 200;
+
+/// Bytes read from the start of a file to build its preview
+const PREVIEW_READ_BYTES: u64 = 16 * 1024;
+
+/// Upper bound on preview lines per file
+pub(crate) const MAX_PREVIEW_LINES: usize = 20;
+
+/// A line in a note that contains the search query
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct ContentMatch {
+    /// Path relative to the notes directory
+    pub path: String,
+    /// 1-based line number
+    pub line_number: usize,
+    /// 1-based char column of the first match in the line
+    pub column: usize,
+    pub snippet: String,
+    /// `[start, end)` char ranges in `snippet` of each match, for highlighting
+    pub highlights: Vec<(usize, usize)>,
+    /// Matching lines in the whole file, including any past the result limit
+    pub file_match_count: usize,
+}
+
+/// A node in a saved project whose text contains the search query
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct ProjectMatch {
+    /// Project path relative to the notes directory
+    pub path: String,
+    pub node_id: String,
+    pub role: String,
+    /// Node summary or first line, to label the hit
+    pub title: String,
+    pub snippet: String,
+}
+
+/// A file whose path fuzzy-matches the search query
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct FileMatch {
+    /// Path relative to the notes directory
+    pub path: String,
+    pub score: i64,
+    /// Char indices into `path` of the matched characters, for highlighting
+    pub indices: Vec<usize>,
+    /// First lines of the note body, when previews were requested
+    pub preview: Option<String>,
+}
+
+/// Cut `line` down to a snippet around the match at char index `match_char`
+fn snippet_around(line: &str, match_char: usize) -> String {
+    snippet_window(line, match_char).0
+}
+
+/// `snippet_around`, also returning the char range of `line` the snippet
+/// shows and how many chars (an ellipsis) precede it in the snippet
+fn snippet_window(line: &str, match_char: usize) -> (String, Range<usize>, usize) {
+    let trimmed_start = line.len() - line.trim_start().len();
+    let leading_chars = line[..trimmed_start].chars().count();
+    let match_char = match_char.saturating_sub(leading_chars);
+    let line = line.trim();
+
+    let start = match_char.saturating_sub(SNIPPET_LEAD_CHARS);
+    let total = line.chars().count();
+    let mut snippet: String = line.chars().skip(start).take(MAX_SNIPPET_CHARS).collect();
+    let shown = start.min(total)..(start + MAX_SNIPPET_CHARS).min(total);
+    let lead = usize::from(start > 0);
+    if start > 0 {
+        snippet.insert(0, '…');
+    }
+    if start + MAX_SNIPPET_CHARS < total {
+        snippet.push('…');
+    }
+    let shown = shown.start + leading_chars..shown.end + leading_chars;
+    (snippet, shown, lead)
+}
+
+/// Case-insensitive matches of `query` in one file's text, one per matching
+/// line with every match in it highlighted
+pub(crate) fn matches_in_text(rel_path: &str, text: &str, query_lower: &str) -> Vec<ContentMatch> {
+    let query_chars = query_lower.chars().count();
+    let mut matches: Vec<ContentMatch> = text
+        .lines()
+        .enumerate()
+        .filter_map(|(idx, line)| {
+            let line_lower = line.to_lowercase();
+            let starts: Vec<usize> = line_lower
+                .match_indices(query_lower)
+                .map(|(byte_idx, _)| line_lower[..byte_idx].chars().count())
+                .collect();
+            let match_char = *starts.first()?;
+            let (snippet, shown, lead) = snippet_window(line, match_char);
+            let highlights = starts
+                .iter()
+                .filter_map(|&start| {
+                    let start_in = start.max(shown.start);
+                    let end_in = (start + query_chars).min(shown.end);
+                    (start_in < end_in)
+                        .then(|| (start_in - shown.start + lead, end_in - shown.start + lead))
+                })
+                .collect();
+            Some(ContentMatch {
+                path: rel_path.to_string(),
+                line_number: idx + 1,
+                column: match_char + 1,
+                snippet,
+                highlights,
+                file_match_count: 0,
+            })
+        })
+        .collect();
+    let count = matches.len();
+    for found in &mut matches {
+        found.file_match_count = count;
+    }
+    matches
+}
+
+/// Snippet of the line of a note body holding the most distinct `terms`
+/// (lowercased, as from `tokenize`), cut around the first of them
+pub(crate) fn best_snippet(text: &str, terms: &HashSet<String>) -> Option<String> {
+    let (_, body) = split_frontmatter(text);
+    let (_, line, term) = body
+        .lines()
+        .filter_map(|line| {
+            let found: HashSet<String> = tokenize(line).filter(|t| terms.contains(t)).collect();
+            let first = tokenize(line).find(|t| terms.contains(t))?;
+            Some((found.len(), line, first))
+        })
+        // First line wins a tie
+        .fold(
+            None,
+            |best: Option<(usize, &str, String)>, candidate| match best {
+                Some(best) if best.0 >= candidate.0 => Some(best),
+                _ => Some(candidate),
+            },
+        )?;
+    let line_lower = line.to_lowercase();
+    let match_char = line_lower
+        .find(&term)
+        .map_or(0, |idx| line_lower[..idx].chars().count());
+    Some(snippet_around(line, match_char))
+}
+
+/// The first `max_lines` lines of a note body, skipping frontmatter and
+/// leading blank lines; each line is capped at the snippet length
+pub(crate) fn preview_text(text: &str, max_lines: usize) -> Option<String> {
+    let (_, body) = split_frontmatter(text);
+    let preview = body
+        .lines()
+        .skip_while(|line| line.trim().is_empty())
+        .take(max_lines.min(MAX_PREVIEW_LINES))
+        .map(|line| line.chars().take(MAX_SNIPPET_CHARS).collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let preview = preview.trim_end();
+    (!preview.is_empty()).then(|| preview.to_string())
+}
+
+/// Preview of a text file from its first few KB; binary files yield `None`
+fn read_preview(path: &Path, max_lines: usize) -> Option<String> {
+    use std::io::Read;
+
+    let mut data = Vec::new();
+    std::fs::File::open(path)
+        .ok()?
+        .take(PREVIEW_READ_BYTES)
+        .read_to_end(&mut data)
+        .ok()?;
+    let text = match std::str::from_utf8(&data) {
+        Ok(text) => text,
+        // A character cut at the read limit is fine; anything else is binary
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&data[..e.valid_up_to()]).ok()?,
+        Err(_) => return None,
+    };
+    preview_text(text, max_lines)
+}
+
+/// Fuzzy-search file paths under `notes_directory`, best matches first. Ties
+/// go to the shorter path. An empty query lists files in directory order.
+/// With `preview_lines > 0`, each hit carries the start of its note.
+pub(crate) fn search_file_names(
+    notes_directory: &Path,
+    query: &str,
+    limit: usize,
+    preview_lines: usize,
+) -> Vec<FileMatch> {
+    let mut results = Vec::new();
+    for entry in WalkDir::new(notes_directory)
+        .follow_links(false)
+        .max_depth(20)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+    {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let rel_path = match entry.path().strip_prefix(notes_directory) {
+            Ok(path) => path.to_string_lossy().to_string(),
+            Err(_) => continue,
+        };
+
+        if query.trim().is_empty() {
+            results.push(FileMatch {
+                path: rel_path,
+                score: 0,
+                indices: Vec::new(),
+                preview: None,
+            });
+            if results.len() >= limit {
+                break;
+            }
+        } else if let Some(found) = fuzzy_match(&rel_path, query) {
+            results.push(FileMatch {
+                path: rel_path,
+                score: found.score,
+                indices: found.indices,
+                preview: None,
+            });
+        }
+    }
+
+    results.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| a.path.len().cmp(&b.path.len()))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    results.truncate(limit);
+
+    if preview_lines > 0 {
+        for result in &mut results {
+            result.preview = read_preview(&notes_directory.join(&result.path), preview_lines);
+        }
+    }
+    results
+}
+
+/// Search the contents of text files under `notes_directory`. Like
+/// `search_files`, symlinks are not followed so results stay inside the notes
+/// directory; binary and very large files are skipped.
+pub(crate) fn search_note_contents(
+    notes_directory: &Path,
+    query: &str,
+    limit: usize,
+) -> Vec<ContentMatch> {
+    let query_lower = query.to_lowercase();
+    if query_lower.trim().is_empty() || limit == 0 {
+        return Vec::new();
+    }
+
+    let mut results = Vec::new();
+    for entry in WalkDir::new(notes_directory)
+        .follow_links(false)
+        .max_depth(20)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+    {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        if !entry
+            .metadata()
+            .is_ok_and(|m| m.len() <= MAX_SEARCH_FILE_BYTES)
+        {
+            continue;
+        }
+        let rel_path = match entry.path().strip_prefix(notes_directory) {
+            Ok(path) => path.to_string_lossy().to_string(),
+            Err(_) => continue,
+        };
+        // Non-UTF-8 content is treated as binary
+        let Ok(text) = std::fs::read_to_string(entry.path()) else {
+            continue;
+        };
+
+        for found in matches_in_text(&rel_path, &text, &query_lower) {
+            results.push(found);
+            if results.len() >= limit {
+                return results;
+            }
+        }
+    }
+
+    results
+}
+
+/// Case-insensitive matches of `query` in a project's nodes, one per node
+/// at its first matching line. Summaries count when the content has none.
+pub(crate) fn matches_in_project(
+    rel_path: &str,
+    project: &ProjectFile,
+    query_lower: &str,
+) -> Vec<ProjectMatch> {
+    project
+        .graph
+        .nodes
+        .iter()
+        .filter_map(|node| {
+            let found = matches_in_text(rel_path, &node.content, query_lower)
+                .into_iter()
+                .chain(
+                    node.summary
+                        .as_deref()
+                        .into_iter()
+                        .flat_map(|summary| matches_in_text(rel_path, summary, query_lower)),
+                )
+                .next()?;
+            Some(ProjectMatch {
+                path: rel_path.to_string(),
+                node_id: node.id.clone(),
+                role: node.role.clone(),
+                title: node_title(node),
+                snippet: found.snippet,
+            })
+        })
+        .collect()
+}
+
+/// Search node text in the `.thoughttree` projects under `notes_directory`,
+/// skipping hidden directories. Projects that fail to parse are skipped.
+pub(crate) fn search_projects(
+    notes_directory: &Path,
+    query: &str,
+    limit: usize,
+) -> Vec<ProjectMatch> {
+    let query_lower = query.to_lowercase();
+    if query_lower.trim().is_empty() || limit == 0 {
+        return Vec::new();
+    }
+
+    let mut results = Vec::new();
+    for path in find_projects(&[notes_directory.to_path_buf()]) {
+        if !std::fs::symlink_metadata(&path).is_ok_and(|m| m.len() <= MAX_SEARCH_PROJECT_BYTES) {
+            continue;
+        }
+        let rel_path = match path.strip_prefix(notes_directory) {
+            Ok(rel) => rel.to_string_lossy().to_string(),
+            Err(_) => continue,
+        };
+        let project = match read_project_data(&path).and_then(|data| ProjectFile::parse(&data)) {
+            Ok(project) => project,
+            Err(e) => {
+                tracing::debug!("Skipping project {} in search: {}", rel_path, e);
+                continue;
+            }
+        };
+
+        for found in matches_in_project(&rel_path, &project, &query_lower) {
+            results.push(found);
+            if results.len() >= limit {
+                return results;
+            }
+        }
+    }
+
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_util::TempDir;
+
+    #[test]
+    fn test_best_snippet_picks_line_with_most_terms() {
+        let terms: HashSet<String> = ["sourdough", "starter"].map(String::from).into();
+        let text = "---\ntitle: Sourdough starter\n---\nBaking notes\nA sourdough loaf\nFeed the Starter before the sourdough bake";
+        assert_eq!(
+            best_snippet(text, &terms).as_deref(),
+            Some("Feed the Starter before the sourdough bake")
+        );
+        assert_eq!(best_snippet("Nothing relevant", &terms), None);
+    }
+
+    #[test]
+    fn test_matches_in_text_is_case_insensitive_with_line_numbers() {
+        let text = "First line\n  The Quick fox\nnothing here\nquick again";
+        let matches = matches_in_text("a.md", text, "quick");
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].line_number, 2);
+        assert_eq!(matches[0].column, 7);
+        assert_eq!(matches[0].snippet, "The Quick fox");
+        assert_eq!(matches[0].highlights, [(4, 9)]);
+        assert_eq!(matches[1].line_number, 4);
+        assert!(matches.iter().all(|m| m.file_match_count == 2));
+    }
+
+    #[test]
+    fn test_highlights_follow_snippet_cut() {
+        let line = format!("{}needle and needle{}", "a".repeat(100), "b".repeat(300));
+        let matches = matches_in_text("a.md", &line, "needle");
+        assert_eq!(matches[0].column, 101);
+        let snippet: Vec<char> = matches[0].snippet.chars().collect();
+        assert_eq!(matches[0].highlights.len(), 2);
+        for &(start, end) in &matches[0].highlights {
+            assert_eq!(snippet[start..end].iter().collect::<String>(), "needle");
+        }
+    }
+
+    #[test]
+    fn test_snippet_is_trimmed_around_long_lines() {
+        let line = format!("{}needle{}", "a".repeat(100), "b".repeat(300));
+        let snippet = snippet_around(&line, 100);
+        assert!(snippet.starts_with('…'));
+        assert!(snippet.ends_with('…'));
+        assert!(snippet.contains("needle"));
+        assert_eq!(snippet.chars().count(), MAX_SNIPPET_CHARS + 2);
+    }
+
+    #[test]
+    fn test_file_name_search_ranks_fuzzy_matches() {
+        let dir = TempDir::new("files");
+        std::fs::create_dir_all(dir.join("archive")).unwrap();
+        std::fs::write(
+            dir.join("2024-meeting-notes.md"),
+            "---\ntags: [work]\n---\n\nAgenda\n- budget\n- hiring\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("archive/mountain-trip-gear-notes.md"), "").unwrap();
+        std::fs::write(dir.join("ideas.md"), "").unwrap();
+
+        let results = search_file_names(&dir, "mtg notes", 10, 0);
+        assert_eq!(results.len(), 2);
+        assert!(results
+            .iter()
+            .all(|r| r.indices.len() == 8 && r.preview.is_none()));
+        assert_eq!(search_file_names(&dir, "", 2, 0).len(), 2);
+
+        let with_preview = search_file_names(&dir, "2024", 10, 2);
+        assert_eq!(with_preview[0].preview.as_deref(), Some("Agenda\n- budget"));
+    }
+
+    #[test]
+    fn test_search_skips_binary_files() {
+        let dir = TempDir::new("search");
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("sub/note.md"), "an idea worth keeping").unwrap();
+        std::fs::write(dir.join("blob.bin"), [0xff, 0xfe, b'i', b'd', b'e', b'a']).unwrap();
+
+        let results = search_note_contents(&dir, "IDEA", 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            results[0].path,
+            Path::new("sub").join("note.md").to_string_lossy()
+        );
+    }
+
+    #[test]
+    fn test_search_projects_finds_nodes_in_plain_and_compressed_projects() {
+        let dir = TempDir::new("projects");
+        std::fs::create_dir_all(dir.join("old")).unwrap();
+        let project = r#"{"version": 3, "graph": {"version": 3,
+            "nodes": [
+                {"id": "a", "role": "user", "content": "What causes tides?"},
+                {"id": "b", "role": "assistant", "content": "Mostly the Moon.\nThe Sun adds spring tides."},
+                {"id": "c", "role": "assistant", "content": "Gravity", "summary": "Lunar pull"}
+            ],
+            "edges": []}}"#;
+        std::fs::write(dir.join("tides.thoughttree"), project).unwrap();
+        std::fs::write(
+            dir.join("old/tides.thoughttree"),
+            crate::backend::project_file::encode_project_data(project, true).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(dir.join("broken.thoughttree"), "not json").unwrap();
+        std::fs::write(dir.join("moon.md"), "The Moon").unwrap();
+
+        let mut results = search_projects(&dir, "MOON", 10);
+        results.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].node_id, "b");
+        assert_eq!(results[0].snippet, "Mostly the Moon.");
+        assert_eq!(
+            results[0].path,
+            format!("old{}tides.thoughttree", std::path::MAIN_SEPARATOR)
+        );
+        assert_eq!(results[1].path, "tides.thoughttree");
+
+        let lunar = search_projects(&dir, "lunar", 10);
+        assert_eq!(lunar[0].node_id, "c");
+        assert_eq!(lunar[0].title, "Lunar pull");
+        assert_eq!(search_projects(&dir, "tides", 1).len(), 1);
+    }
+}
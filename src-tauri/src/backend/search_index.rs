@@ -0,0 +1,338 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+/// Bump when the on-disk layout changes; older indexes are rebuilt
+const INDEX_FORMAT_VERSION: u32 = 1;
+
+/// Files larger than this are not indexed
+const MAX_INDEXED_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Longer tokens are truncated so pasted blobs don't bloat the index
+const MAX_TERM_CHARS: usize = 64;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct IndexedDoc {
+    modified_ms: u64,
+    /// Number of terms, for length normalization
+    length: u32,
+    /// Distinct terms, so the doc can be removed from the postings
+    terms: Vec<String>,
+}
+
+/// Term postings of a set of documents, ranked by tf-idf
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct InvertedIndex {
+    docs: HashMap<String, IndexedDoc>,
+    /// term -> (document key -> occurrences)
+    postings: HashMap<String, HashMap<String, u32>>,
+}
+
+/// Inverted index of note contents under one notes directory, updated
+/// incrementally by comparing modification times
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct SearchIndex {
+    version: u32,
+    root: PathBuf,
+    /// Keyed by path relative to the root
+    #[serde(flatten)]
+    index: InvertedIndex,
+}
+
+/// What an index refresh changed
+#[derive(Clone, Debug, Default, Serialize, PartialEq, Eq)]
+pub(crate) struct IndexStats {
+    pub indexed: usize,
+    pub removed: usize,
+    pub total_documents: usize,
+}
+
+/// A ranked search result
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub(crate) struct IndexHit {
+    pub path: String,
+    pub score: f64,
+}
+
+/// Lowercased alphanumeric terms of at least two characters
+pub(crate) fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| t.chars().nth(1).is_some())
+        .map(|t| t.to_lowercase().chars().take(MAX_TERM_CHARS).collect())
+}
+
+pub(crate) fn modified_ms(metadata: &std::fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+impl InvertedIndex {
+    pub(crate) fn document_count(&self) -> usize {
+        self.docs.len()
+    }
+
+    pub(crate) fn remove_doc(&mut self, path: &str) {
+        if let Some(doc) = self.docs.remove(path) {
+            for term in doc.terms {
+                if let Some(posting) = self.postings.get_mut(&term) {
+                    posting.remove(path);
+                    if posting.is_empty() {
+                        self.postings.remove(&term);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Index (or re-index) one document's text
+    pub(crate) fn index_text(&mut self, path: &str, text: &str, modified_ms: u64) {
+        self.remove_doc(path);
+
+        let mut counts: HashMap<String, u32> = HashMap::new();
+        let mut length = 0u32;
+        for term in tokenize(text) {
+            *counts.entry(term).or_default() += 1;
+            length = length.saturating_add(1);
+        }
+
+        let terms: Vec<String> = counts.keys().cloned().collect();
+        for (term, count) in counts {
+            self.postings
+                .entry(term)
+                .or_default()
+                .insert(path.to_string(), count);
+        }
+        self.docs.insert(
+            path.to_string(),
+            IndexedDoc {
+                modified_ms,
+                length,
+                terms,
+            },
+        );
+    }
+
+    /// Rank documents containing any query term by length-normalized tf-idf
+    pub(crate) fn query(&self, query: &str, limit: usize) -> Vec<IndexHit> {
+        let total = self.docs.len() as f64;
+        let mut scores: HashMap<&str, f64> = HashMap::new();
+
+        let mut terms: Vec<String> = tokenize(query).collect();
+        terms.sort();
+        terms.dedup();
+
+        for term in &terms {
+            let Some(posting) = self.postings.get(term) else {
+                continue;
+            };
+            let idf = (1.0 + total / posting.len() as f64).ln();
+            for (path, count) in posting {
+                let length = self.docs.get(path).map_or(1, |d| d.length.max(1));
+                *scores.entry(path.as_str()).or_default() +=
+                    f64::from(*count) * idf / f64::from(length).sqrt();
+            }
+        }
+
+        let mut hits: Vec<IndexHit> = scores
+            .into_iter()
+            .map(|(path, score)| IndexHit {
+                path: path.to_string(),
+                score,
+            })
+            .collect();
+        hits.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        hits.truncate(limit);
+        hits
+    }
+}
+
+impl SearchIndex {
+    pub(crate) fn new(root: &Path) -> Self {
+        Self {
+            version: INDEX_FORMAT_VERSION,
+            root: root.to_path_buf(),
+            index: InvertedIndex::default(),
+        }
+    }
+
+    /// Whether this index can be reused for `root`
+    pub(crate) fn is_usable_for(&self, root: &Path) -> bool {
+        self.version == INDEX_FORMAT_VERSION && self.root == root
+    }
+
+    pub(crate) fn document_count(&self) -> usize {
+        self.index.document_count()
+    }
+
+    /// Index (or re-index) one document's text
+    pub(crate) fn index_text(&mut self, path: &str, text: &str, modified_ms: u64) {
+        self.index.index_text(path, text, modified_ms);
+    }
+
+    /// Bring the index in line with the files under the root: index new or
+    /// modified files and drop deleted ones. Symlinks are not followed.
+    pub(crate) fn refresh(&mut self) -> IndexStats {
+        let root = self.root.clone();
+        let mut stats = IndexStats::default();
+        let mut seen = std::collections::HashSet::new();
+
+        for entry in WalkDir::new(&root)
+            .follow_links(false)
+            .max_depth(20)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+        {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.len() > MAX_INDEXED_FILE_BYTES {
+                continue;
+            }
+            let rel_path = match entry.path().strip_prefix(&root) {
+                Ok(path) => path.to_string_lossy().to_string(),
+                Err(_) => continue,
+            };
+
+            let modified = modified_ms(&metadata);
+            let unchanged = self
+                .index
+                .docs
+                .get(&rel_path)
+                .is_some_and(|doc| doc.modified_ms == modified);
+            if unchanged {
+                seen.insert(rel_path);
+                continue;
+            }
+
+            // Non-UTF-8 content is treated as binary and left out
+            if let Ok(text) = std::fs::read_to_string(entry.path()) {
+                self.index_text(&rel_path, &text, modified);
+                stats.indexed += 1;
+                seen.insert(rel_path);
+            }
+        }
+
+        let removed: Vec<String> = self
+            .index
+            .docs
+            .keys()
+            .filter(|path| !seen.contains(*path))
+            .cloned()
+            .collect();
+        stats.removed = removed.len();
+        for path in removed {
+            self.index.remove_doc(&path);
+        }
+
+        stats.total_documents = self.index.document_count();
+        stats
+    }
+
+    pub(crate) fn query(&self, query: &str, limit: usize) -> Vec<IndexHit> {
+        self.index.query(query, limit)
+    }
+
+    /// Load a persisted index; a missing, unreadable or corrupt file yields `None`
+    pub(crate) fn load(path: &Path) -> Option<Self> {
+        load_index(path)
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> Result<(), String> {
+        save_index(self, path)
+    }
+}
+
+/// Read a persisted index of any kind; a missing, unreadable or corrupt file
+/// yields `None`
+pub(crate) fn load_index<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let data = std::fs::read(path).ok()?;
+    serde_json::from_slice(&data).ok()
+}
+
+/// Persist an index, writing to a temp file first so a crash mid-write
+/// never leaves a truncated index behind
+pub(crate) fn save_index<T: Serialize>(index: &T, path: &Path) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create index directory: {e}"))?;
+    }
+    let data = serde_json::to_vec(index).map_err(|e| format!("Failed to serialize index: {e}"))?;
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, data).map_err(|e| format!("Failed to write index: {e}"))?;
+    std::fs::rename(&tmp, path).map_err(|e| format!("Failed to replace index: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_util::TempDir;
+
+    #[test]
+    fn test_tokenize() {
+        let terms: Vec<String> = tokenize("Hello, wörld! a b2 x").collect();
+        assert_eq!(terms, vec!["hello", "wörld", "b2"]);
+    }
+
+    #[test]
+    fn test_query_ranks_and_reindexes() {
+        let mut index = SearchIndex::new(Path::new("/notes"));
+        index.index_text("a.md", "rust rust ownership", 1);
+        index.index_text("b.md", "gardening notes about rust on tools", 1);
+        index.index_text("c.md", "nothing relevant", 1);
+
+        let hits = index.query("rust", 10);
+        assert_eq!(
+            hits.iter().map(|h| h.path.as_str()).collect::<Vec<_>>(),
+            vec!["a.md", "b.md"]
+        );
+
+        index.index_text("a.md", "now about tomatoes", 2);
+        assert_eq!(index.query("rust", 10).len(), 1);
+        assert_eq!(index.query("tomatoes", 10)[0].path, "a.md");
+    }
+
+    #[test]
+    fn test_refresh_tracks_new_changed_and_deleted_files() {
+        let dir = TempDir::new("index");
+        std::fs::write(dir.join("one.md"), "first idea").unwrap();
+        std::fs::write(dir.join("two.md"), "second idea").unwrap();
+
+        let mut index = SearchIndex::new(&dir);
+        assert_eq!(
+            index.refresh(),
+            IndexStats {
+                indexed: 2,
+                removed: 0,
+                total_documents: 2
+            }
+        );
+        assert_eq!(index.refresh().indexed, 0);
+
+        std::fs::remove_file(dir.join("two.md")).unwrap();
+        let stats = index.refresh();
+        assert_eq!(stats.removed, 1);
+        assert_eq!(index.query("second", 10), Vec::new());
+
+        let saved = dir.join("index").join("search-index.json");
+        index.save(&saved).unwrap();
+        let loaded = SearchIndex::load(&saved).unwrap();
+        assert!(loaded.is_usable_for(&dir));
+        assert_eq!(loaded.query("first", 10)[0].path, "one.md");
+    }
+}
@@ -0,0 +1,92 @@
+use keyring::Entry;
+
+/// Keychain service the secrets are filed under
+const KEYCHAIN_SERVICE: &str = "com.david.thoughttree";
+
+/// Longest accepted secret, in bytes
+const MAX_SECRET_BYTES: usize = 4096;
+
+/// Gemini API key, used to list the models it can access and passed to
+/// Gemini CLI
+pub(crate) const GEMINI_API_KEY_SECRET: &str = "gemini-api-key";
+
+/// Secret names are short identifiers like `gemini-api-key`, so they can't
+/// be used to reach other apps' keychain items
+pub(crate) fn validate_secret_name(name: &str) -> Result<(), String> {
+    let valid = (1..=64).contains(&name.len())
+        && name
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "-_.".contains(c));
+    if !valid {
+        return Err(format!("Invalid secret name: {name:?}"));
+    }
+    Ok(())
+}
+
+fn entry(name: &str) -> Result<Entry, String> {
+    validate_secret_name(name)?;
+    Entry::new(KEYCHAIN_SERVICE, name).map_err(|e| format!("Failed to open keychain entry: {e}"))
+}
+
+/// Store `value` under `name` in the OS keychain (macOS Keychain, Windows
+/// Credential Manager, or the Linux kernel keyring), replacing any old value
+pub(crate) fn set_secret(name: &str, value: &str) -> Result<(), String> {
+    if value.is_empty() || value.len() > MAX_SECRET_BYTES || value.contains('\0') {
+        return Err(format!(
+            "Secret must be 1 to {MAX_SECRET_BYTES} bytes without NUL characters"
+        ));
+    }
+    entry(name)?
+        .set_password(value)
+        .map_err(|e| format!("Failed to store secret {name}: {e}"))
+}
+
+/// The secret stored under `name`. For use in the backend only; secrets are
+/// never sent to the frontend.
+pub(crate) fn get_secret(name: &str) -> Result<Option<String>, String> {
+    match entry(name)?.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read secret {name}: {e}")),
+    }
+}
+
+pub(crate) fn has_secret(name: &str) -> Result<bool, String> {
+    Ok(get_secret(name)?.is_some())
+}
+
+/// Remove the secret; returns whether there was one
+pub(crate) fn delete_secret(name: &str) -> Result<bool, String> {
+    match entry(name)?.delete_credential() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(e) => Err(format!("Failed to delete secret {name}: {e}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_secret_name() {
+        assert!(validate_secret_name(GEMINI_API_KEY_SECRET).is_ok());
+        assert!(validate_secret_name("openai.key_2").is_ok());
+        assert!(validate_secret_name("").is_err());
+        assert!(validate_secret_name("-leading").is_err());
+        assert!(validate_secret_name("Upper").is_err());
+        assert!(validate_secret_name("a/b").is_err());
+        assert!(validate_secret_name(&"a".repeat(65)).is_err());
+    }
+
+    #[test]
+    fn test_set_secret_rejects_bad_values_before_touching_the_keychain() {
+        assert!(set_secret("test", "").is_err());
+        assert!(set_secret("test", "a\0b").is_err());
+        assert!(set_secret("Bad Name", "value").is_err());
+    }
+}
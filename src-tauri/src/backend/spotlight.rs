@@ -0,0 +1,137 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::backend::project_file::{node_title, read_project_data, ProjectFile};
+
+/// Most node titles stored per project, and their total size in bytes, so
+/// the attributes stay small on huge trees
+const MAX_KEYWORDS: usize = 500;
+const MAX_KEYWORD_BYTES: usize = 32 * 1024;
+
+/// What Spotlight learns about a project: its title and the titles of its
+/// nodes, which it indexes as keywords
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct SpotlightMetadata {
+    pub title: String,
+    pub keywords: Vec<String>,
+}
+
+/// Whether this platform has Spotlight to write metadata for
+pub(crate) fn is_supported() -> bool {
+    cfg!(target_os = "macos")
+}
+
+/// Title (the file name) and distinct node titles of a project, in file order
+pub(crate) fn project_metadata(path: &Path, project: &ProjectFile) -> SpotlightMetadata {
+    let title = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut seen = HashSet::new();
+    let mut bytes = 0;
+    let keywords = project
+        .graph
+        .nodes
+        .iter()
+        .map(node_title)
+        .filter(|t| !t.is_empty() && seen.insert(t.clone()))
+        .take(MAX_KEYWORDS)
+        .take_while(|t| {
+            bytes += t.len();
+            bytes <= MAX_KEYWORD_BYTES
+        })
+        .collect();
+    SpotlightMetadata { title, keywords }
+}
+
+/// Store `project`'s metadata on `path` as Spotlight extended attributes.
+/// Does nothing on platforms without Spotlight.
+pub(crate) fn write_metadata(path: &Path, project: &ProjectFile) -> Result<(), String> {
+    let metadata = project_metadata(path, project);
+    #[cfg(target_os = "macos")]
+    {
+        macos::set_attribute(path, "kMDItemTitle", &metadata.title)?;
+        macos::set_attribute(path, "kMDItemKeywords", &metadata.keywords)?;
+    }
+    #[cfg(not(target_os = "macos"))]
+    let _ = metadata;
+    Ok(())
+}
+
+/// Read the project at `path` and store its metadata
+pub(crate) fn reindex_project(path: &Path) -> Result<(), String> {
+    let project = ProjectFile::parse(&read_project_data(path)?)?;
+    write_metadata(path, &project)
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    use serde::Serialize;
+
+    /// Set `com.apple.metadata:<attribute>` to `value` as a binary plist,
+    /// the form Spotlight imports extended attributes in
+    pub(super) fn set_attribute<T: Serialize + ?Sized>(
+        path: &Path,
+        attribute: &str,
+        value: &T,
+    ) -> Result<(), String> {
+        let mut data = Vec::new();
+        plist::to_writer_binary(&mut data, value)
+            .map_err(|e| format!("Failed to encode {attribute}: {e}"))?;
+        let path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|_| "Invalid project path".to_string())?;
+        let name = CString::new(format!("com.apple.metadata:{attribute}"))
+            .map_err(|_| "Invalid attribute name".to_string())?;
+        // XATTR_NOFOLLOW: never follow a symlink out of the notes directory
+        let result = unsafe {
+            libc::setxattr(
+                path.as_ptr(),
+                name.as_ptr(),
+                data.as_ptr().cast(),
+                data.len(),
+                0,
+                libc::XATTR_NOFOLLOW,
+            )
+        };
+        if result != 0 {
+            return Err(format!(
+                "Failed to set {attribute}: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::project_file::ProjectNode;
+
+    fn node(id: &str, content: &str) -> ProjectNode {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "role": "user",
+            "content": content,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_project_metadata_lists_distinct_titles() {
+        let mut project = ProjectFile::default();
+        project.graph.nodes = vec![
+            node("a", "What is entropy?\nMore detail"),
+            node("b", ""),
+            node("c", "What is entropy?"),
+            node("d", "Follow-up"),
+        ];
+        let metadata = project_metadata(Path::new("/notes/Physics.thoughttree"), &project);
+        assert_eq!(metadata.title, "Physics");
+        assert_eq!(metadata.keywords, ["What is entropy?", "Follow-up"]);
+    }
+}
@@ -1,18 +1,93 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 
 use futures::lock::Mutex;
-use tokio::sync::oneshot;
+
+use crate::backend::acp::children::ChildRegistry;
+use crate::backend::autosave::AutosaveQueue;
+use crate::backend::compaction::CompactionCache;
+use crate::backend::dictation::DictationSession;
+use crate::backend::generations::GenerationRegistry;
+use crate::backend::links::LinkGraph;
+use crate::backend::metrics::PromptSample;
+use crate::backend::permissions::PendingPermissions;
+use crate::backend::project_file::ProjectCache;
+use crate::backend::project_index::ProjectIndex;
+use crate::backend::project_lock::ProjectLocks;
+use crate::backend::project_watch::ProjectWatch;
+use crate::backend::queue::GenerationQueue;
+use crate::backend::replay::StreamReplay;
+use crate::backend::scheduler::JobScheduler;
+use crate::backend::search_index::SearchIndex;
+use crate::backend::types::AgentProvider;
 
 /// App state for managing permission responses
 pub(crate) struct AppState {
-    pub pending_permissions: Arc<Mutex<HashMap<String, oneshot::Sender<String>>>>,
+    /// Permission requests waiting for the user, by node
+    pub pending_permissions: Arc<PendingPermissions>,
+    /// Safe mode: only core project I/O, see [`crate::backend::safe_mode`]
+    pub safe_mode: AtomicBool,
+    /// Timings and throughput of recent prompts, for `get_latency_report`
+    /// and `get_performance_stats`
+    pub latency_samples: Arc<Mutex<VecDeque<PromptSample>>>,
+    /// Live agent subprocesses, terminated on app exit
+    pub children: ChildRegistry,
+    /// Caps how many prompts generate at once
+    pub generation_queue: Arc<GenerationQueue>,
+    /// In-flight generations by node, for cancellation
+    pub generations: Arc<GenerationRegistry>,
+    /// Recent stream chunks per node, for `replay_stream`
+    pub stream_replay: Arc<StreamReplay>,
+    /// Loaded full-text index, see [`crate::backend::indexer`]
+    pub search_index: Arc<std::sync::Mutex<Option<SearchIndex>>>,
+    /// Loaded index of node text across all projects
+    pub project_index: Arc<std::sync::Mutex<Option<ProjectIndex>>>,
+    /// `[[wikilink]]` graph of the notes directory, refreshed on query
+    pub link_graph: Arc<std::sync::Mutex<LinkGraph>>,
+    /// Debounced project saves from `queue_autosave`
+    pub autosave: Arc<AutosaveQueue>,
+    /// The open project file, checked for changes made outside the app
+    pub project_watch: Arc<ProjectWatch>,
+    /// Lock files of open projects, released on close and exit
+    pub project_locks: Arc<ProjectLocks>,
+    /// Parsed project for `load_node_content`
+    pub project_cache: Arc<ProjectCache>,
+    /// Summaries of compacted ancestor messages, by transcript
+    pub compaction_cache: Arc<CompactionCache>,
+    /// The dictation in progress, if any
+    pub dictation: Arc<Mutex<Option<DictationSession>>>,
+    /// Serializes updates of node history files
+    pub history_lock: Arc<std::sync::Mutex<()>>,
+    /// Last runs of the background maintenance jobs
+    pub background_jobs: Arc<JobScheduler>,
+    /// Why each provider's agent last failed to start for model discovery,
+    /// with its stderr, reported by `get_available_providers`
+    pub provider_errors: Arc<std::sync::Mutex<HashMap<AgentProvider, String>>>,
 }
 
 impl Default for AppState {
     fn default() -> Self {
         Self {
-            pending_permissions: Arc::new(Mutex::new(HashMap::new())),
+            pending_permissions: Arc::new(PendingPermissions::default()),
+            safe_mode: AtomicBool::new(false),
+            latency_samples: Arc::new(Mutex::new(VecDeque::new())),
+            children: ChildRegistry::default(),
+            generation_queue: Arc::new(GenerationQueue::default()),
+            generations: Arc::new(GenerationRegistry::default()),
+            stream_replay: Arc::new(StreamReplay::default()),
+            search_index: Arc::new(std::sync::Mutex::new(None)),
+            project_index: Arc::new(std::sync::Mutex::new(None)),
+            link_graph: Arc::new(std::sync::Mutex::new(LinkGraph::default())),
+            autosave: Arc::new(AutosaveQueue::default()),
+            project_watch: Arc::new(ProjectWatch::default()),
+            project_locks: Arc::new(ProjectLocks::default()),
+            project_cache: Arc::new(ProjectCache::default()),
+            compaction_cache: Arc::new(CompactionCache::default()),
+            dictation: Arc::new(Mutex::new(None)),
+            history_lock: Arc::new(std::sync::Mutex::new(())),
+            background_jobs: Arc::new(JobScheduler::default()),
+            provider_errors: Arc::new(std::sync::Mutex::new(HashMap::new())),
         }
     }
 }
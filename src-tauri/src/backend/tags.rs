@@ -0,0 +1,269 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+use serde::Serialize;
+use walkdir::WalkDir;
+
+use crate::backend::frontmatter::split_frontmatter;
+use crate::backend::links::is_markdown;
+use crate::backend::summaries::truncate_str;
+
+/// Notes larger than this are not scanned for tags
+const MAX_TAGGED_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Most used tags offered to the model when suggesting tags
+const MAX_CANDIDATE_TAGS: usize = 200;
+
+/// Tags suggested for one node
+pub(crate) const MAX_SUGGESTED_TAGS: usize = 4;
+
+/// Node content beyond this is not sent when suggesting tags
+const MAX_TAGGING_INPUT_BYTES: usize = 4000;
+
+/// A tag and the number of notes that carry it
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct TagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '/')
+}
+
+/// Normalize a tag: drop a leading `#`, lowercase, and reject purely numeric
+/// tags (`#1` is an issue reference, not a tag)
+fn normalize_tag(raw: &str) -> Option<String> {
+    let tag = raw.trim().trim_start_matches('#').trim_matches('/');
+    if tag.is_empty() || !tag.chars().all(is_tag_char) || tag.chars().all(|c| c.is_numeric()) {
+        return None;
+    }
+    Some(tag.to_lowercase())
+}
+
+/// Inline `#tags` in markdown body text, skipping fenced and inline code.
+/// A tag must start the line or follow whitespace, so headings, URLs with
+/// anchors and `a#b` are not tags.
+fn inline_tags(body: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+    let mut in_fence = false;
+
+    for line in body.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        let mut in_code = false;
+        let mut prev = ' ';
+        for (idx, c) in line.char_indices() {
+            if c == '`' {
+                in_code = !in_code;
+            } else if c == '#' && !in_code && prev.is_whitespace() {
+                let rest = &line[idx + 1..];
+                let end = rest.find(|ch: char| !is_tag_char(ch)).unwrap_or(rest.len());
+                if let Some(tag) = normalize_tag(&rest[..end]) {
+                    tags.push(tag);
+                }
+            }
+            prev = c;
+        }
+    }
+
+    tags
+}
+
+/// All tags of a note: frontmatter `tags`/`tag` (lists or comma/space
+/// separated strings) plus inline `#tags` in the body
+pub(crate) fn note_tags(text: &str) -> BTreeSet<String> {
+    let (frontmatter, body) = split_frontmatter(text);
+    let mut tags: BTreeSet<String> = ["tags", "tag"]
+        .iter()
+        .flat_map(|key| frontmatter.list(key))
+        .flat_map(|value| {
+            value
+                .split([',', ' '])
+                .filter_map(normalize_tag)
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    tags.extend(inline_tags(body));
+    tags
+}
+
+/// Tags of every markdown note under `notes_directory`, keyed by path
+/// relative to it. Symlinks are not followed.
+pub(crate) fn scan_note_tags(notes_directory: &Path) -> Vec<(String, BTreeSet<String>)> {
+    WalkDir::new(notes_directory)
+        .follow_links(false)
+        .max_depth(20)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file() && is_markdown(entry.path()))
+        .filter(|entry| {
+            entry
+                .metadata()
+                .is_ok_and(|m| m.len() <= MAX_TAGGED_FILE_BYTES)
+        })
+        .filter_map(|entry| {
+            let rel_path = entry
+                .path()
+                .strip_prefix(notes_directory)
+                .ok()?
+                .to_string_lossy()
+                .to_string();
+            let text = std::fs::read_to_string(entry.path()).ok()?;
+            Some((rel_path, note_tags(&text)))
+        })
+        .collect()
+}
+
+/// Tags with the number of notes carrying each, most used first
+pub(crate) fn count_tags(notes: &[(String, BTreeSet<String>)]) -> Vec<TagCount> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for (_, tags) in notes {
+        for tag in tags {
+            *counts.entry(tag).or_default() += 1;
+        }
+    }
+
+    let mut counts: Vec<TagCount> = counts
+        .into_iter()
+        .map(|(tag, count)| TagCount {
+            tag: tag.to_string(),
+            count,
+        })
+        .collect();
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+    counts
+}
+
+/// Paths of notes tagged `tag` or a nested tag below it (`project` matches
+/// `project/alpha`), sorted
+pub(crate) fn notes_with_tag(notes: &[(String, BTreeSet<String>)], tag: &str) -> Vec<String> {
+    let Some(tag) = normalize_tag(tag) else {
+        return Vec::new();
+    };
+    let nested_prefix = format!("{tag}/");
+
+    let mut paths: Vec<String> = notes
+        .iter()
+        .filter(|(_, tags)| {
+            tags.iter()
+                .any(|t| *t == tag || t.starts_with(&nested_prefix))
+        })
+        .map(|(path, _)| path.clone())
+        .collect();
+    paths.sort();
+    paths
+}
+/// Ask for 2-4 tags of `content`, chosen from the vault's most used tags
+pub(crate) fn tag_suggestion_prompt(content: &str, known: &[TagCount]) -> String {
+    let candidates: Vec<&str> = known
+        .iter()
+        .take(MAX_CANDIDATE_TAGS)
+        .map(|t| t.tag.as_str())
+        .collect();
+    let content = truncate_str(content, MAX_TAGGING_INPUT_BYTES);
+    format!(
+        "Choose 2 to {MAX_SUGGESTED_TAGS} tags that describe the text below. Use only tags \
+         from this list: {}\n\nDo not call any tools. Return ONLY the chosen tags, one per \
+         line, nothing else.\n\nText:\n{content}",
+        candidates.join(", ")
+    )
+}
+
+/// Tags named in the model's response that exist in the vault, in the
+/// order given, with their usage counts. Anything else is ignored.
+pub(crate) fn parse_tag_suggestions(response: &str, known: &[TagCount]) -> Vec<TagCount> {
+    let mut suggestions: Vec<TagCount> = Vec::new();
+    for raw in response.split(['\n', ',']) {
+        let raw = raw
+            .trim()
+            .trim_start_matches(['-', '*', ' '])
+            .trim_matches('`');
+        let Some(tag) = normalize_tag(raw) else {
+            continue;
+        };
+        if suggestions.iter().any(|s| s.tag == tag) {
+            continue;
+        }
+        if let Some(known) = known.iter().find(|k| k.tag == tag) {
+            suggestions.push(known.clone());
+        }
+        if suggestions.len() == MAX_SUGGESTED_TAGS {
+            break;
+        }
+    }
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_note_tags_combines_frontmatter_and_inline() {
+        let text = "---\ntags: [Rust, \"#ideas\"]\n---\n# Heading\nSome #todo and #project/alpha.\nNot a#tag, not #123, see http://x.y/#anchor\n`#code` and\n```\n#fenced\n```\n";
+        let tags: Vec<String> = note_tags(text).into_iter().collect();
+        assert_eq!(tags, vec!["ideas", "project/alpha", "rust", "todo"]);
+    }
+
+    #[test]
+    fn test_frontmatter_tag_strings_are_split() {
+        let tags: Vec<String> = note_tags("---\ntags: one, two three\n---\n")
+            .into_iter()
+            .collect();
+        assert_eq!(tags, vec!["one", "three", "two"]);
+    }
+
+    #[test]
+    fn test_counts_and_nested_lookup() {
+        let notes = vec![
+            ("a.md".to_string(), note_tags("#project/alpha #todo")),
+            ("b.md".to_string(), note_tags("#project")),
+            ("c.md".to_string(), note_tags("#todo")),
+        ];
+
+        let counts = count_tags(&notes);
+        assert_eq!(
+            counts[0],
+            TagCount {
+                tag: "todo".to_string(),
+                count: 2
+            }
+        );
+        assert_eq!(notes_with_tag(&notes, "#Project"), vec!["a.md", "b.md"]);
+        assert_eq!(notes_with_tag(&notes, "project/alpha"), vec!["a.md"]);
+    }
+
+    #[test]
+    fn test_tag_suggestions_are_limited_to_known_tags() {
+        let known = count_tags(&[
+            ("a.md".to_string(), note_tags("#rust #ideas #todo")),
+            ("b.md".to_string(), note_tags("#rust #project/alpha")),
+        ]);
+        let prompt = tag_suggestion_prompt("Borrow checker notes", &known);
+        assert!(prompt.contains("from this list: rust, ideas, project/alpha, todo\n"));
+        assert!(prompt.ends_with("Text:\nBorrow checker notes"));
+
+        let response = "- #Rust\n- made-up\n`ideas`, rust, project/alpha\ntodo";
+        let tags: Vec<(String, usize)> = parse_tag_suggestions(response, &known)
+            .into_iter()
+            .map(|t| (t.tag, t.count))
+            .collect();
+        assert_eq!(
+            tags,
+            vec![
+                ("rust".to_string(), 2),
+                ("ideas".to_string(), 1),
+                ("project/alpha".to_string(), 1),
+                ("todo".to_string(), 1)
+            ]
+        );
+        assert!(parse_tag_suggestions("nothing useful", &known).is_empty());
+    }
+}
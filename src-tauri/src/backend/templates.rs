@@ -0,0 +1,309 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Map;
+
+use crate::backend::project_file::{
+    LayoutEntry, Position, ProjectEdge, ProjectFile, ProjectGraph, ProjectNode, GRAPH_VERSION,
+};
+
+/// Prefix of ids of templates loaded from the user's templates directory
+const USER_TEMPLATE_PREFIX: &str = "user:";
+
+/// User template files larger than this are skipped
+const MAX_TEMPLATE_BYTES: u64 = 1024 * 1024;
+
+const LAYOUT_X_SPACING: f64 = 420.0;
+const LAYOUT_Y_SPACING: f64 = 260.0;
+
+/// A template as listed to the user
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct TemplateInfo {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub builtin: bool,
+}
+
+/// A node of a template tree. Only user prompts make sense as scaffolding,
+/// but assistant nodes are accepted so a saved conversation can be reused.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct TemplateNode {
+    #[serde(default = "default_role")]
+    pub role: String,
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Unix milliseconds; defaults to when the project is created
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
+    #[serde(default)]
+    pub children: Vec<TemplateNode>,
+}
+
+fn default_role() -> String {
+    "user".to_string()
+}
+
+/// Template file format: `<app data>/templates/<name>.json`
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct ProjectTemplate {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub nodes: Vec<TemplateNode>,
+}
+
+fn prompt(content: &str, children: Vec<TemplateNode>) -> TemplateNode {
+    TemplateNode {
+        role: "user".to_string(),
+        content: content.to_string(),
+        provider: None,
+        model: None,
+        timestamp: None,
+        children,
+    }
+}
+
+/// Templates shipped with the app, by id
+pub(crate) fn builtin_templates() -> Vec<(&'static str, ProjectTemplate)> {
+    vec![
+        (
+            "decision-tree",
+            ProjectTemplate {
+                name: "Decision tree".to_string(),
+                description: "Frame a decision, then explore each option and the criteria separately".to_string(),
+                nodes: vec![prompt(
+                    "I need to decide: <decision>.\n\nContext: <constraints, deadline, who is affected>.\n\nHelp me frame this decision and list the realistic options.",
+                    vec![
+                        prompt("Which criteria should this decision be judged on, and how should they be weighted?", vec![]),
+                        prompt("Make the strongest case for option A: <option>. What would have to be true for it to be the right choice?", vec![]),
+                        prompt("Make the strongest case for option B: <option>. What would have to be true for it to be the right choice?", vec![]),
+                        prompt("What information would change the decision, and how cheaply can I get it?", vec![]),
+                    ],
+                )],
+            },
+        ),
+        (
+            "literature-review",
+            ProjectTemplate {
+                name: "Literature review".to_string(),
+                description: "Map a research question, its key sources, and the open gaps".to_string(),
+                nodes: vec![prompt(
+                    "Research question: <question>.\n\nSummarize the main schools of thought and the key sources I should read first.",
+                    vec![
+                        prompt("Summarize and critique this source: <citation or @note>.", vec![]),
+                        prompt("Where do the sources disagree, and what explains the disagreement?", vec![]),
+                        prompt("What are the open gaps or unanswered questions in this literature?", vec![]),
+                    ],
+                )],
+            },
+        ),
+        (
+            "pre-mortem",
+            ProjectTemplate {
+                name: "Pre-mortem".to_string(),
+                description: "Imagine the project failed and work backwards to the causes".to_string(),
+                nodes: vec![prompt(
+                    "Project: <project and goal>.\n\nImagine it is a year from now and this project has failed badly. Write the story of how it failed.",
+                    vec![
+                        prompt("List the most likely failure causes, ranked by likelihood times impact.", vec![]),
+                        prompt("For the top three causes, what early warning signs should I watch for?", vec![]),
+                        prompt("What can I change in the plan today to prevent or blunt each of them?", vec![]),
+                    ],
+                )],
+            },
+        ),
+    ]
+}
+
+/// Parse the user templates in `dir`, keyed by `user:<file stem>`. Invalid
+/// or oversized files are skipped with a warning.
+pub(crate) fn load_user_templates(dir: &Path) -> Vec<(String, ProjectTemplate)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut templates: Vec<(String, ProjectTemplate)> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_file()))
+        .filter(|entry| entry.path().extension().is_some_and(|e| e == "json"))
+        .filter_map(|entry| {
+            let path = entry.path();
+            if entry.metadata().is_ok_and(|m| m.len() > MAX_TEMPLATE_BYTES) {
+                tracing::warn!("Skipping oversized template {:?}", path);
+                return None;
+            }
+            let stem = path.file_stem()?.to_string_lossy().into_owned();
+            let data = std::fs::read_to_string(&path).ok()?;
+            match serde_json::from_str::<ProjectTemplate>(&data) {
+                Ok(template) => Some((format!("{USER_TEMPLATE_PREFIX}{stem}"), template)),
+                Err(e) => {
+                    tracing::warn!("Skipping invalid template {:?}: {}", path, e);
+                    None
+                }
+            }
+        })
+        .collect();
+    templates.sort_by_key(|(_, t)| t.name.to_lowercase());
+    templates
+}
+
+/// Builtin templates followed by the user's
+pub(crate) fn list_templates(user_dir: &Path) -> Vec<TemplateInfo> {
+    let builtin = builtin_templates()
+        .into_iter()
+        .map(|(id, t)| (id.to_string(), t, true));
+    let user = load_user_templates(user_dir)
+        .into_iter()
+        .map(|(id, t)| (id, t, false));
+    builtin
+        .chain(user)
+        .map(|(id, template, builtin)| TemplateInfo {
+            id,
+            name: template.name,
+            description: template.description,
+            builtin,
+        })
+        .collect()
+}
+
+/// Look up a template by the id from [`list_templates`]. User templates are
+/// matched against the directory listing, never joined into a path.
+pub(crate) fn find_template(id: &str, user_dir: &Path) -> Option<ProjectTemplate> {
+    if id.starts_with(USER_TEMPLATE_PREFIX) {
+        return load_user_templates(user_dir)
+            .into_iter()
+            .find(|(user_id, _)| user_id == id)
+            .map(|(_, t)| t);
+    }
+    builtin_templates()
+        .into_iter()
+        .find(|(builtin_id, _)| *builtin_id == id)
+        .map(|(_, t)| t)
+}
+
+struct Builder {
+    graph: ProjectGraph,
+    now_ms: i64,
+    next_column: f64,
+}
+
+impl Builder {
+    /// Add `node` and its subtree; returns the node's id and x position.
+    /// Leaves take the next free column and parents center over children.
+    fn add(&mut self, node: &TemplateNode, parent: Option<&str>, depth: usize) -> (String, f64) {
+        let id = uuid::Uuid::new_v4().to_string();
+        let role = if node.role == "assistant" {
+            "assistant"
+        } else {
+            "user"
+        };
+        let timestamp = node.timestamp.unwrap_or(self.now_ms);
+        self.graph.nodes.push(ProjectNode {
+            id: id.clone(),
+            role: role.to_string(),
+            content: node.content.clone(),
+            timestamp,
+            content_updated_at: Some(timestamp),
+            summary: None,
+            summary_timestamp: None,
+            images: None,
+            provider: node.provider.clone(),
+            model: node.model.clone(),
+            pinned_provider: None,
+            pinned_model: None,
+            extra: Map::new(),
+        });
+        if let Some(parent) = parent {
+            self.graph.edges.push(ProjectEdge {
+                id: format!("{parent}->{id}"),
+                source: parent.to_string(),
+                target: id.clone(),
+            });
+        }
+
+        let x = if node.children.is_empty() {
+            let x = self.next_column * LAYOUT_X_SPACING;
+            self.next_column += 1.0;
+            x
+        } else {
+            let xs: Vec<f64> = node
+                .children
+                .iter()
+                .map(|child| self.add(child, Some(&id), depth + 1).1)
+                .collect();
+            (xs[0] + xs[xs.len() - 1]) / 2.0
+        };
+        self.graph.layout.push(LayoutEntry {
+            id: id.clone(),
+            position: Position {
+                x,
+                y: depth as f64 * LAYOUT_Y_SPACING,
+            },
+        });
+        (id, x)
+    }
+}
+
+/// A new project containing the template's tree, laid out top-down
+pub(crate) fn build_project(template: &ProjectTemplate, now_ms: i64) -> ProjectFile {
+    let mut builder = Builder {
+        graph: ProjectGraph {
+            version: GRAPH_VERSION,
+            ..Default::default()
+        },
+        now_ms,
+        next_column: 0.0,
+    };
+    for root in &template.nodes {
+        builder.add(root, None, 0);
+    }
+    ProjectFile {
+        graph: builder.graph,
+        settings: Map::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_util::TempDir;
+
+    #[test]
+    fn test_build_project_links_and_lays_out_tree() {
+        let (_, template) = builtin_templates().remove(0);
+        let project = build_project(&template, 1_000);
+        let graph = &project.graph;
+
+        assert_eq!(graph.nodes.len(), 5);
+        assert_eq!(graph.edges.len(), 4);
+        assert_eq!(graph.layout.len(), 5);
+        let root = &graph.nodes[0];
+        assert_eq!(graph.parents(&root.id).count(), 0);
+        assert!(graph.edges.iter().all(|e| e.source == root.id));
+
+        let root_x = graph.layout.iter().find(|l| l.id == root.id).unwrap();
+        assert_eq!(root_x.position.x, 1.5 * LAYOUT_X_SPACING);
+    }
+
+    #[test]
+    fn test_user_templates_are_listed_and_found_by_id() {
+        let dir = TempDir::new("templates");
+        std::fs::write(
+            dir.join("standup.json"),
+            r#"{"name": "Standup", "nodes": [{"content": "What did I do?"}]}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.join("broken.json"), "{").unwrap();
+
+        let listed = list_templates(&dir);
+        assert_eq!(listed.len(), builtin_templates().len() + 1);
+        assert!(listed.iter().any(|t| t.id == "user:standup" && !t.builtin));
+
+        assert!(find_template("user:standup", &dir).is_some());
+        assert!(find_template("user:../standup", &dir).is_none());
+        assert!(find_template("pre-mortem", &dir).is_some());
+    }
+}
@@ -0,0 +1,35 @@
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// A fresh directory under the system temp dir, removed with everything in
+/// it when dropped, so a failing assertion doesn't leave it behind
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Create `tt-<prefix>-<uuid>` in the temp dir
+    pub(crate) fn new(prefix: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("tt-{prefix}-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
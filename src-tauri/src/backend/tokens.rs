@@ -0,0 +1,237 @@
+use serde::Serialize;
+
+use crate::backend::types::{AgentProvider, Message};
+
+/// Rough cost of one image after downscaling
+const IMAGE_TOKENS: usize = 1600;
+
+/// Tokens kept free for the agent's own instructions, tool results and
+/// response
+const RESPONSE_RESERVE_TOKENS: usize = 40_000;
+
+const CLAUDE_CONTEXT_TOKENS: usize = 200_000;
+const CLAUDE_LONG_CONTEXT_TOKENS: usize = 1_000_000;
+const GEMINI_CONTEXT_TOKENS: usize = 1_048_576;
+
+/// Put in front of a message whose beginning was cut
+pub(crate) const TRUNCATION_MARKER: &str = "[Earlier text omitted]\n";
+
+/// What was left out of a prompt to fit the model's context window
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct ContextTruncation {
+    pub budget_tokens: usize,
+    /// Estimate for the full conversation
+    pub estimated_tokens: usize,
+    /// Estimate for what was sent
+    pub sent_tokens: usize,
+    /// Oldest messages dropped entirely
+    pub omitted_messages: usize,
+    /// Whether the oldest message sent lost its beginning
+    pub truncated_message: bool,
+}
+
+/// A text's size as the UI shows it against the model's context window
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct TokenCount {
+    pub tokens: usize,
+    pub context_window: usize,
+}
+
+/// Approximate token count of `text`: about four bytes per token for
+/// English prose and code
+pub(crate) fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(4)
+}
+
+/// Average characters per token of each model family's tokenizer on English
+/// text and code
+fn chars_per_token(provider: &AgentProvider) -> f64 {
+    match provider {
+        AgentProvider::ClaudeCode => 3.5,
+        AgentProvider::GeminiCli => 4.0,
+    }
+}
+
+/// Closer estimate of what `provider`'s tokenizer makes of `text`. Neither
+/// tokenizer is public, so this counts characters: ASCII at the family's
+/// average rate, CJK and other wide scripts at about one token each, and
+/// remaining non-ASCII letters at about two per token.
+pub(crate) fn count_tokens(text: &str, provider: &AgentProvider) -> usize {
+    let (mut ascii, mut wide, mut other) = (0usize, 0usize, 0usize);
+    for c in text.chars() {
+        if c.is_ascii() {
+            ascii += 1;
+        } else if c >= '\u{2E80}' {
+            wide += 1;
+        } else {
+            other += 1;
+        }
+    }
+    let ascii_tokens = (ascii as f64 / chars_per_token(provider)).ceil() as usize;
+    ascii_tokens + wide + other.div_ceil(2)
+}
+
+/// Approximate tokens of one message as it goes into the prompt
+pub(crate) fn message_tokens(message: &Message) -> usize {
+    let images = message.images.as_ref().map_or(0, Vec::len) * IMAGE_TOKENS;
+    // Base64 is 4 characters per 3 bytes; text attachments are sent as text
+    let attachments: usize = message
+        .attachments
+        .iter()
+        .flatten()
+        .map(|a| (a.data.len() / 4 * 3).div_ceil(4))
+        .sum();
+    estimate_tokens(&message.role) + 1 + estimate_tokens(&message.content) + images + attachments
+}
+
+/// Context window of a model, in tokens
+pub(crate) fn context_window(provider: &AgentProvider, model_id: Option<&str>) -> usize {
+    match provider {
+        AgentProvider::ClaudeCode
+            if model_id.is_some_and(|m| m.to_ascii_lowercase().contains("[1m]")) =>
+        {
+            CLAUDE_LONG_CONTEXT_TOKENS
+        }
+        AgentProvider::ClaudeCode => CLAUDE_CONTEXT_TOKENS,
+        AgentProvider::GeminiCli => GEMINI_CONTEXT_TOKENS,
+    }
+}
+
+/// Tokens of the conversation a model can be sent
+pub(crate) fn context_budget(provider: &AgentProvider, model_id: Option<&str>) -> usize {
+    context_window(provider, model_id) - RESPONSE_RESERVE_TOKENS
+}
+
+/// Keep the last `max_bytes` of `text`, cut at a line break when one is
+/// close, with a marker in front
+fn keep_tail(text: &str, max_bytes: usize) -> String {
+    let mut start = text.len().saturating_sub(max_bytes);
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    let tail = &text[start..];
+    let tail = match tail.find('\n') {
+        Some(newline) if newline < tail.len() / 4 => &tail[newline + 1..],
+        _ => tail,
+    };
+    format!("{TRUNCATION_MARKER}{tail}")
+}
+
+/// Fit `messages` into `budget` tokens. Messages are kept newest first; the
+/// first one that doesn't fit whole keeps its end, and everything older is
+/// dropped. The last message is always sent, cut if it has to be. `None`
+/// when the whole conversation fits.
+pub(crate) fn fit_to_budget(
+    messages: &mut Vec<Message>,
+    budget: usize,
+) -> Option<ContextTruncation> {
+    let costs: Vec<usize> = messages.iter().map(message_tokens).collect();
+    let estimated_tokens: usize = costs.iter().sum();
+    if estimated_tokens <= budget {
+        return None;
+    }
+
+    let mut remaining = budget;
+    let mut first_kept = messages.len();
+    let mut truncated_message = false;
+    for (i, cost) in costs.iter().enumerate().rev() {
+        let is_last = i + 1 == messages.len();
+        if *cost <= remaining {
+            remaining -= cost;
+            first_kept = i;
+            continue;
+        }
+        let overhead = cost - estimate_tokens(&messages[i].content);
+        if is_last || remaining > overhead {
+            let text_tokens = remaining
+                .saturating_sub(overhead)
+                .saturating_sub(estimate_tokens(TRUNCATION_MARKER));
+            let content = keep_tail(&messages[i].content, text_tokens * 4);
+            messages[i].content = content;
+            first_kept = i;
+            truncated_message = true;
+        }
+        break;
+    }
+
+    let omitted_messages = first_kept;
+    messages.drain(..first_kept);
+    Some(ContextTruncation {
+        budget_tokens: budget,
+        estimated_tokens,
+        sent_tokens: messages.iter().map(message_tokens).sum(),
+        omitted_messages,
+        truncated_message,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: &str, content: &str) -> Message {
+        Message {
+            role: role.to_string(),
+            content: content.to_string(),
+            images: None,
+            attachments: None,
+            context_paths: None,
+            context_notes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_count_tokens_per_family_and_script() {
+        let english = "The quick brown fox jumps over the lazy dog";
+        assert_eq!(count_tokens(english, &AgentProvider::ClaudeCode), 13);
+        assert_eq!(count_tokens(english, &AgentProvider::GeminiCli), 11);
+        assert_eq!(
+            count_tokens("日本語のテキスト", &AgentProvider::GeminiCli),
+            8
+        );
+        assert_eq!(count_tokens("Grüße", &AgentProvider::ClaudeCode), 2);
+        assert_eq!(count_tokens("", &AgentProvider::ClaudeCode), 0);
+        assert_eq!(
+            context_window(&AgentProvider::ClaudeCode, Some("sonnet[1m]")),
+            CLAUDE_LONG_CONTEXT_TOKENS
+        );
+    }
+
+    #[test]
+    fn test_fit_to_budget_keeps_everything_that_fits() {
+        let mut messages = vec![message("user", "Hello"), message("assistant", "Hi")];
+        assert_eq!(fit_to_budget(&mut messages, 100), None);
+        assert_eq!(messages.len(), 2);
+    }
+
+    #[test]
+    fn test_fit_to_budget_drops_and_truncates_oldest_first() {
+        let mut messages = vec![
+            message("user", &"a".repeat(400)),
+            message("assistant", &format!("first line\n{}", "b".repeat(400))),
+            message("user", &"c".repeat(40)),
+        ];
+        let report = fit_to_budget(&mut messages, 80).unwrap();
+
+        assert_eq!(report.omitted_messages, 1);
+        assert!(report.truncated_message);
+        assert!(report.sent_tokens <= 80);
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].content.starts_with(TRUNCATION_MARKER));
+        assert!(messages[0].content.ends_with('b'));
+        assert_eq!(messages[1].content, "c".repeat(40));
+    }
+
+    #[test]
+    fn test_fit_to_budget_cuts_an_oversized_last_message() {
+        let mut messages = vec![
+            message("user", "old"),
+            message("user", "é".repeat(200).as_str()),
+        ];
+        let report = fit_to_budget(&mut messages, 20).unwrap();
+        assert_eq!(report.omitted_messages, 1);
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].content.ends_with('é'));
+        assert!(messages[0].content.len() < 100);
+    }
+}
@@ -0,0 +1,128 @@
+use std::fmt::Write;
+
+use chrono::{DateTime, Local};
+
+use crate::backend::audit::{AuditEntry, AuditKind};
+use crate::backend::export::node_metadata;
+use crate::backend::project_file::ProjectNode;
+
+/// Inline code span; backticks inside would end it early
+fn code(text: &str) -> String {
+    format!("`{}`", text.replace('`', "'"))
+}
+
+fn activity_line(entry: &AuditEntry) -> String {
+    let time = DateTime::parse_from_rfc3339(&entry.timestamp)
+        .map(|t| t.with_timezone(&Local).format("%H:%M:%S").to_string())
+        .unwrap_or_default();
+    let kind = match entry.kind {
+        AuditKind::ToolCall => "Tool call",
+        AuditKind::Permission => "Permission",
+        AuditKind::FileRead => "File read",
+        AuditKind::Thought => "Thought",
+    };
+    let mut line = format!("- {time} · {kind} {}", code(&entry.tool_name));
+    if !entry.paths.is_empty() {
+        let targets: Vec<String> = entry.paths.iter().map(|p| code(p)).collect();
+        let _ = write!(line, " → {}", targets.join(", "));
+    }
+    if !entry.outcome.is_empty() {
+        let _ = write!(line, " ({})", entry.outcome);
+    }
+    line
+}
+
+/// Render a branch as annotated markdown: each prompt and response in
+/// order, and for responses the agent's recorded thoughts and tool activity
+/// taken from the audit log
+pub(crate) fn transcript_markdown(
+    title: &str,
+    nodes: &[&ProjectNode],
+    activity: &[AuditEntry],
+) -> String {
+    let mut out = format!(
+        "# Transcript: {title}\n\n*Exported {}*\n",
+        Local::now().format("%Y-%m-%d %H:%M")
+    );
+
+    for (i, node) in nodes.iter().enumerate() {
+        let _ = write!(out, "\n## {}. {}\n\n", i + 1, node_metadata(node));
+        let entries: Vec<&AuditEntry> = activity.iter().filter(|e| e.node_id == node.id).collect();
+
+        let thoughts: Vec<&str> = entries
+            .iter()
+            .filter(|e| e.kind == AuditKind::Thought)
+            .filter_map(|e| e.detail.as_deref())
+            .collect();
+        if !thoughts.is_empty() {
+            out.push_str("**Thoughts**\n\n");
+            for line in thoughts.join("\n\n").lines() {
+                let _ = writeln!(out, "> {line}");
+            }
+            out.push('\n');
+        }
+
+        let tools: Vec<String> = entries
+            .iter()
+            .filter(|e| e.kind != AuditKind::Thought)
+            .map(|e| activity_line(e))
+            .collect();
+        if !tools.is_empty() {
+            let _ = write!(out, "**Tool activity**\n\n{}\n\n", tools.join("\n"));
+        }
+
+        if !thoughts.is_empty() || !tools.is_empty() {
+            out.push_str("**Response**\n\n");
+        }
+        let _ = writeln!(out, "{}", node.content.trim());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::project_file::ProjectFile;
+
+    #[test]
+    fn test_transcript_includes_thoughts_and_tool_targets() {
+        let graph = ProjectFile::parse(
+            r#"{"version": 3, "graph": {"version": 3, "nodes": [
+                {"id": "q", "role": "user", "content": "Summarize my note"},
+                {"id": "a", "role": "assistant", "content": "It says hi.", "provider": "claude-code"}
+            ], "edges": [{"id": "q->a", "source": "q", "target": "a"}]}}"#,
+        )
+        .unwrap()
+        .graph;
+        let nodes: Vec<&ProjectNode> = graph.nodes.iter().collect();
+        let activity = vec![
+            AuditEntry::new(
+                AuditKind::ToolCall,
+                "a",
+                "call-1",
+                "Read `a.md`",
+                vec!["/notes/a.md".to_string()],
+                "completed".to_string(),
+            ),
+            AuditEntry::new(AuditKind::Thought, "a", "", "", Vec::new(), String::new())
+                .with_detail("Look at the note first.\nThen answer.".to_string()),
+            AuditEntry::new(
+                AuditKind::FileRead,
+                "other-node",
+                "",
+                "read_text_file",
+                vec!["/notes/b.md".to_string()],
+                "allowed".to_string(),
+            ),
+        ];
+
+        let transcript = transcript_markdown("Notes", &nodes, &activity);
+        assert!(transcript.starts_with("# Transcript: Notes\n"));
+        assert!(transcript.contains("## 1. User\n\nSummarize my note\n"));
+        assert!(transcript.contains("## 2. Assistant · claude-code\n"));
+        assert!(transcript.contains("> Look at the note first.\n> Then answer.\n"));
+        assert!(transcript.contains("Tool call `Read 'a.md'` → `/notes/a.md` (completed)"));
+        assert!(transcript.contains("**Response**\n\nIt says hi.\n"));
+        assert!(!transcript.contains("b.md"));
+    }
+}
@@ -0,0 +1,116 @@
+use tauri::menu::{Menu, MenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+use crate::backend::config;
+
+pub(crate) const MAIN_WINDOW: &str = "main";
+
+const MENU_QUICK_CAPTURE: &str = "quick-capture";
+const MENU_SHOW: &str = "show";
+const MENU_QUIT: &str = "quit";
+
+/// Bring the main window to the front and ask the frontend for a quick
+/// capture prompt, whose text goes to `quick_capture`
+pub(crate) fn request_quick_capture(app: &AppHandle) {
+    show_main_window(app);
+    if let Err(e) = app.emit("quick-capture-requested", ()) {
+        tracing::error!("Failed to emit quick-capture-requested: {:?}", e);
+    }
+}
+
+fn show_main_window(app: &AppHandle) {
+    let Some(window) = app.get_webview_window(MAIN_WINDOW) else {
+        return;
+    };
+    if let Err(e) = window
+        .unminimize()
+        .and(window.show())
+        .and(window.set_focus())
+    {
+        tracing::warn!("Failed to show main window: {}", e);
+    }
+}
+
+/// Add the tray icon with its Quick Capture, Show and Quit items
+pub(crate) fn init_tray(app: &AppHandle) -> tauri::Result<()> {
+    let capture = MenuItem::with_id(
+        app,
+        MENU_QUICK_CAPTURE,
+        "Quick Capture…",
+        true,
+        None::<&str>,
+    )?;
+    let show = MenuItem::with_id(app, MENU_SHOW, "Show ThoughtTree", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, MENU_QUIT, "Quit", true, None::<&str>)?;
+    let menu = Menu::with_items(app, &[&capture, &show, &quit])?;
+
+    let mut tray = TrayIconBuilder::with_id("main")
+        .tooltip("ThoughtTree")
+        .menu(&menu)
+        .on_menu_event(|app, event| match event.id.as_ref() {
+            MENU_QUICK_CAPTURE => request_quick_capture(app),
+            MENU_SHOW => show_main_window(app),
+            MENU_QUIT => app.exit(0),
+            _ => {}
+        });
+    if let Some(icon) = app.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    tray.build(app)?;
+    Ok(())
+}
+
+/// The global shortcut plugin, calling [`request_quick_capture`] whenever a
+/// registered shortcut is pressed
+pub(crate) fn shortcut_plugin() -> tauri::plugin::TauriPlugin<tauri::Wry> {
+    tauri_plugin_global_shortcut::Builder::new()
+        .with_handler(|app, _shortcut, event| {
+            if event.state == ShortcutState::Pressed {
+                request_quick_capture(app);
+            }
+        })
+        .build()
+}
+
+/// Parse a shortcut such as `CommandOrControl+Shift+Space`
+pub(crate) fn parse_shortcut(shortcut: &str) -> Result<Shortcut, String> {
+    shortcut
+        .parse()
+        .map_err(|e| format!("Invalid shortcut {shortcut:?}: {e}"))
+}
+
+/// Replace the registered quick capture shortcut with `shortcut`; `None`
+/// leaves none registered
+pub(crate) fn register_capture_shortcut(
+    app: &AppHandle,
+    shortcut: Option<&str>,
+) -> Result<(), String> {
+    let shortcuts = app.global_shortcut();
+    shortcuts
+        .unregister_all()
+        .map_err(|e| format!("Failed to unregister shortcuts: {e}"))?;
+    if let Some(shortcut) = shortcut {
+        shortcuts
+            .register(parse_shortcut(shortcut)?)
+            .map_err(|e| format!("Failed to register shortcut {shortcut:?}: {e}"))?;
+        tracing::info!("Quick capture shortcut registered: {}", shortcut);
+    }
+    Ok(())
+}
+
+/// Register the configured shortcut at startup. Failures (e.g. the shortcut
+/// is taken by another app) are logged; the tray item still works.
+pub(crate) fn init_capture_shortcut(app: &AppHandle) {
+    let shortcut = match config::get_quick_capture_shortcut(app) {
+        Ok(shortcut) => shortcut,
+        Err(e) => {
+            tracing::warn!("Failed to read quick capture shortcut: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = register_capture_shortcut(app, shortcut.as_deref()) {
+        tracing::warn!("{}", e);
+    }
+}
@@ -1,7 +1,19 @@
+use std::path::PathBuf;
+
 use serde::{Deserialize, Serialize};
 
+use crate::backend::acp::children::ChildRegistry;
+use crate::backend::agent_env::{default_allowlist, AgentEnv};
+use crate::backend::context::ContextNote;
+use crate::backend::gemini_cli::GeminiSettings;
+use crate::backend::metrics::Throughput;
+use crate::backend::proxy::ProxySettings;
+use crate::backend::retry::RetryReason;
+use crate::backend::tags::TagCount;
+use crate::backend::tokens::ContextTruncation;
+
 /// Supported agent providers for ACP connections
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
 #[serde(rename_all = "kebab-case")]
 pub(crate) enum AgentProvider {
     #[default]
@@ -22,9 +34,96 @@ impl AgentProvider {
 /// Provider availability status for frontend
 #[derive(Clone, Debug, Serialize)]
 pub(crate) struct ProviderStatus {
+    pub provider: AgentProvider,
+    pub available: bool,
+    /// Whether the CLI's credentials were found, so "not signed in" can be
+    /// told apart from "not installed"; `None` when it isn't checked
+    pub authenticated: Option<bool>,
+    pub error_message: Option<String>,
+}
+
+/// Installed CLI version for a provider, checked against the minimum ACP-capable version
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct ProviderVersion {
+    pub provider: AgentProvider,
+    pub executable_path: Option<String>,
+    pub version: Option<String>,
+    pub minimum_version: String,
+    pub meets_minimum: Option<bool>,
+    pub error_message: Option<String>,
+}
+
+/// What a health check looked at
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum HealthCheckKind {
+    /// The claude-code-acp launcher
+    Sidecar,
+    /// The active workspace can be read
+    NotesDirectory,
+    /// The provider's CLI was found
+    Cli,
+    /// The CLI is new enough to speak ACP
+    Version,
+    /// The agent starts and answers `initialize`
+    Handshake,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum HealthStatus {
+    Ok,
+    /// Works, but not as intended (e.g. the npx fallback)
+    Warning,
+    Error,
+    /// Not run because a check it depends on failed
+    Skipped,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct HealthCheck {
+    pub kind: HealthCheckKind,
+    /// `None` for checks that aren't about one provider
+    pub provider: Option<AgentProvider>,
+    pub status: HealthStatus,
+    /// What was found, or what went wrong
+    pub detail: String,
+    pub duration_ms: u64,
+}
+
+/// Result of `run_health_check`, for the Diagnostics page
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct HealthReport {
+    /// No check ended in an error
+    pub healthy: bool,
+    pub checks: Vec<HealthCheck>,
+}
+
+impl HealthReport {
+    pub(crate) fn new(checks: Vec<HealthCheck>) -> Self {
+        Self {
+            healthy: checks.iter().all(|c| c.status != HealthStatus::Error),
+            checks,
+        }
+    }
+}
+
+/// Which app features work with a provider, derived from its ACP capabilities
+#[derive(Clone, Debug, Serialize, Default)]
+pub(crate) struct ProviderFeatures {
     pub provider: AgentProvider,
     pub available: bool,
     pub error_message: Option<String>,
+    /// Image content blocks in prompts
+    pub vision: bool,
+    /// Agent thought chunks are streamed alongside the answer
+    pub thought_streaming: bool,
+    /// Model can be changed per session
+    pub model_switching: bool,
+    /// Sessions can be reloaded (`session/load`)
+    pub session_resume: bool,
+    /// Agent advertises slash commands
+    pub slash_commands: bool,
 }
 
 /// Model info discovered from ACP CreateSessionResponse.models.available_models
@@ -34,6 +133,38 @@ pub(crate) struct ModelInfo {
     pub display_name: String,
 }
 
+/// An authentication method advertised by an agent in its initialize response
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct AuthMethodInfo {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+/// A slash command advertised by an agent (e.g. `/compact`)
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct AgentCommandInfo {
+    pub name: String,
+    pub description: String,
+    /// Placeholder describing the command's free-form argument, if it takes one
+    pub input_hint: Option<String>,
+}
+
+/// A session mode (e.g. "plan") advertised by an agent
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct SessionModeInfo {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+/// Session modes available for a provider and the one new sessions start in
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct SessionModes {
+    pub current_mode_id: Option<String>,
+    pub available_modes: Vec<SessionModeInfo>,
+}
+
 /// User's preferred model per provider (stores model_id strings)
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub(crate) struct ModelPreferences {
@@ -44,6 +175,14 @@ pub(crate) struct ModelPreferences {
 }
 
 impl ModelPreferences {
+    /// Get the model preference for a given provider
+    pub(crate) fn get(&self, provider: &AgentProvider) -> Option<&str> {
+        match provider {
+            AgentProvider::ClaudeCode => self.claude_code.as_deref(),
+            AgentProvider::GeminiCli => self.gemini_cli.as_deref(),
+        }
+    }
+
     /// Set the model preference for a given provider
     pub(crate) fn set(&mut self, provider: &AgentProvider, model_id: Option<String>) {
         match provider {
@@ -53,6 +192,57 @@ impl ModelPreferences {
     }
 }
 
+/// Named model choice that outlives any one model's id
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ModelPreset {
+    Fast,
+    Balanced,
+    Deep,
+}
+
+/// Concrete model per provider behind each preset. A provider left unset
+/// keeps the agent's default model.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct ModelPresets {
+    pub fast: ModelPreferences,
+    pub balanced: ModelPreferences,
+    pub deep: ModelPreferences,
+}
+
+impl Default for ModelPresets {
+    fn default() -> Self {
+        let models = |claude: &str, gemini: &str| ModelPreferences {
+            claude_code: Some(claude.to_string()),
+            gemini_cli: Some(gemini.to_string()),
+        };
+        Self {
+            fast: models("haiku", "gemini-2.5-flash"),
+            balanced: models("sonnet", "gemini-2.5-pro"),
+            deep: models("opus", "gemini-3"),
+        }
+    }
+}
+
+impl ModelPresets {
+    pub(crate) fn get(&self, preset: ModelPreset) -> &ModelPreferences {
+        match preset {
+            ModelPreset::Fast => &self.fast,
+            ModelPreset::Balanced => &self.balanced,
+            ModelPreset::Deep => &self.deep,
+        }
+    }
+
+    pub(crate) fn get_mut(&mut self, preset: ModelPreset) -> &mut ModelPreferences {
+        match preset {
+            ModelPreset::Fast => &mut self.fast,
+            ModelPreset::Balanced => &mut self.balanced,
+            ModelPreset::Deep => &mut self.deep,
+        }
+    }
+}
+
 /// Custom executable paths for providers (user-configured overrides)
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub(crate) struct ProviderPaths {
@@ -72,13 +262,284 @@ impl ProviderPaths {
     }
 }
 
+/// A favorite node, identified by its project file and node id
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct PinnedNode {
+    pub project: String,
+    pub node_id: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    pub pinned_at: String,
+}
+
+/// Permission overrides stored in a `.thoughttree` project file. They are
+/// layered over the global policy: tools that are always denied stay denied.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ProjectPermissions {
+    /// Tools denied for this project even if the global policy allows them
+    #[serde(default)]
+    pub deny_tools: Vec<String>,
+    /// Read-only tools auto-approved for this project (paths are still
+    /// restricted to the notes directory)
+    #[serde(default)]
+    pub allow_tools: Vec<String>,
+}
+
+/// Settings that control how agent subprocesses are launched
+#[derive(Clone, Debug)]
+pub(crate) struct SpawnConfig {
+    pub provider_paths: ProviderPaths,
+    /// Run claude-code-acp via npx when the bundled sidecar is missing
+    pub npx_fallback: bool,
+    /// Where spawned agents are registered for cleanup on app exit
+    pub children: ChildRegistry,
+    /// Extra environment variables set on every agent
+    pub env: AgentEnv,
+    /// Parent environment variables agents inherit; everything else is cleared
+    pub env_allowlist: Vec<String>,
+    /// Run agents under the macOS sandbox, see [`crate::backend::acp::sandbox`]
+    pub sandbox: bool,
+    /// Proxies agents and the app's own provider requests go through
+    pub proxy: ProxySettings,
+    /// Settings file and sandbox flags for Gemini CLI
+    pub gemini: GeminiSettings,
+    /// Directories besides the notes agents may read without asking
+    pub read_roots: Vec<PathBuf>,
+}
+
+impl Default for SpawnConfig {
+    fn default() -> Self {
+        Self {
+            provider_paths: ProviderPaths::default(),
+            npx_fallback: false,
+            children: ChildRegistry::default(),
+            env: AgentEnv::default(),
+            env_allowlist: default_allowlist(),
+            sandbox: false,
+            proxy: ProxySettings::default(),
+            gemini: GeminiSettings::default(),
+            read_roots: Vec::new(),
+        }
+    }
+}
+
 // Types for frontend communication
 #[derive(Clone, Serialize)]
+pub(crate) struct QueuePositionPayload {
+    pub node_id: String,
+    pub trace_id: String,
+    /// 1-based position among waiting generations; 0 once generation starts
+    pub position: usize,
+}
+
+/// Phase of a prompt session, reported as `generation-status` events
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum GenerationStatus {
+    /// Summarizing older ancestors before the agent starts
+    Compacting,
+    Spawning,
+    Initializing,
+    SessionCreated,
+    ModelSet,
+    Prompting,
+    Completed,
+    Failed,
+}
+
+#[derive(Clone, Serialize)]
+pub(crate) struct GenerationStatusPayload {
+    pub node_id: String,
+    pub trace_id: String,
+    pub status: GenerationStatus,
+    /// Set with `Failed`
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub(crate) struct AgentCrashedPayload {
+    pub node_id: String,
+    pub trace_id: String,
+    pub provider: AgentProvider,
+    pub exit_status: String,
+    pub stderr_tail: String,
+}
+
+/// A prompt failed with a transient error or a crash and will be sent again
+/// after `delay_secs`
+#[derive(Clone, Serialize)]
+pub(crate) struct GenerationRetryingPayload {
+    pub node_id: String,
+    pub trace_id: String,
+    pub reason: RetryReason,
+    /// Which retry this is, from 1
+    pub attempt: u32,
+    pub max_retries: u32,
+    pub delay_secs: u64,
+    pub message: String,
+}
+
+/// What a failed generation was doing, reported with `generation-error`
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum GenerationErrorCategory {
+    /// The agent couldn't be started
+    Spawn,
+    /// The ACP handshake or session creation failed
+    Connect,
+    /// Setting the model or session mode failed
+    Session,
+    /// The prompt request failed
+    Prompt,
+    /// The agent wants the user to log in (again)
+    Auth,
+    /// The agent process exited mid-prompt
+    Crashed,
+}
+
+impl GenerationErrorCategory {
+    /// Category of a failure in the session phase last entered
+    pub(crate) fn from_phase(phase: Option<GenerationStatus>) -> Self {
+        match phase {
+            None | Some(GenerationStatus::Compacting | GenerationStatus::Spawning) => Self::Spawn,
+            Some(GenerationStatus::Initializing) => Self::Connect,
+            Some(GenerationStatus::SessionCreated | GenerationStatus::ModelSet) => Self::Session,
+            Some(
+                GenerationStatus::Prompting
+                | GenerationStatus::Completed
+                | GenerationStatus::Failed,
+            ) => Self::Prompt,
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub(crate) struct GenerationErrorPayload {
+    pub node_id: String,
+    pub trace_id: String,
+    pub category: GenerationErrorCategory,
+    pub message: String,
+    /// The agent's last stderr lines, oldest first
+    pub stderr_tail: Vec<String>,
+}
+
+/// Why the agent ended its turn
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum StopReason {
+    EndTurn,
+    /// Cut off at the token or turn limit; the answer can be continued
+    MaxTokens,
+    Refusal,
+    Cancelled,
+    /// A reason this version doesn't know
+    Error,
+}
+
+impl StopReason {
+    /// From the wire name of an ACP stop reason, e.g. `end_turn`
+    pub(crate) fn from_acp_name(name: &str) -> Self {
+        match name {
+            "end_turn" => Self::EndTurn,
+            "max_tokens" | "max_turn_requests" => Self::MaxTokens,
+            "refusal" => Self::Refusal,
+            "cancelled" => Self::Cancelled,
+            _ => Self::Error,
+        }
+    }
+
+    /// Stops the user should hear about, announced with a
+    /// `generation-stopped` event
+    pub(crate) fn needs_attention(self) -> bool {
+        matches!(self, Self::MaxTokens | Self::Refusal)
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub(crate) struct GenerationStoppedPayload {
+    pub node_id: String,
+    pub trace_id: String,
+    pub stop_reason: StopReason,
+}
+
+#[derive(Clone, Serialize)]
+pub(crate) struct StreamTimeoutPayload {
+    pub node_id: String,
+    pub trace_id: String,
+    pub timeout_secs: u64,
+}
+
+#[derive(Clone, Serialize)]
+pub(crate) struct AuthRequiredPayload {
+    pub node_id: String,
+    pub trace_id: String,
+    pub provider: AgentProvider,
+    pub methods: Vec<AuthMethodInfo>,
+}
+
+#[derive(Clone, Serialize)]
+pub(crate) struct AutosaveCompletePayload {
+    pub path: String,
+    /// Unix timestamp in milliseconds
+    pub saved_at: u64,
+}
+
+#[derive(Clone, Serialize)]
+pub(crate) struct AutosaveFailedPayload {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Clone, Serialize)]
+pub(crate) struct ProjectChangedPayload {
+    pub path: String,
+}
+
+/// Dictated text so far; `is_final` once recording has stopped
+#[derive(Clone, Serialize)]
+pub(crate) struct TranscriptionPayload {
+    pub text: String,
+    pub is_final: bool,
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
 pub(crate) struct ChunkPayload {
     pub node_id: String,
+    /// Per-node sequence number, for `replay_stream` after a webview reload
+    pub seq: u64,
     pub chunk: String,
 }
 
+/// Tool call progress forwarded to the frontend as `tool-activity` events
+#[derive(Clone, Serialize)]
+pub(crate) struct ToolActivityPayload {
+    pub node_id: String,
+    pub tool_call_id: String,
+    pub title: Option<String>,
+    pub status: Option<String>,
+    pub locations: Vec<String>,
+    pub content: Vec<ToolActivityContent>,
+}
+
+/// Structured result content reported by a tool call (search snippets, file excerpts, diffs)
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum ToolActivityContent {
+    Text {
+        text: String,
+    },
+    Resource {
+        uri: String,
+        text: Option<String>,
+    },
+    Diff {
+        path: String,
+        old_text: Option<String>,
+        new_text: String,
+    },
+}
+
 #[derive(Clone, Serialize)]
 pub(crate) struct PermissionPayload {
     pub id: String,
@@ -86,6 +547,20 @@ pub(crate) struct PermissionPayload {
     pub tool_name: String,
     pub description: String,
     pub options: Vec<PermissionOption>,
+    /// Paths the tool call touches
+    pub locations: Vec<String>,
+    /// The tool's raw input (URL, query, file path, ...) as sent by the agent
+    pub raw_input: Option<serde_json::Value>,
+    /// Content attached to the tool call, such as a proposed diff
+    pub content: Vec<ToolActivityContent>,
+}
+
+/// A permission request nobody answered in time; it was cancelled
+#[derive(Clone, Serialize)]
+pub(crate) struct PermissionExpiredPayload {
+    pub id: String,
+    pub node_id: String,
+    pub timeout_secs: u64,
 }
 
 #[derive(Clone, Serialize)]
@@ -94,6 +569,41 @@ pub(crate) struct PermissionOption {
     pub label: String,
 }
 
+/// One provider to run in `send_prompt_multi`, streaming into `node_id`
+#[derive(Clone, Debug, Deserialize)]
+pub(crate) struct PromptTarget {
+    pub node_id: String,
+    pub provider: AgentProvider,
+    #[serde(default)]
+    pub model_id: Option<String>,
+}
+
+/// Per-provider result of `send_prompt_multi`; one failing provider doesn't fail the rest
+#[derive(Clone, Serialize)]
+pub(crate) struct MultiPromptOutcome {
+    pub node_id: String,
+    pub provider: AgentProvider,
+    pub result: Option<PromptResult>,
+    pub error: Option<String>,
+}
+
+/// Sent by `generate_alternatives` before it starts, so the frontend can
+/// add the child nodes the answers stream into
+#[derive(Clone, Serialize)]
+pub(crate) struct AlternativesCreatedPayload {
+    pub parent_node_id: String,
+    pub node_ids: Vec<String>,
+}
+
+/// Result of one alternative in `generate_alternatives`; one failing doesn't
+/// fail the rest
+#[derive(Clone, Serialize)]
+pub(crate) struct AlternativeOutcome {
+    pub node_id: String,
+    pub result: Option<PromptResult>,
+    pub error: Option<String>,
+}
+
 // Message types from frontend (with optional images)
 #[derive(Clone, Deserialize)]
 pub(crate) struct MessageImage {
@@ -101,17 +611,98 @@ pub(crate) struct MessageImage {
     pub mime_type: String,
 }
 
+/// A non-image file attached to a message (PDF, text, audio)
+#[derive(Clone, Deserialize)]
+pub(crate) struct MessageAttachment {
+    pub name: String,
+    pub mime_type: String,
+    /// Base64 without a `data:` prefix
+    pub data: String,
+}
+
 #[derive(Clone, Deserialize)]
 pub(crate) struct Message {
     pub role: String,
     pub content: String,
     pub images: Option<Vec<MessageImage>>,
+    #[serde(default)]
+    pub attachments: Option<Vec<MessageAttachment>>,
+    /// Notes (relative to the notes directory) whose text is prepended to
+    /// the message as context
+    #[serde(default)]
+    pub context_paths: Option<Vec<String>>,
+    /// The `context_paths` notes, read by the backend, when they are to be
+    /// sent as resource links instead of being prepended to `content`
+    #[serde(skip)]
+    pub context_notes: Vec<ContextNote>,
+}
+
+/// Wall-clock duration of each `prompt_session` phase, in milliseconds
+#[derive(Clone, Debug, Serialize, Default)]
+pub(crate) struct PromptTimings {
+    pub spawn_ms: u64,
+    pub initialize_ms: u64,
+    pub new_session_ms: u64,
+    /// Only set when a model switch was requested
+    pub model_switch_ms: Option<u64>,
+    /// From sending the prompt to the first streamed chunk
+    pub first_chunk_ms: Option<u64>,
+    /// From sending the prompt to the agent's final response
+    pub completion_ms: u64,
+    pub total_ms: u64,
+}
+
+/// A `[[wikilink]]` in a prompt and how much of its note was included
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct LinkedNoteReport {
+    pub target: String,
+    /// Note the link resolved to, relative to the notes directory; `None`
+    /// when no note matches
+    pub path: Option<String>,
+    /// 0 when the note couldn't be read or the budget was already spent
+    pub included_bytes: usize,
+    /// Whether the note was cut short by the budget
+    pub truncated: bool,
+}
+
+/// Result of a `send_prompt` turn
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct PromptResult {
+    pub stop_reason: StopReason,
+    pub timings: PromptTimings,
+    /// Notes added to the prompt for its `[[wikilinks]]`
+    pub linked_notes: Vec<LinkedNoteReport>,
+    /// What was left out to fit the model's context window, if anything
+    pub context_truncation: Option<ContextTruncation>,
+    /// Older messages replaced by a summary, 0 unless compaction is on
+    pub compacted_messages: usize,
+    pub throughput: Throughput,
+    /// Tags this generation's log lines and events
+    pub trace_id: String,
+}
+
+/// A node to title in `generate_summaries`
+#[derive(Clone, Debug, Deserialize)]
+pub(crate) struct SummaryRequest {
+    pub node_id: String,
+    pub content: String,
 }
 
 #[derive(Clone, Serialize)]
 pub(crate) struct SummaryResult {
     pub node_id: String,
     pub summary: String,
+    /// Model the bulk model policy selected for this job
+    pub model_id: Option<String>,
+}
+
+/// Existing vault tags proposed for a node, with how many notes use each
+#[derive(Clone, Serialize)]
+pub(crate) struct TagSuggestions {
+    pub node_id: String,
+    pub tags: Vec<TagCount>,
+    /// Model the bulk model policy selected for this job
+    pub model_id: Option<String>,
 }
 
 #[cfg(test)]
@@ -150,4 +741,64 @@ mod tests {
         assert_eq!(AgentProvider::ClaudeCode.display_name(), "Claude Code");
         assert_eq!(AgentProvider::GeminiCli.display_name(), "Gemini CLI");
     }
+
+    #[test]
+    fn test_model_presets_keep_defaults_for_missing_presets() {
+        let presets: ModelPresets =
+            serde_json::from_str(r#"{"fast": {"claude-code": "claude-haiku-4-5"}}"#).unwrap();
+        let fast = presets.get(ModelPreset::Fast);
+        assert_eq!(
+            fast.get(&AgentProvider::ClaudeCode),
+            Some("claude-haiku-4-5")
+        );
+        assert_eq!(fast.get(&AgentProvider::GeminiCli), None);
+        assert_eq!(
+            presets
+                .get(ModelPreset::Deep)
+                .get(&AgentProvider::ClaudeCode),
+            Some("opus")
+        );
+
+        let preset: ModelPreset = serde_json::from_str("\"balanced\"").unwrap();
+        assert_eq!(preset, ModelPreset::Balanced);
+        assert!(serde_json::from_str::<ModelPreset>("\"cheap\"").is_err());
+    }
+
+    #[test]
+    fn test_stop_reason_from_acp_name() {
+        assert_eq!(StopReason::from_acp_name("end_turn"), StopReason::EndTurn);
+        assert_eq!(
+            StopReason::from_acp_name("max_turn_requests"),
+            StopReason::MaxTokens
+        );
+        assert_eq!(StopReason::from_acp_name("refusal"), StopReason::Refusal);
+        assert_eq!(
+            StopReason::from_acp_name("something_new"),
+            StopReason::Error
+        );
+        assert!(StopReason::Refusal.needs_attention());
+        assert!(!StopReason::Cancelled.needs_attention());
+        assert_eq!(
+            serde_json::to_string(&StopReason::MaxTokens).unwrap(),
+            "\"max-tokens\""
+        );
+    }
+
+    #[test]
+    fn test_generation_error_category_from_phase() {
+        use GenerationErrorCategory as Category;
+        assert_eq!(Category::from_phase(None), Category::Spawn);
+        assert_eq!(
+            Category::from_phase(Some(GenerationStatus::Initializing)),
+            Category::Connect
+        );
+        assert_eq!(
+            Category::from_phase(Some(GenerationStatus::ModelSet)),
+            Category::Session
+        );
+        assert_eq!(
+            Category::from_phase(Some(GenerationStatus::Prompting)),
+            Category::Prompt
+        );
+    }
 }
@@ -0,0 +1,208 @@
+use std::time::Duration;
+
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::backend::proxy::{http_client_builder, ProxySettings};
+
+/// Latest published (non-draft, non-prerelease) release of the app
+const LATEST_RELEASE_URL: &str =
+    "https://api.github.com/repos/davidchris/thoughttree/releases/latest";
+
+/// Only links into the app's own GitHub repository are handed to the
+/// frontend, which may open them
+const RELEASE_URL_PREFIX: &str = "https://github.com/davidchris/thoughttree/";
+
+const UPDATE_CHECK_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Longest release notes passed on, in bytes
+const MAX_RELEASE_NOTES_BYTES: usize = 64 * 1024;
+
+/// Installer extensions per platform, most preferred first
+#[cfg(target_os = "macos")]
+const ASSET_EXTENSIONS: &[&str] = &[".dmg"];
+#[cfg(target_os = "windows")]
+const ASSET_EXTENSIONS: &[&str] = &[".msi", "-setup.exe", ".exe"];
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const ASSET_EXTENSIONS: &[&str] = &[".AppImage", ".deb", ".rpm"];
+
+#[derive(Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    html_url: String,
+    #[serde(default)]
+    body: Option<String>,
+    #[serde(default)]
+    published_at: Option<String>,
+    #[serde(default)]
+    assets: Vec<GithubAsset>,
+}
+
+#[derive(Deserialize)]
+struct GithubAsset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// Result of comparing the running version with the latest release
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct UpdateInfo {
+    pub current_version: String,
+    pub latest_version: String,
+    pub update_available: bool,
+    /// Markdown release notes, truncated if very long
+    pub release_notes: String,
+    /// Release page on GitHub
+    pub release_url: String,
+    /// Installer for this platform, or the release page if there is none
+    pub download_url: String,
+    pub published_at: Option<String>,
+}
+
+/// Version of a release tag such as `v0.4.0` or `app-v0.4.0`
+fn parse_tag(tag: &str) -> Option<Version> {
+    Version::parse(tag.trim_start_matches(|c: char| !c.is_ascii_digit())).ok()
+}
+
+fn is_release_url(url: &str) -> bool {
+    url.starts_with(RELEASE_URL_PREFIX)
+}
+
+fn truncate_notes(mut notes: String) -> String {
+    if notes.len() > MAX_RELEASE_NOTES_BYTES {
+        let mut end = MAX_RELEASE_NOTES_BYTES;
+        while !notes.is_char_boundary(end) {
+            end -= 1;
+        }
+        notes.truncate(end);
+        notes.push_str("\n\n…");
+    }
+    notes
+}
+
+/// Compare the release described by the GitHub API response `body` with
+/// `current_version`
+fn update_info(body: &str, current_version: &str) -> Result<UpdateInfo, String> {
+    let release: GithubRelease =
+        serde_json::from_str(body).map_err(|e| format!("Invalid release response: {e}"))?;
+    let current = Version::parse(current_version)
+        .map_err(|e| format!("Invalid app version {current_version}: {e}"))?;
+    let latest = parse_tag(&release.tag_name)
+        .ok_or_else(|| format!("Release tag {:?} is not a version", release.tag_name))?;
+    if !is_release_url(&release.html_url) {
+        return Err("Release page is outside the app's repository".to_string());
+    }
+    let download_url = ASSET_EXTENSIONS
+        .iter()
+        .find_map(|extension| {
+            release
+                .assets
+                .iter()
+                .find(|asset| asset.name.ends_with(extension))
+        })
+        .map(|asset| asset.browser_download_url.clone())
+        .filter(|url| is_release_url(url))
+        .unwrap_or_else(|| release.html_url.clone());
+
+    Ok(UpdateInfo {
+        current_version: current.to_string(),
+        latest_version: latest.to_string(),
+        update_available: latest > current,
+        release_notes: truncate_notes(release.body.unwrap_or_default()),
+        release_url: release.html_url,
+        download_url,
+        published_at: release.published_at,
+    })
+}
+
+/// Ask GitHub for the latest release and compare it with this build
+pub(crate) async fn check_for_updates(proxy: &ProxySettings) -> Result<UpdateInfo, String> {
+    let client = http_client_builder(proxy)?
+        .timeout(UPDATE_CHECK_TIMEOUT)
+        .user_agent(concat!("ThoughtTree/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {e}"))?;
+    let response = client
+        .get(LATEST_RELEASE_URL)
+        .header("Accept", "application/vnd.github+json")
+        .send()
+        .await
+        .map_err(|e| format!("Failed to check for updates: {}", e.without_url()))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("Update check failed: {status}"));
+    }
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read release info: {}", e.without_url()))?;
+    update_info(&body, env!("CARGO_PKG_VERSION"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(tag: &str, assets: &str) -> String {
+        format!(
+            r#"{{
+                "tag_name": "{tag}",
+                "html_url": "https://github.com/davidchris/thoughttree/releases/tag/{tag}",
+                "body": "- Faster search",
+                "published_at": "2025-06-01T12:00:00Z",
+                "assets": {assets}
+            }}"#
+        )
+    }
+
+    #[test]
+    fn test_update_info_compares_versions() {
+        let info = update_info(&release("v0.4.0", "[]"), "0.3.3").unwrap();
+        assert!(info.update_available);
+        assert_eq!(info.latest_version, "0.4.0");
+        assert_eq!(info.release_notes, "- Faster search");
+        assert_eq!(info.download_url, info.release_url);
+
+        assert!(
+            !update_info(&release("v0.3.3", "[]"), "0.3.3")
+                .unwrap()
+                .update_available
+        );
+        assert!(
+            !update_info(&release("app-v0.3.2", "[]"), "0.3.3")
+                .unwrap()
+                .update_available
+        );
+        // Prereleases sort before the release they lead up to
+        assert!(
+            !update_info(&release("v0.3.3-beta.1", "[]"), "0.3.3")
+                .unwrap()
+                .update_available
+        );
+        assert!(
+            update_info(&release("v0.4.0-dev", "[]"), "0.3.3")
+                .unwrap()
+                .update_available
+        );
+        assert!(update_info(&release("nightly", "[]"), "0.3.3").is_err());
+    }
+
+    #[test]
+    fn test_update_info_only_links_to_the_repository() {
+        let extension = ASSET_EXTENSIONS[0];
+        let assets = format!(
+            r#"[{{"name": "ThoughtTree{extension}", "browser_download_url": "https://github.com/davidchris/thoughttree/releases/download/v0.4.0/ThoughtTree{extension}"}}]"#
+        );
+        let info = update_info(&release("v0.4.0", &assets), "0.3.3").unwrap();
+        assert!(info.download_url.ends_with(extension));
+
+        let assets = format!(
+            r#"[{{"name": "ThoughtTree{extension}", "browser_download_url": "https://evil.example/ThoughtTree{extension}"}}]"#
+        );
+        let info = update_info(&release("v0.4.0", &assets), "0.3.3").unwrap();
+        assert_eq!(info.download_url, info.release_url);
+
+        let moved = release("v0.4.0", "[]").replace("github.com/davidchris", "example.com/x");
+        assert!(update_info(&moved, "0.3.3").is_err());
+    }
+}
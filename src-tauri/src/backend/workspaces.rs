@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+
+/// Name given to the workspace migrated from the old single `notes_directory`
+pub(crate) const DEFAULT_WORKSPACE_NAME: &str = "Default";
+
+const MAX_WORKSPACE_NAME_CHARS: usize = 64;
+
+/// A named notes directory
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct Workspace {
+    pub name: String,
+    pub path: String,
+}
+
+/// All configured workspaces and which one is active. Every notes-directory
+/// lookup (prompts, permission checks, search) resolves the active one.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct WorkspaceConfig {
+    pub workspaces: Vec<Workspace>,
+    pub active: Option<String>,
+}
+
+fn validate_name(name: &str) -> Result<&str, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Workspace name cannot be empty".to_string());
+    }
+    if name.chars().count() > MAX_WORKSPACE_NAME_CHARS {
+        return Err(format!(
+            "Workspace name is longer than {MAX_WORKSPACE_NAME_CHARS} characters"
+        ));
+    }
+    Ok(name)
+}
+
+impl WorkspaceConfig {
+    /// Config equivalent to a pre-workspace `notes_directory` setting
+    pub(crate) fn from_legacy(notes_directory: Option<String>) -> Self {
+        match notes_directory {
+            Some(path) => Self {
+                workspaces: vec![Workspace {
+                    name: DEFAULT_WORKSPACE_NAME.to_string(),
+                    path,
+                }],
+                active: Some(DEFAULT_WORKSPACE_NAME.to_string()),
+            },
+            None => Self::default(),
+        }
+    }
+
+    fn find(&self, name: &str) -> Option<&Workspace> {
+        self.workspaces
+            .iter()
+            .find(|w| w.name.eq_ignore_ascii_case(name))
+    }
+
+    /// The active workspace, falling back to the first one if the active name
+    /// is unset or stale
+    pub(crate) fn active_workspace(&self) -> Option<&Workspace> {
+        self.active
+            .as_deref()
+            .and_then(|name| self.find(name))
+            .or_else(|| self.workspaces.first())
+    }
+
+    pub(crate) fn add(&mut self, name: &str, path: &str) -> Result<(), String> {
+        let name = validate_name(name)?;
+        if self.find(name).is_some() {
+            return Err(format!("A workspace named \"{name}\" already exists"));
+        }
+        self.workspaces.push(Workspace {
+            name: name.to_string(),
+            path: path.to_string(),
+        });
+        if self.active.is_none() {
+            self.active = Some(name.to_string());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn set_active(&mut self, name: &str) -> Result<(), String> {
+        let workspace = self
+            .find(name)
+            .ok_or_else(|| format!("No workspace named \"{name}\""))?;
+        self.active = Some(workspace.name.clone());
+        Ok(())
+    }
+
+    /// Remove a workspace; if it was active, the first remaining one becomes active
+    pub(crate) fn remove(&mut self, name: &str) -> Result<(), String> {
+        let before = self.workspaces.len();
+        self.workspaces
+            .retain(|w| !w.name.eq_ignore_ascii_case(name));
+        if self.workspaces.len() == before {
+            return Err(format!("No workspace named \"{name}\""));
+        }
+        if self
+            .active
+            .as_deref()
+            .is_some_and(|a| a.eq_ignore_ascii_case(name))
+        {
+            self.active = self.workspaces.first().map(|w| w.name.clone());
+        }
+        Ok(())
+    }
+
+    /// Point the active workspace at `path`, creating a default workspace
+    /// when none exists. Keeps `set_notes_directory` working.
+    pub(crate) fn set_active_path(&mut self, path: &str) {
+        let active = self.active_workspace().map(|w| w.name.clone());
+        match active.and_then(|name| self.workspaces.iter_mut().find(|w| w.name == name)) {
+            Some(workspace) => workspace.path = path.to_string(),
+            None => {
+                self.workspaces.push(Workspace {
+                    name: DEFAULT_WORKSPACE_NAME.to_string(),
+                    path: path.to_string(),
+                });
+                self.active = Some(DEFAULT_WORKSPACE_NAME.to_string());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_directory_becomes_default_workspace() {
+        let config = WorkspaceConfig::from_legacy(Some("/notes".to_string()));
+        assert_eq!(config.active_workspace().unwrap().path, "/notes");
+        assert_eq!(
+            WorkspaceConfig::from_legacy(None),
+            WorkspaceConfig::default()
+        );
+    }
+
+    #[test]
+    fn test_add_switch_and_remove() {
+        let mut config = WorkspaceConfig::default();
+        config.add("Work", "/work").unwrap();
+        config.add(" Personal ", "/personal").unwrap();
+        assert!(config.add("work", "/other").is_err());
+        assert!(config.add("  ", "/other").is_err());
+        assert_eq!(config.active_workspace().unwrap().name, "Work");
+
+        config.set_active("personal").unwrap();
+        assert_eq!(config.active_workspace().unwrap().path, "/personal");
+        assert!(config.set_active("missing").is_err());
+
+        config.remove("Personal").unwrap();
+        assert_eq!(config.active.as_deref(), Some("Work"));
+    }
+
+    #[test]
+    fn test_set_active_path_updates_or_creates() {
+        let mut config = WorkspaceConfig::default();
+        config.set_active_path("/a");
+        assert_eq!(
+            config.active_workspace().unwrap().name,
+            DEFAULT_WORKSPACE_NAME
+        );
+
+        config.set_active_path("/b");
+        assert_eq!(config.workspaces.len(), 1);
+        assert_eq!(config.active_workspace().unwrap().path, "/b");
+    }
+}
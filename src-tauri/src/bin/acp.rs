@@ -0,0 +1,5 @@
+//! Terminal front end for the ACP backend, see `acp --repl`
+
+fn main() {
+    std::process::exit(thoughttree_lib::run_acp_cli())
+}
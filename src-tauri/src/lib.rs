@@ -1,57 +1,296 @@
 mod backend;
 
 use backend::commands::{
-    add_recent_project, check_acp_available, export_markdown, generate_summary,
-    get_available_models, get_available_providers, get_default_provider, get_model_preferences,
-    get_notes_directory, get_provider_paths, get_recent_projects, load_project, new_project_dialog,
-    open_project_dialog, pick_notes_directory, pick_provider_executable, remove_recent_project,
-    respond_to_permission, save_project, search_files, send_prompt, set_default_provider,
-    set_model_preference, set_notes_directory, set_provider_path, validate_provider_path,
+    add_recent_project, add_workspace, append_to_note, authenticate_provider, batch_generate,
+    check_acp_available, check_for_updates, clear_audit_log, clear_usage_data, close_project,
+    copy_subtree_markdown, count_tokens, create_project_from_template, delete_secret, diff_nodes,
+    diff_texts, dismiss_recovered_content, export_graph, export_json_canvas, export_markdown,
+    export_opml, export_pdf, export_transcript, export_tree_markdown, find_similar_nodes,
+    force_unlock_project, generate_abstract, generate_alternatives, generate_summaries,
+    generate_summary, get_agent_commands, get_agent_env, get_agent_sandbox_enabled, get_all_tags,
+    get_analytics_enabled, get_audit_log, get_auth_methods, get_available_models,
+    get_available_providers, get_background_job_settings, get_background_jobs, get_backlinks,
+    get_bulk_model_overrides, get_check_updates_on_launch, get_claude_options,
+    get_compact_ancestors, get_compress_projects, get_default_provider, get_dictation_settings,
+    get_env_allowlist, get_export_filename_template, get_feature_matrix, get_gemini_settings,
+    get_generation_queue, get_git_autocommit_enabled, get_image_assets, get_image_max_dimension,
+    get_latency_report, get_max_retries, get_model_preferences, get_model_presets,
+    get_node_generation_config, get_node_history, get_note_metadata, get_note_resource_links,
+    get_note_writes_enabled, get_notes_by_tag, get_notes_directory, get_npx_fallback_enabled,
+    get_outgoing_links, get_path_lookup_enabled, get_performance_stats,
+    get_permission_timeout_secs, get_project_git_log, get_project_stats, get_prompt_preamble,
+    get_prompt_timeout_secs, get_provider_paths, get_provider_versions, get_proxy_settings,
+    get_quick_capture_shortcut, get_quick_capture_target, get_read_roots, get_recent_logs,
+    get_recent_projects, get_retry_on_crash, get_safe_mode, get_session_mode_preferences,
+    get_session_modes, get_summary_style, get_system_prompt, get_usage_report, has_secret,
+    import_conversation, import_opml, list_pinned, list_project_templates, list_workspaces,
+    load_node_content, load_project, load_project_manifest, lookup_provider_on_path,
+    migrate_project, new_project_dialog, open_project_dialog, pick_notes_directory,
+    pick_provider_executable, pin_node, query_project_index, query_search_index, queue_autosave,
+    quick_capture, read_note, rebuild_search_index, recover_pending_content, regenerate_node,
+    reindex_projects, reload_project_if_changed, remove_agent_env_var, remove_recent_project,
+    remove_workspace, replay_stream, respond_to_permission, restore_node_version, run_health_check,
+    save_project, search_files, search_note_contents, search_projects, send_prompt,
+    send_prompt_multi, set_active_workspace, set_agent_env_var, set_agent_sandbox_enabled,
+    set_analytics_enabled, set_background_job_settings, set_bulk_model_override,
+    set_check_updates_on_launch, set_claude_options, set_compact_ancestors, set_compress_projects,
+    set_default_provider, set_dictation_settings, set_env_allowlist, set_export_filename_template,
+    set_gemini_settings, set_git_autocommit_enabled, set_image_assets, set_image_max_dimension,
+    set_max_concurrent_generations, set_max_retries, set_model_preference, set_model_preset,
+    set_note_resource_links, set_note_writes_enabled, set_notes_directory,
+    set_npx_fallback_enabled, set_path_lookup_enabled, set_permission_timeout_secs,
+    set_prompt_preamble, set_prompt_timeout_secs, set_provider_path, set_proxy_settings,
+    set_quick_capture_shortcut, set_quick_capture_target, set_read_roots, set_retry_on_crash,
+    set_safe_mode, set_secret, set_session_mode_preference, set_summary_style, set_system_prompt,
+    start_dictation, stop_dictation, suggest_context, suggest_tags, test_proxy, unpin_node,
+    validate_provider_path,
 };
 use backend::state::AppState;
+use tauri::{AppHandle, Manager, RunEvent, WindowEvent};
 
-pub fn run() {
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::from_default_env()
-                .add_directive("info".parse().unwrap()),
-        )
-        .init();
+/// Kill all agent subprocesses, write pending autosaves and release project
+/// locks. Runs when the main window goes away (the tray can keep the process
+/// alive after that) and again on exit, when nothing is left to do.
+fn release_resources(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    state.children.terminate_all();
+    backend::commands::flush_autosaves(app);
+    state.project_locks.release_all();
+}
 
+/// Entry point of the `acp` binary: `acp --repl` chats with an agent in the
+/// terminal. Returns the exit code.
+pub fn run_acp_cli() -> i32 {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    backend::acp::repl::run_cli(&args)
+}
+
+pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_store::Builder::default().build())
         .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
         .manage(AppState::default())
+        .setup(|app| {
+            // The log directory is only known once the app exists
+            let log_dir = backend::logging::log_dir(app.handle())
+                .inspect_err(|e| eprintln!("File logging disabled: {e}"))
+                .ok();
+            backend::logging::init(log_dir.as_deref());
+            backend::safe_mode::init(app.handle());
+            match backend::config::get_max_concurrent_generations(app.handle()) {
+                Ok(max) => app
+                    .state::<AppState>()
+                    .generation_queue
+                    .set_max_concurrent(max),
+                Err(e) => tracing::warn!("Failed to read generation concurrency: {}", e),
+            }
+            backend::commands::start_background_jobs(app.handle().clone());
+            backend::commands::start_project_watch(app.handle().clone());
+            backend::commands::start_update_check(app.handle().clone());
+            // A user-configured global shortcut is the kind of setting safe
+            // mode has to get past, so the tray and shortcut stay off
+            #[cfg(desktop)]
+            if !backend::safe_mode::is_enabled(app.handle()) {
+                app.handle().plugin(backend::tray::shortcut_plugin())?;
+                backend::tray::init_capture_shortcut(app.handle());
+                backend::tray::init_tray(app.handle())?;
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             send_prompt,
+            send_prompt_multi,
+            generate_alternatives,
+            regenerate_node,
+            replay_stream,
+            recover_pending_content,
+            dismiss_recovered_content,
             respond_to_permission,
             check_acp_available,
+            get_latency_report,
+            get_performance_stats,
+            get_prompt_timeout_secs,
+            set_prompt_timeout_secs,
+            get_permission_timeout_secs,
+            set_permission_timeout_secs,
+            get_image_max_dimension,
+            set_image_max_dimension,
+            get_system_prompt,
+            set_system_prompt,
+            get_note_resource_links,
+            set_note_resource_links,
+            get_prompt_preamble,
+            set_prompt_preamble,
+            count_tokens,
+            get_compact_ancestors,
+            set_compact_ancestors,
+            get_retry_on_crash,
+            set_retry_on_crash,
+            get_max_retries,
+            set_max_retries,
+            get_generation_queue,
+            set_max_concurrent_generations,
             get_available_providers,
+            get_provider_versions,
+            get_feature_matrix,
             get_default_provider,
             set_default_provider,
             get_model_preferences,
             set_model_preference,
+            get_model_presets,
+            set_model_preset,
             get_available_models,
+            get_agent_commands,
+            get_session_modes,
+            get_session_mode_preferences,
+            set_session_mode_preference,
+            get_auth_methods,
+            authenticate_provider,
             get_provider_paths,
             set_provider_path,
             validate_provider_path,
             pick_provider_executable,
+            get_path_lookup_enabled,
+            set_path_lookup_enabled,
+            get_npx_fallback_enabled,
+            set_npx_fallback_enabled,
+            get_agent_env,
+            set_agent_env_var,
+            remove_agent_env_var,
+            get_env_allowlist,
+            set_env_allowlist,
+            get_agent_sandbox_enabled,
+            set_agent_sandbox_enabled,
+            get_read_roots,
+            set_read_roots,
+            get_proxy_settings,
+            set_proxy_settings,
+            get_gemini_settings,
+            set_gemini_settings,
+            get_claude_options,
+            set_claude_options,
+            test_proxy,
+            check_for_updates,
+            quick_capture,
+            get_quick_capture_target,
+            set_quick_capture_target,
+            get_quick_capture_shortcut,
+            set_quick_capture_shortcut,
+            get_check_updates_on_launch,
+            set_check_updates_on_launch,
+            set_secret,
+            has_secret,
+            delete_secret,
+            lookup_provider_on_path,
             get_notes_directory,
             set_notes_directory,
+            list_workspaces,
+            add_workspace,
+            set_active_workspace,
+            remove_workspace,
             pick_notes_directory,
             save_project,
+            queue_autosave,
+            reload_project_if_changed,
+            close_project,
+            force_unlock_project,
+            load_project_manifest,
+            load_node_content,
+            get_node_generation_config,
+            get_project_stats,
+            diff_texts,
+            diff_nodes,
+            start_dictation,
+            stop_dictation,
+            get_dictation_settings,
+            set_dictation_settings,
+            get_compress_projects,
+            set_compress_projects,
+            get_image_assets,
+            set_image_assets,
+            migrate_project,
+            list_project_templates,
+            create_project_from_template,
+            get_git_autocommit_enabled,
+            set_git_autocommit_enabled,
+            get_project_git_log,
+            get_node_history,
+            restore_node_version,
+            find_similar_nodes,
+            suggest_context,
+            get_background_jobs,
+            get_background_job_settings,
+            set_background_job_settings,
             load_project,
             new_project_dialog,
             open_project_dialog,
             export_markdown,
+            export_tree_markdown,
+            export_opml,
+            import_opml,
+            import_conversation,
+            export_json_canvas,
+            export_pdf,
+            export_graph,
+            export_transcript,
+            copy_subtree_markdown,
+            get_export_filename_template,
+            set_export_filename_template,
             get_recent_projects,
             add_recent_project,
             remove_recent_project,
+            pin_node,
+            unpin_node,
+            list_pinned,
             search_files,
+            search_note_contents,
+            search_projects,
+            rebuild_search_index,
+            reindex_projects,
+            query_search_index,
+            query_project_index,
+            get_backlinks,
+            get_outgoing_links,
+            get_all_tags,
+            get_notes_by_tag,
+            get_note_metadata,
+            read_note,
+            append_to_note,
+            get_note_writes_enabled,
+            set_note_writes_enabled,
             generate_summary,
+            generate_summaries,
+            get_summary_style,
+            set_summary_style,
+            suggest_tags,
+            batch_generate,
+            generate_abstract,
+            get_safe_mode,
+            set_safe_mode,
+            get_audit_log,
+            clear_audit_log,
+            get_recent_logs,
+            run_health_check,
+            get_analytics_enabled,
+            set_analytics_enabled,
+            get_usage_report,
+            clear_usage_data,
+            get_bulk_model_overrides,
+            set_bulk_model_override,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| match event {
+            // Don't leave agents running if the window closes mid-generation
+            RunEvent::WindowEvent {
+                label,
+                event: WindowEvent::CloseRequested { .. } | WindowEvent::Destroyed,
+                ..
+            } if label == backend::tray::MAIN_WINDOW => release_resources(app),
+            RunEvent::ExitRequested { .. } | RunEvent::Exit => release_resources(app),
+            _ => {}
+        });
 }
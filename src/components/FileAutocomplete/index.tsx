@@ -1,6 +1,6 @@
 import { useState, useEffect, useRef, useCallback, forwardRef, useImperativeHandle } from 'react';
 import { createPortal } from 'react-dom';
-import { searchFiles } from '../../lib/tauri';
+import { searchFiles, type FileMatch } from '../../lib/tauri';
 import { logger } from '../../lib/logger';
 import './styles.css';
 
@@ -16,9 +16,27 @@ export interface FileAutocompleteRef {
   handleKeyDown: (e: KeyboardEvent) => boolean;
 }
 
+/** Render a path with its fuzzy-matched characters highlighted */
+function HighlightedPath({ path, indices }: { path: string; indices: number[] }) {
+  const matched = new Set(indices);
+  return (
+    <span className="file-path">
+      {Array.from(path).map((char, i) =>
+        matched.has(i) ? (
+          <mark key={i} className="file-path-match">
+            {char}
+          </mark>
+        ) : (
+          char
+        )
+      )}
+    </span>
+  );
+}
+
 export const FileAutocomplete = forwardRef<FileAutocompleteRef, FileAutocompleteProps>(
   function FileAutocomplete({ isOpen, query, position, onSelect, onClose }, ref) {
-    const [files, setFiles] = useState<string[]>([]);
+    const [files, setFiles] = useState<FileMatch[]>([]);
     const [selectedIndex, setSelectedIndex] = useState(0);
     const [isLoading, setIsLoading] = useState(false);
     const listRef = useRef<HTMLDivElement>(null);
@@ -35,7 +53,7 @@ export const FileAutocomplete = forwardRef<FileAutocompleteRef, FileAutocomplete
       setIsLoading(true);
       const timeoutId = setTimeout(async () => {
         try {
-          const results = await searchFiles(query, 15);
+          const results = await searchFiles(query, 15, 2);
           setFiles(results);
           setSelectedIndex(0);
         } catch (error) {
@@ -108,7 +126,7 @@ export const FileAutocomplete = forwardRef<FileAutocompleteRef, FileAutocomplete
           case 'Enter':
           case 'Tab':
             if (files[selectedIndex]) {
-              onSelect(files[selectedIndex]);
+              onSelect(files[selectedIndex].path);
             }
             return true;
           case 'Escape':
@@ -135,12 +153,13 @@ export const FileAutocomplete = forwardRef<FileAutocompleteRef, FileAutocomplete
           )}
           {files.map((file, index) => (
             <div
-              key={file}
+              key={file.path}
               className={`file-autocomplete-item ${index === selectedIndex ? 'selected' : ''}`}
-              onClick={() => onSelect(file)}
+              onClick={() => onSelect(file.path)}
               onMouseEnter={() => setSelectedIndex(index)}
             >
-              <span className="file-path">{file}</span>
+              <HighlightedPath path={file.path} indices={file.indices} />
+              {file.preview && <span className="file-preview">{file.preview}</span>}
             </div>
           ))}
         </div>
@@ -32,6 +32,16 @@ export function PermissionDialog() {
         <div className="permission-content">
           <p className="tool-name">{pendingPermission.toolName}</p>
           <p className="tool-description">{pendingPermission.description}</p>
+          {pendingPermission.rawInput != null && (
+            <pre className="tool-details">{JSON.stringify(pendingPermission.rawInput, null, 2)}</pre>
+          )}
+          {pendingPermission.content.map((item, index) => (
+            <pre key={index} className="tool-details">
+              {item.type === 'diff'
+                ? `${item.path}\n--- old\n${item.old_text ?? ''}\n+++ new\n${item.new_text}`
+                : item.text}
+            </pre>
+          ))}
         </div>
 
         <div className="permission-actions">
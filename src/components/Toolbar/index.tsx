@@ -1,6 +1,7 @@
 import { useState } from 'react';
 import { invoke } from '@tauri-apps/api/core';
-import { useGraphStore } from '../../store/useGraphStore';
+import { serializeProject, useGraphStore } from '../../store/useGraphStore';
+import { exportTreeMarkdown } from '../../lib/tauri';
 import { SettingsDialog } from '../SettingsDialog';
 import { logger } from '../../lib/logger';
 import './Toolbar.css';
@@ -9,6 +10,8 @@ export function Toolbar() {
   const projectPath = useGraphStore((state) => state.projectPath);
   const isDirty = useGraphStore((state) => state.isDirty);
   const lastSavedAt = useGraphStore((state) => state.lastSavedAt);
+  const projectChangedExternally = useGraphStore((state) => state.projectChangedExternally);
+  const reloadProjectIfChanged = useGraphStore((state) => state.reloadProjectIfChanged);
   const nodes = useGraphStore((state) => state.nodes);
   const selectedNodeId = useGraphStore((state) => state.selectedNodeId);
   const setProjectPath = useGraphStore((state) => state.setProjectPath);
@@ -122,9 +125,17 @@ export function Toolbar() {
   const handleExportAll = async () => {
     if (nodes.length === 0) return;
 
-    const allNodeIds = nodes.map((n) => n.id);
-    const markdown = exportSubgraph(allNodeIds);
-    await doExport(markdown, 'full-export.md');
+    try {
+      const path = await exportTreeMarkdown(
+        serializeProject(useGraphStore.getState()),
+        'full-export.md'
+      );
+      if (path) {
+        logger.info('Exported to:', path);
+      }
+    } catch (error) {
+      logger.error('Failed to export:', error);
+    }
   };
 
   const doExport = async (content: string, defaultName: string) => {
@@ -160,6 +171,19 @@ export function Toolbar() {
         {lastSavedAt && (
           <span className="last-saved">{formatLastSaved()}</span>
         )}
+        {projectChangedExternally && (
+          <button
+            className="changed-on-disk"
+            onClick={() => {
+              reloadProjectIfChanged().catch((error) => {
+                logger.error('Failed to reload project:', error);
+              });
+            }}
+            title="The project file was changed by another program. Reload it (discarding unsaved edits) or Save to overwrite it."
+          >
+            Changed on disk – Reload
+          </button>
+        )}
       </div>
 
       <div className="toolbar-center">
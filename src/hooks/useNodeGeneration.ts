@@ -42,7 +42,8 @@ export function useNodeGeneration() {
           context,
           (chunk) => appendToNode(agentNodeId, chunk),
           provider,
-          modelId
+          modelId,
+          useGraphStore.getState().projectPath
         );
       } catch (error) {
         logger.error('Generation failed:', error);
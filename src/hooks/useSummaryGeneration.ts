@@ -6,6 +6,8 @@ import { logger } from '../lib/logger';
 
 const SUMMARY_THRESHOLD = 100; // Characters - content shorter than this uses content directly
 const DEBOUNCE_MS = 1500;      // Wait for content to stabilize before generating
+const BATCH_SIZE = 20;         // Nodes titled per agent session (backend allows 50)
+const BATCH_COLLECT_MS = 50;   // Let debounce timers firing together join one batch
 
 interface SummaryResult {
   node_id: string;
@@ -21,7 +23,8 @@ export function hasFreshSummary(data: Pick<MessageNodeData, 'timestamp' | 'conte
   return data.summaryTimestamp >= getContentVersionTimestamp(data);
 }
 
-// Global queue for serializing summary generation (only one ACP subprocess at a time)
+// Global queue for serializing summary generation (only one ACP subprocess at a time).
+// Whatever is queued when a batch starts shares one agent session.
 const summaryQueue: Array<{ nodeId: string; content: string; resolve: (result: SummaryResult) => void; reject: (error: unknown) => void }> = [];
 let isProcessingQueue = false;
 
@@ -29,17 +32,27 @@ async function processQueue() {
   if (isProcessingQueue || summaryQueue.length === 0) return;
 
   isProcessingQueue = true;
+  await new Promise(resolve => setTimeout(resolve, BATCH_COLLECT_MS));
 
   while (summaryQueue.length > 0) {
-    const item = summaryQueue.shift()!;
+    const batch = summaryQueue.splice(0, BATCH_SIZE);
     try {
-      const result = await invoke<SummaryResult>('generate_summary', {
-        nodeId: item.nodeId,
-        content: item.content,
+      const results = await invoke<SummaryResult[]>('generate_summaries', {
+        nodes: batch.map((item) => ({ node_id: item.nodeId, content: item.content })),
       });
-      item.resolve(result);
+      const byNode = new Map(results.map((result) => [result.node_id, result]));
+      for (const item of batch) {
+        const result = byNode.get(item.nodeId);
+        if (result) {
+          item.resolve(result);
+        } else {
+          item.reject(new Error('No summary generated'));
+        }
+      }
     } catch (error) {
-      item.reject(error);
+      for (const item of batch) {
+        item.reject(error);
+      }
     }
     // Small delay between calls to let subprocess clean up
     await new Promise(resolve => setTimeout(resolve, 500));
@@ -1,13 +1,16 @@
 import { invoke } from '@tauri-apps/api/core';
 import { listen, UnlistenFn } from '@tauri-apps/api/event';
 import { useUIStore } from '../store/useUIStore';
-import type { AgentProvider, ImageAttachment, ModelInfo, ModelPreferences, PermissionRequest, ProviderPaths, ProviderStatus } from '../types';
+import type { AgentProvider, ClaudeOptions, FileAttachment, ImageAttachment, MessageNodeData, ModelInfo, ModelPreferences, ModelPreset, ModelPresets, NodeGenerationConfig, PermissionContent, PermissionRequest, ProviderPaths, ProviderStatus } from '../types';
 
 // Message format with optional images for IPC
 interface MessageWithImages {
   role: string;
   content: string;
   images?: ImageAttachment[];
+  attachments?: FileAttachment[];
+  /** Notes (relative to the notes directory) included as context */
+  contextPaths?: string[];
 }
 
 // Backend-compatible message image format (snake_case)
@@ -16,27 +19,227 @@ interface BackendMessageImage {
   mime_type: string;
 }
 
+interface BackendMessageAttachment {
+  name: string;
+  mime_type: string;
+  data: string;
+}
+
 interface BackendMessage {
   role: string;
   content: string;
   images: BackendMessageImage[] | null;
+  attachments: BackendMessageAttachment[] | null;
+  context_paths: string[] | null;
 }
 
 interface ChunkPayload {
   node_id: string;
+  seq: number;
   chunk: string;
 }
 
+// Per-phase timings of a prompt turn, in milliseconds
+export interface PromptTimings {
+  spawn_ms: number;
+  initialize_ms: number;
+  new_session_ms: number;
+  model_switch_ms: number | null;
+  first_chunk_ms: number | null;
+  completion_ms: number;
+  total_ms: number;
+}
+
+/** How an answer streamed */
+export interface Throughput {
+  chunk_count: number;
+  response_chars: number;
+  /** From the first chunk to the end; null when the answer came in one go */
+  chars_per_sec: number | null;
+}
+
+/** Typical speed of one provider/model over recent prompts */
+export interface ModelPerformance {
+  provider: AgentProvider;
+  /** null when the agent's default model was used */
+  model_id: string | null;
+  samples: number;
+  first_chunk_p50_ms: number | null;
+  total_p50_ms: number;
+  total_p90_ms: number;
+  chars_per_sec_p50: number | null;
+  mean_chunk_count: number;
+}
+
+export interface PerformanceStats {
+  sample_count: number;
+  models: ModelPerformance[];
+}
+
+/** Compare providers and models by latency and streaming speed */
+export async function getPerformanceStats(): Promise<PerformanceStats> {
+  return invoke<PerformanceStats>('get_performance_stats');
+}
+
+/** A `[[wikilink]]` in the prompt and how much of its note was included */
+export interface LinkedNoteReport {
+  target: string;
+  /** Resolved note path, or null when no note matches */
+  path: string | null;
+  included_bytes: number;
+  truncated: boolean;
+}
+
+/** What was left out of a prompt to fit the model's context window */
+export interface ContextTruncation {
+  budget_tokens: number;
+  estimated_tokens: number;
+  sent_tokens: number;
+  /** Oldest messages dropped entirely */
+  omitted_messages: number;
+  /** Whether the oldest message sent lost its beginning */
+  truncated_message: boolean;
+}
+
+/** Why the agent ended its turn; `max-tokens` answers can be continued */
+export type StopReason = 'end-turn' | 'max-tokens' | 'refusal' | 'cancelled' | 'error';
+
+export interface PromptResult {
+  stop_reason: StopReason;
+  timings: PromptTimings;
+  linked_notes: LinkedNoteReport[];
+  context_truncation: ContextTruncation | null;
+  /** Older messages replaced by a summary; 0 unless compaction is on */
+  compacted_messages: number;
+  throughput: Throughput;
+  /** Tags this generation's log lines; see `getRecentLogs` */
+  trace_id: string;
+}
+
+/**
+ * Phase of a node's prompt session. A retry (after a crash or a transient
+ * error) is announced by `generation-retrying` and starts over at
+ * `spawning`; `failed` is only reported once no retry is left. Cancelled
+ * and timed-out sessions report neither `completed` nor `failed`.
+ */
+export type GenerationStatus =
+  | 'compacting'
+  | 'spawning'
+  | 'initializing'
+  | 'session-created'
+  | 'model-set'
+  | 'prompting'
+  | 'completed'
+  | 'failed';
+
+export interface GenerationStatusPayload {
+  node_id: string;
+  trace_id: string;
+  status: GenerationStatus;
+  /** Set with `failed` */
+  error: string | null;
+}
+
+/** Follow the session phases of one node's generation */
+export async function listenGenerationStatus(
+  nodeId: string,
+  onStatus: (status: GenerationStatus, error: string | null) => void
+): Promise<UnlistenFn> {
+  return listen<GenerationStatusPayload>('generation-status', (event) => {
+    if (event.payload.node_id === nodeId) {
+      onStatus(event.payload.status, event.payload.error);
+    }
+  });
+}
+
+export interface GenerationStoppedPayload {
+  node_id: string;
+  trace_id: string;
+  stop_reason: StopReason;
+}
+
+/**
+ * Generations that stopped at the token limit or were refused, so the UI
+ * can offer to continue or explain the refusal
+ */
+export async function listenGenerationStopped(
+  onStopped: (payload: GenerationStoppedPayload) => void
+): Promise<UnlistenFn> {
+  return listen<GenerationStoppedPayload>('generation-stopped', (event) => {
+    onStopped(event.payload);
+  });
+}
+
+/** What a failed generation was doing */
+export type GenerationErrorCategory =
+  | 'spawn'
+  | 'connect'
+  | 'session'
+  | 'prompt'
+  | 'auth'
+  | 'crashed';
+
+export interface GenerationErrorPayload {
+  node_id: string;
+  trace_id: string;
+  category: GenerationErrorCategory;
+  message: string;
+  /** The agent's last stderr lines, oldest first */
+  stderr_tail: string[];
+}
+
+/** Failed generations, with enough detail to debug them from the UI */
+export async function listenGenerationError(
+  onError: (payload: GenerationErrorPayload) => void
+): Promise<UnlistenFn> {
+  return listen<GenerationErrorPayload>('generation-error', (event) => {
+    onError(event.payload);
+  });
+}
+
+export interface GenerationRetryingPayload {
+  node_id: string;
+  trace_id: string;
+  reason: 'rate-limited' | 'overloaded' | 'crashed';
+  /** Which retry this is, from 1 */
+  attempt: number;
+  max_retries: number;
+  delay_secs: number;
+  message: string;
+}
+
+/**
+ * Prompts that failed with a rate-limit or overload error (or a crash) and
+ * will be sent again after `delay_secs`, e.g. to show "retrying in 8s…"
+ */
+export async function listenGenerationRetrying(
+  onRetrying: (payload: GenerationRetryingPayload) => void
+): Promise<UnlistenFn> {
+  return listen<GenerationRetryingPayload>('generation-retrying', (event) => {
+    onRetrying(event.payload);
+  });
+}
+
 interface PermissionPayload {
   id: string;
   tool_type: string;
   tool_name: string;
   description: string;
   options: Array<{ id: string; label: string }>;
+  locations: string[];
+  raw_input: unknown;
+  content: PermissionContent[];
+}
+
+interface PermissionExpiredPayload {
+  id: string;
+  node_id: string;
+  timeout_secs: number;
 }
 
-// Global listener for permission requests
+// Global listeners for permission requests
 let permissionUnlisten: UnlistenFn | null = null;
+let permissionExpiredUnlisten: UnlistenFn | null = null;
 
 export async function initializeListeners(): Promise<void> {
   // Set up permission request listener
@@ -49,10 +252,55 @@ export async function initializeListeners(): Promise<void> {
         toolName: payload.tool_name,
         description: payload.description,
         options: payload.options,
+        locations: payload.locations ?? [],
+        rawInput: payload.raw_input ?? null,
+        content: payload.content ?? [],
       };
       useUIStore.getState().setPendingPermission(permission);
     });
   }
+
+  // Close the dialog of a request the backend gave up waiting on
+  if (!permissionExpiredUnlisten) {
+    permissionExpiredUnlisten = await listen<PermissionExpiredPayload>(
+      'permission-expired',
+      (event) => {
+        const { pendingPermission, setPendingPermission } = useUIStore.getState();
+        if (pendingPermission?.id === event.payload.id) {
+          setPendingPermission(null);
+        }
+      }
+    );
+  }
+}
+
+/**
+ * Convert messages to backend format with images.
+ * Filter out empty messages (e.g., placeholder assistant messages before streaming)
+ */
+function toBackendMessages(messages: MessageWithImages[]): BackendMessage[] {
+  return messages
+    .filter(
+      (m) =>
+        m.content.trim().length > 0 ||
+        (m.images && m.images.length > 0) ||
+        (m.attachments && m.attachments.length > 0) ||
+        (m.contextPaths && m.contextPaths.length > 0)
+    )
+    .map((m) => ({
+      role: m.role,
+      content: m.content,
+      images: m.images?.map((img) => ({
+        data: img.data,
+        mime_type: img.mimeType,
+      })) || null,
+      attachments: m.attachments?.map((file) => ({
+        name: file.name,
+        mime_type: file.mimeType,
+        data: file.data,
+      })) || null,
+      context_paths: m.contextPaths ?? null,
+    }));
 }
 
 export async function sendPrompt(
@@ -60,8 +308,10 @@ export async function sendPrompt(
   messages: MessageWithImages[],
   onChunk: (chunk: string) => void,
   provider?: AgentProvider,
-  modelId?: string
-): Promise<string> {
+  modelId?: string,
+  projectPath?: string | null,
+  preset?: ModelPreset
+): Promise<PromptResult> {
   // Set up listener for streaming chunks
   const unlisten = await listen<ChunkPayload>('stream-chunk', (event) => {
     if (event.payload.node_id === nodeId) {
@@ -70,29 +320,20 @@ export async function sendPrompt(
   });
 
   try {
-    // Convert messages to backend format with images
-    // Filter out empty messages (e.g., placeholder assistant messages before streaming)
-    const backendMessages: BackendMessage[] = messages
-      .filter((m) => m.content.trim().length > 0 || (m.images && m.images.length > 0))
-      .map((m) => ({
-        role: m.role,
-        content: m.content,
-        images: m.images?.map((img) => ({
-          data: img.data,
-          mime_type: img.mimeType,
-        })) || null,
-      }));
+    const backendMessages = toBackendMessages(messages);
 
     // Validate we have messages to send
     if (backendMessages.length === 0) {
       throw new Error('No valid messages to send');
     }
 
-    const result = await invoke<string>('send_prompt', {
+    const result = await invoke<PromptResult>('send_prompt', {
       nodeId,
       messages: backendMessages,
       provider: provider || null,
       modelId: modelId || null,
+      preset: preset || null,
+      projectPath: projectPath || null,
     });
 
     return result;
@@ -101,6 +342,71 @@ export async function sendPrompt(
   }
 }
 
+interface AlternativesCreatedPayload {
+  parent_node_id: string;
+  node_ids: string[];
+}
+
+export interface AlternativeOutcome {
+  node_id: string;
+  result: PromptResult | null;
+  error: string | null;
+}
+
+/**
+ * Generate `count` (2-8) alternative answers to the same conversation, each
+ * streaming into a new child node of `nodeId`. `onCreated` receives the
+ * child node ids before any chunk arrives. `temperatureHint` (0-1) asks
+ * for answers further from the obvious one.
+ */
+export async function generateAlternatives(
+  nodeId: string,
+  messages: MessageWithImages[],
+  count: number,
+  onCreated: (nodeIds: string[]) => void,
+  onChunk: (nodeId: string, chunk: string) => void,
+  temperatureHint?: number,
+  provider?: AgentProvider,
+  modelId?: string,
+  projectPath?: string | null
+): Promise<AlternativeOutcome[]> {
+  const childIds = new Set<string>();
+  const unlistenCreated = await listen<AlternativesCreatedPayload>(
+    'alternatives-created',
+    (event) => {
+      if (event.payload.parent_node_id === nodeId) {
+        event.payload.node_ids.forEach((id) => childIds.add(id));
+        onCreated(event.payload.node_ids);
+      }
+    }
+  );
+  const unlistenChunks = await listen<ChunkPayload>('stream-chunk', (event) => {
+    if (childIds.has(event.payload.node_id)) {
+      onChunk(event.payload.node_id, event.payload.chunk);
+    }
+  });
+
+  try {
+    const backendMessages = toBackendMessages(messages);
+    if (backendMessages.length === 0) {
+      throw new Error('No valid messages to send');
+    }
+
+    return await invoke<AlternativeOutcome[]>('generate_alternatives', {
+      nodeId,
+      messages: backendMessages,
+      count,
+      temperatureHint: temperatureHint ?? null,
+      provider: provider || null,
+      modelId: modelId || null,
+      projectPath: projectPath || null,
+    });
+  } finally {
+    unlistenCreated();
+    unlistenChunks();
+  }
+}
+
 export async function respondToPermission(requestId: string, optionId: string): Promise<void> {
   await invoke('respond_to_permission', {
     requestId,
@@ -108,12 +414,550 @@ export async function respondToPermission(requestId: string, optionId: string):
   });
 }
 
+/** Seconds a permission request waits for an answer before it is cancelled */
+export async function getPermissionTimeoutSecs(): Promise<number> {
+  return invoke<number>('get_permission_timeout_secs');
+}
+
+/** Set the permission request timeout; null restores the default */
+export async function setPermissionTimeoutSecs(secs: number | null): Promise<void> {
+  await invoke('set_permission_timeout_secs', { secs });
+}
+
 export async function checkAcpAvailable(): Promise<boolean> {
   return invoke<boolean>('check_acp_available');
 }
 
-export async function searchFiles(query: string, limit?: number): Promise<string[]> {
-  return invoke<string[]>('search_files', { query, limit });
+/** Longest edge, in pixels, that attached images are scaled down to before sending */
+export async function getImageMaxDimension(): Promise<number> {
+  return invoke<number>('get_image_max_dimension');
+}
+
+/** Set the image size limit; null restores the default */
+export async function setImageMaxDimension(pixels: number | null): Promise<void> {
+  await invoke('set_image_max_dimension', { pixels });
+}
+
+/**
+ * Instructions sent ahead of every prompt. A project's `systemPrompt`
+ * setting replaces it for that project.
+ */
+export async function getSystemPrompt(): Promise<string | null> {
+  return invoke<string | null>('get_system_prompt');
+}
+
+/** Set the global system prompt; null or blank clears it */
+export async function setSystemPrompt(prompt: string | null): Promise<void> {
+  await invoke('set_system_prompt', { prompt });
+}
+
+export async function getNoteResourceLinks(): Promise<boolean> {
+  return invoke<boolean>('get_note_resource_links');
+}
+
+/**
+ * Send referenced notes as resource links the agent reads on demand instead
+ * of inline text. Sessions that can't serve the reads still send the text.
+ */
+export async function setNoteResourceLinks(enabled: boolean): Promise<void> {
+  await invoke('set_note_resource_links', { enabled });
+}
+
+/** Retries of a prompt after rate-limit or overload errors (default 3) */
+export async function getMaxRetries(): Promise<number> {
+  return invoke<number>('get_max_retries');
+}
+
+/**
+ * Set how often a prompt is resent, with growing waits, after rate-limit or
+ * overload errors; 0 turns retries off and null restores the default
+ */
+export async function setMaxRetries(retries: number | null): Promise<void> {
+  await invoke('set_max_retries', { retries });
+}
+
+/** Approximate size of a text next to the model's context window */
+export interface TokenCount {
+  tokens: number;
+  context_window: number;
+}
+
+/** Estimate tokens for the given provider's model family (default provider when omitted) */
+export async function countTokens(
+  text: string,
+  provider?: AgentProvider,
+  model?: string
+): Promise<TokenCount> {
+  return invoke<TokenCount>('count_tokens', {
+    text,
+    provider: provider || null,
+    model: model || null,
+  });
+}
+
+/** Whether deep branches send a summary of older ancestors instead of replaying them */
+export async function getCompactAncestors(): Promise<boolean> {
+  return invoke<boolean>('get_compact_ancestors');
+}
+
+export async function setCompactAncestors(enabled: boolean): Promise<void> {
+  await invoke('set_compact_ancestors', { enabled });
+}
+
+/** Date line and other details sent ahead of every prompt */
+export interface PromptPreamble {
+  enabled: boolean;
+  /** strftime-style, e.g. "%B %d, %Y" */
+  date_format: string;
+  /** e.g. "de_DE"; English month names when null */
+  locale: string | null;
+  user_name: string | null;
+  include_timezone: boolean;
+  include_project_title: boolean;
+  /** Replaces the default lines; {date}, {time}, {timezone}, {user_name} and {project_title} are filled in */
+  template: string | null;
+}
+
+export async function getPromptPreamble(): Promise<PromptPreamble> {
+  return invoke<PromptPreamble>('get_prompt_preamble');
+}
+
+export async function setPromptPreamble(settings: PromptPreamble): Promise<void> {
+  await invoke('set_prompt_preamble', { settings });
+}
+
+/** How node headings are written */
+export interface SummaryStyle {
+  min_words: number;
+  max_words: number;
+  /** Longer headings are cut with an ellipsis */
+  max_chars: number;
+  /** e.g. "German"; the content's language when null */
+  language: string | null;
+  /** e.g. "neutral", "playful" */
+  tone: string | null;
+}
+
+export async function getSummaryStyle(): Promise<SummaryStyle> {
+  return invoke<SummaryStyle>('get_summary_style');
+}
+
+export async function setSummaryStyle(style: SummaryStyle): Promise<void> {
+  await invoke('set_summary_style', { style });
+}
+
+/** A 1-2 sentence abstract of a node, longer than its heading, for collapsed-branch previews */
+export async function generateAbstract(nodeId: string, content: string): Promise<string> {
+  const result = await invoke<{ node_id: string; summary: string }>('generate_abstract', {
+    nodeId,
+    content,
+  });
+  return result.summary;
+}
+
+/** A vault tag and how many notes carry it */
+export interface TagCount {
+  tag: string;
+  count: number;
+}
+
+export interface TagSuggestions {
+  node_id: string;
+  /** 2-4 existing vault tags, most fitting first; empty when the vault has no tags */
+  tags: TagCount[];
+  model_id: string | null;
+}
+
+/** Suggest existing vault tags for a node, to offer as chips */
+export async function suggestTags(nodeId: string, content: string): Promise<TagSuggestions> {
+  return invoke<TagSuggestions>('suggest_tags', { nodeId, content });
+}
+
+/** A fuzzy file-name match; `indices` are char positions in `path` to highlight */
+export interface FileMatch {
+  path: string;
+  score: number;
+  indices: number[];
+  /** First lines of the note, when `previewLines` was passed */
+  preview: string | null;
+}
+
+export async function searchFiles(
+  query: string,
+  limit?: number,
+  previewLines?: number
+): Promise<FileMatch[]> {
+  return invoke<FileMatch[]>('search_files', { query, limit, previewLines });
+}
+
+/** A line in a note containing the query, for a results panel that jumps to it */
+export interface ContentMatch {
+  /** Path relative to the notes directory */
+  path: string;
+  /** 1-based */
+  line_number: number;
+  /** 1-based char column of the first match */
+  column: number;
+  snippet: string;
+  /** `[start, end)` char ranges in `snippet` to highlight */
+  highlights: [number, number][];
+  /** Matching lines in the whole file, including any past the limit */
+  file_match_count: number;
+}
+
+/** Case-insensitive search of note contents, one match per line */
+export async function searchNoteContents(query: string, limit?: number): Promise<ContentMatch[]> {
+  return invoke<ContentMatch[]>('search_note_contents', { query, limit });
+}
+
+/** A node in a saved project whose text contains the query */
+export interface ProjectMatch {
+  /** Project path relative to the notes directory */
+  path: string;
+  node_id: string;
+  role: string;
+  /** Node summary or first line */
+  title: string;
+  snippet: string;
+}
+
+/** Search node text across the saved projects in the notes directory */
+export async function searchProjects(query: string, limit?: number): Promise<ProjectMatch[]> {
+  return invoke<ProjectMatch[]>('search_projects', { query, limit });
+}
+
+/** A ranked node from the cross-project index */
+export interface ProjectIndexHit {
+  /** Absolute project path */
+  path: string;
+  node_id: string;
+  title: string;
+  score: number;
+}
+
+/** Ranked search over the persistent index of every project in the workspaces */
+export async function queryProjectIndex(query: string, limit?: number): Promise<ProjectIndexHit[]> {
+  return invoke<ProjectIndexHit[]>('query_project_index', { query, limit });
+}
+
+/** A note's text; `truncated` is set when it was cut at `maxBytes` */
+export interface NoteContent {
+  path: string;
+  content: string;
+  truncated: boolean;
+  size: number;
+  modified: string | null;
+}
+
+export async function readNote(path: string, maxBytes?: number): Promise<NoteContent> {
+  return invoke<NoteContent>('read_note', { path, maxBytes });
+}
+
+/** Append to an existing markdown note; fails unless note writes are enabled */
+export async function appendToNote(path: string, content: string, heading?: string): Promise<void> {
+  await invoke('append_to_note', { path, content, heading });
+}
+
+export async function getNoteWritesEnabled(): Promise<boolean> {
+  return invoke<boolean>('get_note_writes_enabled');
+}
+
+export async function setNoteWritesEnabled(enabled: boolean): Promise<void> {
+  await invoke('set_note_writes_enabled', { enabled });
+}
+
+// ============================================================================
+// Chunked project loading (for very large projects)
+// ============================================================================
+
+export interface ManifestNode {
+  id: string;
+  role: 'user' | 'assistant';
+  title: string;
+  parentIds: string[];
+  timestamp: number;
+  provider: AgentProvider | null;
+  model: string | null;
+  /** Content size in bytes */
+  contentLength: number;
+  imageCount: number;
+}
+
+export interface ProjectManifest {
+  version: number;
+  nodes: ManifestNode[];
+  edges: Array<{ id: string; source: string; target: string }>;
+  layout: Array<{ id: string; position: { x: number; y: number } }>;
+  /** Project-level settings such as projectModelPreferences and notesDirectory */
+  settings: Record<string, unknown>;
+}
+
+/** Open a project without node bodies; fetch those with loadNodeContent */
+export async function loadProjectManifest(path: string): Promise<ProjectManifest> {
+  return invoke<ProjectManifest>('load_project_manifest', { path });
+}
+
+export async function loadNodeContent(path: string, ids: string[]): Promise<MessageNodeData[]> {
+  return invoke<MessageNodeData[]>('load_node_content', { path, ids });
+}
+
+/**
+ * Provider and model a saved node is regenerated with: its pin, else the
+ * model that generated it, else the project's and then global preferences
+ */
+export async function getNodeGenerationConfig(
+  path: string,
+  nodeId: string
+): Promise<NodeGenerationConfig> {
+  return invoke<NodeGenerationConfig>('get_node_generation_config', { path, nodeId });
+}
+
+export async function getCompressProjects(): Promise<boolean> {
+  return invoke<boolean>('get_compress_projects');
+}
+
+/** Gzip project files on save; compressed and plain projects both load either way */
+export async function setCompressProjects(enabled: boolean): Promise<void> {
+  await invoke('set_compress_projects', { enabled });
+}
+
+export async function getImageAssets(): Promise<boolean> {
+  return invoke<boolean>('get_image_assets');
+}
+
+/**
+ * Save node images as files in a `<project>.assets` folder beside the project
+ * instead of base64 inside it. Loading inlines them again, so nothing else
+ * changes; projects open either way.
+ */
+export async function setImageAssets(enabled: boolean): Promise<void> {
+  await invoke('set_image_assets', { enabled });
+}
+
+export interface MigrationReport {
+  from_version: number;
+  to_version: number;
+  /** Descriptions of the migrations applied, oldest first */
+  applied: string[];
+  /** Copy of the original file, when the project was rewritten */
+  backup_path: string | null;
+}
+
+/** Upgrade a project file on disk to the current format (older files also load without this) */
+export async function migrateProject(path: string): Promise<MigrationReport> {
+  return invoke<MigrationReport>('migrate_project', { path });
+}
+
+// ============================================================================
+// Export
+// ============================================================================
+
+export interface MarkdownExportOptions {
+  /** Render user prompts as well as responses (default true) */
+  include_prompts?: boolean;
+  /** Annotate nodes with role, provider, model and time (default true) */
+  include_metadata?: boolean;
+  /** Include attached images; file exports save them to `assets/` (default true) */
+  include_images?: boolean;
+}
+
+/**
+ * Export the project tree (or the subtree under `root`) as nested-heading
+ * markdown. Returns the chosen path, or null if the dialog was cancelled.
+ */
+export async function exportTreeMarkdown(
+  data: string,
+  defaultName: string,
+  root?: string,
+  options?: MarkdownExportOptions
+): Promise<string | null> {
+  return invoke<string | null>('export_tree_markdown', {
+    data,
+    defaultName,
+    root: root ?? null,
+    options: options ?? null,
+  });
+}
+
+/**
+ * Export the project as an OPML outline. Pass `data` to include unsaved
+ * edits; otherwise the saved file at `path` is exported.
+ */
+export async function exportOpml(
+  path: string | null,
+  data: string | null,
+  defaultName: string
+): Promise<string | null> {
+  return invoke<string | null>('export_opml', { path, data, defaultName });
+}
+
+/**
+ * Write the project as an Obsidian `.canvas` file into the notes directory.
+ * Returns the written path.
+ */
+export async function exportJsonCanvas(
+  path: string | null,
+  data: string | null,
+  defaultName: string
+): Promise<string> {
+  return invoke<string>('export_json_canvas', { path, data, defaultName });
+}
+
+/**
+ * Export the conversation leading to `node` (or the whole tree when null)
+ * as a paginated PDF. Returns the chosen path, or null if cancelled.
+ * Characters the embedded fonts lack (CJK, emoji, ...) can't be shown;
+ * unless `allowReplacements` is set, such text fails with an error naming
+ * them, so the user can confirm they print as `?`.
+ */
+export async function exportPdf(
+  path: string | null,
+  data: string | null,
+  node: string | null,
+  defaultName: string,
+  allowReplacements = false
+): Promise<string | null> {
+  return invoke<string | null>('export_pdf', {
+    path,
+    data,
+    node,
+    defaultName,
+    allowReplacements,
+  });
+}
+
+/**
+ * Export the conversation leading to `nodeId` as annotated markdown with the
+ * agent's recorded thoughts and tool calls. Returns the chosen path, or null
+ * if cancelled.
+ */
+export async function exportTranscript(
+  nodeId: string,
+  path: string | null,
+  data: string | null,
+  defaultName: string
+): Promise<string | null> {
+  return invoke<string | null>('export_transcript', { nodeId, path, data, defaultName });
+}
+
+/**
+ * Copy a node and its descendants to the system clipboard as markdown.
+ * Done natively since the webview clipboard struggles with large trees.
+ */
+export async function copySubtreeMarkdown(
+  projectJson: string,
+  nodeId: string,
+  options?: MarkdownExportOptions
+): Promise<void> {
+  await invoke('copy_subtree_markdown', { projectJson, nodeId, options });
+}
+
+export type DiagramFormat = 'mermaid' | 'dot';
+
+/** The tree's shape as a Mermaid flowchart or Graphviz DOT text */
+export async function exportGraph(
+  path: string | null,
+  data: string | null,
+  format: DiagramFormat
+): Promise<string> {
+  return invoke<string>('export_graph', { path, data, format });
+}
+
+/** Convert an OPML file (inside a workspace) into project JSON */
+export async function importOpml(path: string): Promise<string> {
+  return invoke<string>('import_opml', { path });
+}
+
+export type ConversationFormat = 'chatgpt' | 'claude';
+
+/**
+ * Convert a ChatGPT or Claude `conversations.json` export (inside a
+ * workspace) into project JSON, one tree per conversation
+ */
+export async function importConversation(
+  path: string,
+  format: ConversationFormat
+): Promise<string> {
+  return invoke<string>('import_conversation', { path, format });
+}
+
+// ============================================================================
+// Project templates
+// ============================================================================
+
+export interface TemplateInfo {
+  /** Builtin id, or `user:<file stem>` for files in the templates directory */
+  id: string;
+  name: string;
+  description: string;
+  builtin: boolean;
+}
+
+export async function listProjectTemplates(): Promise<TemplateInfo[]> {
+  return invoke<TemplateInfo[]>('list_project_templates');
+}
+
+/** Write a new project scaffolded from a template; open it with loadProject */
+export async function createProjectFromTemplate(
+  templateId: string,
+  path: string
+): Promise<void> {
+  await invoke('create_project_from_template', { templateId, path });
+}
+
+// ============================================================================
+// Git history
+// ============================================================================
+
+export interface GitLogEntry {
+  hash: string;
+  author: string;
+  /** Unix timestamp in seconds */
+  timestamp: number;
+  message: string;
+}
+
+/** Commits touching a project file, newest first; empty outside a git repository */
+export async function getProjectGitLog(path: string, limit?: number): Promise<GitLogEntry[]> {
+  return invoke<GitLogEntry[]>('get_project_git_log', { path, limit });
+}
+
+export async function getGitAutocommitEnabled(): Promise<boolean> {
+  return invoke<boolean>('get_git_autocommit_enabled');
+}
+
+export async function setGitAutocommitEnabled(enabled: boolean): Promise<void> {
+  await invoke('set_git_autocommit_enabled', { enabled });
+}
+
+// ============================================================================
+// Workspaces (named notes directories)
+// ============================================================================
+
+export interface Workspace {
+  name: string;
+  path: string;
+}
+
+export interface WorkspaceConfig {
+  workspaces: Workspace[];
+  active: string | null;
+}
+
+export async function listWorkspaces(): Promise<WorkspaceConfig> {
+  return invoke<WorkspaceConfig>('list_workspaces');
+}
+
+export async function addWorkspace(name: string, path: string): Promise<WorkspaceConfig> {
+  return invoke<WorkspaceConfig>('add_workspace', { name, path });
+}
+
+export async function setActiveWorkspace(name: string): Promise<WorkspaceConfig> {
+  return invoke<WorkspaceConfig>('set_active_workspace', { name });
+}
+
+export async function removeWorkspace(name: string): Promise<WorkspaceConfig> {
+  return invoke<WorkspaceConfig>('remove_workspace', { name });
 }
 
 // ============================================================================
@@ -147,6 +991,20 @@ export async function setModelPreference(
   await invoke('set_model_preference', { provider, modelId });
 }
 
+/** Model ids behind the fast/balanced/deep presets */
+export async function getModelPresets(): Promise<ModelPresets> {
+  return invoke<ModelPresets>('get_model_presets');
+}
+
+/** Point a preset at another model; null keeps the agent's default */
+export async function setModelPreset(
+  preset: ModelPreset,
+  provider: AgentProvider,
+  modelId: string | null
+): Promise<void> {
+  await invoke('set_model_preset', { preset, provider, modelId });
+}
+
 export async function getAvailableModels(provider: AgentProvider): Promise<ModelInfo[]> {
   return invoke<ModelInfo[]>('get_available_models', { provider });
 }
@@ -178,3 +1036,640 @@ export async function pickProviderExecutable(
 ): Promise<string | null> {
   return invoke<string | null>('pick_provider_executable', { provider });
 }
+
+// ============================================================================
+// Usage analytics (local only, opt-in)
+// ============================================================================
+
+export type UsageRange = 'week' | 'month' | 'year' | 'all';
+
+export interface UsageCount {
+  name: string;
+  prompts: number;
+}
+
+export interface UsageReport {
+  total_prompts: number;
+  failed_prompts: number;
+  /** Days with at least one prompt, oldest first (`YYYY-MM-DD`) */
+  days: Array<{ day: string; prompts: number }>;
+  providers: UsageCount[];
+  /** The agent's default model is reported as `"default"` */
+  models: UsageCount[];
+  mean_duration_ms: number | null;
+  mean_first_chunk_ms: number | null;
+}
+
+export async function getAnalyticsEnabled(): Promise<boolean> {
+  return invoke<boolean>('get_analytics_enabled');
+}
+
+/** Record prompt counts and durations in the app data dir; never leaves the machine */
+export async function setAnalyticsEnabled(enabled: boolean): Promise<void> {
+  await invoke('set_analytics_enabled', { enabled });
+}
+
+export async function getUsageReport(range: UsageRange): Promise<UsageReport> {
+  return invoke<UsageReport>('get_usage_report', { range });
+}
+
+export async function clearUsageData(): Promise<void> {
+  await invoke('clear_usage_data');
+}
+
+// ============================================================================
+// Logs
+// ============================================================================
+
+export type LogLevel = 'error' | 'warn' | 'info' | 'debug' | 'trace';
+
+export interface LogLine {
+  level: 'ERROR' | 'WARN' | 'INFO' | 'DEBUG' | 'TRACE';
+  /** The full event, including continuation lines */
+  text: string;
+}
+
+/**
+ * The last `lines` log events at `level` (default info) or worse, oldest first.
+ * `traceId` (from a failed generation's error or events) keeps only its events.
+ */
+export async function getRecentLogs(
+  lines?: number,
+  level?: LogLevel,
+  traceId?: string
+): Promise<LogLine[]> {
+  return invoke<LogLine[]>('get_recent_logs', {
+    lines: lines ?? null,
+    level: level ?? null,
+    traceId: traceId ?? null,
+  });
+}
+
+// ============================================================================
+// Agent environment
+// ============================================================================
+
+/** Environment variables passed to every spawned agent, by name */
+export async function getAgentEnv(): Promise<Record<string, string>> {
+  return invoke<Record<string, string>>('get_agent_env');
+}
+
+/**
+ * Pass `name=value` to agents spawned from now on (e.g. `HTTPS_PROXY`).
+ * Loader variables like `LD_PRELOAD` are rejected.
+ */
+export async function setAgentEnvVar(name: string, value: string): Promise<void> {
+  await invoke('set_agent_env_var', { name, value });
+}
+
+export async function removeAgentEnvVar(name: string): Promise<void> {
+  await invoke('remove_agent_env_var', { name });
+}
+
+/**
+ * Parent environment variables agents inherit; all others are cleared so
+ * stray secrets don't reach agent tools. A trailing `*` matches a prefix.
+ */
+export async function getEnvAllowlist(): Promise<string[]> {
+  return invoke<string[]>('get_env_allowlist');
+}
+
+/** Replace the allowlist; `null` restores the built-in one */
+export async function setEnvAllowlist(allowlist: string[] | null): Promise<void> {
+  await invoke('set_env_allowlist', { allowlist });
+}
+
+export async function getAgentSandboxEnabled(): Promise<boolean> {
+  return invoke<boolean>('get_agent_sandbox_enabled');
+}
+
+/**
+ * Run agents under a macOS sandbox that blocks writes outside temp and agent
+ * state, and reads outside the notes and read directories. System and
+ * toolchain paths, agent config and `~/.cache` stay readable. The keychain
+ * does not, so logins stored there (e.g. a Claude subscription) need an API
+ * key in the agent environment instead. Rejected on other platforms.
+ */
+export async function setAgentSandboxEnabled(enabled: boolean): Promise<void> {
+  await invoke('set_agent_sandbox_enabled', { enabled });
+}
+
+export async function getReadRoots(): Promise<string[]> {
+  return invoke<string[]>('get_read_roots');
+}
+
+/**
+ * Directories outside the notes that agents' read-only tools may use without
+ * asking, e.g. reference PDFs. Each must be an absolute directory that
+ * doesn't contain the home directory; they are stored resolved.
+ */
+export async function setReadRoots(roots: string[]): Promise<void> {
+  await invoke('set_read_roots', { roots });
+}
+
+// ============================================================================
+// Claude Code options
+// ============================================================================
+
+export async function getClaudeOptions(): Promise<ClaudeOptions> {
+  return invoke<ClaudeOptions>('get_claude_options');
+}
+
+/** Global options; a project's `claudeOptions` replace them */
+export async function setClaudeOptions(options: ClaudeOptions): Promise<void> {
+  await invoke('set_claude_options', { options });
+}
+
+// ============================================================================
+// Gemini CLI
+// ============================================================================
+
+export interface GeminiSettings {
+  /** Absolute path to a settings .json file passed to Gemini CLI */
+  settings_path: string | null;
+  /** Run Gemini CLI's tools in its container sandbox */
+  sandbox: boolean;
+  sandbox_image: string | null;
+}
+
+export async function getGeminiSettings(): Promise<GeminiSettings> {
+  return invoke<GeminiSettings>('get_gemini_settings');
+}
+
+/** The API key is stored separately, with `setSecret('gemini-api-key', key)` */
+export async function setGeminiSettings(settings: GeminiSettings): Promise<void> {
+  await invoke('set_gemini_settings', { settings });
+}
+
+// ============================================================================
+// Proxy
+// ============================================================================
+
+/** Unset or empty fields aren't used */
+export interface ProxySettings {
+  /** `http://host:port` for plain HTTP requests */
+  http: string | null;
+  /** `http://host:port` for HTTPS requests */
+  https: string | null;
+  /** `socks5://host:port` for everything else */
+  socks: string | null;
+  /** Comma-separated hosts that bypass the proxies */
+  no_proxy: string | null;
+}
+
+export interface ProxyTestResult {
+  url: string;
+  /** An HTTP response came back, whatever its status */
+  reachable: boolean;
+  status: number | null;
+  error: string | null;
+  duration_ms: number;
+}
+
+export async function getProxySettings(): Promise<ProxySettings> {
+  return invoke<ProxySettings>('get_proxy_settings');
+}
+
+/**
+ * Proxies for agents and the app's own provider requests. URLs with
+ * credentials are rejected, since settings are stored in plain text.
+ */
+export async function setProxySettings(settings: ProxySettings): Promise<void> {
+  await invoke('set_proxy_settings', { settings });
+}
+
+/** Try to reach the provider APIs through `settings`, or the saved ones */
+export async function testProxy(settings?: ProxySettings): Promise<ProxyTestResult[]> {
+  return invoke<ProxyTestResult[]>('test_proxy', { settings: settings ?? null });
+}
+
+// ============================================================================
+// Health check
+// ============================================================================
+
+export type HealthCheckKind = 'sidecar' | 'notes-directory' | 'cli' | 'version' | 'handshake';
+
+/** `skipped` when a check it depends on failed */
+export type HealthStatus = 'ok' | 'warning' | 'error' | 'skipped';
+
+export interface HealthCheck {
+  kind: HealthCheckKind;
+  /** Null for checks that aren't about one provider */
+  provider: AgentProvider | null;
+  status: HealthStatus;
+  detail: string;
+  duration_ms: number;
+}
+
+export interface HealthReport {
+  /** No check ended in an error */
+  healthy: boolean;
+  checks: HealthCheck[];
+}
+
+/**
+ * Check the sidecar, notes directory and each provider's CLI, version and
+ * ACP handshake. Spawns the agents, so it can take a few seconds.
+ */
+export async function runHealthCheck(): Promise<HealthReport> {
+  return invoke<HealthReport>('run_health_check');
+}
+
+// ============================================================================
+// Crash recovery
+// ============================================================================
+
+/** Answer text of a generation the app didn't live to finish */
+export interface RecoveredContent {
+  node_id: string;
+  /** RFC 3339 start of the generation */
+  started_at: string;
+  content: string;
+}
+
+/** Content of generations cut short by a crash in an earlier run, oldest first */
+export async function recoverPendingContent(): Promise<RecoveredContent[]> {
+  return invoke<RecoveredContent[]>('recover_pending_content');
+}
+
+/** Forget recovered content once it has been restored or declined */
+export async function dismissRecoveredContent(nodeIds: string[]): Promise<void> {
+  await invoke('dismiss_recovered_content', { nodeIds });
+}
+
+// ============================================================================
+// Secrets
+// ============================================================================
+
+/**
+ * Store a secret (e.g. an API key) in the OS keychain. There is deliberately
+ * no way to read one back; use `hasSecret` to show whether it is set.
+ */
+export async function setSecret(name: string, value: string): Promise<void> {
+  await invoke('set_secret', { name, value });
+}
+
+export async function hasSecret(name: string): Promise<boolean> {
+  return invoke<boolean>('has_secret', { name });
+}
+
+/** Returns whether a secret was stored under `name` */
+export async function deleteSecret(name: string): Promise<boolean> {
+  return invoke<boolean>('delete_secret', { name });
+}
+
+// ============================================================================
+// Updates
+// ============================================================================
+
+export interface UpdateInfo {
+  current_version: string;
+  latest_version: string;
+  update_available: boolean;
+  /** Markdown release notes */
+  release_notes: string;
+  release_url: string;
+  /** Installer for this platform, or the release page */
+  download_url: string;
+  published_at: string | null;
+}
+
+/** Compare this build with the latest GitHub release */
+export async function checkForUpdates(): Promise<UpdateInfo> {
+  return invoke<UpdateInfo>('check_for_updates');
+}
+
+export async function getCheckUpdatesOnLaunch(): Promise<boolean> {
+  return invoke<boolean>('get_check_updates_on_launch');
+}
+
+/** When enabled, `update-available` is emitted at startup if there is one */
+export async function setCheckUpdatesOnLaunch(enabled: boolean): Promise<void> {
+  await invoke('set_check_updates_on_launch', { enabled });
+}
+
+// ============================================================================
+// Quick capture
+// ============================================================================
+
+/** Where quick captures go; paths are relative to the notes directory */
+export type CaptureTarget =
+  | { kind: 'note'; path: string }
+  | { kind: 'project'; path: string };
+
+/**
+ * Add a thought to the inbox: a timestamped entry under today's heading in
+ * the inbox note, or a new root node in the inbox project
+ */
+export async function quickCapture(text: string): Promise<void> {
+  await invoke('quick_capture', { text });
+}
+
+export async function getQuickCaptureTarget(): Promise<CaptureTarget> {
+  return invoke<CaptureTarget>('get_quick_capture_target');
+}
+
+export async function setQuickCaptureTarget(target: CaptureTarget): Promise<void> {
+  await invoke('set_quick_capture_target', { target });
+}
+
+/** The global shortcut, e.g. `CommandOrControl+Shift+Space`; null when off */
+export async function getQuickCaptureShortcut(): Promise<string | null> {
+  return invoke<string | null>('get_quick_capture_shortcut');
+}
+
+export async function setQuickCaptureShortcut(shortcut: string | null): Promise<void> {
+  await invoke('set_quick_capture_shortcut', { shortcut });
+}
+
+/** Called when the tray item or global shortcut asks for a capture prompt */
+export async function listenQuickCaptureRequested(onRequest: () => void): Promise<UnlistenFn> {
+  return listen('quick-capture-requested', () => onRequest());
+}
+
+// ============================================================================
+// Spotlight
+// ============================================================================
+
+/**
+ * Rewrite the Spotlight metadata (title and node titles) of every project in
+ * the workspaces; returns how many were updated. macOS only; saved projects
+ * are kept up to date automatically.
+ */
+export async function reindexProjects(): Promise<number> {
+  return invoke<number>('reindex_projects');
+}
+
+// ============================================================================
+// Project statistics
+// ============================================================================
+
+export interface NodeStats {
+  id: string;
+  role: string;
+  words: number;
+  /** Edges from the nearest root; 0 for roots */
+  depth: number;
+  children: number;
+}
+
+export interface ProjectStats {
+  node_count: number;
+  total_words: number;
+  user_words: number;
+  assistant_words: number;
+  reading_time_minutes: number;
+  root_count: number;
+  leaf_count: number;
+  max_depth: number;
+  /** Mean children of nodes that have any */
+  branching_factor: number;
+  max_children: number;
+  nodes: NodeStats[];
+}
+
+/** Word counts, reading time and tree shape of a project, for the stats panel */
+export async function getProjectStats(projectJson: string): Promise<ProjectStats> {
+  return invoke<ProjectStats>('get_project_stats', { projectJson });
+}
+
+// ============================================================================
+// Node history
+// ============================================================================
+
+/** An earlier content of a node */
+export interface NodeVersion {
+  /** Counts up from 1 per node */
+  version: number;
+  content: string;
+  /** Unix milliseconds */
+  timestamp: number;
+}
+
+/** Earlier contents of a node, oldest first; recorded as the project is saved */
+export async function getNodeHistory(projectPath: string, nodeId: string): Promise<NodeVersion[]> {
+  return invoke<NodeVersion[]>('get_node_history', { projectPath, nodeId });
+}
+
+/** Content of an earlier version, to set on the node in the open project */
+export async function restoreNodeVersion(
+  projectPath: string,
+  nodeId: string,
+  version: number
+): Promise<string> {
+  return invoke<string>('restore_node_version', { projectPath, nodeId, version });
+}
+
+// ============================================================================
+// Similar nodes
+// ============================================================================
+
+/** A node, in the current or a recent project, about something similar */
+export interface RelatedNode {
+  project_path: string;
+  node_id: string;
+  title: string;
+  /** Unix milliseconds */
+  timestamp: number;
+  /** Cosine similarity, 0 to 1 */
+  score: number;
+}
+
+/** Nodes of the current and recent projects similar to `content`, best first */
+export async function findSimilarNodes(
+  nodeId: string,
+  content: string,
+  projectPath?: string,
+  limit?: number
+): Promise<RelatedNode[]> {
+  return invoke<RelatedNode[]>('find_similar_nodes', { nodeId, content, projectPath, limit });
+}
+
+/** A note suggested as context for a node */
+export interface NoteSuggestion {
+  /** Path relative to the notes directory */
+  path: string;
+  title: string;
+  score: number;
+  /** The note line sharing the most terms with the node */
+  snippet: string | null;
+}
+
+/** Notes most relevant to a node's content, for a "related notes" sidebar */
+export async function suggestContext(content: string, k?: number): Promise<NoteSuggestion[]> {
+  return invoke<NoteSuggestion[]>('suggest_context', { content, k });
+}
+
+// ============================================================================
+// Diff
+// ============================================================================
+
+export type LineChange = 'equal' | 'delete' | 'insert';
+
+/** Part of a changed line; `changed` marks words that differ from the other side */
+export interface DiffSegment {
+  text: string;
+  changed: boolean;
+}
+
+export interface DiffLine {
+  change: LineChange;
+  /** 1-based line in the first text; null for inserted lines */
+  old_line: number | null;
+  /** 1-based line in the second text; null for deleted lines */
+  new_line: number | null;
+  text: string;
+  /** Word-level breakdown of deleted and inserted lines */
+  segments: DiffSegment[];
+}
+
+/** A run of changes with surrounding context; starts are 1-based */
+export interface DiffHunk {
+  old_start: number;
+  old_len: number;
+  new_start: number;
+  new_len: number;
+  lines: DiffLine[];
+}
+
+export interface TextDiffResult {
+  hunks: DiffHunk[];
+  insertions: number;
+  deletions: number;
+  /** 0 (nothing shared) to 1 (identical) */
+  similarity: number;
+}
+
+export async function diffTexts(a: string, b: string): Promise<TextDiffResult> {
+  return invoke<TextDiffResult>('diff_texts', { a, b });
+}
+
+/** Compare the content of two nodes, e.g. alternative answers to one prompt */
+export async function diffNodes(projectJson: string, a: string, b: string): Promise<TextDiffResult> {
+  return invoke<TextDiffResult>('diff_nodes', { projectJson, a, b });
+}
+
+// ============================================================================
+// Dictation
+// ============================================================================
+
+/** Local transcription with whisper.cpp's `whisper-stream` */
+export interface DictationSettings {
+  /** `whisper-stream` executable; found automatically when null */
+  binary: string | null;
+  /** ggml model file, e.g. `ggml-base.en.bin` */
+  model: string | null;
+  /** Language code like `en`, or `auto`; English when null */
+  language: string | null;
+}
+
+export interface TranscriptionPayload {
+  /** Everything transcribed so far */
+  text: string;
+  is_final: boolean;
+}
+
+/** Start recording; follow the transcript with `listenTranscription` */
+export async function startDictation(): Promise<void> {
+  await invoke('start_dictation');
+}
+
+/** Stop recording and get the final transcript */
+export async function stopDictation(): Promise<string> {
+  return invoke<string>('stop_dictation');
+}
+
+export async function getDictationSettings(): Promise<DictationSettings> {
+  return invoke<DictationSettings>('get_dictation_settings');
+}
+
+export async function setDictationSettings(settings: DictationSettings): Promise<void> {
+  await invoke('set_dictation_settings', { settings });
+}
+
+export async function listenTranscription(
+  onTranscript: (text: string, isFinal: boolean) => void
+): Promise<UnlistenFn> {
+  return listen<TranscriptionPayload>('transcription-partial', (event) => {
+    onTranscript(event.payload.text, event.payload.is_final);
+  });
+}
+
+// ============================================================================
+// Batch generation
+// ============================================================================
+
+export interface BatchResult {
+  node_id: string;
+  /** Answer node added under `node_id`, when one was */
+  result_node_id: string | null;
+  model_id: string | null;
+  error: string | null;
+}
+
+export interface BatchProgress {
+  completed: number;
+  total: number;
+  node_id: string;
+  error: string | null;
+}
+
+/**
+ * Run `promptTemplate` over nodes of a saved project and add each answer as
+ * a child node in the file. `{{content}}` in the template stands for each
+ * node's content.
+ */
+export async function batchGenerate(
+  projectPath: string,
+  nodeIds: string[],
+  promptTemplate: string
+): Promise<BatchResult[]> {
+  return invoke<BatchResult[]>('batch_generate', { projectPath, nodeIds, promptTemplate });
+}
+
+export async function listenBatchProgress(
+  onProgress: (progress: BatchProgress) => void
+): Promise<UnlistenFn> {
+  return listen<BatchProgress>('batch-progress', (event) => {
+    onProgress(event.payload);
+  });
+}
+
+// ============================================================================
+// Background jobs
+// ============================================================================
+
+export type BackgroundJob =
+  | 'search-index'
+  | 'summary-backfill'
+  | 'history-pruning'
+  | 'recent-projects-cleanup';
+
+/** Minutes between runs per job; 0 turns a job off, missing jobs use defaults */
+export interface BackgroundJobSettings {
+  interval_mins: Partial<Record<BackgroundJob, number>>;
+}
+
+/** Times are Unix milliseconds */
+export interface BackgroundJobStatus {
+  job: BackgroundJob;
+  interval_mins: number;
+  running: boolean;
+  last_run_ms: number | null;
+  last_duration_ms: number | null;
+  last_result: string | null;
+  last_error: string | null;
+  next_run_ms: number | null;
+}
+
+export async function getBackgroundJobs(): Promise<BackgroundJobStatus[]> {
+  return invoke<BackgroundJobStatus[]>('get_background_jobs');
+}
+
+export async function getBackgroundJobSettings(): Promise<BackgroundJobSettings> {
+  return invoke<BackgroundJobSettings>('get_background_job_settings');
+}
+
+export async function setBackgroundJobSettings(settings: BackgroundJobSettings): Promise<void> {
+  return invoke('set_background_job_settings', { settings });
+}
@@ -8,9 +8,11 @@ import {
   type NodeChange,
 } from '@xyflow/react';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import {
   AgentNodeData,
   AgentProvider,
+  ClaudeOptions,
   DEFAULT_PROVIDER,
   ImageAttachment,
   MessageNodeData,
@@ -55,6 +57,13 @@ interface ProjectFileV3 {
   version: 3;
   graph: GraphJSON;
   projectModelPreferences?: ModelPreferences | null;
+  // Vault this project belongs to; agents run there instead of the active
+  // workspace (the backend only honors configured workspaces)
+  notesDirectory?: string | null;
+  // Replaces the global system prompt for this project
+  systemPrompt?: string | null;
+  // Replaces the global Claude Code options for this project
+  claudeOptions?: ClaudeOptions | null;
 }
 
 interface ProjectFileLegacyV2 {
@@ -80,10 +89,15 @@ interface GraphState {
   projectPath: string | null;
   lastSavedAt: number | null;
   isDirty: boolean;
+  // The project file was changed by another program while it had unsaved edits
+  projectChangedExternally: boolean;
 
   // Persisted with the project file, unlike global preferences
   // (see useProviderStore)
   projectModelPreferences: ModelPreferences | null;
+  projectNotesDirectory: string | null;
+  projectSystemPrompt: string | null;
+  projectClaudeOptions: ClaudeOptions | null;
 
   // Selection and streaming feed the graph projection, so they live here
   // rather than in useUIStore
@@ -123,8 +137,14 @@ interface GraphState {
   // Summary actions
   setSummary: (nodeId: string, summary: string) => void;
 
+  // Pin the provider/model regenerations of an answer use; null unpins
+  pinNodeModel: (nodeId: string, provider: AgentProvider | null, model?: string) => void;
+
   // Model actions (project-scoped; global preferences live in useProviderStore)
   setProjectModelPreferences: (preferences: ModelPreferences | null) => void;
+  setProjectNotesDirectory: (directory: string | null) => void;
+  setProjectSystemPrompt: (prompt: string | null) => void;
+  setProjectClaudeOptions: (options: ClaudeOptions | null) => void;
   setProjectModelPreference: (provider: AgentProvider, modelId: string | null) => void;
   getEffectiveModel: (provider: AgentProvider) => string | undefined;
 
@@ -132,6 +152,7 @@ interface GraphState {
   setProjectPath: (path: string | null) => void;
   saveProject: () => Promise<void>;
   loadProject: (path: string) => Promise<void>;
+  reloadProjectIfChanged: () => Promise<boolean>;
   newProject: () => void;
   exportSubgraph: (nodeIds: string[]) => string;
 
@@ -149,6 +170,70 @@ function debounce<T extends (...args: unknown[]) => unknown>(fn: T, delay: numbe
   }) as T;
 }
 
+function parseProjectFile(data: string): {
+  graph: Graph;
+  projectModelPreferences: ModelPreferences | null;
+  projectNotesDirectory: string | null;
+  projectSystemPrompt: string | null;
+  projectClaudeOptions: ClaudeOptions | null;
+} {
+  const parsed = JSON.parse(data) as ProjectFile;
+
+  if (parsed.version === GRAPH_JSON_VERSION && 'graph' in parsed) {
+    return {
+      graph: GraphSerialize.fromJSON(parsed.graph),
+      projectModelPreferences: parsed.projectModelPreferences ?? null,
+      projectNotesDirectory: parsed.notesDirectory ?? null,
+      projectSystemPrompt: parsed.systemPrompt ?? null,
+      projectClaudeOptions: parsed.claudeOptions ?? null,
+    };
+  }
+
+  const legacy = parsed as ProjectFileLegacyV2;
+  const migratedNodeData = migrateLegacyV2NodeData(legacy.nodeData);
+  return {
+    graph: GraphSerialize.fromLegacyV2({
+      version: legacy.version,
+      nodes: legacy.nodes,
+      edges: legacy.edges,
+      nodeData: migratedNodeData,
+    }),
+    projectModelPreferences: legacy.projectModelPreferences ?? null,
+    projectNotesDirectory: null,
+    projectSystemPrompt: null,
+    projectClaudeOptions: null,
+  };
+}
+
+// Matches the backend error for a project locked by another app instance
+const PROJECT_LOCKED_MESSAGE = 'open in another ThoughtTree instance';
+
+async function loadProjectData(path: string): Promise<string> {
+  try {
+    return await invoke<string>('load_project', { path });
+  } catch (error) {
+    if (!String(error).includes(PROJECT_LOCKED_MESSAGE)) throw error;
+    const force = window.confirm(
+      `${error}\n\nOpening it here as well may overwrite changes made there. Force unlock and open anyway?`
+    );
+    if (!force) throw error;
+    await invoke('force_unlock_project', { path });
+    return invoke<string>('load_project', { path });
+  }
+}
+
+export function serializeProject(state: GraphState): string {
+  const projectFile: ProjectFileV3 = {
+    version: GRAPH_JSON_VERSION,
+    graph: GraphSerialize.toJSON(state.graph),
+    projectModelPreferences: state.projectModelPreferences,
+    notesDirectory: state.projectNotesDirectory ?? undefined,
+    systemPrompt: state.projectSystemPrompt ?? undefined,
+    claudeOptions: state.projectClaudeOptions ?? undefined,
+  };
+  return JSON.stringify(projectFile, null, 2);
+}
+
 interface ProjectionResult {
   nodes: FlowNode[];
   edges: Edge[];
@@ -200,7 +285,11 @@ export const useGraphStore = create<GraphState>()((set, get) => ({
   projectPath: null,
   lastSavedAt: null,
   isDirty: false,
+  projectChangedExternally: false,
   projectModelPreferences: null,
+  projectNotesDirectory: null,
+  projectSystemPrompt: null,
+  projectClaudeOptions: null,
   selectedNodeId: null,
   streamingNodeIds: new Set<string>(),
 
@@ -512,8 +601,29 @@ export const useGraphStore = create<GraphState>()((set, get) => ({
     set({ graph, ...projectGraph(graph, state.nodes, state.selectedNodeId) });
   },
 
+  pinNodeModel: (nodeId, provider, model) => {
+    const state = get();
+    if (state.nodeData.get(nodeId)?.role !== 'assistant') return;
+    const graph = GraphMutations.updateNode(state.graph, nodeId, {
+      pinnedProvider: provider ?? undefined,
+      pinnedModel: provider ? model : undefined,
+    });
+    if (graph === state.graph) return;
+    set({
+      graph,
+      ...projectGraph(graph, state.nodes, state.selectedNodeId),
+      isDirty: true,
+    });
+  },
+
   setProjectModelPreferences: (preferences) => set({ projectModelPreferences: preferences }),
 
+  setProjectNotesDirectory: (directory) => set({ projectNotesDirectory: directory, isDirty: true }),
+
+  setProjectSystemPrompt: (prompt) => set({ projectSystemPrompt: prompt, isDirty: true }),
+
+  setProjectClaudeOptions: (options) => set({ projectClaudeOptions: options, isDirty: true }),
+
   setProjectModelPreference: (provider, modelId) => {
     set((state) => ({
       projectModelPreferences: {
@@ -534,24 +644,18 @@ export const useGraphStore = create<GraphState>()((set, get) => ({
 
   saveProject: async () => {
     get().flushStreamingChunks();
-    const { projectPath, graph, projectModelPreferences } = get();
+    const { projectPath } = get();
     if (!projectPath) {
       logger.warn('No project path set, cannot save');
       return;
     }
 
-    const projectFile: ProjectFileV3 = {
-      version: GRAPH_JSON_VERSION,
-      graph: GraphSerialize.toJSON(graph),
-      projectModelPreferences,
-    };
-
     try {
       await invoke('save_project', {
         path: projectPath,
-        data: JSON.stringify(projectFile, null, 2),
+        data: serializeProject(get()),
       });
-      set({ lastSavedAt: Date.now(), isDirty: false });
+      set({ lastSavedAt: Date.now(), isDirty: false, projectChangedExternally: false });
       logger.info('Project saved to:', projectPath);
     } catch (error) {
       logger.error('Failed to save project:', error);
@@ -561,34 +665,26 @@ export const useGraphStore = create<GraphState>()((set, get) => ({
 
   loadProject: async (path) => {
     try {
-      const data = await invoke<string>('load_project', { path });
-      const parsed = JSON.parse(data) as ProjectFile;
-
-      let graph: Graph;
-      let projectModelPreferences: ModelPreferences | null = null;
-
-      if (parsed.version === GRAPH_JSON_VERSION && 'graph' in parsed) {
-        graph = GraphSerialize.fromJSON(parsed.graph);
-        projectModelPreferences = parsed.projectModelPreferences ?? null;
-      } else {
-        const legacy = parsed as ProjectFileLegacyV2;
-        const migratedNodeData = migrateLegacyV2NodeData(legacy.nodeData);
-        graph = GraphSerialize.fromLegacyV2({
-          version: legacy.version,
-          nodes: legacy.nodes,
-          edges: legacy.edges,
-          nodeData: migratedNodeData,
-        });
-        projectModelPreferences = legacy.projectModelPreferences ?? null;
-      }
+      const data = await loadProjectData(path);
+      const {
+        graph,
+        projectModelPreferences,
+        projectNotesDirectory,
+        projectSystemPrompt,
+        projectClaudeOptions,
+      } = parseProjectFile(data);
 
       set({
         graph,
         ...projectGraph(graph, [], null),
         projectModelPreferences,
+        projectNotesDirectory,
+        projectSystemPrompt,
+        projectClaudeOptions,
         projectPath: path,
         lastSavedAt: Date.now(),
         isDirty: false,
+        projectChangedExternally: false,
         selectedNodeId: null,
         streamingNodeIds: new Set<string>(),
       });
@@ -607,7 +703,42 @@ export const useGraphStore = create<GraphState>()((set, get) => ({
     }
   },
 
+  reloadProjectIfChanged: async () => {
+    const { projectPath } = get();
+    if (!projectPath) return false;
+
+    const data = await invoke<string | null>('reload_project_if_changed', { path: projectPath });
+    if (data === null) return false;
+
+    const {
+      graph,
+      projectModelPreferences,
+      projectNotesDirectory,
+      projectSystemPrompt,
+      projectClaudeOptions,
+    } = parseProjectFile(data);
+    set({
+      graph,
+      ...projectGraph(graph, get().nodes, get().selectedNodeId),
+      projectModelPreferences,
+      projectNotesDirectory,
+      projectSystemPrompt,
+      projectClaudeOptions,
+      lastSavedAt: Date.now(),
+      isDirty: false,
+      projectChangedExternally: false,
+    });
+    logger.info('Project reloaded after external change:', projectPath);
+    return true;
+  },
+
   newProject: () => {
+    const { projectPath } = get();
+    if (projectPath) {
+      invoke('close_project', { path: projectPath }).catch((error) => {
+        logger.warn('Failed to close project:', error);
+      });
+    }
     const graph = GraphMutations.empty();
     set({
       graph,
@@ -615,9 +746,13 @@ export const useGraphStore = create<GraphState>()((set, get) => ({
       edges: [],
       nodeData: graph.nodes,
       projectModelPreferences: null,
+      projectNotesDirectory: null,
+      projectSystemPrompt: null,
+      projectClaudeOptions: null,
       projectPath: null,
       lastSavedAt: null,
       isDirty: false,
+      projectChangedExternally: false,
       selectedNodeId: null,
       streamingNodeIds: new Set<string>(),
     });
@@ -677,19 +812,73 @@ export const useGraphStore = create<GraphState>()((set, get) => ({
 }));
 
 // Auto-save subscription: graph reference changes whenever domain content mutates.
+// The backend debounces and coalesces the writes; this only batches snapshots
+// so a streaming response doesn't serialize the graph on every chunk.
+let autosavedGraph: Graph | null = null;
+
 const debouncedSave = debounce(async () => {
   const state = useGraphStore.getState();
   if (state.projectPath && state.isDirty) {
+    state.flushStreamingChunks();
+    const snapshot = useGraphStore.getState();
+    autosavedGraph = snapshot.graph;
     try {
-      await state.saveProject();
+      await invoke('queue_autosave', {
+        path: snapshot.projectPath,
+        data: serializeProject(snapshot),
+      });
     } catch (error) {
       logger.error('Auto-save failed:', error);
     }
   }
-}, 2000);
+}, 300);
+
+interface AutosaveCompletePayload {
+  path: string;
+  saved_at: number;
+}
+
+interface AutosaveFailedPayload {
+  path: string;
+  error: string;
+}
+
+void listen<AutosaveCompletePayload>('autosave-complete', ({ payload }) => {
+  const state = useGraphStore.getState();
+  if (payload.path !== state.projectPath) return;
+  // Edits made after the snapshot was queued are still unsaved
+  useGraphStore.setState({
+    lastSavedAt: payload.saved_at,
+    isDirty: state.graph !== autosavedGraph,
+  });
+});
+
+void listen<AutosaveFailedPayload>('autosave-failed', ({ payload }) => {
+  logger.error('Auto-save failed:', payload.path, payload.error);
+});
 
 useGraphStore.subscribe((state, prevState) => {
   if (state.graph !== prevState.graph) {
     debouncedSave();
   }
 });
+
+interface ProjectChangedPayload {
+  path: string;
+}
+
+// Another program (often a sync client) changed the open project. Clean
+// projects are reloaded; with unsaved edits the user decides, and autosave
+// holds off until they do.
+void listen<ProjectChangedPayload>('project-changed-externally', ({ payload }) => {
+  const state = useGraphStore.getState();
+  if (payload.path !== state.projectPath) return;
+  if (state.isDirty || state.streamingNodeIds.size > 0) {
+    logger.warn('Project changed on disk while it has unsaved edits:', payload.path);
+    useGraphStore.setState({ projectChangedExternally: true });
+    return;
+  }
+  state.reloadProjectIfChanged().catch((error) => {
+    logger.error('Failed to reload changed project:', error);
+  });
+});
@@ -7,6 +7,8 @@ export type AgentProvider = 'claude-code' | 'gemini-cli';
 export interface ProviderStatus {
   provider: AgentProvider;
   available: boolean;
+  /** Whether credentials were found; null when not checked */
+  authenticated: boolean | null;
   error_message: string | null;
 }
 
@@ -36,11 +38,32 @@ export interface ModelPreferences {
   'gemini-cli'?: string;
 }
 
+/** Provider and model a node is regenerated with, and which layer chose them */
+export interface NodeGenerationConfig {
+  provider: AgentProvider;
+  model_id: string | null;
+  source: 'pinned' | 'node' | 'project' | 'global';
+}
+
+/** Named model choice resolved to a concrete model per provider */
+export type ModelPreset = 'fast' | 'balanced' | 'deep';
+
+export type ModelPresets = Record<ModelPreset, ModelPreferences>;
+
 export interface ProviderPaths {
   'claude-code'?: string;
   'gemini-cli'?: string;
 }
 
+/** Claude Code session options, global or per project */
+export interface ClaudeOptions {
+  extendedThinking: boolean;
+  /** Thinking tokens per turn (1024-64000); the backend default when null */
+  thinkingBudget: number | null;
+  /** Most agentic turns per prompt (1-100); no limit when null */
+  maxTurns: number | null;
+}
+
 // ============================================================================
 // Node data types - discriminated union for user vs agent nodes
 // ============================================================================
@@ -51,6 +74,13 @@ export interface ImageAttachment {
   name?: string;     // Optional filename for display
 }
 
+/** A non-image file sent with a prompt (PDF, text, audio) */
+export interface FileAttachment {
+  name: string;
+  data: string;      // Base64-encoded file contents
+  mimeType: string;  // e.g., "application/pdf", "text/csv"
+}
+
 export interface UserNodeData {
   id: string;
   role: 'user';
@@ -72,6 +102,8 @@ export interface AgentNodeData {
   summaryTimestamp?: number;  // When summary was last generated
   provider?: AgentProvider;   // Which provider generated this response
   model?: string;             // Which model was used for this response
+  pinnedProvider?: AgentProvider; // Regenerations always use this provider
+  pinnedModel?: string;       // ...and this model (agent default when unset)
   // Note: isStreaming is derived from store.streamingNodeId, not stored here
 }
 
@@ -83,10 +115,18 @@ export interface PermissionOption {
   label: string;
 }
 
+export type PermissionContent =
+  | { type: 'text'; text: string }
+  | { type: 'resource'; uri: string; text: string }
+  | { type: 'diff'; path: string; old_text: string | null; new_text: string };
+
 export interface PermissionRequest {
   id: string;
   toolType: string;
   toolName: string;
   description: string;
   options: PermissionOption[];
+  locations: string[];
+  rawInput: unknown;
+  content: PermissionContent[];
 }